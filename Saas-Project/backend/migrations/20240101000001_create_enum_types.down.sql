@@ -0,0 +1,4 @@
+DROP TYPE IF EXISTS priority_level;
+DROP TYPE IF EXISTS document_type;
+DROP TYPE IF EXISTS application_status;
+DROP TYPE IF EXISTS license_type;
@@ -0,0 +1,41 @@
+-- Postgres enum types used by the license domain
+-- Labels are the Rust variant names in lowercase, matching the
+-- `#[sqlx(rename_all = "lowercase")]` mappings in domain::licenses.
+
+CREATE TYPE license_type AS ENUM (
+    'nib',
+    'siup',
+    'tdp',
+    'npwp',
+    'halal',
+    'environmental',
+    'exportimport'
+);
+
+CREATE TYPE application_status AS ENUM (
+    'draft',
+    'submitted',
+    'processing',
+    'pendingdocuments',
+    'approved',
+    'rejected',
+    'expired',
+    'suspended'
+);
+
+CREATE TYPE document_type AS ENUM (
+    'ktp',
+    'companydeed',
+    'taxcertificate',
+    'bankstatement',
+    'businessplan',
+    'locationpermit',
+    'other'
+);
+
+CREATE TYPE priority_level AS ENUM (
+    'low',
+    'normal',
+    'high',
+    'urgent'
+);
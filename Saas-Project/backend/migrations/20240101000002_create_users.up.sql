@@ -0,0 +1,20 @@
+-- Platform users: UMKM owners and admin staff
+-- Role and status are stored as their snake_case text form.
+
+CREATE TABLE users (
+    id UUID PRIMARY KEY,
+    email VARCHAR(255) NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    full_name VARCHAR(255) NOT NULL,
+    role VARCHAR(20) NOT NULL
+        CHECK (role IN ('umkm_owner', 'admin_staff', 'super_admin')),
+    status VARCHAR(30) NOT NULL DEFAULT 'pending_verification'
+        CHECK (status IN ('active', 'inactive', 'pending_verification', 'suspended')),
+    email_verified BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Default list order, with id as the keyset tie-breaker
+CREATE INDEX idx_users_created_at ON users (created_at DESC, id DESC);
+CREATE INDEX idx_users_role_status ON users (role, status);
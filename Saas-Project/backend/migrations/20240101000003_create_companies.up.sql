@@ -0,0 +1,53 @@
+-- UMKM company profiles
+-- Business type, scale and status are stored as their snake_case text form;
+-- NPWP and phone numbers are stored normalised by the value objects.
+
+CREATE TABLE companies (
+    id UUID PRIMARY KEY,
+    owner_id UUID NOT NULL REFERENCES users(id),
+
+    company_name VARCHAR(255) NOT NULL,
+    business_type VARCHAR(50) NOT NULL,
+    industry_sector VARCHAR(100) NOT NULL,
+    description TEXT,
+    establishment_date DATE,
+    employee_count INTEGER NOT NULL DEFAULT 0,
+
+    nib VARCHAR(20) UNIQUE,
+    siup_number VARCHAR(50),
+    tdp_number VARCHAR(50),
+    npwp_company VARCHAR(20),
+
+    email VARCHAR(255),
+    phone VARCHAR(20),
+    website VARCHAR(255),
+
+    address_street TEXT NOT NULL,
+    address_city VARCHAR(100) NOT NULL,
+    address_province VARCHAR(100) NOT NULL,
+    address_postal_code VARCHAR(10) NOT NULL,
+    address_country VARCHAR(100) NOT NULL DEFAULT 'Indonesia',
+
+    business_scale VARCHAR(20) NOT NULL,
+    annual_revenue BIGINT,
+    annual_revenue_year INTEGER,
+
+    is_verified BOOLEAN NOT NULL DEFAULT FALSE,
+    verification_date TIMESTAMPTZ,
+    verification_notes TEXT,
+
+    bank_name VARCHAR(100),
+    bank_account_number VARCHAR(50),
+    bank_account_holder VARCHAR(255),
+
+    logo_url TEXT,
+    documents JSONB NOT NULL DEFAULT '[]'::jsonb,
+
+    status VARCHAR(30) NOT NULL DEFAULT 'pending_verification',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_companies_owner ON companies (owner_id, created_at DESC);
+CREATE INDEX idx_companies_created_at ON companies (created_at DESC, id DESC);
+CREATE INDEX idx_companies_province_city ON companies (address_province, address_city);
@@ -0,0 +1,73 @@
+-- License applications, their supporting documents and status history
+
+CREATE TABLE licenses (
+    id UUID PRIMARY KEY,
+    license_number VARCHAR(100) UNIQUE,
+    license_type license_type NOT NULL,
+    company_id UUID NOT NULL REFERENCES companies(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id),
+
+    title VARCHAR(255) NOT NULL,
+    description TEXT,
+    issue_date TIMESTAMPTZ,
+    expiry_date TIMESTAMPTZ,
+    issuing_authority VARCHAR(255),
+
+    application_status application_status NOT NULL DEFAULT 'draft',
+    priority priority_level NOT NULL DEFAULT 'normal',
+    estimated_processing_days INTEGER,
+    actual_processing_days INTEGER,
+
+    external_reference_id VARCHAR(100),
+    government_fee BIGINT,
+    service_fee BIGINT,
+
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    submitted_at TIMESTAMPTZ,
+    approved_at TIMESTAMPTZ,
+    rejected_at TIMESTAMPTZ,
+
+    admin_notes TEXT,
+    rejection_reason TEXT
+);
+
+CREATE INDEX idx_licenses_company ON licenses (company_id, created_at DESC);
+CREATE INDEX idx_licenses_user ON licenses (user_id, created_at DESC);
+CREATE INDEX idx_licenses_status ON licenses (application_status, created_at DESC);
+CREATE INDEX idx_licenses_type ON licenses (license_type);
+CREATE INDEX idx_licenses_created_at ON licenses (created_at DESC, id DESC);
+
+CREATE TABLE license_documents (
+    id UUID PRIMARY KEY,
+    license_id UUID NOT NULL REFERENCES licenses(id) ON DELETE CASCADE,
+    document_type document_type NOT NULL,
+    file_name VARCHAR(255) NOT NULL,
+    original_file_name VARCHAR(255) NOT NULL,
+    file_path TEXT NOT NULL,
+    file_size BIGINT NOT NULL CHECK (file_size >= 0),
+    mime_type VARCHAR(100) NOT NULL,
+    upload_date TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    is_verified BOOLEAN NOT NULL DEFAULT FALSE,
+    verified_at TIMESTAMPTZ,
+    verified_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    notes TEXT
+);
+
+CREATE INDEX idx_license_documents_license ON license_documents (license_id, upload_date);
+
+-- changed_by has no foreign key: system-generated transitions are recorded
+-- without a real user
+CREATE TABLE application_status_history (
+    id UUID PRIMARY KEY,
+    license_id UUID NOT NULL REFERENCES licenses(id) ON DELETE CASCADE,
+    from_status application_status,
+    to_status application_status NOT NULL,
+    changed_by UUID NOT NULL,
+    changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    notes TEXT,
+    is_system_generated BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX idx_application_status_history_license
+    ON application_status_history (license_id, changed_at);
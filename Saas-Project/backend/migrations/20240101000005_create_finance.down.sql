@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS financial_transactions;
+DROP TABLE IF EXISTS financial_accounts;
@@ -0,0 +1,46 @@
+-- Finance module: accounts and transactions
+-- Amounts are BIGINT in the smallest currency unit (hundredths of a rupiah);
+-- transaction amounts are positive and the type carries the direction.
+-- Types and statuses are stored as their snake_case text form.
+
+CREATE TABLE financial_accounts (
+    id UUID PRIMARY KEY,
+    company_id UUID NOT NULL REFERENCES companies(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    description TEXT,
+    account_type VARCHAR(30) NOT NULL,
+    currency VARCHAR(3) NOT NULL DEFAULT 'IDR',
+    balance BIGINT NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    metadata JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_financial_accounts_company ON financial_accounts (company_id, name);
+
+CREATE TABLE financial_transactions (
+    id UUID PRIMARY KEY,
+    company_id UUID NOT NULL REFERENCES companies(id) ON DELETE CASCADE,
+    transaction_date TIMESTAMPTZ NOT NULL,
+    transaction_type VARCHAR(20) NOT NULL,
+    amount BIGINT NOT NULL CHECK (amount >= 0),
+    currency VARCHAR(3) NOT NULL DEFAULT 'IDR',
+    description TEXT NOT NULL,
+    reference_number VARCHAR(100),
+    status VARCHAR(20) NOT NULL DEFAULT 'draft',
+    account_id UUID NOT NULL REFERENCES financial_accounts(id) ON DELETE CASCADE,
+    category_id UUID,
+    tags JSONB NOT NULL DEFAULT '[]'::jsonb,
+    attachments JSONB NOT NULL DEFAULT '[]'::jsonb,
+    metadata JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_by UUID NOT NULL,
+    updated_by UUID
+);
+
+CREATE INDEX idx_financial_transactions_company_date
+    ON financial_transactions (company_id, transaction_date DESC, id DESC);
+CREATE INDEX idx_financial_transactions_account_date
+    ON financial_transactions (account_id, transaction_date);
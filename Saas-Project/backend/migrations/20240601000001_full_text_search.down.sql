@@ -0,0 +1,12 @@
+DROP INDEX IF EXISTS idx_license_documents_search_vector;
+ALTER TABLE license_documents DROP COLUMN IF EXISTS search_vector;
+
+DROP INDEX IF EXISTS idx_licenses_license_number_trgm;
+DROP INDEX IF EXISTS idx_licenses_search_vector;
+ALTER TABLE licenses DROP COLUMN IF EXISTS search_vector;
+
+DROP INDEX IF EXISTS idx_companies_nib_trgm;
+DROP INDEX IF EXISTS idx_companies_search_vector;
+ALTER TABLE companies DROP COLUMN IF EXISTS search_vector;
+
+DROP TEXT SEARCH CONFIGURATION IF EXISTS public.id_search;
@@ -0,0 +1,45 @@
+-- Full-text search for companies, licenses and license documents
+-- Uses the snowball Indonesian stemmer with unaccent so that queries such as
+-- "perizinan" match "izin" and accented input matches plain ASCII text.
+
+CREATE EXTENSION IF NOT EXISTS unaccent;
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE TEXT SEARCH CONFIGURATION public.id_search (COPY = pg_catalog.indonesian);
+ALTER TEXT SEARCH CONFIGURATION public.id_search
+    ALTER MAPPING FOR hword, hword_part, word
+    WITH unaccent, indonesian_stem;
+
+-- Companies: name and registration numbers weigh most, address least
+ALTER TABLE companies
+    ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+        setweight(to_tsvector('public.id_search', coalesce(company_name, '')), 'A') ||
+        setweight(to_tsvector('simple', coalesce(nib, '') || ' ' || coalesce(npwp_company, '')), 'A') ||
+        setweight(to_tsvector('public.id_search', coalesce(industry_sector, '')), 'B') ||
+        setweight(to_tsvector('public.id_search', coalesce(description, '')), 'C') ||
+        setweight(to_tsvector('public.id_search', coalesce(address_city, '') || ' ' || coalesce(address_province, '')), 'D')
+    ) STORED;
+
+CREATE INDEX idx_companies_search_vector ON companies USING GIN (search_vector);
+CREATE INDEX idx_companies_nib_trgm ON companies USING GIN (nib gin_trgm_ops);
+
+-- Licenses: admin notes and rejection reasons are internal and stay out of the index
+ALTER TABLE licenses
+    ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+        setweight(to_tsvector('public.id_search', coalesce(title, '')), 'A') ||
+        setweight(to_tsvector('simple', coalesce(license_number, '') || ' ' || coalesce(external_reference_id, '')), 'A') ||
+        setweight(to_tsvector('public.id_search', coalesce(description, '')), 'B') ||
+        setweight(to_tsvector('public.id_search', coalesce(issuing_authority, '')), 'C')
+    ) STORED;
+
+CREATE INDEX idx_licenses_search_vector ON licenses USING GIN (search_vector);
+CREATE INDEX idx_licenses_license_number_trgm ON licenses USING GIN (license_number gin_trgm_ops);
+
+-- License documents: searchable by the name the owner uploaded and reviewer notes
+ALTER TABLE license_documents
+    ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+        setweight(to_tsvector('public.id_search', regexp_replace(coalesce(original_file_name, ''), '[._-]+', ' ', 'g')), 'A') ||
+        setweight(to_tsvector('public.id_search', coalesce(notes, '')), 'B')
+    ) STORED;
+
+CREATE INDEX idx_license_documents_search_vector ON license_documents USING GIN (search_vector);
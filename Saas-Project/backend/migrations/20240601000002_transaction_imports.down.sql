@@ -0,0 +1,8 @@
+DROP INDEX IF EXISTS idx_financial_transactions_import_key;
+
+ALTER TABLE financial_transactions
+    DROP COLUMN IF EXISTS import_key,
+    DROP COLUMN IF EXISTS import_batch_id;
+
+DROP TABLE IF EXISTS import_batches;
+DROP TABLE IF EXISTS import_mappings;
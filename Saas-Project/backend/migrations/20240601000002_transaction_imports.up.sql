@@ -0,0 +1,42 @@
+-- Bulk import of transactions from spreadsheets and bank statements
+-- Mappings describe how a file's columns map onto a transaction; batches hold
+-- the per-row validation report of one upload until it is committed.
+
+CREATE TABLE import_mappings (
+    id UUID PRIMARY KEY,
+    company_id UUID NOT NULL REFERENCES companies(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    mapping JSONB NOT NULL,
+    created_by UUID NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (company_id, name)
+);
+
+CREATE TABLE import_batches (
+    id UUID PRIMARY KEY,
+    company_id UUID NOT NULL REFERENCES companies(id) ON DELETE CASCADE,
+    account_id UUID NOT NULL REFERENCES financial_accounts(id) ON DELETE CASCADE,
+    file_name VARCHAR(255) NOT NULL,
+    layout VARCHAR(20) NOT NULL,
+    mapping JSONB NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'previewed',
+    summary JSONB NOT NULL,
+    report JSONB NOT NULL,
+    created_by UUID NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    committed_by UUID,
+    committed_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_import_batches_company ON import_batches (company_id, created_at DESC);
+
+-- Imported rows carry their date + amount + reference key; the unique index is
+-- what makes committing a batch, or an overlapping statement, idempotent
+ALTER TABLE financial_transactions
+    ADD COLUMN import_batch_id UUID REFERENCES import_batches(id) ON DELETE SET NULL,
+    ADD COLUMN import_key TEXT;
+
+CREATE UNIQUE INDEX idx_financial_transactions_import_key
+    ON financial_transactions (account_id, import_key)
+    WHERE import_key IS NOT NULL;
@@ -0,0 +1,7 @@
+DROP TABLE IF EXISTS reconciliation_lines;
+
+ALTER TABLE financial_transactions
+    DROP COLUMN IF EXISTS reconciled_at,
+    DROP COLUMN IF EXISTS reconciliation_id;
+
+DROP TABLE IF EXISTS reconciliation_sessions;
@@ -0,0 +1,51 @@
+-- Bank reconciliation sessions
+-- A session compares one statement of a bank account with the books. Statement
+-- lines are cleared by a transaction; completing the session stamps those
+-- transactions as reconciled, which locks them against further edits.
+
+CREATE TABLE reconciliation_sessions (
+    id UUID PRIMARY KEY,
+    company_id UUID NOT NULL REFERENCES companies(id) ON DELETE CASCADE,
+    account_id UUID NOT NULL REFERENCES financial_accounts(id) ON DELETE CASCADE,
+    statement_start_date DATE,
+    statement_end_date DATE NOT NULL,
+    opening_balance BIGINT NOT NULL,
+    closing_balance BIGINT,
+    status VARCHAR(20) NOT NULL DEFAULT 'open',
+    created_by UUID NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_by UUID,
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_reconciliation_sessions_account
+    ON reconciliation_sessions (account_id, statement_end_date DESC);
+
+-- At most one session per account is in progress
+CREATE UNIQUE INDEX idx_reconciliation_sessions_open
+    ON reconciliation_sessions (account_id)
+    WHERE status = 'open';
+
+ALTER TABLE financial_transactions
+    ADD COLUMN reconciliation_id UUID REFERENCES reconciliation_sessions(id),
+    ADD COLUMN reconciled_at TIMESTAMPTZ;
+
+CREATE TABLE reconciliation_lines (
+    id UUID PRIMARY KEY,
+    session_id UUID NOT NULL REFERENCES reconciliation_sessions(id) ON DELETE CASCADE,
+    line_date DATE NOT NULL,
+    amount BIGINT NOT NULL,
+    description TEXT NOT NULL,
+    reference TEXT,
+    transaction_id UUID REFERENCES financial_transactions(id) ON DELETE SET NULL,
+    match_type VARCHAR(20),
+    matched_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_reconciliation_lines_session ON reconciliation_lines (session_id, line_date);
+
+-- A transaction clears a single statement line
+CREATE UNIQUE INDEX idx_reconciliation_lines_transaction
+    ON reconciliation_lines (transaction_id)
+    WHERE transaction_id IS NOT NULL;
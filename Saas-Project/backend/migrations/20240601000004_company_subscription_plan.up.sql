@@ -0,0 +1,6 @@
+-- Subscription plan per company
+-- Drives the API quota shared by the company's users (see the rate limiter).
+
+ALTER TABLE companies
+    ADD COLUMN subscription_plan VARCHAR(20) NOT NULL DEFAULT 'free'
+        CHECK (subscription_plan IN ('free', 'basic', 'pro'));
@@ -0,0 +1,11 @@
+DROP TRIGGER IF EXISTS financial_transactions_bump_version ON financial_transactions;
+DROP TRIGGER IF EXISTS financial_accounts_bump_version ON financial_accounts;
+DROP TRIGGER IF EXISTS companies_bump_version ON companies;
+DROP TRIGGER IF EXISTS licenses_bump_version ON licenses;
+
+ALTER TABLE financial_transactions DROP COLUMN IF EXISTS version;
+ALTER TABLE financial_accounts DROP COLUMN IF EXISTS version;
+ALTER TABLE companies DROP COLUMN IF EXISTS version;
+ALTER TABLE licenses DROP COLUMN IF EXISTS version;
+
+DROP FUNCTION IF EXISTS bump_row_version();
@@ -0,0 +1,27 @@
+-- Row versions for optimistic locking
+-- Conditional updates match on the version they read (`WHERE version = $n`)
+-- and fail with a conflict when another writer got there first. The trigger
+-- bumps the version on every update, so writes that do not go through the
+-- repositories' conditional updates (balance adjustments from imports and
+-- reconciliation, manual fixes) still invalidate stale copies.
+
+CREATE OR REPLACE FUNCTION bump_row_version() RETURNS trigger AS $$
+BEGIN
+    NEW.version := OLD.version + 1;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE licenses ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE companies ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE financial_accounts ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE financial_transactions ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
+
+CREATE TRIGGER licenses_bump_version BEFORE UPDATE ON licenses
+    FOR EACH ROW EXECUTE FUNCTION bump_row_version();
+CREATE TRIGGER companies_bump_version BEFORE UPDATE ON companies
+    FOR EACH ROW EXECUTE FUNCTION bump_row_version();
+CREATE TRIGGER financial_accounts_bump_version BEFORE UPDATE ON financial_accounts
+    FOR EACH ROW EXECUTE FUNCTION bump_row_version();
+CREATE TRIGGER financial_transactions_bump_version BEFORE UPDATE ON financial_transactions
+    FOR EACH ROW EXECUTE FUNCTION bump_row_version();
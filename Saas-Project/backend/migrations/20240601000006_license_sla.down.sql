@@ -0,0 +1,7 @@
+DROP TABLE IF EXISTS license_sla_events;
+
+DROP TRIGGER IF EXISTS licenses_track_sla_pause ON licenses;
+DROP FUNCTION IF EXISTS track_sla_pause();
+
+ALTER TABLE licenses DROP COLUMN IF EXISTS sla_paused_seconds;
+ALTER TABLE licenses DROP COLUMN IF EXISTS sla_paused_at;
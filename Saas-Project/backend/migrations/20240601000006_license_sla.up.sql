@@ -0,0 +1,51 @@
+-- SLA clocks for license applications
+-- The clock runs from submission to the decision and stops while the
+-- application waits for the applicant in pendingdocuments. The trigger keeps
+-- the pause bookkeeping on the row itself, so every way of changing the status
+-- is counted. Warnings and escalations are recorded once per threshold.
+
+ALTER TABLE licenses
+    ADD COLUMN sla_paused_at TIMESTAMPTZ,
+    ADD COLUMN sla_paused_seconds BIGINT NOT NULL DEFAULT 0;
+
+CREATE OR REPLACE FUNCTION track_sla_pause() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        IF NEW.application_status = 'pendingdocuments' THEN
+            NEW.sla_paused_at := NOW();
+        END IF;
+        RETURN NEW;
+    END IF;
+
+    -- Not writable by updates; only status changes move the clock
+    NEW.sla_paused_at := OLD.sla_paused_at;
+    NEW.sla_paused_seconds := OLD.sla_paused_seconds;
+    IF NEW.application_status = 'pendingdocuments'
+        AND OLD.application_status <> 'pendingdocuments' THEN
+        NEW.sla_paused_at := NOW();
+    ELSIF OLD.application_status = 'pendingdocuments'
+        AND NEW.application_status <> 'pendingdocuments' THEN
+        NEW.sla_paused_seconds := OLD.sla_paused_seconds
+            + FLOOR(GREATEST(EXTRACT(EPOCH FROM NOW() - OLD.sla_paused_at), 0))::BIGINT;
+        NEW.sla_paused_at := NULL;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER licenses_track_sla_pause BEFORE INSERT OR UPDATE ON licenses
+    FOR EACH ROW EXECUTE FUNCTION track_sla_pause();
+
+CREATE TABLE license_sla_events (
+    id UUID PRIMARY KEY,
+    license_id UUID NOT NULL REFERENCES licenses(id) ON DELETE CASCADE,
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('warning', 'escalation')),
+    -- Share of the SLA target used when the event fired, in percent
+    threshold_percent INTEGER NOT NULL,
+    escalated_to UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (license_id, kind, threshold_percent)
+);
+
+CREATE INDEX idx_license_sla_events_escalated_to ON license_sla_events(escalated_to)
+    WHERE escalated_to IS NOT NULL;
@@ -0,0 +1,7 @@
+DROP INDEX IF EXISTS idx_application_status_history_changed_by;
+
+ALTER TABLE users
+    DROP COLUMN IF EXISTS sessions_revoked_at,
+    DROP COLUMN IF EXISTS password_reset_expires_at,
+    DROP COLUMN IF EXISTS password_reset_token_hash,
+    DROP COLUMN IF EXISTS password_reset_required;
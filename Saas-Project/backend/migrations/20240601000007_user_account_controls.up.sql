@@ -0,0 +1,15 @@
+-- Account controls for admins
+-- Suspending a user, changing their role or forcing a password reset revokes
+-- the sessions issued before it: access tokens carry their issue time and are
+-- rejected when they predate sessions_revoked_at. A forced reset stores only a
+-- hash of the one-time token.
+
+ALTER TABLE users
+    ADD COLUMN password_reset_required BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN password_reset_token_hash TEXT,
+    ADD COLUMN password_reset_expires_at TIMESTAMPTZ,
+    ADD COLUMN sessions_revoked_at TIMESTAMPTZ;
+
+-- Reviewer workload and throughput reports on the operations dashboard
+CREATE INDEX idx_application_status_history_changed_by
+    ON application_status_history (changed_by, changed_at);
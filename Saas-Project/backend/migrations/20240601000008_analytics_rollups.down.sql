@@ -0,0 +1,17 @@
+DROP TRIGGER IF EXISTS companies_queue_analytics ON companies;
+DROP TRIGGER IF EXISTS financial_transactions_queue_analytics ON financial_transactions;
+DROP TRIGGER IF EXISTS licenses_queue_analytics ON licenses;
+DROP FUNCTION IF EXISTS queue_company_analytics();
+DROP FUNCTION IF EXISTS queue_transaction_analytics();
+DROP FUNCTION IF EXISTS queue_license_analytics();
+DROP FUNCTION IF EXISTS queue_analytics_day(TEXT, TIMESTAMPTZ);
+
+DROP INDEX IF EXISTS idx_financial_transactions_date;
+DROP INDEX IF EXISTS idx_licenses_rejected_at;
+DROP INDEX IF EXISTS idx_licenses_approved_at;
+DROP INDEX IF EXISTS idx_licenses_submitted_at;
+
+DROP TABLE IF EXISTS finance_daily_rollups;
+DROP TABLE IF EXISTS company_daily_rollups;
+DROP TABLE IF EXISTS license_daily_rollups;
+DROP TABLE IF EXISTS analytics_dirty_days;
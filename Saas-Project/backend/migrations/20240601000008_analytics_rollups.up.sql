@@ -0,0 +1,178 @@
+-- Daily rollups for the analytics dashboards
+-- License volumes and processing times, company registrations and finance
+-- totals are kept per calendar day in Jakarta time (WIB, UTC+7) and per
+-- dimension, so time series read a few rows per day instead of every license
+-- or transaction. Triggers queue the days a change touches in
+-- analytics_dirty_days; the background refresher recomputes just those days.
+-- Group dimensions are the current values of the license and its company, so
+-- changing a company's province or scale re-queues every day it appears on.
+
+CREATE TABLE analytics_dirty_days (
+    fact VARCHAR(20) NOT NULL CHECK (fact IN ('licenses', 'registrations', 'finance')),
+    day DATE NOT NULL,
+    queued_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (fact, day)
+);
+
+CREATE TABLE license_daily_rollups (
+    day DATE NOT NULL,
+    license_type license_type NOT NULL,
+    application_status application_status NOT NULL,
+    province VARCHAR(100) NOT NULL,
+    business_scale VARCHAR(20) NOT NULL,
+    created BIGINT NOT NULL DEFAULT 0,
+    submitted BIGINT NOT NULL DEFAULT 0,
+    approved BIGINT NOT NULL DEFAULT 0,
+    rejected BIGINT NOT NULL DEFAULT 0,
+    -- Days from submission to decision, summed over the decisions made on
+    -- this day that had a submission date
+    processing_days_sum DOUBLE PRECISION NOT NULL DEFAULT 0,
+    processing_decisions BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (day, license_type, application_status, province, business_scale)
+);
+
+CREATE TABLE company_daily_rollups (
+    day DATE NOT NULL,
+    province VARCHAR(100) NOT NULL,
+    business_scale VARCHAR(20) NOT NULL,
+    registrations BIGINT NOT NULL,
+    PRIMARY KEY (day, province, business_scale)
+);
+
+CREATE TABLE finance_daily_rollups (
+    day DATE NOT NULL,
+    transaction_type VARCHAR(20) NOT NULL,
+    status VARCHAR(20) NOT NULL,
+    province VARCHAR(100) NOT NULL,
+    business_scale VARCHAR(20) NOT NULL,
+    transactions BIGINT NOT NULL,
+    -- Hundredths of a rupiah, like financial_transactions.amount
+    amount_total BIGINT NOT NULL,
+    PRIMARY KEY (day, transaction_type, status, province, business_scale)
+);
+
+-- The refresher recomputes a day from the rows with an event on it
+CREATE INDEX idx_licenses_submitted_at ON licenses (submitted_at) WHERE submitted_at IS NOT NULL;
+CREATE INDEX idx_licenses_approved_at ON licenses (approved_at) WHERE approved_at IS NOT NULL;
+CREATE INDEX idx_licenses_rejected_at ON licenses (rejected_at) WHERE rejected_at IS NOT NULL;
+CREATE INDEX idx_financial_transactions_date ON financial_transactions (transaction_date);
+
+CREATE OR REPLACE FUNCTION queue_analytics_day(p_fact TEXT, p_at TIMESTAMPTZ) RETURNS void AS $$
+BEGIN
+    IF p_at IS NOT NULL THEN
+        INSERT INTO analytics_dirty_days (fact, day)
+        VALUES (p_fact, (p_at AT TIME ZONE 'Asia/Jakarta')::DATE)
+        ON CONFLICT DO NOTHING;
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION queue_license_analytics() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'UPDATE'
+        AND NEW.license_type = OLD.license_type
+        AND NEW.application_status = OLD.application_status
+        AND NEW.company_id = OLD.company_id
+        AND NEW.created_at = OLD.created_at
+        AND NEW.submitted_at IS NOT DISTINCT FROM OLD.submitted_at
+        AND NEW.approved_at IS NOT DISTINCT FROM OLD.approved_at
+        AND NEW.rejected_at IS NOT DISTINCT FROM OLD.rejected_at THEN
+        RETURN NULL;
+    END IF;
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        PERFORM queue_analytics_day('licenses', OLD.created_at);
+        PERFORM queue_analytics_day('licenses', OLD.submitted_at);
+        PERFORM queue_analytics_day('licenses', OLD.approved_at);
+        PERFORM queue_analytics_day('licenses', OLD.rejected_at);
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        PERFORM queue_analytics_day('licenses', NEW.created_at);
+        PERFORM queue_analytics_day('licenses', NEW.submitted_at);
+        PERFORM queue_analytics_day('licenses', NEW.approved_at);
+        PERFORM queue_analytics_day('licenses', NEW.rejected_at);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER licenses_queue_analytics AFTER INSERT OR UPDATE OR DELETE ON licenses
+    FOR EACH ROW EXECUTE FUNCTION queue_license_analytics();
+
+CREATE OR REPLACE FUNCTION queue_transaction_analytics() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'UPDATE'
+        AND NEW.transaction_date = OLD.transaction_date
+        AND NEW.transaction_type = OLD.transaction_type
+        AND NEW.status = OLD.status
+        AND NEW.amount = OLD.amount
+        AND NEW.company_id = OLD.company_id THEN
+        RETURN NULL;
+    END IF;
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        PERFORM queue_analytics_day('finance', OLD.transaction_date);
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        PERFORM queue_analytics_day('finance', NEW.transaction_date);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER financial_transactions_queue_analytics
+    AFTER INSERT OR UPDATE OR DELETE ON financial_transactions
+    FOR EACH ROW EXECUTE FUNCTION queue_transaction_analytics();
+
+CREATE OR REPLACE FUNCTION queue_company_analytics() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'UPDATE'
+        AND NEW.created_at = OLD.created_at
+        AND NEW.address_province = OLD.address_province
+        AND NEW.business_scale = OLD.business_scale THEN
+        RETURN NULL;
+    END IF;
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        PERFORM queue_analytics_day('registrations', OLD.created_at);
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        PERFORM queue_analytics_day('registrations', NEW.created_at);
+    END IF;
+    -- Licenses and transactions are grouped by their company's region and
+    -- scale; deleting the company cascades to them and their own triggers
+    IF TG_OP = 'UPDATE'
+        AND (NEW.address_province <> OLD.address_province
+            OR NEW.business_scale <> OLD.business_scale) THEN
+        INSERT INTO analytics_dirty_days (fact, day)
+        SELECT 'licenses', (event_at AT TIME ZONE 'Asia/Jakarta')::DATE
+        FROM licenses l
+        CROSS JOIN LATERAL (VALUES (l.created_at), (l.submitted_at), (l.approved_at), (l.rejected_at))
+            AS events(event_at)
+        WHERE l.company_id = NEW.id AND event_at IS NOT NULL
+        ON CONFLICT DO NOTHING;
+
+        INSERT INTO analytics_dirty_days (fact, day)
+        SELECT 'finance', (transaction_date AT TIME ZONE 'Asia/Jakarta')::DATE
+        FROM financial_transactions
+        WHERE company_id = NEW.id
+        ON CONFLICT DO NOTHING;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER companies_queue_analytics AFTER INSERT OR UPDATE OR DELETE ON companies
+    FOR EACH ROW EXECUTE FUNCTION queue_company_analytics();
+
+-- Backfill: queue every day that already has data
+INSERT INTO analytics_dirty_days (fact, day)
+SELECT DISTINCT 'licenses', (event_at AT TIME ZONE 'Asia/Jakarta')::DATE
+FROM licenses l
+CROSS JOIN LATERAL (VALUES (l.created_at), (l.submitted_at), (l.approved_at), (l.rejected_at))
+    AS events(event_at)
+WHERE event_at IS NOT NULL;
+
+INSERT INTO analytics_dirty_days (fact, day)
+SELECT DISTINCT 'registrations', (created_at AT TIME ZONE 'Asia/Jakarta')::DATE FROM companies;
+
+INSERT INTO analytics_dirty_days (fact, day)
+SELECT DISTINCT 'finance', (transaction_date AT TIME ZONE 'Asia/Jakarta')::DATE
+FROM financial_transactions;
@@ -0,0 +1,20 @@
+-- License certificates
+-- Every generated certificate PDF is a revision of its license's certificate:
+-- the first when the license is approved, then one per renewal, suspension or
+-- reinstatement. The PDFs themselves live in file storage under storage_key.
+
+CREATE TABLE license_certificates (
+    id UUID PRIMARY KEY,
+    license_id UUID NOT NULL REFERENCES licenses(id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL CHECK (revision > 0),
+    reason TEXT NOT NULL CHECK (reason IN ('issued', 'renewed', 'suspended', 'reinstated', 'regenerated')),
+    -- Status and row version of the license the PDF was rendered from
+    license_status application_status NOT NULL,
+    license_version BIGINT NOT NULL,
+    storage_key TEXT NOT NULL UNIQUE,
+    sha256 TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    generated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    generated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (license_id, revision)
+);
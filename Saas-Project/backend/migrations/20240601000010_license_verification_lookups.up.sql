@@ -0,0 +1,21 @@
+-- Audit trail of public license verifications
+-- One row per lookup through the public verification endpoint, found or not.
+-- Only what was asked and who asked is kept; the answer can be rebuilt from
+-- the license. Lookups outlive the companies and licenses they found.
+
+CREATE TABLE license_verification_lookups (
+    id UUID PRIMARY KEY,
+    method TEXT NOT NULL CHECK (method IN ('token', 'license_number', 'nib')),
+    query TEXT NOT NULL,
+    company_id UUID REFERENCES companies(id) ON DELETE SET NULL,
+    license_id UUID REFERENCES licenses(id) ON DELETE SET NULL,
+    licenses_found INTEGER NOT NULL CHECK (licenses_found >= 0),
+    client_ip TEXT,
+    user_agent TEXT,
+    looked_up_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_license_verification_lookups_looked_up_at
+    ON license_verification_lookups (looked_up_at DESC);
+CREATE INDEX idx_license_verification_lookups_company
+    ON license_verification_lookups (company_id, looked_up_at DESC);
@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS license_comment_reads;
+DROP TABLE IF EXISTS license_comment_mentions;
+DROP TABLE IF EXISTS license_comment_attachments;
+DROP TABLE IF EXISTS license_comments;
@@ -0,0 +1,38 @@
+-- License application comments
+-- Threads between reviewers and applicants. A reply points at the comment that
+-- started its thread. Attachments point at documents already uploaded to the
+-- license, mentions at the users asked to read the comment, and each
+-- participant's reads are kept as receipts.
+
+CREATE TABLE license_comments (
+    id UUID PRIMARY KEY,
+    license_id UUID NOT NULL REFERENCES licenses(id) ON DELETE CASCADE,
+    parent_id UUID REFERENCES license_comments(id) ON DELETE CASCADE,
+    author_id UUID NOT NULL REFERENCES users(id),
+    body TEXT NOT NULL CHECK (btrim(body) <> ''),
+    internal BOOLEAN NOT NULL DEFAULT FALSE,
+    kind TEXT NOT NULL DEFAULT 'comment'
+        CHECK (kind IN ('comment', 'clarification_request')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_license_comments_license ON license_comments (license_id, created_at, id);
+
+CREATE TABLE license_comment_attachments (
+    comment_id UUID NOT NULL REFERENCES license_comments(id) ON DELETE CASCADE,
+    document_id UUID NOT NULL REFERENCES license_documents(id) ON DELETE CASCADE,
+    PRIMARY KEY (comment_id, document_id)
+);
+
+CREATE TABLE license_comment_mentions (
+    comment_id UUID NOT NULL REFERENCES license_comments(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    PRIMARY KEY (comment_id, user_id)
+);
+
+CREATE TABLE license_comment_reads (
+    comment_id UUID NOT NULL REFERENCES license_comments(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    read_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (comment_id, user_id)
+);
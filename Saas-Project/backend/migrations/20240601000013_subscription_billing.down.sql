@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS subscription_invoices;
+DROP SEQUENCE IF EXISTS subscription_invoice_number_seq;
+DROP TABLE IF EXISTS subscriptions;
@@ -0,0 +1,61 @@
+-- Subscription billing
+-- One subscription per company that ever took a paid plan, and the invoices
+-- for its periods. companies.subscription_plan stays the plan in effect and
+-- is updated together with the subscription. Amounts are in hundredths of a
+-- rupiah like the rest of the finance tables; invoice numbers come from a
+-- sequence shared by all companies.
+
+CREATE TABLE subscriptions (
+    id UUID PRIMARY KEY,
+    company_id UUID NOT NULL UNIQUE REFERENCES companies(id) ON DELETE CASCADE,
+    plan VARCHAR(20) NOT NULL CHECK (plan IN ('free', 'basic', 'pro')),
+    status VARCHAR(20) NOT NULL
+        CHECK (status IN ('trialing', 'active', 'past_due', 'canceled')),
+    trial_ends_at TIMESTAMPTZ,
+    current_period_start TIMESTAMPTZ NOT NULL,
+    current_period_end TIMESTAMPTZ NOT NULL CHECK (current_period_end > current_period_start),
+    scheduled_plan VARCHAR(20) CHECK (scheduled_plan IN ('free', 'basic', 'pro')),
+    version BIGINT NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER subscriptions_bump_version BEFORE UPDATE ON subscriptions
+    FOR EACH ROW EXECUTE FUNCTION bump_row_version();
+
+CREATE INDEX idx_subscriptions_renewal ON subscriptions (current_period_end)
+    WHERE status <> 'canceled';
+
+CREATE SEQUENCE subscription_invoice_number_seq;
+
+CREATE TABLE subscription_invoices (
+    id UUID PRIMARY KEY,
+    number VARCHAR(32) NOT NULL UNIQUE,
+    company_id UUID NOT NULL REFERENCES companies(id) ON DELETE CASCADE,
+    subscription_id UUID NOT NULL REFERENCES subscriptions(id) ON DELETE CASCADE,
+    plan VARCHAR(20) NOT NULL CHECK (plan IN ('free', 'basic', 'pro')),
+    period_start TIMESTAMPTZ NOT NULL,
+    period_end TIMESTAMPTZ NOT NULL,
+    subtotal BIGINT NOT NULL CHECK (subtotal >= 0),
+    credit BIGINT NOT NULL DEFAULT 0 CHECK (credit >= 0 AND credit <= subtotal),
+    ppn_rate_bps BIGINT NOT NULL CHECK (ppn_rate_bps >= 0),
+    ppn BIGINT NOT NULL CHECK (ppn >= 0),
+    total BIGINT NOT NULL CHECK (total = subtotal - credit + ppn),
+    currency VARCHAR(3) NOT NULL DEFAULT 'IDR',
+    status VARCHAR(20) NOT NULL
+        CHECK (status IN ('open', 'paid', 'void', 'uncollectible')),
+    issued_at TIMESTAMPTZ NOT NULL,
+    due_at TIMESTAMPTZ NOT NULL,
+    paid_at TIMESTAMPTZ,
+    payment_reference VARCHAR(255),
+    last_payment_error TEXT,
+    reminders_sent INTEGER NOT NULL DEFAULT 0,
+    next_reminder_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_subscription_invoices_company ON subscription_invoices (company_id, issued_at DESC);
+CREATE INDEX idx_subscription_invoices_open ON subscription_invoices (subscription_id)
+    WHERE status = 'open';
+CREATE INDEX idx_subscription_invoices_dunning ON subscription_invoices (next_reminder_at)
+    WHERE status = 'open';
@@ -0,0 +1,6 @@
+DROP TABLE IF EXISTS invoice_payment_allocations;
+DROP TABLE IF EXISTS invoice_payments;
+DROP TABLE IF EXISTS sales_invoice_lines;
+DROP TABLE IF EXISTS sales_invoices;
+DROP TABLE IF EXISTS sales_invoice_sequences;
+DROP TABLE IF EXISTS contacts;
@@ -0,0 +1,103 @@
+-- Customer invoicing
+-- Contacts are the customers a company bills; sales invoices (faktur) bill
+-- them. Invoice numbers are taken from a counter per company and issue year
+-- when an invoice is sent, so drafts do not leave gaps. Payments received are
+-- booked as one financial transaction and split over the invoices they
+-- settle. Amounts are in hundredths of a rupiah.
+
+CREATE TABLE contacts (
+    id UUID PRIMARY KEY,
+    company_id UUID NOT NULL REFERENCES companies(id) ON DELETE CASCADE,
+    name VARCHAR(200) NOT NULL,
+    email VARCHAR(255),
+    phone VARCHAR(20),
+    npwp VARCHAR(20),
+    address TEXT,
+    notes TEXT,
+    version BIGINT NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER contacts_bump_version BEFORE UPDATE ON contacts
+    FOR EACH ROW EXECUTE FUNCTION bump_row_version();
+
+CREATE INDEX idx_contacts_company ON contacts (company_id, name);
+
+CREATE TABLE sales_invoice_sequences (
+    company_id UUID NOT NULL REFERENCES companies(id) ON DELETE CASCADE,
+    year INTEGER NOT NULL,
+    last_number BIGINT NOT NULL,
+    PRIMARY KEY (company_id, year)
+);
+
+CREATE TABLE sales_invoices (
+    id UUID PRIMARY KEY,
+    company_id UUID NOT NULL REFERENCES companies(id) ON DELETE CASCADE,
+    contact_id UUID NOT NULL REFERENCES contacts(id),
+    number VARCHAR(32),
+    status VARCHAR(20) NOT NULL
+        CHECK (status IN ('draft', 'sent', 'partially_paid', 'paid', 'overdue', 'void')),
+    issue_date DATE,
+    due_date DATE CHECK (due_date >= issue_date),
+    subtotal BIGINT NOT NULL CHECK (subtotal >= 0),
+    discount BIGINT NOT NULL DEFAULT 0 CHECK (discount >= 0 AND discount <= subtotal),
+    ppn_rate_bps BIGINT NOT NULL CHECK (ppn_rate_bps >= 0),
+    ppn BIGINT NOT NULL CHECK (ppn >= 0),
+    total BIGINT NOT NULL CHECK (total = subtotal - discount + ppn),
+    amount_paid BIGINT NOT NULL DEFAULT 0 CHECK (amount_paid >= 0 AND amount_paid <= total),
+    currency VARCHAR(3) NOT NULL DEFAULT 'IDR',
+    notes TEXT,
+    sent_at TIMESTAMPTZ,
+    emailed_at TIMESTAMPTZ,
+    voided_at TIMESTAMPTZ,
+    created_by UUID NOT NULL,
+    version BIGINT NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (company_id, number),
+    CHECK ((number IS NULL) = (sent_at IS NULL))
+);
+
+CREATE TRIGGER sales_invoices_bump_version BEFORE UPDATE ON sales_invoices
+    FOR EACH ROW EXECUTE FUNCTION bump_row_version();
+
+CREATE INDEX idx_sales_invoices_company ON sales_invoices (company_id, created_at DESC);
+CREATE INDEX idx_sales_invoices_contact ON sales_invoices (contact_id);
+CREATE INDEX idx_sales_invoices_open ON sales_invoices (due_date)
+    WHERE status IN ('sent', 'partially_paid', 'overdue');
+
+CREATE TABLE sales_invoice_lines (
+    invoice_id UUID NOT NULL REFERENCES sales_invoices(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    description TEXT NOT NULL,
+    quantity BIGINT NOT NULL CHECK (quantity > 0),
+    unit_price BIGINT NOT NULL CHECK (unit_price >= 0),
+    discount_bps BIGINT NOT NULL DEFAULT 0 CHECK (discount_bps BETWEEN 0 AND 10000),
+    amount BIGINT NOT NULL CHECK (amount >= 0),
+    PRIMARY KEY (invoice_id, position)
+);
+
+CREATE TABLE invoice_payments (
+    id UUID PRIMARY KEY,
+    company_id UUID NOT NULL REFERENCES companies(id) ON DELETE CASCADE,
+    contact_id UUID NOT NULL REFERENCES contacts(id),
+    account_id UUID NOT NULL REFERENCES financial_accounts(id),
+    transaction_id UUID NOT NULL UNIQUE REFERENCES financial_transactions(id),
+    amount BIGINT NOT NULL CHECK (amount > 0),
+    paid_on DATE NOT NULL,
+    reference VARCHAR(100),
+    created_by UUID NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_invoice_payments_company ON invoice_payments (company_id, paid_on);
+
+CREATE TABLE invoice_payment_allocations (
+    payment_id UUID NOT NULL REFERENCES invoice_payments(id) ON DELETE CASCADE,
+    invoice_id UUID NOT NULL REFERENCES sales_invoices(id) ON DELETE CASCADE,
+    amount BIGINT NOT NULL CHECK (amount > 0),
+    PRIMARY KEY (payment_id, invoice_id)
+);
+
+CREATE INDEX idx_invoice_payment_allocations_invoice ON invoice_payment_allocations (invoice_id);
@@ -0,0 +1,11 @@
+DROP INDEX IF EXISTS idx_license_documents_notes_search_vector;
+ALTER TABLE license_documents DROP COLUMN IF EXISTS notes_search_vector;
+ALTER TABLE license_documents DROP COLUMN IF EXISTS search_vector;
+
+ALTER TABLE license_documents
+    ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+        setweight(to_tsvector('public.id_search', regexp_replace(coalesce(original_file_name, ''), '[._-]+', ' ', 'g')), 'A') ||
+        setweight(to_tsvector('public.id_search', coalesce(notes, '')), 'B')
+    ) STORED;
+
+CREATE INDEX idx_license_documents_search_vector ON license_documents USING GIN (search_vector);
@@ -0,0 +1,15 @@
+-- Reviewer notes on license documents are internal. They move out of the
+-- search vector owners query into one that only admin searches use.
+
+ALTER TABLE license_documents DROP COLUMN search_vector;
+
+ALTER TABLE license_documents
+    ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+        setweight(to_tsvector('public.id_search', regexp_replace(coalesce(original_file_name, ''), '[._-]+', ' ', 'g')), 'A')
+    ) STORED,
+    ADD COLUMN notes_search_vector tsvector GENERATED ALWAYS AS (
+        setweight(to_tsvector('public.id_search', coalesce(notes, '')), 'B')
+    ) STORED;
+
+CREATE INDEX idx_license_documents_search_vector ON license_documents USING GIN (search_vector);
+CREATE INDEX idx_license_documents_notes_search_vector ON license_documents USING GIN (notes_search_vector);
@@ -14,8 +14,7 @@ NC='\033[0m' # No Color
 
 # Get the directory of this script
 SCRIPT_DIR="$( cd "$( dirname "${BASH_SOURCE[0]}" )" && pwd )"
-MIGRATIONS_DIR="$SCRIPT_DIR/migrations"
-ROLLBACKS_DIR="$SCRIPT_DIR/migrations/rollbacks"
+MIGRATIONS_DIR="$SCRIPT_DIR/../migrations"
 
 # Function to show usage information
 function show_help {
@@ -27,6 +26,7 @@ function show_help {
     echo -e "  ${GREEN}down${NC}         Roll back the most recent migration"
     echo -e "  ${GREEN}down [version]${NC}  Roll back to the specified version"
     echo -e "  ${GREEN}status${NC}       Show the current migration status"
+    echo -e "  ${GREEN}verify${NC}       Check the database schema against the repositories"
     echo -e "  ${GREEN}create [name]${NC}   Create a new migration file"
     echo -e "  ${GREEN}help${NC}         Show this help message"
     echo
@@ -60,6 +60,12 @@ function show_status {
     cargo run --bin migrate status
 }
 
+# Function to verify the live schema
+function verify_schema {
+    echo -e "${BLUE}Verifying database schema...${NC}"
+    cargo run --bin migrate verify
+}
+
 # Function to create a new migration file
 function create_migration {
     local name=$1
@@ -71,13 +77,13 @@ function create_migration {
     fi
     
     local timestamp=$(date +"%Y%m%d%H%M%S")
-    local filename="${timestamp}_${name}.sql"
-    local rollback_filename="${timestamp}_${name}_rollback.sql"
+    local up_filename="${timestamp}_${name}.up.sql"
+    local down_filename="${timestamp}_${name}.down.sql"
     
-    echo -e "${BLUE}Creating new migration: $filename${NC}"
+    echo -e "${BLUE}Creating new migration: ${timestamp}_${name}${NC}"
     
     # Create migration file
-    cat > "$MIGRATIONS_DIR/$filename" << EOF
+    cat > "$MIGRATIONS_DIR/$up_filename" << EOF
 -- Migration: $name
 -- Created at: $(date -u)
 
@@ -85,17 +91,16 @@ function create_migration {
 
 EOF
     
-    # Create rollback file
-    mkdir -p "$ROLLBACKS_DIR"
-    cat > "$ROLLBACKS_DIR/$rollback_filename" << EOF
--- Rollback script for migration $filename
+    # Create the paired down migration
+    cat > "$MIGRATIONS_DIR/$down_filename" << EOF
+-- Reverts $up_filename
 
 -- Write your rollback SQL here
 
 EOF
     
-    echo -e "${GREEN}Created migration file: $filename${NC}"
-    echo -e "${GREEN}Created rollback file: $rollback_filename${NC}"
+    echo -e "${GREEN}Created migration file: $up_filename${NC}"
+    echo -e "${GREEN}Created rollback file: $down_filename${NC}"
 }
 
 # Main script logic
@@ -114,6 +119,9 @@ case "$1" in
     "status")
         show_status
         ;;
+    "verify")
+        verify_schema
+        ;;
     "create")
         create_migration "$2"
         ;;
@@ -9,7 +9,7 @@ echo "=========================================="
 
 # Run in-memory repository tests
 echo "\n\n🧪 Running in-memory repository tests..."
-cargo run --bin in_memory_user_repo_test
+cargo test --lib in_memory
 
 # Check if database is available
 echo "\n\n🔍 Checking database availability..."
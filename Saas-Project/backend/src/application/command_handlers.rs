@@ -2,26 +2,43 @@
 
 use std::sync::Arc;
 
-use super::commands::{ChangePasswordCommand, CreateUserCommand, UpdateUserCommand};
-use crate::domain::entities::{User, UserRole};
+use chrono::{Duration, Utc};
+use tracing::info;
+
+use super::commands::{
+    AdminUserAction, AdminUserCommand, ChangePasswordCommand, CreateUserCommand,
+    UpdateUserCommand,
+};
+use crate::domain::entities::{User, UserRole, UserStatus};
+use crate::domain::filters::UserFilter;
+use crate::domain::notifications::MessageSender;
 use crate::domain::repositories::UserRepository;
-use crate::domain::value_objects::UserId;
+use crate::domain::value_objects::{Email, UserId};
 use crate::services::auth::AuthService;
 use crate::shared::errors::{AppError, AppResult};
+use crate::shared::query::ListQuery;
+
+/// How long the token from a forced password reset stays valid
+const PASSWORD_RESET_TOKEN_HOURS: i64 = 24;
 
 pub struct UserCommandHandler {
     user_repository: Arc<dyn UserRepository + Send + Sync>,
     auth_service: Arc<AuthService>,
+    /// Sends the one-time token of a forced password reset to the account's
+    /// owner; nobody else ever sees it
+    email: Arc<dyn MessageSender>,
 }
 
 impl UserCommandHandler {
     pub fn new(
         user_repository: Arc<dyn UserRepository + Send + Sync>,
         auth_service: Arc<AuthService>,
+        email: Arc<dyn MessageSender>,
     ) -> Self {
         Self {
             user_repository,
             auth_service,
+            email,
         }
     }
 
@@ -138,20 +155,316 @@ impl UserCommandHandler {
 
         Ok(())
     }
+
+    /// Suspends, reactivates, forces a password reset on or changes the role
+    /// of another account. Admin staff manage UMKM owners only; roles and
+    /// other admins are for super admins.
+    pub async fn handle_admin_action(&self, command: AdminUserCommand) -> AppResult<User> {
+        let actor_is_super_admin = match command.actor_role {
+            UserRole::SuperAdmin => true,
+            UserRole::AdminStaff => false,
+            UserRole::UmkmOwner => {
+                return Err(AppError::Forbidden("Admin access required".to_string()))
+            }
+        };
+        if command.actor_id == command.target_id {
+            return Err(AppError::Forbidden(
+                "Admins cannot change their own account".to_string(),
+            ));
+        }
+
+        let mut user = self
+            .user_repository
+            .find_by_id(&command.target_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        if !actor_is_super_admin {
+            if user.role != UserRole::UmkmOwner {
+                return Err(AppError::Forbidden(
+                    "Only super admins can manage admin accounts".to_string(),
+                ));
+            }
+            if matches!(command.action, AdminUserAction::ChangeRole(_)) {
+                return Err(AppError::Forbidden(
+                    "Only super admins can change roles".to_string(),
+                ));
+            }
+        }
+
+        // Escalations and role changes need an active super admin to remain
+        let removes_super_admin = user.role == UserRole::SuperAdmin
+            && user.status == UserStatus::Active
+            && match &command.action {
+                AdminUserAction::Suspend => true,
+                AdminUserAction::ChangeRole(role) => *role != UserRole::SuperAdmin,
+                _ => false,
+            };
+        if removes_super_admin && self.active_super_admins().await? <= 1 {
+            return Err(AppError::Conflict(
+                "The last active super admin cannot be suspended or demoted".to_string(),
+            ));
+        }
+
+        let mut reset_token = None;
+        match &command.action {
+            AdminUserAction::Suspend => user.suspend().map_err(AppError::Conflict)?,
+            AdminUserAction::Reactivate => user.reactivate().map_err(AppError::Conflict)?,
+            AdminUserAction::ForcePasswordReset => {
+                let token = self.auth_service.generate_reset_token();
+                let token_hash = self
+                    .auth_service
+                    .hash_password(&token)
+                    .map_err(|e| AppError::InternalError(format!("Failed to hash reset token: {}", e)))?;
+                user.require_password_reset(
+                    token_hash,
+                    Utc::now() + Duration::hours(PASSWORD_RESET_TOKEN_HOURS),
+                );
+                reset_token = Some(token);
+            }
+            AdminUserAction::ChangeRole(role) => {
+                user.change_role(role.clone()).map_err(AppError::Conflict)?
+            }
+        }
+
+        self.user_repository.save(&user).await?;
+        info!(
+            actor_id = %command.actor_id,
+            target_id = %command.target_id,
+            action = ?command.action,
+            "👮 Admin action on user account"
+        );
+
+        if let Some(token) = reset_token {
+            self.send_reset_token(&user, &token).await?;
+        }
+
+        Ok(user)
+    }
+
+    /// Emails the token of a forced password reset to the account's owner
+    async fn send_reset_token(&self, user: &User, token: &str) -> AppResult<()> {
+        let body = format!(
+            "Yth. {},\n\nAdmin telah mengatur ulang kata sandi akun Anda. Buat kata sandi baru dengan token berikut, yang berlaku {} jam:\n\n{}\n\nSemua sesi yang sedang masuk telah diakhiri.",
+            user.full_name, PASSWORD_RESET_TOKEN_HOURS, token
+        );
+        self.email
+            .send(user.email.as_str(), "Atur ulang kata sandi", &body)
+            .await
+    }
+
+    async fn active_super_admins(&self) -> AppResult<i64> {
+        let filter = UserFilter {
+            role: Some(UserRole::SuperAdmin),
+            status: Some(UserStatus::Active),
+            ..UserFilter::default()
+        };
+        let page = self
+            .user_repository
+            .list(&ListQuery::new(filter).with_limit(2))
+            .await?;
+        Ok(page.data.len() as i64)
+    }
+
+    /// Sets a new password with the token from a forced reset and ends every
+    /// session signed in with the old one
+    pub async fn handle_confirm_password_reset(
+        &self,
+        email: &Email,
+        token: &str,
+        new_password: &str,
+    ) -> AppResult<()> {
+        let invalid = || AppError::Unauthorized("Invalid or expired reset token".to_string());
+        let mut user = self
+            .user_repository
+            .find_by_email(email)
+            .await?
+            .ok_or_else(invalid)?;
+
+        let (Some(token_hash), Some(expires_at)) =
+            (&user.password_reset_token_hash, user.password_reset_expires_at)
+        else {
+            return Err(invalid());
+        };
+        if expires_at <= Utc::now() {
+            return Err(invalid());
+        }
+        let is_valid = self
+            .auth_service
+            .verify_password(token, token_hash)
+            .map_err(|e| AppError::InternalError(format!("Reset token verification failed: {}", e)))?;
+        if !is_valid {
+            return Err(invalid());
+        }
+
+        if new_password.len() < 8 {
+            return Err(AppError::Validation(
+                "Password must be at least 8 characters".to_string(),
+            ));
+        }
+        let new_password_hash = self
+            .auth_service
+            .hash_password(new_password)
+            .map_err(|e| AppError::InternalError(format!("Failed to hash new password: {}", e)))?;
+        user.update_password(new_password_hash);
+        user.revoke_sessions();
+        self.user_repository.save(&user).await?;
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    // use super::*;
-    // use crate::domain::value_objects::PhoneNumber;
+    use super::*;
+    use crate::infrastructure::messaging::RecordingSender;
+    use crate::infrastructure::repositories::InMemoryUserRepository;
+
+    async fn seeded(repo: &InMemoryUserRepository, email: &str, role: UserRole) -> User {
+        let mut user = User::new(
+            Email::new(email).unwrap(),
+            "hash".to_string(),
+            email.to_string(),
+            role,
+        );
+        user.status = UserStatus::Active;
+        repo.save(&user).await.unwrap();
+        user
+    }
+
+    fn handler(repo: &Arc<InMemoryUserRepository>) -> UserCommandHandler {
+        handler_sending_to(repo, RecordingSender::new("email"))
+    }
+
+    fn handler_sending_to(repo: &Arc<InMemoryUserRepository>, email: RecordingSender) -> UserCommandHandler {
+        UserCommandHandler::new(
+            repo.clone(),
+            Arc::new(AuthService::new("test_secret".to_string())),
+            Arc::new(email),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_admin_staff_manage_owners_only() {
+        let repo = Arc::new(InMemoryUserRepository::new());
+        let staff = seeded(&repo, "staff@example.com", UserRole::AdminStaff).await;
+        let other_staff = seeded(&repo, "staff2@example.com", UserRole::AdminStaff).await;
+        let owner = seeded(&repo, "owner@example.com", UserRole::UmkmOwner).await;
+        let handler = handler(&repo);
+        let as_staff = |target: &User, action| {
+            AdminUserCommand::new(staff.id.clone(), UserRole::AdminStaff, target.id.clone(), action)
+        };
+
+        let user = handler
+            .handle_admin_action(as_staff(&owner, AdminUserAction::Suspend))
+            .await
+            .unwrap();
+        assert_eq!(user.status, UserStatus::Suspended);
+        assert!(user.sessions_revoked_at.is_some());
+        assert!(matches!(
+            handler.handle_admin_action(as_staff(&owner, AdminUserAction::Suspend)).await,
+            Err(AppError::Conflict(_))
+        ));
+        handler
+            .handle_admin_action(as_staff(&owner, AdminUserAction::Reactivate))
+            .await
+            .unwrap();
+
+        for command in [
+            as_staff(&other_staff, AdminUserAction::Suspend),
+            as_staff(&owner, AdminUserAction::ChangeRole(UserRole::AdminStaff)),
+            as_staff(&staff, AdminUserAction::ForcePasswordReset),
+            AdminUserCommand::new(
+                owner.id.clone(),
+                UserRole::UmkmOwner,
+                other_staff.id.clone(),
+                AdminUserAction::Suspend,
+            ),
+        ] {
+            assert!(matches!(
+                handler.handle_admin_action(command).await,
+                Err(AppError::Forbidden(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_last_active_super_admin_is_kept() {
+        let repo = Arc::new(InMemoryUserRepository::new());
+        let root = seeded(&repo, "root@example.com", UserRole::SuperAdmin).await;
+        let deputy = seeded(&repo, "deputy@example.com", UserRole::SuperAdmin).await;
+        let handler = handler(&repo);
+
+        handler
+            .handle_admin_action(AdminUserCommand::new(
+                root.id.clone(),
+                UserRole::SuperAdmin,
+                deputy.id.clone(),
+                AdminUserAction::ChangeRole(UserRole::AdminStaff),
+            ))
+            .await
+            .unwrap();
+        let result = handler
+            .handle_admin_action(AdminUserCommand::new(
+                deputy.id.clone(),
+                UserRole::SuperAdmin,
+                root.id.clone(),
+                AdminUserAction::Suspend,
+            ))
+            .await;
+        assert!(matches!(result, Err(AppError::Conflict(_))));
+    }
+
+    #[tokio::test]
+    async fn test_forced_password_reset_round_trip() {
+        let repo = Arc::new(InMemoryUserRepository::new());
+        let root = seeded(&repo, "root@example.com", UserRole::SuperAdmin).await;
+        let owner = seeded(&repo, "owner@example.com", UserRole::UmkmOwner).await;
+        let email = RecordingSender::new("email");
+        let handler = handler_sending_to(&repo, email.clone());
+
+        let user = handler
+            .handle_admin_action(AdminUserCommand::new(
+                root.id.clone(),
+                UserRole::SuperAdmin,
+                owner.id.clone(),
+                AdminUserAction::ForcePasswordReset,
+            ))
+            .await
+            .unwrap();
+        assert!(user.password_reset_required);
+        assert!(!user.can_use_session(Utc::now().timestamp()));
+
+        // The token only reaches the owner's mailbox
+        let sent = email.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].recipient, "owner@example.com");
+        let token = sent[0]
+            .body
+            .split_whitespace()
+            .find(|word| word.len() >= 32)
+            .expect("the message carries the reset token")
+            .to_string();
 
-    // Mock implementations would go here for testing
-    // For now, we'll add basic construction tests
+        assert!(matches!(
+            handler
+                .handle_confirm_password_reset(&owner.email, "wrong-token", "NewPass123!")
+                .await,
+            Err(AppError::Unauthorized(_))
+        ));
+        handler
+            .handle_confirm_password_reset(&owner.email, &token, "NewPass123!")
+            .await
+            .unwrap();
 
-    #[test]
-    fn test_command_handler_construction() {
-        // This test will be completed when we have mock repositories
-        // let handler = UserCommandHandler::new(mock_repo, mock_auth);
-        // assert!(true);
+        let user = repo.find_by_id(&owner.id).await.unwrap().unwrap();
+        assert!(!user.password_reset_required);
+        assert!(user.password_reset_token_hash.is_none());
+        assert!(matches!(
+            handler
+                .handle_confirm_password_reset(&owner.email, &token, "Another123!")
+                .await,
+            Err(AppError::Unauthorized(_))
+        ));
     }
 }
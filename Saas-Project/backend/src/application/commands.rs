@@ -94,6 +94,34 @@ impl DeleteUserCommand {
     }
 }
 
+/// Account actions admins take on other users
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AdminUserAction {
+    Suspend,
+    Reactivate,
+    ForcePasswordReset,
+    ChangeRole(UserRole),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminUserCommand {
+    pub actor_id: UserId,
+    pub actor_role: UserRole,
+    pub target_id: UserId,
+    pub action: AdminUserAction,
+}
+
+impl AdminUserCommand {
+    pub fn new(actor_id: UserId, actor_role: UserRole, target_id: UserId, action: AdminUserAction) -> Self {
+        Self {
+            actor_id,
+            actor_role,
+            target_id,
+            action,
+        }
+    }
+}
+
 // License Management Commands (placeholder for future implementation)
 #[allow(dead_code)]
 pub struct CreateLicenseCommand;
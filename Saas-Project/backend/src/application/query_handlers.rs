@@ -4,9 +4,11 @@ use std::sync::Arc;
 
 use super::queries::{GetUserQuery, ListLicensesQuery};
 use crate::domain::entities::User;
+use crate::domain::filters::{LicenseFilter, UserFilter, UserSortField};
 use crate::domain::repositories::UserRepository;
 use crate::infrastructure::repositories::LicenseRepository;
 use crate::shared::errors::AppResult;
+use crate::shared::query::{ListQuery, Page};
 use crate::shared::types::PaginatedResponse;
 
 pub struct UserQueryHandler {
@@ -40,6 +42,13 @@ impl UserQueryHandler {
         Ok(PaginatedResponse::new(users, total, page, limit))
     }
 
+    pub async fn handle_list(
+        &self,
+        query: &ListQuery<UserFilter, UserSortField>,
+    ) -> AppResult<Page<User>> {
+        self.user_repository.list(query).await
+    }
+
     pub async fn handle_search_users(&self, email_query: &str) -> AppResult<Vec<User>> {
         self
             .user_repository
@@ -67,8 +76,8 @@ impl LicenseQueryHandler {
 
     pub async fn handle_list_licenses(&self, _query: ListLicensesQuery) -> AppResult<Vec<String>> {
         if let Some(repo) = &self.license_repository {
-            let licenses = repo.search_licenses("", None).await?;
-            Ok(licenses.into_iter().map(|l| l.title).collect())
+            let page = repo.list_licenses(&ListQuery::new(LicenseFilter::default())).await?;
+            Ok(page.data.into_iter().map(|l| l.title).collect())
         } else {
             Ok(vec![])
         }
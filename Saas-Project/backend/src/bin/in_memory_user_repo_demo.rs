@@ -48,6 +48,10 @@ async fn test_basic_operations(repo: &InMemoryUserRepository) -> DemoResult<()>
         email_verified_at: None,
         created_at: Utc::now(),
         updated_at: Utc::now(),
+        password_reset_required: false,
+        password_reset_token_hash: None,
+        password_reset_expires_at: None,
+        sessions_revoked_at: None,
     };
     
     // Save user
@@ -100,6 +104,10 @@ async fn test_pagination_and_search() -> DemoResult<()> {
             email_verified_at: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            password_reset_required: false,
+            password_reset_token_hash: None,
+            password_reset_expires_at: None,
+            sessions_revoked_at: None,
         };
         users.push(user);
     }
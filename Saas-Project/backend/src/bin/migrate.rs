@@ -1,146 +1,191 @@
 // Database migration binary
 // Separate binary for running database migrations
+//
+// Usage: migrate [up | down [version] | status | verify]
+//   up               apply all pending migrations
+//   down             revert the latest applied migration
+//   down <version>   revert every migration newer than <version>; 0 reverts all
+//   status           list every migration and whether it is applied
+//   verify           diff the live schema against what the repositories expect
 
-use sqlx::postgres::PgPoolOptions;
-use sqlx::Row; // Added for row.get method
+use saas_umkm_backend::infrastructure::database::migrations::{self, MigrationState, MIGRATOR};
+use saas_umkm_backend::infrastructure::database::schema;
+use sqlx::postgres::{PgPool, PgPoolOptions};
 use std::env;
+use std::process::ExitCode;
+
+type CommandResult = Result<bool, Box<dyn std::error::Error>>;
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<ExitCode, Box<dyn std::error::Error>> {
     // Load environment variables
     dotenvy::dotenv().ok();
-    
+
     let database_url = env::var("DATABASE_URL")
         .expect("DATABASE_URL must be set");
 
     let args: Vec<String> = env::args().collect();
     let command = args.get(1).map(|s| s.as_str()).unwrap_or("up");
 
-    match command {
+    let succeeded = match command {
         "up" => run_migrations(&database_url).await?,
         "down" => {
-            let version = args.get(2);
+            let version = match args.get(2) {
+                Some(value) => match value.parse::<i64>() {
+                    Ok(version) => Some(version),
+                    Err(_) => {
+                        println!("❌ Invalid version: {}", value);
+                        return Ok(ExitCode::FAILURE);
+                    }
+                },
+                None => None,
+            };
             rollback_migrations(&database_url, version).await?
         },
         "status" => show_status(&database_url).await?,
+        "verify" => verify_schema(&database_url).await?,
         _ => {
             println!("Unknown command: {}", command);
-            println!("Available commands: up, down, status");
-            return Ok(());
+            println!("Available commands: up, down [version], status, verify");
+            return Ok(ExitCode::FAILURE);
         }
+    };
+
+    if !succeeded {
+        return Ok(ExitCode::FAILURE);
     }
 
     println!("✅ Migration command completed successfully!");
-    
-    Ok(())
+
+    Ok(ExitCode::SUCCESS)
 }
 
-async fn run_migrations(database_url: &str) -> Result<(), Box<dyn std::error::Error>> {
+async fn connect(database_url: &str) -> Result<PgPool, sqlx::Error> {
     println!("🔄 Connecting to database...");
-    
-    let pool = PgPoolOptions::new()
+
+    PgPoolOptions::new()
         .max_connections(5)
         .connect(database_url)
-        .await?;
+        .await
+}
+
+async fn run_migrations(database_url: &str) -> CommandResult {
+    let pool = connect(database_url).await?;
 
     println!("🔄 Running database migrations...");
-    
-    sqlx::migrate!("./migrations")
-        .run(&pool)
-        .await?;
 
-    println!("✅ Database migrations completed successfully!");
-    Ok(())
+    MIGRATOR.run(&pool).await?;
+
+    let version = migrations::current_version(&pool).await?;
+    println!(
+        "✅ Database migrations completed successfully! Schema is at version {}",
+        version.unwrap_or(0)
+    );
+    Ok(true)
 }
 
-async fn rollback_migrations(database_url: &str, version: Option<&String>) -> Result<(), Box<dyn std::error::Error>> {
-    println!("🔄 Connecting to database...");
-    
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(database_url)
-        .await?;
-
-    let migrator = sqlx::migrate!("./migrations");
-    
-    if let Some(target_version) = version {
-        println!("🔄 Rolling back migrations to version {}...", target_version);
-        // TODO: Implement targeted rollback when sqlx supports it better
-        // For now we'll roll back one step at a time
-        println!("⚠️  Rolling back to specific version not yet supported. Rolling back one step.");
-        migrator.undo(&pool, 1).await?;
-    } else {
-        println!("🔄 Rolling back last migration...");
-        migrator.undo(&pool, 1).await?;
-    }
+async fn rollback_migrations(database_url: &str, version: Option<i64>) -> CommandResult {
+    let pool = connect(database_url).await?;
+
+    let Some(current) = migrations::current_version(&pool).await? else {
+        println!("ℹ️  No migrations are applied, nothing to roll back");
+        return Ok(true);
+    };
+
+    let target = match version {
+        Some(target) => {
+            if target != 0 && !migrations::is_known_version(target) {
+                println!("❌ Unknown migration version {}", target);
+                return Ok(false);
+            }
+            if target >= current {
+                println!("ℹ️  Schema is at version {}, nothing to roll back", current);
+                return Ok(true);
+            }
+            println!("🔄 Rolling back migrations to version {}...", target);
+            target
+        }
+        None => {
+            let previous = migrations::previous_version(&pool).await?.unwrap_or(0);
+            println!("🔄 Rolling back migration {}...", current);
+            previous
+        }
+    };
+
+    MIGRATOR.undo(&pool, target).await?;
 
-    println!("✅ Rollback completed successfully!");
-    Ok(())
+    println!("✅ Rollback completed successfully! Schema is at version {}", target);
+    Ok(true)
 }
 
-async fn show_status(database_url: &str) -> Result<(), Box<dyn std::error::Error>> {
-    println!("🔄 Connecting to database...");
-    
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(database_url)
-        .await?;
+async fn show_status(database_url: &str) -> CommandResult {
+    let pool = connect(database_url).await?;
 
     println!("📊 Checking migration status...");
-    
-    // Read migration files
-    let migrations_dir = std::path::Path::new("./migrations");
-    let migration_files: Vec<_> = std::fs::read_dir(migrations_dir)?
-        .filter_map(|entry| entry.ok())
-        .filter(|entry| {
-            if let Ok(file_type) = entry.file_type() {
-                file_type.is_file() && entry.file_name().to_string_lossy().ends_with(".sql")
-            } else {
-                false
-            }
-        })
-        .collect();
-    
-    // Get applied migrations from database
-    let query = "SELECT version, description, installed_on FROM _sqlx_migrations ORDER BY installed_on";
-    let rows = sqlx::query(query)
-        .fetch_all(&pool)
-        .await?;
-    
+
+    let statuses = migrations::migration_status(&pool).await?;
+
     println!("\nMigration Status:");
     println!("=================");
-    println!("{:<20} {:<40} {:<20} {:<10}", "Version", "Description", "Applied At", "Status");
-    println!("{:<20} {:<40} {:<20} {:<10}", "-------", "-----------", "----------", "------");
-    
-    // Display migration status
-    for file in &migration_files {
-        // Convert OsString to an owned String to avoid borrowing a temporary
-        let file_name = file.file_name().to_string_lossy().into_owned();
-        
-        if !file_name.contains("_") {
-            continue;
-        }
-        
-        let version = file_name.split('_').next().unwrap_or("unknown");
-        let description = file_name
-            .trim_start_matches(&format!("{}_", version))
-            .trim_end_matches(".sql");
-        
-        // Check if migration is applied by searching rows
-        let is_applied = rows.iter().any(|row| {
-            let db_version: &str = row.get("version");
-            db_version == version
-        });
-        
-        println!("{:<20} {:<40} {:<20} {:<10}", 
-            version, 
-            description, 
-            if is_applied { "Yes" } else { "No" },
-            if is_applied { "✅" } else { "❌" }
+    println!("{:<16} {:<32} {:<22} {:<18}", "Version", "Description", "Applied At", "Status");
+    println!("{:<16} {:<32} {:<22} {:<18}", "-------", "-----------", "----------", "------");
+
+    for status in &statuses {
+        let icon = match status.state {
+            MigrationState::Applied => "✅",
+            MigrationState::Pending => "⏳",
+            _ => "❌",
+        };
+        let applied_at = status
+            .installed_on
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "-".to_string());
+
+        println!(
+            "{:<16} {:<32} {:<22} {} {}",
+            status.version, status.description, applied_at, icon, status.state
         );
     }
-    
-    println!("\nTotal: {} migrations found in directory", migration_files.len());
-    
-    Ok(())
+
+    let applied = statuses.iter().filter(|s| s.state == MigrationState::Applied).count();
+    let pending = statuses.iter().filter(|s| s.state == MigrationState::Pending).count();
+    let broken = statuses.len() - applied - pending;
+
+    println!(
+        "\nTotal: {} migrations, {} applied, {} pending, {} with problems",
+        statuses.len(),
+        applied,
+        pending,
+        broken
+    );
+
+    Ok(broken == 0)
+}
+
+async fn verify_schema(database_url: &str) -> CommandResult {
+    let pool = connect(database_url).await?;
+
+    println!("🔍 Verifying database schema...");
+
+    let statuses = migrations::migration_status(&pool).await?;
+    for status in statuses.iter().filter(|s| s.state != MigrationState::Applied) {
+        println!("⚠️  Migration {} ({}) is {}", status.version, status.description, status.state);
+    }
+
+    let issues = schema::verify_schema(&pool).await?;
+    if issues.is_empty() {
+        println!(
+            "✅ Schema matches the repositories (latest migration {})",
+            migrations::latest_version().unwrap_or(0)
+        );
+        return Ok(true);
+    }
+
+    println!("\nSchema drift:");
+    for issue in &issues {
+        println!("  ❌ {}", issue);
+    }
+    println!("\nTotal: {} problems found", issues.len());
+
+    Ok(false)
 }
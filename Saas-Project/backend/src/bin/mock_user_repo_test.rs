@@ -1,216 +0,0 @@
-//! Mock test for user repository functionality
-//! This test creates a mock version of the user repository without requiring a real database
-
-use chrono::Utc;
-use async_trait::async_trait;
-use uuid::Uuid;
-
-// Import simplified versions of the necessary types
-// These are simplified for the test only
-
-#[derive(Debug, Clone, PartialEq)]
-struct UserId(Uuid);
-
-impl UserId {
-    fn new() -> Self {
-        Self(Uuid::new_v4())
-    }
-    
-    fn as_uuid(&self) -> Uuid {
-        self.0
-    }
-}
-
-#[derive(Debug, Clone, PartialEq)]
-struct Email(String);
-
-impl Email {
-    fn new(email: &str) -> Result<Self, &'static str> {
-        // Very basic validation
-        if email.contains('@') {
-            Ok(Self(email.to_string()))
-        } else {
-            Err("Invalid email format")
-        }
-    }
-    
-    fn as_str(&self) -> &str {
-        &self.0
-    }
-}
-
-#[derive(Debug, Clone, PartialEq)]
-enum UserRole {
-    UmkmOwner,
-    AdminStaff,
-    SuperAdmin,
-}
-
-#[derive(Debug, Clone, PartialEq)]
-enum UserStatus {
-    Active,
-    Inactive,
-    PendingVerification,
-    Suspended,
-}
-
-#[derive(Debug, Clone)]
-struct User {
-    id: UserId,
-    email: Email,
-    password_hash: String,
-    full_name: String,
-    phone: Option<String>,
-    role: UserRole,
-    status: UserStatus,
-    email_verified_at: Option<chrono::DateTime<Utc>>,
-    created_at: chrono::DateTime<Utc>,
-    updated_at: chrono::DateTime<Utc>,
-}
-
-// Simplified error type
-enum AppError {
-    NotFound(String),
-    Database(String),
-    Validation(String),
-}
-
-type AppResult<T> = Result<T, AppError>;
-
-// Repository trait
-#[async_trait]
-trait UserRepository {
-    async fn find_by_id(&self, id: &UserId) -> AppResult<Option<User>>;
-    async fn find_by_email(&self, email: &Email) -> AppResult<Option<User>>;
-    async fn save(&self, user: &User) -> AppResult<()>;
-    async fn delete(&self, id: &UserId) -> AppResult<()>;
-    async fn list_all(&self, limit: Option<i32>, offset: Option<i32>) -> AppResult<Vec<User>>;
-    async fn count_all(&self) -> AppResult<i64>;
-    async fn search(&self, query: &str, limit: Option<i32>, offset: Option<i32>) -> AppResult<Vec<User>>;
-}
-
-// Mock implementation
-struct MockUserRepository {
-    users: Vec<User>,
-}
-
-impl MockUserRepository {
-    fn new() -> Self {
-        Self { users: Vec::new() }
-    }
-}
-
-#[async_trait]
-impl UserRepository for MockUserRepository {
-    async fn find_by_id(&self, id: &UserId) -> AppResult<Option<User>> {
-        println!("Finding user by ID: {}", id.as_uuid());
-        let user = self.users.iter().find(|u| u.id.as_uuid() == id.as_uuid()).cloned();
-        Ok(user)
-    }
-    
-    async fn find_by_email(&self, email: &Email) -> AppResult<Option<User>> {
-        println!("Finding user by email: {}", email.as_str());
-        let user = self.users.iter().find(|u| u.email.as_str() == email.as_str()).cloned();
-        Ok(user)
-    }
-    
-    async fn save(&self, user: &User) -> AppResult<()> {
-        println!("Saving user: {}", user.id.as_uuid());
-        // In a real implementation, we would mutate self.users, but since we're mocking,
-        // we'll just pretend it worked
-        Ok(())
-    }
-    
-    async fn delete(&self, id: &UserId) -> AppResult<()> {
-        println!("Deleting user: {}", id.as_uuid());
-        // Check if user exists before deleting
-        if self.users.iter().any(|u| u.id.as_uuid() == id.as_uuid()) {
-            // In a real implementation, we would mutate self.users
-            Ok(())
-        } else {
-            Err(AppError::NotFound("User not found".to_string()))
-        }
-    }
-    
-    async fn list_all(&self, _limit: Option<i32>, _offset: Option<i32>) -> AppResult<Vec<User>> {
-        println!("Listing users with limit: {:?}, offset: {:?}", _limit, _offset);
-        // In a real implementation, we would apply limit and offset
-        Ok(self.users.clone())
-    }
-    
-    async fn count_all(&self) -> AppResult<i64> {
-        println!("Counting all users");
-        Ok(self.users.len() as i64)
-    }
-    
-    async fn search(&self, query: &str, _limit: Option<i32>, _offset: Option<i32>) -> AppResult<Vec<User>> {
-        println!("Searching users for: {}", query);
-        // Very simple search implementation
-        let results: Vec<User> = self.users.iter()
-            .filter(|u| {
-                u.email.as_str().contains(query) || 
-                u.full_name.contains(query)
-            })
-            .cloned()
-            .collect();
-        
-        Ok(results)
-    }
-}
-
-// Test function
-async fn test_user_repository() -> AppResult<()> {
-    println!("Testing user repository...");
-    
-    let repo = MockUserRepository::new();
-    
-    // Test user
-    let user_id = UserId::new();
-    let email = Email::new("test@example.com").unwrap();
-    
-    let user = User {
-        id: user_id.clone(),
-        email: email.clone(),
-        password_hash: "hashed_password".to_string(),
-        full_name: "Test User".to_string(),
-        phone: None,
-        role: UserRole::UmkmOwner,
-        status: UserStatus::Active,
-        email_verified_at: None,
-        created_at: Utc::now(),
-        updated_at: Utc::now(),
-    };
-    
-    // Test save
-    repo.save(&user).await?;
-    println!("User saved successfully");
-    
-    // Test find by ID (will always return None in our mock)
-    let found = repo.find_by_id(&user_id).await?;
-    println!("Find by ID: {:?}", found.is_some());
-    
-    // Test find by email (will always return None in our mock)
-    let found = repo.find_by_email(&email).await?;
-    println!("Find by email: {:?}", found.is_some());
-    
-    // Test count (will always return 0 in our mock)
-    let count = repo.count_all().await?;
-    println!("User count: {}", count);
-    
-    println!("All tests passed successfully!");
-    Ok(())
-}
-
-#[tokio::main]
-async fn main() {
-    println!("Starting mock user repository test...");
-    
-    match test_user_repository().await {
-        Ok(_) => println!("Test completed successfully!"),
-        Err(e) => match e {
-            AppError::NotFound(msg) => println!("Not found error: {}", msg),
-            AppError::Database(msg) => println!("Database error: {}", msg),
-            AppError::Validation(msg) => println!("Validation error: {}", msg),
-        }
-    }
-}
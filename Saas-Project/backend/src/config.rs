@@ -3,8 +3,15 @@
 
 use serde::Deserialize;
 use std::env;
+use std::net::IpAddr;
 use tracing::instrument;
 
+use crate::domain::billing::BillingPolicy;
+use crate::domain::invoicing::InvoicingPolicy;
+use crate::domain::licenses::{LicenseType, PriorityLevel};
+use crate::domain::notifications::RetryPolicy;
+use crate::domain::sla::{SlaPolicy, DEFAULT_WARNING_THRESHOLDS};
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct AppConfig {
     pub database_url: String,
@@ -14,58 +21,29 @@ pub struct AppConfig {
     pub jwt_expires_in: String,
     pub jwt_refresh_expires_in: String,
     pub redis_url: Option<String>,
+    /// Whether the instance is unusable without Redis; when false an
+    /// unreachable cache only degrades readiness
+    pub redis_required: bool,
+    pub cache: CacheConfig,
     pub upload_dir: String,
+    /// Where generated documents are kept; unlike `upload_dir` it is not
+    /// served as static files
+    pub storage_dir: String,
     pub max_file_size: u64,
     pub smtp: SmtpConfig,
     pub external_apis: ExternalApiConfig,
+    pub tracing: TracingConfig,
     pub cors_origins: Vec<String>,
-    #[serde(skip)]
-    pub rate_limiter: Option<RateLimiterWrapper>,
+    pub rate_limit: RateLimitConfig,
+    pub sla: SlaConfig,
+    pub analytics: AnalyticsConfig,
+    pub certificates: CertificateConfig,
+    pub notifications: NotificationConfig,
+    pub billing: BillingConfig,
+    pub invoicing: InvoicingConfig,
     pub enable_compression: bool,
 }
 
-// Wrapper around governor's RateLimiter to implement Clone and Deserialize
-#[derive(Debug, Clone)]
-pub struct RateLimiterWrapper {
-    inner: std::sync::Arc<
-        governor::RateLimiter<
-            governor::state::NotKeyed,
-            governor::state::InMemoryState,
-            governor::clock::DefaultClock,
-        >,
-    >,
-}
-
-impl RateLimiterWrapper {
-    pub fn check(&self) -> Result<(), governor::NotUntil<governor::clock::QuantaInstant>> {
-        self.inner.check()
-    }
-
-    pub async fn check_n_async(
-        &self,
-        n: u32,
-    ) -> Result<(), governor::NotUntil<governor::clock::QuantaInstant>> {
-        use std::num::NonZeroU32;
-
-        // Convert to NonZeroU32, which is what governor's check_n requires
-        match NonZeroU32::new(n) {
-            Some(_non_zero) => {
-                // First check with normal check
-                if let Err(e) = self.inner.check() {
-                    return Err(e);
-                }
-                // If basic check passed, treat as success
-                // This is a simplified approach just to fix the return type issue
-                Ok(())
-            }
-            None => Ok(()), // If n is 0, no need to check rate limit
-        }
-    }
-
-    pub async fn is_rate_limited(&self, _ip: std::net::IpAddr) -> bool {
-        self.check().is_err()
-    }
-}
 #[derive(Debug, Clone, Deserialize)]
 pub struct SmtpConfig {
     pub host: String,
@@ -73,6 +51,15 @@ pub struct SmtpConfig {
     pub username: String,
     pub password: String,
     pub from_email: String,
+    pub from_name: String,
+}
+
+impl SmtpConfig {
+    /// Whether email can actually be sent; without credentials it is only
+    /// recorded
+    pub fn is_configured(&self) -> bool {
+        !self.host.is_empty() && !self.username.is_empty()
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -85,42 +72,271 @@ pub struct ExternalApiConfig {
     pub midtrans_is_production: bool,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct TracingConfig {
+    pub service_name: String,
+    /// OTLP/HTTP collector base URL, e.g. `http://otel-collector:4318`; spans
+    /// are not exported when unset
+    pub otlp_endpoint: Option<String>,
+    /// Fraction of new traces to sample, 0.0 to 1.0; traces continued from a
+    /// caller follow the caller's decision
+    pub sample_ratio: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CacheConfig {
+    /// Entries kept in each instance's in-process L1; 0 disables it
+    pub l1_capacity: usize,
+    /// Upper bound on how long an L1 entry is served without asking Redis
+    pub l1_ttl_secs: u64,
+}
+
+/// Token bucket: up to `limit` requests in a burst, refilled evenly over `window_secs`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct RatePolicy {
+    pub limit: u32,
+    pub window_secs: u64,
+}
+
+impl RatePolicy {
+    pub const fn new(limit: u32, window_secs: u64) -> Self {
+        Self { limit, window_secs }
+    }
+
+    /// Parses `<requests>/<seconds>`, e.g. `5/60`
+    pub fn parse(value: &str) -> Option<Self> {
+        let (limit, window) = value.trim().split_once('/')?;
+        let policy = Self::new(limit.trim().parse().ok()?, window.trim().parse().ok()?);
+        (policy.limit > 0 && policy.window_secs > 0).then_some(policy)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// Take the client IP from `X-Forwarded-For`; only safe behind a proxy
+    /// that appends the address it saw to the header
+    pub trust_forwarded_for: bool,
+    /// Further proxies in front of that one, e.g. a CDN, whose entries in
+    /// `X-Forwarded-For` are skipped
+    pub trusted_proxies: Vec<IpAddr>,
+    /// Every route not in a stricter group, per user or per IP
+    pub default_policy: RatePolicy,
+    /// Login, registration and password reset, per IP
+    pub login_policy: RatePolicy,
+    /// Document uploads and import previews, per user or per IP
+    pub upload_policy: RatePolicy,
+    /// Public license verification, per IP
+    pub verify_policy: RatePolicy,
+    /// Quotas shared by all users of a company, by subscription plan
+    pub free_plan: RatePolicy,
+    pub basic_plan: RatePolicy,
+    pub pro_plan: RatePolicy,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SlaConfig {
+    /// Percentages of the processing target at which a warning is raised
+    pub warning_thresholds: Vec<u32>,
+    /// How often in-flight applications are checked for warnings and breaches
+    pub sweep_interval_secs: u64,
+    pub target_overrides: Vec<SlaTargetOverride>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnalyticsConfig {
+    /// How often the daily rollups behind the analytics series are brought up
+    /// to date
+    pub refresh_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CertificateConfig {
+    /// Where the API is reachable from outside; certificate QR codes link to
+    /// its verification endpoint
+    pub public_base_url: String,
+    /// Key of the HMAC in verification tokens; the JWT secret if not set
+    pub signing_key: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotificationConfig {
+    /// Where the web app is reachable; emails and WhatsApp messages link to it
+    pub app_url: String,
+    /// How often queued email and WhatsApp messages are sent
+    pub dispatch_interval_secs: u64,
+    /// Attempts per message before it is given up on, the first included
+    pub max_attempts: i32,
+    /// Wait after the first failed attempt, doubled after each further one
+    pub retry_base_secs: i64,
+    pub retry_max_secs: i64,
+    pub whatsapp: WhatsAppConfig,
+}
+
+/// WhatsApp Business Cloud API
+#[derive(Debug, Clone, Deserialize)]
+pub struct WhatsAppConfig {
+    pub api_url: String,
+    pub phone_number_id: String,
+    pub access_token: String,
+}
+
+impl WhatsAppConfig {
+    /// Whether messages can actually be sent; otherwise they are only recorded
+    pub fn is_configured(&self) -> bool {
+        !self.phone_number_id.is_empty() && !self.access_token.is_empty()
+    }
+}
+
+impl NotificationConfig {
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.max_attempts,
+            base_delay: chrono::Duration::seconds(self.retry_base_secs),
+            max_delay: chrono::Duration::seconds(self.retry_max_secs),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BillingConfig {
+    /// Trial of a company's first paid subscription; 0 for none
+    pub trial_days: i64,
+    /// PPN in basis points, 1100 for 11%
+    pub ppn_rate_bps: i64,
+    pub payment_terms_days: i64,
+    /// Days after the due date on which the owner is reminded of an unpaid
+    /// invoice
+    pub dunning_days: Vec<i64>,
+    /// Days after the due date at which an unpaid invoice is written off and
+    /// the company moved to the free plan
+    pub grace_days: i64,
+    /// How often renewals and dunning run
+    pub sweep_interval_secs: u64,
+}
+
+impl BillingConfig {
+    pub fn policy(&self) -> BillingPolicy {
+        BillingPolicy {
+            trial_days: self.trial_days,
+            ppn_rate_bps: self.ppn_rate_bps,
+            payment_terms_days: self.payment_terms_days,
+            reminder_days: self.dunning_days.clone(),
+            grace_days: self.grace_days,
+        }
+    }
+}
+
+/// Defaults for the invoices companies send their customers
+#[derive(Debug, Clone, Deserialize)]
+pub struct InvoicingConfig {
+    /// PPN in basis points for invoices that do not set their own; sellers
+    /// that are not PKP set 0 on their invoices
+    pub ppn_rate_bps: i64,
+    /// Days from issue to due date when an invoice does not set a due date
+    pub payment_terms_days: i64,
+    /// How often invoices past their due date are marked overdue
+    pub overdue_sweep_interval_secs: u64,
+}
+
+impl InvoicingConfig {
+    pub fn policy(&self) -> InvoicingPolicy {
+        InvoicingPolicy {
+            ppn_rate_bps: self.ppn_rate_bps,
+            payment_terms_days: self.payment_terms_days,
+        }
+    }
+}
+
+/// Processing target in days for a license type, optionally for one priority
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SlaTargetOverride {
+    pub license_type: LicenseType,
+    pub priority: Option<PriorityLevel>,
+    pub days: i64,
+}
+
+impl SlaTargetOverride {
+    /// Parses `<type>[:<priority>]=<days>`, e.g. `halal=21` or `siup:urgent=3`
+    pub fn parse(value: &str) -> Option<Self> {
+        let (key, days) = value.trim().split_once('=')?;
+        let (license_type, priority) = match key.split_once(':') {
+            Some((license_type, priority)) => (license_type, Some(priority)),
+            None => (key, None),
+        };
+        let license_type = [
+            LicenseType::Nib,
+            LicenseType::Siup,
+            LicenseType::Tdp,
+            LicenseType::Npwp,
+            LicenseType::Halal,
+            LicenseType::Environmental,
+            LicenseType::ExportImport,
+        ]
+        .into_iter()
+        .find(|t| t.to_string() == license_type.trim().to_lowercase())?;
+        let priority = match priority.map(|p| p.trim().to_lowercase()) {
+            None => None,
+            Some(p) => Some(match p.as_str() {
+                "low" => PriorityLevel::Low,
+                "normal" => PriorityLevel::Normal,
+                "high" => PriorityLevel::High,
+                "urgent" => PriorityLevel::Urgent,
+                _ => return None,
+            }),
+        };
+        let days: i64 = days.trim().parse().ok()?;
+        (days > 0).then_some(Self {
+            license_type,
+            priority,
+            days,
+        })
+    }
+}
+
+impl SlaConfig {
+    pub fn policy(&self) -> SlaPolicy {
+        self.target_overrides.iter().fold(
+            SlaPolicy::new(self.warning_thresholds.clone()),
+            |policy, target| {
+                policy.with_target_days(target.license_type, target.priority.clone(), target.days)
+            },
+        )
+    }
+}
+
 impl AppConfig {
     #[instrument(level = "debug", name = "config.from_env", skip_all)]
     pub fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
+        Self::load(false)
+    }
+
+    /// Configuration for `--demo`: no database or Redis is used, so neither
+    /// has to be configured, and the JWT secret falls back to a fixed one
+    #[instrument(level = "debug", name = "config.demo_from_env", skip_all)]
+    pub fn demo_from_env() -> Result<Self, Box<dyn std::error::Error>> {
+        let mut config = Self::load(true)?;
+        config.redis_url = None;
+        config.redis_required = false;
+        Ok(config)
+    }
+
+    fn load(demo: bool) -> Result<Self, Box<dyn std::error::Error>> {
         // Load environment variables from .env file
         dotenvy::dotenv().ok();
 
-        // Configure rate limiter based on environment variables
-        let rate_limiter =
-            if env::var("ENABLE_RATE_LIMITING").unwrap_or_else(|_| "true".to_string()) == "true" {
-                let max_requests = env::var("RATE_LIMIT_MAX_REQUESTS")
-                    .unwrap_or_else(|_| "100".to_string())
-                    .parse()
-                    .unwrap_or(100);
+        let required = |name: &str, demo_default: &str| -> String {
+            match env::var(name) {
+                Ok(value) => value,
+                Err(_) if demo => demo_default.to_string(),
+                Err(_) => panic!("{} must be set", name),
+            }
+        };
 
-                // Parse window seconds for rate limiter (underscore prefix to mark as intentionally unused)
-                let _window_secs = env::var("RATE_LIMIT_WINDOW_SECS")
-                    .unwrap_or_else(|_| "60".to_string())
-                    .parse()
-                    .unwrap_or(60);
-
-                use governor::{Quota, RateLimiter};
-                use std::num::NonZeroU32;
-                use std::sync::Arc;
-
-                let quota = Quota::per_second(
-                    NonZeroU32::new(max_requests as u32).unwrap_or(NonZeroU32::new(100).unwrap()),
-                );
-                Some(RateLimiterWrapper {
-                    inner: Arc::new(RateLimiter::direct(quota)),
-                })
-            } else {
-                None
-            };
+        let jwt_secret = required("JWT_SECRET", "demo-jwt-secret-not-for-production");
 
         Ok(Self {
-            database_url: env::var("DATABASE_URL").expect("DATABASE_URL must be set"),
+            database_url: required("DATABASE_URL", ""),
 
             app_host: env::var("APP_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
 
@@ -129,7 +345,16 @@ impl AppConfig {
                 .parse()
                 .expect("APP_PORT must be a valid number"),
 
-            jwt_secret: env::var("JWT_SECRET").expect("JWT_SECRET must be set"),
+            certificates: CertificateConfig {
+                public_base_url: env::var("PUBLIC_BASE_URL")
+                    .unwrap_or_else(|_| "http://localhost:8000".to_string()),
+                signing_key: env::var("CERTIFICATE_SIGNING_KEY")
+                    .ok()
+                    .filter(|key| !key.is_empty())
+                    .unwrap_or_else(|| jwt_secret.clone()),
+            },
+
+            jwt_secret,
 
             jwt_expires_in: env::var("JWT_EXPIRES_IN").unwrap_or_else(|_| "24h".to_string()),
 
@@ -138,14 +363,113 @@ impl AppConfig {
 
             redis_url: env::var("REDIS_URL").ok(),
 
+            redis_required: env::var("REDIS_REQUIRED").unwrap_or_else(|_| "false".to_string())
+                == "true",
+
+            cache: CacheConfig {
+                l1_capacity: env::var("CACHE_L1_CAPACITY")
+                    .ok()
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or(10_000),
+                l1_ttl_secs: env::var("CACHE_L1_TTL_SECS")
+                    .ok()
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or(30),
+            },
+
             upload_dir: env::var("UPLOAD_DIR").unwrap_or_else(|_| "./uploads".to_string()),
 
+            storage_dir: env::var("STORAGE_DIR").unwrap_or_else(|_| "./storage".to_string()),
+
             max_file_size: env::var("MAX_FILE_SIZE")
                 .unwrap_or_else(|_| "10485760".to_string()) // 10MB default
                 .parse()
                 .expect("MAX_FILE_SIZE must be a valid number"),
 
-            rate_limiter,
+            rate_limit: RateLimitConfig {
+                enabled: env::var("ENABLE_RATE_LIMITING").unwrap_or_else(|_| "true".to_string())
+                    == "true",
+                trust_forwarded_for: env::var("RATE_LIMIT_TRUST_FORWARDED_FOR")
+                    .unwrap_or_else(|_| "false".to_string())
+                    == "true",
+                trusted_proxies: env::var("RATE_LIMIT_TRUSTED_PROXIES")
+                    .map(|value| {
+                        value
+                            .split(',')
+                            .filter(|ip| !ip.trim().is_empty())
+                            .map(|ip| {
+                                ip.trim()
+                                    .parse()
+                                    .expect("RATE_LIMIT_TRUSTED_PROXIES must be comma-separated IP addresses")
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                default_policy: RatePolicy::new(
+                    env::var("RATE_LIMIT_MAX_REQUESTS")
+                        .ok()
+                        .and_then(|n| n.parse().ok())
+                        .filter(|n| *n > 0)
+                        .unwrap_or(100),
+                    env::var("RATE_LIMIT_WINDOW_SECS")
+                        .ok()
+                        .and_then(|n| n.parse().ok())
+                        .filter(|n| *n > 0)
+                        .unwrap_or(60),
+                ),
+                login_policy: policy_from_env("RATE_LIMIT_LOGIN", RatePolicy::new(5, 60)),
+                upload_policy: policy_from_env("RATE_LIMIT_UPLOAD", RatePolicy::new(20, 60)),
+                verify_policy: policy_from_env("RATE_LIMIT_VERIFY", RatePolicy::new(30, 60)),
+                free_plan: policy_from_env("RATE_LIMIT_PLAN_FREE", RatePolicy::new(300, 60)),
+                basic_plan: policy_from_env("RATE_LIMIT_PLAN_BASIC", RatePolicy::new(1_200, 60)),
+                pro_plan: policy_from_env("RATE_LIMIT_PLAN_PRO", RatePolicy::new(6_000, 60)),
+            },
+
+            sla: SlaConfig {
+                warning_thresholds: env::var("SLA_WARNING_THRESHOLDS")
+                    .map(|value| {
+                        value
+                            .split(',')
+                            .filter(|t| !t.trim().is_empty())
+                            .map(|t| {
+                                t.trim()
+                                    .parse()
+                                    .ok()
+                                    .filter(|t| (1..100).contains(t))
+                                    .unwrap_or_else(|| {
+                                        panic!("SLA_WARNING_THRESHOLDS must be percentages between 1 and 99, e.g. 50,75,90")
+                                    })
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_else(|_| DEFAULT_WARNING_THRESHOLDS.to_vec()),
+                sweep_interval_secs: env::var("SLA_SWEEP_INTERVAL_SECS")
+                    .ok()
+                    .and_then(|n| n.parse().ok())
+                    .filter(|n| *n > 0)
+                    .unwrap_or(300),
+                target_overrides: env::var("SLA_TARGET_DAYS")
+                    .map(|value| {
+                        value
+                            .split(',')
+                            .filter(|t| !t.trim().is_empty())
+                            .map(|t| {
+                                SlaTargetOverride::parse(t).unwrap_or_else(|| {
+                                    panic!("SLA_TARGET_DAYS must look like <type>[:<priority>]=<days>, e.g. halal=21,siup:urgent=3")
+                                })
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            },
+
+            analytics: AnalyticsConfig {
+                refresh_interval_secs: env::var("ANALYTICS_REFRESH_INTERVAL_SECS")
+                    .ok()
+                    .and_then(|n| n.parse().ok())
+                    .filter(|n| *n > 0)
+                    .unwrap_or(300),
+            },
 
             enable_compression: env::var("ENABLE_COMPRESSION")
                 .unwrap_or_else(|_| "true".to_string())
@@ -157,10 +481,111 @@ impl AppConfig {
                     .unwrap_or_else(|_| "587".to_string())
                     .parse()
                     .expect("SMTP_PORT must be a valid number"),
-                username: env::var("SMTP_USER").unwrap_or_default(),
+                username: env::var("SMTP_USERNAME")
+                    .or_else(|_| env::var("SMTP_USER"))
+                    .unwrap_or_default(),
                 password: env::var("SMTP_PASSWORD").unwrap_or_default(),
-                from_email: env::var("FROM_EMAIL")
+                from_email: env::var("SMTP_FROM_EMAIL")
+                    .or_else(|_| env::var("FROM_EMAIL"))
                     .unwrap_or_else(|_| "noreply@saas-umkm.id".to_string()),
+                from_name: env::var("SMTP_FROM_NAME").unwrap_or_else(|_| "SaaS UMKM".to_string()),
+            },
+
+            notifications: NotificationConfig {
+                app_url: env::var("APP_URL")
+                    .unwrap_or_else(|_| "http://localhost:3000".to_string())
+                    .trim_end_matches('/')
+                    .to_string(),
+                dispatch_interval_secs: env::var("NOTIFICATION_DISPATCH_INTERVAL_SECS")
+                    .ok()
+                    .and_then(|n| n.parse().ok())
+                    .filter(|n| *n > 0)
+                    .unwrap_or(15),
+                max_attempts: env::var("NOTIFICATION_MAX_ATTEMPTS")
+                    .ok()
+                    .and_then(|n| n.parse().ok())
+                    .filter(|n| *n > 0)
+                    .unwrap_or(6),
+                retry_base_secs: env::var("NOTIFICATION_RETRY_BASE_SECS")
+                    .ok()
+                    .and_then(|n| n.parse().ok())
+                    .filter(|n| *n > 0)
+                    .unwrap_or(30),
+                retry_max_secs: env::var("NOTIFICATION_RETRY_MAX_SECS")
+                    .ok()
+                    .and_then(|n| n.parse().ok())
+                    .filter(|n| *n > 0)
+                    .unwrap_or(3_600),
+                whatsapp: WhatsAppConfig {
+                    api_url: env::var("WHATSAPP_API_URL")
+                        .unwrap_or_else(|_| "https://graph.facebook.com/v19.0".to_string())
+                        .trim_end_matches('/')
+                        .to_string(),
+                    phone_number_id: env::var("WHATSAPP_PHONE_NUMBER_ID").unwrap_or_default(),
+                    access_token: env::var("WHATSAPP_ACCESS_TOKEN").unwrap_or_default(),
+                },
+            },
+
+            billing: {
+                let defaults = BillingPolicy::default();
+                BillingConfig {
+                    trial_days: env::var("BILLING_TRIAL_DAYS")
+                        .ok()
+                        .and_then(|n| n.parse().ok())
+                        .filter(|n| *n >= 0)
+                        .unwrap_or(defaults.trial_days),
+                    ppn_rate_bps: env::var("BILLING_PPN_RATE_BPS")
+                        .ok()
+                        .and_then(|n| n.parse().ok())
+                        .filter(|n| *n >= 0)
+                        .unwrap_or(defaults.ppn_rate_bps),
+                    payment_terms_days: env::var("BILLING_PAYMENT_TERMS_DAYS")
+                        .ok()
+                        .and_then(|n| n.parse().ok())
+                        .filter(|n| *n >= 0)
+                        .unwrap_or(defaults.payment_terms_days),
+                    dunning_days: env::var("BILLING_DUNNING_DAYS")
+                        .map(|value| {
+                            value
+                                .split(',')
+                                .filter(|d| !d.trim().is_empty())
+                                .map(|d| {
+                                    d.trim().parse().ok().filter(|d| *d > 0).unwrap_or_else(|| {
+                                        panic!("BILLING_DUNNING_DAYS must be days after the due date, e.g. 1,3,7")
+                                    })
+                                })
+                                .collect()
+                        })
+                        .unwrap_or(defaults.reminder_days),
+                    grace_days: env::var("BILLING_GRACE_DAYS")
+                        .ok()
+                        .and_then(|n| n.parse().ok())
+                        .filter(|n| *n > 0)
+                        .unwrap_or(defaults.grace_days),
+                    sweep_interval_secs: env::var("BILLING_SWEEP_INTERVAL_SECS")
+                        .ok()
+                        .and_then(|n| n.parse().ok())
+                        .filter(|n| *n > 0)
+                        .unwrap_or(3_600),
+                }
+            },
+
+            invoicing: InvoicingConfig {
+                ppn_rate_bps: env::var("INVOICE_PPN_RATE_BPS")
+                    .ok()
+                    .and_then(|n| n.parse().ok())
+                    .filter(|n| (0..=10_000).contains(n))
+                    .unwrap_or(1_100),
+                payment_terms_days: env::var("INVOICE_PAYMENT_TERMS_DAYS")
+                    .ok()
+                    .and_then(|n| n.parse().ok())
+                    .filter(|n| *n >= 0)
+                    .unwrap_or(30),
+                overdue_sweep_interval_secs: env::var("INVOICE_OVERDUE_SWEEP_INTERVAL_SECS")
+                    .ok()
+                    .and_then(|n| n.parse().ok())
+                    .filter(|n| *n > 0)
+                    .unwrap_or(3_600),
             },
 
             external_apis: ExternalApiConfig {
@@ -176,6 +601,19 @@ impl AppConfig {
                     .unwrap_or(false),
             },
 
+            tracing: TracingConfig {
+                service_name: env::var("OTEL_SERVICE_NAME")
+                    .unwrap_or_else(|_| "saas-umkm-backend".to_string()),
+                otlp_endpoint: env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+                    .ok()
+                    .filter(|url| !url.trim().is_empty()),
+                sample_ratio: env::var("OTEL_TRACES_SAMPLER_ARG")
+                    .ok()
+                    .and_then(|ratio| ratio.parse::<f64>().ok())
+                    .map(|ratio| ratio.clamp(0.0, 1.0))
+                    .unwrap_or(1.0),
+            },
+
             cors_origins: env::var("CORS_ORIGINS")
                 .unwrap_or_else(|_| "http://localhost:3000".to_string())
                 .split(',')
@@ -184,3 +622,11 @@ impl AppConfig {
         })
     }
 }
+
+fn policy_from_env(name: &str, default: RatePolicy) -> RatePolicy {
+    match env::var(name) {
+        Ok(value) => RatePolicy::parse(&value)
+            .unwrap_or_else(|| panic!("{} must look like <requests>/<seconds>, e.g. 5/60", name)),
+        Err(_) => default,
+    }
+}
@@ -0,0 +1,169 @@
+// Operations figures for the admin dashboard
+// Pending work is a snapshot of the queue; every other section covers a
+// `ReportRange` of submissions, decisions or registrations. Amounts are whole
+// rupiah, as stored on the license.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::licenses::LicenseType;
+use crate::shared::errors::AppResult;
+
+/// Half-open range `[from, to)` a report covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportRange {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+impl ReportRange {
+    pub fn new(from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Self, String> {
+        if from >= to {
+            return Err("`from` must be before `to`".to_string());
+        }
+        Ok(Self { from, to })
+    }
+
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        self.from <= at && at < self.to
+    }
+}
+
+/// Applications and documents waiting on an admin
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PendingWork {
+    pub submitted: i64,
+    pub processing: i64,
+    /// Waiting on the applicant rather than an admin, listed for follow-up
+    pub pending_documents: i64,
+    /// Unverified documents on submitted or processing applications
+    pub unverified_documents: i64,
+    pub oldest_submitted_at: Option<DateTime<Utc>>,
+}
+
+/// Submissions and decisions for one license type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessingPerformance {
+    pub license_type: LicenseType,
+    /// Applications submitted in the range
+    pub submitted_count: i64,
+    /// Applications approved or rejected in the range
+    pub total_processed: i64,
+    pub approved_count: i64,
+    pub rejected_count: i64,
+    /// Share of decisions that were approvals, absent without decisions
+    pub approval_rate: Option<f64>,
+    /// Days from submission to decision
+    pub avg_processing_days: Option<f64>,
+    pub min_processing_days: Option<i32>,
+    pub max_processing_days: Option<i32>,
+}
+
+impl ProcessingPerformance {
+    pub fn approval_rate(approved: i64, rejected: i64) -> Option<f64> {
+        let decided = approved + rejected;
+        (decided > 0).then(|| approved as f64 / decided as f64)
+    }
+}
+
+/// What one admin has on their desk and what they did in the range
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewerWorkload {
+    pub reviewer_id: Uuid,
+    pub full_name: String,
+    /// Applications in processing or pending documents that this admin moved
+    /// there last
+    pub open_applications: i64,
+    /// Status changes recorded in the range
+    pub status_changes: i64,
+    pub approved: i64,
+    pub rejected: i64,
+}
+
+/// Companies registered in a province
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegionRegistrations {
+    pub province: String,
+    pub companies: i64,
+}
+
+/// Service fees on the licenses of one type approved in the range
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceFeeRevenue {
+    pub license_type: LicenseType,
+    pub approved_licenses: i64,
+    pub service_fee_total: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OperationsReport {
+    pub generated_at: DateTime<Utc>,
+    pub range: ReportRange,
+    pub pending: PendingWork,
+    pub throughput: Vec<ProcessingPerformance>,
+    pub reviewers: Vec<ReviewerWorkload>,
+    pub registrations: Vec<RegionRegistrations>,
+    pub revenue: Vec<ServiceFeeRevenue>,
+    pub revenue_total: i64,
+}
+
+/// Aggregates over users, companies and licenses for the admin dashboard.
+/// Rows are ordered by license type name, by workload and by registrations.
+#[async_trait]
+pub trait AdminStatsRepository: Send + Sync {
+    async fn pending_work(&self) -> AppResult<PendingWork>;
+    async fn throughput(&self, range: &ReportRange) -> AppResult<Vec<ProcessingPerformance>>;
+    /// Admins with open applications or status changes in the range
+    async fn reviewer_workload(&self, range: &ReportRange) -> AppResult<Vec<ReviewerWorkload>>;
+    async fn registrations_by_province(&self, range: &ReportRange) -> AppResult<Vec<RegionRegistrations>>;
+    async fn service_fee_revenue(&self, range: &ReportRange) -> AppResult<Vec<ServiceFeeRevenue>>;
+}
+
+/// Every section of the dashboard for `range`
+pub async fn operations_report(
+    stats: &dyn AdminStatsRepository,
+    range: ReportRange,
+    now: DateTime<Utc>,
+) -> AppResult<OperationsReport> {
+    let (pending, throughput, reviewers, registrations, revenue) = tokio::try_join!(
+        stats.pending_work(),
+        stats.throughput(&range),
+        stats.reviewer_workload(&range),
+        stats.registrations_by_province(&range),
+        stats.service_fee_revenue(&range),
+    )?;
+
+    Ok(OperationsReport {
+        generated_at: now,
+        range,
+        pending,
+        throughput,
+        reviewers,
+        registrations,
+        revenue_total: revenue.iter().map(|r| r.service_fee_total).sum(),
+        revenue,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_report_range_is_half_open() {
+        let from = Utc::now();
+        let range = ReportRange::new(from, from + Duration::days(1)).unwrap();
+        assert!(range.contains(from));
+        assert!(!range.contains(from + Duration::days(1)));
+        assert!(ReportRange::new(from, from).is_err());
+    }
+
+    #[test]
+    fn test_approval_rate() {
+        assert_eq!(ProcessingPerformance::approval_rate(3, 1), Some(0.75));
+        assert_eq!(ProcessingPerformance::approval_rate(0, 0), None);
+    }
+}
@@ -0,0 +1,359 @@
+// Time series for the analytics dashboards
+// License volumes and processing times, company registrations and finance
+// totals, bucketed by day, ISO week or month and optionally grouped by type,
+// status, province and business scale. Days are calendar days in Jakarta time
+// (WIB), the way the daily rollups store them. Groups use the current type,
+// status, province and scale of each license, transaction or company.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::shared::errors::AppResult;
+
+/// Offset of Western Indonesia Time, which has no daylight saving
+pub const REPORTING_UTC_OFFSET_SECS: i32 = 7 * 3600;
+
+/// Longest range one query may cover, about ten years
+pub const MAX_SERIES_DAYS: i64 = 3660;
+
+/// Calendar day in Jakarta time that `at` falls on
+pub fn reporting_day(at: DateTime<Utc>) -> NaiveDate {
+    let wib = FixedOffset::east_opt(REPORTING_UTC_OFFSET_SECS).expect("valid offset");
+    at.with_timezone(&wib).date_naive()
+}
+
+/// Instant the Jakarta calendar day `day` starts
+pub fn reporting_day_start(day: NaiveDate) -> DateTime<Utc> {
+    let midnight = day.and_hms_opt(0, 0, 0).expect("valid time").and_utc();
+    midnight - Duration::seconds(REPORTING_UTC_OFFSET_SECS as i64)
+}
+
+/// What a series counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    /// Licenses created, submitted, approved and rejected, and days from
+    /// submission to decision
+    Licenses,
+    /// Companies registered
+    Registrations,
+    /// Number and total amount of financial transactions
+    Finance,
+}
+
+impl Metric {
+    /// Dimensions a series of this metric can be grouped by
+    pub fn dimensions(&self) -> &'static [Dimension] {
+        match self {
+            Metric::Licenses | Metric::Finance => &[
+                Dimension::Type,
+                Dimension::Status,
+                Dimension::Province,
+                Dimension::BusinessScale,
+            ],
+            Metric::Registrations => &[Dimension::Province, Dimension::BusinessScale],
+        }
+    }
+}
+
+impl std::fmt::Display for Metric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Metric::Licenses => write!(f, "licenses"),
+            Metric::Registrations => write!(f, "registrations"),
+            Metric::Finance => write!(f, "finance"),
+        }
+    }
+}
+
+impl std::str::FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "licenses" => Ok(Metric::Licenses),
+            "registrations" => Ok(Metric::Registrations),
+            "finance" => Ok(Metric::Finance),
+            _ => Err(format!("Unknown metric: {}", s)),
+        }
+    }
+}
+
+/// What a series can be grouped by. `type` and `status` are the license type
+/// and application status for licenses, and the transaction type and status
+/// for finance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dimension {
+    Type,
+    Status,
+    Province,
+    BusinessScale,
+}
+
+impl std::fmt::Display for Dimension {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Dimension::Type => write!(f, "type"),
+            Dimension::Status => write!(f, "status"),
+            Dimension::Province => write!(f, "province"),
+            Dimension::BusinessScale => write!(f, "business_scale"),
+        }
+    }
+}
+
+impl std::str::FromStr for Dimension {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "type" => Ok(Dimension::Type),
+            "status" => Ok(Dimension::Status),
+            "province" => Ok(Dimension::Province),
+            "business_scale" => Ok(Dimension::BusinessScale),
+            _ => Err(format!("Unknown dimension: {}", s)),
+        }
+    }
+}
+
+/// Width of one point of a series
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bucket {
+    #[default]
+    Day,
+    /// ISO week, starting on Monday
+    Week,
+    Month,
+}
+
+impl Bucket {
+    /// First day of the bucket `day` falls in
+    pub fn start(&self, day: NaiveDate) -> NaiveDate {
+        match self {
+            Bucket::Day => day,
+            Bucket::Week => day - Duration::days(day.weekday().num_days_from_monday() as i64),
+            Bucket::Month => day.with_day(1).expect("every month has a first day"),
+        }
+    }
+}
+
+impl std::fmt::Display for Bucket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Bucket::Day => write!(f, "day"),
+            Bucket::Week => write!(f, "week"),
+            Bucket::Month => write!(f, "month"),
+        }
+    }
+}
+
+impl std::str::FromStr for Bucket {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "day" => Ok(Bucket::Day),
+            "week" => Ok(Bucket::Week),
+            "month" => Ok(Bucket::Month),
+            _ => Err(format!("Unknown bucket: {}", s)),
+        }
+    }
+}
+
+/// A validated series request over the days `[from, to)`. Buckets at the edges
+/// keep their usual start date but only count the days inside the range.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SeriesQuery {
+    pub metric: Metric,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub bucket: Bucket,
+    pub group_by: Vec<Dimension>,
+}
+
+impl SeriesQuery {
+    pub fn new(
+        metric: Metric,
+        from: NaiveDate,
+        to: NaiveDate,
+        bucket: Bucket,
+        group_by: Vec<Dimension>,
+    ) -> Result<Self, String> {
+        if from >= to {
+            return Err("`from` must be before `to`".to_string());
+        }
+        if (to - from).num_days() > MAX_SERIES_DAYS {
+            return Err(format!("A series covers at most {} days", MAX_SERIES_DAYS));
+        }
+        for (i, dimension) in group_by.iter().enumerate() {
+            if !metric.dimensions().contains(dimension) {
+                return Err(format!("{} cannot be grouped by {}", metric, dimension));
+            }
+            if group_by[..i].contains(dimension) {
+                return Err(format!("{} is grouped by more than once", dimension));
+            }
+        }
+        Ok(Self {
+            metric,
+            from,
+            to,
+            bucket,
+            group_by,
+        })
+    }
+
+    pub fn contains(&self, day: NaiveDate) -> bool {
+        self.from <= day && day < self.to
+    }
+
+    /// Group of a row, keeping only the dimensions the query groups by
+    pub fn group(&self, values: &[(Dimension, &str)]) -> BTreeMap<Dimension, String> {
+        values
+            .iter()
+            .filter(|(dimension, _)| self.group_by.contains(dimension))
+            .map(|(dimension, value)| (*dimension, value.to_string()))
+            .collect()
+    }
+}
+
+/// One bucket of one group
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SeriesPoint<V> {
+    /// First day of the bucket
+    pub bucket: NaiveDate,
+    /// Value of every dimension the query groups by
+    pub group: BTreeMap<Dimension, String>,
+    #[serde(flatten)]
+    pub values: V,
+}
+
+/// Orders points by bucket, then by group values
+pub fn sort_points<V>(points: &mut [SeriesPoint<V>]) {
+    points.sort_by(|a, b| {
+        a.bucket
+            .cmp(&b.bucket)
+            .then_with(|| a.group.values().cmp(b.group.values()))
+    });
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LicenseVolume {
+    pub created: i64,
+    pub submitted: i64,
+    pub approved: i64,
+    pub rejected: i64,
+    /// Days from submission to decision over the bucket's decisions, absent
+    /// without decisions
+    pub avg_processing_days: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RegistrationVolume {
+    pub companies: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FinanceTotals {
+    pub transactions: i64,
+    /// Hundredths of a rupiah
+    pub amount: i64,
+}
+
+/// A series with the query it answers
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Series<V> {
+    #[serde(flatten)]
+    pub query: SeriesQuery,
+    pub points: Vec<SeriesPoint<V>>,
+}
+
+/// Days recomputed by one rollup refresh
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RollupRefresh {
+    pub license_days: usize,
+    pub registration_days: usize,
+    pub finance_days: usize,
+}
+
+/// Time series over daily rollups. Series reflect the data as of the last
+/// `refresh`, which recomputes the days changed since the one before.
+#[async_trait]
+pub trait AnalyticsRepository: Send + Sync {
+    async fn refresh(&self) -> AppResult<RollupRefresh>;
+    async fn license_series(&self, query: &SeriesQuery) -> AppResult<Vec<SeriesPoint<LicenseVolume>>>;
+    async fn registration_series(
+        &self,
+        query: &SeriesQuery,
+    ) -> AppResult<Vec<SeriesPoint<RegistrationVolume>>>;
+    async fn finance_series(&self, query: &SeriesQuery) -> AppResult<Vec<SeriesPoint<FinanceTotals>>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_reporting_day_is_jakarta_calendar_day() {
+        let evening_utc = Utc.with_ymd_and_hms(2024, 3, 10, 17, 30, 0).unwrap();
+        assert_eq!(reporting_day(evening_utc), day(2024, 3, 11));
+        let morning_utc = Utc.with_ymd_and_hms(2024, 3, 10, 16, 59, 59).unwrap();
+        assert_eq!(reporting_day(morning_utc), day(2024, 3, 10));
+        assert_eq!(
+            reporting_day_start(day(2024, 3, 11)),
+            Utc.with_ymd_and_hms(2024, 3, 10, 17, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_bucket_start() {
+        // 2024-03-14 is a Thursday
+        assert_eq!(Bucket::Day.start(day(2024, 3, 14)), day(2024, 3, 14));
+        assert_eq!(Bucket::Week.start(day(2024, 3, 14)), day(2024, 3, 11));
+        assert_eq!(Bucket::Week.start(day(2024, 3, 11)), day(2024, 3, 11));
+        assert_eq!(Bucket::Month.start(day(2024, 3, 14)), day(2024, 3, 1));
+    }
+
+    #[test]
+    fn test_series_query_validation() {
+        let (from, to) = (day(2024, 1, 1), day(2024, 2, 1));
+        assert!(SeriesQuery::new(Metric::Licenses, to, from, Bucket::Day, vec![]).is_err());
+        assert!(SeriesQuery::new(Metric::Licenses, from, day(2040, 1, 1), Bucket::Month, vec![]).is_err());
+        assert!(SeriesQuery::new(Metric::Registrations, from, to, Bucket::Day, vec![Dimension::Type]).is_err());
+        assert!(SeriesQuery::new(
+            Metric::Finance,
+            from,
+            to,
+            Bucket::Week,
+            vec![Dimension::Province, Dimension::Province]
+        )
+        .is_err());
+
+        let query = SeriesQuery::new(
+            Metric::Licenses,
+            from,
+            to,
+            Bucket::Week,
+            vec![Dimension::Province, Dimension::Type],
+        )
+        .unwrap();
+        assert!(query.contains(from) && !query.contains(to));
+        let group = query.group(&[
+            (Dimension::Type, "nib"),
+            (Dimension::Status, "approved"),
+            (Dimension::Province, "Bali"),
+            (Dimension::BusinessScale, "mikro"),
+        ]);
+        assert_eq!(group.into_iter().collect::<Vec<_>>(), vec![
+            (Dimension::Type, "nib".to_string()),
+            (Dimension::Province, "Bali".to_string()),
+        ]);
+    }
+}
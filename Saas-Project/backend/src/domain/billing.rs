@@ -0,0 +1,761 @@
+// SaaS billing - what our customers pay us
+// Every company is on a plan: free unless it subscribes to basic or pro. A
+// first paid subscription starts with a trial; after that each monthly period
+// is invoiced in advance, in rupiah with PPN on top. Upgrades take effect at
+// once and credit the unused part of a paid period; downgrades and
+// cancellations wait for the period to end. An invoice left unpaid after its
+// due date is dunned: the owner is reminded on a schedule, and once the grace
+// period is over the invoice is written off and the company falls back to
+// the free plan. The plan decides the entitlements checked across the app.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Months, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::domain::companies::SubscriptionPlan;
+use crate::domain::value_objects::Money;
+use crate::shared::errors::AppResult;
+
+/// Every plan, cheapest first
+pub const PLANS: [SubscriptionPlan; 3] = [
+    SubscriptionPlan::Free,
+    SubscriptionPlan::Basic,
+    SubscriptionPlan::Pro,
+];
+
+const MEGABYTE: i64 = 1024 * 1024;
+const GIGABYTE: i64 = 1024 * MEGABYTE;
+
+/// Monthly price before PPN
+pub fn monthly_price(plan: SubscriptionPlan) -> Money {
+    let rupiah = match plan {
+        SubscriptionPlan::Free => 0,
+        SubscriptionPlan::Basic => 149_000,
+        SubscriptionPlan::Pro => 399_000,
+    };
+    Money::idr(rupiah * Money::RUPIAH)
+}
+
+/// Position among `PLANS`; a higher rank is an upgrade
+pub fn rank(plan: SubscriptionPlan) -> u8 {
+    match plan {
+        SubscriptionPlan::Free => 0,
+        SubscriptionPlan::Basic => 1,
+        SubscriptionPlan::Pro => 2,
+    }
+}
+
+/// What a plan allows; `None` is unlimited
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PlanLimits {
+    /// Companies the owner may register, counted across all their companies
+    pub max_companies: Option<i64>,
+    /// Licenses the company may create per calendar month, Jakarta time
+    pub licenses_per_month: Option<i64>,
+    /// Bytes of license documents the company may keep
+    pub storage_bytes: i64,
+    /// Bookkeeping, imports, reconciliation and invoicing
+    pub finance_module: bool,
+}
+
+impl PlanLimits {
+    pub fn of(plan: SubscriptionPlan) -> Self {
+        match plan {
+            SubscriptionPlan::Free => Self {
+                max_companies: Some(1),
+                licenses_per_month: Some(2),
+                storage_bytes: 100 * MEGABYTE,
+                finance_module: false,
+            },
+            SubscriptionPlan::Basic => Self {
+                max_companies: Some(3),
+                licenses_per_month: Some(10),
+                storage_bytes: 2 * GIGABYTE,
+                finance_module: true,
+            },
+            SubscriptionPlan::Pro => Self {
+                max_companies: None,
+                licenses_per_month: None,
+                storage_bytes: 20 * GIGABYTE,
+                finance_module: true,
+            },
+        }
+    }
+
+    /// Whether one more of `requirement` fits next to `usage`, and why not
+    pub fn allows(&self, plan: SubscriptionPlan, usage: &Usage, requirement: Requirement) -> Result<(), String> {
+        let within = |limit: Option<i64>, used: i64, adding: i64| limit.is_none_or(|limit| used + adding <= limit);
+        let allowed = match requirement {
+            Requirement::Company => within(self.max_companies, usage.companies, 1),
+            Requirement::License => within(self.licenses_per_month, usage.licenses_this_month, 1),
+            Requirement::Storage { bytes } => within(Some(self.storage_bytes), usage.storage_bytes, bytes),
+            Requirement::FinanceModule => self.finance_module,
+        };
+        if allowed {
+            return Ok(());
+        }
+        Err(match requirement {
+            Requirement::Company => format!(
+                "The {} plan allows {} compan{}; upgrade to register more",
+                plan,
+                self.max_companies.unwrap_or_default(),
+                if self.max_companies == Some(1) { "y" } else { "ies" }
+            ),
+            Requirement::License => format!(
+                "The {} plan allows {} new licenses a month; upgrade to apply for more",
+                plan,
+                self.licenses_per_month.unwrap_or_default()
+            ),
+            Requirement::Storage { .. } => format!(
+                "The {} plan stores up to {} MB of documents; upgrade or remove documents to upload more",
+                plan,
+                self.storage_bytes / MEGABYTE
+            ),
+            Requirement::FinanceModule => format!(
+                "The finance module is not part of the {} plan; upgrade to use it",
+                plan
+            ),
+        })
+    }
+}
+
+/// Something a request is about to use up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Requirement {
+    /// Registering another company
+    Company,
+    /// Creating another license this month
+    License,
+    /// Storing a document of this size
+    Storage { bytes: i64 },
+    FinanceModule,
+}
+
+impl Requirement {
+    /// The limit it counts against, for metrics
+    pub fn limit_name(&self) -> &'static str {
+        match self {
+            Requirement::Company => "companies",
+            Requirement::License => "licenses_per_month",
+            Requirement::Storage { .. } => "storage",
+            Requirement::FinanceModule => "finance_module",
+        }
+    }
+}
+
+/// What a company has used of its limits
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    /// Companies of the company's owner, this one included
+    pub companies: i64,
+    pub licenses_this_month: i64,
+    pub storage_bytes: i64,
+}
+
+/// A company's plan, its limits and how much of them is used
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Entitlements {
+    pub company_id: Uuid,
+    pub plan: SubscriptionPlan,
+    /// `None` for companies that never subscribed
+    pub status: Option<SubscriptionStatus>,
+    pub limits: PlanLimits,
+    pub usage: Usage,
+}
+
+impl Entitlements {
+    pub fn new(company_id: Uuid, subscription: Option<&Subscription>, usage: Usage) -> Self {
+        let plan = subscription.map_or(SubscriptionPlan::Free, Subscription::effective_plan);
+        Self {
+            company_id,
+            plan,
+            status: subscription.map(|s| s.status),
+            limits: PlanLimits::of(plan),
+            usage,
+        }
+    }
+
+    pub fn check(&self, requirement: Requirement) -> Result<(), String> {
+        self.limits.allows(self.plan, &self.usage, requirement)
+    }
+}
+
+/// Trial length, tax and payment terms
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BillingPolicy {
+    /// Trial of a company's first paid subscription; zero for none
+    pub trial_days: i64,
+    /// PPN in basis points of the taxable amount, 1100 for 11%
+    pub ppn_rate_bps: i64,
+    /// Days from issuing an invoice to its due date
+    pub payment_terms_days: i64,
+    /// Days after the due date on which the owner is reminded
+    pub reminder_days: Vec<i64>,
+    /// Days after the due date at which an unpaid invoice is written off
+    pub grace_days: i64,
+}
+
+impl Default for BillingPolicy {
+    fn default() -> Self {
+        Self {
+            trial_days: 14,
+            ppn_rate_bps: 1100,
+            payment_terms_days: 7,
+            reminder_days: vec![1, 3, 7],
+            grace_days: 14,
+        }
+    }
+}
+
+impl BillingPolicy {
+    /// When the reminder after `sent` earlier ones is due, or the write-off
+    /// once they are all sent
+    fn next_reminder_at(&self, due_at: DateTime<Utc>, sent: i32) -> DateTime<Utc> {
+        let days = self
+            .reminder_days
+            .get(sent as usize)
+            .copied()
+            .unwrap_or(self.grace_days)
+            .min(self.grace_days);
+        due_at + Duration::days(days)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionStatus {
+    /// Paid plan without an invoice yet
+    Trialing,
+    Active,
+    /// An invoice is overdue; the plan stays until it is written off
+    PastDue,
+    /// Back on the free plan
+    Canceled,
+}
+
+impl fmt::Display for SubscriptionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubscriptionStatus::Trialing => write!(f, "trialing"),
+            SubscriptionStatus::Active => write!(f, "active"),
+            SubscriptionStatus::PastDue => write!(f, "past_due"),
+            SubscriptionStatus::Canceled => write!(f, "canceled"),
+        }
+    }
+}
+
+impl FromStr for SubscriptionStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "trialing" => Ok(SubscriptionStatus::Trialing),
+            "active" => Ok(SubscriptionStatus::Active),
+            "past_due" => Ok(SubscriptionStatus::PastDue),
+            "canceled" => Ok(SubscriptionStatus::Canceled),
+            _ => Err(format!("Invalid subscription status: {}", s)),
+        }
+    }
+}
+
+/// A company's subscription; there is at most one per company
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Subscription {
+    pub id: Uuid,
+    pub company_id: Uuid,
+    pub plan: SubscriptionPlan,
+    pub status: SubscriptionStatus,
+    /// Set once the company has had its trial
+    pub trial_ends_at: Option<DateTime<Utc>>,
+    /// The trial while trialing, otherwise the invoiced month
+    pub current_period_start: DateTime<Utc>,
+    pub current_period_end: DateTime<Utc>,
+    /// Plan taking over at the end of the period; free for a cancellation
+    pub scheduled_plan: Option<SubscriptionPlan>,
+    pub version: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What a plan change asks to be invoiced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanChange {
+    /// Nothing to invoice now: a trial, a scheduled downgrade or a resumed plan
+    Deferred,
+    /// A new period starts now and is invoiced
+    Invoiced,
+}
+
+fn one_month_after(at: DateTime<Utc>) -> DateTime<Utc> {
+    at.checked_add_months(Months::new(1)).expect("date in range")
+}
+
+impl Subscription {
+    /// A company's first paid subscription, starting with a trial if the
+    /// policy gives one
+    pub fn start(company_id: Uuid, plan: SubscriptionPlan, policy: &BillingPolicy, now: DateTime<Utc>) -> Result<(Self, PlanChange), String> {
+        if plan == SubscriptionPlan::Free {
+            return Err("The free plan needs no subscription".to_string());
+        }
+        let trial_ends_at = (policy.trial_days > 0).then(|| now + Duration::days(policy.trial_days));
+        let subscription = Self {
+            id: Uuid::new_v4(),
+            company_id,
+            plan,
+            status: if trial_ends_at.is_some() {
+                SubscriptionStatus::Trialing
+            } else {
+                SubscriptionStatus::Active
+            },
+            trial_ends_at,
+            current_period_start: now,
+            current_period_end: trial_ends_at.unwrap_or_else(|| one_month_after(now)),
+            scheduled_plan: None,
+            version: 1,
+            created_at: now,
+            updated_at: now,
+        };
+        let change = if trial_ends_at.is_some() {
+            PlanChange::Deferred
+        } else {
+            PlanChange::Invoiced
+        };
+        Ok((subscription, change))
+    }
+
+    /// The plan the company gets now
+    pub fn effective_plan(&self) -> SubscriptionPlan {
+        match self.status {
+            SubscriptionStatus::Canceled => SubscriptionPlan::Free,
+            _ => self.plan,
+        }
+    }
+
+    fn start_period(&mut self, plan: SubscriptionPlan, now: DateTime<Utc>) {
+        self.plan = plan;
+        self.status = SubscriptionStatus::Active;
+        self.current_period_start = now;
+        self.current_period_end = one_month_after(now);
+        self.scheduled_plan = None;
+    }
+
+    /// Moves to `plan`: at once when it is an upgrade, a trial or a return
+    /// after cancellation, at the end of the period otherwise
+    pub fn change_plan(&mut self, plan: SubscriptionPlan, now: DateTime<Utc>) -> Result<PlanChange, String> {
+        if self.status == SubscriptionStatus::Canceled {
+            if plan == SubscriptionPlan::Free {
+                return Err("The company is already on the free plan".to_string());
+            }
+            self.start_period(plan, now);
+            return Ok(PlanChange::Invoiced);
+        }
+        if plan == self.plan {
+            // Takes back a scheduled downgrade or cancellation
+            self.scheduled_plan = None;
+            return Ok(PlanChange::Deferred);
+        }
+        if self.status == SubscriptionStatus::Trialing && plan != SubscriptionPlan::Free {
+            self.plan = plan;
+            self.scheduled_plan = None;
+            return Ok(PlanChange::Deferred);
+        }
+        if rank(plan) < rank(self.plan) {
+            self.scheduled_plan = Some(plan);
+            return Ok(PlanChange::Deferred);
+        }
+        self.start_period(plan, now);
+        Ok(PlanChange::Invoiced)
+    }
+
+    /// Cancels at the end of the period
+    pub fn cancel(&mut self) -> Result<(), String> {
+        if self.status == SubscriptionStatus::Canceled {
+            return Err("The subscription is already canceled".to_string());
+        }
+        self.scheduled_plan = Some(SubscriptionPlan::Free);
+        Ok(())
+    }
+
+    /// Whether the period is over and `renew` is due
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.status != SubscriptionStatus::Canceled && self.current_period_end <= now
+    }
+
+    /// Starts the period following the one that ended, on the scheduled plan
+    /// if there is one; `Deferred` when that plan is free and the
+    /// subscription ends instead
+    pub fn renew(&mut self) -> PlanChange {
+        let plan = self.scheduled_plan.take().unwrap_or(self.plan);
+        if plan == SubscriptionPlan::Free {
+            self.plan = SubscriptionPlan::Free;
+            self.status = SubscriptionStatus::Canceled;
+            return PlanChange::Deferred;
+        }
+        self.plan = plan;
+        if self.status == SubscriptionStatus::Trialing {
+            self.status = SubscriptionStatus::Active;
+        }
+        self.current_period_start = self.current_period_end;
+        self.current_period_end = one_month_after(self.current_period_start);
+        PlanChange::Invoiced
+    }
+
+    pub fn mark_past_due(&mut self) {
+        if self.status == SubscriptionStatus::Active {
+            self.status = SubscriptionStatus::PastDue;
+        }
+    }
+
+    /// Back in good standing once nothing is overdue
+    pub fn settle(&mut self) {
+        if self.status == SubscriptionStatus::PastDue {
+            self.status = SubscriptionStatus::Active;
+        }
+    }
+
+    /// Ends the subscription over an invoice written off
+    pub fn cancel_for_nonpayment(&mut self) {
+        self.plan = SubscriptionPlan::Free;
+        self.status = SubscriptionStatus::Canceled;
+        self.scheduled_plan = None;
+    }
+
+    /// Part of the period not used yet, in millionths
+    fn unused_share(&self, now: DateTime<Utc>) -> i128 {
+        let length = (self.current_period_end - self.current_period_start).num_seconds().max(1) as i128;
+        let unused = (self.current_period_end - now).num_seconds().clamp(0, length as i64) as i128;
+        unused * 1_000_000 / length
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvoiceStatus {
+    Open,
+    Paid,
+    /// Replaced before it was paid, e.g. by an upgrade
+    Void,
+    /// Written off after dunning
+    Uncollectible,
+}
+
+impl fmt::Display for InvoiceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvoiceStatus::Open => write!(f, "open"),
+            InvoiceStatus::Paid => write!(f, "paid"),
+            InvoiceStatus::Void => write!(f, "void"),
+            InvoiceStatus::Uncollectible => write!(f, "uncollectible"),
+        }
+    }
+}
+
+impl FromStr for InvoiceStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "open" => Ok(InvoiceStatus::Open),
+            "paid" => Ok(InvoiceStatus::Paid),
+            "void" => Ok(InvoiceStatus::Void),
+            "uncollectible" => Ok(InvoiceStatus::Uncollectible),
+            _ => Err(format!("Invalid invoice status: {}", s)),
+        }
+    }
+}
+
+/// An invoice for one period of a subscription. Amounts are in hundredths of a
+/// rupiah; PPN is charged on the subtotal less any credit, rounded down to
+/// whole rupiah.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubscriptionInvoice {
+    pub id: Uuid,
+    /// `SUB/<year>/<sequence>`, assigned when the invoice is first stored
+    pub number: String,
+    pub company_id: Uuid,
+    pub subscription_id: Uuid,
+    pub plan: SubscriptionPlan,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub subtotal: Money,
+    /// Unused part of the previous period, when upgrading from it
+    pub credit: Money,
+    pub ppn_rate_bps: i64,
+    pub ppn: Money,
+    pub total: Money,
+    pub status: InvoiceStatus,
+    pub issued_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+    pub paid_at: Option<DateTime<Utc>>,
+    /// Payment gateway or bank transfer reference
+    pub payment_reference: Option<String>,
+    pub last_payment_error: Option<String>,
+    pub reminders_sent: i32,
+    /// Next dunning step while the invoice is open
+    pub next_reminder_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What a dunning step did to an invoice
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DunningStep {
+    /// The owner is reminded for the given time
+    Reminded(i32),
+    /// The grace period is over and the invoice written off
+    WrittenOff,
+}
+
+impl SubscriptionInvoice {
+    /// Invoices the current period of `subscription`
+    pub fn issue(subscription: &Subscription, credit: Money, policy: &BillingPolicy, now: DateTime<Utc>) -> Self {
+        let subtotal = monthly_price(subscription.plan);
+        let credit = Money::idr(credit.amount.clamp(0, subtotal.amount));
+        let taxable = subtotal.amount - credit.amount;
+        let ppn = taxable * policy.ppn_rate_bps / 10_000 / Money::RUPIAH * Money::RUPIAH;
+        let total = taxable + ppn;
+        let due_at = now + Duration::days(policy.payment_terms_days);
+        let settled = total == 0;
+        Self {
+            id: Uuid::new_v4(),
+            number: String::new(),
+            company_id: subscription.company_id,
+            subscription_id: subscription.id,
+            plan: subscription.plan,
+            period_start: subscription.current_period_start,
+            period_end: subscription.current_period_end,
+            subtotal,
+            credit,
+            ppn_rate_bps: policy.ppn_rate_bps,
+            ppn: Money::idr(ppn),
+            total: Money::idr(total),
+            status: if settled { InvoiceStatus::Paid } else { InvoiceStatus::Open },
+            issued_at: now,
+            due_at,
+            paid_at: settled.then_some(now),
+            payment_reference: None,
+            last_payment_error: None,
+            reminders_sent: 0,
+            next_reminder_at: (!settled).then(|| policy.next_reminder_at(due_at, 0)),
+            updated_at: now,
+        }
+    }
+
+    /// Credit for the unused part of this invoice's period when `subscription`
+    /// moves off it at `now`; only a paid invoice earns one
+    pub fn unused_credit(&self, subscription: &Subscription, now: DateTime<Utc>) -> Money {
+        if self.status != InvoiceStatus::Paid {
+            return Money::idr(0);
+        }
+        let paid = (self.subtotal.amount - self.credit.amount) as i128;
+        let credit = paid * subscription.unused_share(now) / 1_000_000;
+        Money::idr(credit as i64 / Money::RUPIAH * Money::RUPIAH)
+    }
+
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        self.status == InvoiceStatus::Open && self.due_at <= now
+    }
+
+    pub fn mark_paid(&mut self, reference: Option<String>, at: DateTime<Utc>) -> Result<(), String> {
+        if self.status != InvoiceStatus::Open {
+            return Err(format!("Invoice {} is {} and cannot be paid", self.number, self.status));
+        }
+        self.status = InvoiceStatus::Paid;
+        self.paid_at = Some(at);
+        self.payment_reference = reference;
+        self.next_reminder_at = None;
+        self.updated_at = at;
+        Ok(())
+    }
+
+    /// Records a payment attempt that failed; dunning carries on as planned
+    pub fn record_failed_payment(&mut self, reason: String, at: DateTime<Utc>) -> Result<(), String> {
+        if self.status != InvoiceStatus::Open {
+            return Err(format!("Invoice {} is {}", self.number, self.status));
+        }
+        self.last_payment_error = Some(reason);
+        self.updated_at = at;
+        Ok(())
+    }
+
+    pub fn void(&mut self, at: DateTime<Utc>) {
+        if self.status == InvoiceStatus::Open {
+            self.status = InvoiceStatus::Void;
+            self.next_reminder_at = None;
+            self.updated_at = at;
+        }
+    }
+
+    /// The dunning step due at `now`, if any
+    pub fn dun(&mut self, policy: &BillingPolicy, now: DateTime<Utc>) -> Option<DunningStep> {
+        if self.status != InvoiceStatus::Open || self.next_reminder_at.is_none_or(|at| at > now) {
+            return None;
+        }
+        self.updated_at = now;
+        if now >= self.due_at + Duration::days(policy.grace_days) {
+            self.status = InvoiceStatus::Uncollectible;
+            self.next_reminder_at = None;
+            return Some(DunningStep::WrittenOff);
+        }
+        self.reminders_sent += 1;
+        self.next_reminder_at = Some(policy.next_reminder_at(self.due_at, self.reminders_sent));
+        Some(DunningStep::Reminded(self.reminders_sent))
+    }
+}
+
+/// Writes that belong together, applied in one transaction
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BillingChange {
+    /// Inserted, or updated at its version
+    pub subscription: Option<Subscription>,
+    /// Inserted when they have no number yet, updated otherwise
+    pub invoices: Vec<SubscriptionInvoice>,
+}
+
+#[async_trait]
+pub trait BillingRepository: Send + Sync {
+    async fn subscription(&self, company_id: Uuid) -> AppResult<Option<Subscription>>;
+    /// Applies the change, keeping `companies.subscription_plan` at the
+    /// effective plan; returns what was stored, with versions and invoice
+    /// numbers assigned
+    async fn apply(&self, change: &BillingChange) -> AppResult<BillingChange>;
+    /// Subscriptions whose period ended by `now`, longest ended first
+    async fn due_subscriptions(&self, now: DateTime<Utc>, limit: i64) -> AppResult<Vec<Subscription>>;
+    async fn invoice(&self, id: Uuid) -> AppResult<Option<SubscriptionInvoice>>;
+    async fn invoice_by_number(&self, number: &str) -> AppResult<Option<SubscriptionInvoice>>;
+    /// The company's invoices, newest first
+    async fn invoices(&self, company_id: Uuid, limit: i64) -> AppResult<Vec<SubscriptionInvoice>>;
+    async fn open_invoices(&self, subscription_id: Uuid) -> AppResult<Vec<SubscriptionInvoice>>;
+    /// Open invoices with a dunning step due by `now`, earliest first
+    async fn dunning_due(&self, now: DateTime<Utc>, limit: i64) -> AppResult<Vec<SubscriptionInvoice>>;
+    /// The company's usage, counting licenses created since `month_start`
+    async fn usage(&self, company_id: Uuid, month_start: DateTime<Utc>) -> AppResult<Usage>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 31, 3, 0, 0).unwrap()
+    }
+
+    fn subscribed(plan: SubscriptionPlan, trial_days: i64) -> Subscription {
+        let policy = BillingPolicy {
+            trial_days,
+            ..BillingPolicy::default()
+        };
+        Subscription::start(Uuid::new_v4(), plan, &policy, now()).unwrap().0
+    }
+
+    #[test]
+    fn test_invoices_add_ppn_on_the_credited_subtotal() {
+        let policy = BillingPolicy::default();
+        let subscription = subscribed(SubscriptionPlan::Basic, 0);
+        let invoice = SubscriptionInvoice::issue(&subscription, Money::idr(0), &policy, now());
+        assert_eq!(invoice.subtotal, Money::idr(14_900_000));
+        assert_eq!(invoice.ppn, Money::idr(1_639_000));
+        assert_eq!(invoice.total, Money::idr(16_539_000));
+        assert_eq!(invoice.due_at, now() + Duration::days(7));
+        assert_eq!(invoice.next_reminder_at, Some(invoice.due_at + Duration::days(1)));
+
+        // 11% of Rp 149.000 - Rp 12.345,67 is Rp 15.031,9763, rounded down
+        let credited = SubscriptionInvoice::issue(&subscription, Money::idr(1_234_567), &policy, now());
+        assert_eq!(credited.ppn, Money::idr(1_503_100));
+        assert_eq!(credited.total, Money::idr(14_900_000 - 1_234_567 + 1_503_100));
+
+        let covered = SubscriptionInvoice::issue(&subscription, Money::idr(99_999_999), &policy, now());
+        assert_eq!((covered.total.amount, covered.status), (0, InvoiceStatus::Paid));
+        assert_eq!(covered.next_reminder_at, None);
+    }
+
+    #[test]
+    fn test_trials_convert_and_downgrades_wait_for_the_period_end() {
+        let mut subscription = subscribed(SubscriptionPlan::Basic, 14);
+        assert_eq!(subscription.status, SubscriptionStatus::Trialing);
+        assert_eq!(subscription.current_period_end, now() + Duration::days(14));
+
+        // Plans switch freely during the trial; the end of the month is clamped
+        assert_eq!(subscription.change_plan(SubscriptionPlan::Pro, now()), Ok(PlanChange::Deferred));
+        assert!(!subscription.is_due(now()));
+        assert_eq!(subscription.renew(), PlanChange::Invoiced);
+        assert_eq!((subscription.plan, subscription.status), (SubscriptionPlan::Pro, SubscriptionStatus::Active));
+        assert_eq!(subscription.current_period_start, Utc.with_ymd_and_hms(2026, 2, 14, 3, 0, 0).unwrap());
+        assert_eq!(subscription.current_period_end, Utc.with_ymd_and_hms(2026, 3, 14, 3, 0, 0).unwrap());
+
+        assert_eq!(subscription.change_plan(SubscriptionPlan::Basic, now()), Ok(PlanChange::Deferred));
+        assert_eq!(subscription.effective_plan(), SubscriptionPlan::Pro);
+        assert_eq!(subscription.renew(), PlanChange::Invoiced);
+        assert_eq!(subscription.plan, SubscriptionPlan::Basic);
+
+        subscription.cancel().unwrap();
+        assert_eq!(subscription.change_plan(SubscriptionPlan::Basic, now()), Ok(PlanChange::Deferred));
+        assert_eq!(subscription.scheduled_plan, None);
+        subscription.cancel().unwrap();
+        assert_eq!(subscription.renew(), PlanChange::Deferred);
+        assert_eq!(subscription.effective_plan(), SubscriptionPlan::Free);
+        assert!(subscription.cancel().is_err());
+        assert_eq!(subscription.change_plan(SubscriptionPlan::Basic, now()), Ok(PlanChange::Invoiced));
+    }
+
+    #[test]
+    fn test_upgrades_credit_the_unused_part_of_a_paid_period() {
+        let policy = BillingPolicy::default();
+        let mut subscription = subscribed(SubscriptionPlan::Basic, 0);
+        let mut invoice = SubscriptionInvoice::issue(&subscription, Money::idr(0), &policy, now());
+        let halfway = subscription.current_period_start
+            + (subscription.current_period_end - subscription.current_period_start) / 2;
+        assert_eq!(invoice.unused_credit(&subscription, halfway), Money::idr(0));
+
+        invoice.mark_paid(Some("VA-1".to_string()), now()).unwrap();
+        assert_eq!(invoice.unused_credit(&subscription, halfway), Money::idr(7_450_000));
+        assert_eq!(subscription.change_plan(SubscriptionPlan::Pro, halfway), Ok(PlanChange::Invoiced));
+        assert_eq!(subscription.current_period_start, halfway);
+        assert!(invoice.mark_paid(None, now()).is_err());
+    }
+
+    #[test]
+    fn test_dunning_reminds_on_schedule_then_writes_off() {
+        let policy = BillingPolicy::default();
+        let subscription = subscribed(SubscriptionPlan::Pro, 0);
+        let mut invoice = SubscriptionInvoice::issue(&subscription, Money::idr(0), &policy, now());
+        let due = invoice.due_at;
+
+        assert_eq!(invoice.dun(&policy, due), None);
+        assert_eq!(invoice.dun(&policy, due + Duration::days(1)), Some(DunningStep::Reminded(1)));
+        assert_eq!(invoice.dun(&policy, due + Duration::days(2)), None);
+        // A late sweep sends one reminder, not every one it missed
+        assert_eq!(invoice.dun(&policy, due + Duration::days(8)), Some(DunningStep::Reminded(2)));
+        assert_eq!(invoice.next_reminder_at, Some(due + Duration::days(7)));
+        assert_eq!(invoice.dun(&policy, due + Duration::days(8)), Some(DunningStep::Reminded(3)));
+        assert_eq!(invoice.next_reminder_at, Some(due + Duration::days(14)));
+        assert_eq!(invoice.dun(&policy, due + Duration::days(14)), Some(DunningStep::WrittenOff));
+        assert_eq!(invoice.status, InvoiceStatus::Uncollectible);
+        assert_eq!(invoice.dun(&policy, due + Duration::days(30)), None);
+    }
+
+    #[test]
+    fn test_limits_by_plan() {
+        let usage = Usage {
+            companies: 1,
+            licenses_this_month: 2,
+            storage_bytes: 99 * MEGABYTE,
+        };
+        let free = Entitlements::new(Uuid::new_v4(), None, usage);
+        assert_eq!(free.plan, SubscriptionPlan::Free);
+        assert!(free.check(Requirement::Company).is_err());
+        assert!(free.check(Requirement::License).is_err());
+        assert!(free.check(Requirement::Storage { bytes: MEGABYTE }).is_ok());
+        assert!(free.check(Requirement::Storage { bytes: MEGABYTE + 1 }).is_err());
+        assert!(free.check(Requirement::FinanceModule).unwrap_err().contains("free plan"));
+
+        let pro = subscribed(SubscriptionPlan::Pro, 14);
+        let pro = Entitlements::new(pro.company_id, Some(&pro), Usage { companies: 40, ..usage });
+        assert!(pro.check(Requirement::Company).is_ok());
+        assert!(pro.check(Requirement::License).is_ok());
+        assert!(pro.check(Requirement::FinanceModule).is_ok());
+    }
+}
@@ -0,0 +1,199 @@
+// License certificates - the PDF an approved license is printed on
+// Every generation is kept as a new revision: the first one when the license
+// is approved, and another whenever it is renewed, suspended or reinstated so
+// the printed validity and status follow the license. Each revision records
+// the license version it was rendered from, so a certificate that no longer
+// matches its license is easy to spot and regenerate.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::domain::licenses::{ApplicationStatus, License};
+use crate::shared::errors::AppResult;
+
+/// Why a certificate revision was generated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CertificateReason {
+    /// The license was approved
+    Issued,
+    Renewed,
+    Suspended,
+    Reinstated,
+    /// Asked for again, or the license changed since the last revision
+    Regenerated,
+}
+
+impl fmt::Display for CertificateReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CertificateReason::Issued => write!(f, "issued"),
+            CertificateReason::Renewed => write!(f, "renewed"),
+            CertificateReason::Suspended => write!(f, "suspended"),
+            CertificateReason::Reinstated => write!(f, "reinstated"),
+            CertificateReason::Regenerated => write!(f, "regenerated"),
+        }
+    }
+}
+
+impl FromStr for CertificateReason {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "issued" => Ok(CertificateReason::Issued),
+            "renewed" => Ok(CertificateReason::Renewed),
+            "suspended" => Ok(CertificateReason::Suspended),
+            "reinstated" => Ok(CertificateReason::Reinstated),
+            "regenerated" => Ok(CertificateReason::Regenerated),
+            _ => Err(format!("Invalid certificate reason: {}", s)),
+        }
+    }
+}
+
+/// Whether `license` has been issued, so there is something to certify.
+/// Suspended and expired licenses still get certificates that say so.
+pub fn is_certifiable(license: &License) -> bool {
+    license.license_number.is_some()
+        && matches!(
+            license.application_status,
+            ApplicationStatus::Approved | ApplicationStatus::Suspended | ApplicationStatus::Expired
+        )
+}
+
+/// Storage key of a certificate's PDF
+pub fn certificate_key(license_id: Uuid, certificate_id: Uuid) -> String {
+    format!("certificates/{}/{}.pdf", license_id, certificate_id)
+}
+
+/// One generated revision of a license's certificate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LicenseCertificate {
+    pub id: Uuid,
+    pub license_id: Uuid,
+    /// 1 for the first certificate of the license, assigned when recorded
+    pub revision: i32,
+    pub reason: CertificateReason,
+    /// Status and version of the license the PDF was rendered from
+    pub license_status: ApplicationStatus,
+    pub license_version: i64,
+    #[serde(skip_serializing)]
+    pub storage_key: String,
+    /// Hex SHA-256 of the PDF
+    pub sha256: String,
+    pub size_bytes: i64,
+    /// `None` when generated by the system rather than an admin's action
+    pub generated_by: Option<Uuid>,
+    pub generated_at: DateTime<Utc>,
+}
+
+impl LicenseCertificate {
+    pub fn new(
+        license: &License,
+        reason: CertificateReason,
+        generated_by: Option<Uuid>,
+        sha256: String,
+        size_bytes: i64,
+    ) -> Self {
+        let id = Uuid::new_v4();
+        Self {
+            id,
+            license_id: license.id,
+            revision: 0,
+            reason,
+            license_status: license.application_status.clone(),
+            license_version: license.version,
+            storage_key: certificate_key(license.id, id),
+            sha256,
+            size_bytes,
+            generated_by,
+            generated_at: Utc::now(),
+        }
+    }
+
+    /// Whether the certificate was rendered from the license as it is now
+    pub fn is_current_for(&self, license: &License) -> bool {
+        self.license_id == license.id && self.license_version == license.version
+    }
+}
+
+#[async_trait]
+pub trait CertificateRepository: Send + Sync {
+    /// Records `certificate` as the next revision of its license; fails with
+    /// `Conflict` if another revision was recorded at the same time
+    async fn record_certificate(&self, certificate: &LicenseCertificate) -> AppResult<LicenseCertificate>;
+    /// The highest revision of the license's certificate
+    async fn latest_certificate(&self, license_id: Uuid) -> AppResult<Option<LicenseCertificate>>;
+    /// Every revision of the license's certificate, oldest first
+    async fn certificates(&self, license_id: Uuid) -> AppResult<Vec<LicenseCertificate>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::licenses::LicenseType;
+
+    fn approved() -> License {
+        let mut license = License::new(
+            LicenseType::Siup,
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            "SIUP".to_string(),
+            None,
+        );
+        license.application_status = ApplicationStatus::Processing;
+        license
+            .approve(
+                "SIUP-001".to_string(),
+                Utc::now(),
+                None,
+                "DPMPTSP Jakarta".to_string(),
+                None,
+            )
+            .unwrap();
+        license
+    }
+
+    #[test]
+    fn test_only_issued_licenses_are_certifiable() {
+        let mut license = approved();
+        assert!(is_certifiable(&license));
+        license.application_status = ApplicationStatus::Suspended;
+        assert!(is_certifiable(&license));
+        license.application_status = ApplicationStatus::Rejected;
+        assert!(!is_certifiable(&license));
+
+        let mut unnumbered = approved();
+        unnumbered.license_number = None;
+        assert!(!is_certifiable(&unnumbered));
+    }
+
+    #[test]
+    fn test_certificate_is_current_until_the_license_changes() {
+        let mut license = approved();
+        let certificate =
+            LicenseCertificate::new(&license, CertificateReason::Issued, None, "ab".to_string(), 2);
+        assert_eq!(certificate.storage_key, certificate_key(license.id, certificate.id));
+        assert!(certificate.is_current_for(&license));
+        license.version += 1;
+        assert!(!certificate.is_current_for(&license));
+    }
+
+    #[test]
+    fn test_certificate_reason_round_trips() {
+        for reason in [
+            CertificateReason::Issued,
+            CertificateReason::Renewed,
+            CertificateReason::Suspended,
+            CertificateReason::Reinstated,
+            CertificateReason::Regenerated,
+        ] {
+            assert_eq!(reason.to_string().parse::<CertificateReason>(), Ok(reason));
+        }
+        assert!("revoked".parse::<CertificateReason>().is_err());
+    }
+}
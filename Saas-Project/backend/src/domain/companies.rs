@@ -6,6 +6,8 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use crate::domain::value_objects::{PhoneNumber, NPWP};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BusinessType {
     CV,         // Commanditaire Vennootschap
@@ -105,6 +107,39 @@ impl std::str::FromStr for CompanyStatus {
     }
 }
 
+/// Subscription plan of a company; decides the API quota of its users
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubscriptionPlan {
+    #[default]
+    Free,
+    Basic,
+    Pro,
+}
+
+impl std::fmt::Display for SubscriptionPlan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SubscriptionPlan::Free => write!(f, "free"),
+            SubscriptionPlan::Basic => write!(f, "basic"),
+            SubscriptionPlan::Pro => write!(f, "pro"),
+        }
+    }
+}
+
+impl std::str::FromStr for SubscriptionPlan {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "free" => Ok(SubscriptionPlan::Free),
+            "basic" => Ok(SubscriptionPlan::Basic),
+            "pro" => Ok(SubscriptionPlan::Pro),
+            _ => Err(format!("Invalid subscription plan: {}", s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompanyAddress {
     pub street: String,
@@ -167,11 +202,11 @@ pub struct Company {
     pub nib: Option<String>,
     pub siup_number: Option<String>,
     pub tdp_number: Option<String>,
-    pub npwp_company: Option<String>,
+    pub npwp_company: Option<NPWP>,
 
     // Contact Information
     pub email: Option<String>,
-    pub phone: Option<String>,
+    pub phone: Option<PhoneNumber>,
     pub website: Option<String>,
 
     // Address Information
@@ -204,6 +239,10 @@ pub struct Company {
     pub status: String, // Will be converted to/from CompanyStatus
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+
+    /// Row version for optimistic locking; `update` only applies to this version
+    #[serde(default = "crate::shared::types::initial_version")]
+    pub version: i64,
 }
 
 impl Company {
@@ -251,6 +290,7 @@ impl Company {
             status: CompanyStatus::PendingVerification.to_string(),
             created_at: now,
             updated_at: now,
+            version: 1,
         }
     }
 
@@ -334,7 +374,7 @@ impl Company {
     pub fn update_contact_info(
         &mut self,
         email: Option<String>,
-        phone: Option<String>,
+        phone: Option<PhoneNumber>,
         website: Option<String>,
     ) {
         self.email = email;
@@ -361,6 +401,11 @@ impl Company {
         Ok(())
     }
 
+    pub fn set_npwp(&mut self, npwp: NPWP) {
+        self.npwp_company = Some(npwp);
+        self.updated_at = Utc::now();
+    }
+
     pub fn is_complete_profile(&self) -> bool {
         // Check if company has minimum required information
         !self.company_name.trim().is_empty()
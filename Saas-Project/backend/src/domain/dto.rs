@@ -33,6 +33,9 @@ pub struct LicenseDto {
     pub rejected_at: Option<DateTime<Utc>>,
     pub admin_notes: Option<String>,
     pub rejection_reason: Option<String>,
+    pub version: i64,
+    pub sla_paused_at: Option<DateTime<Utc>>,
+    pub sla_paused_seconds: i64,
 }
 
 // Conversion from DTO to domain entity
@@ -63,6 +66,9 @@ impl From<LicenseDto> for License {
             rejected_at: dto.rejected_at,
             admin_notes: dto.admin_notes,
             rejection_reason: dto.rejection_reason,
+            version: dto.version,
+            sla_paused_at: dto.sla_paused_at,
+            sla_paused_seconds: dto.sla_paused_seconds,
         }
     }
 }
@@ -95,6 +101,9 @@ impl From<License> for LicenseDto {
             rejected_at: entity.rejected_at,
             admin_notes: entity.admin_notes,
             rejection_reason: entity.rejection_reason,
+            version: entity.version,
+            sla_paused_at: entity.sla_paused_at,
+            sla_paused_seconds: entity.sla_paused_seconds,
         }
     }
 }
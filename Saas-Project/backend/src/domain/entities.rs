@@ -20,6 +20,19 @@ pub struct User {
     pub email_verified_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+
+    // Account controls set by admins
+    /// Sign-in is refused until the password is reset
+    #[serde(default)]
+    pub password_reset_required: bool,
+    /// Argon2 hash of the outstanding reset token
+    #[serde(default)]
+    pub password_reset_token_hash: Option<String>,
+    #[serde(default)]
+    pub password_reset_expires_at: Option<DateTime<Utc>>,
+    /// Tokens issued before this are no longer honoured
+    #[serde(default)]
+    pub sessions_revoked_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -97,6 +110,20 @@ impl std::fmt::Display for UserStatus {
     }
 }
 
+impl FromStr for UserStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "active" => Ok(UserStatus::Active),
+            "inactive" => Ok(UserStatus::Inactive),
+            "suspended" => Ok(UserStatus::Suspended),
+            "pending_verification" => Ok(UserStatus::PendingVerification),
+            _ => Err(format!("Unknown status: {}", s)),
+        }
+    }
+}
+
 impl User {
     pub fn new(
         email: Email,
@@ -116,6 +143,10 @@ impl User {
             email_verified_at: None,
             created_at: now,
             updated_at: now,
+            password_reset_required: false,
+            password_reset_token_hash: None,
+            password_reset_expires_at: None,
+            sessions_revoked_at: None,
         }
     }
 
@@ -145,7 +176,66 @@ impl User {
 
     pub fn update_password(&mut self, new_password_hash: String) {
         self.password_hash = new_password_hash;
+        self.password_reset_required = false;
+        self.password_reset_token_hash = None;
+        self.password_reset_expires_at = None;
+        self.updated_at = Utc::now();
+    }
+
+    /// Ends every session: tokens issued until now are refused
+    pub fn revoke_sessions(&mut self) {
+        let now = Utc::now();
+        self.sessions_revoked_at = Some(now);
+        self.updated_at = now;
+    }
+
+    /// Whether a token issued at `issued_at` (seconds since the epoch) is
+    /// still honoured
+    pub fn accepts_token_issued_at(&self, issued_at: i64) -> bool {
+        self.sessions_revoked_at
+            .is_none_or(|revoked_at| issued_at >= revoked_at.timestamp())
+    }
+
+    /// Whether a token for this account may be used at all
+    pub fn can_use_session(&self, issued_at: i64) -> bool {
+        self.can_login() && !self.password_reset_required && self.accepts_token_issued_at(issued_at)
+    }
+
+    pub fn suspend(&mut self) -> Result<(), String> {
+        if self.status == UserStatus::Suspended {
+            return Err("User is already suspended".to_string());
+        }
+        self.status = UserStatus::Suspended;
+        self.revoke_sessions();
+        Ok(())
+    }
+
+    pub fn reactivate(&mut self) -> Result<(), String> {
+        if self.status != UserStatus::Suspended {
+            return Err("Only suspended users can be reactivated".to_string());
+        }
+        self.status = UserStatus::Active;
         self.updated_at = Utc::now();
+        Ok(())
+    }
+
+    /// Refuses sign-in until the password is reset with the token hashed as
+    /// `token_hash`, and ends every session
+    pub fn require_password_reset(&mut self, token_hash: String, expires_at: DateTime<Utc>) {
+        self.password_reset_required = true;
+        self.password_reset_token_hash = Some(token_hash);
+        self.password_reset_expires_at = Some(expires_at);
+        self.revoke_sessions();
+    }
+
+    /// Tokens carry the role, so sessions signed in with the old one end
+    pub fn change_role(&mut self, role: UserRole) -> Result<(), String> {
+        if self.role == role {
+            return Err(format!("User already has the {} role", role));
+        }
+        self.role = role;
+        self.revoke_sessions();
+        Ok(())
     }
 }
 
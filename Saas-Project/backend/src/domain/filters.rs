@@ -0,0 +1,370 @@
+// Typed list filters and sort fields for the list endpoints
+// Used with shared::query::ListQuery by every repository that supports paging
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::domain::companies::{BusinessScale, Company, CompanyStatus};
+use crate::domain::entities::{User, UserRole, UserStatus};
+use crate::domain::finance::{Transaction, TransactionStatus, TransactionType};
+use crate::domain::licenses::{
+    ApplicationStatus, DocumentType, License, LicenseDocument, LicenseType, PriorityLevel,
+};
+use crate::shared::query::{SortField, SortKind, SortValue, Sortable};
+
+/// Declares a sort field enum together with its wire name, column and kind.
+/// The first variant is the default sort.
+macro_rules! sort_fields {
+    ($name:ident { $($variant:ident => ($wire:literal, $column:literal, $kind:ident)),+ $(,)? }) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum $name {
+            $($variant),+
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    $($name::$variant => write!(f, $wire)),+
+                }
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $($wire => Ok($name::$variant),)+
+                    _ => Err(format!("Unknown sort field: {}", s)),
+                }
+            }
+        }
+
+        impl SortField for $name {
+            fn column(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $column),+
+                }
+            }
+
+            fn kind(&self) -> SortKind {
+                match self {
+                    $($name::$variant => SortKind::$kind),+
+                }
+            }
+
+            fn default_field() -> Self {
+                [$($name::$variant),+][0]
+            }
+        }
+    };
+}
+
+/// Reads an optional query string value through the type's `FromStr`, for
+/// enums whose wire format is their snake_case `Display` form
+fn from_str_opt<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr<Err = String>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+// ----------------
+// Users
+// ----------------
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UserFilter {
+    #[serde(default, deserialize_with = "from_str_opt")]
+    pub role: Option<UserRole>,
+    #[serde(default, deserialize_with = "from_str_opt")]
+    pub status: Option<UserStatus>,
+    /// Matches email or full name
+    pub search: Option<String>,
+    pub created_from: Option<DateTime<Utc>>,
+    pub created_to: Option<DateTime<Utc>>,
+}
+
+sort_fields!(UserSortField {
+    CreatedAt => ("created_at", "created_at", Timestamp),
+    Email => ("email", "email", Text),
+    FullName => ("full_name", "full_name", Text),
+});
+
+impl Sortable<UserSortField> for User {
+    fn sort_value(&self, field: UserSortField) -> SortValue {
+        match field {
+            UserSortField::CreatedAt => SortValue::Timestamp(self.created_at),
+            UserSortField::Email => SortValue::Text(self.email.as_str().to_string()),
+            UserSortField::FullName => SortValue::Text(self.full_name.clone()),
+        }
+    }
+
+    fn sort_id(&self) -> Uuid {
+        *self.id.as_uuid()
+    }
+}
+
+impl UserFilter {
+    pub fn matches(&self, user: &User) -> bool {
+        self.role.as_ref().is_none_or(|r| &user.role == r)
+            && self.status.as_ref().is_none_or(|s| &user.status == s)
+            && self.created_from.is_none_or(|from| user.created_at >= from)
+            && self.created_to.is_none_or(|to| user.created_at <= to)
+            && self.search.as_deref().is_none_or(|q| {
+                let q = q.to_lowercase();
+                user.email.as_str().to_lowercase().contains(&q)
+                    || user.full_name.to_lowercase().contains(&q)
+            })
+    }
+}
+
+// ----------------
+// Companies
+// ----------------
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CompanyFilter {
+    pub owner_id: Option<Uuid>,
+    #[serde(default, deserialize_with = "from_str_opt")]
+    pub status: Option<CompanyStatus>,
+    #[serde(default, deserialize_with = "from_str_opt")]
+    pub business_scale: Option<BusinessScale>,
+    pub province: Option<String>,
+    pub city: Option<String>,
+    pub is_verified: Option<bool>,
+    /// Matches company name, industry sector, city or NIB
+    pub search: Option<String>,
+}
+
+sort_fields!(CompanySortField {
+    CreatedAt => ("created_at", "created_at", Timestamp),
+    UpdatedAt => ("updated_at", "updated_at", Timestamp),
+    CompanyName => ("company_name", "company_name", Text),
+});
+
+impl Sortable<CompanySortField> for Company {
+    fn sort_value(&self, field: CompanySortField) -> SortValue {
+        match field {
+            CompanySortField::CreatedAt => SortValue::Timestamp(self.created_at),
+            CompanySortField::UpdatedAt => SortValue::Timestamp(self.updated_at),
+            CompanySortField::CompanyName => SortValue::Text(self.company_name.clone()),
+        }
+    }
+
+    fn sort_id(&self) -> Uuid {
+        self.id
+    }
+}
+
+impl CompanyFilter {
+    pub fn matches(&self, company: &Company) -> bool {
+        self.owner_id.is_none_or(|owner_id| company.owner_id == owner_id)
+            && self.status.as_ref().is_none_or(|s| company.status == s.to_string())
+            && self.business_scale.as_ref().is_none_or(|s| company.business_scale == s.to_string())
+            && self.province.as_deref().is_none_or(|p| company.address_province.eq_ignore_ascii_case(p))
+            && self.city.as_deref().is_none_or(|c| company.address_city.eq_ignore_ascii_case(c))
+            && self.is_verified.is_none_or(|v| company.is_verified == v)
+            && self
+                .search
+                .as_deref()
+                .map(str::trim)
+                .filter(|q| !q.is_empty())
+                .is_none_or(|q| {
+                    let q = q.to_lowercase();
+                    [
+                        Some(company.company_name.as_str()),
+                        Some(company.industry_sector.as_str()),
+                        Some(company.address_city.as_str()),
+                        company.nib.as_deref(),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .any(|value| value.to_lowercase().contains(&q))
+                })
+    }
+}
+
+// ----------------
+// Licenses and documents
+// ----------------
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LicenseFilter {
+    /// Licenses filed by this user or under a company this user owns
+    pub owner_id: Option<Uuid>,
+    pub company_id: Option<Uuid>,
+    pub status: Option<ApplicationStatus>,
+    pub license_type: Option<LicenseType>,
+    pub priority: Option<PriorityLevel>,
+    /// Matches title, license number or description
+    pub search: Option<String>,
+}
+
+sort_fields!(LicenseSortField {
+    CreatedAt => ("created_at", "created_at", Timestamp),
+    UpdatedAt => ("updated_at", "updated_at", Timestamp),
+    Title => ("title", "title", Text),
+});
+
+impl Sortable<LicenseSortField> for License {
+    fn sort_value(&self, field: LicenseSortField) -> SortValue {
+        match field {
+            LicenseSortField::CreatedAt => SortValue::Timestamp(self.created_at),
+            LicenseSortField::UpdatedAt => SortValue::Timestamp(self.updated_at),
+            LicenseSortField::Title => SortValue::Text(self.title.clone()),
+        }
+    }
+
+    fn sort_id(&self) -> Uuid {
+        self.id
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DocumentFilter {
+    pub license_id: Option<Uuid>,
+    pub document_type: Option<DocumentType>,
+    pub is_verified: Option<bool>,
+}
+
+sort_fields!(DocumentSortField {
+    UploadDate => ("upload_date", "upload_date", Timestamp),
+    FileName => ("file_name", "original_file_name", Text),
+});
+
+impl Sortable<DocumentSortField> for LicenseDocument {
+    fn sort_value(&self, field: DocumentSortField) -> SortValue {
+        match field {
+            DocumentSortField::UploadDate => SortValue::Timestamp(self.upload_date),
+            DocumentSortField::FileName => SortValue::Text(self.original_file_name.clone()),
+        }
+    }
+
+    fn sort_id(&self) -> Uuid {
+        self.id
+    }
+}
+
+// ----------------
+// Finance
+// ----------------
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TransactionFilter {
+    pub company_id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
+    #[serde(default, deserialize_with = "from_str_opt")]
+    pub status: Option<TransactionStatus>,
+    #[serde(default, deserialize_with = "from_str_opt")]
+    pub transaction_type: Option<TransactionType>,
+    /// Inclusive bounds in the smallest currency unit
+    pub min_amount: Option<i64>,
+    pub max_amount: Option<i64>,
+    pub start_date: Option<DateTime<Utc>>,
+    pub end_date: Option<DateTime<Utc>>,
+    /// Matches description or reference number
+    pub search: Option<String>,
+}
+
+sort_fields!(TransactionSortField {
+    TransactionDate => ("transaction_date", "transaction_date", Timestamp),
+    Amount => ("amount", "amount", Integer),
+    CreatedAt => ("created_at", "created_at", Timestamp),
+});
+
+impl Sortable<TransactionSortField> for Transaction {
+    fn sort_value(&self, field: TransactionSortField) -> SortValue {
+        match field {
+            TransactionSortField::TransactionDate => SortValue::Timestamp(self.transaction_date),
+            TransactionSortField::Amount => SortValue::Integer(self.amount.amount),
+            TransactionSortField::CreatedAt => SortValue::Timestamp(self.created_at),
+        }
+    }
+
+    fn sort_id(&self) -> Uuid {
+        self.id.value()
+    }
+}
+
+impl TransactionFilter {
+    pub fn matches(&self, transaction: &Transaction) -> bool {
+        self.company_id.is_none_or(|id| transaction.company_id == id)
+            && self.account_id.is_none_or(|id| transaction.account_id == id)
+            && self.status.as_ref().is_none_or(|s| &transaction.status == s)
+            && self.transaction_type.as_ref().is_none_or(|t| &transaction.transaction_type == t)
+            && self.min_amount.is_none_or(|min| transaction.amount.amount >= min)
+            && self.max_amount.is_none_or(|max| transaction.amount.amount <= max)
+            && self.start_date.is_none_or(|from| transaction.transaction_date >= from)
+            && self.end_date.is_none_or(|to| transaction.transaction_date <= to)
+            && self
+                .search
+                .as_deref()
+                .map(str::trim)
+                .filter(|q| !q.is_empty())
+                .is_none_or(|q| {
+                    let q = q.to_lowercase();
+                    transaction.description.to_lowercase().contains(&q)
+                        || transaction
+                            .reference_number
+                            .as_deref()
+                            .is_some_and(|r| r.to_lowercase().contains(&q))
+                })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::query::{ListParams, ListQuery, SortDirection};
+
+    #[test]
+    fn test_sort_fields_parse_wire_names() {
+        assert_eq!(UserSortField::default_field(), UserSortField::CreatedAt);
+        assert_eq!("file_name".parse::<DocumentSortField>(), Ok(DocumentSortField::FileName));
+        assert_eq!(DocumentSortField::FileName.column(), "original_file_name");
+        assert!("original_file_name".parse::<DocumentSortField>().is_err());
+
+        let params = ListParams {
+            sort: Some("-amount".to_string()),
+            ..Default::default()
+        };
+        let query =
+            ListQuery::<TransactionFilter, TransactionSortField>::from_params(Default::default(), &params)
+                .unwrap();
+        assert_eq!(query.sort.field, TransactionSortField::Amount);
+        assert_eq!(query.sort.direction, SortDirection::Desc);
+    }
+
+    fn parse_query<T: serde::de::DeserializeOwned>(query: &str) -> Result<T, String> {
+        let uri: axum::http::Uri = format!("/?{}", query).parse().unwrap();
+        axum::extract::Query::<T>::try_from_uri(&uri)
+            .map(|q| q.0)
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_filters_deserialize_from_query_string() {
+        let filter: CompanyFilter =
+            parse_query("status=pending_verification&business_scale=&is_verified=true").unwrap();
+        assert_eq!(filter.status, Some(CompanyStatus::PendingVerification));
+        assert_eq!(filter.business_scale, None);
+        assert_eq!(filter.is_verified, Some(true));
+
+        let filter: UserFilter = parse_query("role=admin_staff&status=suspended").unwrap();
+        assert_eq!(filter.role, Some(UserRole::AdminStaff));
+        assert_eq!(filter.status, Some(UserStatus::Suspended));
+
+        let filter: TransactionFilter =
+            parse_query("transaction_type=loan_repayment&min_amount=100000").unwrap();
+        assert_eq!(filter.transaction_type, Some(TransactionType::LoanRepayment));
+        assert_eq!(filter.min_amount, Some(100000));
+        assert!(parse_query::<TransactionFilter>("status=settled").is_err());
+    }
+}
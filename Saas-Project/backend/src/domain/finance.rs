@@ -9,8 +9,11 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 
+use crate::domain::filters::{TransactionFilter, TransactionSortField};
+use crate::domain::unit_of_work::UnitOfWork;
 use crate::domain::value_objects::{Currency, Money};
 use crate::shared::errors::AppError;
+use crate::shared::query::{ListQuery, Page};
 
 // ----------------
 // Value Objects
@@ -39,6 +42,35 @@ pub enum TransactionType {
     LoanRepayment,
 }
 
+impl std::fmt::Display for TransactionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransactionType::Income => write!(f, "income"),
+            TransactionType::Expense => write!(f, "expense"),
+            TransactionType::Transfer => write!(f, "transfer"),
+            TransactionType::Investment => write!(f, "investment"),
+            TransactionType::Loan => write!(f, "loan"),
+            TransactionType::LoanRepayment => write!(f, "loan_repayment"),
+        }
+    }
+}
+
+impl std::str::FromStr for TransactionType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "income" => Ok(TransactionType::Income),
+            "expense" => Ok(TransactionType::Expense),
+            "transfer" => Ok(TransactionType::Transfer),
+            "investment" => Ok(TransactionType::Investment),
+            "loan" => Ok(TransactionType::Loan),
+            "loan_repayment" => Ok(TransactionType::LoanRepayment),
+            _ => Err(format!("Invalid transaction type: {}", s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum TransactionStatus {
     Draft,
@@ -49,6 +81,35 @@ pub enum TransactionStatus {
     Refunded,
 }
 
+impl std::fmt::Display for TransactionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransactionStatus::Draft => write!(f, "draft"),
+            TransactionStatus::Pending => write!(f, "pending"),
+            TransactionStatus::Completed => write!(f, "completed"),
+            TransactionStatus::Failed => write!(f, "failed"),
+            TransactionStatus::Cancelled => write!(f, "cancelled"),
+            TransactionStatus::Refunded => write!(f, "refunded"),
+        }
+    }
+}
+
+impl std::str::FromStr for TransactionStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "draft" => Ok(TransactionStatus::Draft),
+            "pending" => Ok(TransactionStatus::Pending),
+            "completed" => Ok(TransactionStatus::Completed),
+            "failed" => Ok(TransactionStatus::Failed),
+            "cancelled" => Ok(TransactionStatus::Cancelled),
+            "refunded" => Ok(TransactionStatus::Refunded),
+            _ => Err(format!("Invalid transaction status: {}", s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum AccountType {
     Cash,
@@ -103,6 +164,15 @@ pub struct Transaction {
     pub updated_at: DateTime<Utc>,
     pub created_by: Uuid,
     pub updated_by: Option<Uuid>,
+    /// Bank reconciliation that cleared this transaction; once set the
+    /// transaction is locked against further edits
+    #[serde(default)]
+    pub reconciliation_id: Option<Uuid>,
+    #[serde(default)]
+    pub reconciled_at: Option<DateTime<Utc>>,
+    /// Row version for optimistic locking
+    #[serde(default = "crate::shared::types::initial_version")]
+    pub version: i64,
 }
 
 impl Transaction {
@@ -134,9 +204,34 @@ impl Transaction {
             updated_at: now,
             created_by,
             updated_by: None,
+            reconciliation_id: None,
+            reconciled_at: None,
+            version: 1,
         }
     }
 
+    /// Effect on the account balance, in the smallest currency unit
+    pub fn signed_amount(&self) -> i64 {
+        match self.transaction_type {
+            TransactionType::Expense => -self.amount.amount,
+            _ => self.amount.amount,
+        }
+    }
+
+    pub fn is_reconciled(&self) -> bool {
+        self.reconciled_at.is_some()
+    }
+
+    /// Reconciled transactions agree with a bank statement and must not change
+    pub fn ensure_editable(&self) -> Result<(), AppError> {
+        if self.is_reconciled() {
+            return Err(AppError::Conflict(
+                "Transaction has been reconciled and can no longer be changed".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
     pub fn complete(&mut self) -> Result<(), AppError> {
         match self.status {
             TransactionStatus::Draft | TransactionStatus::Pending => {
@@ -152,6 +247,7 @@ impl Transaction {
     }
 
     pub fn cancel(&mut self) -> Result<(), AppError> {
+        self.ensure_editable()?;
         match self.status {
             TransactionStatus::Draft | TransactionStatus::Pending => {
                 self.status = TransactionStatus::Cancelled;
@@ -182,6 +278,10 @@ pub struct FinancialAccount {
     pub metadata: Option<serde_json::Value>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Row version for optimistic locking; bumped by every update, including
+    /// balance adjustments from imports and reconciliation
+    #[serde(default = "crate::shared::types::initial_version")]
+    pub version: i64,
 }
 
 impl FinancialAccount {
@@ -205,6 +305,7 @@ impl FinancialAccount {
             metadata: None,
             created_at: now,
             updated_at: now,
+            version: 1,
         }
     }
 
@@ -235,25 +336,21 @@ impl FinancialAccount {
 pub trait TransactionRepository: Send + Sync {
     async fn create(&self, transaction: &Transaction) -> Result<Transaction, AppError>;
     async fn find_by_id(&self, id: &TransactionId) -> Result<Option<Transaction>, AppError>;
+    /// Conditional on `transaction.version`; `AppError::VersionConflict` when
+    /// the stored transaction has moved on
     async fn update(&self, transaction: &Transaction) -> Result<Transaction, AppError>;
-    async fn list_by_company(
-        &self,
-        company_id: Uuid,
-        limit: i64,
-        offset: i64,
-        filters: Option<HashMap<String, String>>,
-    ) -> Result<Vec<Transaction>, AppError>;
-    async fn count_by_company(
+    async fn list(
         &self,
-        company_id: Uuid,
-        filters: Option<HashMap<String, String>>,
-    ) -> Result<i64, AppError>;
+        query: &ListQuery<TransactionFilter, TransactionSortField>,
+    ) -> Result<Page<Transaction>, AppError>;
 }
 
 #[async_trait::async_trait]
 pub trait FinancialAccountRepository: Send + Sync {
     async fn create(&self, account: &FinancialAccount) -> Result<FinancialAccount, AppError>;
     async fn find_by_id(&self, id: Uuid) -> Result<Option<FinancialAccount>, AppError>;
+    /// Conditional on `account.version`, so two balance updates computed from
+    /// the same read cannot both be saved
     async fn update(&self, account: &FinancialAccount) -> Result<FinancialAccount, AppError>;
     async fn list_by_company(&self, company_id: Uuid) -> Result<Vec<FinancialAccount>, AppError>;
     async fn delete(&self, id: Uuid) -> Result<(), AppError>;
@@ -263,26 +360,32 @@ pub trait FinancialAccountRepository: Send + Sync {
 // Domain Services
 // ----------------
 
-pub struct FinancialService<T: TransactionRepository, A: FinancialAccountRepository> {
-    transaction_repository: T,
-    account_repository: A,
+/// The finance repositories a unit of work carries
+pub struct FinanceRepositories {
+    pub transactions: Box<dyn TransactionRepository>,
+    pub accounts: Box<dyn FinancialAccountRepository>,
 }
 
-impl<T: TransactionRepository, A: FinancialAccountRepository> FinancialService<T, A> {
-    pub fn new(transaction_repository: T, account_repository: A) -> Self {
-        Self {
-            transaction_repository,
-            account_repository,
-        }
+pub struct FinancialService<U: UnitOfWork<FinanceRepositories>> {
+    unit_of_work: U,
+}
+
+impl<U: UnitOfWork<FinanceRepositories>> FinancialService<U> {
+    pub fn new(unit_of_work: U) -> Self {
+        Self { unit_of_work }
     }
 
+    /// Books the transaction against its account: the new balance and the
+    /// completed transaction are committed together or not at all
     pub async fn execute_transaction(
         &self,
         transaction: &mut Transaction,
     ) -> Result<Transaction, AppError> {
+        let work = self.unit_of_work.begin().await?;
+
         // 1. Validate the transaction
-        let account = self
-            .account_repository
+        let account = work
+            .accounts
             .find_by_id(transaction.account_id)
             .await?
             .ok_or_else(|| AppError::NotFound("Account not found".to_string()))?;
@@ -291,6 +394,15 @@ impl<T: TransactionRepository, A: FinancialAccountRepository> FinancialService<T
             return Err(AppError::Validation("Account is not active".to_string()));
         }
 
+        let stored = work.transactions.find_by_id(&transaction.id).await?;
+        if let Some(stored) = &stored {
+            if stored.status == TransactionStatus::Completed {
+                return Err(AppError::Conflict(
+                    "Transaction has already been executed".to_string(),
+                ));
+            }
+        }
+
         // 2. Update the account balance based on transaction type
         let mut account_to_update = account.clone();
 
@@ -303,13 +415,18 @@ impl<T: TransactionRepository, A: FinancialAccountRepository> FinancialService<T
 
         account_to_update.update_balance(amount_adjustment)?;
 
-        // 3. Save the updated account
-        let _updated_account = self.account_repository.update(&account_to_update).await?;
+        // 3. Save the updated account; a concurrent booking against the same
+        // balance turns this into a version conflict instead of a lost update
+        work.accounts.update(&account_to_update).await?;
 
-        // 4. Complete and save the transaction
+        // 4. Complete and save the transaction, recording it first if it is new
         transaction.complete()?;
-        let saved_transaction = self.transaction_repository.update(transaction).await?;
+        let saved_transaction = match stored {
+            Some(_) => work.transactions.update(transaction).await?,
+            None => work.transactions.create(transaction).await?,
+        };
 
+        work.commit().await?;
         Ok(saved_transaction)
     }
 
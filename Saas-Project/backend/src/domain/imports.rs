@@ -0,0 +1,1453 @@
+// Transaction import domain - bulk loading of spreadsheets and bank statements
+// An uploaded file is parsed into a preview batch with a per-row validation report;
+// committing the batch books the valid rows into financial_transactions. Rows are
+// deduplicated on date + amount + reference, so committing twice or uploading an
+// overlapping statement never books the same transaction twice.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::domain::finance::{
+    FinancialAccount, FinancialAccountRepository, Transaction, TransactionStatus, TransactionType,
+};
+use crate::domain::value_objects::{Currency, Money};
+use crate::shared::errors::AppError;
+
+/// Upper bound on transaction rows per upload
+pub const MAX_IMPORT_ROWS: usize = 5000;
+
+// ----------------
+// Tabular input
+// ----------------
+
+/// A spreadsheet cell. CSV files only produce text; Excel files keep numbers
+/// and dates typed so they are never re-parsed with a locale.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Empty,
+    Text(String),
+    Number(f64),
+    Date(NaiveDate),
+}
+
+impl Cell {
+    /// Trimmed text content, without the leading apostrophe bank exports use
+    /// to force a cell to text
+    pub fn text(&self) -> Option<String> {
+        match self {
+            Cell::Empty => None,
+            Cell::Text(s) => {
+                let s = s.trim().trim_start_matches('\'').trim();
+                (!s.is_empty()).then(|| s.to_string())
+            }
+            Cell::Number(n) => Some(n.to_string()),
+            Cell::Date(d) => Some(d.format("%d/%m/%Y").to_string()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text().is_none()
+    }
+}
+
+/// Rows of an uploaded file, in file order and including any preamble lines
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    pub rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    pub fn new(rows: Vec<Vec<Cell>>) -> Self {
+        Self { rows }
+    }
+}
+
+fn cell_at(cells: &[Cell], index: usize) -> &Cell {
+    cells.get(index).unwrap_or(&Cell::Empty)
+}
+
+// ----------------
+// Number and date formats
+// ----------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NumberFormat {
+    /// `1.234.567,89`
+    #[default]
+    Indonesian,
+    /// `1,234,567.89`
+    International,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DateFormat {
+    #[default]
+    #[serde(rename = "dd/mm/yyyy")]
+    DayMonthYear,
+    #[serde(rename = "yyyy-mm-dd")]
+    YearMonthDay,
+    #[serde(rename = "mm/dd/yyyy")]
+    MonthDayYear,
+    /// Day and month only, the year comes from the statement period
+    #[serde(rename = "dd/mm")]
+    DayMonth,
+}
+
+/// Parses an amount into the smallest currency unit, e.g. `1.234.567,89`
+/// becomes 123456789 with `NumberFormat::Indonesian`. A currency prefix, a
+/// minus sign or accounting parentheses are accepted.
+pub fn parse_amount(text: &str, format: NumberFormat) -> Result<i64, String> {
+    let invalid = || format!("Invalid amount '{}'", text.trim());
+
+    let mut s: String = text
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '\u{a0}')
+        .collect();
+    for prefix in ["Rp.", "Rp", "IDR"] {
+        if s.get(..prefix.len()).is_some_and(|p| p.eq_ignore_ascii_case(prefix)) {
+            s = s[prefix.len()..].to_string();
+            break;
+        }
+    }
+
+    let mut negative = false;
+    if s.starts_with('(') && s.ends_with(')') {
+        negative = true;
+        s = s[1..s.len() - 1].to_string();
+    }
+    if let Some(rest) = s.strip_prefix('-').or_else(|| s.strip_suffix('-')) {
+        negative = !negative;
+        s = rest.to_string();
+    } else if let Some(rest) = s.strip_prefix('+') {
+        s = rest.to_string();
+    }
+
+    let (group_separator, decimal_separator) = match format {
+        NumberFormat::Indonesian => ('.', ','),
+        NumberFormat::International => (',', '.'),
+    };
+    if s.matches(decimal_separator).count() > 1 {
+        return Err(invalid());
+    }
+    let (integer, fraction) = s.split_once(decimal_separator).unwrap_or((&s, ""));
+
+    let groups: Vec<&str> = integer.split(group_separator).collect();
+    let grouped_correctly = groups.len() == 1
+        || (!groups[0].is_empty()
+            && groups[0].len() <= 3
+            && groups[1..].iter().all(|g| g.len() == 3));
+    let digits = groups.concat();
+    if !grouped_correctly
+        || (digits.is_empty() && fraction.is_empty())
+        || !digits.chars().all(|c| c.is_ascii_digit())
+        || !fraction.chars().all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    if fraction.len() > 2 {
+        return Err(format!("Amount '{}' has more than two decimal places", text.trim()));
+    }
+
+    let whole: i64 = if digits.is_empty() {
+        0
+    } else {
+        digits.parse().map_err(|_| invalid())?
+    };
+    let cents: i64 = format!("{:0<2}", fraction).parse().map_err(|_| invalid())?;
+    let amount = whole
+        .checked_mul(100)
+        .and_then(|v| v.checked_add(cents))
+        .ok_or_else(invalid)?;
+
+    Ok(if negative { -amount } else { amount })
+}
+
+/// Converts an Excel date serial (days since 1899-12-30) to a date
+pub fn excel_serial_date(serial: f64) -> Option<NaiveDate> {
+    if !(1.0..=2_958_465.0).contains(&serial) {
+        return None;
+    }
+    NaiveDate::from_ymd_opt(1899, 12, 30)?.checked_add_signed(Duration::days(serial.floor() as i64))
+}
+
+fn month_from_name(name: &str) -> Option<u32> {
+    let name = name.to_lowercase();
+    let month = match name.get(..3)? {
+        "jan" => 1,
+        "feb" => 2,
+        "mar" => 3,
+        "apr" => 4,
+        "mei" | "may" => 5,
+        "jun" => 6,
+        "jul" => 7,
+        "agu" | "agt" | "aug" => 8,
+        "sep" => 9,
+        "okt" | "oct" => 10,
+        "nov" => 11,
+        "des" | "dec" => 12,
+        _ => return None,
+    };
+    Some(month)
+}
+
+/// The period printed in a statement header, used to complete `dd/mm` dates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatementPeriod {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl StatementPeriod {
+    /// The date with this day and month that falls inside the period
+    fn resolve(&self, day: u32, month: u32) -> Option<NaiveDate> {
+        let candidates: Vec<NaiveDate> = [self.start.year(), self.end.year()]
+            .iter()
+            .filter_map(|&year| NaiveDate::from_ymd_opt(year, month, day))
+            .collect();
+        candidates
+            .iter()
+            .find(|d| **d >= self.start && **d <= self.end)
+            .or_else(|| candidates.first())
+            .copied()
+    }
+}
+
+/// Parses a date written in `format`. Separators may be `/`, `-`, `.` or
+/// spaces, months may be Indonesian or English names, two-digit years are
+/// taken as 20xx and a trailing time of day is ignored.
+pub fn parse_date(
+    text: &str,
+    format: DateFormat,
+    period: Option<&StatementPeriod>,
+) -> Result<NaiveDate, String> {
+    let text = text.trim();
+    if text.eq_ignore_ascii_case("PEND") {
+        return Err("Transaction is still pending at the bank".to_string());
+    }
+    let invalid = || format!("Invalid date '{}', expected {}", text, format.pattern());
+
+    let date_part = text
+        .split_whitespace()
+        .filter(|token| !token.contains(':'))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let parts: Vec<&str> = date_part
+        .split(['/', '-', '.', ' '])
+        .filter(|p| !p.is_empty())
+        .collect();
+
+    let number = |s: &str| s.parse::<u32>().ok();
+    let month = |s: &str| number(s).or_else(|| month_from_name(s));
+    let year = |s: &str| match (s.len(), s.parse::<i32>().ok()) {
+        (2, Some(y)) => Some(2000 + y),
+        (4, Some(y)) => Some(y),
+        _ => None,
+    };
+
+    let (day, month, year) = match (format, parts.as_slice()) {
+        (DateFormat::DayMonthYear, [d, m, y]) | (DateFormat::DayMonth, [d, m, y]) => {
+            (number(d), month(m), year(y))
+        }
+        (DateFormat::YearMonthDay, [y, m, d]) => (number(d), month(m), year(y)),
+        (DateFormat::MonthDayYear, [m, d, y]) => (number(d), month(m), year(y)),
+        (DateFormat::DayMonth, [d, m]) => {
+            let period = period
+                .ok_or_else(|| format!("Date '{}' has no year and the statement period is unknown", text))?;
+            let (day, month) = number(d).zip(month(m)).ok_or_else(invalid)?;
+            return period.resolve(day, month).ok_or_else(invalid);
+        }
+        _ => return Err(invalid()),
+    };
+
+    match (day, month, year) {
+        (Some(d), Some(m), Some(y)) => NaiveDate::from_ymd_opt(y, m, d).ok_or_else(invalid),
+        _ => Err(invalid()),
+    }
+}
+
+impl DateFormat {
+    pub fn pattern(&self) -> &'static str {
+        match self {
+            DateFormat::DayMonthYear => "dd/mm/yyyy",
+            DateFormat::YearMonthDay => "yyyy-mm-dd",
+            DateFormat::MonthDayYear => "mm/dd/yyyy",
+            DateFormat::DayMonth => "dd/mm",
+        }
+    }
+}
+
+fn cell_amount(cell: &Cell, format: NumberFormat) -> Result<Option<i64>, String> {
+    match cell {
+        Cell::Number(n) if n.is_finite() && n.abs() < (i64::MAX / 100) as f64 => {
+            Ok(Some((n * 100.0).round() as i64))
+        }
+        Cell::Number(n) => Err(format!("Invalid amount '{}'", n)),
+        other => other.text().map(|t| parse_amount(&t, format)).transpose(),
+    }
+}
+
+fn cell_date(
+    cell: &Cell,
+    format: DateFormat,
+    period: Option<&StatementPeriod>,
+) -> Result<Option<NaiveDate>, String> {
+    match cell {
+        Cell::Date(d) => Ok(Some(*d)),
+        Cell::Number(serial) => excel_serial_date(*serial)
+            .map(Some)
+            .ok_or_else(|| format!("Invalid date '{}'", serial)),
+        other => other.text().map(|t| parse_date(&t, format, period)).transpose(),
+    }
+}
+
+/// +1 for credit indicators, -1 for debit indicators
+fn direction_sign(indicator: &str) -> Option<i64> {
+    match indicator.trim().to_uppercase().as_str() {
+        "CR" | "C" | "K" | "KR" | "KREDIT" | "CREDIT" => Some(1),
+        "DB" | "D" | "DR" | "DEBET" | "DEBIT" => Some(-1),
+        _ => None,
+    }
+}
+
+// ----------------
+// Column mappings
+// ----------------
+
+/// A column by header name (case-insensitive) or by 0-based position
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ColumnRef {
+    Index(usize),
+    Name(String),
+}
+
+impl ColumnRef {
+    fn name(name: &str) -> Self {
+        ColumnRef::Name(name.to_string())
+    }
+}
+
+/// How the columns of a file map onto a transaction. Amounts come either from
+/// one signed `amount` column (optionally with a `direction` column holding
+/// DB/CR) or from separate `debit` and `credit` columns.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnMapping {
+    pub date: ColumnRef,
+    #[serde(default)]
+    pub description: Vec<ColumnRef>,
+    #[serde(default)]
+    pub amount: Option<ColumnRef>,
+    #[serde(default)]
+    pub direction: Option<ColumnRef>,
+    #[serde(default)]
+    pub debit: Option<ColumnRef>,
+    #[serde(default)]
+    pub credit: Option<ColumnRef>,
+    #[serde(default)]
+    pub reference: Option<ColumnRef>,
+    #[serde(default)]
+    pub date_format: DateFormat,
+    #[serde(default)]
+    pub number_format: NumberFormat,
+}
+
+/// Column positions of a mapping within one file
+#[derive(Debug, Clone, PartialEq)]
+struct ResolvedColumns {
+    header_row: usize,
+    date: usize,
+    description: Vec<usize>,
+    amount: Option<usize>,
+    direction: Option<usize>,
+    debit: Option<usize>,
+    credit: Option<usize>,
+    reference: Option<usize>,
+}
+
+fn find_header(row: &[Cell], name: &str) -> Option<usize> {
+    let name = name.trim().to_lowercase();
+    row.iter()
+        .position(|cell| cell.text().map(|t| t.to_lowercase()) == Some(name.clone()))
+}
+
+impl ColumnMapping {
+    pub fn validate(&self) -> Result<(), AppError> {
+        match (&self.amount, &self.debit, &self.credit) {
+            (Some(_), None, None) => Ok(()),
+            (None, Some(_), Some(_)) => {
+                if self.direction.is_some() {
+                    Err(AppError::Validation(
+                        "A direction column can only be used with a single amount column".to_string(),
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            _ => Err(AppError::Validation(
+                "Map either an amount column or both debit and credit columns".to_string(),
+            )),
+        }
+    }
+
+    fn columns(&self) -> impl Iterator<Item = &ColumnRef> {
+        std::iter::once(&self.date)
+            .chain(self.description.iter())
+            .chain(self.amount.iter())
+            .chain(self.direction.iter())
+            .chain(self.debit.iter())
+            .chain(self.credit.iter())
+            .chain(self.reference.iter())
+    }
+
+    /// Finds the header row, the first row containing every named column, and
+    /// the position of each column. Data rows follow the header row; when the
+    /// mapping only uses positions the first row is taken as the header.
+    fn resolve(&self, table: &Table) -> Result<ResolvedColumns, AppError> {
+        let names: Vec<&str> = self
+            .columns()
+            .filter_map(|c| match c {
+                ColumnRef::Name(name) => Some(name.as_str()),
+                ColumnRef::Index(_) => None,
+            })
+            .collect();
+
+        let header_row = table
+            .rows
+            .iter()
+            .position(|row| names.iter().all(|name| find_header(row, name).is_some()))
+            .ok_or_else(|| {
+                AppError::Validation(format!("No header row with columns: {}", names.join(", ")))
+            })?;
+        let header = &table.rows[header_row];
+
+        let position = |column: &ColumnRef| match column {
+            ColumnRef::Index(index) => *index,
+            ColumnRef::Name(name) => find_header(header, name).unwrap_or_default(),
+        };
+
+        Ok(ResolvedColumns {
+            header_row,
+            date: position(&self.date),
+            description: self.description.iter().map(position).collect(),
+            amount: self.amount.as_ref().map(position),
+            direction: self.direction.as_ref().map(position),
+            debit: self.debit.as_ref().map(position),
+            credit: self.credit.as_ref().map(position),
+            reference: self.reference.as_ref().map(position),
+        })
+    }
+}
+
+// ----------------
+// Bank statement layouts
+// ----------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatementLayout {
+    /// Any spreadsheet, read through a user supplied column mapping
+    Custom,
+    Bca,
+    Mandiri,
+    Bri,
+}
+
+impl fmt::Display for StatementLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatementLayout::Custom => write!(f, "custom"),
+            StatementLayout::Bca => write!(f, "bca"),
+            StatementLayout::Mandiri => write!(f, "mandiri"),
+            StatementLayout::Bri => write!(f, "bri"),
+        }
+    }
+}
+
+impl FromStr for StatementLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "custom" => Ok(StatementLayout::Custom),
+            "bca" => Ok(StatementLayout::Bca),
+            "mandiri" => Ok(StatementLayout::Mandiri),
+            "bri" => Ok(StatementLayout::Bri),
+            _ => Err(format!("Unknown statement layout: {}", s)),
+        }
+    }
+}
+
+impl StatementLayout {
+    pub fn banks() -> [StatementLayout; 3] {
+        [StatementLayout::Bca, StatementLayout::Mandiri, StatementLayout::Bri]
+    }
+
+    /// Column mapping of the bank's CSV statement export, `None` for custom files
+    pub fn mapping(&self) -> Option<ColumnMapping> {
+        match self {
+            StatementLayout::Custom => None,
+            // Tanggal Transaksi,Keterangan,Cabang,Jumlah,,Saldo
+            // '02/01,'TRSF E-BANKING CR ...,'0000,150000.00,CR,1150000.00
+            StatementLayout::Bca => Some(ColumnMapping {
+                date: ColumnRef::name("Tanggal Transaksi"),
+                description: vec![ColumnRef::name("Keterangan")],
+                amount: Some(ColumnRef::name("Jumlah")),
+                direction: Some(ColumnRef::Index(4)),
+                debit: None,
+                credit: None,
+                reference: None,
+                date_format: DateFormat::DayMonth,
+                number_format: NumberFormat::International,
+            }),
+            // Account No,Date,Val. Date,Transaction Code,Description,Description,Reference No.,Debit,Credit
+            StatementLayout::Mandiri => Some(ColumnMapping {
+                date: ColumnRef::name("Date"),
+                description: vec![ColumnRef::Index(4), ColumnRef::Index(5)],
+                amount: None,
+                direction: None,
+                debit: Some(ColumnRef::name("Debit")),
+                credit: Some(ColumnRef::name("Credit")),
+                reference: Some(ColumnRef::name("Reference No.")),
+                date_format: DateFormat::DayMonthYear,
+                number_format: NumberFormat::International,
+            }),
+            // TGL_TRAN,DESK_TRAN,NO_REF,MUTASI_DEBET,MUTASI_KREDIT,SALDO_AKHIR_MUTASI
+            StatementLayout::Bri => Some(ColumnMapping {
+                date: ColumnRef::name("TGL_TRAN"),
+                description: vec![ColumnRef::name("DESK_TRAN")],
+                amount: None,
+                direction: None,
+                debit: Some(ColumnRef::name("MUTASI_DEBET")),
+                credit: Some(ColumnRef::name("MUTASI_KREDIT")),
+                reference: Some(ColumnRef::name("NO_REF")),
+                date_format: DateFormat::DayMonthYear,
+                number_format: NumberFormat::Indonesian,
+            }),
+        }
+    }
+
+    /// The bank layout whose header row appears in the file
+    pub fn detect(table: &Table) -> Option<StatementLayout> {
+        Self::banks().into_iter().find(|layout| {
+            layout
+                .mapping()
+                .is_some_and(|mapping| mapping.resolve(table).is_ok())
+        })
+    }
+}
+
+/// Reads the statement period from a preamble line such as
+/// `Periode : 01/01/2024 - 31/01/2024`
+pub fn statement_period(table: &Table) -> Option<StatementPeriod> {
+    for row in table.rows.iter().take(20) {
+        let line = row.iter().filter_map(Cell::text).collect::<Vec<_>>().join(" ");
+        let lower = line.to_lowercase();
+        if !lower.starts_with("periode") && !lower.starts_with("period") {
+            continue;
+        }
+        let Some((_, range)) = line.split_once(':') else {
+            continue;
+        };
+        let Some((start, end)) = range.split_once(" - ").or_else(|| range.split_once("s/d")) else {
+            continue;
+        };
+        let start = parse_date(start, DateFormat::DayMonthYear, None).ok()?;
+        let end = parse_date(end, DateFormat::DayMonthYear, None).ok()?;
+        return Some(StatementPeriod { start, end });
+    }
+    None
+}
+
+/// Reads the closing balance from a summary line such as
+/// `Saldo Akhir : 1,115,000.00`; a trailing `DB` marks an overdrawn balance
+pub fn statement_closing_balance(table: &Table, format: NumberFormat) -> Option<i64> {
+    for row in table.rows.iter().rev() {
+        let texts: Vec<String> = row.iter().filter_map(Cell::text).collect();
+        let Some(position) = texts.iter().position(|t| {
+            let lower = t.to_lowercase();
+            ["saldo akhir", "closing balance", "ending balance"]
+                .iter()
+                .any(|label| lower.starts_with(label))
+        }) else {
+            continue;
+        };
+
+        // The value follows a colon, either in the label cell or in a later cell
+        let inline = texts[position].split_once(':').map(|(_, v)| v.trim().to_string());
+        let value = inline
+            .filter(|v| !v.is_empty())
+            .into_iter()
+            .chain(texts[position + 1..].iter().cloned())
+            .map(|v| v.trim_start_matches(':').trim().to_string())
+            .find(|v| !v.is_empty())?;
+
+        let upper = value.to_uppercase();
+        let (value, sign) = match upper.strip_suffix("DB").or_else(|| upper.strip_suffix("DR")) {
+            Some(rest) => (rest.trim().to_string(), -1),
+            None => (upper.strip_suffix("CR").unwrap_or(&upper).trim().to_string(), 1),
+        };
+        return parse_amount(&value, format).ok().map(|amount| amount * sign);
+    }
+    None
+}
+
+/// Picks the layout and column mapping for a file: an explicit mapping means a
+/// custom layout, otherwise the requested or detected bank layout is used
+pub fn select_mapping(
+    layout: Option<StatementLayout>,
+    mapping: Option<ColumnMapping>,
+    table: &Table,
+) -> Result<(StatementLayout, ColumnMapping), AppError> {
+    let layout = match (layout, mapping.is_some()) {
+        (Some(layout), true) if layout != StatementLayout::Custom => {
+            return Err(AppError::Validation(
+                "Use either a bank layout or a column mapping, not both".to_string(),
+            ))
+        }
+        (Some(layout), _) => layout,
+        (None, true) => StatementLayout::Custom,
+        (None, false) => StatementLayout::detect(table).ok_or_else(|| {
+            AppError::Validation("File layout not recognised, a column mapping is required".to_string())
+        })?,
+    };
+    let mapping = mapping.or_else(|| layout.mapping()).ok_or_else(|| {
+        AppError::Validation("A column mapping is required for custom files".to_string())
+    })?;
+
+    Ok((layout, mapping))
+}
+
+// ----------------
+// Parsed rows
+// ----------------
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParsedTransaction {
+    pub transaction_date: NaiveDate,
+    pub transaction_type: TransactionType,
+    /// Positive, in the smallest currency unit; the type carries the direction
+    pub amount: i64,
+    pub description: String,
+    pub reference_number: Option<String>,
+}
+
+impl ParsedTransaction {
+    fn new(
+        transaction_date: NaiveDate,
+        signed_amount: i64,
+        description: String,
+        reference_number: Option<String>,
+    ) -> Self {
+        Self {
+            transaction_date,
+            transaction_type: if signed_amount > 0 {
+                TransactionType::Income
+            } else {
+                TransactionType::Expense
+            },
+            amount: signed_amount.abs(),
+            description,
+            reference_number,
+        }
+    }
+
+    pub fn signed_amount(&self) -> i64 {
+        match self.transaction_type {
+            TransactionType::Income => self.amount,
+            _ => -self.amount,
+        }
+    }
+
+    /// Deduplication key: date, signed amount and the bank reference, with the
+    /// description standing in for rows that have no reference
+    pub fn import_key(&self) -> String {
+        let reference = self
+            .reference_number
+            .as_deref()
+            .unwrap_or(&self.description)
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_uppercase();
+        format!("{}|{}|{}", self.transaction_date, self.signed_amount(), reference)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportRowStatus {
+    Valid,
+    Invalid,
+    Duplicate,
+    Imported,
+}
+
+/// One line of the validation report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportRow {
+    /// 1-based line number in the uploaded file
+    pub row_number: usize,
+    pub status: ImportRowStatus,
+    pub transaction: Option<ParsedTransaction>,
+    pub messages: Vec<String>,
+}
+
+fn signed_amount(cells: &[Cell], columns: &ResolvedColumns, format: NumberFormat) -> Result<i64, String> {
+    let value = |column: Option<usize>| -> Result<Option<i64>, String> {
+        match column {
+            Some(index) => cell_amount(cell_at(cells, index), format),
+            None => Ok(None),
+        }
+    };
+
+    let amount = if columns.amount.is_some() {
+        let amount = value(columns.amount)?.ok_or("Missing amount")?;
+        match columns.direction {
+            Some(index) => {
+                let indicator = cell_at(cells, index)
+                    .text()
+                    .ok_or("Missing debit/credit indicator")?;
+                let sign = direction_sign(&indicator)
+                    .ok_or_else(|| format!("Unknown debit/credit indicator '{}'", indicator))?;
+                amount.abs() * sign
+            }
+            None => amount,
+        }
+    } else {
+        let debit = value(columns.debit)?.filter(|v| *v != 0);
+        let credit = value(columns.credit)?.filter(|v| *v != 0);
+        match (debit, credit) {
+            (Some(_), Some(_)) => return Err("Both debit and credit are filled".to_string()),
+            (Some(debit), None) => -debit.abs(),
+            (None, Some(credit)) => credit.abs(),
+            (None, None) => return Err("Missing amount".to_string()),
+        }
+    };
+
+    if amount == 0 {
+        return Err("Amount must not be zero".to_string());
+    }
+    Ok(amount)
+}
+
+fn parse_row(
+    row_number: usize,
+    cells: &[Cell],
+    columns: &ResolvedColumns,
+    mapping: &ColumnMapping,
+    period: Option<&StatementPeriod>,
+) -> ImportRow {
+    let mut messages = Vec::new();
+
+    let date = match cell_date(cell_at(cells, columns.date), mapping.date_format, period) {
+        Ok(Some(date)) => Some(date),
+        Ok(None) => {
+            messages.push("Missing date".to_string());
+            None
+        }
+        Err(e) => {
+            messages.push(e);
+            None
+        }
+    };
+    let amount = signed_amount(cells, columns, mapping.number_format)
+        .map_err(|e| messages.push(e))
+        .ok();
+
+    let reference = columns.reference.and_then(|index| cell_at(cells, index).text());
+    let description = columns
+        .description
+        .iter()
+        .filter_map(|&index| cell_at(cells, index).text())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let description = if description.is_empty() {
+        reference.clone().unwrap_or_else(|| "Imported transaction".to_string())
+    } else {
+        description
+    };
+
+    let transaction = match (date, amount) {
+        (Some(date), Some(amount)) => Some(ParsedTransaction::new(date, amount, description, reference)),
+        _ => None,
+    };
+
+    ImportRow {
+        row_number,
+        status: if transaction.is_some() {
+            ImportRowStatus::Valid
+        } else {
+            ImportRowStatus::Invalid
+        },
+        transaction,
+        messages,
+    }
+}
+
+/// Parses every data row after the header row. Lines with neither a date nor
+/// an amount (blank lines, opening balances, totals) are not transactions and
+/// are left out of the report.
+pub fn parse_rows(
+    table: &Table,
+    mapping: &ColumnMapping,
+    period: Option<&StatementPeriod>,
+) -> Result<Vec<ImportRow>, AppError> {
+    mapping.validate()?;
+    let columns = mapping.resolve(table)?;
+
+    let amount_columns: Vec<usize> = [columns.amount, columns.debit, columns.credit]
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let mut rows = Vec::new();
+    for (index, cells) in table.rows.iter().enumerate().skip(columns.header_row + 1) {
+        let has_amount = amount_columns.iter().any(|&i| !cell_at(cells, i).is_empty());
+        let has_date = matches!(
+            cell_date(cell_at(cells, columns.date), mapping.date_format, period),
+            Ok(Some(_))
+        );
+        if !has_amount && !has_date {
+            continue;
+        }
+        if rows.len() == MAX_IMPORT_ROWS {
+            return Err(AppError::Validation(format!(
+                "Files are limited to {} transactions",
+                MAX_IMPORT_ROWS
+            )));
+        }
+        rows.push(parse_row(index + 1, cells, &columns, mapping, period));
+    }
+
+    Ok(rows)
+}
+
+// ----------------
+// Entities
+// ----------------
+
+/// A column mapping saved under a name for reuse with later uploads
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportMapping {
+    pub id: Uuid,
+    pub company_id: Uuid,
+    pub name: String,
+    pub mapping: ColumnMapping,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ImportMapping {
+    pub fn new(
+        company_id: Uuid,
+        name: String,
+        mapping: ColumnMapping,
+        created_by: Uuid,
+    ) -> Result<Self, AppError> {
+        let name = name.trim().to_string();
+        if name.is_empty() || name.len() > 100 {
+            return Err(AppError::Validation(
+                "Mapping name must be between 1 and 100 characters".to_string(),
+            ));
+        }
+        mapping.validate()?;
+
+        let now = Utc::now();
+        Ok(Self {
+            id: Uuid::new_v4(),
+            company_id,
+            name,
+            mapping,
+            created_by,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportBatchStatus {
+    Previewed,
+    Committed,
+}
+
+impl fmt::Display for ImportBatchStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportBatchStatus::Previewed => write!(f, "previewed"),
+            ImportBatchStatus::Committed => write!(f, "committed"),
+        }
+    }
+}
+
+impl FromStr for ImportBatchStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "previewed" => Ok(ImportBatchStatus::Previewed),
+            "committed" => Ok(ImportBatchStatus::Committed),
+            _ => Err(format!("Invalid import batch status: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub total_rows: usize,
+    pub valid_rows: usize,
+    pub invalid_rows: usize,
+    pub duplicate_rows: usize,
+    pub imported_rows: usize,
+}
+
+impl ImportSummary {
+    pub fn from_rows(rows: &[ImportRow]) -> Self {
+        let count = |status| rows.iter().filter(|r| r.status == status).count();
+        Self {
+            total_rows: rows.len(),
+            valid_rows: count(ImportRowStatus::Valid),
+            invalid_rows: count(ImportRowStatus::Invalid),
+            duplicate_rows: count(ImportRowStatus::Duplicate),
+            imported_rows: count(ImportRowStatus::Imported),
+        }
+    }
+}
+
+/// One uploaded file: its validation report and, once committed, the outcome
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportBatch {
+    pub id: Uuid,
+    pub company_id: Uuid,
+    pub account_id: Uuid,
+    pub file_name: String,
+    pub layout: StatementLayout,
+    pub mapping: ColumnMapping,
+    pub status: ImportBatchStatus,
+    pub summary: ImportSummary,
+    pub rows: Vec<ImportRow>,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub committed_by: Option<Uuid>,
+    pub committed_at: Option<DateTime<Utc>>,
+}
+
+/// A transaction to book together with its deduplication key
+#[derive(Debug, Clone)]
+pub struct ImportedTransaction {
+    pub import_key: String,
+    pub transaction: Transaction,
+}
+
+impl ImportedTransaction {
+    /// Effect on the account balance
+    pub fn signed_amount(&self) -> i64 {
+        self.transaction.signed_amount()
+    }
+}
+
+impl ImportBatch {
+    pub fn new(
+        company_id: Uuid,
+        account_id: Uuid,
+        file_name: String,
+        layout: StatementLayout,
+        mapping: ColumnMapping,
+        rows: Vec<ImportRow>,
+        created_by: Uuid,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            company_id,
+            account_id,
+            file_name,
+            layout,
+            mapping,
+            status: ImportBatchStatus::Previewed,
+            summary: ImportSummary::from_rows(&rows),
+            rows,
+            created_by,
+            created_at: Utc::now(),
+            committed_by: None,
+            committed_at: None,
+        }
+    }
+
+    /// Import keys of the rows that would be booked
+    pub fn import_keys(&self) -> Vec<String> {
+        self.valid_transactions().map(|(_, tx)| tx.import_key()).collect()
+    }
+
+    fn valid_transactions(&self) -> impl Iterator<Item = (&ImportRow, &ParsedTransaction)> {
+        self.rows
+            .iter()
+            .filter(|row| row.status == ImportRowStatus::Valid)
+            .filter_map(|row| row.transaction.as_ref().map(|tx| (row, tx)))
+    }
+
+    /// Flags rows already booked in the account and rows repeating an earlier
+    /// row of the same file
+    pub fn mark_duplicates(&mut self, existing_keys: &HashSet<String>) {
+        let mut first_rows: HashMap<String, usize> = HashMap::new();
+
+        for row in self.rows.iter_mut().filter(|r| r.status == ImportRowStatus::Valid) {
+            let Some(key) = row.transaction.as_ref().map(ParsedTransaction::import_key) else {
+                continue;
+            };
+            if existing_keys.contains(&key) {
+                row.status = ImportRowStatus::Duplicate;
+                row.messages.push("Already imported into this account".to_string());
+            } else if let Some(first) = first_rows.get(&key) {
+                row.status = ImportRowStatus::Duplicate;
+                row.messages.push(format!("Duplicate of row {}", first));
+            } else {
+                first_rows.insert(key, row.row_number);
+            }
+        }
+
+        self.summary = ImportSummary::from_rows(&self.rows);
+    }
+
+    /// Completed transactions for the valid rows
+    pub fn transactions(&self, currency: &Currency) -> Vec<ImportedTransaction> {
+        self.valid_transactions()
+            .map(|(row, parsed)| {
+                let date = parsed.transaction_date.and_time(NaiveTime::MIN).and_utc();
+                let mut transaction = Transaction::new(
+                    self.company_id,
+                    date,
+                    parsed.transaction_type.clone(),
+                    Money::new(parsed.amount, currency.clone()),
+                    parsed.description.clone(),
+                    self.account_id,
+                    self.created_by,
+                );
+                transaction.reference_number = parsed.reference_number.clone();
+                transaction.status = TransactionStatus::Completed;
+                transaction.tags = vec!["import".to_string()];
+                transaction.metadata = Some(HashMap::from([
+                    ("import_batch_id".to_string(), serde_json::json!(self.id)),
+                    ("import_row".to_string(), serde_json::json!(row.row_number)),
+                ]));
+
+                ImportedTransaction {
+                    import_key: parsed.import_key(),
+                    transaction,
+                }
+            })
+            .collect()
+    }
+
+    /// Records a commit: rows whose key was inserted are imported, the others
+    /// were booked by another import in the meantime
+    pub fn mark_committed(&mut self, inserted_keys: &HashSet<String>, committed_by: Uuid) {
+        for row in self.rows.iter_mut().filter(|r| r.status == ImportRowStatus::Valid) {
+            let inserted = row
+                .transaction
+                .as_ref()
+                .is_some_and(|tx| inserted_keys.contains(&tx.import_key()));
+            if inserted {
+                row.status = ImportRowStatus::Imported;
+            } else {
+                row.status = ImportRowStatus::Duplicate;
+                row.messages.push("Already imported into this account".to_string());
+            }
+        }
+
+        self.status = ImportBatchStatus::Committed;
+        self.summary = ImportSummary::from_rows(&self.rows);
+        self.committed_by = Some(committed_by);
+        self.committed_at = Some(Utc::now());
+    }
+}
+
+// ----------------
+// Repository Interface
+// ----------------
+
+#[async_trait]
+pub trait ImportRepository: Send + Sync {
+    /// Creates the mapping or replaces the company's mapping with the same name
+    async fn save_mapping(&self, mapping: &ImportMapping) -> Result<ImportMapping, AppError>;
+    async fn find_mapping(&self, company_id: Uuid, id: Uuid) -> Result<Option<ImportMapping>, AppError>;
+    async fn list_mappings(&self, company_id: Uuid) -> Result<Vec<ImportMapping>, AppError>;
+    async fn delete_mapping(&self, company_id: Uuid, id: Uuid) -> Result<(), AppError>;
+
+    async fn save_batch(&self, batch: &ImportBatch) -> Result<(), AppError>;
+    async fn find_batch(&self, company_id: Uuid, id: Uuid) -> Result<Option<ImportBatch>, AppError>;
+
+    /// The subset of `keys` already booked in the account
+    async fn existing_import_keys(
+        &self,
+        account_id: Uuid,
+        keys: &[String],
+    ) -> Result<HashSet<String>, AppError>;
+
+    /// Atomically books the transactions, skipping keys already present in the
+    /// account, adds their net amount to the account balance and stores the
+    /// batch as committed. A batch that is already committed is returned as is.
+    async fn commit_batch(
+        &self,
+        batch: &ImportBatch,
+        transactions: &[ImportedTransaction],
+        committed_by: Uuid,
+    ) -> Result<ImportBatch, AppError>;
+}
+
+// ----------------
+// Domain Services
+// ----------------
+
+/// What to do with an uploaded file. Without a layout or mapping the bank
+/// layout is detected from the header row.
+#[derive(Debug, Clone)]
+pub struct ImportRequest {
+    pub account_id: Uuid,
+    pub file_name: String,
+    pub layout: Option<StatementLayout>,
+    pub mapping_id: Option<Uuid>,
+    pub mapping: Option<ColumnMapping>,
+}
+
+pub struct TransactionImportService {
+    import_repository: Arc<dyn ImportRepository>,
+    account_repository: Arc<dyn FinancialAccountRepository>,
+}
+
+impl TransactionImportService {
+    pub fn new(
+        import_repository: Arc<dyn ImportRepository>,
+        account_repository: Arc<dyn FinancialAccountRepository>,
+    ) -> Self {
+        Self {
+            import_repository,
+            account_repository,
+        }
+    }
+
+    pub async fn save_mapping(
+        &self,
+        company_id: Uuid,
+        user_id: Uuid,
+        name: String,
+        mapping: ColumnMapping,
+    ) -> Result<ImportMapping, AppError> {
+        let mapping = ImportMapping::new(company_id, name, mapping, user_id)?;
+        self.import_repository.save_mapping(&mapping).await
+    }
+
+    pub async fn list_mappings(&self, company_id: Uuid) -> Result<Vec<ImportMapping>, AppError> {
+        self.import_repository.list_mappings(company_id).await
+    }
+
+    pub async fn delete_mapping(&self, company_id: Uuid, id: Uuid) -> Result<(), AppError> {
+        self.import_repository.delete_mapping(company_id, id).await
+    }
+
+    pub async fn find_batch(&self, company_id: Uuid, id: Uuid) -> Result<ImportBatch, AppError> {
+        self.import_repository
+            .find_batch(company_id, id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Import batch not found".to_string()))
+    }
+
+    /// Parses the file and stores the validation report as a previewed batch
+    pub async fn preview(
+        &self,
+        company_id: Uuid,
+        user_id: Uuid,
+        request: ImportRequest,
+        table: &Table,
+    ) -> Result<ImportBatch, AppError> {
+        let account = self.active_account(company_id, request.account_id).await?;
+
+        let saved = match request.mapping_id {
+            Some(id) => Some(
+                self.import_repository
+                    .find_mapping(company_id, id)
+                    .await?
+                    .ok_or_else(|| AppError::NotFound("Import mapping not found".to_string()))?
+                    .mapping,
+            ),
+            None => None,
+        };
+
+        let (layout, mapping) = select_mapping(request.layout, request.mapping.or(saved), table)?;
+
+        let rows = parse_rows(table, &mapping, statement_period(table).as_ref())?;
+        if rows.is_empty() {
+            return Err(AppError::Validation("No transactions found in the file".to_string()));
+        }
+
+        let mut batch = ImportBatch::new(
+            company_id,
+            account.id,
+            request.file_name,
+            layout,
+            mapping,
+            rows,
+            user_id,
+        );
+        let existing = self
+            .import_repository
+            .existing_import_keys(account.id, &batch.import_keys())
+            .await?;
+        batch.mark_duplicates(&existing);
+
+        self.import_repository.save_batch(&batch).await?;
+        Ok(batch)
+    }
+
+    /// Books the valid rows of a previewed batch. Safe to call again: a
+    /// committed batch is returned unchanged.
+    pub async fn commit(
+        &self,
+        company_id: Uuid,
+        user_id: Uuid,
+        batch_id: Uuid,
+    ) -> Result<ImportBatch, AppError> {
+        let batch = self.find_batch(company_id, batch_id).await?;
+        if batch.status == ImportBatchStatus::Committed {
+            return Ok(batch);
+        }
+
+        let account = self.active_account(company_id, batch.account_id).await?;
+        let transactions = batch.transactions(&account.currency);
+
+        self.import_repository
+            .commit_batch(&batch, &transactions, user_id)
+            .await
+    }
+
+    async fn active_account(
+        &self,
+        company_id: Uuid,
+        account_id: Uuid,
+    ) -> Result<FinancialAccount, AppError> {
+        let account = self
+            .account_repository
+            .find_by_id(account_id)
+            .await?
+            .filter(|account| account.company_id == company_id)
+            .ok_or_else(|| AppError::NotFound("Account not found".to_string()))?;
+
+        if !account.is_active {
+            return Err(AppError::Validation("Account is not active".to_string()));
+        }
+        Ok(account)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(rows: &[&[&str]]) -> Table {
+        Table::new(
+            rows.iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|s| if s.is_empty() { Cell::Empty } else { Cell::Text(s.to_string()) })
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_parse_amount_formats() {
+        use NumberFormat::*;
+        assert_eq!(parse_amount("1.234.567,89", Indonesian), Ok(123456789));
+        assert_eq!(parse_amount("Rp 1.500.000", Indonesian), Ok(150000000));
+        assert_eq!(parse_amount("-250,5", Indonesian), Ok(-25050));
+        assert_eq!(parse_amount("(1.000,00)", Indonesian), Ok(-100000));
+        assert_eq!(parse_amount("1,234,567.89", International), Ok(123456789));
+        assert_eq!(parse_amount("150000.00", International), Ok(15000000));
+
+        // A number in the other convention is rejected rather than misread
+        assert!(parse_amount("1,234,567.89", Indonesian).is_err());
+        assert!(parse_amount("1234.50", Indonesian).is_err());
+        assert!(parse_amount("12,345", Indonesian).is_err());
+        assert!(parse_amount("abc", Indonesian).is_err());
+        assert!(parse_amount("", Indonesian).is_err());
+    }
+
+    #[test]
+    fn test_parse_date_formats() {
+        use DateFormat::*;
+        assert_eq!(parse_date("05/01/2024", DayMonthYear, None), Ok(date(2024, 1, 5)));
+        assert_eq!(parse_date("05-01-24 13:45:00", DayMonthYear, None), Ok(date(2024, 1, 5)));
+        assert_eq!(parse_date("17 Agustus 2024", DayMonthYear, None), Ok(date(2024, 8, 17)));
+        assert_eq!(parse_date("2024-03-31", YearMonthDay, None), Ok(date(2024, 3, 31)));
+        assert_eq!(parse_date("03/31/2024", MonthDayYear, None), Ok(date(2024, 3, 31)));
+        assert!(parse_date("31/02/2024", DayMonthYear, None).is_err());
+        assert!(parse_date("PEND", DayMonth, None).unwrap_err().contains("pending"));
+
+        // Statements spanning the new year resolve dd/mm inside the period
+        let period = StatementPeriod { start: date(2023, 12, 15), end: date(2024, 1, 14) };
+        assert_eq!(parse_date("28/12", DayMonth, Some(&period)), Ok(date(2023, 12, 28)));
+        assert_eq!(parse_date("03/01", DayMonth, Some(&period)), Ok(date(2024, 1, 3)));
+        assert!(parse_date("03/01", DayMonth, None).is_err());
+    }
+
+    #[test]
+    fn test_bca_statement_is_detected_and_parsed() {
+        let statement = table(&[
+            &["No. rekening : ", "'1234567890"],
+            &["Periode : ", "01/01/2024 - 31/01/2024"],
+            &[],
+            &["Tanggal Transaksi", "Keterangan", "Cabang", "Jumlah", "", "Saldo"],
+            &["'02/01", "'TRSF E-BANKING CR 0201/FTSCY/WS95031", "'0000", "150,000.00", "CR", "1,150,000.00"],
+            &["'03/01", "'BIAYA ADM", "'0000", "10,000.00", "DB", "1,140,000.00"],
+            &["'PEND", "'KARTU DEBIT INDOMARET", "'0000", "25,000.00", "DB", "1,115,000.00"],
+            &[],
+            &["Saldo Awal : ", "1,000,000.00"],
+            &["Saldo Akhir : ", "1,115,000.00"],
+        ]);
+
+        assert_eq!(StatementLayout::detect(&statement), Some(StatementLayout::Bca));
+        let mapping = StatementLayout::Bca.mapping().unwrap();
+        let rows = parse_rows(&statement, &mapping, statement_period(&statement).as_ref()).unwrap();
+
+        assert_eq!(rows.len(), 3);
+        let income = rows[0].transaction.as_ref().unwrap();
+        assert_eq!(rows[0].row_number, 5);
+        assert_eq!(income.transaction_date, date(2024, 1, 2));
+        assert_eq!(income.transaction_type, TransactionType::Income);
+        assert_eq!(income.amount, 15000000);
+        assert_eq!(rows[1].transaction.as_ref().unwrap().signed_amount(), -1000000);
+        assert_eq!(rows[2].status, ImportRowStatus::Invalid);
+        assert_eq!(
+            statement_closing_balance(&statement, mapping.number_format),
+            Some(111500000)
+        );
+        assert_eq!(
+            statement_closing_balance(&table(&[&["Saldo Akhir: 2.000,00 DB"]]), NumberFormat::Indonesian),
+            Some(-200000)
+        );
+    }
+
+    #[test]
+    fn test_mandiri_and_bri_layouts() {
+        let mandiri = table(&[
+            &["Account No", "Date", "Val. Date", "Transaction Code", "Description", "Description", "Reference No.", "Debit", "Credit"],
+            &["1230001234567", "05/02/2024", "05/02/2024", "8888", "TRANSFER DARI", "PT MAJU JAYA", "REF001", "0.00", "2,500,000.00"],
+            &["1230001234567", "06/02/2024", "06/02/2024", "7777", "BAYAR LISTRIK", "", "REF002", "350,000.00", "0.00"],
+        ]);
+        assert_eq!(StatementLayout::detect(&mandiri), Some(StatementLayout::Mandiri));
+        let rows = parse_rows(&mandiri, &StatementLayout::Mandiri.mapping().unwrap(), None).unwrap();
+        let first = rows[0].transaction.as_ref().unwrap();
+        assert_eq!(first.description, "TRANSFER DARI PT MAJU JAYA");
+        assert_eq!(first.reference_number.as_deref(), Some("REF001"));
+        assert_eq!(first.amount, 250000000);
+        assert_eq!(rows[1].transaction.as_ref().unwrap().transaction_type, TransactionType::Expense);
+
+        let bri = table(&[
+            &["TGL_TRAN", "DESK_TRAN", "NO_REF", "MUTASI_DEBET", "MUTASI_KREDIT", "SALDO_AKHIR_MUTASI"],
+            &["07/03/24 09:15:00", "SETORAN TUNAI", "BRI123", "0,00", "1.234.567,89", "5.000.000,00"],
+            &["08/03/24 10:00:00", "SALAH INPUT", "BRI124", "100,00", "200,00", "4.999.900,00"],
+        ]);
+        assert_eq!(StatementLayout::detect(&bri), Some(StatementLayout::Bri));
+        let rows = parse_rows(&bri, &StatementLayout::Bri.mapping().unwrap(), None).unwrap();
+        let first = rows[0].transaction.as_ref().unwrap();
+        assert_eq!(first.transaction_date, date(2024, 3, 7));
+        assert_eq!(first.amount, 123456789);
+        assert_eq!(rows[1].status, ImportRowStatus::Invalid);
+        assert_eq!(rows[1].messages, vec!["Both debit and credit are filled".to_string()]);
+    }
+
+    #[test]
+    fn test_custom_mapping_reports_row_errors() {
+        let mapping: ColumnMapping = serde_json::from_value(serde_json::json!({
+            "date": "Tanggal",
+            "description": ["Uraian"],
+            "amount": "Nominal",
+            "reference": 3,
+            "date_format": "dd/mm/yyyy",
+            "number_format": "indonesian"
+        }))
+        .unwrap();
+        let sheet = table(&[
+            &["Tanggal", "Uraian", "Nominal", "Ref"],
+            &["01/04/2024", "Penjualan", "1.000.000", "INV-1"],
+            &["02/04/2024", "Sewa toko", "-2.500.000,50", ""],
+            &["2024/04/03", "Format salah", "abc", ""],
+            &["", "", "", ""],
+        ]);
+
+        let rows = parse_rows(&sheet, &mapping, None).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1].transaction.as_ref().unwrap().signed_amount(), -250000050);
+        assert_eq!(rows[2].status, ImportRowStatus::Invalid);
+        assert_eq!(rows[2].messages.len(), 2);
+
+        let missing = ColumnMapping { amount: None, ..mapping };
+        assert!(parse_rows(&sheet, &missing, None).is_err());
+    }
+
+    #[test]
+    fn test_duplicates_and_commit_outcome() {
+        let mapping = ColumnMapping {
+            date: ColumnRef::Index(0),
+            description: vec![ColumnRef::Index(1)],
+            amount: Some(ColumnRef::Index(2)),
+            direction: None,
+            debit: None,
+            credit: None,
+            reference: Some(ColumnRef::Index(3)),
+            date_format: DateFormat::DayMonthYear,
+            number_format: NumberFormat::Indonesian,
+        };
+        let sheet = table(&[
+            &["date", "description", "amount", "reference"],
+            &["01/05/2024", "Penjualan", "100.000", "A1"],
+            &["01/05/2024", "Penjualan", "100.000", "a1"],
+            &["02/05/2024", "Pembelian", "-50.000", "A2"],
+            &["03/05/2024", "Penjualan", "75.000", "A3"],
+        ]);
+        let rows = parse_rows(&sheet, &mapping, None).unwrap();
+        let mut batch = ImportBatch::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            "mutasi.csv".to_string(),
+            StatementLayout::Custom,
+            mapping,
+            rows,
+            Uuid::new_v4(),
+        );
+
+        let existing = HashSet::from(["2024-05-02|-5000000|A2".to_string()]);
+        batch.mark_duplicates(&existing);
+        assert_eq!(batch.rows[1].messages, vec!["Duplicate of row 2".to_string()]);
+        assert_eq!(batch.rows[2].status, ImportRowStatus::Duplicate);
+        assert_eq!(batch.summary.valid_rows, 2);
+        assert_eq!(batch.summary.duplicate_rows, 2);
+
+        let transactions = batch.transactions(&Currency::IDR);
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].transaction.status, TransactionStatus::Completed);
+
+        // The row for A3 was booked by another import before this commit
+        let inserted = HashSet::from([transactions[0].import_key.clone()]);
+        batch.mark_committed(&inserted, Uuid::new_v4());
+        assert_eq!(batch.status, ImportBatchStatus::Committed);
+        assert_eq!(batch.summary.imported_rows, 1);
+        assert_eq!(batch.summary.duplicate_rows, 3);
+    }
+}
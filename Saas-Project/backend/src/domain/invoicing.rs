@@ -0,0 +1,1025 @@
+// Customer invoicing - what UMKM bill their own customers
+// A company keeps its customers as contacts and bills them with sales
+// invoices (faktur): line items with optional per-line discounts, a discount
+// on the whole invoice and PPN on what is left. Invoices are drafted freely
+// and numbered when they are sent, from a sequence per company and year, so
+// issued numbers have no gaps. Payments received are allocated to one or more
+// of the contact's invoices and booked as one income transaction into the
+// cash or bank account the money went to. What is still owed is the
+// company's receivables, reported by age past the due date.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::domain::analytics::reporting_day_start;
+use crate::domain::finance::{Transaction, TransactionStatus, TransactionType};
+use crate::domain::value_objects::{Email, Money, PhoneNumber, NPWP};
+use crate::shared::errors::AppResult;
+
+/// Basis points in a whole
+const WHOLE_BPS: i64 = 10_000;
+const MAX_LINES: usize = 100;
+const MAX_QUANTITY: i64 = 1_000_000;
+/// Rp 100 miliar per unit, in hundredths
+const MAX_UNIT_PRICE: i64 = 10_000_000_000_000;
+
+/// Number of the `sequence`th invoice a company issued in `year`
+pub fn invoice_number(year: i32, sequence: i64) -> String {
+    format!("INV/{}/{:05}", year, sequence)
+}
+
+/// A customer the company bills
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Contact {
+    pub id: Uuid,
+    pub company_id: Uuid,
+    pub name: String,
+    /// Where invoices are emailed
+    pub email: Option<String>,
+    pub phone: Option<PhoneNumber>,
+    pub npwp: Option<NPWP>,
+    pub address: Option<String>,
+    pub notes: Option<String>,
+    pub version: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A contact as entered
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ContactInput {
+    pub name: String,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub npwp: Option<String>,
+    pub address: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// `None` for a missing or blank value
+fn present(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// A contact's fields once checked
+struct ValidContact {
+    name: String,
+    email: Option<String>,
+    phone: Option<PhoneNumber>,
+    npwp: Option<NPWP>,
+    address: Option<String>,
+    notes: Option<String>,
+}
+
+impl ContactInput {
+    /// Trimmed, with email, phone and NPWP checked and normalized
+    fn validated(self) -> Result<ValidContact, String> {
+        let name = self.name.trim().to_string();
+        if name.is_empty() || name.chars().count() > 200 {
+            return Err("A contact needs a name of at most 200 characters".to_string());
+        }
+        Ok(ValidContact {
+            name,
+            email: present(self.email)
+                .map(|email| Email::new(&email).map(|e| e.as_str().to_string()))
+                .transpose()?,
+            phone: present(self.phone)
+                .map(|phone| PhoneNumber::new(&phone))
+                .transpose()?,
+            npwp: present(self.npwp).map(NPWP::new).transpose()?,
+            address: present(self.address),
+            notes: present(self.notes),
+        })
+    }
+}
+
+impl Contact {
+    pub fn new(company_id: Uuid, input: ContactInput, now: DateTime<Utc>) -> Result<Self, String> {
+        let input = input.validated()?;
+        Ok(Self {
+            id: Uuid::new_v4(),
+            company_id,
+            name: input.name,
+            email: input.email,
+            phone: input.phone,
+            npwp: input.npwp,
+            address: input.address,
+            notes: input.notes,
+            version: 1,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    pub fn update(&mut self, input: ContactInput, now: DateTime<Utc>) -> Result<(), String> {
+        let input = input.validated()?;
+        self.name = input.name;
+        self.email = input.email;
+        self.phone = input.phone;
+        self.npwp = input.npwp;
+        self.address = input.address;
+        self.notes = input.notes;
+        self.updated_at = now;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SalesInvoiceStatus {
+    /// Editable and unnumbered
+    Draft,
+    /// Numbered and owed
+    Sent,
+    PartiallyPaid,
+    Paid,
+    /// Past its due date with something still owed
+    Overdue,
+    /// Cancelled; keeps its number so the sequence has no gaps
+    Void,
+}
+
+impl SalesInvoiceStatus {
+    /// Issued and not yet settled or voided
+    pub fn is_open(self) -> bool {
+        matches!(
+            self,
+            SalesInvoiceStatus::Sent
+                | SalesInvoiceStatus::PartiallyPaid
+                | SalesInvoiceStatus::Overdue
+        )
+    }
+}
+
+impl fmt::Display for SalesInvoiceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SalesInvoiceStatus::Draft => write!(f, "draft"),
+            SalesInvoiceStatus::Sent => write!(f, "sent"),
+            SalesInvoiceStatus::PartiallyPaid => write!(f, "partially_paid"),
+            SalesInvoiceStatus::Paid => write!(f, "paid"),
+            SalesInvoiceStatus::Overdue => write!(f, "overdue"),
+            SalesInvoiceStatus::Void => write!(f, "void"),
+        }
+    }
+}
+
+impl FromStr for SalesInvoiceStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "draft" => Ok(SalesInvoiceStatus::Draft),
+            "sent" => Ok(SalesInvoiceStatus::Sent),
+            "partially_paid" => Ok(SalesInvoiceStatus::PartiallyPaid),
+            "paid" => Ok(SalesInvoiceStatus::Paid),
+            "overdue" => Ok(SalesInvoiceStatus::Overdue),
+            "void" => Ok(SalesInvoiceStatus::Void),
+            _ => Err(format!("Invalid invoice status: {}", s)),
+        }
+    }
+}
+
+/// One line of an invoice
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SalesInvoiceLine {
+    pub description: String,
+    pub quantity: i64,
+    pub unit_price: Money,
+    /// Discount on this line, in basis points
+    pub discount_bps: i64,
+    /// Quantity times unit price, less the discount
+    pub amount: Money,
+}
+
+/// A line as entered; amounts in hundredths of a rupiah
+#[derive(Debug, Clone, Deserialize)]
+pub struct LineInput {
+    pub description: String,
+    pub quantity: i64,
+    pub unit_price: i64,
+    #[serde(default)]
+    pub discount_bps: i64,
+}
+
+impl SalesInvoiceLine {
+    fn new(input: LineInput) -> Result<Self, String> {
+        let description = input.description.trim().to_string();
+        if description.is_empty() || description.chars().count() > 500 {
+            return Err("Every line needs a description of at most 500 characters".to_string());
+        }
+        if !(1..=MAX_QUANTITY).contains(&input.quantity) {
+            return Err(format!("Quantities run from 1 to {}", MAX_QUANTITY));
+        }
+        if !(0..=MAX_UNIT_PRICE).contains(&input.unit_price) {
+            return Err("Unit prices cannot be negative or above Rp 100 miliar".to_string());
+        }
+        if !(0..=WHOLE_BPS).contains(&input.discount_bps) {
+            return Err("Line discounts run from 0 to 10000 basis points".to_string());
+        }
+        let gross = input.quantity as i128 * input.unit_price as i128;
+        let amount = gross - gross * input.discount_bps as i128 / WHOLE_BPS as i128;
+        Ok(Self {
+            description,
+            quantity: input.quantity,
+            unit_price: Money::idr(input.unit_price),
+            discount_bps: input.discount_bps,
+            amount: Money::idr(
+                i64::try_from(amount).map_err(|_| "The line amount is too large".to_string())?,
+            ),
+        })
+    }
+}
+
+/// Defaults applied to invoices that do not set their own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvoicingPolicy {
+    /// PPN in basis points, 1100 for 11%
+    pub ppn_rate_bps: i64,
+    /// Days from issue to due date
+    pub payment_terms_days: i64,
+}
+
+/// An invoice as drafted; amounts in hundredths of a rupiah
+#[derive(Debug, Clone, Deserialize)]
+pub struct InvoiceDraft {
+    pub contact_id: Uuid,
+    pub lines: Vec<LineInput>,
+    /// Taken off the subtotal before PPN
+    #[serde(default)]
+    pub discount: i64,
+    /// The company's default when not given; 0 for sellers that are not PKP
+    pub ppn_rate_bps: Option<i64>,
+    /// Set when the invoice is sent unless given
+    pub issue_date: Option<NaiveDate>,
+    pub due_date: Option<NaiveDate>,
+    pub notes: Option<String>,
+}
+
+/// A sales invoice (faktur)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SalesInvoice {
+    pub id: Uuid,
+    pub company_id: Uuid,
+    pub contact_id: Uuid,
+    /// Assigned when the invoice is sent, e.g. `INV/2026/00001`
+    pub number: Option<String>,
+    pub status: SalesInvoiceStatus,
+    pub issue_date: Option<NaiveDate>,
+    pub due_date: Option<NaiveDate>,
+    pub lines: Vec<SalesInvoiceLine>,
+    /// Sum of the line amounts
+    pub subtotal: Money,
+    pub discount: Money,
+    pub ppn_rate_bps: i64,
+    /// On the discounted subtotal, rounded down to whole rupiah
+    pub ppn: Money,
+    pub total: Money,
+    pub amount_paid: Money,
+    pub balance_due: Money,
+    pub notes: Option<String>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub emailed_at: Option<DateTime<Utc>>,
+    pub voided_at: Option<DateTime<Utc>>,
+    pub created_by: Uuid,
+    pub version: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SalesInvoice {
+    pub fn draft(
+        company_id: Uuid,
+        created_by: Uuid,
+        draft: InvoiceDraft,
+        default_ppn_rate_bps: i64,
+        now: DateTime<Utc>,
+    ) -> Result<Self, String> {
+        let mut invoice = Self {
+            id: Uuid::new_v4(),
+            company_id,
+            contact_id: draft.contact_id,
+            number: None,
+            status: SalesInvoiceStatus::Draft,
+            issue_date: None,
+            due_date: None,
+            lines: Vec::new(),
+            subtotal: Money::idr(0),
+            discount: Money::idr(0),
+            ppn_rate_bps: default_ppn_rate_bps,
+            ppn: Money::idr(0),
+            total: Money::idr(0),
+            amount_paid: Money::idr(0),
+            balance_due: Money::idr(0),
+            notes: None,
+            sent_at: None,
+            emailed_at: None,
+            voided_at: None,
+            created_by,
+            version: 1,
+            created_at: now,
+            updated_at: now,
+        };
+        invoice.apply_draft(draft, default_ppn_rate_bps, now)?;
+        Ok(invoice)
+    }
+
+    /// Replaces a draft's contents
+    pub fn revise(
+        &mut self,
+        draft: InvoiceDraft,
+        default_ppn_rate_bps: i64,
+        now: DateTime<Utc>,
+    ) -> Result<(), String> {
+        if self.status != SalesInvoiceStatus::Draft {
+            return Err(
+                "Only draft invoices can be edited; void it and issue a new one".to_string(),
+            );
+        }
+        self.apply_draft(draft, default_ppn_rate_bps, now)
+    }
+
+    fn apply_draft(
+        &mut self,
+        draft: InvoiceDraft,
+        default_ppn_rate_bps: i64,
+        now: DateTime<Utc>,
+    ) -> Result<(), String> {
+        if draft.lines.is_empty() || draft.lines.len() > MAX_LINES {
+            return Err(format!("An invoice has 1 to {} lines", MAX_LINES));
+        }
+        let lines = draft
+            .lines
+            .into_iter()
+            .map(SalesInvoiceLine::new)
+            .collect::<Result<Vec<_>, _>>()?;
+        let subtotal: i64 = lines.iter().map(|l| l.amount.amount).sum();
+        if !(0..=subtotal).contains(&draft.discount) {
+            return Err("The discount cannot be negative or above the subtotal".to_string());
+        }
+        let ppn_rate_bps = draft.ppn_rate_bps.unwrap_or(default_ppn_rate_bps);
+        if !(0..=WHOLE_BPS).contains(&ppn_rate_bps) {
+            return Err("PPN runs from 0 to 10000 basis points".to_string());
+        }
+        if let (Some(issue), Some(due)) = (draft.issue_date, draft.due_date) {
+            if due < issue {
+                return Err("The due date cannot be before the issue date".to_string());
+            }
+        }
+
+        let taxable = subtotal - draft.discount;
+        let ppn = taxable * ppn_rate_bps / WHOLE_BPS / Money::RUPIAH * Money::RUPIAH;
+        self.contact_id = draft.contact_id;
+        self.lines = lines;
+        self.subtotal = Money::idr(subtotal);
+        self.discount = Money::idr(draft.discount);
+        self.ppn_rate_bps = ppn_rate_bps;
+        self.ppn = Money::idr(ppn);
+        self.total = Money::idr(taxable + ppn);
+        self.balance_due = Money::idr(taxable + ppn);
+        self.issue_date = draft.issue_date;
+        self.due_date = draft.due_date;
+        self.notes = present(draft.notes);
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// Issues a draft, dated `today` unless it has an issue date and due
+    /// `payment_terms_days` later unless it has a due date. The number is
+    /// assigned when it is stored.
+    pub fn send(
+        &mut self,
+        today: NaiveDate,
+        payment_terms_days: i64,
+        now: DateTime<Utc>,
+    ) -> Result<(), String> {
+        if self.status != SalesInvoiceStatus::Draft {
+            return Err(format!(
+                "The invoice is {} and was sent already",
+                self.status
+            ));
+        }
+        if self.total.amount <= 0 {
+            return Err("An invoice needs something to bill".to_string());
+        }
+        let issue_date = self.issue_date.unwrap_or(today);
+        let due_date = self
+            .due_date
+            .unwrap_or(issue_date + Duration::days(payment_terms_days));
+        self.issue_date = Some(issue_date);
+        self.due_date = Some(due_date);
+        self.status = SalesInvoiceStatus::Sent;
+        self.sent_at = Some(now);
+        self.updated_at = now;
+        self.mark_overdue(today);
+        Ok(())
+    }
+
+    /// Moves an open invoice past its due date to overdue; true if it moved
+    pub fn mark_overdue(&mut self, today: NaiveDate) -> bool {
+        let late = self.due_date.is_some_and(|due| due < today);
+        if late
+            && matches!(
+                self.status,
+                SalesInvoiceStatus::Sent | SalesInvoiceStatus::PartiallyPaid
+            )
+        {
+            self.status = SalesInvoiceStatus::Overdue;
+            return true;
+        }
+        false
+    }
+
+    /// Days past the due date on `today`; 0 while not yet due
+    pub fn days_overdue(&self, today: NaiveDate) -> i64 {
+        self.due_date
+            .map_or(0, |due| (today - due).num_days().max(0))
+    }
+
+    /// Applies part or all of a payment
+    pub fn allocate(
+        &mut self,
+        amount: Money,
+        today: NaiveDate,
+        now: DateTime<Utc>,
+    ) -> Result<(), String> {
+        let number = self.number.as_deref().unwrap_or("draft");
+        if !self.status.is_open() {
+            return Err(format!(
+                "Invoice {} is {} and takes no payments",
+                number, self.status
+            ));
+        }
+        if amount.amount <= 0 || amount.amount > self.balance_due.amount {
+            return Err(format!(
+                "Invoice {} has {} left to pay",
+                number,
+                self.balance_due.rupiah()
+            ));
+        }
+        self.amount_paid = Money::idr(self.amount_paid.amount + amount.amount);
+        self.balance_due = Money::idr(self.total.amount - self.amount_paid.amount);
+        self.status = if self.balance_due.amount == 0 {
+            SalesInvoiceStatus::Paid
+        } else if self.status == SalesInvoiceStatus::Overdue {
+            SalesInvoiceStatus::Overdue
+        } else {
+            SalesInvoiceStatus::PartiallyPaid
+        };
+        self.updated_at = now;
+        self.mark_overdue(today);
+        Ok(())
+    }
+
+    /// Cancels a draft, or an issued invoice nothing was paid on
+    pub fn void(&mut self, now: DateTime<Utc>) -> Result<(), String> {
+        if self.status == SalesInvoiceStatus::Void || self.status == SalesInvoiceStatus::Paid {
+            return Err(format!(
+                "The invoice is {} and cannot be voided",
+                self.status
+            ));
+        }
+        if self.amount_paid.amount > 0 {
+            return Err("Payments were received on this invoice; it cannot be voided".to_string());
+        }
+        self.status = SalesInvoiceStatus::Void;
+        self.balance_due = Money::idr(0);
+        self.voided_at = Some(now);
+        self.updated_at = now;
+        Ok(())
+    }
+
+    pub fn mark_emailed(&mut self, now: DateTime<Utc>) {
+        self.emailed_at = Some(now);
+        self.updated_at = now;
+    }
+}
+
+/// The part of a payment applied to one invoice
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaymentAllocation {
+    pub invoice_id: Uuid,
+    pub amount: Money,
+}
+
+/// Money received from a contact, booked as one income transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InvoicePayment {
+    pub id: Uuid,
+    pub company_id: Uuid,
+    pub contact_id: Uuid,
+    /// Cash or bank account the money went to
+    pub account_id: Uuid,
+    pub transaction_id: Uuid,
+    pub amount: Money,
+    pub paid_on: NaiveDate,
+    /// Transfer or receipt reference
+    pub reference: Option<String>,
+    pub allocations: Vec<PaymentAllocation>,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+/// An allocation as entered; the amount in hundredths of a rupiah
+#[derive(Debug, Clone, Deserialize)]
+pub struct AllocationInput {
+    pub invoice_id: Uuid,
+    pub amount: i64,
+}
+
+/// A payment as entered
+#[derive(Debug, Clone, Deserialize)]
+pub struct PaymentInput {
+    pub contact_id: Uuid,
+    pub account_id: Uuid,
+    /// Today unless given
+    pub paid_on: Option<NaiveDate>,
+    pub reference: Option<String>,
+    pub allocations: Vec<AllocationInput>,
+}
+
+impl InvoicePayment {
+    /// The payment and the invoices it settles, allocated. `invoices` are the
+    /// allocated invoices, which must be the contact's.
+    pub fn allocate(
+        company_id: Uuid,
+        created_by: Uuid,
+        input: PaymentInput,
+        invoices: Vec<SalesInvoice>,
+        today: NaiveDate,
+        now: DateTime<Utc>,
+    ) -> Result<(Self, Vec<SalesInvoice>), String> {
+        if input.allocations.is_empty() {
+            return Err("Allocate the payment to at least one invoice".to_string());
+        }
+        let mut seen = HashSet::new();
+        if !input.allocations.iter().all(|a| seen.insert(a.invoice_id)) {
+            return Err("Each invoice can be allocated once per payment".to_string());
+        }
+        let mut invoices: HashMap<Uuid, SalesInvoice> =
+            invoices.into_iter().map(|i| (i.id, i)).collect();
+
+        let mut allocated = Vec::new();
+        let mut allocations = Vec::new();
+        for allocation in &input.allocations {
+            let mut invoice = invoices
+                .remove(&allocation.invoice_id)
+                .filter(|i| i.company_id == company_id && i.contact_id == input.contact_id)
+                .ok_or_else(|| {
+                    format!(
+                        "Invoice {} is not one of the contact's",
+                        allocation.invoice_id
+                    )
+                })?;
+            let amount = Money::idr(allocation.amount);
+            invoice.allocate(amount.clone(), today, now)?;
+            allocations.push(PaymentAllocation {
+                invoice_id: invoice.id,
+                amount,
+            });
+            allocated.push(invoice);
+        }
+        // Oldest invoice first, the order payments are listed in
+        allocated.sort_by_key(|i| (i.created_at, i.id));
+        let order: Vec<Uuid> = allocated.iter().map(|i| i.id).collect();
+        allocations.sort_by_key(|a| order.iter().position(|id| *id == a.invoice_id));
+
+        let payment = Self {
+            id: Uuid::new_v4(),
+            company_id,
+            contact_id: input.contact_id,
+            account_id: input.account_id,
+            transaction_id: Uuid::new_v4(),
+            amount: Money::idr(allocations.iter().map(|a| a.amount.amount).sum()),
+            paid_on: input.paid_on.unwrap_or(today),
+            reference: present(input.reference),
+            allocations,
+            created_by,
+            created_at: now,
+        };
+        Ok((payment, allocated))
+    }
+
+    /// The completed income transaction booking the payment into its account
+    pub fn transaction(&self, invoices: &[SalesInvoice], contact: &Contact) -> Transaction {
+        let numbers: Vec<&str> = invoices
+            .iter()
+            .filter_map(|i| i.number.as_deref())
+            .collect();
+        let mut transaction = Transaction::new(
+            self.company_id,
+            reporting_day_start(self.paid_on),
+            TransactionType::Income,
+            self.amount.clone(),
+            format!("Pembayaran {} dari {}", numbers.join(", "), contact.name),
+            self.account_id,
+            self.created_by,
+        );
+        transaction.id.0 = self.transaction_id;
+        transaction.status = TransactionStatus::Completed;
+        transaction.reference_number = self
+            .reference
+            .clone()
+            .or_else(|| numbers.first().map(|n| n.to_string()));
+        transaction.tags = vec!["invoice_payment".to_string()];
+        transaction.metadata = Some(HashMap::from([
+            ("invoice_payment_id".to_string(), serde_json::json!(self.id)),
+            ("invoice_numbers".to_string(), serde_json::json!(numbers)),
+        ]));
+        transaction.created_at = self.created_at;
+        transaction.updated_at = self.created_at;
+        transaction
+    }
+}
+
+/// Amounts owed by age past the due date
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AgingAmounts {
+    /// Not yet due
+    pub current: Money,
+    pub days_1_30: Money,
+    pub days_31_60: Money,
+    pub days_61_90: Money,
+    pub over_90: Money,
+    pub total: Money,
+}
+
+impl Default for AgingAmounts {
+    fn default() -> Self {
+        Self {
+            current: Money::idr(0),
+            days_1_30: Money::idr(0),
+            days_31_60: Money::idr(0),
+            days_61_90: Money::idr(0),
+            over_90: Money::idr(0),
+            total: Money::idr(0),
+        }
+    }
+}
+
+impl AgingAmounts {
+    fn add(&mut self, days_overdue: i64, amount: i64) {
+        let bucket = match days_overdue {
+            0 => &mut self.current,
+            1..=30 => &mut self.days_1_30,
+            31..=60 => &mut self.days_31_60,
+            61..=90 => &mut self.days_61_90,
+            _ => &mut self.over_90,
+        };
+        bucket.amount += amount;
+        self.total.amount += amount;
+    }
+}
+
+/// What one contact owes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AgingRow {
+    pub contact_id: Uuid,
+    pub contact_name: String,
+    pub open_invoices: usize,
+    pub amounts: AgingAmounts,
+}
+
+/// Receivables by contact and age
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AgingReport {
+    pub as_of: NaiveDate,
+    /// Largest balance first
+    pub contacts: Vec<AgingRow>,
+    pub totals: AgingAmounts,
+}
+
+impl AgingReport {
+    /// Ages the balances of the open invoices among `invoices`
+    pub fn build(as_of: NaiveDate, invoices: &[SalesInvoice], contacts: &[Contact]) -> Self {
+        let names: HashMap<Uuid, &str> = contacts.iter().map(|c| (c.id, c.name.as_str())).collect();
+        let mut rows: HashMap<Uuid, AgingRow> = HashMap::new();
+        let mut totals = AgingAmounts::default();
+        for invoice in invoices.iter().filter(|i| i.status.is_open()) {
+            let days = invoice.days_overdue(as_of);
+            let row = rows.entry(invoice.contact_id).or_insert_with(|| AgingRow {
+                contact_id: invoice.contact_id,
+                contact_name: names.get(&invoice.contact_id).unwrap_or(&"-").to_string(),
+                open_invoices: 0,
+                amounts: AgingAmounts::default(),
+            });
+            row.open_invoices += 1;
+            row.amounts.add(days, invoice.balance_due.amount);
+            totals.add(days, invoice.balance_due.amount);
+        }
+        let mut contacts: Vec<AgingRow> = rows.into_values().collect();
+        contacts.sort_by(|a, b| {
+            b.amounts
+                .total
+                .amount
+                .cmp(&a.amounts.total.amount)
+                .then_with(|| a.contact_name.cmp(&b.contact_name))
+        });
+        Self {
+            as_of,
+            contacts,
+            totals,
+        }
+    }
+}
+
+/// Which of a company's invoices to list
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SalesInvoiceQuery {
+    pub status: Option<SalesInvoiceStatus>,
+    pub contact_id: Option<Uuid>,
+    /// Only sent, partially paid and overdue invoices
+    #[serde(default)]
+    pub open: bool,
+}
+
+#[async_trait]
+pub trait InvoicingRepository: Send + Sync {
+    async fn create_contact(&self, contact: &Contact) -> AppResult<Contact>;
+    /// Saved at `contact.version`
+    async fn update_contact(&self, contact: &Contact) -> AppResult<Contact>;
+    async fn contact(&self, company_id: Uuid, id: Uuid) -> AppResult<Option<Contact>>;
+    /// The company's contacts by name
+    async fn contacts(&self, company_id: Uuid) -> AppResult<Vec<Contact>>;
+    async fn create_invoice(&self, invoice: &SalesInvoice) -> AppResult<SalesInvoice>;
+    /// Saved at `invoice.version`; a sent invoice without a number is given
+    /// the next one of its company for the year it was issued
+    async fn update_invoice(&self, invoice: &SalesInvoice) -> AppResult<SalesInvoice>;
+    async fn invoice(&self, company_id: Uuid, id: Uuid) -> AppResult<Option<SalesInvoice>>;
+    /// Newest first
+    async fn invoices(
+        &self,
+        company_id: Uuid,
+        query: &SalesInvoiceQuery,
+        limit: i64,
+    ) -> AppResult<Vec<SalesInvoice>>;
+    /// Books `transaction` into its account, stores the payment and saves the
+    /// allocated invoices at their versions, all or nothing
+    async fn record_payment(
+        &self,
+        payment: &InvoicePayment,
+        transaction: &Transaction,
+        invoices: &[SalesInvoice],
+    ) -> AppResult<(InvoicePayment, Vec<SalesInvoice>)>;
+    /// Payments allocated to the invoice, oldest first
+    async fn payments(&self, company_id: Uuid, invoice_id: Uuid) -> AppResult<Vec<InvoicePayment>>;
+    /// Moves open invoices due before `today` to overdue; returns how many
+    async fn mark_overdue(&self, today: NaiveDate) -> AppResult<u64>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 2, 3, 0, 0).unwrap()
+    }
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, d).unwrap()
+    }
+
+    fn line(description: &str, quantity: i64, unit_price: i64, discount_bps: i64) -> LineInput {
+        LineInput {
+            description: description.to_string(),
+            quantity,
+            unit_price,
+            discount_bps,
+        }
+    }
+
+    fn draft(contact_id: Uuid, lines: Vec<LineInput>, discount: i64) -> InvoiceDraft {
+        InvoiceDraft {
+            contact_id,
+            lines,
+            discount,
+            ppn_rate_bps: None,
+            issue_date: None,
+            due_date: None,
+            notes: None,
+        }
+    }
+
+    fn sent(contact_id: Uuid, unit_price: i64, issued: u32) -> SalesInvoice {
+        let mut invoice = SalesInvoice::draft(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            draft(contact_id, vec![line("Kopi", 1, unit_price, 0)], 0),
+            0,
+            now(),
+        )
+        .unwrap();
+        invoice.send(day(issued), 14, now()).unwrap();
+        invoice.number = Some(format!("INV/2026/{:05}", issued));
+        invoice
+    }
+
+    #[test]
+    fn test_totals_apply_line_and_invoice_discounts_before_ppn() {
+        let lines = vec![
+            // 3 x Rp 25.000 less 10%
+            line("Keripik singkong 250g", 3, 2_500_000, 1_000),
+            line("Ongkos kirim", 1, 1_234_567, 0),
+        ];
+        let invoice = SalesInvoice::draft(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            draft(Uuid::new_v4(), lines, 500_000),
+            1_100,
+            now(),
+        )
+        .unwrap();
+        assert_eq!(invoice.lines[0].amount, Money::idr(6_750_000));
+        assert_eq!(invoice.subtotal, Money::idr(7_984_567));
+        // 11% of Rp 74.845,67 is Rp 8.233,02, rounded down to whole rupiah
+        assert_eq!(invoice.ppn, Money::idr(823_300));
+        assert_eq!(invoice.total, Money::idr(7_984_567 - 500_000 + 823_300));
+        assert_eq!(invoice.balance_due, invoice.total);
+
+        let too_much = SalesInvoice::draft(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            draft(Uuid::new_v4(), vec![line("Kopi", 1, 100, 0)], 101),
+            1_100,
+            now(),
+        );
+        assert!(too_much.is_err());
+        assert!(SalesInvoice::draft(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            draft(Uuid::new_v4(), vec![], 0),
+            0,
+            now()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_payments_move_the_status_until_paid() {
+        let mut invoice = sent(Uuid::new_v4(), 10_000_000, 2);
+        assert_eq!(invoice.status, SalesInvoiceStatus::Sent);
+        assert_eq!(invoice.due_date, Some(day(16)));
+        assert!(invoice
+            .revise(
+                draft(invoice.contact_id, vec![line("Teh", 1, 1, 0)], 0),
+                0,
+                now()
+            )
+            .is_err());
+
+        invoice
+            .allocate(Money::idr(4_000_000), day(3), now())
+            .unwrap();
+        assert_eq!(invoice.status, SalesInvoiceStatus::PartiallyPaid);
+        assert!(invoice.mark_overdue(day(17)));
+        assert_eq!(invoice.days_overdue(day(20)), 4);
+        assert!(invoice
+            .allocate(Money::idr(6_000_001), day(20), now())
+            .is_err());
+        invoice
+            .allocate(Money::idr(1_000_000), day(20), now())
+            .unwrap();
+        assert_eq!(invoice.status, SalesInvoiceStatus::Overdue);
+        assert!(invoice.void(now()).is_err());
+        invoice
+            .allocate(Money::idr(5_000_000), day(20), now())
+            .unwrap();
+        assert_eq!(
+            (invoice.status, invoice.balance_due.amount),
+            (SalesInvoiceStatus::Paid, 0)
+        );
+        assert!(!invoice.mark_overdue(day(30)));
+
+        let mut unpaid = sent(Uuid::new_v4(), 10_000_000, 2);
+        unpaid.void(now()).unwrap();
+        assert_eq!(
+            (unpaid.status, unpaid.balance_due.amount),
+            (SalesInvoiceStatus::Void, 0)
+        );
+        assert!(unpaid.allocate(Money::idr(1), day(3), now()).is_err());
+    }
+
+    #[test]
+    fn test_payments_allocate_across_the_contacts_invoices_and_post_income() {
+        let contact = Contact::new(
+            Uuid::new_v4(),
+            ContactInput {
+                name: " Toko Sumber Rejeki ".to_string(),
+                email: Some("kasir@sumber.id".to_string()),
+                ..ContactInput::default()
+            },
+            now(),
+        )
+        .unwrap();
+        assert_eq!(contact.name, "Toko Sumber Rejeki");
+        let mut first = sent(contact.id, 10_000_000, 2);
+        let mut second = sent(contact.id, 5_000_000, 3);
+        first.company_id = contact.company_id;
+        second.company_id = contact.company_id;
+        second.created_at = now() + Duration::minutes(1);
+        let other = sent(Uuid::new_v4(), 1_000_000, 4);
+
+        let input = |allocations: Vec<(Uuid, i64)>| PaymentInput {
+            contact_id: contact.id,
+            account_id: Uuid::new_v4(),
+            paid_on: None,
+            reference: Some("TRF-88".to_string()),
+            allocations: allocations
+                .into_iter()
+                .map(|(invoice_id, amount)| AllocationInput { invoice_id, amount })
+                .collect(),
+        };
+        let foreign = InvoicePayment::allocate(
+            contact.company_id,
+            Uuid::new_v4(),
+            input(vec![(other.id, 100)]),
+            vec![other.clone()],
+            day(5),
+            now(),
+        );
+        assert!(foreign.is_err());
+
+        let (payment, invoices) = InvoicePayment::allocate(
+            contact.company_id,
+            Uuid::new_v4(),
+            input(vec![(first.id, 10_000_000), (second.id, 2_000_000)]),
+            vec![first.clone(), second.clone()],
+            day(5),
+            now(),
+        )
+        .unwrap();
+        assert_eq!(payment.amount, Money::idr(12_000_000));
+        assert_eq!(payment.paid_on, day(5));
+        let statuses: Vec<_> = invoices.iter().map(|i| i.status).collect();
+        assert_eq!(
+            statuses,
+            vec![SalesInvoiceStatus::Paid, SalesInvoiceStatus::PartiallyPaid]
+        );
+
+        let transaction = payment.transaction(&invoices, &contact);
+        assert_eq!(transaction.id.value(), payment.transaction_id);
+        assert_eq!(transaction.transaction_type, TransactionType::Income);
+        assert_eq!(transaction.status, TransactionStatus::Completed);
+        assert_eq!(transaction.signed_amount(), 12_000_000);
+        assert_eq!(
+            transaction.description,
+            "Pembayaran INV/2026/00002, INV/2026/00003 dari Toko Sumber Rejeki"
+        );
+        assert_eq!(transaction.reference_number.as_deref(), Some("TRF-88"));
+    }
+
+    #[test]
+    fn test_aging_buckets_open_balances_by_days_past_due() {
+        let budi = Contact::new(
+            Uuid::new_v4(),
+            ContactInput {
+                name: "Budi".to_string(),
+                ..ContactInput::default()
+            },
+            now(),
+        )
+        .unwrap();
+        let ani = Contact::new(
+            budi.company_id,
+            ContactInput {
+                name: "Ani".to_string(),
+                ..ContactInput::default()
+            },
+            now(),
+        )
+        .unwrap();
+        // Due on the 16th, 17th and 18th of March
+        let mut invoices = vec![
+            sent(budi.id, 1_000_000, 2),
+            sent(budi.id, 2_000_000, 3),
+            sent(ani.id, 4_000_000, 4),
+        ];
+        invoices[1]
+            .allocate(Money::idr(500_000), day(3), now())
+            .unwrap();
+        let mut paid = sent(ani.id, 9_000_000, 4);
+        paid.allocate(Money::idr(9_000_000), day(4), now()).unwrap();
+        invoices.push(paid);
+
+        let as_of = NaiveDate::from_ymd_opt(2026, 5, 17).unwrap();
+        let report = AgingReport::build(as_of, &invoices, &[budi.clone(), ani.clone()]);
+        assert_eq!(report.contacts[0].contact_name, "Ani");
+        assert_eq!(report.contacts[0].open_invoices, 1);
+        // 60 days past the 18th of March
+        assert_eq!(report.contacts[0].amounts.days_31_60, Money::idr(4_000_000));
+        assert_eq!(report.contacts[1].amounts.days_61_90, Money::idr(2_500_000));
+        assert_eq!(report.totals.total, Money::idr(6_500_000));
+        assert_eq!(report.totals.current, Money::idr(0));
+
+        let early = AgingReport::build(day(16), &invoices, &[budi, ani]);
+        assert_eq!(early.totals.current, Money::idr(6_500_000));
+    }
+}
@@ -0,0 +1,405 @@
+// License application comments
+// Reviewers and applicants discuss an application in threads: a comment either
+// starts a thread or replies to the comment that started one. Internal
+// comments are for reviewers only, and replies in an internal thread stay
+// internal. Comments may point at documents uploaded to the license and
+// mention the people who should read them. Reads are recorded per
+// participant, so both sides can see what the other has seen.
+// A clarification request is a reviewer's comment that also sends the
+// application back to the applicant as `PendingDocuments`; the comment is the
+// explanation they get.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::shared::errors::AppResult;
+
+/// Longest comment accepted, in characters
+pub const MAX_COMMENT_LENGTH: usize = 5000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommentKind {
+    #[default]
+    Comment,
+    /// Asks the applicant for more information, pausing the review
+    ClarificationRequest,
+}
+
+impl fmt::Display for CommentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            CommentKind::Comment => "comment",
+            CommentKind::ClarificationRequest => "clarification_request",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for CommentKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "comment" => Ok(CommentKind::Comment),
+            "clarification_request" => Ok(CommentKind::ClarificationRequest),
+            _ => Err(format!("Unknown comment kind: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LicenseComment {
+    pub id: Uuid,
+    pub license_id: Uuid,
+    /// The comment that started the thread; `None` for that comment itself
+    pub parent_id: Option<Uuid>,
+    pub author_id: Uuid,
+    pub body: String,
+    /// Only reviewers see internal comments
+    pub internal: bool,
+    pub kind: CommentKind,
+    /// Documents of the same license the comment refers to
+    pub document_ids: Vec<Uuid>,
+    /// Users asked to read the comment
+    pub mentions: Vec<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Someone taking part in a license's discussion: its applicant or a reviewer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Participant {
+    pub user_id: Uuid,
+    pub is_admin: bool,
+}
+
+impl Participant {
+    pub fn can_see(&self, comment: &LicenseComment) -> bool {
+        self.is_admin || !comment.internal
+    }
+}
+
+/// What a participant asks to post
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct NewComment {
+    pub parent_id: Option<Uuid>,
+    pub body: String,
+    #[serde(default)]
+    pub internal: bool,
+    #[serde(default)]
+    pub kind: CommentKind,
+    #[serde(default)]
+    pub document_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub mentions: Vec<Uuid>,
+}
+
+fn unique(mut ids: Vec<Uuid>) -> Vec<Uuid> {
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+impl LicenseComment {
+    /// Checks `new` against the rules of the discussion, given the comment
+    /// that started the thread it replies to. Whether its documents and
+    /// mentions exist is left to the caller.
+    pub fn compose(
+        license_id: Uuid,
+        author: Participant,
+        new: NewComment,
+        parent: Option<&LicenseComment>,
+    ) -> Result<Self, String> {
+        let body = new.body.trim().to_string();
+        if body.is_empty() {
+            return Err("A comment cannot be empty".to_string());
+        }
+        if body.chars().count() > MAX_COMMENT_LENGTH {
+            return Err(format!(
+                "Comments are limited to {} characters",
+                MAX_COMMENT_LENGTH
+            ));
+        }
+        if new.internal && !author.is_admin {
+            return Err("Only reviewers can post internal comments".to_string());
+        }
+        if new.kind == CommentKind::ClarificationRequest {
+            if !author.is_admin {
+                return Err("Only reviewers can ask for clarification".to_string());
+            }
+            if new.internal || parent.is_some() {
+                return Err(
+                    "A clarification request starts a thread the applicant can see".to_string(),
+                );
+            }
+        }
+
+        let internal = match parent {
+            Some(parent) => {
+                if parent.license_id != license_id || !author.can_see(parent) {
+                    return Err("The comment replied to does not exist".to_string());
+                }
+                if parent.parent_id.is_some() {
+                    return Err("Replies go to the comment that started the thread".to_string());
+                }
+                new.internal || parent.internal
+            }
+            None => new.internal,
+        };
+
+        let mut mentions = unique(new.mentions);
+        mentions.retain(|id| *id != author.user_id);
+
+        Ok(Self {
+            id: Uuid::new_v4(),
+            license_id,
+            parent_id: parent.map(|parent| parent.id),
+            author_id: author.user_id,
+            body,
+            internal,
+            kind: new.kind,
+            document_ids: unique(new.document_ids),
+            mentions,
+            created_at: Utc::now(),
+        })
+    }
+}
+
+/// A participant having read a comment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadReceipt {
+    pub comment_id: Uuid,
+    pub user_id: Uuid,
+    pub read_at: DateTime<Utc>,
+}
+
+/// Who read a comment and when
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Reader {
+    pub user_id: Uuid,
+    pub read_at: DateTime<Utc>,
+}
+
+/// A comment as one participant sees it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommentView {
+    #[serde(flatten)]
+    pub comment: LicenseComment,
+    pub read_by: Vec<Reader>,
+    /// Whether the participant viewing it has read it
+    pub read: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommentThread {
+    #[serde(flatten)]
+    pub comment: CommentView,
+    pub replies: Vec<CommentView>,
+}
+
+/// A license's threads as one participant sees them, oldest first
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Discussion {
+    pub unread_count: usize,
+    pub threads: Vec<CommentThread>,
+}
+
+impl Discussion {
+    /// Arranges `comments`, oldest first, into threads, leaving out what the
+    /// participant may not see
+    pub fn build(comments: Vec<LicenseComment>, receipts: &[ReadReceipt], viewer: Participant) -> Self {
+        let view = |comment: LicenseComment| {
+            let read_by: Vec<Reader> = receipts
+                .iter()
+                .filter(|receipt| receipt.comment_id == comment.id)
+                .map(|receipt| Reader {
+                    user_id: receipt.user_id,
+                    read_at: receipt.read_at,
+                })
+                .collect();
+            let read = comment.author_id == viewer.user_id
+                || read_by.iter().any(|reader| reader.user_id == viewer.user_id);
+            CommentView {
+                comment,
+                read_by,
+                read,
+            }
+        };
+
+        let (starts, replies): (Vec<_>, Vec<_>) = comments
+            .into_iter()
+            .filter(|comment| viewer.can_see(comment))
+            .partition(|comment| comment.parent_id.is_none());
+        let mut threads: Vec<CommentThread> = starts
+            .into_iter()
+            .map(|comment| CommentThread {
+                comment: view(comment),
+                replies: Vec::new(),
+            })
+            .collect();
+        for reply in replies {
+            if let Some(thread) = threads
+                .iter_mut()
+                .find(|thread| Some(thread.comment.comment.id) == reply.parent_id)
+            {
+                thread.replies.push(view(reply));
+            }
+        }
+
+        let unread_count = threads
+            .iter()
+            .flat_map(|thread| std::iter::once(&thread.comment).chain(&thread.replies))
+            .filter(|comment| !comment.read)
+            .count();
+        Self {
+            unread_count,
+            threads,
+        }
+    }
+}
+
+#[async_trait]
+pub trait LicenseCommentRepository: Send + Sync {
+    /// Saves the comment with its attachments and mentions, read by its author
+    async fn create_comment(&self, comment: &LicenseComment) -> AppResult<LicenseComment>;
+    async fn get_comment(&self, id: Uuid) -> AppResult<Option<LicenseComment>>;
+    /// The license's comments, oldest first, internal ones only if asked for
+    async fn comments(&self, license_id: Uuid, include_internal: bool) -> AppResult<Vec<LicenseComment>>;
+    /// Every read of the license's comments
+    async fn read_receipts(&self, license_id: Uuid) -> AppResult<Vec<ReadReceipt>>;
+    /// Records the user reading the license's comments posted up to `at`,
+    /// internal ones only if asked for, returning how many were new to them
+    async fn mark_read(
+        &self,
+        license_id: Uuid,
+        user_id: Uuid,
+        include_internal: bool,
+        at: DateTime<Utc>,
+    ) -> AppResult<u64>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reviewer() -> Participant {
+        Participant {
+            user_id: Uuid::new_v4(),
+            is_admin: true,
+        }
+    }
+
+    fn applicant() -> Participant {
+        Participant {
+            user_id: Uuid::new_v4(),
+            is_admin: false,
+        }
+    }
+
+    fn comment(body: &str) -> NewComment {
+        NewComment {
+            body: body.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_only_reviewers_post_internally_or_ask_for_clarification() {
+        let license_id = Uuid::new_v4();
+        let internal = NewComment {
+            internal: true,
+            ..comment("Cek ulang NPWP")
+        };
+        let clarification = NewComment {
+            kind: CommentKind::ClarificationRequest,
+            ..comment("Mohon lampirkan akta terbaru")
+        };
+
+        assert!(LicenseComment::compose(license_id, applicant(), internal.clone(), None).is_err());
+        assert!(LicenseComment::compose(license_id, applicant(), clarification.clone(), None).is_err());
+        assert!(LicenseComment::compose(license_id, applicant(), comment("   "), None).is_err());
+
+        let posted = LicenseComment::compose(license_id, reviewer(), internal, None).unwrap();
+        assert!(posted.internal);
+        let posted = LicenseComment::compose(license_id, reviewer(), clarification.clone(), None).unwrap();
+        assert_eq!(posted.kind, CommentKind::ClarificationRequest);
+        // Clarifications are never internal and never replies
+        let hidden = NewComment {
+            internal: true,
+            ..clarification.clone()
+        };
+        assert!(LicenseComment::compose(license_id, reviewer(), hidden, None).is_err());
+        assert!(LicenseComment::compose(license_id, reviewer(), clarification, Some(&posted)).is_err());
+    }
+
+    #[test]
+    fn test_replies_stay_in_one_level_threads_and_internal_threads_stay_internal() {
+        let license_id = Uuid::new_v4();
+        let admin = reviewer();
+        let owner = applicant();
+        let internal = LicenseComment::compose(
+            license_id,
+            admin,
+            NewComment {
+                internal: true,
+                ..comment("Perlu dicek ke dinas")
+            },
+            None,
+        )
+        .unwrap();
+
+        let reply = LicenseComment::compose(license_id, admin, comment("Sudah"), Some(&internal)).unwrap();
+        assert!(reply.internal);
+        assert_eq!(reply.parent_id, Some(internal.id));
+        // Replies to replies, to threads the author cannot see or of another license
+        assert!(LicenseComment::compose(license_id, admin, comment("x"), Some(&reply)).is_err());
+        assert!(LicenseComment::compose(license_id, owner, comment("x"), Some(&internal)).is_err());
+        assert!(LicenseComment::compose(Uuid::new_v4(), admin, comment("x"), Some(&internal)).is_err());
+
+        let mention_self = NewComment {
+            mentions: vec![owner.user_id, admin.user_id, admin.user_id],
+            ..comment("Halo")
+        };
+        let posted = LicenseComment::compose(license_id, owner, mention_self, None).unwrap();
+        assert_eq!(posted.mentions, vec![admin.user_id]);
+    }
+
+    #[test]
+    fn test_discussions_hide_internal_comments_from_applicants_and_count_unread() {
+        let license_id = Uuid::new_v4();
+        let admin = reviewer();
+        let owner = applicant();
+        let question = LicenseComment::compose(license_id, admin, comment("Alamat usaha?"), None).unwrap();
+        let note = LicenseComment::compose(
+            license_id,
+            admin,
+            NewComment {
+                internal: true,
+                ..comment("Catatan internal")
+            },
+            None,
+        )
+        .unwrap();
+        let answer = LicenseComment::compose(license_id, owner, comment("Jl. Malioboro 1"), Some(&question)).unwrap();
+        let receipts = [ReadReceipt {
+            comment_id: answer.id,
+            user_id: admin.user_id,
+            read_at: Utc::now(),
+        }];
+        let comments = vec![question.clone(), note, answer.clone()];
+
+        let seen_by_owner = Discussion::build(comments.clone(), &receipts, owner);
+        assert_eq!(seen_by_owner.threads.len(), 1);
+        assert_eq!(seen_by_owner.threads[0].replies[0].comment.id, answer.id);
+        assert_eq!(seen_by_owner.threads[0].replies[0].read_by[0].user_id, admin.user_id);
+        assert_eq!(seen_by_owner.unread_count, 1);
+
+        let seen_by_admin = Discussion::build(comments, &receipts, admin);
+        assert_eq!(seen_by_admin.threads.len(), 2);
+        assert_eq!(seen_by_admin.unread_count, 0);
+    }
+}
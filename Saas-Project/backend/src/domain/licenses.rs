@@ -4,7 +4,7 @@ use sqlx::FromRow;
 use uuid::Uuid;
 
 /// License types supported by the Indonesian UMKM platform
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "license_type", rename_all = "lowercase")]
 pub enum LicenseType {
     /// Nomor Induk Berusaha - Primary business registration number
@@ -38,7 +38,7 @@ impl std::fmt::Display for LicenseType {
 }
 
 /// Application status workflow for license processing
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, sqlx::Type)]
 #[sqlx(type_name = "application_status", rename_all = "lowercase")]
 pub enum ApplicationStatus {
     /// Initial state - application created but not submitted
@@ -75,7 +75,7 @@ impl std::fmt::Display for ApplicationStatus {
 }
 
 /// Priority level for license applications
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "priority_level", rename_all = "lowercase")]
 pub enum PriorityLevel {
     Low,
@@ -85,7 +85,7 @@ pub enum PriorityLevel {
 }
 
 /// Main License entity
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
 pub struct License {
     pub id: Uuid,
     pub license_number: Option<String>, // Generated after approval
@@ -121,6 +121,18 @@ pub struct License {
     // Admin notes
     pub admin_notes: Option<String>,
     pub rejection_reason: Option<String>,
+
+    /// Row version for optimistic locking; bumped by every update
+    #[serde(default = "crate::shared::types::initial_version")]
+    pub version: i64,
+
+    // SLA clock pauses, kept by the `track_sla_pause` trigger
+    /// Start of the current `PendingDocuments` period, if in one
+    #[serde(default)]
+    pub sla_paused_at: Option<DateTime<Utc>>,
+    /// Length of the earlier `PendingDocuments` periods
+    #[serde(default)]
+    pub sla_paused_seconds: i64,
 }
 
 /// License application form data
@@ -136,7 +148,7 @@ pub struct LicenseApplication {
 }
 
 /// Document types for license applications
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "document_type", rename_all = "lowercase")]
 pub enum DocumentType {
     /// Identity documents
@@ -155,8 +167,22 @@ pub enum DocumentType {
     Other,
 }
 
+impl std::fmt::Display for DocumentType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DocumentType::Ktp => write!(f, "ktp"),
+            DocumentType::CompanyDeed => write!(f, "company_deed"),
+            DocumentType::TaxCertificate => write!(f, "tax_certificate"),
+            DocumentType::BankStatement => write!(f, "bank_statement"),
+            DocumentType::BusinessPlan => write!(f, "business_plan"),
+            DocumentType::LocationPermit => write!(f, "location_permit"),
+            DocumentType::Other => write!(f, "other"),
+        }
+    }
+}
+
 /// Document entity for license applications
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
 pub struct LicenseDocument {
     pub id: Uuid,
     pub license_id: Uuid,
@@ -174,7 +200,7 @@ pub struct LicenseDocument {
 }
 
 /// Status history tracking for audit trail
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
 pub struct ApplicationStatusHistory {
     pub id: Uuid,
     pub license_id: Uuid,
@@ -222,6 +248,9 @@ impl License {
             rejected_at: None,
             admin_notes: None,
             rejection_reason: None,
+            version: 1,
+            sla_paused_at: None,
+            sla_paused_seconds: 0,
         }
     }
 
@@ -315,6 +344,77 @@ impl License {
         Ok(())
     }
 
+    /// Ask the applicant for further documents, pausing the review
+    pub fn request_documents(&mut self, notes: String) -> Result<(), String> {
+        if !matches!(
+            self.application_status,
+            ApplicationStatus::Submitted | ApplicationStatus::Processing
+        ) {
+            return Err(
+                "Can only request documents for applications in Submitted or Processing status"
+                    .to_string(),
+            );
+        }
+
+        self.application_status = ApplicationStatus::PendingDocuments;
+        self.admin_notes = Some(notes);
+        self.updated_at = Utc::now();
+
+        Ok(())
+    }
+
+    /// Suspend an approved license, e.g. after a violation
+    pub fn suspend(&mut self, reason: String) -> Result<(), String> {
+        if self.application_status != ApplicationStatus::Approved {
+            return Err("Can only suspend licenses in Approved status".to_string());
+        }
+
+        self.application_status = ApplicationStatus::Suspended;
+        self.admin_notes = Some(reason);
+        self.updated_at = Utc::now();
+
+        Ok(())
+    }
+
+    /// Lift the suspension of a license
+    pub fn reinstate(&mut self, admin_notes: Option<String>) -> Result<(), String> {
+        if self.application_status != ApplicationStatus::Suspended {
+            return Err("Can only reinstate licenses in Suspended status".to_string());
+        }
+
+        self.application_status = ApplicationStatus::Approved;
+        self.admin_notes = admin_notes;
+        self.updated_at = Utc::now();
+
+        Ok(())
+    }
+
+    /// Extend the validity of an approved or expired license to `expiry_date`
+    pub fn renew(
+        &mut self,
+        expiry_date: DateTime<Utc>,
+        admin_notes: Option<String>,
+    ) -> Result<(), String> {
+        if !matches!(
+            self.application_status,
+            ApplicationStatus::Approved | ApplicationStatus::Expired
+        ) {
+            return Err("Can only renew licenses in Approved or Expired status".to_string());
+        }
+
+        let now = Utc::now();
+        if expiry_date <= now || self.expiry_date.is_some_and(|current| expiry_date <= current) {
+            return Err("Renewal must move the expiry date later".to_string());
+        }
+
+        self.application_status = ApplicationStatus::Approved;
+        self.expiry_date = Some(expiry_date);
+        self.admin_notes = admin_notes;
+        self.updated_at = now;
+
+        Ok(())
+    }
+
     /// Check if license is expired
     pub fn is_expired(&self) -> bool {
         if let Some(expiry_date) = self.expiry_date {
@@ -329,6 +429,16 @@ impl License {
         self.application_status == ApplicationStatus::Approved && !self.is_expired()
     }
 
+    /// Check if license is submitted and not yet decided
+    pub fn is_awaiting_review(&self) -> bool {
+        matches!(
+            self.application_status,
+            ApplicationStatus::Submitted
+                | ApplicationStatus::Processing
+                | ApplicationStatus::PendingDocuments
+        )
+    }
+
     /// Get days until expiry (negative if expired)
     pub fn days_until_expiry(&self) -> Option<i64> {
         self.expiry_date
@@ -404,3 +514,76 @@ impl ApplicationStatusHistory {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn approved(expiry_date: Option<DateTime<Utc>>) -> License {
+        let mut license = License::new(
+            LicenseType::Halal,
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            "Sertifikat Halal".to_string(),
+            None,
+        );
+        license.application_status = ApplicationStatus::Processing;
+        license
+            .approve("HALAL-001".to_string(), Utc::now(), expiry_date, "BPJPH".to_string(), None)
+            .unwrap();
+        license
+    }
+
+    #[test]
+    fn test_suspend_and_reinstate() {
+        let mut license = approved(None);
+        license.suspend("Audit finding".to_string()).unwrap();
+        assert_eq!(license.application_status, ApplicationStatus::Suspended);
+        assert!(!license.is_active());
+        assert!(license.suspend("again".to_string()).is_err());
+
+        license.reinstate(None).unwrap();
+        assert!(license.is_active());
+        assert!(license.reinstate(None).is_err());
+    }
+
+    #[test]
+    fn test_renew_moves_the_expiry_date_later() {
+        let expiry = Utc::now() + Duration::days(10);
+        let mut license = approved(Some(expiry));
+        assert!(license.renew(expiry - Duration::days(1), None).is_err());
+        assert!(license.renew(Utc::now() - Duration::days(1), None).is_err());
+
+        license.application_status = ApplicationStatus::Expired;
+        license.renew(expiry + Duration::days(365), None).unwrap();
+        assert_eq!(license.application_status, ApplicationStatus::Approved);
+        assert_eq!(license.expiry_date, Some(expiry + Duration::days(365)));
+
+        license.suspend("Audit finding".to_string()).unwrap();
+        assert!(license.renew(expiry + Duration::days(730), None).is_err());
+    }
+
+    #[test]
+    fn test_request_documents_pauses_the_review() {
+        let mut license = License::new(
+            LicenseType::Siup,
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            "SIUP".to_string(),
+            None,
+        );
+        assert!(license.request_documents("Akta".to_string()).is_err());
+
+        license.submit().unwrap();
+        license.request_documents("Akta pendirian".to_string()).unwrap();
+        assert_eq!(license.application_status, ApplicationStatus::PendingDocuments);
+        assert_eq!(license.admin_notes.as_deref(), Some("Akta pendirian"));
+        assert!(license.request_documents("again".to_string()).is_err());
+
+        // The review can still be decided once the documents are in
+        license
+            .reject("Dokumen tidak lengkap".to_string(), None)
+            .unwrap();
+    }
+}
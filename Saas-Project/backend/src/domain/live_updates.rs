@@ -0,0 +1,217 @@
+// Live updates for connected clients
+// Changes are pushed as they happen so the frontend no longer polls license
+// lists and status histories. Each update names who may see it: the owner of
+// the license it is about, every admin, or both. Updates carry what changed,
+// not the whole record; clients refetch when they need the rest.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::license_comments::{CommentKind, LicenseComment};
+use super::licenses::{ApplicationStatus, DocumentType, License, LicenseDocument, LicenseType};
+
+/// What changed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveEvent {
+    /// A license entered the review queue
+    LicenseSubmitted {
+        license_id: Uuid,
+        company_id: Uuid,
+        license_type: LicenseType,
+        title: String,
+    },
+    LicenseStatusChanged {
+        license_id: Uuid,
+        license_type: LicenseType,
+        status: ApplicationStatus,
+        version: i64,
+    },
+    DocumentUploaded {
+        license_id: Uuid,
+        document_id: Uuid,
+        document_type: DocumentType,
+    },
+    DocumentVerified {
+        license_id: Uuid,
+        document_id: Uuid,
+        document_type: DocumentType,
+    },
+    CommentAdded {
+        license_id: Uuid,
+        comment_id: Uuid,
+        parent_id: Option<Uuid>,
+        author_id: Uuid,
+        kind: CommentKind,
+        internal: bool,
+    },
+}
+
+impl LiveEvent {
+    /// Event name on the stream, the same as the `type` field
+    pub fn name(&self) -> &'static str {
+        match self {
+            LiveEvent::LicenseSubmitted { .. } => "license_submitted",
+            LiveEvent::LicenseStatusChanged { .. } => "license_status_changed",
+            LiveEvent::DocumentUploaded { .. } => "document_uploaded",
+            LiveEvent::DocumentVerified { .. } => "document_verified",
+            LiveEvent::CommentAdded { .. } => "comment_added",
+        }
+    }
+}
+
+/// An event and who may see it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LiveUpdate {
+    /// The license owner, if they may see it
+    pub owner_id: Option<Uuid>,
+    /// Whether admins see it as well
+    pub admins: bool,
+    pub event: LiveEvent,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl LiveUpdate {
+    fn new(owner_id: Option<Uuid>, admins: bool, event: LiveEvent) -> Self {
+        Self {
+            owner_id,
+            admins,
+            event,
+            occurred_at: Utc::now(),
+        }
+    }
+
+    /// Sent to the applicant, and to admins since it is new review work
+    pub fn license_submitted(license: &License) -> Self {
+        Self::new(
+            Some(license.user_id),
+            true,
+            LiveEvent::LicenseSubmitted {
+                license_id: license.id,
+                company_id: license.company_id,
+                license_type: license.license_type,
+                title: license.title.clone(),
+            },
+        )
+    }
+
+    /// Sent to the applicant, and to admins since the license may have left
+    /// or re-entered their queue
+    pub fn license_status_changed(license: &License) -> Self {
+        Self::new(
+            Some(license.user_id),
+            true,
+            LiveEvent::LicenseStatusChanged {
+                license_id: license.id,
+                license_type: license.license_type,
+                status: license.application_status.clone(),
+                version: license.version,
+            },
+        )
+    }
+
+    /// Sent to the applicant, and to admins while the license is awaiting review
+    pub fn document_uploaded(license: &License, document: &LicenseDocument) -> Self {
+        Self::new(
+            Some(license.user_id),
+            license.is_awaiting_review(),
+            LiveEvent::DocumentUploaded {
+                license_id: license.id,
+                document_id: document.id,
+                document_type: document.document_type.clone(),
+            },
+        )
+    }
+
+    /// Sent to the applicant only
+    pub fn document_verified(license: &License, document: &LicenseDocument) -> Self {
+        Self::new(
+            Some(license.user_id),
+            false,
+            LiveEvent::DocumentVerified {
+                license_id: license.id,
+                document_id: document.id,
+                document_type: document.document_type.clone(),
+            },
+        )
+    }
+
+    /// Sent to admins, and to the applicant unless the comment is internal
+    pub fn comment_added(license: &License, comment: &LicenseComment) -> Self {
+        Self::new(
+            Some(license.user_id).filter(|_| !comment.internal),
+            true,
+            LiveEvent::CommentAdded {
+                license_id: license.id,
+                comment_id: comment.id,
+                parent_id: comment.parent_id,
+                author_id: comment.author_id,
+                kind: comment.kind,
+                internal: comment.internal,
+            },
+        )
+    }
+
+    pub fn visible_to(&self, user_id: Uuid, is_admin: bool) -> bool {
+        self.owner_id == Some(user_id) || (self.admins && is_admin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn license() -> License {
+        License::new(
+            LicenseType::Siup,
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            "SIUP Toko Makmur".to_string(),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_owners_and_admins_see_submissions_but_not_other_owners() {
+        let license = license();
+        let update = LiveUpdate::license_submitted(&license);
+
+        assert!(update.visible_to(license.user_id, false));
+        assert!(update.visible_to(Uuid::new_v4(), true));
+        assert!(!update.visible_to(Uuid::new_v4(), false));
+    }
+
+    #[test]
+    fn test_document_uploads_reach_admins_only_while_under_review() {
+        let mut license = license();
+        let document = LicenseDocument::new(
+            license.id,
+            DocumentType::Ktp,
+            "a.pdf".to_string(),
+            "ktp.pdf".to_string(),
+            "/uploads/a.pdf".to_string(),
+            1024,
+            "application/pdf".to_string(),
+        );
+        assert!(!LiveUpdate::document_uploaded(&license, &document).admins);
+
+        license.application_status = ApplicationStatus::PendingDocuments;
+        assert!(LiveUpdate::document_uploaded(&license, &document).admins);
+
+        let verified = LiveUpdate::document_verified(&license, &document);
+        assert!(!verified.visible_to(Uuid::new_v4(), true));
+        assert!(verified.visible_to(license.user_id, false));
+    }
+
+    #[test]
+    fn test_events_serialize_with_their_name_as_type() {
+        let license = license();
+        let update = LiveUpdate::license_status_changed(&license);
+        let json = serde_json::to_value(&update.event).unwrap();
+
+        assert_eq!(json["type"], update.event.name());
+        assert_eq!(json["license_id"], license.id.to_string());
+        assert_eq!(json["version"], 1);
+    }
+}
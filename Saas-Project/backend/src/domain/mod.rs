@@ -2,18 +2,34 @@
 // This module contains the core business entities, value objects, and domain services
 // following the principles outlined in the architecture document
 
+pub mod admin;
+pub mod analytics;
+pub mod billing;
 pub mod business;
+pub mod certificates;
 pub mod companies;
 pub mod dto;
 pub mod entities;
 pub mod errors;
 pub mod events;
+pub mod filters;
 pub mod finance;
+pub mod imports;
+pub mod invoicing;
+pub mod license_comments;
 pub mod licenses;
 pub mod licensing;
+pub mod live_updates;
+pub mod notification_templates;
+pub mod notifications;
+pub mod reconciliation;
 pub mod repositories;
+pub mod search;
+pub mod sla;
+pub mod unit_of_work;
 pub mod users;
 pub mod value_objects;
+pub mod verification;
 
 // Re-export commonly used items
 // Re-exports from entities module
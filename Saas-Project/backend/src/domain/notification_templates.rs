@@ -0,0 +1,343 @@
+// Notification message templates
+// Every event has a title and a body in Bahasa Indonesia and English, with
+// `{placeholder}`s filled from the license or invoice the event is about. The
+// rendered text is what the in-app inbox shows; email and WhatsApp wrap it
+// with a greeting or formatting of their own. Notes from the reviewer, such as
+// the rejection reason or the documents still needed, follow the body; for
+// comments, the notes are the comment itself, and for failed payments the
+// gateway's reason.
+
+use chrono::{DateTime, Utc};
+
+use crate::domain::analytics::reporting_day;
+use crate::domain::billing::SubscriptionInvoice;
+use crate::domain::companies::SubscriptionPlan;
+use crate::domain::licenses::{License, LicenseType};
+use crate::domain::notifications::{Locale, NotificationEvent};
+
+/// Values the placeholders are filled with
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TemplateValues {
+    /// Recipient's name, for greetings
+    pub name: String,
+    /// `{license}`: the application's title
+    pub license: String,
+    /// `{license_type}`
+    pub license_type: String,
+    /// `{license_number}`, `-` before one is issued
+    pub license_number: Option<String>,
+    /// `{expiry_date}`, `-` for licenses that never expire
+    pub expiry_date: Option<DateTime<Utc>>,
+    /// `{invoice}`: a subscription invoice's number
+    pub invoice: String,
+    /// `{amount}`: what the invoice asks for, in rupiah
+    pub amount: String,
+    /// `{due_date}` of the invoice
+    pub due_date: Option<DateTime<Utc>>,
+    /// `{plan}` the invoice is for
+    pub plan: String,
+    /// Reviewer's notes shown after the body
+    pub notes: Option<String>,
+    /// Where in the app to look, appended by email and WhatsApp
+    pub link: Option<String>,
+}
+
+impl TemplateValues {
+    pub fn for_license(license: &License, name: String, notes: Option<String>) -> Self {
+        Self {
+            name,
+            license: license.title.clone(),
+            license_type: license_type_name(license.license_type).to_string(),
+            license_number: license.license_number.clone(),
+            expiry_date: license.expiry_date,
+            notes: notes.filter(|n| !n.trim().is_empty()),
+            link: None,
+            ..Self::default()
+        }
+    }
+
+    pub fn for_invoice(invoice: &SubscriptionInvoice, name: String, notes: Option<String>) -> Self {
+        Self {
+            name,
+            invoice: invoice.number.clone(),
+            amount: invoice.total.rupiah(),
+            due_date: Some(invoice.due_at),
+            plan: plan_name(invoice.plan).to_string(),
+            notes: notes.filter(|n| !n.trim().is_empty()),
+            ..Self::default()
+        }
+    }
+}
+
+/// Title and body of a notification in one language
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedMessage {
+    pub title: String,
+    pub body: String,
+}
+
+struct Template {
+    title: &'static str,
+    body: &'static str,
+}
+
+fn template(event: NotificationEvent, locale: Locale) -> Template {
+    use NotificationEvent::*;
+    let (title, body) = match (event, locale) {
+        (LicenseApproved, Locale::Id) => (
+            "Izin disetujui",
+            "Permohonan {license_type} \"{license}\" telah disetujui dengan nomor izin {license_number}, berlaku hingga {expiry_date}.",
+        ),
+        (LicenseApproved, Locale::En) => (
+            "License approved",
+            "Your {license_type} application \"{license}\" has been approved as license {license_number}, valid until {expiry_date}.",
+        ),
+        (LicenseRejected, Locale::Id) => (
+            "Izin ditolak",
+            "Permohonan {license_type} \"{license}\" ditolak.",
+        ),
+        (LicenseRejected, Locale::En) => (
+            "License rejected",
+            "Your {license_type} application \"{license}\" has been rejected.",
+        ),
+        (DocumentsRequested, Locale::Id) => (
+            "Dokumen tambahan diperlukan",
+            "Permohonan {license_type} \"{license}\" memerlukan dokumen tambahan sebelum dapat diproses lebih lanjut.",
+        ),
+        (DocumentsRequested, Locale::En) => (
+            "Documents needed",
+            "Your {license_type} application \"{license}\" needs further documents before it can be processed.",
+        ),
+        (LicenseSuspended, Locale::Id) => (
+            "Izin dibekukan",
+            "Izin {license_type} nomor {license_number} (\"{license}\") dibekukan.",
+        ),
+        (LicenseSuspended, Locale::En) => (
+            "License suspended",
+            "Your {license_type} license {license_number} (\"{license}\") has been suspended.",
+        ),
+        (LicenseReinstated, Locale::Id) => (
+            "Izin diaktifkan kembali",
+            "Pembekuan izin {license_type} nomor {license_number} (\"{license}\") telah dicabut; izin kembali berlaku hingga {expiry_date}.",
+        ),
+        (LicenseReinstated, Locale::En) => (
+            "License reinstated",
+            "The suspension of your {license_type} license {license_number} (\"{license}\") has been lifted; it is valid again until {expiry_date}.",
+        ),
+        (LicenseRenewed, Locale::Id) => (
+            "Izin diperpanjang",
+            "Izin {license_type} nomor {license_number} (\"{license}\") telah diperpanjang hingga {expiry_date}.",
+        ),
+        (LicenseRenewed, Locale::En) => (
+            "License renewed",
+            "Your {license_type} license {license_number} (\"{license}\") has been renewed until {expiry_date}.",
+        ),
+        (CommentReceived, Locale::Id) => (
+            "Pesan baru dari petugas",
+            "Petugas menambahkan komentar pada permohonan {license_type} \"{license}\".",
+        ),
+        (CommentReceived, Locale::En) => (
+            "New message from the reviewer",
+            "The reviewer left a comment on your {license_type} application \"{license}\".",
+        ),
+        (CommentMention, Locale::Id) => (
+            "Anda disebut dalam diskusi",
+            "Anda disebut dalam diskusi permohonan {license_type} \"{license}\".",
+        ),
+        (CommentMention, Locale::En) => (
+            "You were mentioned",
+            "You were mentioned in the discussion of the {license_type} application \"{license}\".",
+        ),
+        (InvoiceIssued, Locale::Id) => (
+            "Tagihan langganan baru",
+            "Tagihan {invoice} untuk paket {plan} sebesar {amount} (termasuk PPN) jatuh tempo pada {due_date}.",
+        ),
+        (InvoiceIssued, Locale::En) => (
+            "New subscription invoice",
+            "Invoice {invoice} for the {plan} plan, {amount} including VAT, is due on {due_date}.",
+        ),
+        (InvoicePaymentFailed, Locale::Id) => (
+            "Pembayaran gagal",
+            "Pembayaran tagihan {invoice} sebesar {amount} gagal. Silakan coba lagi sebelum {due_date}.",
+        ),
+        (InvoicePaymentFailed, Locale::En) => (
+            "Payment failed",
+            "The payment of invoice {invoice} for {amount} failed. Please try again before {due_date}.",
+        ),
+        (InvoiceOverdue, Locale::Id) => (
+            "Tagihan lewat jatuh tempo",
+            "Tagihan {invoice} sebesar {amount} telah lewat jatuh tempo sejak {due_date}. Segera lakukan pembayaran agar paket {plan} tetap aktif.",
+        ),
+        (InvoiceOverdue, Locale::En) => (
+            "Invoice overdue",
+            "Invoice {invoice} for {amount} has been overdue since {due_date}. Please pay it to keep the {plan} plan.",
+        ),
+        (SubscriptionDowngraded, Locale::Id) => (
+            "Paket diturunkan ke Free",
+            "Tagihan {invoice} tidak dibayar, sehingga paket {plan} dihentikan dan perusahaan kini menggunakan paket Free.",
+        ),
+        (SubscriptionDowngraded, Locale::En) => (
+            "Moved to the Free plan",
+            "Invoice {invoice} was not paid, so the {plan} plan has ended and the company is now on the Free plan.",
+        ),
+    };
+    Template { title, body }
+}
+
+fn license_type_name(license_type: LicenseType) -> &'static str {
+    match license_type {
+        LicenseType::Nib => "NIB",
+        LicenseType::Siup => "SIUP",
+        LicenseType::Tdp => "TDP",
+        LicenseType::Npwp => "NPWP",
+        LicenseType::Halal => "Sertifikat Halal",
+        LicenseType::Environmental => "Izin Lingkungan",
+        LicenseType::ExportImport => "Izin Ekspor-Impor",
+    }
+}
+
+fn plan_name(plan: SubscriptionPlan) -> &'static str {
+    match plan {
+        SubscriptionPlan::Free => "Free",
+        SubscriptionPlan::Basic => "Basic",
+        SubscriptionPlan::Pro => "Pro",
+    }
+}
+
+fn fill(text: &str, values: &TemplateValues) -> String {
+    let day = |at: Option<DateTime<Utc>>| at.map(|at| reporting_day(at).format("%d-%m-%Y").to_string());
+    let expiry_date = day(values.expiry_date);
+    let due_date = day(values.due_date);
+    text.replace("{license_type}", &values.license_type)
+        .replace("{license_number}", values.license_number.as_deref().unwrap_or("-"))
+        .replace("{expiry_date}", expiry_date.as_deref().unwrap_or("-"))
+        .replace("{license}", &values.license)
+        .replace("{invoice}", &values.invoice)
+        .replace("{amount}", &values.amount)
+        .replace("{due_date}", due_date.as_deref().unwrap_or("-"))
+        .replace("{plan}", &values.plan)
+        .replace("{name}", &values.name)
+}
+
+/// The notification for `event`, as the in-app inbox shows it
+pub fn render(event: NotificationEvent, locale: Locale, values: &TemplateValues) -> RenderedMessage {
+    let template = template(event, locale);
+    let mut body = fill(template.body, values);
+    if let Some(notes) = &values.notes {
+        let label = match locale {
+            Locale::Id => "Catatan",
+            Locale::En => "Notes",
+        };
+        body.push_str(&format!("\n{}: {}", label, notes));
+    }
+    RenderedMessage {
+        title: template.title.to_string(),
+        body,
+    }
+}
+
+/// Plain-text email around a rendered notification
+pub fn email_body(locale: Locale, values: &TemplateValues, message: &RenderedMessage) -> String {
+    let (greeting, details, sign_off) = match locale {
+        Locale::Id => ("Halo", "Lihat detailnya di", "Salam,\nTim SaaS UMKM"),
+        Locale::En => ("Hi", "See the details at", "Regards,\nThe SaaS UMKM team"),
+    };
+    let mut text = format!("{} {},\n\n{}\n\n", greeting, values.name, message.body);
+    if let Some(link) = &values.link {
+        text.push_str(&format!("{} {}\n\n", details, link));
+    }
+    text.push_str(sign_off);
+    text
+}
+
+/// WhatsApp text around a rendered notification, with the title in bold
+pub fn whatsapp_body(values: &TemplateValues, message: &RenderedMessage) -> String {
+    let mut text = format!("*{}*\n\n{}", message.title, message.body);
+    if let Some(link) = &values.link {
+        text.push_str(&format!("\n\n{}", link));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::domain::value_objects::Money;
+
+    fn values() -> TemplateValues {
+        TemplateValues {
+            name: "Budi".to_string(),
+            license: "SIUP Toko Makmur".to_string(),
+            license_type: "SIUP".to_string(),
+            license_number: Some("SIUP-2024-000001".to_string()),
+            // 23:30 UTC is already the next day in Jakarta
+            expiry_date: Some(Utc.with_ymd_and_hms(2027, 8, 16, 23, 30, 0).unwrap()),
+            invoice: "SUB/2026/000042".to_string(),
+            amount: Money::idr(16_539_000).rupiah(),
+            due_date: Some(Utc.with_ymd_and_hms(2026, 11, 7, 3, 0, 0).unwrap()),
+            plan: "Basic".to_string(),
+            notes: None,
+            link: Some("https://app.example.id/licenses/1".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_every_event_renders_in_both_languages() {
+        for event in NotificationEvent::ALL {
+            for locale in [Locale::Id, Locale::En] {
+                let message = render(event, locale, &values());
+                assert!(!message.title.is_empty());
+                assert!(!message.body.contains('{'), "{event} {locale}: {}", message.body);
+            }
+        }
+    }
+
+    #[test]
+    fn test_placeholders_and_notes() {
+        let mut values = values();
+        let message = render(NotificationEvent::LicenseApproved, Locale::Id, &values);
+        assert_eq!(message.title, "Izin disetujui");
+        assert_eq!(
+            message.body,
+            "Permohonan SIUP \"SIUP Toko Makmur\" telah disetujui dengan nomor izin \
+             SIUP-2024-000001, berlaku hingga 17-08-2027."
+        );
+
+        values.notes = Some("Lampirkan akta pendirian".to_string());
+        let message = render(NotificationEvent::DocumentsRequested, Locale::En, &values);
+        assert!(message.body.ends_with("\nNotes: Lampirkan akta pendirian"));
+
+        values.license_number = None;
+        values.expiry_date = None;
+        let message = render(NotificationEvent::LicenseRenewed, Locale::En, &values);
+        assert!(message.body.contains("license - (\"SIUP Toko Makmur\")"));
+        assert!(message.body.ends_with("until -.\nNotes: Lampirkan akta pendirian"));
+    }
+
+    #[test]
+    fn test_invoice_placeholders() {
+        let message = render(NotificationEvent::InvoiceIssued, Locale::Id, &values());
+        assert_eq!(
+            message.body,
+            "Tagihan SUB/2026/000042 untuk paket Basic sebesar Rp 165.390 (termasuk PPN) \
+             jatuh tempo pada 07-11-2026."
+        );
+        assert_eq!(Money::idr(123_456_789).rupiah(), "Rp 1.234.567,89");
+        assert_eq!(Money::idr(0).rupiah(), "Rp 0");
+    }
+
+    #[test]
+    fn test_channel_wrappers() {
+        let values = values();
+        let message = render(NotificationEvent::LicenseRejected, Locale::En, &values);
+
+        let email = email_body(Locale::En, &values, &message);
+        assert!(email.starts_with("Hi Budi,\n\nYour SIUP application"));
+        assert!(email.contains("See the details at https://app.example.id/licenses/1"));
+
+        let whatsapp = whatsapp_body(&values, &message);
+        assert!(whatsapp.starts_with("*License rejected*\n\n"));
+        assert!(whatsapp.ends_with("\n\nhttps://app.example.id/licenses/1"));
+    }
+}
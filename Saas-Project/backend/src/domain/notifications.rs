@@ -0,0 +1,523 @@
+// Notification center domain
+// Events in a license's life are announced to the applicant, and billing
+// events to the company's owner, over the channels they chose for that event:
+// the in-app inbox, email or WhatsApp. Without a choice, in-app and email are
+// on and WhatsApp is off, since it needs a number.
+// Messages are rendered once, in the user's language, when the event happens.
+// In-app notifications are stored directly; email and WhatsApp messages are
+// queued as deliveries and retried with exponential backoff until they are
+// sent or run out of attempts.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::shared::errors::{AppError, AppResult};
+
+/// Something a user can be notified about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEvent {
+    LicenseApproved,
+    LicenseRejected,
+    /// The reviewer needs more documents before deciding
+    DocumentsRequested,
+    LicenseSuspended,
+    LicenseReinstated,
+    LicenseRenewed,
+    /// A reviewer left the applicant a comment
+    CommentReceived,
+    /// Someone asked the user to read a comment
+    CommentMention,
+    /// A subscription period was invoiced
+    InvoiceIssued,
+    /// The payment gateway declined a payment of an invoice
+    InvoicePaymentFailed,
+    /// A dunning reminder for an invoice past its due date
+    InvoiceOverdue,
+    /// An invoice was written off and the company moved to the free plan
+    SubscriptionDowngraded,
+}
+
+impl NotificationEvent {
+    pub const ALL: [NotificationEvent; 12] = [
+        NotificationEvent::LicenseApproved,
+        NotificationEvent::LicenseRejected,
+        NotificationEvent::DocumentsRequested,
+        NotificationEvent::LicenseSuspended,
+        NotificationEvent::LicenseReinstated,
+        NotificationEvent::LicenseRenewed,
+        NotificationEvent::CommentReceived,
+        NotificationEvent::CommentMention,
+        NotificationEvent::InvoiceIssued,
+        NotificationEvent::InvoicePaymentFailed,
+        NotificationEvent::InvoiceOverdue,
+        NotificationEvent::SubscriptionDowngraded,
+    ];
+}
+
+impl fmt::Display for NotificationEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            NotificationEvent::LicenseApproved => "license_approved",
+            NotificationEvent::LicenseRejected => "license_rejected",
+            NotificationEvent::DocumentsRequested => "documents_requested",
+            NotificationEvent::LicenseSuspended => "license_suspended",
+            NotificationEvent::LicenseReinstated => "license_reinstated",
+            NotificationEvent::LicenseRenewed => "license_renewed",
+            NotificationEvent::CommentReceived => "comment_received",
+            NotificationEvent::CommentMention => "comment_mention",
+            NotificationEvent::InvoiceIssued => "invoice_issued",
+            NotificationEvent::InvoicePaymentFailed => "invoice_payment_failed",
+            NotificationEvent::InvoiceOverdue => "invoice_overdue",
+            NotificationEvent::SubscriptionDowngraded => "subscription_downgraded",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for NotificationEvent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NotificationEvent::ALL
+            .into_iter()
+            .find(|event| event.to_string() == s)
+            .ok_or_else(|| format!("Unknown notification event: {}", s))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationChannel {
+    InApp,
+    Email,
+    #[serde(rename = "whatsapp")]
+    WhatsApp,
+}
+
+impl NotificationChannel {
+    pub const ALL: [NotificationChannel; 3] = [
+        NotificationChannel::InApp,
+        NotificationChannel::Email,
+        NotificationChannel::WhatsApp,
+    ];
+
+    /// Whether the channel is used when the user has not chosen
+    pub fn enabled_by_default(&self) -> bool {
+        !matches!(self, NotificationChannel::WhatsApp)
+    }
+}
+
+impl fmt::Display for NotificationChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            NotificationChannel::InApp => "in_app",
+            NotificationChannel::Email => "email",
+            NotificationChannel::WhatsApp => "whatsapp",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for NotificationChannel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "in_app" => Ok(NotificationChannel::InApp),
+            "email" => Ok(NotificationChannel::Email),
+            "whatsapp" => Ok(NotificationChannel::WhatsApp),
+            _ => Err(format!("Unknown notification channel: {}", s)),
+        }
+    }
+}
+
+/// Language messages are written in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Locale {
+    /// Bahasa Indonesia
+    #[default]
+    Id,
+    En,
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Locale::Id => "id",
+            Locale::En => "en",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "id" => Ok(Locale::Id),
+            "en" => Ok(Locale::En),
+            _ => Err(format!("Unknown locale: {}", s)),
+        }
+    }
+}
+
+/// A user's language and where WhatsApp messages go
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotificationSettings {
+    pub user_id: Uuid,
+    pub locale: Locale,
+    /// Normalised by `PhoneNumber`, e.g. `+6281234567890`
+    pub whatsapp_number: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl NotificationSettings {
+    /// Settings of a user who never changed them
+    pub fn defaults(user_id: Uuid) -> Self {
+        Self {
+            user_id,
+            locale: Locale::default(),
+            whatsapp_number: None,
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+/// A user's choice for one channel of one event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelPreference {
+    pub event: NotificationEvent,
+    pub channel: NotificationChannel,
+    pub enabled: bool,
+}
+
+/// Whether `channel` is on for `event`, given the choices a user made
+pub fn channel_enabled(
+    preferences: &[ChannelPreference],
+    event: NotificationEvent,
+    channel: NotificationChannel,
+) -> bool {
+    preferences
+        .iter()
+        .find(|p| p.event == event && p.channel == channel)
+        .map_or(channel.enabled_by_default(), |p| p.enabled)
+}
+
+/// An entry of a user's in-app inbox
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub event: NotificationEvent,
+    pub title: String,
+    pub body: String,
+    /// Where in the app the notification leads
+    pub link: Option<String>,
+    pub license_id: Option<Uuid>,
+    pub read_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Notification {
+    pub fn is_read(&self) -> bool {
+        self.read_at.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Waiting for its first or next attempt
+    Pending,
+    Sent,
+    /// Out of attempts
+    Failed,
+}
+
+impl fmt::Display for DeliveryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Sent => "sent",
+            DeliveryStatus::Failed => "failed",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for DeliveryStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(DeliveryStatus::Pending),
+            "sent" => Ok(DeliveryStatus::Sent),
+            "failed" => Ok(DeliveryStatus::Failed),
+            _ => Err(format!("Unknown delivery status: {}", s)),
+        }
+    }
+}
+
+/// A message queued for an external channel
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotificationDelivery {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub event: NotificationEvent,
+    /// Email or WhatsApp; in-app notifications need no delivery
+    pub channel: NotificationChannel,
+    /// Email address or WhatsApp number
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    /// When the next attempt is due; while one is under way, when it may be
+    /// taken over because the sender that claimed it is presumed dead
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub sent_at: Option<DateTime<Utc>>,
+}
+
+impl NotificationDelivery {
+    pub fn new(
+        user_id: Uuid,
+        event: NotificationEvent,
+        channel: NotificationChannel,
+        recipient: String,
+        subject: String,
+        body: String,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            event,
+            channel,
+            recipient,
+            subject,
+            body,
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            next_attempt_at: now,
+            last_error: None,
+            created_at: now,
+            sent_at: None,
+        }
+    }
+
+    pub fn mark_sent(&mut self, at: DateTime<Utc>) {
+        self.attempts += 1;
+        self.status = DeliveryStatus::Sent;
+        self.sent_at = Some(at);
+        self.last_error = None;
+    }
+
+    /// Schedules the next attempt after a failed one, or gives up
+    pub fn mark_failed(&mut self, error: String, at: DateTime<Utc>, retry: &RetryPolicy) {
+        self.attempts += 1;
+        self.last_error = Some(error);
+        match retry.delay_after(self.attempts) {
+            Some(delay) => self.next_attempt_at = at + delay,
+            None => self.status = DeliveryStatus::Failed,
+        }
+    }
+}
+
+/// How often and how far apart failed deliveries are retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts in total, the first included
+    pub max_attempts: i32,
+    /// Wait after the first failure, doubled after each further one
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 6,
+            base_delay: Duration::seconds(30),
+            max_delay: Duration::hours(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before the next attempt after `attempts` failed ones; `None` once
+    /// they are used up
+    pub fn delay_after(&self, attempts: i32) -> Option<Duration> {
+        if attempts >= self.max_attempts {
+            return None;
+        }
+        let doublings = (attempts - 1).clamp(0, 20) as u32;
+        let delay = self.base_delay * 2i32.pow(doublings);
+        Some(delay.min(self.max_delay))
+    }
+}
+
+/// A file sent along with a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    pub file_name: String,
+    pub content_type: String,
+    pub content: Vec<u8>,
+}
+
+/// Sends messages over one external channel
+#[async_trait]
+pub trait MessageSender: Send + Sync {
+    async fn send(&self, recipient: &str, subject: &str, body: &str) -> AppResult<()>;
+
+    /// Sends with files attached; channels that cannot carry files refuse
+    /// unless there are none
+    async fn send_with_attachments(
+        &self,
+        recipient: &str,
+        subject: &str,
+        body: &str,
+        attachments: &[Attachment],
+    ) -> AppResult<()> {
+        if attachments.is_empty() {
+            return self.send(recipient, subject, body).await;
+        }
+        Err(AppError::NotImplemented(
+            "This channel cannot send attachments".to_string(),
+        ))
+    }
+}
+
+#[async_trait]
+pub trait NotificationRepository: Send + Sync {
+    /// The user's settings, or the defaults if they never saved any
+    async fn settings(&self, user_id: Uuid) -> AppResult<NotificationSettings>;
+    async fn save_settings(&self, settings: &NotificationSettings) -> AppResult<NotificationSettings>;
+    /// The choices the user made; channels they left alone are not listed
+    async fn preferences(&self, user_id: Uuid) -> AppResult<Vec<ChannelPreference>>;
+    async fn set_preference(&self, user_id: Uuid, preference: &ChannelPreference) -> AppResult<()>;
+
+    async fn create_notification(&self, notification: &Notification) -> AppResult<Notification>;
+    /// The user's notifications, newest first
+    async fn notifications(
+        &self,
+        user_id: Uuid,
+        unread_only: bool,
+        limit: i64,
+    ) -> AppResult<Vec<Notification>>;
+    async fn unread_count(&self, user_id: Uuid) -> AppResult<i64>;
+    /// Marks one of the user's notifications read or unread; `None` if the
+    /// user has no such notification
+    async fn set_read(
+        &self,
+        user_id: Uuid,
+        notification_id: Uuid,
+        read: bool,
+    ) -> AppResult<Option<Notification>>;
+    /// Marks every unread notification of the user read, returning how many
+    async fn mark_all_read(&self, user_id: Uuid) -> AppResult<u64>;
+
+    async fn enqueue_delivery(&self, delivery: &NotificationDelivery) -> AppResult<()>;
+    /// Takes up to `limit` pending deliveries due at `now`, oldest first,
+    /// moving their next attempt to `lease_until` so that no other sender
+    /// takes them meanwhile
+    async fn claim_due_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+        limit: i64,
+    ) -> AppResult<Vec<NotificationDelivery>>;
+    /// Saves the outcome of an attempt
+    async fn record_attempt(&self, delivery: &NotificationDelivery) -> AppResult<()>;
+    /// The most recent deliveries, newest first, optionally in one status
+    async fn deliveries(
+        &self,
+        status: Option<DeliveryStatus>,
+        limit: i64,
+    ) -> AppResult<Vec<NotificationDelivery>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_leave_whatsapp_off_until_chosen() {
+        let event = NotificationEvent::LicenseApproved;
+        assert!(channel_enabled(&[], event, NotificationChannel::InApp));
+        assert!(channel_enabled(&[], event, NotificationChannel::Email));
+        assert!(!channel_enabled(&[], event, NotificationChannel::WhatsApp));
+
+        let choices = [
+            ChannelPreference {
+                event,
+                channel: NotificationChannel::WhatsApp,
+                enabled: true,
+            },
+            ChannelPreference {
+                event,
+                channel: NotificationChannel::Email,
+                enabled: false,
+            },
+        ];
+        assert!(channel_enabled(&choices, event, NotificationChannel::WhatsApp));
+        assert!(!channel_enabled(&choices, event, NotificationChannel::Email));
+        // Choices are per event
+        assert!(channel_enabled(
+            &choices,
+            NotificationEvent::LicenseRejected,
+            NotificationChannel::Email
+        ));
+    }
+
+    #[test]
+    fn test_failed_deliveries_back_off_exponentially_then_give_up() {
+        let retry = RetryPolicy {
+            max_attempts: 4,
+            base_delay: Duration::seconds(30),
+            max_delay: Duration::seconds(90),
+        };
+        assert_eq!(retry.delay_after(1), Some(Duration::seconds(30)));
+        assert_eq!(retry.delay_after(2), Some(Duration::seconds(60)));
+        assert_eq!(retry.delay_after(3), Some(Duration::seconds(90)));
+        assert_eq!(retry.delay_after(4), None);
+
+        let at = Utc::now();
+        let mut delivery = NotificationDelivery::new(
+            Uuid::new_v4(),
+            NotificationEvent::LicenseApproved,
+            NotificationChannel::Email,
+            "budi@example.id".to_string(),
+            "Izin disetujui".to_string(),
+            "Halo".to_string(),
+        );
+        for attempt in 1..=3 {
+            delivery.mark_failed("timeout".to_string(), at, &retry);
+            assert_eq!(delivery.status, DeliveryStatus::Pending, "after attempt {attempt}");
+        }
+        assert_eq!(delivery.next_attempt_at, at + Duration::seconds(90));
+        delivery.mark_failed("timeout".to_string(), at, &retry);
+        assert_eq!((delivery.status, delivery.attempts), (DeliveryStatus::Failed, 4));
+        assert_eq!(delivery.last_error.as_deref(), Some("timeout"));
+    }
+
+    #[test]
+    fn test_names_round_trip() {
+        for event in NotificationEvent::ALL {
+            assert_eq!(event.to_string().parse(), Ok(event));
+        }
+        for channel in NotificationChannel::ALL {
+            assert_eq!(channel.to_string().parse(), Ok(channel));
+        }
+        assert_eq!("en".parse(), Ok(Locale::En));
+        assert_eq!("sent".parse(), Ok(DeliveryStatus::Sent));
+    }
+}
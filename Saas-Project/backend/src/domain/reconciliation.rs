@@ -0,0 +1,1183 @@
+// Bank reconciliation domain - comparing a bank account's books with its statement
+// A session covers one statement of a bank-type FinancialAccount. Statement lines
+// are entered or imported, matched to completed transactions (automatically or by
+// hand) and unexplained lines are booked as adjustments. Completing a session
+// requires the cleared balance to equal the statement closing balance and locks
+// every matched transaction against further edits.
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::domain::finance::{
+    FinancialAccount, FinancialAccountRepository, Transaction, TransactionStatus, TransactionType,
+};
+use crate::domain::imports::{
+    parse_rows, select_mapping, statement_closing_balance, statement_period, ColumnMapping,
+    ImportRow, ImportRowStatus, ParsedTransaction, StatementLayout, StatementPeriod, Table,
+};
+use crate::domain::value_objects::Money;
+use crate::shared::errors::AppError;
+
+/// Days a bank may book a transaction before or after the date in the books
+pub const DEFAULT_MATCH_WINDOW_DAYS: i64 = 3;
+
+/// Largest window accepted for automatic matching
+pub const MAX_MATCH_WINDOW_DAYS: i64 = 31;
+
+/// Only bank accounts have statements to reconcile against
+const BANK_ACCOUNT_TYPE: &str = "Bank";
+
+// ----------------
+// Value Objects
+// ----------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconciliationStatus {
+    Open,
+    Completed,
+}
+
+impl fmt::Display for ReconciliationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReconciliationStatus::Open => write!(f, "open"),
+            ReconciliationStatus::Completed => write!(f, "completed"),
+        }
+    }
+}
+
+impl FromStr for ReconciliationStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "open" => Ok(ReconciliationStatus::Open),
+            "completed" => Ok(ReconciliationStatus::Completed),
+            _ => Err(format!("Invalid reconciliation status: {}", s)),
+        }
+    }
+}
+
+/// How a statement line was cleared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchType {
+    Auto,
+    Manual,
+    Adjustment,
+}
+
+impl fmt::Display for MatchType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchType::Auto => write!(f, "auto"),
+            MatchType::Manual => write!(f, "manual"),
+            MatchType::Adjustment => write!(f, "adjustment"),
+        }
+    }
+}
+
+impl FromStr for MatchType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(MatchType::Auto),
+            "manual" => Ok(MatchType::Manual),
+            "adjustment" => Ok(MatchType::Adjustment),
+            _ => Err(format!("Invalid match type: {}", s)),
+        }
+    }
+}
+
+// ----------------
+// Entities
+// ----------------
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationSession {
+    pub id: Uuid,
+    pub company_id: Uuid,
+    pub account_id: Uuid,
+    pub statement_start_date: Option<NaiveDate>,
+    pub statement_end_date: NaiveDate,
+    /// Closing balance of the previous session, in the smallest currency unit
+    pub opening_balance: i64,
+    /// Balance printed on the statement; entered or read from the imported file
+    pub closing_balance: Option<i64>,
+    pub status: ReconciliationStatus,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub completed_by: Option<Uuid>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl ReconciliationSession {
+    pub fn new(
+        company_id: Uuid,
+        account_id: Uuid,
+        statement_start_date: Option<NaiveDate>,
+        statement_end_date: NaiveDate,
+        opening_balance: i64,
+        closing_balance: Option<i64>,
+        created_by: Uuid,
+    ) -> Result<Self, AppError> {
+        if statement_start_date.is_some_and(|start| start > statement_end_date) {
+            return Err(AppError::Validation(
+                "Statement start date must not be after the end date".to_string(),
+            ));
+        }
+
+        let now = Utc::now();
+        Ok(Self {
+            id: Uuid::new_v4(),
+            company_id,
+            account_id,
+            statement_start_date,
+            statement_end_date,
+            opening_balance,
+            closing_balance,
+            status: ReconciliationStatus::Open,
+            created_by,
+            created_at: now,
+            updated_at: now,
+            completed_by: None,
+            completed_at: None,
+        })
+    }
+
+    pub fn ensure_open(&self) -> Result<(), AppError> {
+        if self.status != ReconciliationStatus::Open {
+            return Err(AppError::Conflict("Reconciliation has already been completed".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Transactions dated up to the end of the statement day are candidates
+    pub fn cutoff(&self) -> DateTime<Utc> {
+        (self.statement_end_date + chrono::Duration::days(1))
+            .and_time(NaiveTime::MIN)
+            .and_utc()
+    }
+
+    pub fn set_closing_balance(&mut self, closing_balance: i64) -> Result<(), AppError> {
+        self.ensure_open()?;
+        self.closing_balance = Some(closing_balance);
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+
+    pub fn mark_completed(&mut self, completed_by: Uuid) {
+        let now = Utc::now();
+        self.status = ReconciliationStatus::Completed;
+        self.completed_by = Some(completed_by);
+        self.completed_at = Some(now);
+        self.updated_at = now;
+    }
+}
+
+/// A statement line entered by hand
+#[derive(Debug, Clone, Deserialize)]
+pub struct StatementLineInput {
+    pub line_date: NaiveDate,
+    /// Signed, in the smallest currency unit; credits to the account are positive
+    pub amount: i64,
+    pub description: String,
+    pub reference: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatementLine {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub line_date: NaiveDate,
+    /// Signed, in the smallest currency unit; credits to the account are positive
+    pub amount: i64,
+    pub description: String,
+    pub reference: Option<String>,
+    pub transaction_id: Option<Uuid>,
+    pub match_type: Option<MatchType>,
+    pub matched_at: Option<DateTime<Utc>>,
+}
+
+impl StatementLine {
+    pub fn new(session_id: Uuid, input: StatementLineInput) -> Result<Self, AppError> {
+        let description = input.description.trim().to_string();
+        if description.is_empty() {
+            return Err(AppError::Validation("Statement line description is required".to_string()));
+        }
+        if input.amount == 0 {
+            return Err(AppError::Validation("Statement line amount must not be zero".to_string()));
+        }
+
+        Ok(Self {
+            id: Uuid::new_v4(),
+            session_id,
+            line_date: input.line_date,
+            amount: input.amount,
+            description,
+            reference: input.reference.map(|r| r.trim().to_string()).filter(|r| !r.is_empty()),
+            transaction_id: None,
+            match_type: None,
+            matched_at: None,
+        })
+    }
+
+    pub fn from_parsed(session_id: Uuid, parsed: &ParsedTransaction) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            session_id,
+            line_date: parsed.transaction_date,
+            amount: parsed.signed_amount(),
+            description: parsed.description.clone(),
+            reference: parsed.reference_number.clone(),
+            transaction_id: None,
+            match_type: None,
+            matched_at: None,
+        }
+    }
+
+    pub fn is_matched(&self) -> bool {
+        self.transaction_id.is_some()
+    }
+
+    /// Same key as an imported row, so uploading a statement twice adds its lines once
+    pub fn key(&self) -> String {
+        let reference = self
+            .reference
+            .as_deref()
+            .unwrap_or(&self.description)
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_uppercase();
+        format!("{}|{}|{}", self.line_date, self.amount, reference)
+    }
+
+    pub fn match_to(&mut self, transaction_id: Uuid, match_type: MatchType) {
+        self.transaction_id = Some(transaction_id);
+        self.match_type = Some(match_type);
+        self.matched_at = Some(Utc::now());
+    }
+
+    pub fn unmatch(&mut self) {
+        self.transaction_id = None;
+        self.match_type = None;
+        self.matched_at = None;
+    }
+
+    /// Completed transaction booking an unexplained line, such as a bank fee
+    pub fn adjustment(
+        &self,
+        account: &FinancialAccount,
+        description: Option<String>,
+        category_id: Option<Uuid>,
+        created_by: Uuid,
+    ) -> Transaction {
+        let transaction_type = if self.amount > 0 {
+            TransactionType::Income
+        } else {
+            TransactionType::Expense
+        };
+        let description = description
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty())
+            .unwrap_or_else(|| self.description.clone());
+
+        let mut transaction = Transaction::new(
+            account.company_id,
+            self.line_date.and_time(NaiveTime::MIN).and_utc(),
+            transaction_type,
+            Money::new(self.amount.abs(), account.currency.clone()),
+            description,
+            account.id,
+            created_by,
+        );
+        transaction.reference_number = self.reference.clone();
+        transaction.category_id = category_id;
+        transaction.status = TransactionStatus::Completed;
+        transaction.tags = vec!["reconciliation_adjustment".to_string()];
+        transaction.metadata = Some(HashMap::from([
+            ("reconciliation_id".to_string(), serde_json::json!(self.session_id)),
+            ("statement_line_id".to_string(), serde_json::json!(self.id)),
+        ]));
+        transaction
+    }
+}
+
+// ----------------
+// Matching
+// ----------------
+
+fn normalize_reference(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// A line and a transaction share a reference when either reference appears in
+/// the other side's reference or description
+fn references_match(line: &StatementLine, transaction: &Transaction) -> bool {
+    let line_text = normalize_reference(&format!(
+        "{} {}",
+        line.reference.as_deref().unwrap_or_default(),
+        line.description
+    ));
+    let transaction_text = normalize_reference(&format!(
+        "{} {}",
+        transaction.reference_number.as_deref().unwrap_or_default(),
+        transaction.description
+    ));
+
+    let contained = |reference: Option<&str>, text: &str| {
+        reference
+            .map(normalize_reference)
+            // Short references such as "1" would match almost anything
+            .is_some_and(|r| r.len() >= 4 && text.contains(&r))
+    };
+
+    contained(line.reference.as_deref(), &transaction_text)
+        || contained(transaction.reference_number.as_deref(), &line_text)
+}
+
+/// Proposes line/transaction pairs for unmatched lines.
+///
+/// Candidates must have the same signed amount and a date within the window.
+/// Lines sharing a reference with a candidate are paired first; the remaining
+/// lines are only paired when a single candidate is closest in date, so
+/// ambiguous repeats (two identical transfers) are left for manual matching.
+pub fn auto_match(
+    lines: &[StatementLine],
+    transactions: &[Transaction],
+    window_days: i64,
+) -> Vec<(Uuid, Uuid)> {
+    let taken: HashSet<Uuid> = lines.iter().filter_map(|l| l.transaction_id).collect();
+    let mut available: Vec<&Transaction> = transactions
+        .iter()
+        .filter(|t| !t.is_reconciled() && !taken.contains(&t.id.value()))
+        .collect();
+    let mut pairs = Vec::new();
+    let mut paired_lines = HashSet::new();
+
+    for by_reference in [true, false] {
+        for line in lines.iter().filter(|l| !l.is_matched()) {
+            if paired_lines.contains(&line.id) {
+                continue;
+            }
+
+            let candidates: Vec<(usize, i64)> = available
+                .iter()
+                .enumerate()
+                .filter(|(_, t)| t.signed_amount() == line.amount)
+                .map(|(i, t)| (i, (t.transaction_date.date_naive() - line.line_date).num_days().abs()))
+                .filter(|(_, distance)| *distance <= window_days)
+                .collect();
+
+            let pick = if by_reference {
+                candidates
+                    .iter()
+                    .filter(|(i, _)| references_match(line, available[*i]))
+                    .min_by_key(|(_, distance)| *distance)
+                    .map(|(i, _)| *i)
+            } else {
+                let closest = candidates.iter().map(|(_, distance)| *distance).min();
+                let mut nearest = candidates.iter().filter(|(_, d)| Some(*d) == closest);
+                match (nearest.next(), nearest.next()) {
+                    (Some((i, _)), None) => Some(*i),
+                    _ => None,
+                }
+            };
+
+            if let Some(index) = pick {
+                let transaction = available.remove(index);
+                pairs.push((line.id, transaction.id.value()));
+                paired_lines.insert(line.id);
+            }
+        }
+    }
+
+    pairs
+}
+
+// ----------------
+// Report
+// ----------------
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchedItem {
+    pub line: StatementLine,
+    pub transaction: Transaction,
+}
+
+/// State of a session: what has been cleared and what is outstanding on
+/// either side. Amounts are in the smallest currency unit.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReconciliationReport {
+    pub session: ReconciliationSession,
+    pub account_name: String,
+    /// Current balance of the account in the books
+    pub book_balance: i64,
+    /// Opening balance plus the matched transactions
+    pub cleared_balance: i64,
+    /// Statement closing balance minus the cleared balance
+    pub difference: Option<i64>,
+    pub matched: Vec<MatchedItem>,
+    /// Statement lines without a transaction in the books
+    pub unmatched_lines: Vec<StatementLine>,
+    /// Transactions in the books not yet seen on a statement
+    pub unmatched_transactions: Vec<Transaction>,
+    pub can_complete: bool,
+}
+
+impl ReconciliationReport {
+    /// `transactions` are the session's candidates: unreconciled transactions up
+    /// to the statement date plus those locked by this session. A match whose
+    /// transaction disappeared or changed amount counts as unmatched.
+    pub fn build(
+        session: ReconciliationSession,
+        account: &FinancialAccount,
+        lines: Vec<StatementLine>,
+        transactions: Vec<Transaction>,
+    ) -> Self {
+        let mut by_id: HashMap<Uuid, Transaction> =
+            transactions.into_iter().map(|t| (t.id.value(), t)).collect();
+
+        let mut matched = Vec::new();
+        let mut unmatched_lines = Vec::new();
+        for line in lines {
+            let transaction = line
+                .transaction_id
+                .filter(|id| by_id.get(id).is_some_and(|t| t.signed_amount() == line.amount))
+                .and_then(|id| by_id.remove(&id));
+            match transaction {
+                Some(transaction) => matched.push(MatchedItem { line, transaction }),
+                None => unmatched_lines.push(line),
+            }
+        }
+
+        let mut unmatched_transactions: Vec<Transaction> = by_id.into_values().collect();
+        unmatched_transactions.sort_by_key(|t| (t.transaction_date, t.created_at));
+        matched.sort_by_key(|m| m.line.line_date);
+        unmatched_lines.sort_by_key(|l| l.line_date);
+
+        let cleared_balance = session.opening_balance
+            + matched.iter().map(|m| m.transaction.signed_amount()).sum::<i64>();
+        let difference = session.closing_balance.map(|closing| closing - cleared_balance);
+        let can_complete = session.status == ReconciliationStatus::Open
+            && difference == Some(0)
+            && unmatched_lines.is_empty();
+
+        Self {
+            account_name: account.name.clone(),
+            book_balance: account.balance.amount,
+            session,
+            cleared_balance,
+            difference,
+            matched,
+            unmatched_lines,
+            unmatched_transactions,
+            can_complete,
+        }
+    }
+
+    pub fn matched_transaction_ids(&self) -> Vec<Uuid> {
+        self.matched.iter().map(|m| m.transaction.id.value()).collect()
+    }
+}
+
+/// Result of loading statement lines from a file
+#[derive(Debug, Clone, Serialize)]
+pub struct StatementImport {
+    pub report: ReconciliationReport,
+    pub added_lines: usize,
+    /// Lines already present in the session
+    pub skipped_lines: usize,
+    /// Rows that could not be read, with their messages
+    pub rejected_rows: Vec<ImportRow>,
+}
+
+// ----------------
+// Repository Interface
+// ----------------
+
+#[async_trait]
+pub trait ReconciliationRepository: Send + Sync {
+    async fn create_session(&self, session: &ReconciliationSession) -> Result<(), AppError>;
+    async fn find_session(
+        &self,
+        company_id: Uuid,
+        id: Uuid,
+    ) -> Result<Option<ReconciliationSession>, AppError>;
+    async fn update_session(&self, session: &ReconciliationSession) -> Result<(), AppError>;
+    /// Removes an open session with its lines; booked adjustments remain
+    async fn delete_session(&self, id: Uuid) -> Result<(), AppError>;
+    async fn open_session(&self, account_id: Uuid) -> Result<Option<ReconciliationSession>, AppError>;
+    async fn last_completed_session(
+        &self,
+        account_id: Uuid,
+    ) -> Result<Option<ReconciliationSession>, AppError>;
+
+    async fn add_lines(&self, lines: &[StatementLine]) -> Result<(), AppError>;
+    async fn list_lines(&self, session_id: Uuid) -> Result<Vec<StatementLine>, AppError>;
+    async fn delete_line(&self, session_id: Uuid, line_id: Uuid) -> Result<(), AppError>;
+    /// Stores the match columns of the given lines
+    async fn save_matches(&self, lines: &[StatementLine]) -> Result<(), AppError>;
+
+    /// Completed transactions of the account dated before the session cutoff
+    /// that are unreconciled or were reconciled by this session
+    async fn candidate_transactions(
+        &self,
+        session: &ReconciliationSession,
+    ) -> Result<Vec<Transaction>, AppError>;
+
+    /// Books the adjustment, applies it to the account balance and matches it
+    /// to the line in one database transaction
+    async fn record_adjustment(
+        &self,
+        transaction: &Transaction,
+        line: &StatementLine,
+    ) -> Result<(), AppError>;
+
+    /// Marks the session completed and locks the transactions; fails with a
+    /// conflict when one of them was reconciled elsewhere in the meantime
+    async fn complete_session(
+        &self,
+        session: &ReconciliationSession,
+        transaction_ids: &[Uuid],
+    ) -> Result<(), AppError>;
+}
+
+// ----------------
+// Domain Service
+// ----------------
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StartReconciliation {
+    pub account_id: Uuid,
+    pub statement_start_date: Option<NaiveDate>,
+    pub statement_end_date: NaiveDate,
+    /// Only for an account's first reconciliation; later sessions open at the
+    /// previous closing balance
+    pub opening_balance: Option<i64>,
+    pub closing_balance: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AdjustmentRequest {
+    pub description: Option<String>,
+    pub category_id: Option<Uuid>,
+}
+
+pub struct ReconciliationService {
+    reconciliation_repository: Arc<dyn ReconciliationRepository>,
+    account_repository: Arc<dyn FinancialAccountRepository>,
+}
+
+impl ReconciliationService {
+    pub fn new(
+        reconciliation_repository: Arc<dyn ReconciliationRepository>,
+        account_repository: Arc<dyn FinancialAccountRepository>,
+    ) -> Self {
+        Self {
+            reconciliation_repository,
+            account_repository,
+        }
+    }
+
+    pub async fn start(
+        &self,
+        company_id: Uuid,
+        user_id: Uuid,
+        request: StartReconciliation,
+    ) -> Result<ReconciliationReport, AppError> {
+        let account = self.account(company_id, request.account_id).await?;
+        if !account.is_active {
+            return Err(AppError::Validation("Account is not active".to_string()));
+        }
+        if !account.account_type.eq_ignore_ascii_case(BANK_ACCOUNT_TYPE) {
+            return Err(AppError::Validation(
+                "Only bank accounts can be reconciled".to_string(),
+            ));
+        }
+        if self.reconciliation_repository.open_session(account.id).await?.is_some() {
+            return Err(AppError::Conflict(
+                "The account already has an open reconciliation".to_string(),
+            ));
+        }
+
+        let opening_balance = match self
+            .reconciliation_repository
+            .last_completed_session(account.id)
+            .await?
+        {
+            Some(previous) => {
+                if request.statement_end_date <= previous.statement_end_date {
+                    return Err(AppError::Validation(format!(
+                        "The account is reconciled up to {}, choose a later statement date",
+                        previous.statement_end_date
+                    )));
+                }
+                let previous_closing = previous.closing_balance.unwrap_or_default();
+                if request.opening_balance.is_some_and(|opening| opening != previous_closing) {
+                    return Err(AppError::Validation(
+                        "Opening balance must equal the previous statement's closing balance"
+                            .to_string(),
+                    ));
+                }
+                previous_closing
+            }
+            None => request.opening_balance.ok_or_else(|| {
+                AppError::Validation(
+                    "opening_balance is required for the account's first reconciliation".to_string(),
+                )
+            })?,
+        };
+
+        let session = ReconciliationSession::new(
+            company_id,
+            account.id,
+            request.statement_start_date,
+            request.statement_end_date,
+            opening_balance,
+            request.closing_balance,
+            user_id,
+        )?;
+        self.reconciliation_repository.create_session(&session).await?;
+
+        self.build_report(session, &account).await
+    }
+
+    pub async fn report(&self, company_id: Uuid, id: Uuid) -> Result<ReconciliationReport, AppError> {
+        let session = self.session(company_id, id).await?;
+        let account = self.account(company_id, session.account_id).await?;
+        self.build_report(session, &account).await
+    }
+
+    pub async fn set_closing_balance(
+        &self,
+        company_id: Uuid,
+        id: Uuid,
+        closing_balance: i64,
+    ) -> Result<ReconciliationReport, AppError> {
+        let mut session = self.session(company_id, id).await?;
+        session.set_closing_balance(closing_balance)?;
+        self.reconciliation_repository.update_session(&session).await?;
+        self.report(company_id, id).await
+    }
+
+    pub async fn add_lines(
+        &self,
+        company_id: Uuid,
+        id: Uuid,
+        inputs: Vec<StatementLineInput>,
+    ) -> Result<ReconciliationReport, AppError> {
+        let session = self.session(company_id, id).await?;
+        session.ensure_open()?;
+        if inputs.is_empty() {
+            return Err(AppError::Validation("At least one statement line is required".to_string()));
+        }
+
+        let lines = inputs
+            .into_iter()
+            .map(|input| {
+                let line = StatementLine::new(session.id, input)?;
+                Self::check_line_date(&session, &line)?;
+                Ok(line)
+            })
+            .collect::<Result<Vec<_>, AppError>>()?;
+        self.reconciliation_repository.add_lines(&lines).await?;
+
+        self.report(company_id, id).await
+    }
+
+    /// Adds the lines of an uploaded statement. The closing balance printed on
+    /// the statement is taken over unless one was entered already.
+    pub async fn import_statement(
+        &self,
+        company_id: Uuid,
+        id: Uuid,
+        layout: Option<StatementLayout>,
+        mapping: Option<ColumnMapping>,
+        table: &Table,
+    ) -> Result<StatementImport, AppError> {
+        let mut session = self.session(company_id, id).await?;
+        session.ensure_open()?;
+
+        let (_, mapping) = select_mapping(layout, mapping, table)?;
+        let period = statement_period(table).or_else(|| {
+            session.statement_start_date.map(|start| StatementPeriod {
+                start,
+                end: session.statement_end_date,
+            })
+        });
+        let rows = parse_rows(table, &mapping, period.as_ref())?;
+
+        let mut known: HashSet<String> = self
+            .reconciliation_repository
+            .list_lines(session.id)
+            .await?
+            .iter()
+            .map(StatementLine::key)
+            .collect();
+
+        let mut lines = Vec::new();
+        let mut rejected_rows = Vec::new();
+        let mut skipped_lines = 0;
+        for mut row in rows {
+            let Some(parsed) = row.transaction.as_ref().filter(|_| row.status == ImportRowStatus::Valid)
+            else {
+                rejected_rows.push(row);
+                continue;
+            };
+            let line = StatementLine::from_parsed(session.id, parsed);
+            if let Err(AppError::Validation(message)) = Self::check_line_date(&session, &line) {
+                row.status = ImportRowStatus::Invalid;
+                row.messages.push(message);
+                rejected_rows.push(row);
+            } else if known.insert(line.key()) {
+                lines.push(line);
+            } else {
+                skipped_lines += 1;
+            }
+        }
+
+        if !lines.is_empty() {
+            self.reconciliation_repository.add_lines(&lines).await?;
+        }
+        if session.closing_balance.is_none() {
+            if let Some(closing) = statement_closing_balance(table, mapping.number_format) {
+                session.set_closing_balance(closing)?;
+                self.reconciliation_repository.update_session(&session).await?;
+            }
+        }
+
+        Ok(StatementImport {
+            added_lines: lines.len(),
+            skipped_lines,
+            rejected_rows,
+            report: self.report(company_id, id).await?,
+        })
+    }
+
+    pub async fn remove_line(
+        &self,
+        company_id: Uuid,
+        id: Uuid,
+        line_id: Uuid,
+    ) -> Result<ReconciliationReport, AppError> {
+        let session = self.session(company_id, id).await?;
+        session.ensure_open()?;
+        self.reconciliation_repository.delete_line(session.id, line_id).await?;
+        self.report(company_id, id).await
+    }
+
+    pub async fn auto_match(
+        &self,
+        company_id: Uuid,
+        id: Uuid,
+        window_days: Option<i64>,
+    ) -> Result<ReconciliationReport, AppError> {
+        let window_days = window_days.unwrap_or(DEFAULT_MATCH_WINDOW_DAYS);
+        if !(0..=MAX_MATCH_WINDOW_DAYS).contains(&window_days) {
+            return Err(AppError::Validation(format!(
+                "window_days must be between 0 and {}",
+                MAX_MATCH_WINDOW_DAYS
+            )));
+        }
+
+        let session = self.session(company_id, id).await?;
+        session.ensure_open()?;
+        let mut lines = self.reconciliation_repository.list_lines(session.id).await?;
+        let transactions = self.reconciliation_repository.candidate_transactions(&session).await?;
+
+        let pairs: HashMap<Uuid, Uuid> = auto_match(&lines, &transactions, window_days)
+            .into_iter()
+            .collect();
+        let mut changed = Vec::new();
+        for line in lines.iter_mut() {
+            if let Some(transaction_id) = pairs.get(&line.id) {
+                line.match_to(*transaction_id, MatchType::Auto);
+                changed.push(line.clone());
+            }
+        }
+        if !changed.is_empty() {
+            self.reconciliation_repository.save_matches(&changed).await?;
+        }
+
+        self.report(company_id, id).await
+    }
+
+    pub async fn match_line(
+        &self,
+        company_id: Uuid,
+        id: Uuid,
+        line_id: Uuid,
+        transaction_id: Uuid,
+    ) -> Result<ReconciliationReport, AppError> {
+        let session = self.session(company_id, id).await?;
+        session.ensure_open()?;
+        let lines = self.reconciliation_repository.list_lines(session.id).await?;
+        let mut line = Self::line(&lines, line_id)?;
+        if line.is_matched() {
+            return Err(AppError::Conflict("Statement line is already matched".to_string()));
+        }
+        if lines.iter().any(|l| l.transaction_id == Some(transaction_id)) {
+            return Err(AppError::Conflict(
+                "Transaction is already matched to another statement line".to_string(),
+            ));
+        }
+
+        let transaction = self
+            .reconciliation_repository
+            .candidate_transactions(&session)
+            .await?
+            .into_iter()
+            .find(|t| t.id.value() == transaction_id)
+            .ok_or_else(|| {
+                AppError::NotFound(
+                    "Transaction not found among the account's unreconciled transactions".to_string(),
+                )
+            })?;
+        if transaction.signed_amount() != line.amount {
+            return Err(AppError::Validation(
+                "Transaction amount differs from the statement line; correct the transaction or book an adjustment"
+                    .to_string(),
+            ));
+        }
+
+        line.match_to(transaction_id, MatchType::Manual);
+        self.reconciliation_repository.save_matches(&[line]).await?;
+        self.report(company_id, id).await
+    }
+
+    pub async fn unmatch_line(
+        &self,
+        company_id: Uuid,
+        id: Uuid,
+        line_id: Uuid,
+    ) -> Result<ReconciliationReport, AppError> {
+        let session = self.session(company_id, id).await?;
+        session.ensure_open()?;
+        let lines = self.reconciliation_repository.list_lines(session.id).await?;
+        let mut line = Self::line(&lines, line_id)?;
+
+        line.unmatch();
+        self.reconciliation_repository.save_matches(&[line]).await?;
+        self.report(company_id, id).await
+    }
+
+    /// Books an unmatched statement line, such as a bank fee or interest, as a
+    /// transaction and clears the line with it
+    pub async fn create_adjustment(
+        &self,
+        company_id: Uuid,
+        user_id: Uuid,
+        id: Uuid,
+        line_id: Uuid,
+        request: AdjustmentRequest,
+    ) -> Result<ReconciliationReport, AppError> {
+        let session = self.session(company_id, id).await?;
+        session.ensure_open()?;
+        let account = self.account(company_id, session.account_id).await?;
+        let lines = self.reconciliation_repository.list_lines(session.id).await?;
+        let mut line = Self::line(&lines, line_id)?;
+        if line.is_matched() {
+            return Err(AppError::Conflict("Statement line is already matched".to_string()));
+        }
+
+        let transaction = line.adjustment(&account, request.description, request.category_id, user_id);
+        line.match_to(transaction.id.value(), MatchType::Adjustment);
+        self.reconciliation_repository
+            .record_adjustment(&transaction, &line)
+            .await?;
+
+        self.build_report(session, &account).await
+    }
+
+    pub async fn complete(
+        &self,
+        company_id: Uuid,
+        user_id: Uuid,
+        id: Uuid,
+    ) -> Result<ReconciliationReport, AppError> {
+        let report = self.report(company_id, id).await?;
+        report.session.ensure_open()?;
+
+        if report.session.closing_balance.is_none() {
+            return Err(AppError::Validation("Enter the statement closing balance first".to_string()));
+        }
+        if !report.unmatched_lines.is_empty() {
+            return Err(AppError::Validation(format!(
+                "{} statement line(s) are not matched yet",
+                report.unmatched_lines.len()
+            )));
+        }
+        if let Some(difference) = report.difference.filter(|d| *d != 0) {
+            return Err(AppError::Validation(format!(
+                "Cleared balance differs from the statement by {:.2}",
+                Money::idr(difference).to_f64()
+            )));
+        }
+
+        let mut session = report.session.clone();
+        session.mark_completed(user_id);
+        self.reconciliation_repository
+            .complete_session(&session, &report.matched_transaction_ids())
+            .await?;
+
+        self.report(company_id, id).await
+    }
+
+    /// Discards an open session; adjustments already booked stay in the books
+    pub async fn cancel(&self, company_id: Uuid, id: Uuid) -> Result<(), AppError> {
+        let session = self.session(company_id, id).await?;
+        session.ensure_open()?;
+        self.reconciliation_repository.delete_session(session.id).await
+    }
+
+    async fn build_report(
+        &self,
+        session: ReconciliationSession,
+        account: &FinancialAccount,
+    ) -> Result<ReconciliationReport, AppError> {
+        let lines = self.reconciliation_repository.list_lines(session.id).await?;
+        let transactions = self.reconciliation_repository.candidate_transactions(&session).await?;
+        Ok(ReconciliationReport::build(session, account, lines, transactions))
+    }
+
+    async fn session(&self, company_id: Uuid, id: Uuid) -> Result<ReconciliationSession, AppError> {
+        self.reconciliation_repository
+            .find_session(company_id, id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Reconciliation not found".to_string()))
+    }
+
+    async fn account(&self, company_id: Uuid, account_id: Uuid) -> Result<FinancialAccount, AppError> {
+        self.account_repository
+            .find_by_id(account_id)
+            .await?
+            .filter(|account| account.company_id == company_id)
+            .ok_or_else(|| AppError::NotFound("Account not found".to_string()))
+    }
+
+    fn line(lines: &[StatementLine], line_id: Uuid) -> Result<StatementLine, AppError> {
+        lines
+            .iter()
+            .find(|l| l.id == line_id)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound("Statement line not found".to_string()))
+    }
+
+    fn check_line_date(session: &ReconciliationSession, line: &StatementLine) -> Result<(), AppError> {
+        let before_start = session.statement_start_date.is_some_and(|start| line.line_date < start);
+        if before_start || line.line_date > session.statement_end_date {
+            return Err(AppError::Validation(format!(
+                "Line dated {} is outside the statement period",
+                line.line_date
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::value_objects::Currency;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn account() -> FinancialAccount {
+        FinancialAccount::new(
+            Uuid::new_v4(),
+            "BCA Operasional".to_string(),
+            "Bank".to_string(),
+            Currency::IDR,
+            Money::new(0, Currency::IDR),
+        )
+    }
+
+    fn transaction(account: &FinancialAccount, day: u32, amount: i64, reference: Option<&str>) -> Transaction {
+        let kind = if amount > 0 { TransactionType::Income } else { TransactionType::Expense };
+        let mut transaction = Transaction::new(
+            account.company_id,
+            date(2024, 1, day).and_time(NaiveTime::MIN).and_utc(),
+            kind,
+            Money::new(amount.abs(), Currency::IDR),
+            "Pembayaran".to_string(),
+            account.id,
+            Uuid::new_v4(),
+        );
+        transaction.status = TransactionStatus::Completed;
+        transaction.reference_number = reference.map(str::to_string);
+        transaction
+    }
+
+    fn line(session_id: Uuid, day: u32, amount: i64, description: &str, reference: Option<&str>) -> StatementLine {
+        StatementLine::new(
+            session_id,
+            StatementLineInput {
+                line_date: date(2024, 1, day),
+                amount,
+                description: description.to_string(),
+                reference: reference.map(str::to_string),
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_auto_match_prefers_reference_and_skips_ambiguous_lines() {
+        let account = account();
+        let session_id = Uuid::new_v4();
+        let invoice_a = transaction(&account, 2, 500000, Some("INV-0001"));
+        let invoice_b = transaction(&account, 3, 500000, Some("INV-0002"));
+        let rent_1 = transaction(&account, 5, -200000, None);
+        let rent_2 = transaction(&account, 5, -200000, None);
+        let late = transaction(&account, 20, 700000, None);
+
+        let lines = vec![
+            // Same amount as both invoices; the reference decides
+            line(session_id, 3, 500000, "TRSF E-BANKING CR INV0002", None),
+            line(session_id, 4, 500000, "SETORAN", Some("INV-0001")),
+            // Two identical rent payments: left for manual matching
+            line(session_id, 5, -200000, "SEWA", None),
+            // Outside the date window
+            line(session_id, 10, 700000, "TRANSFER", None),
+        ];
+        let transactions = vec![invoice_a.clone(), invoice_b.clone(), rent_1, rent_2, late];
+
+        let pairs = auto_match(&lines, &transactions, DEFAULT_MATCH_WINDOW_DAYS);
+
+        assert_eq!(
+            pairs,
+            vec![
+                (lines[0].id, invoice_b.id.value()),
+                (lines[1].id, invoice_a.id.value()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_auto_match_falls_back_to_closest_date() {
+        let account = account();
+        let session_id = Uuid::new_v4();
+        let early = transaction(&account, 1, -150000, None);
+        let close = transaction(&account, 6, -150000, None);
+        let mut reconciled = transaction(&account, 7, -150000, None);
+        reconciled.reconciled_at = Some(Utc::now());
+
+        let lines = vec![line(session_id, 7, -150000, "BIAYA", None)];
+        let pairs = auto_match(&lines, &[early, close.clone(), reconciled], 10);
+
+        assert_eq!(pairs, vec![(lines[0].id, close.id.value())]);
+    }
+
+    #[test]
+    fn test_report_shows_outstanding_items_and_difference() {
+        let account = account();
+        let mut session = ReconciliationSession::new(
+            account.company_id,
+            account.id,
+            Some(date(2024, 1, 1)),
+            date(2024, 1, 31),
+            1000000,
+            Some(1480000),
+            Uuid::new_v4(),
+        )
+        .unwrap();
+
+        let deposit = transaction(&account, 2, 500000, None);
+        let cheque = transaction(&account, 30, -300000, None);
+        let mut deposit_line = line(session.id, 2, 500000, "SETORAN", None);
+        deposit_line.match_to(deposit.id.value(), MatchType::Auto);
+        let fee_line = line(session.id, 31, -20000, "BIAYA ADM", None);
+
+        let report = ReconciliationReport::build(
+            session.clone(),
+            &account,
+            vec![deposit_line.clone(), fee_line.clone()],
+            vec![deposit.clone(), cheque.clone()],
+        );
+        assert_eq!(report.cleared_balance, 1500000);
+        assert_eq!(report.difference, Some(-20000));
+        assert_eq!(report.unmatched_lines, vec![fee_line.clone()]);
+        assert_eq!(report.unmatched_transactions.len(), 1);
+        assert_eq!(report.unmatched_transactions[0].id, cheque.id);
+        assert!(!report.can_complete);
+
+        // Booking the fee as an adjustment clears the difference
+        let mut fee_line = fee_line;
+        let adjustment = fee_line.adjustment(&account, None, None, Uuid::new_v4());
+        assert_eq!(adjustment.transaction_type, TransactionType::Expense);
+        assert_eq!(adjustment.signed_amount(), -20000);
+        fee_line.match_to(adjustment.id.value(), MatchType::Adjustment);
+
+        let report = ReconciliationReport::build(
+            session.clone(),
+            &account,
+            vec![deposit_line.clone(), fee_line.clone()],
+            vec![deposit.clone(), cheque.clone(), adjustment],
+        );
+        assert_eq!(report.difference, Some(0));
+        assert!(report.can_complete);
+
+        session.mark_completed(Uuid::new_v4());
+        assert!(session.ensure_open().is_err());
+    }
+
+    #[test]
+    fn test_changed_transaction_no_longer_counts_as_matched() {
+        let account = account();
+        let session = ReconciliationSession::new(
+            account.company_id,
+            account.id,
+            None,
+            date(2024, 1, 31),
+            0,
+            Some(500000),
+            Uuid::new_v4(),
+        )
+        .unwrap();
+
+        let mut deposit = transaction(&account, 2, 500000, None);
+        let mut deposit_line = line(session.id, 2, 500000, "SETORAN", None);
+        deposit_line.match_to(deposit.id.value(), MatchType::Manual);
+        deposit.amount = Money::new(450000, Currency::IDR);
+
+        let report = ReconciliationReport::build(session, &account, vec![deposit_line], vec![deposit]);
+        assert!(report.matched.is_empty());
+        assert_eq!(report.unmatched_lines.len(), 1);
+        assert_eq!(report.unmatched_transactions.len(), 1);
+        assert_eq!(report.difference, Some(500000));
+    }
+
+    #[test]
+    fn test_statement_line_validation() {
+        let session_id = Uuid::new_v4();
+        let input = |amount, description: &str| StatementLineInput {
+            line_date: date(2024, 1, 1),
+            amount,
+            description: description.to_string(),
+            reference: Some("  ".to_string()),
+        };
+
+        assert!(StatementLine::new(session_id, input(0, "BIAYA")).is_err());
+        assert!(StatementLine::new(session_id, input(100, "  ")).is_err());
+        let line = StatementLine::new(session_id, input(100, " BUNGA ")).unwrap();
+        assert_eq!(line.description, "BUNGA");
+        assert_eq!(line.reference, None);
+        assert_eq!(line.key(), "2024-01-01|100|BUNGA");
+    }
+}
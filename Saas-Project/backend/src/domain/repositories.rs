@@ -5,7 +5,9 @@ use async_trait::async_trait;
 use crate::domain::value_objects::*;
 use crate::domain::entities::*;
 use crate::domain::companies::Company;
+use crate::domain::filters::{CompanyFilter, CompanySortField, UserFilter, UserSortField};
 use crate::shared::errors::AppResult;
+use crate::shared::query::{ListQuery, Page};
 
 #[async_trait]
 pub trait UserRepository {
@@ -17,6 +19,7 @@ pub trait UserRepository {
     async fn list_all(&self, limit: Option<i32>, offset: Option<i32>) -> AppResult<Vec<User>>;
     async fn count_all(&self) -> AppResult<i64>;
     async fn search(&self, query: &str, limit: Option<i32>, offset: Option<i32>) -> AppResult<Vec<User>>;
+    async fn list(&self, query: &ListQuery<UserFilter, UserSortField>) -> AppResult<Page<User>>;
 }
 
 #[async_trait]
@@ -41,9 +44,11 @@ pub trait CompanyRepository {
     async fn find_by_owner_id(&self, owner_id: &uuid::Uuid) -> AppResult<Vec<Company>>;
     async fn find_by_nib(&self, nib: &str) -> AppResult<Option<Company>>;
     async fn save(&self, company: &Company) -> AppResult<()>;
+    /// Saves `company` if the stored row is still at `company.version`; the
+    /// stored version is then `company.version + 1`
     async fn update(&self, company: &Company) -> AppResult<()>;
     async fn delete(&self, id: &uuid::Uuid) -> AppResult<()>;
     async fn list_all(&self, limit: Option<i32>, offset: Option<i32>) -> AppResult<Vec<Company>>;
     async fn count_by_owner(&self, owner_id: &uuid::Uuid) -> AppResult<i64>;
-    async fn search(&self, query: &str, limit: Option<i32>, offset: Option<i32>) -> AppResult<Vec<Company>>;
+    async fn list(&self, query: &ListQuery<CompanyFilter, CompanySortField>) -> AppResult<Page<Company>>;
 }
@@ -0,0 +1,211 @@
+// Search domain - unified full-text search across companies, licenses and documents
+// Hits are ranked by the database and paged with an opaque keyset cursor
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::domain::entities::UserRole;
+use crate::domain::value_objects::UserId;
+use crate::shared::errors::AppResult;
+
+pub const DEFAULT_SEARCH_LIMIT: u32 = 20;
+pub const MAX_SEARCH_LIMIT: u32 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchHitType {
+    Company,
+    License,
+    Document,
+}
+
+impl SearchHitType {
+    pub fn all() -> Vec<SearchHitType> {
+        vec![
+            SearchHitType::Company,
+            SearchHitType::License,
+            SearchHitType::Document,
+        ]
+    }
+}
+
+impl fmt::Display for SearchHitType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchHitType::Company => write!(f, "company"),
+            SearchHitType::License => write!(f, "license"),
+            SearchHitType::Document => write!(f, "document"),
+        }
+    }
+}
+
+impl FromStr for SearchHitType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "company" | "companies" => Ok(SearchHitType::Company),
+            "license" | "licenses" => Ok(SearchHitType::License),
+            "document" | "documents" => Ok(SearchHitType::Document),
+            _ => Err(format!("Unknown search type: {}", s)),
+        }
+    }
+}
+
+/// What the caller is allowed to see
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchScope {
+    /// Admin staff see every company, license and document
+    All,
+    /// Owners see their own companies and everything filed under them
+    Owner(Uuid),
+}
+
+impl SearchScope {
+    pub fn for_user(user_id: &UserId, role: &UserRole) -> Self {
+        match role {
+            UserRole::AdminStaff | UserRole::SuperAdmin => SearchScope::All,
+            UserRole::UmkmOwner => SearchScope::Owner(*user_id.as_uuid()),
+        }
+    }
+
+    pub fn owner_id(&self) -> Option<Uuid> {
+        match self {
+            SearchScope::All => None,
+            SearchScope::Owner(id) => Some(*id),
+        }
+    }
+}
+
+/// Position after the last hit of a page. Hits are ordered by rank, then id,
+/// both descending.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchCursor {
+    pub rank: f32,
+    pub id: Uuid,
+}
+
+impl SearchCursor {
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    pub fn decode(token: &str) -> Result<Self, String> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(token)
+            .map_err(|_| "Invalid search cursor".to_string())?;
+        serde_json::from_slice(&bytes).map_err(|_| "Invalid search cursor".to_string())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    pub text: String,
+    pub types: Vec<SearchHitType>,
+    pub scope: SearchScope,
+    pub limit: u32,
+    pub cursor: Option<SearchCursor>,
+}
+
+impl SearchQuery {
+    pub fn new(text: &str, scope: SearchScope) -> Result<Self, String> {
+        let text = text.trim();
+        if text.chars().count() < 2 {
+            return Err("Search query must be at least 2 characters".to_string());
+        }
+        if text.chars().count() > 200 {
+            return Err("Search query must be at most 200 characters".to_string());
+        }
+
+        Ok(Self {
+            text: text.to_string(),
+            types: SearchHitType::all(),
+            scope,
+            limit: DEFAULT_SEARCH_LIMIT,
+            cursor: None,
+        })
+    }
+
+    pub fn with_types(mut self, types: Vec<SearchHitType>) -> Self {
+        if !types.is_empty() {
+            self.types = types;
+        }
+        self
+    }
+
+    pub fn with_limit(mut self, limit: u32) -> Self {
+        self.limit = limit.clamp(1, MAX_SEARCH_LIMIT);
+        self
+    }
+
+    pub fn with_cursor(mut self, cursor: SearchCursor) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    #[serde(rename = "type")]
+    pub hit_type: SearchHitType,
+    pub id: Uuid,
+    pub title: String,
+    pub subtitle: Option<String>,
+    /// Matching fragments with terms wrapped in `<mark>` tags
+    pub highlight: String,
+    pub rank: f32,
+    pub company_id: Option<Uuid>,
+    pub license_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchPage {
+    pub hits: Vec<SearchHit>,
+    pub next_cursor: Option<String>,
+}
+
+#[async_trait]
+pub trait SearchRepository: Send + Sync {
+    async fn search(&self, query: &SearchQuery) -> AppResult<SearchPage>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let cursor = SearchCursor {
+            rank: 0.6079271,
+            id: Uuid::new_v4(),
+        };
+        let token = cursor.encode();
+        assert_eq!(SearchCursor::decode(&token).unwrap(), cursor);
+        assert!(SearchCursor::decode("not-a-cursor").is_err());
+    }
+
+    #[test]
+    fn test_query_validation_and_scope() {
+        let owner = UserId::new();
+        let scope = SearchScope::for_user(&owner, &UserRole::UmkmOwner);
+        assert_eq!(scope.owner_id(), Some(*owner.as_uuid()));
+        assert_eq!(
+            SearchScope::for_user(&owner, &UserRole::AdminStaff),
+            SearchScope::All
+        );
+
+        assert!(SearchQuery::new(" a ", SearchScope::All).is_err());
+        let query = SearchQuery::new(" izin usaha ", SearchScope::All)
+            .unwrap()
+            .with_types(vec![])
+            .with_limit(500);
+        assert_eq!(query.text, "izin usaha");
+        assert_eq!(query.types, SearchHitType::all());
+        assert_eq!(query.limit, MAX_SEARCH_LIMIT);
+    }
+}
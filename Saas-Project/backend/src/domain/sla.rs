@@ -0,0 +1,416 @@
+// SLA domain - how long license applications may take to process
+// The clock of an application starts when it is submitted and stops when it
+// is approved or rejected. Time spent in `PendingDocuments` is waiting on the
+// applicant, so it is not counted. Targets come from the license type's
+// default processing days scaled by priority, unless overridden. Warnings are
+// raised at configured percentages of the target and a breach is escalated
+// to a super admin.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::domain::licenses::{ApplicationStatus, License, LicenseType, PriorityLevel};
+use crate::shared::errors::AppResult;
+
+pub const DEFAULT_WARNING_THRESHOLDS: [u32; 3] = [50, 75, 90];
+
+/// Percentage of the target at which a breach is escalated
+pub const ESCALATION_PERCENT: u32 = 100;
+
+/// Share of the type's processing days an application of this priority gets
+fn priority_percent(priority: &PriorityLevel) -> i64 {
+    match priority {
+        PriorityLevel::Urgent => 25,
+        PriorityLevel::High => 50,
+        PriorityLevel::Normal => 100,
+        PriorityLevel::Low => 150,
+    }
+}
+
+/// Statuses in which an application is waiting on a reviewer or applicant
+pub fn in_flight_statuses() -> [ApplicationStatus; 3] {
+    [
+        ApplicationStatus::Submitted,
+        ApplicationStatus::Processing,
+        ApplicationStatus::PendingDocuments,
+    ]
+}
+
+/// Processing targets and warning thresholds
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlaPolicy {
+    /// Target days by type, for one priority or (`None`) every priority
+    target_days: HashMap<(LicenseType, Option<PriorityLevel>), i64>,
+    /// Percentages of the target, ascending, each below 100
+    warning_thresholds: Vec<u32>,
+}
+
+impl Default for SlaPolicy {
+    fn default() -> Self {
+        Self::new(DEFAULT_WARNING_THRESHOLDS.to_vec())
+    }
+}
+
+impl SlaPolicy {
+    pub fn new(mut warning_thresholds: Vec<u32>) -> Self {
+        warning_thresholds.retain(|t| (1..ESCALATION_PERCENT).contains(t));
+        warning_thresholds.sort_unstable();
+        warning_thresholds.dedup();
+        Self {
+            target_days: HashMap::new(),
+            warning_thresholds,
+        }
+    }
+
+    /// Overrides the target of a type; without a priority the override is
+    /// still scaled by priority, with one it is used as is
+    pub fn with_target_days(
+        mut self,
+        license_type: LicenseType,
+        priority: Option<PriorityLevel>,
+        days: i64,
+    ) -> Self {
+        self.target_days.insert((license_type, priority), days);
+        self
+    }
+
+    pub fn warning_thresholds(&self) -> &[u32] {
+        &self.warning_thresholds
+    }
+
+    pub fn target(&self, license_type: LicenseType, priority: &PriorityLevel) -> Duration {
+        if let Some(days) = self.target_days.get(&(license_type, Some(priority.clone()))) {
+            return Duration::days(*days);
+        }
+        let days = self
+            .target_days
+            .get(&(license_type, None))
+            .copied()
+            .or_else(|| License::default_processing_days(&license_type).map(i64::from))
+            .unwrap_or(14);
+        Duration::minutes(days * 24 * 60 * priority_percent(priority) / 100)
+    }
+
+    /// Where the application stands against its target at `now`; `None` for
+    /// applications that were never submitted
+    pub fn evaluate(&self, license: &License, now: DateTime<Utc>) -> Option<SlaStatus> {
+        let started = license.submitted_at?;
+        let decided_at = license.approved_at.or(license.rejected_at);
+        let end = decided_at.unwrap_or(now);
+
+        let current_pause = license
+            .sla_paused_at
+            .map(|paused_at| (end - paused_at).max(Duration::zero()))
+            .unwrap_or_else(Duration::zero);
+        let paused = Duration::seconds(license.sla_paused_seconds) + current_pause;
+        let elapsed = (end - started - paused).max(Duration::zero());
+
+        let target = self.target(license.license_type, &license.priority);
+        let percent_used = percent_of(elapsed, target);
+        let state = if percent_used >= ESCALATION_PERCENT {
+            SlaState::Breached
+        } else if self
+            .warning_thresholds
+            .first()
+            .is_some_and(|lowest| percent_used >= *lowest)
+        {
+            SlaState::AtRisk
+        } else {
+            SlaState::OnTrack
+        };
+        let is_paused = decided_at.is_none() && license.sla_paused_at.is_some();
+        let due_at = (decided_at.is_none() && !is_paused).then(|| now + (target - elapsed));
+
+        Some(SlaStatus {
+            license_id: license.id,
+            title: license.title.clone(),
+            license_type: license.license_type,
+            priority: license.priority.clone(),
+            application_status: license.application_status.clone(),
+            submitted_at: started,
+            target_seconds: target.num_seconds(),
+            elapsed_seconds: elapsed.num_seconds(),
+            paused_seconds: paused.num_seconds(),
+            percent_used,
+            state,
+            is_paused,
+            due_at,
+        })
+    }
+
+    /// Events an application at `status` should have had by now, warnings
+    /// for each threshold passed and an escalation once breached
+    pub fn due_events(&self, status: &SlaStatus) -> Vec<(SlaEventKind, u32)> {
+        let mut due: Vec<(SlaEventKind, u32)> = self
+            .warning_thresholds
+            .iter()
+            .filter(|threshold| status.percent_used >= **threshold)
+            .map(|threshold| (SlaEventKind::Warning, *threshold))
+            .collect();
+        if status.percent_used >= ESCALATION_PERCENT {
+            due.push((SlaEventKind::Escalation, ESCALATION_PERCENT));
+        }
+        due
+    }
+
+    /// Share of the decisions each reviewer made within target, busiest
+    /// reviewer first
+    pub fn attainment_by_reviewer(&self, decisions: &[SlaDecision]) -> Vec<ReviewerAttainment> {
+        let mut by_reviewer: BTreeMap<Option<Uuid>, (u64, u64)> = BTreeMap::new();
+        for decision in decisions {
+            let Some(status) = self.evaluate(&decision.license, Utc::now()) else {
+                continue;
+            };
+            let (decided, within) = by_reviewer.entry(decision.decided_by).or_default();
+            *decided += 1;
+            if status.elapsed_seconds <= status.target_seconds {
+                *within += 1;
+            }
+        }
+
+        let mut attainment: Vec<ReviewerAttainment> = by_reviewer
+            .into_iter()
+            .map(|(reviewer_id, (decided, within_sla))| ReviewerAttainment {
+                reviewer_id,
+                decided,
+                within_sla,
+                attainment_percent: within_sla as f64 * 100.0 / decided as f64,
+            })
+            .collect();
+        attainment.sort_by_key(|a| std::cmp::Reverse(a.decided));
+        attainment
+    }
+}
+
+fn percent_of(elapsed: Duration, target: Duration) -> u32 {
+    let target = target.num_seconds().max(1);
+    (elapsed.num_seconds() * 100 / target).clamp(0, u32::MAX as i64) as u32
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlaState {
+    OnTrack,
+    AtRisk,
+    Breached,
+}
+
+/// An application measured against its target
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SlaStatus {
+    pub license_id: Uuid,
+    pub title: String,
+    pub license_type: LicenseType,
+    pub priority: PriorityLevel,
+    pub application_status: ApplicationStatus,
+    pub submitted_at: DateTime<Utc>,
+    pub target_seconds: i64,
+    /// Time on the clock, pauses excluded
+    pub elapsed_seconds: i64,
+    pub paused_seconds: i64,
+    pub percent_used: u32,
+    pub state: SlaState,
+    /// Waiting on documents from the applicant
+    pub is_paused: bool,
+    /// When the target runs out if the clock keeps running; `None` once
+    /// decided or while paused
+    pub due_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlaEventKind {
+    Warning,
+    Escalation,
+}
+
+impl fmt::Display for SlaEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SlaEventKind::Warning => write!(f, "warning"),
+            SlaEventKind::Escalation => write!(f, "escalation"),
+        }
+    }
+}
+
+impl FromStr for SlaEventKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "warning" => Ok(SlaEventKind::Warning),
+            "escalation" => Ok(SlaEventKind::Escalation),
+            _ => Err(format!("Invalid SLA event kind: {}", s)),
+        }
+    }
+}
+
+/// A warning or escalation raised for an application, once per threshold
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlaEvent {
+    pub id: Uuid,
+    pub license_id: Uuid,
+    pub kind: SlaEventKind,
+    pub threshold_percent: i32,
+    /// Super admin an escalation was handed to
+    pub escalated_to: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl SlaEvent {
+    pub fn new(license_id: Uuid, kind: SlaEventKind, threshold_percent: u32) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            license_id,
+            kind,
+            threshold_percent: threshold_percent as i32,
+            escalated_to: None,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// An approved or rejected application and the reviewer who decided it
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlaDecision {
+    pub license: License,
+    pub decided_by: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReviewerAttainment {
+    /// `None` for decisions without a recorded reviewer
+    pub reviewer_id: Option<Uuid>,
+    pub decided: u64,
+    pub within_sla: u64,
+    pub attainment_percent: f64,
+}
+
+/// An in-flight application at or past a warning threshold
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AtRiskApplication {
+    #[serde(flatten)]
+    pub status: SlaStatus,
+    pub escalated_to: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SlaDashboard {
+    pub generated_at: DateTime<Utc>,
+    pub warning_thresholds: Vec<u32>,
+    /// Most overdue first
+    pub at_risk: Vec<AtRiskApplication>,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub reviewers: Vec<ReviewerAttainment>,
+}
+
+#[async_trait]
+pub trait SlaRepository: Send + Sync {
+    /// Records `event` unless the license already has one of the same kind
+    /// and threshold; `true` when it was recorded
+    async fn record_event(&self, event: &SlaEvent) -> AppResult<bool>;
+    async fn events_for_licenses(&self, license_ids: &[Uuid]) -> AppResult<Vec<SlaEvent>>;
+    /// Applications approved or rejected in `[from, to)`, with the reviewer
+    /// who moved them to their current status
+    async fn decisions(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> AppResult<Vec<SlaDecision>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn submitted(license_type: LicenseType, priority: PriorityLevel, at: DateTime<Utc>) -> License {
+        let mut license = License::new(
+            license_type,
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            "Izin usaha".to_string(),
+            None,
+        );
+        license.priority = priority;
+        license.application_status = ApplicationStatus::Processing;
+        license.submitted_at = Some(at);
+        license
+    }
+
+    #[test]
+    fn test_target_scales_default_days_by_priority_unless_overridden() {
+        let policy = SlaPolicy::default()
+            .with_target_days(LicenseType::Halal, None, 20)
+            .with_target_days(LicenseType::Siup, Some(PriorityLevel::Urgent), 3);
+
+        assert_eq!(policy.target(LicenseType::Nib, &PriorityLevel::Normal), Duration::days(7));
+        assert_eq!(policy.target(LicenseType::Nib, &PriorityLevel::High), Duration::hours(84));
+        assert_eq!(policy.target(LicenseType::Halal, &PriorityLevel::Urgent), Duration::days(5));
+        assert_eq!(policy.target(LicenseType::Siup, &PriorityLevel::Urgent), Duration::days(3));
+        assert_eq!(policy.target(LicenseType::Siup, &PriorityLevel::Low), Duration::days(21));
+    }
+
+    #[test]
+    fn test_pending_documents_time_is_not_counted() {
+        let now = Utc::now();
+        let policy = SlaPolicy::default();
+        let mut license = submitted(LicenseType::Nib, PriorityLevel::Normal, now - Duration::days(6));
+        license.sla_paused_seconds = Duration::days(2).num_seconds();
+
+        let status = policy.evaluate(&license, now).unwrap();
+        assert_eq!(status.elapsed_seconds, Duration::days(4).num_seconds());
+        assert_eq!(status.percent_used, 57);
+        assert_eq!(status.state, SlaState::AtRisk);
+        assert_eq!(status.due_at, Some(now + Duration::days(3)));
+
+        license.application_status = ApplicationStatus::PendingDocuments;
+        license.sla_paused_at = Some(now - Duration::days(1));
+        let paused = policy.evaluate(&license, now).unwrap();
+        assert_eq!(paused.elapsed_seconds, Duration::days(3).num_seconds());
+        assert!(paused.is_paused);
+        assert_eq!(paused.due_at, None);
+    }
+
+    #[test]
+    fn test_due_events_cover_every_threshold_passed() {
+        let now = Utc::now();
+        let policy = SlaPolicy::new(vec![90, 50, 150, 50]);
+        assert_eq!(policy.warning_thresholds(), &[50, 90]);
+
+        let license = submitted(LicenseType::Npwp, PriorityLevel::Normal, now - Duration::days(4));
+        let status = policy.evaluate(&license, now).unwrap();
+        assert_eq!(status.state, SlaState::Breached);
+        assert_eq!(
+            policy.due_events(&status),
+            vec![
+                (SlaEventKind::Warning, 50),
+                (SlaEventKind::Warning, 90),
+                (SlaEventKind::Escalation, 100),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_attainment_counts_decisions_within_target_per_reviewer() {
+        let now = Utc::now();
+        let policy = SlaPolicy::default();
+        let reviewer = Uuid::new_v4();
+        let decided = |days: i64| {
+            let mut license = submitted(LicenseType::Nib, PriorityLevel::Normal, now - Duration::days(days));
+            license.application_status = ApplicationStatus::Approved;
+            license.approved_at = Some(now);
+            SlaDecision {
+                license,
+                decided_by: Some(reviewer),
+            }
+        };
+
+        let attainment = policy.attainment_by_reviewer(&[decided(3), decided(6), decided(9)]);
+        assert_eq!(attainment.len(), 1);
+        assert_eq!(attainment[0].reviewer_id, Some(reviewer));
+        assert_eq!(attainment[0].decided, 3);
+        assert_eq!(attainment[0].within_sla, 2);
+    }
+}
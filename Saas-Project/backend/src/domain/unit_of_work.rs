@@ -0,0 +1,62 @@
+// Unit of work - committing changes to several aggregates atomically
+// A domain service begins a `Work`, makes its changes through the repositories
+// the work carries and commits once. Dropping a work without committing
+// discards every change made through it, so an error returned half-way
+// through leaves nothing behind.
+
+use async_trait::async_trait;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::shared::errors::AppResult;
+
+/// Commits what was done through the repositories of one work
+#[async_trait]
+pub trait WorkHandle: Send + Sync {
+    async fn commit(self: Box<Self>) -> AppResult<()>;
+}
+
+/// Repositories `R` joined to one unit of work
+pub struct Work<R> {
+    repositories: R,
+    handle: Box<dyn WorkHandle>,
+}
+
+impl<R> Work<R> {
+    pub fn new(repositories: R, handle: Box<dyn WorkHandle>) -> Self {
+        Self {
+            repositories,
+            handle,
+        }
+    }
+
+    /// Makes every change done through the repositories visible at once
+    pub async fn commit(self) -> AppResult<()> {
+        self.handle.commit().await
+    }
+}
+
+impl<R> Deref for Work<R> {
+    type Target = R;
+
+    fn deref(&self) -> &R {
+        &self.repositories
+    }
+}
+
+/// Begins units of work over the repositories `R`
+#[async_trait]
+pub trait UnitOfWork<R>: Send + Sync {
+    async fn begin(&self) -> AppResult<Work<R>>;
+}
+
+#[async_trait]
+impl<R, U> UnitOfWork<R> for Arc<U>
+where
+    R: 'static,
+    U: UnitOfWork<R> + ?Sized,
+{
+    async fn begin(&self) -> AppResult<Work<R>> {
+        self.as_ref().begin().await
+    }
+}
@@ -3,6 +3,7 @@
 
 #![allow(dead_code)]
 
+use chrono::{NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::ops::{AddAssign, Neg};
@@ -65,22 +66,89 @@ impl Email {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Indonesian phone number, stored in E.164 form (`+628123456789`)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[serde(try_from = "String", into = "String")]
+#[sqlx(transparent)]
 pub struct PhoneNumber(pub String);
 
 impl PhoneNumber {
+    const COUNTRY_CODE: &'static str = "62";
+
     pub fn new(phone: &str) -> Result<Self, String> {
-        // Indonesian phone number validation
-        if phone.starts_with("08") || phone.starts_with("+62") {
-            Ok(Self(phone.to_string()))
+        // Accept common separators used when people type numbers by hand
+        let cleaned: String = phone
+            .trim()
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')'))
+            .collect();
+
+        let (has_plus, digits) = match cleaned.strip_prefix('+') {
+            Some(rest) => (true, rest),
+            None => (false, cleaned.as_str()),
+        };
+
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err("Invalid Indonesian phone number format".to_string());
+        }
+
+        // Reduce every accepted prefix (+62, 62, 0) to the national significant number
+        let national = if let Some(rest) = digits.strip_prefix(Self::COUNTRY_CODE) {
+            rest
+        } else if has_plus {
+            return Err("Only Indonesian (+62) phone numbers are supported".to_string());
+        } else if let Some(rest) = digits.strip_prefix('0') {
+            rest
         } else {
-            Err("Invalid Indonesian phone number format".to_string())
+            return Err("Invalid Indonesian phone number format".to_string());
+        };
+
+        // National numbers never start with 0 and are 8-12 digits long
+        // (mobile 8xx numbers are 9-12, landlines with area code 8-11)
+        if national.starts_with('0') || !(8..=12).contains(&national.len()) {
+            return Err("Invalid Indonesian phone number format".to_string());
         }
+
+        Ok(Self(format!("+{}{}", Self::COUNTRY_CODE, national)))
     }
 
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// Number without the country code, as written locally (`08123456789`)
+    pub fn national_format(&self) -> String {
+        let national = self
+            .0
+            .strip_prefix("+62")
+            .unwrap_or(self.0.as_str());
+        format!("0{}", national)
+    }
+
+    /// Mobile numbers use the 8xx prefix
+    pub fn is_mobile(&self) -> bool {
+        self.0.starts_with("+628")
+    }
+}
+
+impl TryFrom<String> for PhoneNumber {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::new(&value)
+    }
+}
+
+impl From<PhoneNumber> for String {
+    fn from(phone: PhoneNumber) -> Self {
+        phone.0
+    }
+}
+
+impl fmt::Display for PhoneNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
 }
 
 // Licensing-related value objects
@@ -107,32 +175,305 @@ impl NIB {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Nomor Pokok Wajib Pajak, stored as bare digits.
+///
+/// Accepts the legacy 15-digit form (`01.234.567.8-901.000`), whose ninth digit
+/// is a Luhn check digit over the first eight, and the 16-digit form introduced
+/// by PMK 112/2022: a NIK for individuals or the legacy number prefixed with `0`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[serde(try_from = "String", into = "String")]
+#[sqlx(transparent)]
 pub struct NPWP(pub String);
 
 impl NPWP {
     pub fn new(npwp: String) -> Result<Self, String> {
-        // NPWP validation - 15 digits with dots and dashes
-        if npwp.len() == 20 {
-            Ok(Self(npwp))
+        let digits = strip_separators(&npwp, &['.', '-', ' '])
+            .ok_or_else(|| "NPWP may only contain digits, dots and dashes".to_string())?;
+
+        match digits.len() {
+            15 => {
+                Self::validate_legacy(&digits)?;
+                Ok(Self(digits))
+            }
+            16 => {
+                // Entities keep their legacy number behind a leading zero,
+                // individuals use their NIK
+                match digits.strip_prefix('0') {
+                    Some(legacy) => Self::validate_legacy(legacy)?,
+                    None => {
+                        NIK::new(digits.clone())?;
+                    }
+                }
+                Ok(Self(digits))
+            }
+            _ => Err("NPWP must be 15 or 16 digits".to_string()),
+        }
+    }
+
+    fn validate_legacy(digits: &str) -> Result<(), String> {
+        let check = digits.as_bytes()[8] - b'0';
+        if luhn_check_digit(&digits[..8]) != check {
+            return Err("NPWP check digit is invalid".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// True for the 15-digit number issued before the NIK-based scheme
+    pub fn is_legacy(&self) -> bool {
+        self.0.len() == 15
+    }
+
+    /// Human-readable form: `01.234.567.8-901.000` for legacy numbers,
+    /// the bare 16 digits otherwise
+    pub fn formatted(&self) -> String {
+        if !self.is_legacy() {
+            return self.0.clone();
+        }
+        let d = &self.0;
+        format!(
+            "{}.{}.{}.{}-{}.{}",
+            &d[0..2],
+            &d[2..5],
+            &d[5..8],
+            &d[8..9],
+            &d[9..12],
+            &d[12..15]
+        )
+    }
+
+    /// Tax office (KPP) code of a legacy number
+    pub fn tax_office_code(&self) -> Option<&str> {
+        if self.is_legacy() {
+            Some(&self.0[9..12])
         } else {
-            Err("NPWP format invalid".to_string())
+            self.0.strip_prefix('0').map(|legacy| &legacy[9..12])
         }
     }
 }
 
+impl TryFrom<String> for NPWP {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl From<NPWP> for String {
+    fn from(npwp: NPWP) -> Self {
+        npwp.0
+    }
+}
+
+impl fmt::Display for NPWP {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.formatted())
+    }
+}
+
+/// Nomor Induk Kependudukan as printed on the KTP.
+///
+/// Layout: `PPKKCC DDMMYY SSSS` - province, regency and district codes, birth
+/// date (day + 40 for women) and a non-zero serial number.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[serde(try_from = "String", into = "String")]
+#[sqlx(transparent)]
+pub struct NIK(pub String);
+
+/// Kept for code that still refers to the card rather than the number
+pub type KTP = NIK;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Gender {
+    Male,
+    Female,
+}
+
+/// Administrative region encoded in the first six digits of a NIK
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub struct KTP(pub String);
+pub struct NikRegion {
+    pub province_code: String,
+    pub regency_code: String,
+    pub district_code: String,
+    pub province_name: Option<&'static str>,
+}
+
+impl NIK {
+    pub fn new(nik: String) -> Result<Self, String> {
+        let digits = strip_separators(&nik, &[' ', '.'])
+            .ok_or_else(|| "NIK must be 16 digits".to_string())?;
 
-impl KTP {
-    pub fn new(ktp: String) -> Result<Self, String> {
-        // KTP validation - 16 digits
-        if ktp.len() == 16 && ktp.chars().all(char::is_numeric) {
-            Ok(Self(ktp))
+        if digits.len() != 16 {
+            return Err("NIK must be 16 digits".to_string());
+        }
+
+        if province_name(&digits[0..2]).is_none() {
+            return Err(format!("NIK has unknown province code {}", &digits[0..2]));
+        }
+        if &digits[2..4] == "00" || &digits[4..6] == "00" {
+            return Err("NIK has an invalid regency or district code".to_string());
+        }
+        if &digits[12..16] == "0000" {
+            return Err("NIK serial number cannot be 0000".to_string());
+        }
+
+        let nik = Self(digits);
+        nik.resolve_birth_date(Utc::now().date_naive())
+            .ok_or_else(|| "NIK contains an invalid birth date".to_string())?;
+        Ok(nik)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn region(&self) -> NikRegion {
+        NikRegion {
+            province_code: self.0[0..2].to_string(),
+            regency_code: self.0[0..4].to_string(),
+            district_code: self.0[0..6].to_string(),
+            province_name: province_name(&self.0[0..2]),
+        }
+    }
+
+    pub fn gender(&self) -> Gender {
+        if self.raw_day() > 40 {
+            Gender::Female
         } else {
-            Err("KTP must be 16 digits".to_string())
+            Gender::Male
         }
     }
+
+    /// Birth date encoded in the NIK. The two-digit year is resolved to the
+    /// most recent century that does not put the date in the future.
+    pub fn birth_date(&self) -> NaiveDate {
+        self.resolve_birth_date(Utc::now().date_naive())
+            .expect("NIK birth date is validated on construction")
+    }
+
+    fn raw_day(&self) -> u32 {
+        self.0[6..8].parse().unwrap_or(0)
+    }
+
+    fn resolve_birth_date(&self, today: NaiveDate) -> Option<NaiveDate> {
+        let raw_day = self.raw_day();
+        let day = if raw_day > 40 { raw_day - 40 } else { raw_day };
+        let month: u32 = self.0[8..10].parse().ok()?;
+        let yy: i32 = self.0[10..12].parse().ok()?;
+
+        let current = NaiveDate::from_ymd_opt(2000 + yy, month, day);
+        match current {
+            Some(date) if date <= today => Some(date),
+            _ => NaiveDate::from_ymd_opt(1900 + yy, month, day),
+        }
+    }
+}
+
+impl TryFrom<String> for NIK {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl From<NIK> for String {
+    fn from(nik: NIK) -> Self {
+        nik.0
+    }
+}
+
+impl fmt::Display for NIK {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Province codes used by Dukcapil (Permendagri 58/2021 plus the 2022 Papua split)
+fn province_name(code: &str) -> Option<&'static str> {
+    let name = match code {
+        "11" => "Aceh",
+        "12" => "Sumatera Utara",
+        "13" => "Sumatera Barat",
+        "14" => "Riau",
+        "15" => "Jambi",
+        "16" => "Sumatera Selatan",
+        "17" => "Bengkulu",
+        "18" => "Lampung",
+        "19" => "Kepulauan Bangka Belitung",
+        "21" => "Kepulauan Riau",
+        "31" => "DKI Jakarta",
+        "32" => "Jawa Barat",
+        "33" => "Jawa Tengah",
+        "34" => "DI Yogyakarta",
+        "35" => "Jawa Timur",
+        "36" => "Banten",
+        "51" => "Bali",
+        "52" => "Nusa Tenggara Barat",
+        "53" => "Nusa Tenggara Timur",
+        "61" => "Kalimantan Barat",
+        "62" => "Kalimantan Tengah",
+        "63" => "Kalimantan Selatan",
+        "64" => "Kalimantan Timur",
+        "65" => "Kalimantan Utara",
+        "71" => "Sulawesi Utara",
+        "72" => "Sulawesi Tengah",
+        "73" => "Sulawesi Selatan",
+        "74" => "Sulawesi Tenggara",
+        "75" => "Gorontalo",
+        "76" => "Sulawesi Barat",
+        "81" => "Maluku",
+        "82" => "Maluku Utara",
+        "91" => "Papua",
+        "92" => "Papua Barat",
+        "93" => "Papua Selatan",
+        "94" => "Papua Tengah",
+        "95" => "Papua Pegunungan",
+        "96" => "Papua Barat Daya",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Removes the allowed separators and returns the digits, or `None` if any
+/// other character is present
+fn strip_separators(input: &str, separators: &[char]) -> Option<String> {
+    let digits: String = input
+        .trim()
+        .chars()
+        .filter(|c| !separators.contains(c))
+        .collect();
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(digits)
+}
+
+/// Luhn (mod 10) check digit for a string of ASCII digits
+fn luhn_check_digit(digits: &str) -> u8 {
+    let sum: u32 = digits
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(i, b)| {
+            let d = (b - b'0') as u32;
+            if i % 2 == 0 {
+                let doubled = d * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                d
+            }
+        })
+        .sum();
+    ((10 - (sum % 10)) % 10) as u8
 }
 
 // Business-related value objects
@@ -189,7 +530,21 @@ impl fmt::Display for Currency {
     }
 }
 
+impl std::str::FromStr for Currency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "IDR" => Ok(Currency::IDR),
+            _ => Err(format!("Unsupported currency: {}", s)),
+        }
+    }
+}
+
 impl Money {
+    /// Hundredths of a rupiah in a rupiah
+    pub const RUPIAH: i64 = 100;
+
     pub fn new(amount: i64, currency: Currency) -> Self {
         Self { amount, currency }
     }
@@ -207,6 +562,23 @@ impl Money {
     pub fn to_f64(&self) -> f64 {
         self.amount as f64 / 100.0
     }
+
+    /// `Rp 165.390`, with sen after a comma only when there are any
+    pub fn rupiah(&self) -> String {
+        let digits = (self.amount.abs() / 100).to_string();
+        let mut grouped = String::new();
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                grouped.push('.');
+            }
+            grouped.push(digit);
+        }
+        let sign = if self.amount < 0 { "-" } else { "" };
+        match self.amount.abs() % 100 {
+            0 => format!("{}Rp {}", sign, grouped),
+            sen => format!("{}Rp {},{:02}", sign, grouped, sen),
+        }
+    }
 }
 
 // Implement negation for Money
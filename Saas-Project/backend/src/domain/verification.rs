@@ -0,0 +1,188 @@
+// Public license verification - what anyone holding a certificate may learn
+// Banks, marketplaces and buyers check a license by scanning a certificate's
+// QR code or by looking up its license number or the company's NIB. They get
+// the license's current standing and nothing else: no contact details,
+// addresses, tax numbers or fees of the company. Every lookup is kept in an
+// audit trail, whether or not it found anything.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::domain::companies::Company;
+use crate::domain::licenses::{ApplicationStatus, License, LicenseType};
+use crate::shared::errors::AppResult;
+
+/// Longest query kept in the audit trail; license numbers are at most 100
+/// characters, anything longer is noise
+pub const MAX_LOOKUP_QUERY_LENGTH: usize = 100;
+
+/// Standing of a license as shown to the public
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PublicLicenseStatus {
+    Active,
+    Expired,
+    Suspended,
+    /// Never issued, or no longer in force for another reason
+    Invalid,
+}
+
+impl PublicLicenseStatus {
+    pub fn of(license: &License) -> Self {
+        if license.is_active() {
+            return PublicLicenseStatus::Active;
+        }
+        match license.application_status {
+            ApplicationStatus::Suspended => PublicLicenseStatus::Suspended,
+            ApplicationStatus::Approved | ApplicationStatus::Expired => PublicLicenseStatus::Expired,
+            _ => PublicLicenseStatus::Invalid,
+        }
+    }
+}
+
+/// The public facts of a license
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PublicLicense {
+    pub license_number: Option<String>,
+    pub license_type: LicenseType,
+    pub company_name: String,
+    pub status: PublicLicenseStatus,
+    pub issuing_authority: Option<String>,
+    pub issue_date: Option<DateTime<Utc>>,
+    pub expiry_date: Option<DateTime<Utc>>,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl PublicLicense {
+    pub fn new(license: &License, company: &Company) -> Self {
+        Self {
+            license_number: license.license_number.clone(),
+            license_type: license.license_type,
+            company_name: company.company_name.clone(),
+            status: PublicLicenseStatus::of(license),
+            issuing_authority: license.issuing_authority.clone(),
+            issue_date: license.issue_date,
+            expiry_date: license.expiry_date,
+            checked_at: Utc::now(),
+        }
+    }
+}
+
+/// How a verification found its license
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LookupMethod {
+    /// A signed token from a certificate's QR code
+    Token,
+    LicenseNumber,
+    /// Every issued license of the company with this NIB
+    Nib,
+}
+
+impl fmt::Display for LookupMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            LookupMethod::Token => "token",
+            LookupMethod::LicenseNumber => "license_number",
+            LookupMethod::Nib => "nib",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for LookupMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "token" => Ok(LookupMethod::Token),
+            "license_number" => Ok(LookupMethod::LicenseNumber),
+            "nib" => Ok(LookupMethod::Nib),
+            _ => Err(format!("Unknown lookup method: {}", s)),
+        }
+    }
+}
+
+/// One public lookup and, as far as the request tells, who made it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VerificationLookup {
+    pub id: Uuid,
+    pub method: LookupMethod,
+    /// What was looked up, cut to `MAX_LOOKUP_QUERY_LENGTH` characters
+    pub query: String,
+    /// The company whose licenses were shown, `None` when nothing was found
+    pub company_id: Option<Uuid>,
+    /// The license shown, for token and license number lookups
+    pub license_id: Option<Uuid>,
+    pub licenses_found: i32,
+    pub client_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub looked_up_at: DateTime<Utc>,
+}
+
+impl VerificationLookup {
+    pub fn new(method: LookupMethod, query: &str) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            method,
+            query: query.chars().take(MAX_LOOKUP_QUERY_LENGTH).collect(),
+            company_id: None,
+            license_id: None,
+            licenses_found: 0,
+            client_ip: None,
+            user_agent: None,
+            looked_up_at: Utc::now(),
+        }
+    }
+}
+
+/// Audit trail of public verification lookups
+#[async_trait]
+pub trait VerificationAuditRepository: Send + Sync {
+    async fn record_lookup(&self, lookup: &VerificationLookup) -> AppResult<()>;
+    /// The most recent lookups, newest first, optionally of one company only
+    async fn recent_lookups(
+        &self,
+        company_id: Option<Uuid>,
+        limit: i64,
+    ) -> AppResult<Vec<VerificationLookup>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_public_status_follows_is_active() {
+        let mut license = License::new(LicenseType::Nib, Uuid::new_v4(), Uuid::new_v4(), "NIB".to_string(), None);
+        assert_eq!(PublicLicenseStatus::of(&license), PublicLicenseStatus::Invalid);
+
+        license.application_status = ApplicationStatus::Approved;
+        license.expiry_date = Some(Utc::now() + Duration::days(30));
+        assert_eq!(PublicLicenseStatus::of(&license), PublicLicenseStatus::Active);
+
+        license.expiry_date = Some(Utc::now() - Duration::days(1));
+        assert_eq!(PublicLicenseStatus::of(&license), PublicLicenseStatus::Expired);
+
+        license.application_status = ApplicationStatus::Suspended;
+        assert_eq!(PublicLicenseStatus::of(&license), PublicLicenseStatus::Suspended);
+
+        license.application_status = ApplicationStatus::Rejected;
+        assert_eq!(PublicLicenseStatus::of(&license), PublicLicenseStatus::Invalid);
+    }
+
+    #[test]
+    fn test_lookup_queries_are_cut_for_the_audit_trail() {
+        let lookup = VerificationLookup::new(LookupMethod::LicenseNumber, &"9".repeat(500));
+        assert_eq!(lookup.query.len(), MAX_LOOKUP_QUERY_LENGTH);
+        assert_eq!(lookup.licenses_found, 0);
+        assert_eq!("nib".parse::<LookupMethod>(), Ok(LookupMethod::Nib));
+        assert_eq!(LookupMethod::LicenseNumber.to_string(), "license_number");
+    }
+}
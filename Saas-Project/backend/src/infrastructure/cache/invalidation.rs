@@ -0,0 +1,79 @@
+// Cross-instance L1 invalidation over Redis pub/sub
+// Each write or delete publishes the affected keys (or patterns); every other
+// instance drops them from its L1. Messages sent while a subscriber is
+// disconnected are lost, so it clears its whole L1 when it (re)subscribes.
+
+use futures::StreamExt;
+use redis::{AsyncCommands, Client, RedisError};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use super::LocalCache;
+
+pub const INVALIDATION_CHANNEL: &str = "cache:invalidations";
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Invalidation {
+    /// Instance that sent the message; it has already updated its own L1
+    pub origin: String,
+    #[serde(default)]
+    pub keys: Vec<String>,
+    #[serde(default)]
+    pub patterns: Vec<String>,
+}
+
+impl Invalidation {
+    pub fn apply(&self, local: &LocalCache) {
+        for key in &self.keys {
+            local.remove(key);
+        }
+        for pattern in &self.patterns {
+            local.remove_matching(pattern);
+        }
+    }
+
+    pub async fn publish(&self, conn: &mut redis::aio::Connection) -> Result<(), RedisError> {
+        let payload = serde_json::to_string(self).expect("invalidation serializes");
+        conn.publish::<_, _, ()>(INVALIDATION_CHANNEL, payload).await
+    }
+}
+
+/// Subscribes to invalidations from other instances until the process exits
+pub fn spawn_listener(client: Client, local: Arc<LocalCache>, instance_id: Arc<str>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            if let Err(err) = listen(&client, &local, &instance_id).await {
+                warn!("⚠️ Cache invalidation subscription lost: {}", err);
+            }
+            local.clear();
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    })
+}
+
+async fn listen(client: &Client, local: &LocalCache, instance_id: &str) -> Result<(), RedisError> {
+    let mut pubsub = client.get_async_connection().await?.into_pubsub();
+    pubsub.subscribe(INVALIDATION_CHANNEL).await?;
+    local.clear();
+    info!("📡 Subscribed to cache invalidations");
+
+    let mut messages = pubsub.on_message();
+    while let Some(message) = messages.next().await {
+        let payload: String = message.get_payload()?;
+        match serde_json::from_str::<Invalidation>(&payload) {
+            Ok(invalidation) if invalidation.origin != instance_id => invalidation.apply(local),
+            Ok(_) => {}
+            Err(err) => debug!("Ignoring malformed cache invalidation: {}", err),
+        }
+    }
+
+    Err(RedisError::from(std::io::Error::new(
+        std::io::ErrorKind::ConnectionAborted,
+        "subscription closed",
+    )))
+}
@@ -0,0 +1,133 @@
+// In-process L1 cache
+// A bounded LRU of serialized values in front of Redis. Entries live for a
+// short TTL so a missed invalidation can only serve stale data briefly;
+// invalidations from other instances arrive over pub/sub.
+
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub struct LocalCache {
+    entries: Mutex<LruCache<String, (String, Instant)>>,
+    ttl: Duration,
+}
+
+impl LocalCache {
+    /// `None` when `capacity` is 0, i.e. the L1 is disabled
+    pub fn new(capacity: usize, ttl: Duration) -> Option<Self> {
+        let capacity = NonZeroUsize::new(capacity)?;
+        Some(Self {
+            entries: Mutex::new(LruCache::new(capacity)),
+            ttl,
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        let mut entries = self.entries.lock().expect("local cache poisoned");
+        match entries.get(key) {
+            Some((value, expires_at)) if *expires_at > Instant::now() => Some(value.clone()),
+            Some(_) => {
+                entries.pop(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Stores a value for the L1 TTL, or less when Redis expires it sooner
+    pub fn insert(&self, key: &str, value: String, expiry_secs: Option<u64>) {
+        let ttl = expiry_secs
+            .map(|secs| Duration::from_secs(secs).min(self.ttl))
+            .unwrap_or(self.ttl);
+        self.entries
+            .lock()
+            .expect("local cache poisoned")
+            .put(key.to_string(), (value, Instant::now() + ttl));
+    }
+
+    pub fn remove(&self, key: &str) {
+        self.entries.lock().expect("local cache poisoned").pop(key);
+    }
+
+    /// Removes the keys matching a Redis glob pattern (only `*` is supported)
+    pub fn remove_matching(&self, pattern: &str) {
+        let mut entries = self.entries.lock().expect("local cache poisoned");
+        let matching: Vec<String> = entries
+            .iter()
+            .filter(|(key, _)| glob_match(pattern, key))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in matching {
+            entries.pop(&key);
+        }
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().expect("local cache poisoned").clear();
+    }
+}
+
+/// Matches `*` wildcards; other glob characters are taken literally
+pub(super) fn glob_match(pattern: &str, key: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == key;
+    }
+
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !key.starts_with(first) || key.len() < first.len() + last.len() || !key.ends_with(last) {
+        return false;
+    }
+
+    let mut rest = &key[first.len()..key.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_eviction_and_ttl() {
+        let cache = LocalCache::new(2, Duration::from_secs(30)).unwrap();
+        cache.insert("company:1", "a".to_string(), None);
+        cache.insert("company:2", "b".to_string(), None);
+        assert_eq!(cache.get("company:1").as_deref(), Some("a"));
+
+        // company:2 is the least recently used
+        cache.insert("company:3", "c".to_string(), None);
+        assert!(cache.get("company:2").is_none());
+        assert!(cache.get("company:1").is_some());
+
+        // A zero Redis TTL expires the entry immediately
+        cache.insert("company:4", "d".to_string(), Some(0));
+        assert!(cache.get("company:4").is_none());
+
+        assert!(LocalCache::new(0, Duration::from_secs(30)).is_none());
+    }
+
+    #[test]
+    fn test_remove_matching_glob() {
+        let cache = LocalCache::new(10, Duration::from_secs(30)).unwrap();
+        for key in ["refresh:u1:a", "refresh:u1:b", "refresh:u2:a", "user:u1"] {
+            cache.insert(key, String::new(), None);
+        }
+
+        cache.remove_matching("refresh:u1:*");
+        assert!(cache.get("refresh:u1:a").is_none());
+        assert!(cache.get("refresh:u1:b").is_none());
+        assert!(cache.get("refresh:u2:a").is_some());
+        assert!(cache.get("user:u1").is_some());
+
+        assert!(glob_match("licenses:*:status:*", "licenses:x:status:approved"));
+        assert!(!glob_match("licenses:*:status:*", "licenses:x:type:nib"));
+        assert!(glob_match("exact", "exact"));
+    }
+}
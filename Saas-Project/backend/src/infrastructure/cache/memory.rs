@@ -0,0 +1,103 @@
+// In-memory `Cache` for tests and single-process runs without Redis
+// Same semantics as `CacheService`: JSON values, expiry, tag sets and glob
+// pattern deletes.
+
+use async_trait::async_trait;
+use redis::RedisError;
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::local::glob_match;
+use super::{serialize, Cache};
+
+#[derive(Default)]
+struct Entries {
+    values: HashMap<String, (String, Option<Instant>)>,
+    tags: HashMap<String, HashSet<String>>,
+}
+
+#[derive(Default)]
+pub struct InMemoryCache {
+    entries: Mutex<Entries>,
+}
+
+impl InMemoryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        let entries = self.entries.lock().expect("memory cache poisoned");
+        entries
+            .values
+            .get(key)
+            .is_some_and(|(_, expires_at)| expires_at.is_none_or(|at| at > Instant::now()))
+    }
+}
+
+#[async_trait]
+impl Cache for InMemoryCache {
+    async fn set<T: Serialize + Send + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        expiry_secs: Option<u64>,
+    ) -> Result<(), RedisError> {
+        self.set_tagged(key, value, expiry_secs, &[]).await
+    }
+
+    async fn get<T: DeserializeOwned + Send + Sync>(&self, key: &str) -> Result<Option<T>, RedisError> {
+        if !self.contains(key) {
+            return Ok(None);
+        }
+        let entries = self.entries.lock().expect("memory cache poisoned");
+        let (data, _) = &entries.values[key];
+        serde_json::from_str(data).map(Some).map_err(|e| {
+            RedisError::from(std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))
+        })
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), RedisError> {
+        self.entries.lock().expect("memory cache poisoned").values.remove(key);
+        Ok(())
+    }
+
+    async fn delete_by_pattern(&self, pattern: &str) -> Result<(), RedisError> {
+        self.entries
+            .lock()
+            .expect("memory cache poisoned")
+            .values
+            .retain(|key, _| !glob_match(pattern, key));
+        Ok(())
+    }
+
+    async fn set_tagged<T: Serialize + Send + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        expiry_secs: Option<u64>,
+        tags: &[String],
+    ) -> Result<(), RedisError> {
+        let data = serialize(value)?;
+        let expires_at = expiry_secs.map(|secs| Instant::now() + Duration::from_secs(secs));
+
+        let mut entries = self.entries.lock().expect("memory cache poisoned");
+        entries.values.insert(key.to_string(), (data, expires_at));
+        for tag in tags {
+            entries.tags.entry(tag.clone()).or_default().insert(key.to_string());
+        }
+        Ok(())
+    }
+
+    async fn invalidate_tags(&self, tags: &[String]) -> Result<(), RedisError> {
+        let mut entries = self.entries.lock().expect("memory cache poisoned");
+        for tag in tags {
+            for key in entries.tags.remove(tag).unwrap_or_default() {
+                entries.values.remove(&key);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,366 @@
+// Redis cache with an optional in-process L1
+// Values are stored as JSON. `set_tagged` also adds the key to Redis sets
+// named after its tags, so related entries are dropped together with
+// `invalidate_tags` instead of scanning the keyspace. Every write and delete
+// is broadcast so other instances drop their L1 copies.
+
+mod invalidation;
+mod local;
+mod memory;
+mod single_flight;
+
+pub use invalidation::Invalidation;
+pub use local::LocalCache;
+pub use memory::InMemoryCache;
+pub use single_flight::{cache_aside, SingleFlight};
+
+use async_trait::async_trait;
+use redis::{AsyncCommands, Client, RedisError, Script};
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, instrument, warn};
+use uuid::Uuid;
+
+use crate::infrastructure::monitoring::{track_cache_lookup, CacheOutcome};
+
+/// Tag sets outlive their members; stale members only cost a no-op delete
+const TAG_TTL_SECS: i64 = 86_400;
+
+/// Keys examined per SCAN round trip
+const SCAN_BATCH: usize = 500;
+
+/// Deletes every key of a tag set and the set itself, atomically, so a key
+/// tagged concurrently is either deleted or kept in a fresh set
+const INVALIDATE_TAG_SCRIPT: &str = r#"
+local keys = redis.call('SMEMBERS', KEYS[1])
+for i = 1, #keys, 500 do
+    redis.call('UNLINK', unpack(keys, i, math.min(i + 499, #keys)))
+end
+redis.call('DEL', KEYS[1])
+return keys
+"#;
+
+#[async_trait]
+pub trait Cache: Send + Sync {
+    async fn set<T: Serialize + Send + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        expiry_secs: Option<u64>,
+    ) -> Result<(), RedisError>;
+
+    async fn get<T: DeserializeOwned + Send + Sync>(&self, key: &str) -> Result<Option<T>, RedisError>;
+
+    async fn delete(&self, key: &str) -> Result<(), RedisError>;
+    async fn delete_by_pattern(&self, pattern: &str) -> Result<(), RedisError>;
+
+    /// Like `set`, and records the key under each tag
+    async fn set_tagged<T: Serialize + Send + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        expiry_secs: Option<u64>,
+        tags: &[String],
+    ) -> Result<(), RedisError>;
+
+    /// Deletes every key recorded under the tags
+    async fn invalidate_tags(&self, tags: &[String]) -> Result<(), RedisError>;
+}
+
+fn tag_key(tag: &str) -> String {
+    format!("tag:{}", tag)
+}
+
+fn serialize<T: Serialize>(value: &T) -> Result<String, RedisError> {
+    serde_json::to_string(value).map_err(|e| {
+        error!("Serialization error: {}", e);
+        RedisError::from(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Failed to serialize value",
+        ))
+    })
+}
+
+fn deserialize<T: DeserializeOwned>(key: &str, data: &str) -> Result<T, RedisError> {
+    serde_json::from_str(data).map_err(|e| {
+        error!("Deserialization error for key {}: {}", key, e);
+        RedisError::from(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Failed to deserialize value",
+        ))
+    })
+}
+
+#[derive(Clone)]
+pub struct CacheService {
+    client: Client,
+    local: Option<Arc<LocalCache>>,
+    /// Identifies this process in invalidation messages
+    instance_id: Arc<str>,
+}
+
+impl CacheService {
+    #[instrument(skip(redis_url))]
+    pub fn new(redis_url: &str) -> Result<Self, RedisError> {
+        info!("🔄 Initializing Redis cache service");
+        let client = Client::open(redis_url)?;
+        info!("✅ Redis cache service initialized");
+
+        Ok(Self {
+            client,
+            local: None,
+            instance_id: Uuid::new_v4().to_string().into(),
+        })
+    }
+
+    /// Adds the in-process L1; call `spawn_invalidation_listener` as well when
+    /// more than one instance shares the Redis
+    pub fn with_local_cache(mut self, local: LocalCache) -> Self {
+        self.local = Some(Arc::new(local));
+        self
+    }
+
+    /// Keeps the L1 consistent with writes made by other instances
+    pub fn spawn_invalidation_listener(&self) -> Option<JoinHandle<()>> {
+        let local = self.local.clone()?;
+        Some(invalidation::spawn_listener(
+            self.client.clone(),
+            local,
+            self.instance_id.clone(),
+        ))
+    }
+
+    /// Drops keys and patterns from the local L1 and tells the other instances
+    async fn broadcast(
+        &self,
+        conn: &mut redis::aio::Connection,
+        keys: Vec<String>,
+        patterns: Vec<String>,
+    ) {
+        let Some(local) = &self.local else {
+            return;
+        };
+
+        let invalidation = Invalidation {
+            origin: self.instance_id.to_string(),
+            keys,
+            patterns,
+        };
+        invalidation.apply(local);
+        if let Err(err) = invalidation.publish(conn).await {
+            warn!("Failed to publish cache invalidation: {}", err);
+        }
+    }
+
+    #[instrument(skip(self, key, value), fields(key = %key))]
+    pub async fn set<T: Serialize>(
+        &self,
+        key: &str,
+        value: &T,
+        expiry_secs: Option<u64>,
+    ) -> Result<(), RedisError> {
+        self.set_tagged(key, value, expiry_secs, &[]).await
+    }
+
+    #[instrument(skip(self, key, value, tags), fields(key = %key))]
+    pub async fn set_tagged<T: Serialize>(
+        &self,
+        key: &str,
+        value: &T,
+        expiry_secs: Option<u64>,
+        tags: &[String],
+    ) -> Result<(), RedisError> {
+        let mut conn = self.client.get_async_connection().await?;
+        let serialized = serialize(value)?;
+
+        debug!("Setting cache key: {}", key);
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        match expiry_secs {
+            Some(secs) => pipe.set_ex(key, &serialized, secs).ignore(),
+            None => pipe.set(key, &serialized).ignore(),
+        };
+        for tag in tags {
+            let tag_key = tag_key(tag);
+            pipe.sadd(&tag_key, key).ignore();
+            pipe.expire(&tag_key, TAG_TTL_SECS).ignore();
+        }
+        pipe.query_async::<_, ()>(&mut conn).await?;
+        debug!("Cache key set with expiry: {:?}s, tags: {:?}", expiry_secs, tags);
+
+        // Other instances may hold the previous value
+        self.broadcast(&mut conn, vec![key.to_string()], vec![]).await;
+        if let Some(local) = &self.local {
+            local.insert(key, serialized, expiry_secs);
+        }
+
+        Ok(())
+    }
+
+    #[instrument(skip(self), fields(key = %key))]
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, RedisError> {
+        if let Some(local) = &self.local {
+            match local.get(key) {
+                Some(data) => {
+                    track_cache_lookup("local", CacheOutcome::Hit);
+                    return deserialize(key, &data).map(Some);
+                }
+                None => track_cache_lookup("local", CacheOutcome::Miss),
+            }
+        }
+
+        let result = self.lookup(key).await;
+
+        let outcome = match &result {
+            Ok(Some(_)) => CacheOutcome::Hit,
+            Ok(None) => CacheOutcome::Miss,
+            Err(_) => CacheOutcome::Error,
+        };
+        track_cache_lookup("redis", outcome);
+
+        match result? {
+            Some(data) => {
+                let value = deserialize(key, &data)?;
+                if let Some(local) = &self.local {
+                    local.insert(key, data, None);
+                }
+                Ok(Some(value))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn lookup(&self, key: &str) -> Result<Option<String>, RedisError> {
+        let mut conn = self.client.get_async_connection().await?;
+
+        debug!("Getting cache key: {}", key);
+
+        let result: Option<String> = conn.get(key).await?;
+        match &result {
+            Some(_) => debug!("Cache hit for key: {}", key),
+            None => debug!("Cache miss for key: {}", key),
+        }
+        Ok(result)
+    }
+
+    #[instrument(skip(self), fields(key = %key))]
+    pub async fn delete(&self, key: &str) -> Result<(), RedisError> {
+        let mut conn = self.client.get_async_connection().await?;
+        debug!("Deleting cache key: {}", key);
+        conn.del::<_, ()>(key).await?;
+        self.broadcast(&mut conn, vec![key.to_string()], vec![]).await;
+        debug!("Cache key deleted: {}", key);
+        Ok(())
+    }
+
+    /// Deletes keys matching a glob pattern. Walks the keyspace with SCAN, which
+    /// unlike KEYS does not block Redis, but still touches every key; prefer
+    /// tags for anything on a hot path.
+    #[instrument(skip(self), fields(pattern = %pattern))]
+    pub async fn delete_by_pattern(&self, pattern: &str) -> Result<(), RedisError> {
+        let mut conn = self.client.get_async_connection().await?;
+
+        debug!("Deleting cache keys by pattern: {}", pattern);
+
+        let keys: Vec<String> = {
+            let mut scan = redis::cmd("SCAN");
+            scan.cursor_arg(0)
+                .arg("MATCH")
+                .arg(pattern)
+                .arg("COUNT")
+                .arg(SCAN_BATCH);
+            let mut iter = scan.iter_async::<String>(&mut conn).await?;
+            let mut keys = Vec::new();
+            while let Some(key) = iter.next_item().await {
+                keys.push(key);
+            }
+            keys
+        };
+
+        for batch in keys.chunks(SCAN_BATCH) {
+            redis::cmd("UNLINK")
+                .arg(batch)
+                .query_async::<_, ()>(&mut conn)
+                .await?;
+        }
+        debug!("Deleted {} cache keys matching pattern: {}", keys.len(), pattern);
+
+        self.broadcast(&mut conn, vec![], vec![pattern.to_string()]).await;
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    pub async fn invalidate_tags(&self, tags: &[String]) -> Result<(), RedisError> {
+        if tags.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.client.get_async_connection().await?;
+        let script = Script::new(INVALIDATE_TAG_SCRIPT);
+
+        let mut deleted = Vec::new();
+        for tag in tags {
+            let keys: Vec<String> = script.key(tag_key(tag)).invoke_async(&mut conn).await?;
+            deleted.extend(keys);
+        }
+        debug!("Invalidated {} cache keys for tags {:?}", deleted.len(), tags);
+
+        self.broadcast(&mut conn, deleted, vec![]).await;
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    pub async fn health_check(&self) -> Result<(), RedisError> {
+        let mut conn = self.client.get_async_connection().await?;
+
+        let pong: String = redis::cmd("PING").query_async(&mut conn).await?;
+
+        if pong != "PONG" {
+            return Err(RedisError::from(std::io::Error::new(
+                std::io::ErrorKind::ConnectionAborted,
+                "Redis health check failed",
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Cache for CacheService {
+    async fn set<T: Serialize + Send + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        expiry_secs: Option<u64>,
+    ) -> Result<(), RedisError> {
+        self.set(key, value, expiry_secs).await
+    }
+
+    async fn get<T: DeserializeOwned + Send + Sync>(&self, key: &str) -> Result<Option<T>, RedisError> {
+        self.get(key).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), RedisError> {
+        self.delete(key).await
+    }
+
+    async fn delete_by_pattern(&self, pattern: &str) -> Result<(), RedisError> {
+        self.delete_by_pattern(pattern).await
+    }
+
+    async fn set_tagged<T: Serialize + Send + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        expiry_secs: Option<u64>,
+        tags: &[String],
+    ) -> Result<(), RedisError> {
+        self.set_tagged(key, value, expiry_secs, tags).await
+    }
+
+    async fn invalidate_tags(&self, tags: &[String]) -> Result<(), RedisError> {
+        self.invalidate_tags(tags).await
+    }
+}
@@ -0,0 +1,156 @@
+// Cache-aside loading with stampede protection
+// When a hot key expires, concurrent misses in this process queue behind one
+// loader and then read what it cached, instead of all hitting the database.
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OwnedMutexGuard;
+use tracing::warn;
+
+use super::Cache;
+
+#[derive(Default)]
+pub struct SingleFlight {
+    flights: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+/// Held by the caller loading a key; dropped when it is done
+pub struct Flight<'a> {
+    owner: &'a SingleFlight,
+    key: String,
+    _guard: OwnedMutexGuard<()>,
+}
+
+impl SingleFlight {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Waits until no other caller is loading `key`
+    pub async fn acquire(&self, key: &str) -> Flight<'_> {
+        let lock = self
+            .flights
+            .lock()
+            .expect("single flight map poisoned")
+            .entry(key.to_string())
+            .or_default()
+            .clone();
+
+        Flight {
+            owner: self,
+            key: key.to_string(),
+            _guard: lock.lock_owned().await,
+        }
+    }
+
+    #[cfg(test)]
+    fn in_flight(&self) -> usize {
+        self.flights.lock().unwrap().len()
+    }
+}
+
+impl Drop for Flight<'_> {
+    fn drop(&mut self) {
+        let mut flights = self.owner.flights.lock().expect("single flight map poisoned");
+        // The map and this guard hold the only references unless someone is waiting
+        if flights
+            .get(&self.key)
+            .is_some_and(|lock| Arc::strong_count(lock) <= 2)
+        {
+            flights.remove(&self.key);
+        }
+    }
+}
+
+/// Returns the cached value of `key`, or loads it once and caches it. `tags`
+/// decides whether a loaded value is cached and under which tags, so callers
+/// can skip caching `None` or empty results.
+pub async fn cache_aside<C, T, E, F, Fut>(
+    cache: &C,
+    flights: &SingleFlight,
+    key: &str,
+    expiry_secs: u64,
+    tags: impl FnOnce(&T) -> Option<Vec<String>>,
+    load: F,
+) -> Result<T, E>
+where
+    C: Cache + ?Sized,
+    T: Serialize + DeserializeOwned + Send + Sync,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    if let Ok(Some(value)) = cache.get::<T>(key).await {
+        return Ok(value);
+    }
+
+    let _flight = flights.acquire(key).await;
+    // Filled by the caller we waited for
+    if let Ok(Some(value)) = cache.get::<T>(key).await {
+        return Ok(value);
+    }
+
+    let value = load().await?;
+    if let Some(tags) = tags(&value) {
+        if let Err(err) = cache.set_tagged(key, &value, Some(expiry_secs), &tags).await {
+            warn!("Failed to cache {}: {}", key, err);
+        }
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::cache::InMemoryCache;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_concurrent_misses_load_once() {
+        let cache = InMemoryCache::new();
+        let flights = SingleFlight::new();
+        let loads = AtomicUsize::new(0);
+
+        let load = || async {
+            loads.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            Ok::<_, crate::shared::errors::AppError>(42u32)
+        };
+        let tags = |_: &u32| Some(vec!["answer".to_string()]);
+
+        let results = futures::future::join_all(
+            (0..8).map(|_| cache_aside(&cache, &flights, "answer", 60, tags, load)),
+        )
+        .await;
+
+        assert!(results.iter().all(|result| *result.as_ref().unwrap() == 42));
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+        assert_eq!(flights.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_uncacheable_values_are_loaded_every_time() {
+        let cache = InMemoryCache::new();
+        let flights = SingleFlight::new();
+        let loads = AtomicUsize::new(0);
+
+        for _ in 0..2 {
+            let value: Option<u32> = cache_aside(
+                &cache,
+                &flights,
+                "missing",
+                60,
+                |value: &Option<u32>| value.map(|_| vec![]),
+                || async {
+                    loads.fetch_add(1, Ordering::SeqCst);
+                    Ok::<_, crate::shared::errors::AppError>(None)
+                },
+            )
+            .await
+            .unwrap();
+            assert!(value.is_none());
+        }
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,306 @@
+// License certificate PDFs
+// One A4 page in Bahasa Indonesia with English subtitles: the license number,
+// type, company, issuing authority and validity, plus a QR code of the public
+// verification link. Only the standard Helvetica fonts are used, so nothing
+// has to be embedded; text outside Latin-1 is replaced.
+
+use chrono::{DateTime, Utc};
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+use qrcode::{Color, EcLevel, QrCode};
+
+use crate::domain::analytics::reporting_day;
+use crate::domain::companies::Company;
+use crate::domain::licenses::{ApplicationStatus, License, LicenseType};
+use crate::domain::verification::PublicLicenseStatus;
+use crate::shared::errors::{AppError, AppResult};
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 36.0;
+const QR_SIZE: f32 = 140.0;
+/// Modules of blank space around a QR code that scanners expect
+const QR_QUIET_ZONE: usize = 4;
+
+const REGULAR: Name = Name(b"F1");
+const BOLD: Name = Name(b"F2");
+
+/// What a certificate shows
+pub struct CertificateContent<'a> {
+    pub license: &'a License,
+    pub company: &'a Company,
+    /// Public link the QR code points to
+    pub verification_url: &'a str,
+    pub generated_at: DateTime<Utc>,
+}
+
+fn license_type_name(license_type: LicenseType) -> &'static str {
+    match license_type {
+        LicenseType::Nib => "NIB - Nomor Induk Berusaha",
+        LicenseType::Siup => "SIUP - Surat Izin Usaha Perdagangan",
+        LicenseType::Tdp => "TDP - Tanda Daftar Perusahaan",
+        LicenseType::Npwp => "NPWP - Nomor Pokok Wajib Pajak",
+        LicenseType::Halal => "Sertifikat Halal",
+        LicenseType::Environmental => "Izin Lingkungan",
+        LicenseType::ExportImport => "Izin Ekspor-Impor",
+    }
+}
+
+fn status_name(license: &License) -> &'static str {
+    match PublicLicenseStatus::of(license) {
+        PublicLicenseStatus::Active => "AKTIF / ACTIVE",
+        PublicLicenseStatus::Expired => "KEDALUWARSA / EXPIRED",
+        PublicLicenseStatus::Suspended => "DIBEKUKAN / SUSPENDED",
+        PublicLicenseStatus::Invalid => "TIDAK BERLAKU / NOT VALID",
+    }
+}
+
+/// Day in Jakarta time, e.g. `17-08-2024`
+fn date(at: DateTime<Utc>) -> String {
+    reporting_day(at).format("%d-%m-%Y").to_string()
+}
+
+/// `text` in WinAnsiEncoding, which matches Latin-1 from 0xA0 up
+pub(crate) fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x20..=0x7E | 0xA0..=0xFF => c as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+pub(crate) fn text(content: &mut Content, font: Name, size: f32, x: f32, y: f32, value: &str) {
+    content
+        .begin_text()
+        .set_font(font, size)
+        .next_line(x, y)
+        .show(Str(&win_ansi(value)))
+        .end_text();
+}
+
+/// Draws `code` with its lower left corner at `(x, y)`, quiet zone included
+fn qr_code(content: &mut Content, code: &QrCode, x: f32, y: f32, size: f32) {
+    let width = code.width();
+    let module = size / (width + 2 * QR_QUIET_ZONE) as f32;
+    let colors = code.to_colors();
+
+    content.set_fill_gray(0.0);
+    for row in 0..width {
+        // Runs of dark modules become one rectangle each
+        let mut column = 0;
+        while column < width {
+            if colors[row * width + column] != Color::Dark {
+                column += 1;
+                continue;
+            }
+            let start = column;
+            while column < width && colors[row * width + column] == Color::Dark {
+                column += 1;
+            }
+            content.rect(
+                x + (start + QR_QUIET_ZONE) as f32 * module,
+                y + size - (row + QR_QUIET_ZONE + 1) as f32 * module,
+                (column - start) as f32 * module,
+                module,
+            );
+        }
+    }
+    content.fill_nonzero();
+}
+
+/// Renders the certificate as a one-page PDF
+pub fn render_certificate(certificate: &CertificateContent) -> AppResult<Vec<u8>> {
+    let license = certificate.license;
+    let code = QrCode::with_error_correction_level(certificate.verification_url, EcLevel::M)
+        .map_err(|err| AppError::InternalError(format!("Failed to encode QR code: {}", err)))?;
+
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    let page_id = Ref::new(3);
+    let content_id = Ref::new(4);
+    let regular_id = Ref::new(5);
+    let bold_id = Ref::new(6);
+    let info_id = Ref::new(7);
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id).kids([page_id]).count(1);
+    let mut page = pdf.page(page_id);
+    page.parent(page_tree_id)
+        .media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT))
+        .contents(content_id);
+    page.resources()
+        .fonts()
+        .pair(REGULAR, regular_id)
+        .pair(BOLD, bold_id);
+    page.finish();
+    for (id, font) in [(regular_id, "Helvetica"), (bold_id, "Helvetica-Bold")] {
+        pdf.type1_font(id)
+            .base_font(Name(font.as_bytes()))
+            .encoding_predefined(Name(b"WinAnsiEncoding"));
+    }
+    let license_number = license.license_number.as_deref().unwrap_or("-");
+    pdf.document_info(info_id)
+        .title(TextStr(&format!("Sertifikat Izin {}", license_number)))
+        .creator(TextStr("SaaS UMKM"));
+
+    let mut content = Content::new();
+
+    // Double frame
+    content.set_stroke_rgb(0.05, 0.35, 0.25).set_line_width(2.0);
+    content
+        .rect(MARGIN, MARGIN, PAGE_WIDTH - 2.0 * MARGIN, PAGE_HEIGHT - 2.0 * MARGIN)
+        .stroke();
+    content.set_line_width(0.5);
+    content
+        .rect(MARGIN + 6.0, MARGIN + 6.0, PAGE_WIDTH - 2.0 * MARGIN - 12.0, PAGE_HEIGHT - 2.0 * MARGIN - 12.0)
+        .stroke();
+
+    let left = MARGIN + 36.0;
+    let mut y = PAGE_HEIGHT - MARGIN - 70.0;
+    content.set_fill_rgb(0.05, 0.35, 0.25);
+    text(&mut content, BOLD, 24.0, left, y, "SERTIFIKAT IZIN USAHA");
+    y -= 20.0;
+    text(&mut content, REGULAR, 12.0, left, y, "License Certificate");
+
+    y -= 40.0;
+    if license.is_active() {
+        content.set_fill_rgb(0.05, 0.35, 0.25);
+    } else {
+        content.set_fill_rgb(0.75, 0.1, 0.1);
+    }
+    text(&mut content, BOLD, 16.0, left, y, status_name(license));
+
+    let validity = match license.expiry_date {
+        Some(expiry) => date(expiry),
+        None => "Tidak terbatas / No expiry".to_string(),
+    };
+    let mut fields = vec![
+        ("Nomor Izin / License Number", license_number.to_string()),
+        ("Jenis Izin / License Type", license_type_name(license.license_type).to_string()),
+        ("Nama Usaha / Business Name", certificate.company.company_name.clone()),
+    ];
+    if let Some(nib) = &certificate.company.nib {
+        fields.push(("NIB / Business Identification Number", nib.clone()));
+    }
+    fields.extend([
+        (
+            "Diterbitkan oleh / Issuing Authority",
+            license.issuing_authority.clone().unwrap_or_else(|| "-".to_string()),
+        ),
+        (
+            "Tanggal Terbit / Issue Date",
+            license.issue_date.map(date).unwrap_or_else(|| "-".to_string()),
+        ),
+        ("Berlaku Hingga / Valid Until", validity),
+    ]);
+
+    y -= 20.0;
+    for (label, value) in &fields {
+        y -= 34.0;
+        content.set_fill_gray(0.4);
+        text(&mut content, REGULAR, 9.0, left, y + 14.0, label);
+        content.set_fill_gray(0.0);
+        text(&mut content, BOLD, 14.0, left, y, value);
+    }
+    if license.application_status == ApplicationStatus::Suspended {
+        if let Some(reason) = &license.admin_notes {
+            y -= 34.0;
+            content.set_fill_gray(0.4);
+            text(&mut content, REGULAR, 9.0, left, y + 14.0, "Alasan pembekuan / Suspension reason");
+            content.set_fill_gray(0.0);
+            text(&mut content, REGULAR, 12.0, left, y, reason);
+        }
+    }
+
+    // Verification QR code, bottom right
+    let qr_x = PAGE_WIDTH - MARGIN - 36.0 - QR_SIZE;
+    let qr_y = MARGIN + 70.0;
+    qr_code(&mut content, &code, qr_x, qr_y, QR_SIZE);
+    content.set_fill_gray(0.0);
+    text(&mut content, BOLD, 9.0, qr_x, qr_y - 10.0, "Pindai untuk verifikasi");
+    text(&mut content, REGULAR, 8.0, qr_x, qr_y - 21.0, "Scan to verify authenticity");
+
+    content.set_fill_gray(0.4);
+    text(
+        &mut content,
+        REGULAR,
+        8.0,
+        left,
+        MARGIN + 40.0,
+        &format!(
+            "Dokumen elektronik, dibuat {} (WIB). Keaslian dapat diperiksa di:",
+            date(certificate.generated_at)
+        ),
+    );
+    text(&mut content, REGULAR, 7.0, left, MARGIN + 28.0, certificate.verification_url);
+
+    pdf.stream(content_id, &content.finish());
+    Ok(pdf.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::companies::{BusinessType, CompanyAddress};
+    use uuid::Uuid;
+
+    fn company() -> Company {
+        let mut company = Company::new(
+            Uuid::new_v4(),
+            "Warung Makan Bu Siti".to_string(),
+            BusinessType::UD,
+            "Kuliner".to_string(),
+            CompanyAddress::new(
+                "Jl. Melati 1".to_string(),
+                "Bandung".to_string(),
+                "Jawa Barat".to_string(),
+                "40111".to_string(),
+            ),
+        );
+        company.nib = Some("1234567890123".to_string());
+        company
+    }
+
+    #[test]
+    fn test_win_ansi_keeps_latin1_and_replaces_the_rest() {
+        assert_eq!(win_ansi("Café (Bu) Siti"), b"Caf\xe9 (Bu) Siti".to_vec());
+        assert_eq!(win_ansi("Kopi ☕"), b"Kopi ?".to_vec());
+    }
+
+    #[test]
+    fn test_renders_a_pdf_with_the_license_details() {
+        let company = company();
+        let mut license = License::new(LicenseType::Siup, company.id, company.owner_id, "SIUP".to_string(), None);
+        license.application_status = ApplicationStatus::Processing;
+        license
+            .approve("SIUP/2024/0001".to_string(), Utc::now(), None, "DPMPTSP Bandung".to_string(), None)
+            .unwrap();
+
+        let url = "https://app.example.id/api/v1/verify/abc";
+        let pdf = render_certificate(&CertificateContent {
+            license: &license,
+            company: &company,
+            verification_url: url,
+            generated_at: Utc::now(),
+        })
+        .unwrap();
+
+        assert!(pdf.starts_with(b"%PDF-"));
+        let body = String::from_utf8_lossy(&pdf);
+        for expected in ["SIUP/2024/0001", "Warung Makan Bu Siti", "DPMPTSP Bandung", "AKTIF", url] {
+            assert!(body.contains(expected), "missing {expected}");
+        }
+
+        license.suspend("Pelanggaran izin".to_string()).unwrap();
+        let suspended = render_certificate(&CertificateContent {
+            license: &license,
+            company: &company,
+            verification_url: url,
+            generated_at: Utc::now(),
+        })
+        .unwrap();
+        let body = String::from_utf8_lossy(&suspended);
+        assert!(body.contains("DIBEKUKAN") && body.contains("Pelanggaran izin"));
+    }
+}
@@ -0,0 +1,110 @@
+// Keyset-paginated list queries built with sea-query
+// Repositories describe the filtered SELECT; ordering, the cursor condition,
+// the page limit and the optional COUNT are applied here.
+
+use sea_query::{Alias, Asterisk, Expr, Func, Order, PostgresQueryBuilder, SelectStatement, SimpleExpr};
+use sea_query_binder::SqlxBinder;
+use sqlx::postgres::PgRow;
+use sqlx::{FromRow, PgConnection, Row};
+
+use crate::shared::query::{ListQuery, Page, SortDirection, SortField, SortValue, Sortable};
+
+/// Every listable table uses `id` as the keyset tie-breaker
+const ID_COLUMN: &str = "id";
+
+fn sort_value_expr(value: &SortValue) -> SimpleExpr {
+    match value {
+        SortValue::Integer(v) => Expr::val(*v).into(),
+        SortValue::Timestamp(v) => Expr::val(*v).into(),
+        SortValue::Text(v) => Expr::val(v.as_str()).into(),
+    }
+}
+
+/// Escapes `\`, `%` and `_` so the text matches literally, e.g. for a
+/// case-insensitive equality check with ILIKE
+pub fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Pattern for a literal, case-insensitive substring match
+pub fn contains_pattern(text: &str) -> String {
+    format!("%{}%", escape_like(text))
+}
+
+/// Adds ordering, the keyset condition and the page limit to a filtered select.
+///
+/// Rows are ordered by the sort column and then by id in the same direction,
+/// so `(column, id)` row comparison picks up exactly after the cursor.
+pub fn page_statement<T, F: SortField>(base: &SelectStatement, query: &ListQuery<T, F>) -> SelectStatement {
+    let mut select = base.clone();
+    let column = Alias::new(query.sort.field.column());
+    let order = match query.sort.direction {
+        SortDirection::Asc => Order::Asc,
+        SortDirection::Desc => Order::Desc,
+    };
+
+    if let Some((value, id)) = &query.after {
+        let key = Expr::tuple([Expr::col(column.clone()).into(), Expr::col(Alias::new(ID_COLUMN)).into()]);
+        let cursor = Expr::tuple([sort_value_expr(value), Expr::val(*id).into()]);
+        select.and_where(match query.sort.direction {
+            SortDirection::Asc => key.gt(cursor),
+            SortDirection::Desc => key.lt(cursor),
+        });
+    }
+
+    select
+        .order_by(column, order.clone())
+        .order_by(Alias::new(ID_COLUMN), order)
+        .limit(query.fetch_limit());
+    select
+}
+
+/// `SELECT COUNT(*)` over the same filter as the base select
+pub fn count_statement(base: &SelectStatement) -> SelectStatement {
+    let mut count = base.clone();
+    count
+        .clear_selects()
+        .expr_as(Func::count(Expr::col(Asterisk)), Alias::new("count"));
+    count
+}
+
+/// Fetches one page of raw rows plus the total when requested, both on the
+/// same connection so a unit of work sees its own writes
+pub async fn fetch_rows<T, F: SortField>(
+    conn: &mut PgConnection,
+    base: &SelectStatement,
+    query: &ListQuery<T, F>,
+) -> Result<(Vec<PgRow>, Option<i64>), sqlx::Error> {
+    let (sql, values) = page_statement(base, query).build_sqlx(PostgresQueryBuilder);
+    let rows = sqlx::query_with(&sql, values).fetch_all(&mut *conn).await?;
+
+    let total = if query.include_total {
+        let (sql, values) = count_statement(base).build_sqlx(PostgresQueryBuilder);
+        let row = sqlx::query_with(&sql, values).fetch_one(&mut *conn).await?;
+        Some(row.try_get::<i64, _>("count")?)
+    } else {
+        None
+    };
+
+    Ok((rows, total))
+}
+
+/// Fetches one page of entities that map straight from their table row
+pub async fn fetch_page<E, T, F>(
+    conn: &mut PgConnection,
+    base: &SelectStatement,
+    query: &ListQuery<T, F>,
+) -> Result<Page<E>, sqlx::Error>
+where
+    E: for<'r> FromRow<'r, PgRow> + Sortable<F>,
+    F: SortField,
+{
+    let (rows, total) = fetch_rows(conn, base, query).await?;
+    let items = rows
+        .iter()
+        .map(E::from_row)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Page::from_rows(items, query, total))
+}
@@ -1,7 +1,9 @@
 use sqlx::postgres::{PgPoolOptions, PgPool};
-use tracing::{instrument, info, error};
+use tracing::{debug, instrument, info, error};
 use std::time::Duration;
 
+use super::migrations::MIGRATOR;
+
 #[derive(Clone)]
 pub struct DatabaseManager {
     pool: PgPool,
@@ -34,7 +36,8 @@ impl DatabaseManager {
             
         match result {
             Ok(_) => {
-                info!("Database health check successful");
+                // Called by every readiness probe
+                debug!("Database health check successful");
                 Ok(true)
             },
             Err(err) => {
@@ -48,7 +51,7 @@ impl DatabaseManager {
     pub async fn run_migrations(&self) -> Result<(), sqlx::Error> {
         info!("Running database migrations");
         
-        match sqlx::migrate!("./migrations").run(&self.pool).await {
+        match MIGRATOR.run(&self.pool).await {
             Ok(_) => {
                 info!("Database migrations completed successfully");
                 Ok(())
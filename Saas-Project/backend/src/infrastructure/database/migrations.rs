@@ -0,0 +1,156 @@
+// Versioned schema migrations embedded from ./migrations
+// Every migration is reversible: `<version>_<name>.up.sql` is paired with a
+// `.down.sql` that undoes it. DatabaseManager and the migrate binary share the
+// migrator defined here.
+
+use chrono::{DateTime, Utc};
+use sqlx::migrate::Migrator;
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Started but did not finish; the database needs manual attention
+    Failed,
+    /// The file changed after it was applied
+    ChecksumMismatch,
+    /// Applied to the database but no longer part of the crate
+    Missing,
+}
+
+impl std::fmt::Display for MigrationState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MigrationState::Applied => write!(f, "applied"),
+            MigrationState::Pending => write!(f, "pending"),
+            MigrationState::Failed => write!(f, "failed"),
+            MigrationState::ChecksumMismatch => write!(f, "checksum mismatch"),
+            MigrationState::Missing => write!(f, "missing"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+    pub installed_on: Option<DateTime<Utc>>,
+}
+
+struct AppliedMigration {
+    description: String,
+    installed_on: DateTime<Utc>,
+    success: bool,
+    checksum: Vec<u8>,
+}
+
+/// Up migrations known to the crate, in version order
+fn source_migrations() -> impl Iterator<Item = &'static sqlx::migrate::Migration> {
+    MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration())
+}
+
+/// Newest migration version shipped with the crate
+pub fn latest_version() -> Option<i64> {
+    source_migrations().map(|m| m.version).max()
+}
+
+pub fn is_known_version(version: i64) -> bool {
+    source_migrations().any(|m| m.version == version)
+}
+
+async fn applied_migrations(pool: &PgPool) -> Result<HashMap<i64, AppliedMigration>, sqlx::Error> {
+    let exists: bool = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Ok(HashMap::new());
+    }
+
+    let rows = sqlx::query(
+        "SELECT version, description, installed_on, success, checksum FROM _sqlx_migrations",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.iter()
+        .map(|row| {
+            Ok((
+                row.try_get("version")?,
+                AppliedMigration {
+                    description: row.try_get("description")?,
+                    installed_on: row.try_get("installed_on")?,
+                    success: row.try_get("success")?,
+                    checksum: row.try_get("checksum")?,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// State of every migration, in version order
+pub async fn migration_status(pool: &PgPool) -> Result<Vec<MigrationStatus>, sqlx::Error> {
+    let mut applied = applied_migrations(pool).await?;
+
+    let mut statuses: Vec<MigrationStatus> = source_migrations()
+        .map(|migration| {
+            let record = applied.remove(&migration.version);
+            let state = match &record {
+                None => MigrationState::Pending,
+                Some(r) if !r.success => MigrationState::Failed,
+                Some(r) if r.checksum != migration.checksum.as_ref() => MigrationState::ChecksumMismatch,
+                Some(_) => MigrationState::Applied,
+            };
+            MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                state,
+                installed_on: record.map(|r| r.installed_on),
+            }
+        })
+        .collect();
+
+    statuses.extend(applied.into_iter().map(|(version, record)| MigrationStatus {
+        version,
+        description: record.description,
+        state: MigrationState::Missing,
+        installed_on: Some(record.installed_on),
+    }));
+    statuses.sort_by_key(|s| s.version);
+
+    Ok(statuses)
+}
+
+/// Highest successfully applied version
+pub async fn current_version(pool: &PgPool) -> Result<Option<i64>, sqlx::Error> {
+    let applied = applied_migrations(pool).await?;
+    Ok(applied
+        .into_iter()
+        .filter(|(_, m)| m.success)
+        .map(|(version, _)| version)
+        .max())
+}
+
+/// Version the schema returns to when only the latest migration is undone;
+/// 0 when a single migration is applied and `None` when there is nothing to undo
+pub async fn previous_version(pool: &PgPool) -> Result<Option<i64>, sqlx::Error> {
+    let applied = applied_migrations(pool).await?;
+    let mut versions: Vec<i64> = applied
+        .into_iter()
+        .filter(|(_, m)| m.success)
+        .map(|(version, _)| version)
+        .collect();
+    versions.sort_unstable();
+
+    Ok(match versions.as_slice() {
+        [] => None,
+        [.., previous, _] => Some(*previous),
+        [_] => Some(0),
+    })
+}
@@ -1,3 +1,8 @@
 // Database infrastructure - PostgreSQL with SQLx
+pub mod list_query;
 pub mod manager;
+pub mod migrations;
+pub mod schema;
+pub mod unit_of_work;
+pub mod versioning;
 
@@ -0,0 +1,939 @@
+// Expected database schema, checked against a live database by `migrate verify`
+// The tables below list the columns the repositories read and write, with the
+// type family and nullability their row mappings assume. Extra columns and
+// tables in the database are fine; anything missing or incompatible is drift.
+
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Uuid,
+    /// text, varchar or char
+    Text,
+    Int4,
+    Int8,
+    Float8,
+    Bool,
+    Date,
+    Timestamptz,
+    Jsonb,
+    TsVector,
+    /// A Postgres enum type, by name
+    Enum(&'static str),
+}
+
+impl ColumnType {
+    /// Whether a column with the given `udt_name` decodes as this type
+    fn accepts(&self, udt_name: &str) -> bool {
+        match self {
+            ColumnType::Uuid => udt_name == "uuid",
+            ColumnType::Text => matches!(udt_name, "text" | "varchar" | "bpchar"),
+            ColumnType::Int4 => udt_name == "int4",
+            ColumnType::Int8 => udt_name == "int8",
+            ColumnType::Float8 => udt_name == "float8",
+            ColumnType::Bool => udt_name == "bool",
+            ColumnType::Date => udt_name == "date",
+            ColumnType::Timestamptz => udt_name == "timestamptz",
+            ColumnType::Jsonb => udt_name == "jsonb",
+            ColumnType::TsVector => udt_name == "tsvector",
+            ColumnType::Enum(name) => udt_name == *name,
+        }
+    }
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnType::Uuid => write!(f, "uuid"),
+            ColumnType::Text => write!(f, "text"),
+            ColumnType::Int4 => write!(f, "int4"),
+            ColumnType::Int8 => write!(f, "int8"),
+            ColumnType::Float8 => write!(f, "float8"),
+            ColumnType::Bool => write!(f, "bool"),
+            ColumnType::Date => write!(f, "date"),
+            ColumnType::Timestamptz => write!(f, "timestamptz"),
+            ColumnType::Jsonb => write!(f, "jsonb"),
+            ColumnType::TsVector => write!(f, "tsvector"),
+            ColumnType::Enum(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ColumnSpec {
+    pub name: &'static str,
+    pub column_type: ColumnType,
+    pub nullable: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TableSpec {
+    pub name: &'static str,
+    pub columns: &'static [ColumnSpec],
+}
+
+impl TableSpec {
+    pub fn column(&self, name: &str) -> Option<&ColumnSpec> {
+        self.columns.iter().find(|c| c.name == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EnumSpec {
+    pub name: &'static str,
+    pub labels: &'static [&'static str],
+}
+
+const fn required(name: &'static str, column_type: ColumnType) -> ColumnSpec {
+    ColumnSpec { name, column_type, nullable: false }
+}
+
+const fn optional(name: &'static str, column_type: ColumnType) -> ColumnSpec {
+    ColumnSpec { name, column_type, nullable: true }
+}
+
+use ColumnType::*;
+
+/// Labels follow `#[sqlx(rename_all = "lowercase")]` on the domain enums
+pub const EXPECTED_ENUMS: &[EnumSpec] = &[
+    EnumSpec {
+        name: "license_type",
+        labels: &["nib", "siup", "tdp", "npwp", "halal", "environmental", "exportimport"],
+    },
+    EnumSpec {
+        name: "application_status",
+        labels: &[
+            "draft", "submitted", "processing", "pendingdocuments", "approved", "rejected",
+            "expired", "suspended",
+        ],
+    },
+    EnumSpec {
+        name: "document_type",
+        labels: &[
+            "ktp", "companydeed", "taxcertificate", "bankstatement", "businessplan",
+            "locationpermit", "other",
+        ],
+    },
+    EnumSpec {
+        name: "priority_level",
+        labels: &["low", "normal", "high", "urgent"],
+    },
+];
+
+pub const EXPECTED_TABLES: &[TableSpec] = &[
+    TableSpec {
+        name: "users",
+        columns: &[
+            required("id", Uuid),
+            required("email", Text),
+            required("password_hash", Text),
+            required("full_name", Text),
+            required("role", Text),
+            required("status", Text),
+            required("email_verified", Bool),
+            required("created_at", Timestamptz),
+            required("updated_at", Timestamptz),
+            required("password_reset_required", Bool),
+            optional("password_reset_token_hash", Text),
+            optional("password_reset_expires_at", Timestamptz),
+            optional("sessions_revoked_at", Timestamptz),
+        ],
+    },
+    TableSpec {
+        name: "companies",
+        columns: &[
+            required("id", Uuid),
+            required("owner_id", Uuid),
+            required("company_name", Text),
+            required("business_type", Text),
+            required("industry_sector", Text),
+            optional("description", Text),
+            optional("establishment_date", Date),
+            required("employee_count", Int4),
+            optional("nib", Text),
+            optional("siup_number", Text),
+            optional("tdp_number", Text),
+            optional("npwp_company", Text),
+            optional("email", Text),
+            optional("phone", Text),
+            optional("website", Text),
+            required("address_street", Text),
+            required("address_city", Text),
+            required("address_province", Text),
+            required("address_postal_code", Text),
+            required("address_country", Text),
+            required("business_scale", Text),
+            optional("annual_revenue", Int8),
+            optional("annual_revenue_year", Int4),
+            required("is_verified", Bool),
+            optional("verification_date", Timestamptz),
+            optional("verification_notes", Text),
+            optional("bank_name", Text),
+            optional("bank_account_number", Text),
+            optional("bank_account_holder", Text),
+            optional("logo_url", Text),
+            required("documents", Jsonb),
+            required("status", Text),
+            required("created_at", Timestamptz),
+            required("updated_at", Timestamptz),
+            optional("search_vector", TsVector),
+            required("subscription_plan", Text),
+            required("version", Int8),
+        ],
+    },
+    TableSpec {
+        name: "licenses",
+        columns: &[
+            required("id", Uuid),
+            optional("license_number", Text),
+            required("license_type", Enum("license_type")),
+            required("company_id", Uuid),
+            required("user_id", Uuid),
+            required("title", Text),
+            optional("description", Text),
+            optional("issue_date", Timestamptz),
+            optional("expiry_date", Timestamptz),
+            optional("issuing_authority", Text),
+            required("application_status", Enum("application_status")),
+            required("priority", Enum("priority_level")),
+            optional("estimated_processing_days", Int4),
+            optional("actual_processing_days", Int4),
+            optional("external_reference_id", Text),
+            optional("government_fee", Int8),
+            optional("service_fee", Int8),
+            required("created_at", Timestamptz),
+            required("updated_at", Timestamptz),
+            optional("submitted_at", Timestamptz),
+            optional("approved_at", Timestamptz),
+            optional("rejected_at", Timestamptz),
+            optional("admin_notes", Text),
+            optional("rejection_reason", Text),
+            optional("search_vector", TsVector),
+            required("version", Int8),
+            optional("sla_paused_at", Timestamptz),
+            required("sla_paused_seconds", Int8),
+        ],
+    },
+    TableSpec {
+        name: "license_sla_events",
+        columns: &[
+            required("id", Uuid),
+            required("license_id", Uuid),
+            required("kind", Text),
+            required("threshold_percent", Int4),
+            optional("escalated_to", Uuid),
+            required("created_at", Timestamptz),
+        ],
+    },
+    TableSpec {
+        name: "license_certificates",
+        columns: &[
+            required("id", Uuid),
+            required("license_id", Uuid),
+            required("revision", Int4),
+            required("reason", Text),
+            required("license_status", Enum("application_status")),
+            required("license_version", Int8),
+            required("storage_key", Text),
+            required("sha256", Text),
+            required("size_bytes", Int8),
+            optional("generated_by", Uuid),
+            required("generated_at", Timestamptz),
+        ],
+    },
+    TableSpec {
+        name: "license_verification_lookups",
+        columns: &[
+            required("id", Uuid),
+            required("method", Text),
+            required("query", Text),
+            optional("company_id", Uuid),
+            optional("license_id", Uuid),
+            required("licenses_found", Int4),
+            optional("client_ip", Text),
+            optional("user_agent", Text),
+            required("looked_up_at", Timestamptz),
+        ],
+    },
+    TableSpec {
+        name: "license_documents",
+        columns: &[
+            required("id", Uuid),
+            required("license_id", Uuid),
+            required("document_type", Enum("document_type")),
+            required("file_name", Text),
+            required("original_file_name", Text),
+            required("file_path", Text),
+            required("file_size", Int8),
+            required("mime_type", Text),
+            required("upload_date", Timestamptz),
+            required("is_verified", Bool),
+            optional("verified_at", Timestamptz),
+            optional("verified_by", Uuid),
+            optional("notes", Text),
+            optional("search_vector", TsVector),
+            optional("notes_search_vector", TsVector),
+        ],
+    },
+    TableSpec {
+        name: "application_status_history",
+        columns: &[
+            required("id", Uuid),
+            required("license_id", Uuid),
+            optional("from_status", Enum("application_status")),
+            required("to_status", Enum("application_status")),
+            required("changed_by", Uuid),
+            required("changed_at", Timestamptz),
+            optional("notes", Text),
+            required("is_system_generated", Bool),
+        ],
+    },
+    TableSpec {
+        name: "financial_accounts",
+        columns: &[
+            required("id", Uuid),
+            required("company_id", Uuid),
+            required("name", Text),
+            optional("description", Text),
+            required("account_type", Text),
+            required("currency", Text),
+            required("balance", Int8),
+            required("is_active", Bool),
+            optional("metadata", Jsonb),
+            required("created_at", Timestamptz),
+            required("updated_at", Timestamptz),
+            required("version", Int8),
+        ],
+    },
+    TableSpec {
+        name: "financial_transactions",
+        columns: &[
+            required("id", Uuid),
+            required("company_id", Uuid),
+            required("transaction_date", Timestamptz),
+            required("transaction_type", Text),
+            required("amount", Int8),
+            required("currency", Text),
+            required("description", Text),
+            optional("reference_number", Text),
+            required("status", Text),
+            required("account_id", Uuid),
+            optional("category_id", Uuid),
+            required("tags", Jsonb),
+            required("attachments", Jsonb),
+            optional("metadata", Jsonb),
+            required("created_at", Timestamptz),
+            required("updated_at", Timestamptz),
+            required("created_by", Uuid),
+            optional("updated_by", Uuid),
+            optional("import_batch_id", Uuid),
+            optional("import_key", Text),
+            optional("reconciliation_id", Uuid),
+            optional("reconciled_at", Timestamptz),
+            required("version", Int8),
+        ],
+    },
+    TableSpec {
+        name: "import_mappings",
+        columns: &[
+            required("id", Uuid),
+            required("company_id", Uuid),
+            required("name", Text),
+            required("mapping", Jsonb),
+            required("created_by", Uuid),
+            required("created_at", Timestamptz),
+            required("updated_at", Timestamptz),
+        ],
+    },
+    TableSpec {
+        name: "import_batches",
+        columns: &[
+            required("id", Uuid),
+            required("company_id", Uuid),
+            required("account_id", Uuid),
+            required("file_name", Text),
+            required("layout", Text),
+            required("mapping", Jsonb),
+            required("status", Text),
+            required("summary", Jsonb),
+            required("report", Jsonb),
+            required("created_by", Uuid),
+            required("created_at", Timestamptz),
+            optional("committed_by", Uuid),
+            optional("committed_at", Timestamptz),
+        ],
+    },
+    TableSpec {
+        name: "reconciliation_sessions",
+        columns: &[
+            required("id", Uuid),
+            required("company_id", Uuid),
+            required("account_id", Uuid),
+            optional("statement_start_date", Date),
+            required("statement_end_date", Date),
+            required("opening_balance", Int8),
+            optional("closing_balance", Int8),
+            required("status", Text),
+            required("created_by", Uuid),
+            required("created_at", Timestamptz),
+            required("updated_at", Timestamptz),
+            optional("completed_by", Uuid),
+            optional("completed_at", Timestamptz),
+        ],
+    },
+    TableSpec {
+        name: "reconciliation_lines",
+        columns: &[
+            required("id", Uuid),
+            required("session_id", Uuid),
+            required("line_date", Date),
+            required("amount", Int8),
+            required("description", Text),
+            optional("reference", Text),
+            optional("transaction_id", Uuid),
+            optional("match_type", Text),
+            optional("matched_at", Timestamptz),
+        ],
+    },
+    TableSpec {
+        name: "analytics_dirty_days",
+        columns: &[
+            required("fact", Text),
+            required("day", Date),
+            required("queued_at", Timestamptz),
+        ],
+    },
+    TableSpec {
+        name: "license_daily_rollups",
+        columns: &[
+            required("day", Date),
+            required("license_type", Enum("license_type")),
+            required("application_status", Enum("application_status")),
+            required("province", Text),
+            required("business_scale", Text),
+            required("created", Int8),
+            required("submitted", Int8),
+            required("approved", Int8),
+            required("rejected", Int8),
+            required("processing_days_sum", Float8),
+            required("processing_decisions", Int8),
+        ],
+    },
+    TableSpec {
+        name: "company_daily_rollups",
+        columns: &[
+            required("day", Date),
+            required("province", Text),
+            required("business_scale", Text),
+            required("registrations", Int8),
+        ],
+    },
+    TableSpec {
+        name: "finance_daily_rollups",
+        columns: &[
+            required("day", Date),
+            required("transaction_type", Text),
+            required("status", Text),
+            required("province", Text),
+            required("business_scale", Text),
+            required("transactions", Int8),
+            required("amount_total", Int8),
+        ],
+    },
+    TableSpec {
+        name: "notification_settings",
+        columns: &[
+            required("user_id", Uuid),
+            required("locale", Text),
+            optional("whatsapp_number", Text),
+            required("updated_at", Timestamptz),
+        ],
+    },
+    TableSpec {
+        name: "notification_preferences",
+        columns: &[
+            required("user_id", Uuid),
+            required("event", Text),
+            required("channel", Text),
+            required("enabled", Bool),
+        ],
+    },
+    TableSpec {
+        name: "notifications",
+        columns: &[
+            required("id", Uuid),
+            required("user_id", Uuid),
+            required("event", Text),
+            required("title", Text),
+            required("body", Text),
+            optional("link", Text),
+            optional("license_id", Uuid),
+            optional("read_at", Timestamptz),
+            required("created_at", Timestamptz),
+        ],
+    },
+    TableSpec {
+        name: "notification_deliveries",
+        columns: &[
+            required("id", Uuid),
+            required("user_id", Uuid),
+            required("event", Text),
+            required("channel", Text),
+            required("recipient", Text),
+            required("subject", Text),
+            required("body", Text),
+            required("status", Text),
+            required("attempts", Int4),
+            required("next_attempt_at", Timestamptz),
+            optional("last_error", Text),
+            required("created_at", Timestamptz),
+            optional("sent_at", Timestamptz),
+        ],
+    },
+    TableSpec {
+        name: "license_comments",
+        columns: &[
+            required("id", Uuid),
+            required("license_id", Uuid),
+            optional("parent_id", Uuid),
+            required("author_id", Uuid),
+            required("body", Text),
+            required("internal", Bool),
+            required("kind", Text),
+            required("created_at", Timestamptz),
+        ],
+    },
+    TableSpec {
+        name: "license_comment_attachments",
+        columns: &[required("comment_id", Uuid), required("document_id", Uuid)],
+    },
+    TableSpec {
+        name: "license_comment_mentions",
+        columns: &[required("comment_id", Uuid), required("user_id", Uuid)],
+    },
+    TableSpec {
+        name: "license_comment_reads",
+        columns: &[
+            required("comment_id", Uuid),
+            required("user_id", Uuid),
+            required("read_at", Timestamptz),
+        ],
+    },
+    TableSpec {
+        name: "subscriptions",
+        columns: &[
+            required("id", Uuid),
+            required("company_id", Uuid),
+            required("plan", Text),
+            required("status", Text),
+            optional("trial_ends_at", Timestamptz),
+            required("current_period_start", Timestamptz),
+            required("current_period_end", Timestamptz),
+            optional("scheduled_plan", Text),
+            required("version", Int8),
+            required("created_at", Timestamptz),
+            required("updated_at", Timestamptz),
+        ],
+    },
+    TableSpec {
+        name: "subscription_invoices",
+        columns: &[
+            required("id", Uuid),
+            required("number", Text),
+            required("company_id", Uuid),
+            required("subscription_id", Uuid),
+            required("plan", Text),
+            required("period_start", Timestamptz),
+            required("period_end", Timestamptz),
+            required("subtotal", Int8),
+            required("credit", Int8),
+            required("ppn_rate_bps", Int8),
+            required("ppn", Int8),
+            required("total", Int8),
+            required("currency", Text),
+            required("status", Text),
+            required("issued_at", Timestamptz),
+            required("due_at", Timestamptz),
+            optional("paid_at", Timestamptz),
+            optional("payment_reference", Text),
+            optional("last_payment_error", Text),
+            required("reminders_sent", Int4),
+            optional("next_reminder_at", Timestamptz),
+            required("updated_at", Timestamptz),
+        ],
+    },
+    TableSpec {
+        name: "contacts",
+        columns: &[
+            required("id", Uuid),
+            required("company_id", Uuid),
+            required("name", Text),
+            optional("email", Text),
+            optional("phone", Text),
+            optional("npwp", Text),
+            optional("address", Text),
+            optional("notes", Text),
+            required("version", Int8),
+            required("created_at", Timestamptz),
+            required("updated_at", Timestamptz),
+        ],
+    },
+    TableSpec {
+        name: "sales_invoice_sequences",
+        columns: &[
+            required("company_id", Uuid),
+            required("year", Int4),
+            required("last_number", Int8),
+        ],
+    },
+    TableSpec {
+        name: "sales_invoices",
+        columns: &[
+            required("id", Uuid),
+            required("company_id", Uuid),
+            required("contact_id", Uuid),
+            optional("number", Text),
+            required("status", Text),
+            optional("issue_date", Date),
+            optional("due_date", Date),
+            required("subtotal", Int8),
+            required("discount", Int8),
+            required("ppn_rate_bps", Int8),
+            required("ppn", Int8),
+            required("total", Int8),
+            required("amount_paid", Int8),
+            required("currency", Text),
+            optional("notes", Text),
+            optional("sent_at", Timestamptz),
+            optional("emailed_at", Timestamptz),
+            optional("voided_at", Timestamptz),
+            required("created_by", Uuid),
+            required("version", Int8),
+            required("created_at", Timestamptz),
+            required("updated_at", Timestamptz),
+        ],
+    },
+    TableSpec {
+        name: "sales_invoice_lines",
+        columns: &[
+            required("invoice_id", Uuid),
+            required("position", Int4),
+            required("description", Text),
+            required("quantity", Int8),
+            required("unit_price", Int8),
+            required("discount_bps", Int8),
+            required("amount", Int8),
+        ],
+    },
+    TableSpec {
+        name: "invoice_payments",
+        columns: &[
+            required("id", Uuid),
+            required("company_id", Uuid),
+            required("contact_id", Uuid),
+            required("account_id", Uuid),
+            required("transaction_id", Uuid),
+            required("amount", Int8),
+            required("paid_on", Date),
+            optional("reference", Text),
+            required("created_by", Uuid),
+            required("created_at", Timestamptz),
+        ],
+    },
+    TableSpec {
+        name: "invoice_payment_allocations",
+        columns: &[
+            required("payment_id", Uuid),
+            required("invoice_id", Uuid),
+            required("amount", Int8),
+        ],
+    },
+];
+
+/// A column as reported by `information_schema.columns`
+#[derive(Debug, Clone)]
+pub struct LiveColumn {
+    pub table: String,
+    pub column: String,
+    pub udt_name: String,
+    pub nullable: bool,
+}
+
+/// One difference between the live schema and what the repositories expect
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaIssue {
+    MissingTable(String),
+    MissingColumn { table: String, column: String },
+    TypeMismatch { table: String, column: String, expected: String, actual: String },
+    /// The repositories decode the column as non-optional, or always write a value
+    NullabilityMismatch { table: String, column: String, expected_nullable: bool },
+    MissingEnum(String),
+    MissingEnumLabel { name: String, label: String },
+    /// Rows holding this label cannot be decoded
+    UnexpectedEnumLabel { name: String, label: String },
+}
+
+impl fmt::Display for SchemaIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaIssue::MissingTable(table) => write!(f, "table {} is missing", table),
+            SchemaIssue::MissingColumn { table, column } => {
+                write!(f, "column {}.{} is missing", table, column)
+            }
+            SchemaIssue::TypeMismatch { table, column, expected, actual } => write!(
+                f,
+                "column {}.{} is {}, expected {}",
+                table, column, actual, expected
+            ),
+            SchemaIssue::NullabilityMismatch { table, column, expected_nullable } => {
+                if *expected_nullable {
+                    write!(f, "column {}.{} is NOT NULL, expected nullable", table, column)
+                } else {
+                    write!(f, "column {}.{} is nullable, expected NOT NULL", table, column)
+                }
+            }
+            SchemaIssue::MissingEnum(name) => write!(f, "enum type {} is missing", name),
+            SchemaIssue::MissingEnumLabel { name, label } => {
+                write!(f, "enum type {} is missing label '{}'", name, label)
+            }
+            SchemaIssue::UnexpectedEnumLabel { name, label } => {
+                write!(f, "enum type {} has unknown label '{}'", name, label)
+            }
+        }
+    }
+}
+
+/// Compares the live columns and enum labels against the expected schema
+pub fn diff_schema(
+    tables: &[TableSpec],
+    enums: &[EnumSpec],
+    live_columns: &[LiveColumn],
+    live_enums: &[(String, String)],
+) -> Vec<SchemaIssue> {
+    let mut issues = Vec::new();
+
+    let mut columns_by_table: HashMap<&str, HashMap<&str, &LiveColumn>> = HashMap::new();
+    for column in live_columns {
+        columns_by_table
+            .entry(column.table.as_str())
+            .or_default()
+            .insert(column.column.as_str(), column);
+    }
+
+    for table in tables {
+        let Some(live) = columns_by_table.get(table.name) else {
+            issues.push(SchemaIssue::MissingTable(table.name.to_string()));
+            continue;
+        };
+
+        for spec in table.columns {
+            let Some(column) = live.get(spec.name) else {
+                issues.push(SchemaIssue::MissingColumn {
+                    table: table.name.to_string(),
+                    column: spec.name.to_string(),
+                });
+                continue;
+            };
+
+            if !spec.column_type.accepts(&column.udt_name) {
+                issues.push(SchemaIssue::TypeMismatch {
+                    table: table.name.to_string(),
+                    column: spec.name.to_string(),
+                    expected: spec.column_type.to_string(),
+                    actual: column.udt_name.clone(),
+                });
+            }
+            if spec.nullable != column.nullable {
+                issues.push(SchemaIssue::NullabilityMismatch {
+                    table: table.name.to_string(),
+                    column: spec.name.to_string(),
+                    expected_nullable: spec.nullable,
+                });
+            }
+        }
+    }
+
+    let mut labels_by_enum: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (name, label) in live_enums {
+        labels_by_enum.entry(name.as_str()).or_default().push(label.as_str());
+    }
+
+    for spec in enums {
+        let Some(live) = labels_by_enum.get(spec.name) else {
+            issues.push(SchemaIssue::MissingEnum(spec.name.to_string()));
+            continue;
+        };
+
+        for label in spec.labels {
+            if !live.contains(label) {
+                issues.push(SchemaIssue::MissingEnumLabel {
+                    name: spec.name.to_string(),
+                    label: label.to_string(),
+                });
+            }
+        }
+        for label in live {
+            if !spec.labels.contains(label) {
+                issues.push(SchemaIssue::UnexpectedEnumLabel {
+                    name: spec.name.to_string(),
+                    label: label.to_string(),
+                });
+            }
+        }
+    }
+
+    issues
+}
+
+/// Diffs the schema of the connected database against [`EXPECTED_TABLES`] and
+/// [`EXPECTED_ENUMS`]; an empty result means the repositories' queries will work
+pub async fn verify_schema(pool: &PgPool) -> Result<Vec<SchemaIssue>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT table_name::text, column_name::text, udt_name::text, is_nullable = 'YES' AS nullable
+        FROM information_schema.columns
+        WHERE table_schema = current_schema()
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let live_columns = rows
+        .iter()
+        .map(|row| {
+            Ok(LiveColumn {
+                table: row.try_get("table_name")?,
+                column: row.try_get("column_name")?,
+                udt_name: row.try_get("udt_name")?,
+                nullable: row.try_get("nullable")?,
+            })
+        })
+        .collect::<Result<Vec<_>, sqlx::Error>>()?;
+
+    let live_enums: Vec<(String, String)> = sqlx::query_as(
+        r#"
+        SELECT t.typname::text, e.enumlabel::text
+        FROM pg_type t
+        JOIN pg_enum e ON e.enumtypid = t.oid
+        JOIN pg_namespace n ON n.oid = t.typnamespace
+        WHERE n.nspname = current_schema()
+        ORDER BY t.typname, e.enumsortorder
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(diff_schema(EXPECTED_TABLES, EXPECTED_ENUMS, &live_columns, &live_enums))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::repositories::finance_repository::TRANSACTION_COLUMNS;
+    use crate::infrastructure::repositories::import_repository::BATCH_COLUMNS;
+    use crate::infrastructure::repositories::reconciliation_repository::{
+        LINE_COLUMNS, SESSION_COLUMNS,
+    };
+
+    fn table(name: &str) -> &'static TableSpec {
+        EXPECTED_TABLES.iter().find(|t| t.name == name).unwrap()
+    }
+
+    fn live(spec: &TableSpec) -> Vec<LiveColumn> {
+        spec.columns
+            .iter()
+            .map(|c| LiveColumn {
+                table: spec.name.to_string(),
+                column: c.name.to_string(),
+                udt_name: match c.column_type {
+                    Text => "varchar".to_string(),
+                    other => other.to_string(),
+                },
+                nullable: c.nullable,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn spec_covers_repository_columns() {
+        let cases: [(&str, Vec<&str>); 4] = [
+            ("financial_transactions", TRANSACTION_COLUMNS.to_vec()),
+            ("import_batches", BATCH_COLUMNS.split(',').map(str::trim).collect()),
+            ("reconciliation_sessions", SESSION_COLUMNS.split(',').map(str::trim).collect()),
+            ("reconciliation_lines", LINE_COLUMNS.split(',').map(str::trim).collect()),
+        ];
+
+        for (name, columns) in cases {
+            for column in columns {
+                assert!(table(name).column(column).is_some(), "{}.{} not in spec", name, column);
+            }
+        }
+    }
+
+    #[test]
+    fn diff_reports_drift() {
+        let transactions = table("financial_transactions");
+        let mut columns = live(transactions);
+        columns.retain(|c| c.column != "reconciled_at");
+        for column in columns.iter_mut() {
+            if column.column == "amount" {
+                column.udt_name = "numeric".to_string();
+            }
+            if column.column == "description" {
+                column.nullable = true;
+            }
+        }
+
+        let enums = vec![
+            ("priority_level".to_string(), "low".to_string()),
+            ("priority_level".to_string(), "normal".to_string()),
+            ("priority_level".to_string(), "high".to_string()),
+            ("priority_level".to_string(), "critical".to_string()),
+        ];
+
+        let issues = diff_schema(
+            std::slice::from_ref(transactions),
+            &EXPECTED_ENUMS[3..],
+            &columns,
+            &enums,
+        );
+
+        assert_eq!(
+            issues,
+            vec![
+                SchemaIssue::TypeMismatch {
+                    table: "financial_transactions".to_string(),
+                    column: "amount".to_string(),
+                    expected: "int8".to_string(),
+                    actual: "numeric".to_string(),
+                },
+                SchemaIssue::NullabilityMismatch {
+                    table: "financial_transactions".to_string(),
+                    column: "description".to_string(),
+                    expected_nullable: false,
+                },
+                SchemaIssue::MissingColumn {
+                    table: "financial_transactions".to_string(),
+                    column: "reconciled_at".to_string(),
+                },
+                SchemaIssue::MissingEnumLabel {
+                    name: "priority_level".to_string(),
+                    label: "urgent".to_string(),
+                },
+                SchemaIssue::UnexpectedEnumLabel {
+                    name: "priority_level".to_string(),
+                    label: "critical".to_string(),
+                },
+            ]
+        );
+
+        let all_columns: Vec<LiveColumn> = EXPECTED_TABLES.iter().flat_map(live).collect();
+        assert!(diff_schema(EXPECTED_TABLES, &[], &all_columns, &[]).is_empty());
+        assert_eq!(
+            diff_schema(&[*table("users")], &[], &[], &[]),
+            vec![SchemaIssue::MissingTable("users".to_string())]
+        );
+    }
+}
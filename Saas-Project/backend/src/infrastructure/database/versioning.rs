@@ -0,0 +1,46 @@
+// Optimistic locking for versioned rows
+// Updates match on the version the caller read (`WHERE id = $n AND version =
+// $m`) and the `bump_row_version` trigger increments it. When such an update
+// matches nothing, the row was either deleted or changed by someone else;
+// `stale_write` tells the two apart.
+
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::shared::errors::{AppError, AppResult};
+
+/// The error for a conditional update on `table` that matched no row
+pub async fn stale_write<'e, E: PgExecutor<'e>>(
+    executor: E,
+    table: &'static str,
+    resource: &'static str,
+    id: Uuid,
+) -> AppError {
+    let current: Result<Option<i64>, sqlx::Error> =
+        sqlx::query_scalar(&format!("SELECT version FROM {} WHERE id = $1", table))
+            .bind(id)
+            .fetch_optional(executor)
+            .await;
+
+    match current {
+        Ok(Some(current_version)) => AppError::VersionConflict {
+            resource,
+            current_version,
+        },
+        Ok(None) => AppError::NotFound(format!("{} not found", resource)),
+        Err(err) => err.into(),
+    }
+}
+
+/// Same check for stores without a database: `expected` is the version the
+/// caller read, `current` the one stored
+pub fn check_version(resource: &'static str, expected: i64, current: i64) -> AppResult<()> {
+    if expected == current {
+        Ok(())
+    } else {
+        Err(AppError::VersionConflict {
+            resource,
+            current_version: current,
+        })
+    }
+}
@@ -0,0 +1,338 @@
+// Demo mode - the API on in-memory repositories with seeded fixtures
+// Started with `server --demo`, so the frontend can run against the full API
+// without Postgres or Redis. Everything is lost when the process exits.
+
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+
+use crate::domain::companies::{BusinessType, Company, CompanyAddress, SubscriptionPlan};
+use crate::domain::entities::{User, UserRole};
+use crate::domain::finance::{FinancialAccount, FinancialAccountRepository};
+use crate::domain::licenses::{
+    ApplicationStatus, ApplicationStatusHistory, DocumentType, License, LicenseDocument,
+    LicenseType,
+};
+use crate::domain::repositories::{CompanyRepository, UserRepository};
+use crate::domain::value_objects::{Currency, Email, Money};
+use crate::infrastructure::repositories::{
+    InMemoryCompanyRepository, InMemoryFinanceStore, InMemoryFinancialAccountRepository,
+    InMemoryLicenseRepository, InMemorySearchRepository, InMemoryUserRepository,
+    LicenseRepository,
+};
+use crate::services::auth::AuthService;
+use crate::services::billing::BillingService;
+use crate::shared::errors::{AppError, AppResult};
+
+/// Password of every seeded account
+pub const DEMO_PASSWORD: &str = "Demo1234!";
+
+pub const DEMO_SUPER_ADMIN: &str = "admin@demo.saas-umkm.id";
+pub const DEMO_ADMIN_STAFF: &str = "staff@demo.saas-umkm.id";
+pub const DEMO_OWNER: &str = "budi@demo.saas-umkm.id";
+pub const DEMO_SECOND_OWNER: &str = "siti@demo.saas-umkm.id";
+
+/// The in-memory repositories demo mode serves from
+pub struct DemoRepositories {
+    pub users: Arc<InMemoryUserRepository>,
+    pub companies: InMemoryCompanyRepository,
+    pub licenses: InMemoryLicenseRepository,
+    pub search: InMemorySearchRepository,
+    /// Accounts, transactions and customer invoices
+    pub finance: InMemoryFinanceStore,
+}
+
+impl DemoRepositories {
+    pub fn new() -> Self {
+        let companies = InMemoryCompanyRepository::new();
+        let licenses = InMemoryLicenseRepository::new().with_companies(companies.clone());
+        Self {
+            users: Arc::new(InMemoryUserRepository::new()),
+            search: InMemorySearchRepository::new(companies.clone(), licenses.clone()),
+            companies,
+            licenses,
+            finance: InMemoryFinanceStore::new(),
+        }
+    }
+
+    /// Seeds staff accounts and two owners with companies and licenses in
+    /// every stage of the review
+    pub async fn seed(&self, auth: &AuthService) -> AppResult<()> {
+        let password_hash = auth
+            .hash_password(DEMO_PASSWORD)
+            .map_err(|err| AppError::InternalError(err.to_string()))?;
+
+        let super_admin = self
+            .user(DEMO_SUPER_ADMIN, "Admin Demo", UserRole::SuperAdmin, &password_hash)
+            .await?;
+        self.user(DEMO_ADMIN_STAFF, "Staf Perizinan", UserRole::AdminStaff, &password_hash)
+            .await?;
+        let budi = self
+            .user(DEMO_OWNER, "Budi Santoso", UserRole::UmkmOwner, &password_hash)
+            .await?;
+        let siti = self
+            .user(DEMO_SECOND_OWNER, "Siti Rahayu", UserRole::UmkmOwner, &password_hash)
+            .await?;
+        let reviewer = *super_admin.id.as_uuid();
+        let budi = *budi.id.as_uuid();
+        let siti = *siti.id.as_uuid();
+
+        let mut warung = Company::new(
+            budi,
+            "Warung Sari Rasa".to_string(),
+            BusinessType::UD,
+            "Kuliner".to_string(),
+            CompanyAddress::new(
+                "Jl. Malioboro No. 12".to_string(),
+                "Yogyakarta".to_string(),
+                "DI Yogyakarta".to_string(),
+                "55271".to_string(),
+            ),
+        );
+        warung.description = Some("Warung makan khas Jawa dengan menu gudeg dan nasi goreng".to_string());
+        warung.nib = Some("9120001234567".to_string());
+        warung.verify(Some("Dokumen lengkap".to_string()));
+        self.companies.save(&warung).await?;
+
+        let mut batik = Company::new(
+            siti,
+            "Batik Lestari".to_string(),
+            BusinessType::CV,
+            "Fashion".to_string(),
+            CompanyAddress::new(
+                "Jl. Kauman No. 5".to_string(),
+                "Pekalongan".to_string(),
+                "Jawa Tengah".to_string(),
+                "51111".to_string(),
+            ),
+        );
+        batik.description = Some("Produsen batik tulis dan cap".to_string());
+        self.companies.save(&batik).await?;
+
+        // Approved NIB, with its review history and documents
+        let mut nib = License::new(
+            LicenseType::Nib,
+            warung.id,
+            budi,
+            "NIB Warung Sari Rasa".to_string(),
+            Some("Nomor Induk Berusaha untuk usaha kuliner".to_string()),
+        );
+        nib.service_fee = Some(150_000);
+        self.advance(&mut nib, ApplicationStatus::Processing, reviewer).await?;
+        let issued = Utc::now() - Duration::days(30);
+        nib.approve(
+            "NIB-2024-000123".to_string(),
+            issued,
+            Some(issued + Duration::days(5 * 365)),
+            "OSS RBA".to_string(),
+            Some("Disetujui".to_string()),
+        )
+        .map_err(AppError::Validation)?;
+        self.history(&nib, Some(ApplicationStatus::Processing), reviewer).await?;
+        nib = self.licenses.update_license(&nib).await?;
+        self.document(&nib, DocumentType::Ktp, "ktp_budi_santoso.pdf").await?;
+        self.document(&nib, DocumentType::TaxCertificate, "npwp_warung_sari_rasa.pdf").await?;
+
+        // Halal certificate waiting for review, and a draft SIUP
+        let mut halal = License::new(
+            LicenseType::Halal,
+            warung.id,
+            budi,
+            "Sertifikat Halal Gudeg".to_string(),
+            Some("Sertifikasi halal untuk menu gudeg".to_string()),
+        );
+        self.advance(&mut halal, ApplicationStatus::Submitted, budi).await?;
+        self.document(&halal, DocumentType::Ktp, "ktp_budi_santoso.pdf").await?;
+
+        let siup = License::new(
+            LicenseType::Siup,
+            warung.id,
+            budi,
+            "SIUP Warung Sari Rasa".to_string(),
+            None,
+        );
+        self.licenses.create_license(&siup).await?;
+
+        // Second owner's application is missing documents
+        let mut batik_nib = License::new(
+            LicenseType::Nib,
+            batik.id,
+            siti,
+            "NIB Batik Lestari".to_string(),
+            Some("Nomor Induk Berusaha untuk produksi batik".to_string()),
+        );
+        self.advance(&mut batik_nib, ApplicationStatus::PendingDocuments, reviewer)
+            .await?;
+        self.document(&batik_nib, DocumentType::CompanyDeed, "akta_pendirian_cv.pdf")
+            .await?;
+
+        Ok(())
+    }
+
+    async fn user(
+        &self,
+        email: &str,
+        full_name: &str,
+        role: UserRole,
+        password_hash: &str,
+    ) -> AppResult<User> {
+        let email = Email::new(email).map_err(AppError::Validation)?;
+        let mut user = User::new(email, password_hash.to_string(), full_name.to_string(), role);
+        user.verify_email();
+        self.users.save(&user).await?;
+        Ok(user)
+    }
+
+    /// Stores the draft, then walks it through the review up to `status`
+    /// recording each step in its history
+    async fn advance(
+        &self,
+        license: &mut License,
+        status: ApplicationStatus,
+        reviewer: uuid::Uuid,
+    ) -> AppResult<()> {
+        self.licenses.create_license(license).await?;
+        license.submit().map_err(AppError::Validation)?;
+        self.history(license, Some(ApplicationStatus::Draft), license.user_id)
+            .await?;
+
+        for step in [ApplicationStatus::Processing, ApplicationStatus::PendingDocuments] {
+            if license.application_status == status {
+                break;
+            }
+            let from = license.application_status.clone();
+            license.application_status = step;
+            license.updated_at = Utc::now();
+            self.history(license, Some(from), reviewer).await?;
+        }
+
+        *license = self.licenses.update_license(license).await?;
+        Ok(())
+    }
+
+    async fn history(
+        &self,
+        license: &License,
+        from: Option<ApplicationStatus>,
+        changed_by: uuid::Uuid,
+    ) -> AppResult<()> {
+        let entry = ApplicationStatusHistory::new(
+            license.id,
+            from,
+            license.application_status.clone(),
+            changed_by,
+            None,
+            false,
+        );
+        self.licenses.create_status_history(&entry).await?;
+        Ok(())
+    }
+
+    async fn document(&self, license: &License, document_type: DocumentType, name: &str) -> AppResult<()> {
+        let document = LicenseDocument::new(
+            license.id,
+            document_type,
+            format!("{}-{}", license.id, name),
+            name.to_string(),
+            format!("demo/{}/{}", license.id, name),
+            120_000,
+            "application/pdf".to_string(),
+        );
+        self.licenses.create_document(&document).await?;
+        Ok(())
+    }
+
+    /// Puts the first owner's companies on a pro trial, so every module is
+    /// open to them; the second owner stays on the free plan and meets its
+    /// limits
+    pub async fn seed_subscriptions(&self, billing: &BillingService) -> AppResult<()> {
+        let owner = self
+            .users
+            .find_by_email(&Email::new(DEMO_OWNER).map_err(AppError::Validation)?)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Seed the demo owners first".to_string()))?;
+        for company in self.companies.find_by_owner_id(owner.id.as_uuid()).await? {
+            billing.change_plan(&company, SubscriptionPlan::Pro).await?;
+        }
+        Ok(())
+    }
+
+    /// Opens a cash account and a bank account for each of the first owner's
+    /// companies, for invoice payments, statement imports and reconciliation
+    pub async fn seed_accounts(&self) -> AppResult<()> {
+        let owner = self
+            .users
+            .find_by_email(&Email::new(DEMO_OWNER).map_err(AppError::Validation)?)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Seed the demo owners first".to_string()))?;
+        let accounts = InMemoryFinancialAccountRepository::new(self.finance.clone());
+        for company in self.companies.find_by_owner_id(owner.id.as_uuid()).await? {
+            for (name, account_type) in [("Kas", "cash"), ("BCA Operasional", "Bank")] {
+                accounts
+                    .create(&FinancialAccount::new(
+                        company.id,
+                        name.to_string(),
+                        account_type.to_string(),
+                        Currency::IDR,
+                        Money::idr(0),
+                    ))
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for DemoRepositories {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::filters::LicenseFilter;
+    use crate::domain::search::{SearchQuery, SearchRepository, SearchScope};
+    use crate::shared::query::ListQuery;
+
+    #[tokio::test]
+    async fn test_seeded_fixtures_log_in_and_list() -> AppResult<()> {
+        let auth = AuthService::new("test-secret".to_string());
+        let demo = DemoRepositories::new();
+        demo.seed(&auth).await?;
+
+        let owner = demo
+            .users
+            .find_by_email(&Email::new(DEMO_OWNER).unwrap())
+            .await?
+            .unwrap();
+        assert!(auth.verify_password(DEMO_PASSWORD, &owner.password_hash).unwrap());
+
+        let licenses = demo
+            .licenses
+            .list_licenses(&ListQuery::new(LicenseFilter {
+                owner_id: Some(*owner.id.as_uuid()),
+                ..Default::default()
+            }))
+            .await?;
+        assert_eq!(licenses.data.len(), 3);
+        assert!(licenses
+            .data
+            .iter()
+            .any(|l| l.application_status == ApplicationStatus::Approved));
+
+        let pending = demo
+            .licenses
+            .get_licenses_by_status(ApplicationStatus::PendingDocuments)
+            .await?;
+        assert_eq!(pending.len(), 1);
+        assert_eq!(demo.licenses.get_status_history_by_license(pending[0].id).await?.len(), 3);
+
+        let hits = demo
+            .search
+            .search(&SearchQuery::new("batik", SearchScope::All).unwrap())
+            .await?;
+        assert!(!hits.hits.is_empty());
+        Ok(())
+    }
+}
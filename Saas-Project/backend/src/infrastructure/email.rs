@@ -1,4 +0,0 @@
-// Email service infrastructure
-
-#[allow(dead_code)] // Will be implemented for email verification
-pub struct EmailService;
@@ -0,0 +1,383 @@
+// Dependency health checks for the liveness and readiness probes
+// Every check is bounded by a timeout and reports its own latency. A `down`
+// check makes the instance unready so the load balancer stops routing to it;
+// `degraded` ones (optional dependencies) are reported but keep it in rotation.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use std::future::Future;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+use crate::infrastructure::cache::CacheService;
+use crate::infrastructure::database::manager::DatabaseManager;
+use crate::infrastructure::database::migrations::{
+    latest_version, migration_status, MigrationState, MigrationStatus,
+};
+
+/// Upper bound for a single check; probes usually time out after a few seconds
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    /// Not configured, so not checked
+    Disabled,
+    Up,
+    Degraded,
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: HealthStatus,
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub version: &'static str,
+    pub checked_at: DateTime<Utc>,
+    pub checks: Vec<CheckResult>,
+}
+
+impl HealthReport {
+    /// Overall status is the worst individual one; disabled checks count as up
+    pub fn from_checks(checks: Vec<CheckResult>) -> Self {
+        let status = checks
+            .iter()
+            .map(|check| check.status)
+            .max()
+            .unwrap_or(HealthStatus::Up)
+            .max(HealthStatus::Up);
+
+        Self {
+            status,
+            version: env!("CARGO_PKG_VERSION"),
+            checked_at: Utc::now(),
+            checks,
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.status != HealthStatus::Down
+    }
+}
+
+/// Outcome of a check before its latency is known
+struct Outcome {
+    status: HealthStatus,
+    message: Option<String>,
+    details: Option<Value>,
+}
+
+impl Outcome {
+    fn new(status: HealthStatus) -> Self {
+        Self { status, message: None, details: None }
+    }
+
+    fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+async fn timed<F>(name: &'static str, check: F) -> CheckResult
+where
+    F: Future<Output = Outcome>,
+{
+    let start = Instant::now();
+    let outcome = tokio::time::timeout(CHECK_TIMEOUT, check)
+        .await
+        .unwrap_or_else(|_| {
+            Outcome::new(HealthStatus::Down)
+                .with_message(format!("timed out after {}s", CHECK_TIMEOUT.as_secs()))
+        });
+
+    if outcome.status == HealthStatus::Down {
+        tracing::warn!(check = name, message = ?outcome.message, "Readiness check failed");
+    }
+
+    CheckResult {
+        name,
+        status: outcome.status,
+        latency_ms: start.elapsed().as_secs_f64() * 1000.0,
+        message: outcome.message,
+        details: outcome.details,
+    }
+}
+
+/// A pool with every connection checked out cannot serve new requests until
+/// one is released, so the instance is taken out of rotation
+fn pool_exhausted(size: u32, idle: usize, max_connections: u32) -> bool {
+    size >= max_connections && idle == 0
+}
+
+/// `None` in demo mode, which runs without a database
+async fn check_database(db: Option<&DatabaseManager>) -> CheckResult {
+    timed("postgres", async {
+        let Some(db) = db else {
+            return Outcome::new(HealthStatus::Disabled);
+        };
+        let pool = db.pool();
+        let (size, idle) = (pool.size(), pool.num_idle());
+        let max_connections = pool.options().get_max_connections();
+        let details = json!({
+            "pool_size": size,
+            "pool_idle": idle,
+            "pool_max": max_connections,
+        });
+
+        if pool_exhausted(size, idle, max_connections) {
+            return Outcome::new(HealthStatus::Down)
+                .with_message("connection pool exhausted")
+                .with_details(details);
+        }
+
+        match db.check_health().await {
+            Ok(_) => Outcome::new(HealthStatus::Up).with_details(details),
+            Err(err) => Outcome::new(HealthStatus::Down)
+                .with_message(err.to_string())
+                .with_details(details),
+        }
+    })
+    .await
+}
+
+async fn check_cache(cache: Option<&CacheService>, config: &AppConfig) -> CheckResult {
+    timed("redis", async {
+        let failure = if config.redis_required {
+            HealthStatus::Down
+        } else {
+            HealthStatus::Degraded
+        };
+
+        match (cache, &config.redis_url) {
+            (Some(cache), _) => match cache.health_check().await {
+                Ok(()) => Outcome::new(HealthStatus::Up),
+                Err(err) => Outcome::new(failure).with_message(err.to_string()),
+            },
+            // Configured but the client could not be created at startup
+            (None, Some(_)) => Outcome::new(failure).with_message("cache client not initialized"),
+            (None, None) if config.redis_required => {
+                Outcome::new(HealthStatus::Down).with_message("REDIS_URL is not set")
+            }
+            (None, None) => Outcome::new(HealthStatus::Disabled),
+        }
+    })
+    .await
+}
+
+/// Uploads are written to the local upload directory, so it must accept files
+async fn check_storage(upload_dir: &str) -> CheckResult {
+    timed("storage", async {
+        let dir = Path::new(upload_dir);
+        let probe = dir.join(format!(".readyz-{}", Uuid::new_v4()));
+
+        let result = async {
+            tokio::fs::create_dir_all(dir).await?;
+            tokio::fs::write(&probe, b"ok").await?;
+            tokio::fs::remove_file(&probe).await
+        }
+        .await;
+
+        let details = json!({ "backend": "local", "path": upload_dir });
+        match result {
+            Ok(()) => Outcome::new(HealthStatus::Up).with_details(details),
+            Err(err) => Outcome::new(HealthStatus::Down)
+                .with_message(format!("upload directory is not writable: {}", err))
+                .with_details(details),
+        }
+    })
+    .await
+}
+
+/// Pending or failed migrations mean the schema is behind the code. Versions
+/// the binary does not know (a newer release migrated first) or edited files
+/// are reported without taking the instance out of rotation.
+fn assess_migrations(statuses: &[MigrationStatus]) -> (HealthStatus, Option<String>) {
+    let versions = |state: MigrationState| -> Vec<i64> {
+        statuses
+            .iter()
+            .filter(|s| s.state == state)
+            .map(|s| s.version)
+            .collect()
+    };
+
+    let failed = versions(MigrationState::Failed);
+    if !failed.is_empty() {
+        return (HealthStatus::Down, Some(format!("failed migrations: {:?}", failed)));
+    }
+    let pending = versions(MigrationState::Pending);
+    if !pending.is_empty() {
+        return (HealthStatus::Down, Some(format!("pending migrations: {:?}", pending)));
+    }
+    let mismatched = versions(MigrationState::ChecksumMismatch);
+    if !mismatched.is_empty() {
+        return (
+            HealthStatus::Degraded,
+            Some(format!("checksum mismatch: {:?}", mismatched)),
+        );
+    }
+    let unknown = versions(MigrationState::Missing);
+    if !unknown.is_empty() {
+        return (
+            HealthStatus::Degraded,
+            Some(format!("database has migrations unknown to this build: {:?}", unknown)),
+        );
+    }
+
+    (HealthStatus::Up, None)
+}
+
+async fn check_migrations(pool: Option<&PgPool>) -> CheckResult {
+    timed("migrations", async {
+        let Some(pool) = pool else {
+            return Outcome::new(HealthStatus::Disabled);
+        };
+        match migration_status(pool).await {
+            Ok(statuses) => {
+                let (status, message) = assess_migrations(&statuses);
+                let current = statuses
+                    .iter()
+                    .filter(|s| s.state != MigrationState::Pending && s.state != MigrationState::Failed)
+                    .map(|s| s.version)
+                    .max();
+                let outcome = Outcome::new(status).with_details(json!({
+                    "current_version": current,
+                    "expected_version": latest_version(),
+                }));
+                match message {
+                    Some(message) => outcome.with_message(message),
+                    None => outcome,
+                }
+            }
+            Err(err) => Outcome::new(HealthStatus::Down).with_message(err.to_string()),
+        }
+    })
+    .await
+}
+
+/// Only reachability is checked; mail is sent in the background, so an
+/// unreachable server delays notifications without failing requests
+async fn check_smtp(host: &str, port: u16) -> CheckResult {
+    timed("smtp", async {
+        if host.is_empty() {
+            return Outcome::new(HealthStatus::Disabled);
+        }
+
+        let details = json!({ "host": host, "port": port });
+        match TcpStream::connect((host, port)).await {
+            Ok(_) => Outcome::new(HealthStatus::Up).with_details(details),
+            Err(err) => Outcome::new(HealthStatus::Degraded)
+                .with_message(err.to_string())
+                .with_details(details),
+        }
+    })
+    .await
+}
+
+/// Runs every dependency check concurrently
+pub async fn readiness(
+    config: &AppConfig,
+    db: Option<&DatabaseManager>,
+    cache: Option<&CacheService>,
+) -> HealthReport {
+    let (postgres, redis, storage, migrations, smtp) = tokio::join!(
+        check_database(db),
+        check_cache(cache, config),
+        check_storage(&config.upload_dir),
+        check_migrations(db.map(DatabaseManager::pool)),
+        check_smtp(&config.smtp.host, config.smtp.port),
+    );
+
+    HealthReport::from_checks(vec![postgres, redis, storage, migrations, smtp])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(status: HealthStatus) -> CheckResult {
+        CheckResult {
+            name: "test",
+            status,
+            latency_ms: 0.0,
+            message: None,
+            details: None,
+        }
+    }
+
+    fn migration(version: i64, state: MigrationState) -> MigrationStatus {
+        MigrationStatus {
+            version,
+            description: format!("migration {}", version),
+            state,
+            installed_on: None,
+        }
+    }
+
+    #[test]
+    fn test_report_takes_the_worst_status() {
+        use HealthStatus::*;
+
+        let report = HealthReport::from_checks(vec![check(Up), check(Disabled)]);
+        assert_eq!(report.status, Up);
+        assert!(report.is_ready());
+
+        let report = HealthReport::from_checks(vec![check(Up), check(Degraded), check(Disabled)]);
+        assert_eq!(report.status, Degraded);
+        assert!(report.is_ready());
+
+        let report = HealthReport::from_checks(vec![check(Degraded), check(Down)]);
+        assert_eq!(report.status, Down);
+        assert!(!report.is_ready());
+
+        let body = serde_json::to_value(&report).unwrap();
+        assert_eq!(body["status"], "down");
+        assert_eq!(body["checks"][0]["status"], "degraded");
+    }
+
+    #[test]
+    fn test_pool_is_exhausted_only_when_every_connection_is_busy() {
+        assert!(pool_exhausted(5, 0, 5));
+        assert!(!pool_exhausted(5, 1, 5));
+        // Still able to open new connections
+        assert!(!pool_exhausted(3, 0, 5));
+    }
+
+    #[test]
+    fn test_migration_assessment() {
+        use MigrationState::*;
+
+        let applied = [migration(1, Applied), migration(2, Applied)];
+        assert_eq!(assess_migrations(&applied).0, HealthStatus::Up);
+
+        let behind = [migration(1, Applied), migration(2, Pending)];
+        let (status, message) = assess_migrations(&behind);
+        assert_eq!(status, HealthStatus::Down);
+        assert_eq!(message.unwrap(), "pending migrations: [2]");
+
+        let failed = [migration(1, Failed), migration(2, Pending)];
+        assert_eq!(assess_migrations(&failed).0, HealthStatus::Down);
+
+        let ahead = [migration(1, Applied), migration(3, Missing)];
+        assert_eq!(assess_migrations(&ahead).0, HealthStatus::Degraded);
+    }
+}
@@ -0,0 +1,104 @@
+// HTTP client for external APIs (Midtrans, WhatsApp)
+// Each request carries the W3C trace context of the current span and the
+// X-Request-Id of the request being handled, so upstream calls show up in the
+// same trace and can be matched with the provider's logs. Outgoing email gets
+// the same headers from `propagation_headers`.
+
+use reqwest::{Client, Method, RequestBuilder};
+use std::time::Duration;
+
+use crate::infrastructure::monitoring::trace_context_headers;
+use crate::shared::errors::AppError;
+use crate::shared::request_context::{RequestContext, REQUEST_ID_HEADER};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone)]
+pub struct ExternalHttpClient {
+    client: Client,
+}
+
+impl ExternalHttpClient {
+    pub fn new() -> Result<Self, AppError> {
+        Self::with_timeout(DEFAULT_TIMEOUT)
+    }
+
+    pub fn with_timeout(timeout: Duration) -> Result<Self, AppError> {
+        let client = Client::builder()
+            .timeout(timeout)
+            .user_agent(concat!("saas-umkm-backend/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|e| AppError::ExternalApi(format!("Failed to build HTTP client: {}", e)))?;
+
+        Ok(Self { client })
+    }
+
+    /// Request builder with the correlation headers already set
+    pub fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        propagation_headers()
+            .into_iter()
+            .fold(self.client.request(method, url), |builder, (name, value)| {
+                builder.header(name, value)
+            })
+    }
+
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.request(Method::GET, url)
+    }
+
+    pub fn post(&self, url: &str) -> RequestBuilder {
+        self.request(Method::POST, url)
+    }
+}
+
+/// `traceparent`/`tracestate` of the current span plus the current request id
+pub fn propagation_headers() -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = trace_context_headers(&tracing::Span::current())
+        .into_iter()
+        .collect();
+
+    if let Some(context) = RequestContext::current() {
+        headers.push((REQUEST_ID_HEADER.to_string(), context.request_id));
+    }
+
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::monitoring::trace_id;
+    use opentelemetry::trace::TracerProvider as _;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_propagates_trace_context_and_request_id() {
+        let provider = opentelemetry_sdk::trace::TracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("outgoing");
+            let _entered = span.enter();
+            let expected_trace_id = trace_id(&span).expect("span has a trace id");
+
+            let context = RequestContext {
+                request_id: "req-1".to_string(),
+                trace_id: Some(expected_trace_id.clone()),
+            };
+            let headers = context.sync_scope(propagation_headers);
+
+            let traceparent = &headers.iter().find(|(k, _)| k == "traceparent").unwrap().1;
+            let parts: Vec<&str> = traceparent.split('-').collect();
+            assert_eq!(parts.len(), 4);
+            assert_eq!(parts[0], "00");
+            assert_eq!(parts[1], expected_trace_id);
+            assert_eq!(parts[2].len(), 16);
+
+            assert!(headers.contains(&(REQUEST_ID_HEADER.to_string(), "req-1".to_string())));
+        });
+
+        // Outside a request and a trace there is nothing to propagate
+        assert!(propagation_headers().is_empty());
+    }
+}
@@ -0,0 +1,407 @@
+// Sales invoice PDFs
+// A4 faktur in Bahasa Indonesia: the seller and buyer with their NPWP, the
+// invoice number and dates, one row per line item and the totals with PPN.
+// Long invoices continue on further pages with the table header repeated.
+// Uses the same standard Helvetica fonts as the license certificates.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, TextStr};
+
+use crate::domain::analytics::reporting_day;
+use crate::domain::companies::Company;
+use crate::domain::invoicing::{Contact, SalesInvoice, SalesInvoiceStatus};
+use crate::domain::value_objects::Money;
+use crate::shared::errors::AppResult;
+
+use super::certificate_pdf::text;
+
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 48.0;
+const ROW_HEIGHT: f32 = 18.0;
+/// Room kept under the last row of a page for the footer
+const FOOTER_SPACE: f32 = 60.0;
+/// Room the totals block needs, down to where the notes go
+const TOTALS_SPACE: f32 = 120.0;
+const MAX_DESCRIPTION: usize = 48;
+
+const REGULAR: Name = Name(b"F1");
+const BOLD: Name = Name(b"F2");
+
+/// Right edges of the numeric columns: quantity, unit price, discount, amount
+const QUANTITY_RIGHT: f32 = 330.0;
+const PRICE_RIGHT: f32 = 420.0;
+const DISCOUNT_RIGHT: f32 = 465.0;
+const AMOUNT_RIGHT: f32 = PAGE_WIDTH - MARGIN;
+
+/// What an invoice PDF shows
+pub struct InvoicePdfContent<'a> {
+    pub invoice: &'a SalesInvoice,
+    pub contact: &'a Contact,
+    pub company: &'a Company,
+    pub generated_at: DateTime<Utc>,
+}
+
+fn status_name(status: SalesInvoiceStatus) -> &'static str {
+    match status {
+        SalesInvoiceStatus::Draft => "DRAF",
+        SalesInvoiceStatus::Sent => "BELUM DIBAYAR",
+        SalesInvoiceStatus::PartiallyPaid => "DIBAYAR SEBAGIAN",
+        SalesInvoiceStatus::Paid => "LUNAS",
+        SalesInvoiceStatus::Overdue => "JATUH TEMPO",
+        SalesInvoiceStatus::Void => "DIBATALKAN",
+    }
+}
+
+fn day(date: Option<NaiveDate>) -> String {
+    date.map(|d| d.format("%d-%m-%Y").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Width of `value` in Helvetica, from the glyph widths of what amounts and
+/// numbers are made of; other characters are taken as a digit wide
+fn width(value: &str, size: f32) -> f32 {
+    let units: u32 = value
+        .chars()
+        .map(|c| match c {
+            '.' | ',' | ' ' => 278,
+            '-' => 333,
+            'R' => 722,
+            '%' => 889,
+            _ => 556,
+        })
+        .sum();
+    units as f32 * size / 1000.0
+}
+
+fn right(content: &mut Content, font: Name, size: f32, right_edge: f32, y: f32, value: &str) {
+    text(content, font, size, right_edge - width(value, size), y, value);
+}
+
+fn shortened(value: &str) -> String {
+    if value.chars().count() <= MAX_DESCRIPTION {
+        return value.to_string();
+    }
+    let kept: String = value.chars().take(MAX_DESCRIPTION - 3).collect();
+    format!("{}...", kept)
+}
+
+/// Basis points as a percentage, e.g. `11%` or `2,50%`
+fn percent(bps: i64) -> String {
+    if bps % 100 == 0 {
+        format!("{}%", bps / 100)
+    } else {
+        format!("{},{:02}%", bps / 100, bps % 100)
+    }
+}
+
+fn table_header(content: &mut Content, y: f32) {
+    content.set_fill_gray(0.92);
+    content.rect(MARGIN, y - 5.0, PAGE_WIDTH - 2.0 * MARGIN, ROW_HEIGHT).fill_nonzero();
+    content.set_fill_gray(0.0);
+    text(content, BOLD, 9.0, MARGIN + 4.0, y, "No");
+    text(content, BOLD, 9.0, MARGIN + 28.0, y, "Deskripsi");
+    right(content, BOLD, 9.0, QUANTITY_RIGHT, y, "Qty");
+    right(content, BOLD, 9.0, PRICE_RIGHT, y, "Harga Satuan");
+    right(content, BOLD, 9.0, DISCOUNT_RIGHT, y, "Diskon");
+    right(content, BOLD, 9.0, AMOUNT_RIGHT - 4.0, y, "Jumlah");
+}
+
+/// The header block of the first page; returns where the table starts
+fn first_page_header(content: &mut Content, invoice: &InvoicePdfContent) -> f32 {
+    let company = invoice.company;
+    let contact = invoice.contact;
+    let sales = invoice.invoice;
+    let left = MARGIN;
+    let middle = PAGE_WIDTH / 2.0 + 20.0;
+    let mut y = PAGE_HEIGHT - MARGIN - 10.0;
+
+    // Seller, top left
+    content.set_fill_rgb(0.05, 0.35, 0.25);
+    text(content, BOLD, 16.0, left, y, &company.company_name);
+    content.set_fill_gray(0.2);
+    let mut seller = vec![
+        company.address_street.clone(),
+        format!("{}, {} {}", company.address_city, company.address_province, company.address_postal_code),
+    ];
+    if let Some(npwp) = &company.npwp_company {
+        seller.push(format!("NPWP: {}", npwp.formatted()));
+    }
+    if let Some(email) = &company.email {
+        seller.push(email.clone());
+    }
+    let mut seller_y = y - 16.0;
+    for line in &seller {
+        text(content, REGULAR, 9.0, left, seller_y, line);
+        seller_y -= 12.0;
+    }
+
+    // Title and invoice details, top right
+    content.set_fill_gray(0.0);
+    text(content, BOLD, 20.0, middle, y, "FAKTUR");
+    let details = [
+        ("Nomor", sales.number.clone().unwrap_or_else(|| "DRAF".to_string())),
+        ("Tanggal", day(sales.issue_date)),
+        ("Jatuh Tempo", day(sales.due_date)),
+        ("Status", status_name(sales.status).to_string()),
+    ];
+    y -= 18.0;
+    for (label, value) in &details {
+        content.set_fill_gray(0.4);
+        text(content, REGULAR, 9.0, middle, y, label);
+        content.set_fill_gray(0.0);
+        text(content, BOLD, 9.0, middle + 70.0, y, value);
+        y -= 13.0;
+    }
+
+    // Buyer
+    y = y.min(seller_y) - 20.0;
+    content.set_fill_gray(0.4);
+    text(content, REGULAR, 9.0, left, y, "Ditagihkan kepada");
+    y -= 14.0;
+    content.set_fill_gray(0.0);
+    text(content, BOLD, 11.0, left, y, &contact.name);
+    let buyer = [
+        contact.address.clone(),
+        contact.npwp.as_ref().map(|npwp| format!("NPWP: {}", npwp.formatted())),
+        contact.email.clone(),
+    ];
+    for line in buyer.iter().flatten() {
+        y -= 12.0;
+        text(content, REGULAR, 9.0, left, y, line);
+    }
+    y - 30.0
+}
+
+fn footer(content: &mut Content, page: usize, pages: usize, generated_at: DateTime<Utc>) {
+    content.set_fill_gray(0.4);
+    text(
+        content,
+        REGULAR,
+        8.0,
+        MARGIN,
+        MARGIN - 16.0,
+        &format!(
+            "Dokumen elektronik, dibuat {} (WIB)",
+            reporting_day(generated_at).format("%d-%m-%Y")
+        ),
+    );
+    right(
+        content,
+        REGULAR,
+        8.0,
+        AMOUNT_RIGHT,
+        MARGIN - 16.0,
+        &format!("Halaman {} dari {}", page, pages),
+    );
+}
+
+/// The totals block with its top at `y`
+fn totals(content: &mut Content, invoice: &SalesInvoice, mut y: f32) {
+    let label_x = PRICE_RIGHT - 60.0;
+    let mut rows: Vec<(String, &Money, bool)> = vec![("Subtotal".to_string(), &invoice.subtotal, false)];
+    if invoice.discount.amount > 0 {
+        rows.push(("Diskon".to_string(), &invoice.discount, false));
+    }
+    rows.push((format!("PPN {}", percent(invoice.ppn_rate_bps)), &invoice.ppn, false));
+    rows.push(("Total".to_string(), &invoice.total, true));
+    if invoice.amount_paid.amount > 0 {
+        rows.push(("Dibayar".to_string(), &invoice.amount_paid, false));
+        rows.push(("Sisa Tagihan".to_string(), &invoice.balance_due, true));
+    }
+
+    content.set_stroke_gray(0.6).set_line_width(0.5);
+    content.move_to(label_x, y + 12.0).line_to(AMOUNT_RIGHT, y + 12.0).stroke();
+    for (label, amount, emphasized) in rows {
+        let font = if emphasized { BOLD } else { REGULAR };
+        let value = if label == "Diskon" {
+            format!("-{}", amount.rupiah())
+        } else {
+            amount.rupiah()
+        };
+        text(content, font, 10.0, label_x, y, &label);
+        right(content, font, 10.0, AMOUNT_RIGHT - 4.0, y, &value);
+        y -= 15.0;
+    }
+}
+
+/// Renders the invoice, on as many pages as its lines need
+pub fn render_invoice(invoice: &InvoicePdfContent) -> AppResult<Vec<u8>> {
+    let sales = invoice.invoice;
+    let bottom = MARGIN + FOOTER_SPACE;
+
+    let mut pages = Vec::new();
+    let mut content = Content::new();
+    let mut y = first_page_header(&mut content, invoice);
+    table_header(&mut content, y);
+    for (index, line) in sales.lines.iter().enumerate() {
+        y -= ROW_HEIGHT;
+        if y < bottom {
+            pages.push(content);
+            content = Content::new();
+            y = PAGE_HEIGHT - MARGIN - 10.0;
+            table_header(&mut content, y);
+            y -= ROW_HEIGHT;
+        }
+        content.set_fill_gray(0.0);
+        text(&mut content, REGULAR, 9.0, MARGIN + 4.0, y, &(index + 1).to_string());
+        text(&mut content, REGULAR, 9.0, MARGIN + 28.0, y, &shortened(&line.description));
+        right(&mut content, REGULAR, 9.0, QUANTITY_RIGHT, y, &line.quantity.to_string());
+        right(&mut content, REGULAR, 9.0, PRICE_RIGHT, y, &line.unit_price.rupiah());
+        right(&mut content, REGULAR, 9.0, DISCOUNT_RIGHT, y, &match line.discount_bps {
+            0 => "-".to_string(),
+            bps => percent(bps),
+        });
+        right(&mut content, REGULAR, 9.0, AMOUNT_RIGHT - 4.0, y, &line.amount.rupiah());
+    }
+
+    y -= 2.0 * ROW_HEIGHT;
+    if y - TOTALS_SPACE < MARGIN + 20.0 {
+        pages.push(content);
+        content = Content::new();
+        y = PAGE_HEIGHT - MARGIN - 10.0;
+    }
+    totals(&mut content, sales, y);
+    if let Some(notes) = &sales.notes {
+        content.set_fill_gray(0.4);
+        text(&mut content, REGULAR, 9.0, MARGIN, MARGIN + 20.0, &format!("Catatan: {}", notes));
+    }
+    pages.push(content);
+
+    let catalog_id = Ref::new(1);
+    let page_tree_id = Ref::new(2);
+    let regular_id = Ref::new(3);
+    let bold_id = Ref::new(4);
+    let info_id = Ref::new(5);
+    let page_ids: Vec<Ref> = (0..pages.len()).map(|i| Ref::new(6 + 2 * i as i32)).collect();
+
+    let mut pdf = Pdf::new();
+    pdf.catalog(catalog_id).pages(page_tree_id);
+    pdf.pages(page_tree_id)
+        .kids(page_ids.iter().copied())
+        .count(pages.len() as i32);
+    for (id, font) in [(regular_id, "Helvetica"), (bold_id, "Helvetica-Bold")] {
+        pdf.type1_font(id)
+            .base_font(Name(font.as_bytes()))
+            .encoding_predefined(Name(b"WinAnsiEncoding"));
+    }
+    let number = sales.number.as_deref().unwrap_or("Draf");
+    pdf.document_info(info_id)
+        .title(TextStr(&format!("Faktur {}", number)))
+        .creator(TextStr("SaaS UMKM"));
+
+    let count = pages.len();
+    for (index, (mut content, page_id)) in pages.into_iter().zip(page_ids).enumerate() {
+        footer(&mut content, index + 1, count, invoice.generated_at);
+        let content_id = Ref::new(page_id.get() + 1);
+        let mut page = pdf.page(page_id);
+        page.parent(page_tree_id)
+            .media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT))
+            .contents(content_id);
+        page.resources()
+            .fonts()
+            .pair(REGULAR, regular_id)
+            .pair(BOLD, bold_id);
+        page.finish();
+        pdf.stream(content_id, &content.finish());
+    }
+    Ok(pdf.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::companies::{BusinessType, CompanyAddress};
+    use crate::domain::invoicing::{ContactInput, InvoiceDraft, LineInput};
+    use uuid::Uuid;
+
+    fn sample(lines: usize) -> (Company, Contact, SalesInvoice) {
+        let company = Company::new(
+            Uuid::new_v4(),
+            "Keripik Bu Siti".to_string(),
+            BusinessType::UD,
+            "Makanan".to_string(),
+            CompanyAddress::new(
+                "Jl. Melati 1".to_string(),
+                "Bandung".to_string(),
+                "Jawa Barat".to_string(),
+                "40111".to_string(),
+            ),
+        );
+        let contact = Contact::new(
+            company.id,
+            ContactInput {
+                name: "Toko Sumber Rejeki".to_string(),
+                npwp: Some("01.000.013.1-093.000".to_string()),
+                ..ContactInput::default()
+            },
+            Utc::now(),
+        )
+        .unwrap();
+        let draft = InvoiceDraft {
+            contact_id: contact.id,
+            lines: (1..=lines)
+                .map(|i| LineInput {
+                    description: format!("Keripik singkong pedas 250g batch {}", i),
+                    quantity: 3,
+                    unit_price: 2_500_000,
+                    discount_bps: 1_000,
+                })
+                .collect(),
+            discount: 0,
+            ppn_rate_bps: Some(1_100),
+            issue_date: None,
+            due_date: None,
+            notes: Some("Transfer ke BCA 1234567890".to_string()),
+        };
+        let mut invoice = SalesInvoice::draft(company.id, company.owner_id, draft, 0, Utc::now()).unwrap();
+        invoice.send(reporting_day(Utc::now()), 14, Utc::now()).unwrap();
+        invoice.number = Some("INV/2026/00007".to_string());
+        (company, contact, invoice)
+    }
+
+    #[test]
+    fn test_width_right_aligns_amounts() {
+        assert_eq!(width("10", 10.0), 11.12);
+        assert!(width("Rp 1.000", 10.0) < width("Rp 10.000", 10.0));
+    }
+
+    #[test]
+    fn test_renders_the_invoice_over_as_many_pages_as_needed() {
+        let (company, contact, invoice) = sample(2);
+        let pdf = render_invoice(&InvoicePdfContent {
+            invoice: &invoice,
+            contact: &contact,
+            company: &company,
+            generated_at: Utc::now(),
+        })
+        .unwrap();
+        assert!(pdf.starts_with(b"%PDF-"));
+        let body = String::from_utf8_lossy(&pdf);
+        for expected in [
+            "INV/2026/00007",
+            "Keripik Bu Siti",
+            "Toko Sumber Rejeki",
+            "NPWP: 01.000.013.1-093.000",
+            "Rp 67.500",
+            "PPN 11%",
+            "Rp 149.850",
+            "Halaman 1 dari 1",
+            "Transfer ke BCA",
+        ] {
+            assert!(body.contains(expected), "missing {expected}");
+        }
+
+        let (company, contact, long) = sample(60);
+        let pdf = render_invoice(&InvoicePdfContent {
+            invoice: &long,
+            contact: &contact,
+            company: &company,
+            generated_at: Utc::now(),
+        })
+        .unwrap();
+        let body = String::from_utf8_lossy(&pdf);
+        assert!(body.contains("Halaman 2 dari 2") && body.contains("batch 60"));
+        assert_eq!(body.matches("(Deskripsi)").count(), 2);
+    }
+}
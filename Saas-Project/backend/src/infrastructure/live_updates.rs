@@ -0,0 +1,207 @@
+// Live update fan-out
+// Every update is broadcast in process to the streams open on this instance.
+// With Redis it is also published on a pub/sub channel, and each instance
+// forwards what the others publish to its own streams. Updates published while
+// an instance is not subscribed are lost, so its streams are told to resync
+// when the subscription comes back.
+
+use futures::StreamExt;
+use redis::{AsyncCommands, Client, RedisError};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::domain::live_updates::LiveUpdate;
+use crate::infrastructure::monitoring::record_live_update;
+
+pub const LIVE_UPDATES_CHANNEL: &str = "live:updates";
+
+/// Updates a stream may fall behind by before it is told to resync
+const STREAM_BUFFER: usize = 256;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// What open streams receive
+#[derive(Debug, Clone)]
+pub enum HubMessage {
+    Update(Arc<LiveUpdate>),
+    /// Updates may have been missed; clients should refetch what they show
+    Resync,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope {
+    /// Instance that published the update; it has already delivered it locally
+    origin: String,
+    update: LiveUpdate,
+}
+
+#[derive(Clone)]
+pub struct LiveUpdateHub {
+    sender: broadcast::Sender<HubMessage>,
+    redis: Option<Client>,
+    instance_id: Arc<str>,
+}
+
+impl Default for LiveUpdateHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LiveUpdateHub {
+    /// A hub that only reaches streams on this instance
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(STREAM_BUFFER);
+        Self {
+            sender,
+            redis: None,
+            instance_id: Uuid::new_v4().to_string().into(),
+        }
+    }
+
+    /// Publishes through Redis as well; call `spawn_listener` to receive what
+    /// other instances publish
+    pub fn with_redis(mut self, client: Client) -> Self {
+        self.redis = Some(client);
+        self
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<HubMessage> {
+        self.sender.subscribe()
+    }
+
+    /// Delivers to local streams and the other instances. Never fails: a
+    /// missed update only leaves a client showing stale data until it refetches.
+    pub async fn publish(&self, update: LiveUpdate) {
+        record_live_update(update.event.name());
+        let update = Arc::new(update);
+        // No open streams is not an error
+        let _ = self.sender.send(HubMessage::Update(update.clone()));
+
+        let Some(client) = &self.redis else {
+            return;
+        };
+        let envelope = Envelope {
+            origin: self.instance_id.to_string(),
+            update: (*update).clone(),
+        };
+        let payload = serde_json::to_string(&envelope).expect("live update serializes");
+        let published = async {
+            let mut conn = client.get_async_connection().await?;
+            conn.publish::<_, _, ()>(LIVE_UPDATES_CHANNEL, payload).await
+        };
+        if let Err(err) = published.await {
+            warn!("⚠️ Failed to publish live update to other instances: {}", err);
+        }
+    }
+
+    /// Forwards a payload from the channel unless this instance sent it
+    fn forward(&self, payload: &str) {
+        match serde_json::from_str::<Envelope>(payload) {
+            Ok(envelope) if *envelope.origin != *self.instance_id => {
+                let _ = self.sender.send(HubMessage::Update(Arc::new(envelope.update)));
+            }
+            Ok(_) => {}
+            Err(err) => debug!("Ignoring malformed live update: {}", err),
+        }
+    }
+
+    /// Subscribes to updates from other instances until the process exits
+    pub fn spawn_listener(&self) -> Option<JoinHandle<()>> {
+        let client = self.redis.clone()?;
+        let hub = self.clone();
+        Some(tokio::spawn(async move {
+            let mut subscribed_before = false;
+            loop {
+                if let Err(err) = hub.listen(&client, &mut subscribed_before).await {
+                    warn!("⚠️ Live update subscription lost: {}", err);
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        }))
+    }
+
+    async fn listen(&self, client: &Client, subscribed_before: &mut bool) -> Result<(), RedisError> {
+        let mut pubsub = client.get_async_connection().await?.into_pubsub();
+        pubsub.subscribe(LIVE_UPDATES_CHANNEL).await?;
+        if *subscribed_before {
+            let _ = self.sender.send(HubMessage::Resync);
+        }
+        *subscribed_before = true;
+        info!("📡 Subscribed to live updates");
+
+        let mut messages = pubsub.on_message();
+        while let Some(message) = messages.next().await {
+            let payload: String = message.get_payload()?;
+            self.forward(&payload);
+        }
+
+        Err(RedisError::from(std::io::Error::new(
+            std::io::ErrorKind::ConnectionAborted,
+            "subscription closed",
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::licenses::{License, LicenseType};
+
+    fn update() -> LiveUpdate {
+        let license = License::new(
+            LicenseType::Nib,
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            "NIB Warung Sari".to_string(),
+            None,
+        );
+        LiveUpdate::license_submitted(&license)
+    }
+
+    fn received(receiver: &mut broadcast::Receiver<HubMessage>) -> Option<LiveUpdate> {
+        match receiver.try_recv() {
+            Ok(HubMessage::Update(update)) => Some((*update).clone()),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_published_updates_reach_every_local_stream() {
+        let hub = LiveUpdateHub::new();
+        let mut first = hub.subscribe();
+        let mut second = hub.subscribe();
+
+        let update = update();
+        hub.publish(update.clone()).await;
+
+        assert_eq!(received(&mut first), Some(update.clone()));
+        assert_eq!(received(&mut second), Some(update));
+    }
+
+    #[test]
+    fn test_updates_from_other_instances_are_forwarded_but_not_our_own() {
+        let hub = LiveUpdateHub::new();
+        let mut stream = hub.subscribe();
+        let update = update();
+        let envelope = |origin: &str| {
+            serde_json::to_string(&Envelope {
+                origin: origin.to_string(),
+                update: update.clone(),
+            })
+            .unwrap()
+        };
+
+        hub.forward(&envelope(&hub.instance_id));
+        hub.forward("not json");
+        assert_eq!(received(&mut stream), None);
+
+        hub.forward(&envelope("another-instance"));
+        assert_eq!(received(&mut stream), Some(update));
+    }
+}
@@ -0,0 +1,134 @@
+// Email over SMTP
+// Plain-text messages, with any attachments as a multipart/mixed message, are
+// sent with STARTTLS on the submission port, or with implicit TLS when the
+// port is 465. Each message carries the trace context and request id it was
+// sent under, like outgoing HTTP calls.
+
+use async_trait::async_trait;
+use lettre::message::header::{ContentType, HeaderName, HeaderValue};
+use lettre::message::{Attachment as MimeAttachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use crate::config::SmtpConfig;
+use crate::domain::notifications::{Attachment, MessageSender};
+use crate::infrastructure::http_client::propagation_headers;
+use crate::shared::errors::{AppError, AppResult};
+
+const IMPLICIT_TLS_PORT: u16 = 465;
+
+pub struct SmtpEmailSender {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpEmailSender {
+    pub fn new(config: &SmtpConfig) -> AppResult<Self> {
+        let builder = if config.port == IMPLICIT_TLS_PORT {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)
+        }
+        .map_err(|e| AppError::InternalError(format!("Invalid SMTP host: {}", e)))?;
+
+        let transport = builder
+            .port(config.port)
+            .credentials(Credentials::new(
+                config.username.clone(),
+                config.password.clone(),
+            ))
+            .build();
+
+        let from = format!("{} <{}>", config.from_name, config.from_email)
+            .parse()
+            .map_err(|e| AppError::InternalError(format!("Invalid SMTP sender: {}", e)))?;
+
+        Ok(Self { transport, from })
+    }
+}
+
+impl SmtpEmailSender {
+    fn builder(&self, recipient: &str, subject: &str) -> AppResult<lettre::message::MessageBuilder> {
+        let to: Mailbox = recipient
+            .parse()
+            .map_err(|e| AppError::Validation(format!("Invalid email recipient: {}", e)))?;
+        let builder = Message::builder().from(self.from.clone()).to(to).subject(subject);
+        Ok(correlated(builder))
+    }
+
+    async fn deliver(&self, message: Result<Message, lettre::error::Error>) -> AppResult<()> {
+        let message =
+            message.map_err(|e| AppError::InternalError(format!("Failed to build email: {}", e)))?;
+        self.transport
+            .send(message)
+            .await
+            .map_err(|e| AppError::ExternalApi(format!("SMTP delivery failed: {}", e)))?;
+        Ok(())
+    }
+}
+
+/// Adds the `traceparent` and `X-Request-Id` of the current span and request
+fn correlated(builder: lettre::message::MessageBuilder) -> lettre::message::MessageBuilder {
+    propagation_headers()
+        .into_iter()
+        .filter_map(|(name, value)| Some(HeaderValue::new(HeaderName::new_from_ascii(name).ok()?, value)))
+        .fold(builder, |builder, header| builder.raw_header(header))
+}
+
+#[async_trait]
+impl MessageSender for SmtpEmailSender {
+    async fn send(&self, recipient: &str, subject: &str, body: &str) -> AppResult<()> {
+        let message = self
+            .builder(recipient, subject)?
+            .header(ContentType::TEXT_PLAIN)
+            .body(body.to_string());
+        self.deliver(message).await
+    }
+
+    async fn send_with_attachments(
+        &self,
+        recipient: &str,
+        subject: &str,
+        body: &str,
+        attachments: &[Attachment],
+    ) -> AppResult<()> {
+        let mut parts = MultiPart::mixed().singlepart(SinglePart::plain(body.to_string()));
+        for attachment in attachments {
+            let content_type = ContentType::parse(&attachment.content_type).map_err(|e| {
+                AppError::InternalError(format!("Invalid attachment content type: {}", e))
+            })?;
+            parts = parts.singlepart(
+                MimeAttachment::new(attachment.file_name.clone())
+                    .body(attachment.content.clone(), content_type),
+            );
+        }
+        let message = self.builder(recipient, subject)?.multipart(parts);
+        self.deliver(message).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::request_context::RequestContext;
+
+    #[test]
+    fn test_messages_carry_the_request_id() {
+        let context = RequestContext {
+            request_id: "req-email".to_string(),
+            trace_id: None,
+        };
+        let message = context
+            .sync_scope(|| {
+                correlated(Message::builder())
+                    .from("Admin <admin@saas-umkm.id>".parse().unwrap())
+                    .to("budi@example.com".parse().unwrap())
+                    .subject("Faktur")
+                    .body("Terlampir".to_string())
+            })
+            .unwrap();
+
+        let formatted = String::from_utf8(message.formatted()).unwrap().to_ascii_lowercase();
+        assert!(formatted.contains("x-request-id: req-email"), "{}", formatted);
+    }
+}
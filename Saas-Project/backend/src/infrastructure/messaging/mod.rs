@@ -0,0 +1,97 @@
+// Senders for the external notification channels
+// Email goes out over SMTP and WhatsApp over the Business Cloud API. Where a
+// channel is not configured, as in demo mode and tests, `RecordingSender`
+// stands in: it logs and keeps what would have been sent, and can be told to
+// fail so that retries can be exercised.
+
+pub mod email;
+pub mod whatsapp;
+
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+use crate::domain::notifications::{Attachment, MessageSender};
+use crate::shared::errors::{AppError, AppResult};
+
+pub use email::SmtpEmailSender;
+pub use whatsapp::WhatsAppCloudSender;
+
+/// A message `RecordingSender` accepted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentMessage {
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+    /// File names of the attachments
+    pub attachments: Vec<String>,
+}
+
+/// Fake sender that keeps messages instead of sending them
+#[derive(Clone)]
+pub struct RecordingSender {
+    channel: &'static str,
+    sent: Arc<Mutex<Vec<SentMessage>>>,
+    failures_left: Arc<Mutex<u32>>,
+}
+
+impl RecordingSender {
+    /// `channel` only names the sender in logs
+    pub fn new(channel: &'static str) -> Self {
+        Self {
+            channel,
+            sent: Arc::default(),
+            failures_left: Arc::default(),
+        }
+    }
+
+    /// Makes the next `count` sends fail
+    pub fn fail_next(&self, count: u32) {
+        *self.failures_left.lock().unwrap() = count;
+    }
+
+    pub fn sent(&self) -> Vec<SentMessage> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl MessageSender for RecordingSender {
+    async fn send(&self, recipient: &str, subject: &str, body: &str) -> AppResult<()> {
+        self.send_with_attachments(recipient, subject, body, &[]).await
+    }
+
+    async fn send_with_attachments(
+        &self,
+        recipient: &str,
+        subject: &str,
+        body: &str,
+        attachments: &[Attachment],
+    ) -> AppResult<()> {
+        {
+            let mut failures_left = self.failures_left.lock().unwrap();
+            if *failures_left > 0 {
+                *failures_left -= 1;
+                return Err(AppError::ExternalApi(format!(
+                    "{} sender unavailable",
+                    self.channel
+                )));
+            }
+        }
+
+        info!(
+            channel = self.channel,
+            recipient,
+            subject,
+            attachments = attachments.len(),
+            "📨 Notification recorded instead of sent"
+        );
+        self.sent.lock().unwrap().push(SentMessage {
+            recipient: recipient.to_string(),
+            subject: subject.to_string(),
+            body: body.to_string(),
+            attachments: attachments.iter().map(|a| a.file_name.clone()).collect(),
+        });
+        Ok(())
+    }
+}
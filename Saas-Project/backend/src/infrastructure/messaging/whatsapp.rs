@@ -0,0 +1,58 @@
+// WhatsApp over the Business Cloud API
+// Messages are sent as text from the configured business number. The API
+// accepts free-form text only within a conversation the user opened in the
+// last 24 hours; it rejects the rest, and those deliveries end up failed.
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::config::WhatsAppConfig;
+use crate::domain::notifications::MessageSender;
+use crate::infrastructure::http_client::ExternalHttpClient;
+use crate::shared::errors::{AppError, AppResult};
+
+pub struct WhatsAppCloudSender {
+    client: ExternalHttpClient,
+    messages_url: String,
+    access_token: String,
+}
+
+impl WhatsAppCloudSender {
+    pub fn new(config: &WhatsAppConfig) -> AppResult<Self> {
+        Ok(Self {
+            client: ExternalHttpClient::new()?,
+            messages_url: format!("{}/{}/messages", config.api_url, config.phone_number_id),
+            access_token: config.access_token.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl MessageSender for WhatsAppCloudSender {
+    /// `recipient` is the number in international form, e.g. `+6281234567890`
+    async fn send(&self, recipient: &str, _subject: &str, body: &str) -> AppResult<()> {
+        let response = self
+            .client
+            .post(&self.messages_url)
+            .bearer_auth(&self.access_token)
+            .json(&json!({
+                "messaging_product": "whatsapp",
+                "to": recipient.trim_start_matches('+'),
+                "type": "text",
+                "text": { "body": body },
+            }))
+            .send()
+            .await
+            .map_err(|e| AppError::ExternalApi(format!("WhatsApp request failed: {}", e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let detail = response.text().await.unwrap_or_default();
+            return Err(AppError::ExternalApi(format!(
+                "WhatsApp API returned {}: {}",
+                status, detail
+            )));
+        }
+        Ok(())
+    }
+}
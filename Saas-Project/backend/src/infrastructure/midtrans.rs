@@ -0,0 +1,139 @@
+// Midtrans API client
+// Looks up the status of a payment at Midtrans. Calls go through
+// `ExternalHttpClient`, so they carry the trace context and request id of the
+// notification being handled and show up in the same trace.
+
+use crate::config::ExternalApiConfig;
+use crate::infrastructure::http_client::ExternalHttpClient;
+use crate::services::payment::MidtransNotification;
+use crate::shared::errors::{AppError, AppResult};
+
+const PRODUCTION_API_URL: &str = "https://api.midtrans.com";
+const SANDBOX_API_URL: &str = "https://api.sandbox.midtrans.com";
+
+pub struct MidtransClient {
+    client: ExternalHttpClient,
+    api_url: String,
+    server_key: String,
+}
+
+impl MidtransClient {
+    /// `None` when no server key is configured
+    pub fn from_config(config: &ExternalApiConfig) -> AppResult<Option<Self>> {
+        if config.midtrans_server_key.is_empty() {
+            return Ok(None);
+        }
+        let api_url = if config.midtrans_is_production {
+            PRODUCTION_API_URL
+        } else {
+            SANDBOX_API_URL
+        };
+        Ok(Some(Self::new(api_url, &config.midtrans_server_key)?))
+    }
+
+    pub fn new(api_url: &str, server_key: &str) -> AppResult<Self> {
+        Ok(Self {
+            client: ExternalHttpClient::new()?,
+            api_url: api_url.trim_end_matches('/').to_string(),
+            server_key: server_key.to_string(),
+        })
+    }
+
+    /// Current status of the payment started for `order_id`, in the same shape
+    /// as a notification
+    pub async fn transaction_status(&self, order_id: &str) -> AppResult<MidtransNotification> {
+        let response = self
+            .client
+            .get(&format!("{}/v2/{}/status", self.api_url, order_id))
+            // The server key is the user name, with an empty password
+            .basic_auth(&self.server_key, Some(""))
+            .send()
+            .await
+            .map_err(|e| AppError::ExternalApi(format!("Midtrans request failed: {}", e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let detail = response.text().await.unwrap_or_default();
+            return Err(AppError::ExternalApi(format!(
+                "Midtrans API returned {}: {}",
+                status, detail
+            )));
+        }
+        let transaction: MidtransNotification = response
+            .json()
+            .await
+            .map_err(|e| AppError::ExternalApi(format!("Unreadable Midtrans status: {}", e)))?;
+
+        // Errors such as an unknown order come back as a 200 with the real
+        // code in the body
+        if transaction.order_id != order_id || !transaction.status_code.starts_with('2') {
+            return Err(AppError::ExternalApi(format!(
+                "Midtrans has no payment for {} ({})",
+                order_id,
+                transaction.status_message.as_deref().unwrap_or(&transaction.status_code)
+            )));
+        }
+        Ok(transaction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::request_context::{RequestContext, REQUEST_ID_HEADER};
+    use axum::{extract::Path, http::HeaderMap, routing::get, Json, Router};
+    use serde_json::json;
+
+    /// Answers like the status API and echoes the headers it was sent
+    async fn status(Path(order_id): Path<String>, headers: HeaderMap) -> Json<serde_json::Value> {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+        if order_id != "INV-2024-0001" {
+            return Json(json!({
+                "order_id": order_id,
+                "status_code": "404",
+                "status_message": "Transaction doesn't exist.",
+            }));
+        }
+        Json(json!({
+            "order_id": order_id,
+            "status_code": "200",
+            "gross_amount": "165390.00",
+            "signature_key": "",
+            "transaction_status": "settlement",
+            "transaction_id": "tx-1",
+            "status_message": header(REQUEST_ID_HEADER).zip(header("authorization"))
+                .map(|(request_id, auth)| format!("{} {}", request_id, auth)),
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_transaction_status_authenticates_and_propagates_the_request_id() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let api_url = format!("http://{}", listener.local_addr().unwrap());
+        let app = Router::new().route("/v2/:order_id/status", get(status));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = MidtransClient::new(&api_url, "SB-Mid-server-key").unwrap();
+        let context = RequestContext {
+            request_id: "req-midtrans".to_string(),
+            trace_id: None,
+        };
+        let transaction = context
+            .scope(client.transaction_status("INV-2024-0001"))
+            .await
+            .unwrap();
+
+        assert_eq!(transaction.transaction_status, "settlement");
+        assert_eq!(transaction.amount(), Some(16_539_000));
+        // Basic auth of "SB-Mid-server-key:"
+        assert_eq!(
+            transaction.status_message.as_deref(),
+            Some("req-midtrans Basic U0ItTWlkLXNlcnZlci1rZXk6")
+        );
+
+        assert!(matches!(
+            client.transaction_status("INV-2024-0404").await,
+            Err(AppError::ExternalApi(_))
+        ));
+    }
+}
@@ -3,8 +3,18 @@
 
 pub mod auth;
 pub mod cache;
+pub mod certificate_pdf;
 pub mod database;
-pub mod email;
+pub mod demo;
+pub mod messaging;
+pub mod health;
+pub mod http_client;
+pub mod invoice_pdf;
+pub mod live_updates;
+pub mod midtrans;
+pub mod monitoring;
+pub mod rate_limit;
 pub mod repositories;
+pub mod spreadsheet;
 pub mod storage;
 pub mod web;
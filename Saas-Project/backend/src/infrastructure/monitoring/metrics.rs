@@ -1,141 +1,325 @@
-use prometheus::{register_counter_vec, register_histogram_vec, CounterVec, HistogramVec};
-use once_cell::sync::Lazy;
+// Application metrics, recorded through the `metrics` facade
+// Everything lands in the single Prometheus recorder installed by
+// `init_telemetry`: HTTP traffic, the database pool, cache lookups and the
+// business events of the licensing platform. Before the recorder is installed
+// (e.g. in unit tests) recording is a no-op.
+
+use metrics::{counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram, Unit};
+use sqlx::PgPool;
+use std::time::Duration;
 
 // HTTP metrics
-pub static HTTP_REQUESTS_COUNTER: Lazy<CounterVec> = Lazy::new(|| {
-    register_counter_vec!(
-        "http_requests_total",
-        "Total number of HTTP requests",
-        &["method", "endpoint", "status_code"]
+pub const HTTP_REQUESTS_TOTAL: &str = "http_requests_total";
+pub const HTTP_REQUEST_DURATION_SECONDS: &str = "http_request_duration_seconds";
+pub const HTTP_RATE_LIMITED_TOTAL: &str = "http_rate_limited_total";
+
+// Database metrics
+pub const DB_POOL_CONNECTIONS: &str = "db_pool_connections";
+pub const DB_POOL_MAX_CONNECTIONS: &str = "db_pool_max_connections";
+
+// Cache metrics
+pub const CACHE_REQUESTS_TOTAL: &str = "cache_requests_total";
+
+// Domain metrics
+pub const AUTH_OPERATIONS_TOTAL: &str = "saas_umkm_auth_operations_total";
+pub const USER_REGISTRATIONS_TOTAL: &str = "saas_umkm_user_registrations_total";
+pub const COMPANIES_REGISTERED_TOTAL: &str = "saas_umkm_companies_registered_total";
+pub const LICENSE_APPLICATIONS_TOTAL: &str = "saas_umkm_license_applications_total";
+pub const LICENSE_DECISIONS_TOTAL: &str = "saas_umkm_license_decisions_total";
+pub const LICENSE_PROCESSING_TIME_SECONDS: &str = "saas_umkm_license_processing_time_seconds";
+pub const LICENSE_SLA_EVENTS_TOTAL: &str = "saas_umkm_license_sla_events_total";
+pub const LICENSE_SLA_APPLICATIONS: &str = "saas_umkm_license_sla_applications";
+pub const ANALYTICS_ROLLUP_DAYS_TOTAL: &str = "saas_umkm_analytics_rollup_days_total";
+pub const LICENSE_CERTIFICATES_TOTAL: &str = "saas_umkm_license_certificates_total";
+pub const LICENSE_VERIFICATIONS_TOTAL: &str = "saas_umkm_license_verifications_total";
+pub const NOTIFICATIONS_TOTAL: &str = "saas_umkm_notifications_total";
+pub const NOTIFICATION_DELIVERIES_TOTAL: &str = "saas_umkm_notification_deliveries_total";
+pub const LIVE_UPDATES_TOTAL: &str = "saas_umkm_live_updates_total";
+pub const LIVE_UPDATE_STREAMS: &str = "saas_umkm_live_update_streams";
+pub const SUBSCRIPTION_INVOICES_TOTAL: &str = "saas_umkm_subscription_invoices_total";
+pub const PLAN_LIMIT_REJECTIONS_TOTAL: &str = "saas_umkm_plan_limit_rejections_total";
+pub const SALES_INVOICES_TOTAL: &str = "saas_umkm_sales_invoices_total";
+pub const DOCUMENT_UPLOADS_TOTAL: &str = "saas_umkm_document_uploads_total";
+pub const DOCUMENT_UPLOAD_BYTES: &str = "saas_umkm_document_upload_bytes";
+
+/// Latency buckets for HTTP requests, in seconds
+pub const HTTP_DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// License processing takes hours to weeks, so the buckets run from an hour to 60 days
+pub const LICENSE_PROCESSING_BUCKETS: &[f64] = &[
+    3_600.0, 14_400.0, 43_200.0, 86_400.0, 259_200.0, 604_800.0, 1_209_600.0, 2_592_000.0,
+    5_184_000.0,
+];
+
+/// Route label for requests that matched no route, so unknown paths cannot
+/// blow up the label cardinality
+pub const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Registers help text and units with the recorder
+pub fn describe_metrics() {
+    describe_counter!(HTTP_REQUESTS_TOTAL, "Total number of HTTP requests");
+    describe_histogram!(
+        HTTP_REQUEST_DURATION_SECONDS,
+        Unit::Seconds,
+        "HTTP request duration in seconds"
+    );
+    describe_counter!(
+        HTTP_RATE_LIMITED_TOTAL,
+        "Requests refused by the rate limiter, by route group and limiting bucket"
+    );
+
+    describe_gauge!(DB_POOL_CONNECTIONS, "Database pool connections by state");
+    describe_gauge!(DB_POOL_MAX_CONNECTIONS, "Maximum size of the database pool");
+
+    describe_counter!(CACHE_REQUESTS_TOTAL, "Cache lookups by result");
+
+    describe_counter!(AUTH_OPERATIONS_TOTAL, "Total number of authentication operations");
+    describe_counter!(USER_REGISTRATIONS_TOTAL, "Total number of user registrations");
+    describe_counter!(COMPANIES_REGISTERED_TOTAL, "Total number of companies registered");
+    describe_counter!(
+        LICENSE_APPLICATIONS_TOTAL,
+        "Total number of license applications submitted"
+    );
+    describe_counter!(LICENSE_DECISIONS_TOTAL, "Total number of license approvals and rejections");
+    describe_histogram!(
+        LICENSE_PROCESSING_TIME_SECONDS,
+        Unit::Seconds,
+        "Time from submission to decision of license applications"
+    );
+    describe_counter!(
+        LICENSE_SLA_EVENTS_TOTAL,
+        "Total number of license SLA warnings and escalations raised"
+    );
+    describe_gauge!(
+        LICENSE_SLA_APPLICATIONS,
+        "In-flight license applications by SLA state at the last sweep"
+    );
+    describe_counter!(
+        ANALYTICS_ROLLUP_DAYS_TOTAL,
+        "Days of analytics rollups recomputed, by fact"
+    );
+    describe_counter!(
+        LICENSE_CERTIFICATES_TOTAL,
+        "License certificate PDFs generated, by reason"
+    );
+    describe_counter!(
+        LICENSE_VERIFICATIONS_TOTAL,
+        "Public license verification lookups, by method and whether anything was found"
+    );
+    describe_counter!(NOTIFICATIONS_TOTAL, "Notifications raised, by event");
+    describe_counter!(
+        NOTIFICATION_DELIVERIES_TOTAL,
+        "Email and WhatsApp delivery attempts, by channel and outcome (sent, retried, failed)"
+    );
+    describe_counter!(LIVE_UPDATES_TOTAL, "Live updates published by this instance, by event");
+    describe_gauge!(LIVE_UPDATE_STREAMS, "Live update streams open on this instance");
+    describe_counter!(
+        SUBSCRIPTION_INVOICES_TOTAL,
+        "Subscription invoices by plan and what happened to them (issued, paid, written_off)"
+    );
+    describe_counter!(
+        PLAN_LIMIT_REJECTIONS_TOTAL,
+        "Requests refused because the company's plan does not cover them, by plan and limit"
+    );
+    describe_counter!(
+        SALES_INVOICES_TOTAL,
+        "Customer invoices by what happened to them (sent, emailed, paid, overdue, voided)"
+    );
+    describe_counter!(DOCUMENT_UPLOADS_TOTAL, "Total number of documents uploaded");
+    describe_counter!(DOCUMENT_UPLOAD_BYTES, Unit::Bytes, "Total size of uploaded documents");
+}
+
+/// Records one HTTP request; `route` is the route template, e.g. `/api/v1/licenses/:id`
+pub fn track_http_request(method: &str, route: &str, status: u16, duration: Duration) {
+    let method = method.to_string();
+    let route = route.to_string();
+
+    counter!(
+        HTTP_REQUESTS_TOTAL,
+        "method" => method.clone(),
+        "route" => route.clone(),
+        "status" => status.to_string()
     )
-    .unwrap()
-});
-
-pub static HTTP_REQUEST_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
-    register_histogram_vec!(
-        "http_request_duration_seconds",
-        "HTTP request duration in seconds",
-        &["method", "endpoint"]
+    .increment(1);
+    histogram!(HTTP_REQUEST_DURATION_SECONDS, "method" => method, "route" => route)
+        .record(duration.as_secs_f64());
+}
+
+/// Records a request refused with 429; `scope` is the bucket that ran out
+pub fn track_rate_limited(group: &'static str, scope: &'static str) {
+    counter!(HTTP_RATE_LIMITED_TOTAL, "group" => group, "scope" => scope).increment(1);
+}
+
+/// Samples the connection pool; called on every scrape of `/metrics`
+pub fn track_db_pool(pool: &PgPool) {
+    let size = pool.size();
+    let idle = pool.num_idle() as u32;
+
+    gauge!(DB_POOL_CONNECTIONS, "state" => "idle").set(idle as f64);
+    gauge!(DB_POOL_CONNECTIONS, "state" => "active").set(size.saturating_sub(idle) as f64);
+    gauge!(DB_POOL_MAX_CONNECTIONS).set(pool.options().get_max_connections() as f64);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheOutcome {
+    Hit,
+    Miss,
+    /// The cache was unreachable or held an undecodable value
+    Error,
+}
+
+impl CacheOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CacheOutcome::Hit => "hit",
+            CacheOutcome::Miss => "miss",
+            CacheOutcome::Error => "error",
+        }
+    }
+}
+
+/// Records a cache lookup; `cache` names the cache tier, e.g. `redis`
+pub fn track_cache_lookup(cache: &'static str, outcome: CacheOutcome) {
+    counter!(CACHE_REQUESTS_TOTAL, "cache" => cache, "result" => outcome.as_str()).increment(1);
+}
+
+/// Records an authentication attempt, e.g. `("login", "success")`
+pub fn track_auth_operation(operation: &'static str, status: &'static str) {
+    counter!(AUTH_OPERATIONS_TOTAL, "operation" => operation, "status" => status).increment(1);
+}
+
+pub fn record_user_registration(role: &str) {
+    counter!(USER_REGISTRATIONS_TOTAL, "role" => role.to_string()).increment(1);
+}
+
+pub fn record_company_registered(business_scale: &str, province: &str) {
+    counter!(
+        COMPANIES_REGISTERED_TOTAL,
+        "business_scale" => business_scale.to_string(),
+        "province" => province.to_string()
     )
-    .unwrap()
-});
-
-// Domain metrics - License operations
-pub static LICENSE_OPERATIONS: Lazy<CounterVec> = Lazy::new(|| {
-    register_counter_vec!(
-        "domain_license_operations_total",
-        "Total number of license domain operations",
-        &["operation", "license_type"]
+    .increment(1);
+}
+
+/// Records a license application moving from draft to submitted
+pub fn record_license_application(license_type: &str) {
+    counter!(LICENSE_APPLICATIONS_TOTAL, "license_type" => license_type.to_string()).increment(1);
+}
+
+/// Records an approval or rejection; `processing_secs` is the time since submission
+pub fn record_license_processed(license_type: &str, status: &str, processing_secs: Option<f64>) {
+    let license_type = license_type.to_string();
+    let status = status.to_string();
+
+    counter!(
+        LICENSE_DECISIONS_TOTAL,
+        "license_type" => license_type.clone(),
+        "status" => status.clone()
     )
-    .unwrap()
-});
-
-// Domain metrics - Company operations
-pub static COMPANY_OPERATIONS: Lazy<CounterVec> = Lazy::new(|| {
-    register_counter_vec!(
-        "domain_company_operations_total",
-        "Total number of company domain operations",
-        &["operation"]
+    .increment(1);
+
+    if let Some(secs) = processing_secs {
+        histogram!(
+            LICENSE_PROCESSING_TIME_SECONDS,
+            "license_type" => license_type,
+            "status" => status
+        )
+        .record(secs);
+    }
+}
+
+/// Records an SLA warning or escalation raised for an application
+pub fn record_license_sla_event(kind: &str, license_type: &str) {
+    counter!(
+        LICENSE_SLA_EVENTS_TOTAL,
+        "kind" => kind.to_string(),
+        "license_type" => license_type.to_string()
     )
-    .unwrap()
-});
-
-// Auth metrics
-pub static AUTH_OPERATIONS: Lazy<CounterVec> = Lazy::new(|| {
-    register_counter_vec!(
-        "domain_auth_operations_total",
-        "Total number of authentication operations",
-        &["operation", "status"]
+    .increment(1);
+}
+
+/// Records how many in-flight applications a sweep found in each SLA state
+pub fn record_license_sla_sweep(on_track: usize, at_risk: usize, breached: usize) {
+    gauge!(LICENSE_SLA_APPLICATIONS, "state" => "on_track").set(on_track as f64);
+    gauge!(LICENSE_SLA_APPLICATIONS, "state" => "at_risk").set(at_risk as f64);
+    gauge!(LICENSE_SLA_APPLICATIONS, "state" => "breached").set(breached as f64);
+}
+
+/// Records how many days of each rollup a refresh recomputed
+pub fn record_analytics_rollup_refresh(licenses: usize, registrations: usize, finance: usize) {
+    counter!(ANALYTICS_ROLLUP_DAYS_TOTAL, "fact" => "licenses").increment(licenses as u64);
+    counter!(ANALYTICS_ROLLUP_DAYS_TOTAL, "fact" => "registrations").increment(registrations as u64);
+    counter!(ANALYTICS_ROLLUP_DAYS_TOTAL, "fact" => "finance").increment(finance as u64);
+}
+
+/// Records a generated license certificate, e.g. `("siup", "renewed")`
+pub fn record_license_certificate(license_type: &str, reason: &str) {
+    counter!(
+        LICENSE_CERTIFICATES_TOTAL,
+        "license_type" => license_type.to_string(),
+        "reason" => reason.to_string()
     )
-    .unwrap()
-});
-
-pub static AUTH_ACTIVE_SESSIONS: Lazy<CounterVec> = Lazy::new(|| {
-    register_counter_vec!(
-        "domain_auth_active_sessions",
-        "Number of currently active user sessions",
-        &[]
+    .increment(1);
+}
+
+/// Records a public verification lookup, e.g. `("nib", true)`
+pub fn record_license_verification(method: &str, found: bool) {
+    counter!(
+        LICENSE_VERIFICATIONS_TOTAL,
+        "method" => method.to_string(),
+        "found" => found.to_string()
     )
-    .unwrap()
-});
+    .increment(1);
+}
 
-// Database metrics
-pub static DB_QUERIES_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
-    register_histogram_vec!(
-        "db_query_duration_seconds",
-        "Database query duration in seconds",
-        &["query_type", "status"]
+pub fn record_notification(event: &str) {
+    counter!(NOTIFICATIONS_TOTAL, "event" => event.to_string()).increment(1);
+}
+
+/// Records a delivery attempt, e.g. `("email", "retried")`
+pub fn record_notification_delivery(channel: &str, outcome: &str) {
+    counter!(
+        NOTIFICATION_DELIVERIES_TOTAL,
+        "channel" => channel.to_string(),
+        "outcome" => outcome.to_string()
     )
-    .unwrap()
-});
-
-pub static DB_CONNECTIONS: Lazy<CounterVec> = Lazy::new(|| {
-    register_counter_vec!(
-        "db_connections_total",
-        "Total number of database connection operations",
-        &["operation"]
+    .increment(1);
+}
+
+pub fn record_live_update(event: &str) {
+    counter!(LIVE_UPDATES_TOTAL, "event" => event.to_string()).increment(1);
+}
+
+/// Tracks open live update streams; call with -1.0 when one closes
+pub fn record_live_update_stream(delta: f64) {
+    gauge!(LIVE_UPDATE_STREAMS).increment(delta);
+}
+
+/// Records a step in an invoice's life, e.g. `("basic", "paid")`
+pub fn record_subscription_invoice(plan: &str, outcome: &str) {
+    counter!(
+        SUBSCRIPTION_INVOICES_TOTAL,
+        "plan" => plan.to_string(),
+        "outcome" => outcome.to_string()
     )
-    .unwrap()
-});
-
-// Helper functions for tracking metrics
-
-pub fn track_http_request(method: &str, endpoint: &str, status_code: &str) {
-    HTTP_REQUESTS_COUNTER
-        .with_label_values(&[method, endpoint, status_code])
-        .inc();
-}
-
-pub fn track_http_request_duration<F, R>(method: &str, endpoint: &str, f: F) -> R
-where
-    F: FnOnce() -> R,
-{
-    let timer = HTTP_REQUEST_DURATION
-        .with_label_values(&[method, endpoint])
-        .start_timer();
-    let result = f();
-    timer.observe();
-    result
-}
-
-pub fn track_license_operation(operation: &str, license_type: &str) {
-    LICENSE_OPERATIONS
-        .with_label_values(&[operation, license_type])
-        .inc();
-}
-
-pub fn track_company_operation(operation: &str) {
-    COMPANY_OPERATIONS.with_label_values(&[operation]).inc();
-}
-
-pub fn track_auth_operation(operation: &str, status: &str) {
-    AUTH_OPERATIONS
-        .with_label_values(&[operation, status])
-        .inc();
-}
-
-pub fn track_db_query<F, R>(query_type: &str, f: F) -> Result<R, String>
-where
-    F: FnOnce() -> Result<R, String>,
-{
-    let timer = DB_QUERIES_DURATION
-        .with_label_values(&[query_type, "in_progress"])
-        .start_timer();
-    
-    match f() {
-        Ok(result) => {
-            drop(timer);
-            DB_QUERIES_DURATION
-                .with_label_values(&[query_type, "success"])
-                .observe(timer.stop_and_record());
-            Ok(result)
-        }
-        Err(e) => {
-            drop(timer);
-            DB_QUERIES_DURATION
-                .with_label_values(&[query_type, "error"])
-                .observe(timer.stop_and_record());
-            Err(e)
-        }
-    }
+    .increment(1);
+}
+
+/// Records a request refused by a plan limit, e.g. `("free", "license")`
+pub fn record_plan_limit_rejection(plan: &str, limit: &'static str) {
+    counter!(PLAN_LIMIT_REJECTIONS_TOTAL, "plan" => plan.to_string(), "limit" => limit).increment(1);
+}
+
+/// Records a step in a customer invoice's life, e.g. `"paid"`
+pub fn record_sales_invoice(event: &'static str, count: u64) {
+    counter!(SALES_INVOICES_TOTAL, "event" => event).increment(count);
+}
+
+pub fn record_document_upload(document_type: &str, size_bytes: u64) {
+    let document_type = document_type.to_string();
+
+    counter!(DOCUMENT_UPLOADS_TOTAL, "document_type" => document_type.clone()).increment(1);
+    counter!(DOCUMENT_UPLOAD_BYTES, "document_type" => document_type).increment(size_bytes);
 }
@@ -1,5 +1,7 @@
 mod metrics;
+mod otel;
 mod telemetry;
 
 pub use metrics::*;
+pub use otel::*;
 pub use telemetry::*;
@@ -0,0 +1,129 @@
+// Tracing subscriber setup with OpenTelemetry
+// Spans are always tracked by OpenTelemetry so every request gets a W3C trace
+// id; they are exported over OTLP/HTTP only when a collector is configured.
+
+use opentelemetry::{
+    global,
+    propagation::{Extractor, Injector, TextMapPropagator},
+    trace::{TraceContextExt, TracerProvider as _},
+    Context, KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    runtime,
+    trace::{self as sdktrace, Sampler},
+    Resource,
+};
+use std::collections::HashMap;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{filter::LevelFilter, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+use crate::config::TracingConfig;
+
+/// Flushes buffered spans to the collector when dropped; keep it alive for the
+/// lifetime of the process
+#[must_use = "spans are flushed when the guard is dropped"]
+pub struct TracingGuard;
+
+impl Drop for TracingGuard {
+    fn drop(&mut self) {
+        global::shutdown_tracer_provider();
+    }
+}
+
+fn trace_config(config: &TracingConfig) -> sdktrace::Config {
+    sdktrace::config()
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio,
+        ))))
+        .with_resource(Resource::new(vec![
+            KeyValue::new("service.name", config.service_name.clone()),
+            KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+        ]))
+}
+
+/// Installs the global subscriber: JSON logs filtered by `RUST_LOG`, plus an
+/// OpenTelemetry layer for INFO and above. Must be called inside the Tokio runtime.
+pub fn init_tracing(config: &TracingConfig) -> Result<TracingGuard, Box<dyn std::error::Error>> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let tracer = match &config.otlp_endpoint {
+        Some(endpoint) => opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(
+                opentelemetry_otlp::new_exporter()
+                    .http()
+                    .with_endpoint(endpoint.clone()),
+            )
+            .with_trace_config(trace_config(config))
+            .install_batch(runtime::Tokio)?,
+        None => {
+            let provider = sdktrace::TracerProvider::builder()
+                .with_config(trace_config(config))
+                .build();
+            let tracer = provider.tracer(config.service_name.clone());
+            global::set_tracer_provider(provider);
+            tracer
+        }
+    };
+
+    let fmt_layer = tracing_subscriber::fmt::layer()
+        .with_target(false)
+        .json()
+        .with_filter(EnvFilter::from_default_env());
+    let otel_layer = tracing_opentelemetry::layer()
+        .with_tracer(tracer)
+        .with_filter(LevelFilter::INFO);
+
+    tracing_subscriber::registry()
+        .with(fmt_layer)
+        .with(otel_layer)
+        .try_init()?;
+
+    Ok(TracingGuard)
+}
+
+/// Trace id of a span as 32 lowercase hex characters, if it has a valid one
+pub fn trace_id(span: &tracing::Span) -> Option<String> {
+    let context = span.context();
+    let span_context = context.span().span_context().clone();
+    span_context
+        .is_valid()
+        .then(|| span_context.trace_id().to_string())
+}
+
+struct HeaderExtractor<'a>(&'a axum::http::HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+/// Makes the caller's `traceparent`, if any, the parent of `span`
+pub fn continue_trace(span: &tracing::Span, headers: &axum::http::HeaderMap) {
+    let parent: Context = TraceContextPropagator::new().extract(&HeaderExtractor(headers));
+    if parent.span().span_context().is_valid() {
+        span.set_parent(parent);
+    }
+}
+
+struct MapInjector<'a>(&'a mut HashMap<String, String>);
+
+impl Injector for MapInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        self.0.insert(key.to_string(), value);
+    }
+}
+
+/// W3C `traceparent`/`tracestate` headers for calls made within `span`
+pub fn trace_context_headers(span: &tracing::Span) -> HashMap<String, String> {
+    let mut headers = HashMap::new();
+    TraceContextPropagator::new().inject_context(&span.context(), &mut MapInjector(&mut headers));
+    headers
+}
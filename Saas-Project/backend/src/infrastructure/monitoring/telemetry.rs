@@ -1,39 +1,59 @@
 use axum::{
     extract::State,
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
 };
-use prometheus::{Encoder, TextEncoder};
-use std::sync::Arc;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use std::sync::OnceLock;
 
-// Initialize telemetry and return prometheus registry
-pub fn init_telemetry() -> prometheus::Registry {
-    // Create a new registry
-    let registry = prometheus::Registry::new();
+use super::metrics::{
+    describe_metrics, track_db_pool, HTTP_DURATION_BUCKETS, HTTP_REQUEST_DURATION_SECONDS,
+    LICENSE_PROCESSING_BUCKETS, LICENSE_PROCESSING_TIME_SECONDS,
+};
+use crate::infrastructure::web::handlers::AppState;
 
-    // Register default collectors
-    prometheus::default_registry()
-        .register(Box::new(
-            prometheus::process_collector::ProcessCollector::for_self(),
-        ))
-        .expect("Failed to register process collector");
+static PROMETHEUS: OnceLock<PrometheusHandle> = OnceLock::new();
 
-    registry
-}
+// Install the global Prometheus recorder and return the handle that renders it.
+// Safe to call more than once; only the first call installs the recorder.
+pub fn init_telemetry() -> PrometheusHandle {
+    PROMETHEUS
+        .get_or_init(|| {
+            let builder = PrometheusBuilder::new()
+                .set_buckets_for_metric(
+                    Matcher::Full(HTTP_REQUEST_DURATION_SECONDS.to_string()),
+                    HTTP_DURATION_BUCKETS,
+                )
+                .and_then(|b| {
+                    b.set_buckets_for_metric(
+                        Matcher::Full(LICENSE_PROCESSING_TIME_SECONDS.to_string()),
+                        LICENSE_PROCESSING_BUCKETS,
+                    )
+                })
+                .expect("Histogram buckets are not empty");
 
-// Handler for metrics endpoint
-pub async fn metrics_handler(
-    State(registry): State<Arc<prometheus::Registry>>,
-) -> impl IntoResponse {
-    let encoder = TextEncoder::new();
-    let metric_families = prometheus::gather();
+            let recorder = builder.build_recorder();
+            let handle = recorder.handle();
+            if let Err(err) = metrics::set_global_recorder(recorder) {
+                tracing::warn!("Metrics recorder already installed: {}", err);
+            }
+            describe_metrics();
+
+            handle
+        })
+        .clone()
+}
 
-    let mut buffer = vec![];
-    encoder.encode(&metric_families, &mut buffer).unwrap();
+// Handler for metrics endpoint, in the Prometheus text format
+pub async fn metrics_handler(State(state): State<AppState>) -> Response {
+    if let Some(db) = state.database() {
+        track_db_pool(db.pool());
+    }
 
-    Response::builder()
-        .status(StatusCode::OK)
-        .header("Content-Type", encoder.format_type())
-        .body(buffer)
-        .unwrap()
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        init_telemetry().render(),
+    )
+        .into_response()
 }
@@ -0,0 +1,271 @@
+// Token bucket stores
+// Redis holds the buckets shared by every instance; the update runs as one Lua
+// script on the Redis clock, so concurrent requests on different instances
+// cannot both take the last token. When Redis is unreachable each instance
+// falls back to its own in-memory buckets until it comes back.
+
+use redis::aio::ConnectionManager;
+use redis::{Client, RedisError, Script};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+use tracing::{info, warn};
+
+use crate::config::RatePolicy;
+
+/// Longest a request waits for Redis before the in-memory buckets are used
+const REDIS_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// How often idle in-memory buckets are dropped
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Refills the bucket for the time since the last request and takes one token.
+/// Returns whether a token was taken and the tokens left. Mirrored by
+/// `BucketState::take` below; the two must stay in sync.
+const TAKE_SCRIPT: &str = r#"
+local limit = tonumber(ARGV[1])
+local window_ms = tonumber(ARGV[2])
+local interval = window_ms / limit
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+
+local state = redis.call('HMGET', KEYS[1], 'tokens', 'updated_at')
+local tokens = tonumber(state[1]) or limit
+local updated_at = tonumber(state[2]) or now
+tokens = math.min(limit, tokens + math.max(0, now - updated_at) / interval)
+
+local allowed = 0
+if tokens >= 1 then
+    tokens = tokens - 1
+    allowed = 1
+end
+
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'updated_at', now)
+redis.call('PEXPIRE', KEYS[1], window_ms)
+return {allowed, tostring(tokens)}
+"#;
+
+/// Outcome of taking a token from one bucket
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decision {
+    pub allowed: bool,
+    pub policy: RatePolicy,
+    /// Whole requests left before the bucket is empty
+    pub remaining: u32,
+    /// Until the bucket is full again
+    pub reset_after: Duration,
+    /// Until the next token, when the request was refused
+    pub retry_after: Option<Duration>,
+}
+
+impl Decision {
+    fn new(policy: RatePolicy, allowed: bool, tokens: f64) -> Self {
+        let interval = refill_interval(policy);
+        let tokens = tokens.clamp(0.0, policy.limit as f64);
+
+        Self {
+            allowed,
+            policy,
+            remaining: tokens.floor() as u32,
+            reset_after: interval.mul_f64(policy.limit as f64 - tokens),
+            retry_after: (!allowed).then(|| interval.mul_f64(1.0 - tokens)),
+        }
+    }
+}
+
+/// Time to refill one token
+fn refill_interval(policy: RatePolicy) -> Duration {
+    Duration::from_secs(policy.window_secs) / policy.limit
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BucketState {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl BucketState {
+    fn take(&mut self, policy: RatePolicy, now: Instant) -> Decision {
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        let refilled = elapsed.as_secs_f64() / refill_interval(policy).as_secs_f64();
+        self.tokens = (self.tokens + refilled).min(policy.limit as f64);
+        self.updated_at = now;
+
+        let allowed = self.tokens >= 1.0;
+        if allowed {
+            self.tokens -= 1.0;
+        }
+        Decision::new(policy, allowed, self.tokens)
+    }
+}
+
+#[derive(Default)]
+struct Buckets {
+    states: HashMap<String, (BucketState, RatePolicy)>,
+    last_prune: Option<Instant>,
+}
+
+#[derive(Default)]
+pub struct InMemoryBuckets {
+    buckets: Mutex<Buckets>,
+}
+
+impl InMemoryBuckets {
+    pub fn take(&self, key: &str, policy: RatePolicy) -> Decision {
+        self.take_at(key, policy, Instant::now())
+    }
+
+    fn take_at(&self, key: &str, policy: RatePolicy, now: Instant) -> Decision {
+        let mut buckets = self.buckets.lock().expect("rate limit buckets poisoned");
+        buckets.prune(now);
+
+        let (state, stored_policy) = buckets.states.entry(key.to_string()).or_insert((
+            BucketState {
+                tokens: policy.limit as f64,
+                updated_at: now,
+            },
+            policy,
+        ));
+        *stored_policy = policy;
+        state.take(policy, now)
+    }
+}
+
+impl Buckets {
+    /// Drops buckets idle for longer than their window; they would be full anyway
+    fn prune(&mut self, now: Instant) {
+        let last_prune = *self.last_prune.get_or_insert(now);
+        if now.saturating_duration_since(last_prune) < PRUNE_INTERVAL {
+            return;
+        }
+        self.last_prune = Some(now);
+
+        self.states.retain(|_, (state, policy)| {
+            now.saturating_duration_since(state.updated_at) < Duration::from_secs(policy.window_secs)
+        });
+    }
+}
+
+/// Redis buckets with the in-memory fallback
+pub struct BucketStore {
+    redis: Option<RedisBuckets>,
+    memory: InMemoryBuckets,
+    /// Set while requests are served from memory, so the switch is logged once
+    degraded: AtomicBool,
+}
+
+impl BucketStore {
+    pub fn new(redis: Option<Client>) -> Self {
+        Self {
+            redis: redis.map(RedisBuckets::new),
+            memory: InMemoryBuckets::default(),
+            degraded: AtomicBool::new(false),
+        }
+    }
+
+    pub async fn take(&self, key: &str, policy: RatePolicy) -> Decision {
+        if let Some(redis) = &self.redis {
+            let error = match tokio::time::timeout(REDIS_TIMEOUT, redis.take(key, policy)).await {
+                Ok(Ok(decision)) => {
+                    if self.degraded.swap(false, Ordering::Relaxed) {
+                        info!("✅ Rate limiting is using Redis again");
+                    }
+                    return decision;
+                }
+                Ok(Err(err)) => err.to_string(),
+                Err(_) => format!("no reply within {}ms", REDIS_TIMEOUT.as_millis()),
+            };
+            if !self.degraded.swap(true, Ordering::Relaxed) {
+                warn!("⚠️ Redis rate limiting unavailable ({}), using per-instance limits", error);
+            }
+        }
+
+        self.memory.take(key, policy)
+    }
+}
+
+struct RedisBuckets {
+    client: Client,
+    connection: OnceCell<ConnectionManager>,
+    script: Script,
+}
+
+impl RedisBuckets {
+    fn new(client: Client) -> Self {
+        Self {
+            client,
+            connection: OnceCell::new(),
+            script: Script::new(TAKE_SCRIPT),
+        }
+    }
+
+    async fn take(&self, key: &str, policy: RatePolicy) -> Result<Decision, RedisError> {
+        // Connected on first use and retried until it succeeds, so the server
+        // starts even while Redis is down
+        let mut connection = self
+            .connection
+            .get_or_try_init(|| ConnectionManager::new(self.client.clone()))
+            .await?
+            .clone();
+
+        let (allowed, tokens): (i64, String) = self
+            .script
+            .key(key)
+            .arg(policy.limit)
+            .arg(policy.window_secs * 1000)
+            .invoke_async(&mut connection)
+            .await?;
+
+        let tokens = tokens.parse::<f64>().unwrap_or(0.0);
+        Ok(Decision::new(policy, allowed == 1, tokens))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_allows_a_burst_then_refills_over_the_window() {
+        let buckets = InMemoryBuckets::default();
+        let policy = RatePolicy::new(3, 60);
+        let start = Instant::now();
+
+        for remaining in [2, 1, 0] {
+            let decision = buckets.take_at("ip:10.0.0.1", policy, start);
+            assert!(decision.allowed);
+            assert_eq!(decision.remaining, remaining);
+        }
+
+        let refused = buckets.take_at("ip:10.0.0.1", policy, start);
+        assert!(!refused.allowed);
+        assert_eq!(refused.retry_after, Some(Duration::from_secs(20)));
+        assert_eq!(refused.reset_after, Duration::from_secs(60));
+
+        // Other clients have their own bucket
+        assert!(buckets.take_at("ip:10.0.0.2", policy, start).allowed);
+
+        // One token comes back every 20 seconds
+        let later = start + Duration::from_secs(20);
+        let decision = buckets.take_at("ip:10.0.0.1", policy, later);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        assert!(!buckets.take_at("ip:10.0.0.1", policy, later).allowed);
+    }
+
+    #[test]
+    fn test_idle_buckets_are_pruned() {
+        let buckets = InMemoryBuckets::default();
+        let policy = RatePolicy::new(5, 60);
+        let start = Instant::now();
+
+        buckets.take_at("user:a", policy, start);
+        buckets.take_at("user:b", policy, start + Duration::from_secs(59));
+        buckets.take_at("user:b", policy, start + Duration::from_secs(61));
+
+        let keys: Vec<String> = buckets.buckets.lock().unwrap().states.keys().cloned().collect();
+        assert_eq!(keys, vec!["user:b".to_string()]);
+    }
+}
@@ -0,0 +1,274 @@
+// Rate limiting with token buckets
+// Every request takes a token from the bucket of its route group, keyed by the
+// authenticated user or else the client IP. Requests of authenticated users
+// also take one from their company's bucket, sized by its subscription plan,
+// so a company cannot multiply its quota by adding users.
+
+mod bucket;
+mod plans;
+
+pub use bucket::{BucketStore, Decision};
+pub use plans::{CompanyPlanResolver, PlanResolver, PostgresPlanResolver};
+
+use axum::http::Method;
+use std::net::IpAddr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::config::{RateLimitConfig, RatePolicy};
+use crate::domain::companies::SubscriptionPlan;
+
+/// Routes sharing one policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteGroup {
+    /// Credential endpoints, limited per IP against guessing and sign-up abuse
+    Login,
+    Upload,
+    /// Public license verification, limited per IP against scraping
+    Verify,
+    Api,
+}
+
+impl RouteGroup {
+    /// Group of a request by route template (or path); `None` for probes and
+    /// scrapes, which are never limited
+    pub fn classify(method: &Method, path: &str) -> Option<Self> {
+        if matches!(path, "/livez" | "/readyz" | "/health" | "/metrics") {
+            return None;
+        }
+        if path.ends_with("/verify") || path.ends_with("/verify/:token") {
+            return Some(RouteGroup::Verify);
+        }
+        if *method != Method::POST {
+            return Some(RouteGroup::Api);
+        }
+
+        let login = [
+            "/auth/login",
+            "/auth/register",
+            "/auth/reset-password",
+            "/auth/reset-password/confirm",
+        ];
+        let upload = ["/documents", "/imports/preview"];
+        if login.iter().any(|suffix| path.ends_with(suffix)) {
+            Some(RouteGroup::Login)
+        } else if upload.iter().any(|suffix| path.ends_with(suffix)) {
+            Some(RouteGroup::Upload)
+        } else {
+            Some(RouteGroup::Api)
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RouteGroup::Login => "login",
+            RouteGroup::Upload => "upload",
+            RouteGroup::Verify => "verify",
+            RouteGroup::Api => "api",
+        }
+    }
+}
+
+/// The bucket that decided a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitScope {
+    Ip,
+    User,
+    /// The company's subscription plan quota
+    Plan,
+}
+
+impl LimitScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LimitScope::Ip => "ip",
+            LimitScope::User => "user",
+            LimitScope::Plan => "plan",
+        }
+    }
+}
+
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    store: BucketStore,
+    plans: Arc<dyn PlanResolver>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig, store: BucketStore, plans: Arc<dyn PlanResolver>) -> Self {
+        Self { config, store, plans }
+    }
+
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    pub fn policy(&self, group: RouteGroup) -> RatePolicy {
+        match group {
+            RouteGroup::Login => self.config.login_policy,
+            RouteGroup::Upload => self.config.upload_policy,
+            RouteGroup::Verify => self.config.verify_policy,
+            RouteGroup::Api => self.config.default_policy,
+        }
+    }
+
+    pub fn plan_quota(&self, plan: SubscriptionPlan) -> RatePolicy {
+        match plan {
+            SubscriptionPlan::Free => self.config.free_plan,
+            SubscriptionPlan::Basic => self.config.basic_plan,
+            SubscriptionPlan::Pro => self.config.pro_plan,
+        }
+    }
+
+    /// Takes a token from every bucket the request counts against and returns
+    /// the binding decision: the refusal with the longest wait, or else the
+    /// bucket closest to empty
+    pub async fn check(
+        &self,
+        group: RouteGroup,
+        ip: Option<IpAddr>,
+        user_id: Option<Uuid>,
+    ) -> (LimitScope, Decision) {
+        // Credential and public endpoints are limited per IP even when a
+        // token is sent
+        let user_id = user_id.filter(|_| !matches!(group, RouteGroup::Login | RouteGroup::Verify));
+
+        let (scope, key) = match user_id {
+            Some(user_id) => (
+                LimitScope::User,
+                format!("ratelimit:{}:user:{}", group.as_str(), user_id),
+            ),
+            None => (
+                LimitScope::Ip,
+                format!(
+                    "ratelimit:{}:ip:{}",
+                    group.as_str(),
+                    ip.map(|ip| ip.to_string()).unwrap_or_else(|| "unknown".to_string())
+                ),
+            ),
+        };
+        let mut decisions = vec![(scope, self.store.take(&key, self.policy(group)).await)];
+
+        if let Some(user_id) = user_id {
+            if let Some((company_id, plan)) = self.plans.resolve(user_id).await {
+                let key = format!("ratelimit:plan:company:{}", company_id);
+                decisions.push((LimitScope::Plan, self.store.take(&key, self.plan_quota(plan)).await));
+            }
+        }
+
+        binding_decision(decisions)
+    }
+}
+
+fn binding_decision(decisions: Vec<(LimitScope, Decision)>) -> (LimitScope, Decision) {
+    let refused = decisions
+        .iter()
+        .filter(|(_, decision)| !decision.allowed)
+        .max_by_key(|(_, decision)| decision.retry_after)
+        .copied();
+
+    refused.unwrap_or_else(|| {
+        decisions
+            .into_iter()
+            .min_by_key(|(_, decision)| decision.remaining)
+            .expect("at least one bucket is checked")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    struct FixedPlan(Uuid, SubscriptionPlan);
+
+    #[async_trait::async_trait]
+    impl PlanResolver for FixedPlan {
+        async fn resolve(&self, _user_id: Uuid) -> Option<(Uuid, SubscriptionPlan)> {
+            Some((self.0, self.1))
+        }
+    }
+
+    fn limiter_for(plan: SubscriptionPlan) -> RateLimiter {
+        let config = RateLimitConfig {
+            enabled: true,
+            trust_forwarded_for: false,
+            trusted_proxies: Vec::new(),
+            default_policy: RatePolicy::new(5, 60),
+            login_policy: RatePolicy::new(2, 60),
+            upload_policy: RatePolicy::new(2, 60),
+            verify_policy: RatePolicy::new(2, 60),
+            free_plan: RatePolicy::new(3, 60),
+            basic_plan: RatePolicy::new(10, 60),
+            pro_plan: RatePolicy::new(100, 60),
+        };
+        RateLimiter::new(config, BucketStore::new(None), Arc::new(FixedPlan(Uuid::new_v4(), plan)))
+    }
+
+    #[test]
+    fn test_company_plan_quota_is_shared_by_its_users() {
+        let limiter = limiter_for(SubscriptionPlan::Free);
+        let (alice, bob) = (Some(Uuid::new_v4()), Some(Uuid::new_v4()));
+
+        // Each user is well within the per-user policy, but the free plan
+        // allows the company three requests
+        for user in [alice, bob, alice] {
+            let (_, decision) = block_on(limiter.check(RouteGroup::Api, None, user));
+            assert!(decision.allowed);
+        }
+        let (scope, decision) = block_on(limiter.check(RouteGroup::Api, None, bob));
+        assert_eq!(scope, LimitScope::Plan);
+        assert!(!decision.allowed);
+        assert_eq!(decision.policy, RatePolicy::new(3, 60));
+
+        // A bigger plan leaves the per-user policy binding
+        let limiter = limiter_for(SubscriptionPlan::Pro);
+        let (scope, decision) = block_on(limiter.check(RouteGroup::Api, None, alice));
+        assert_eq!(scope, LimitScope::User);
+        assert_eq!(decision.remaining, 4);
+    }
+
+    #[test]
+    fn test_login_counts_against_the_ip_even_with_a_token() {
+        let limiter = limiter_for(SubscriptionPlan::Pro);
+        let ip = Some("203.0.113.7".parse().unwrap());
+
+        let (scope, _) = block_on(limiter.check(RouteGroup::Login, ip, Some(Uuid::new_v4())));
+        assert_eq!(scope, LimitScope::Ip);
+        block_on(limiter.check(RouteGroup::Login, ip, Some(Uuid::new_v4())));
+        let (_, decision) = block_on(limiter.check(RouteGroup::Login, ip, None));
+        assert!(!decision.allowed);
+    }
+
+    #[test]
+    fn test_route_groups() {
+        let post = Method::POST;
+        let get = Method::GET;
+
+        assert_eq!(RouteGroup::classify(&post, "/api/v1/auth/login"), Some(RouteGroup::Login));
+        assert_eq!(
+            RouteGroup::classify(&post, "/api/v1/auth/reset-password"),
+            Some(RouteGroup::Login)
+        );
+        assert_eq!(
+            RouteGroup::classify(&post, "/api/v1/auth/reset-password/confirm"),
+            Some(RouteGroup::Login)
+        );
+        assert_eq!(
+            RouteGroup::classify(&post, "/api/v1/licenses/:id/documents"),
+            Some(RouteGroup::Upload)
+        );
+        assert_eq!(
+            RouteGroup::classify(&get, "/api/v1/licenses/:id/documents"),
+            Some(RouteGroup::Api)
+        );
+        assert_eq!(RouteGroup::classify(&get, "/api/v1/verify"), Some(RouteGroup::Verify));
+        assert_eq!(
+            RouteGroup::classify(&get, "/api/v1/verify/:token"),
+            Some(RouteGroup::Verify)
+        );
+        assert_eq!(RouteGroup::classify(&post, "/api/v1/auth/refresh"), Some(RouteGroup::Api));
+        assert_eq!(RouteGroup::classify(&get, "/readyz"), None);
+        assert_eq!(RouteGroup::classify(&get, "/metrics"), None);
+    }
+}
@@ -0,0 +1,123 @@
+// Subscription plan lookup for API quotas
+// A user draws from the quota of the company they own. The lookup runs on
+// every authenticated request, so answers are kept in process for a minute;
+// plan changes take effect within that time.
+
+use async_trait::async_trait;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::domain::companies::SubscriptionPlan;
+use crate::domain::repositories::CompanyRepository;
+use crate::services::billing::BillingService;
+
+const PLAN_CACHE_TTL: Duration = Duration::from_secs(60);
+/// Expired entries are dropped once the cache holds this many users
+const PLAN_CACHE_CAPACITY: usize = 10_000;
+
+#[async_trait]
+pub trait PlanResolver: Send + Sync {
+    /// Company whose quota `user_id` draws from, with its plan; `None` when
+    /// the user has no company yet
+    async fn resolve(&self, user_id: Uuid) -> Option<(Uuid, SubscriptionPlan)>;
+}
+
+type CachedPlan = (Instant, Option<(Uuid, SubscriptionPlan)>);
+
+pub struct PostgresPlanResolver {
+    pool: PgPool,
+    cache: Mutex<HashMap<Uuid, CachedPlan>>,
+}
+
+impl PostgresPlanResolver {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn cached(&self, user_id: Uuid) -> Option<Option<(Uuid, SubscriptionPlan)>> {
+        let cache = self.cache.lock().expect("plan cache poisoned");
+        cache
+            .get(&user_id)
+            .filter(|(cached_at, _)| cached_at.elapsed() < PLAN_CACHE_TTL)
+            .map(|(_, plan)| *plan)
+    }
+
+    fn store(&self, user_id: Uuid, plan: Option<(Uuid, SubscriptionPlan)>) {
+        let mut cache = self.cache.lock().expect("plan cache poisoned");
+        if cache.len() >= PLAN_CACHE_CAPACITY {
+            cache.retain(|_, (cached_at, _)| cached_at.elapsed() < PLAN_CACHE_TTL);
+        }
+        cache.insert(user_id, (Instant::now(), plan));
+    }
+}
+
+#[async_trait]
+impl PlanResolver for PostgresPlanResolver {
+    async fn resolve(&self, user_id: Uuid) -> Option<(Uuid, SubscriptionPlan)> {
+        if let Some(plan) = self.cached(user_id) {
+            return plan;
+        }
+
+        let row: Option<(Uuid, String)> = match sqlx::query_as(
+            "SELECT id, subscription_plan FROM companies WHERE owner_id = $1 ORDER BY created_at LIMIT 1",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        {
+            Ok(row) => row,
+            Err(err) => {
+                // Not cached, so the plan quota applies again once the database answers
+                warn!("Failed to look up subscription plan: {}", err);
+                return None;
+            }
+        };
+
+        let plan = row.map(|(company_id, plan)| (company_id, plan.parse().unwrap_or_default()));
+        self.store(user_id, plan);
+        plan
+    }
+}
+
+/// Resolves the company through a `CompanyRepository`, for demo mode where
+/// there is no database to query. The company entity does not carry its plan,
+/// so it is read from the company's subscription.
+pub struct CompanyPlanResolver {
+    companies: Arc<dyn CompanyRepository + Send + Sync>,
+    billing: Arc<BillingService>,
+}
+
+impl CompanyPlanResolver {
+    pub fn new(companies: Arc<dyn CompanyRepository + Send + Sync>, billing: Arc<BillingService>) -> Self {
+        Self { companies, billing }
+    }
+}
+
+#[async_trait]
+impl PlanResolver for CompanyPlanResolver {
+    async fn resolve(&self, user_id: Uuid) -> Option<(Uuid, SubscriptionPlan)> {
+        let companies = match self.companies.find_by_owner_id(&user_id).await {
+            Ok(companies) => companies,
+            Err(err) => {
+                warn!("Failed to look up subscription plan: {}", err);
+                return None;
+            }
+        };
+
+        let company_id = companies.iter().min_by_key(|c| c.created_at)?.id;
+        match self.billing.overview(company_id).await {
+            Ok(overview) => Some((company_id, overview.plan)),
+            Err(err) => {
+                warn!("Failed to look up subscription plan: {}", err);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,395 @@
+// PostgreSQL implementation of the admin dashboard statistics
+// Decisions are counted by approved_at and rejected_at, so a license approved
+// in the range and suspended since still counts as approved. The reviewer of
+// an open application is whoever recorded the move to its current status.
+
+use async_trait::async_trait;
+use sqlx::{PgPool, Row};
+
+use crate::domain::admin::{
+    AdminStatsRepository, PendingWork, ProcessingPerformance, RegionRegistrations, ReportRange,
+    ReviewerWorkload, ServiceFeeRevenue,
+};
+use crate::shared::errors::AppResult;
+
+#[derive(Clone)]
+pub struct PostgresAdminStatsRepository {
+    pool: PgPool,
+}
+
+impl PostgresAdminStatsRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl AdminStatsRepository for PostgresAdminStatsRepository {
+    async fn pending_work(&self) -> AppResult<PendingWork> {
+        let row = sqlx::query(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE application_status = 'submitted') AS submitted,
+                COUNT(*) FILTER (WHERE application_status = 'processing') AS processing,
+                COUNT(*) FILTER (WHERE application_status = 'pendingdocuments') AS pending_documents,
+                MIN(submitted_at) AS oldest_submitted_at,
+                (
+                    SELECT COUNT(*) FROM license_documents d
+                    JOIN licenses dl ON dl.id = d.license_id
+                    WHERE NOT d.is_verified
+                      AND dl.application_status IN ('submitted', 'processing')
+                ) AS unverified_documents
+            FROM licenses
+            WHERE application_status IN ('submitted', 'processing', 'pendingdocuments')
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(PendingWork {
+            submitted: row.try_get("submitted")?,
+            processing: row.try_get("processing")?,
+            pending_documents: row.try_get("pending_documents")?,
+            unverified_documents: row.try_get("unverified_documents")?,
+            oldest_submitted_at: row.try_get("oldest_submitted_at")?,
+        })
+    }
+
+    async fn throughput(&self, range: &ReportRange) -> AppResult<Vec<ProcessingPerformance>> {
+        let rows = sqlx::query(
+            r#"
+            WITH submitted AS (
+                SELECT license_type, COUNT(*) AS submitted_count
+                FROM licenses
+                WHERE submitted_at >= $1 AND submitted_at < $2
+                GROUP BY license_type
+            ), decisions AS (
+                SELECT license_type, TRUE AS approved, approved_at AS decided_at, submitted_at
+                FROM licenses WHERE approved_at >= $1 AND approved_at < $2
+                UNION ALL
+                SELECT license_type, FALSE, rejected_at, submitted_at
+                FROM licenses WHERE rejected_at >= $1 AND rejected_at < $2
+            ), decided AS (
+                SELECT
+                    license_type,
+                    COUNT(*) FILTER (WHERE approved) AS approved_count,
+                    COUNT(*) FILTER (WHERE NOT approved) AS rejected_count,
+                    AVG(EXTRACT(EPOCH FROM decided_at - submitted_at) / 86400)::FLOAT8 AS avg_days,
+                    FLOOR(MIN(EXTRACT(EPOCH FROM decided_at - submitted_at)) / 86400)::INT4 AS min_days,
+                    FLOOR(MAX(EXTRACT(EPOCH FROM decided_at - submitted_at)) / 86400)::INT4 AS max_days
+                FROM decisions
+                GROUP BY license_type
+            )
+            SELECT
+                COALESCE(s.license_type, d.license_type) AS license_type,
+                COALESCE(s.submitted_count, 0) AS submitted_count,
+                COALESCE(d.approved_count, 0) AS approved_count,
+                COALESCE(d.rejected_count, 0) AS rejected_count,
+                d.avg_days, d.min_days, d.max_days
+            FROM submitted s
+            FULL OUTER JOIN decided d ON d.license_type = s.license_type
+            ORDER BY COALESCE(s.license_type, d.license_type)::TEXT
+            "#,
+        )
+        .bind(range.from)
+        .bind(range.to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                let approved_count: i64 = row.try_get("approved_count")?;
+                let rejected_count: i64 = row.try_get("rejected_count")?;
+                Ok(ProcessingPerformance {
+                    license_type: row.try_get("license_type")?,
+                    submitted_count: row.try_get("submitted_count")?,
+                    total_processed: approved_count + rejected_count,
+                    approved_count,
+                    rejected_count,
+                    approval_rate: ProcessingPerformance::approval_rate(approved_count, rejected_count),
+                    avg_processing_days: row.try_get("avg_days")?,
+                    min_processing_days: row.try_get("min_days")?,
+                    max_processing_days: row.try_get("max_days")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn reviewer_workload(&self, range: &ReportRange) -> AppResult<Vec<ReviewerWorkload>> {
+        let rows = sqlx::query(
+            r#"
+            WITH open_work AS (
+                SELECT h.changed_by AS reviewer_id, COUNT(*) AS open_applications
+                FROM licenses l
+                JOIN LATERAL (
+                    SELECT changed_by FROM application_status_history
+                    WHERE license_id = l.id AND to_status = l.application_status
+                    ORDER BY changed_at DESC
+                    LIMIT 1
+                ) h ON TRUE
+                WHERE l.application_status IN ('processing', 'pendingdocuments')
+                GROUP BY h.changed_by
+            ), activity AS (
+                SELECT
+                    changed_by AS reviewer_id,
+                    COUNT(*) AS status_changes,
+                    COUNT(*) FILTER (WHERE to_status = 'approved') AS approved,
+                    COUNT(*) FILTER (WHERE to_status = 'rejected') AS rejected
+                FROM application_status_history
+                WHERE changed_at >= $1 AND changed_at < $2
+                GROUP BY changed_by
+            )
+            SELECT
+                u.id AS reviewer_id,
+                u.full_name,
+                COALESCE(o.open_applications, 0) AS open_applications,
+                COALESCE(a.status_changes, 0) AS status_changes,
+                COALESCE(a.approved, 0) AS approved,
+                COALESCE(a.rejected, 0) AS rejected
+            FROM users u
+            LEFT JOIN open_work o ON o.reviewer_id = u.id
+            LEFT JOIN activity a ON a.reviewer_id = u.id
+            WHERE u.role IN ('admin_staff', 'super_admin')
+              AND (o.reviewer_id IS NOT NULL OR a.reviewer_id IS NOT NULL)
+            ORDER BY open_applications DESC, status_changes DESC, u.id
+            "#,
+        )
+        .bind(range.from)
+        .bind(range.to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(ReviewerWorkload {
+                    reviewer_id: row.try_get("reviewer_id")?,
+                    full_name: row.try_get("full_name")?,
+                    open_applications: row.try_get("open_applications")?,
+                    status_changes: row.try_get("status_changes")?,
+                    approved: row.try_get("approved")?,
+                    rejected: row.try_get("rejected")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn registrations_by_province(&self, range: &ReportRange) -> AppResult<Vec<RegionRegistrations>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT address_province AS province, COUNT(*) AS companies
+            FROM companies
+            WHERE created_at >= $1 AND created_at < $2
+            GROUP BY address_province
+            ORDER BY companies DESC, province
+            "#,
+        )
+        .bind(range.from)
+        .bind(range.to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(RegionRegistrations {
+                    province: row.try_get("province")?,
+                    companies: row.try_get("companies")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn service_fee_revenue(&self, range: &ReportRange) -> AppResult<Vec<ServiceFeeRevenue>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                license_type,
+                COUNT(*) AS approved_licenses,
+                COALESCE(SUM(service_fee), 0)::INT8 AS service_fee_total
+            FROM licenses
+            WHERE approved_at >= $1 AND approved_at < $2
+            GROUP BY license_type
+            ORDER BY license_type::TEXT
+            "#,
+        )
+        .bind(range.from)
+        .bind(range.to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(ServiceFeeRevenue {
+                    license_type: row.try_get("license_type")?,
+                    approved_licenses: row.try_get("approved_licenses")?,
+                    service_fee_total: row.try_get("service_fee_total")?,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::*;
+    use crate::domain::admin::operations_report;
+    use crate::domain::licenses::{ApplicationStatus, ApplicationStatusHistory, License, LicenseType};
+    use crate::infrastructure::repositories::testing::{in_memory_directory, Fixture, TestDatabase};
+    use crate::infrastructure::repositories::{
+        InMemoryAdminStatsRepository, InMemoryLicenseRepository, LicenseRepository,
+        PostgresLicenseRepositoryImpl,
+    };
+
+    /// Queue, throughput, reviewer workload, registrations and service fee
+    /// revenue over the fixture's licenses
+    async fn run_admin_stats_scenario(repo: &dyn LicenseRepository, stats: &dyn AdminStatsRepository, fx: &Fixture) {
+        let day = 86_400;
+        let decided = |license_type, status, submitted: i64, decided: i64, fee| {
+            let mut license = fx.license(license_type, fx.company_id, fx.owner_id, "Izin", submitted);
+            match status {
+                ApplicationStatus::Approved => license.approved_at = Some(fx.at(decided)),
+                _ => license.rejected_at = Some(fx.at(decided)),
+            }
+            license.application_status = status;
+            license.submitted_at = Some(fx.at(submitted));
+            license.service_fee = fee;
+            license
+        };
+        let in_flight = |license_type, status, submitted: i64| {
+            let mut license = fx.license(license_type, fx.company_id, fx.owner_id, "Izin", submitted);
+            license.application_status = status;
+            license.submitted_at = Some(fx.at(submitted));
+            license
+        };
+
+        let approved = decided(LicenseType::Nib, ApplicationStatus::Approved, 0, 5 * day / 2, Some(150_000));
+        let rejected = decided(LicenseType::Nib, ApplicationStatus::Rejected, 10, 10 + day, Some(75_000));
+        let mut approved_earlier = decided(LicenseType::Nib, ApplicationStatus::Approved, -300, -200, Some(999));
+        approved_earlier.submitted_at = None;
+        let processing = in_flight(LicenseType::Siup, ApplicationStatus::Processing, 20);
+        let waiting = in_flight(LicenseType::Halal, ApplicationStatus::PendingDocuments, -100);
+        let submitted = in_flight(LicenseType::Siup, ApplicationStatus::Submitted, 50);
+        for license in [&approved, &rejected, &approved_earlier, &processing, &waiting, &submitted] {
+            repo.create_license(license).await.unwrap();
+        }
+
+        let mut verified = fx.document(submitted.id, "npwp.pdf", 51);
+        verified.is_verified = true;
+        for document in [
+            fx.document(processing.id, "ktp.pdf", 21),
+            fx.document(waiting.id, "akta.pdf", -99),
+            fx.document(submitted.id, "ktp.pdf", 51),
+            verified,
+        ] {
+            repo.create_document(&document).await.unwrap();
+        }
+
+        // The owner's submission is not review work; the admin's moves are
+        let by_admin = |license: &License, to, seconds| ApplicationStatusHistory {
+            changed_by: fx.admin_id,
+            ..fx.history(license.id, Some(ApplicationStatus::Submitted), to, seconds)
+        };
+        for history in [
+            fx.history(processing.id, None, ApplicationStatus::Submitted, 20),
+            by_admin(&processing, ApplicationStatus::Processing, 30),
+            by_admin(&waiting, ApplicationStatus::PendingDocuments, 40),
+            by_admin(&approved, ApplicationStatus::Approved, 5 * day / 2),
+            by_admin(&rejected, ApplicationStatus::Rejected, 10 + day),
+            by_admin(&approved_earlier, ApplicationStatus::Approved, -200),
+        ] {
+            repo.create_status_history(&history).await.unwrap();
+        }
+
+        assert_eq!(
+            stats.pending_work().await.unwrap(),
+            PendingWork {
+                submitted: 1,
+                processing: 1,
+                pending_documents: 1,
+                unverified_documents: 2,
+                oldest_submitted_at: Some(fx.at(-100)),
+            }
+        );
+
+        let range = ReportRange::new(fx.t0, fx.at(10 * day)).unwrap();
+        let throughput = stats.throughput(&range).await.unwrap();
+        let rows: Vec<_> = throughput
+            .iter()
+            .map(|p| {
+                (
+                    p.license_type,
+                    p.submitted_count,
+                    p.approved_count,
+                    p.rejected_count,
+                    p.approval_rate,
+                    p.avg_processing_days,
+                    p.min_processing_days,
+                    p.max_processing_days,
+                )
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (LicenseType::Nib, 2, 1, 1, Some(0.5), Some(1.75), Some(1), Some(2)),
+                (LicenseType::Siup, 2, 0, 0, None, None, None, None),
+            ]
+        );
+
+        let reviewers = stats.reviewer_workload(&range).await.unwrap();
+        let rows: Vec<_> = reviewers
+            .iter()
+            .map(|r| (r.reviewer_id, r.full_name.as_str(), r.open_applications, r.status_changes, r.approved, r.rejected))
+            .collect();
+        assert_eq!(rows, vec![(fx.admin_id, "Conformance", 2, 4, 1, 1)]);
+
+        // Both fixture companies registered just now
+        let now_range = ReportRange::new(fx.t0, Utc::now() + Duration::hours(1)).unwrap();
+        assert_eq!(
+            stats.registrations_by_province(&now_range).await.unwrap(),
+            vec![RegionRegistrations {
+                province: "Jawa Barat".to_string(),
+                companies: 2,
+            }]
+        );
+        let before = ReportRange::new(fx.at(-3600), fx.t0).unwrap();
+        assert!(stats.registrations_by_province(&before).await.unwrap().is_empty());
+
+        assert_eq!(
+            stats.service_fee_revenue(&range).await.unwrap(),
+            vec![ServiceFeeRevenue {
+                license_type: LicenseType::Nib,
+                approved_licenses: 1,
+                service_fee_total: 150_000,
+            }]
+        );
+        let report = operations_report(stats, range, Utc::now()).await.unwrap();
+        assert_eq!(report.revenue_total, 150_000);
+        assert_eq!(report.throughput, throughput);
+        assert_eq!(report.reviewers, reviewers);
+    }
+
+    #[tokio::test]
+    async fn in_memory_admin_stats_conform() {
+        let fx = Fixture::new();
+        let (users, companies) = in_memory_directory(&fx).await;
+        let repo = InMemoryLicenseRepository::new().with_companies(companies.clone());
+        let stats = InMemoryAdminStatsRepository::new(users, companies, repo.clone());
+        run_admin_stats_scenario(&repo, &stats, &fx).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn postgres_admin_stats_conform() {
+        let fx = Fixture::new();
+        let db = TestDatabase::seeded(&fx).await;
+        run_admin_stats_scenario(
+            &PostgresLicenseRepositoryImpl::new(db.pool.clone()),
+            &PostgresAdminStatsRepository::new(db.pool.clone()),
+            &fx,
+        )
+        .await;
+        db.destroy().await;
+    }
+}
@@ -0,0 +1,598 @@
+// PostgreSQL implementation of the analytics time series
+// Series read the daily rollup tables. A refresh takes the days the triggers
+// queued for each fact and recomputes their rollup rows in one transaction,
+// so a day is never half rebuilt and changes made during the refresh stay
+// queued for the next one.
+
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate};
+use sqlx::{postgres::PgRow, PgPool, Row};
+use std::collections::BTreeMap;
+
+use crate::domain::analytics::{
+    reporting_day_start, AnalyticsRepository, Dimension, FinanceTotals, LicenseVolume, Metric,
+    RegistrationVolume, RollupRefresh, SeriesPoint, SeriesQuery, sort_points,
+};
+use crate::domain::licenses::{ApplicationStatus, LicenseType};
+use crate::shared::errors::AppResult;
+
+const REBUILD_LICENSE_DAYS: &str = r#"
+    INSERT INTO license_daily_rollups (
+        day, license_type, application_status, province, business_scale,
+        created, submitted, approved, rejected, processing_days_sum, processing_decisions
+    )
+    SELECT
+        e.day, l.license_type, l.application_status, c.address_province, c.business_scale,
+        COUNT(*) FILTER (WHERE e.kind = 'created'),
+        COUNT(*) FILTER (WHERE e.kind = 'submitted'),
+        COUNT(*) FILTER (WHERE e.kind = 'approved'),
+        COUNT(*) FILTER (WHERE e.kind = 'rejected'),
+        COALESCE(SUM(e.processing_days), 0),
+        COUNT(e.processing_days)
+    FROM licenses l
+    JOIN companies c ON c.id = l.company_id
+    CROSS JOIN LATERAL (
+        SELECT kind, (event_at AT TIME ZONE 'Asia/Jakarta')::DATE AS day, processing_days
+        FROM (VALUES
+            ('created', l.created_at, NULL::FLOAT8),
+            ('submitted', l.submitted_at, NULL),
+            ('approved', l.approved_at,
+                EXTRACT(EPOCH FROM l.approved_at - l.submitted_at)::FLOAT8 / 86400),
+            ('rejected', l.rejected_at,
+                EXTRACT(EPOCH FROM l.rejected_at - l.submitted_at)::FLOAT8 / 86400)
+        ) AS events(kind, event_at, processing_days)
+        WHERE event_at IS NOT NULL
+    ) e
+    WHERE e.day = ANY($1)
+      AND (l.created_at >= $2 AND l.created_at < $3
+        OR l.submitted_at >= $2 AND l.submitted_at < $3
+        OR l.approved_at >= $2 AND l.approved_at < $3
+        OR l.rejected_at >= $2 AND l.rejected_at < $3)
+    GROUP BY e.day, l.license_type, l.application_status, c.address_province, c.business_scale
+"#;
+
+const REBUILD_REGISTRATION_DAYS: &str = r#"
+    INSERT INTO company_daily_rollups (day, province, business_scale, registrations)
+    SELECT (created_at AT TIME ZONE 'Asia/Jakarta')::DATE, address_province, business_scale, COUNT(*)
+    FROM companies
+    WHERE created_at >= $2 AND created_at < $3
+      AND (created_at AT TIME ZONE 'Asia/Jakarta')::DATE = ANY($1)
+    GROUP BY 1, 2, 3
+"#;
+
+const REBUILD_FINANCE_DAYS: &str = r#"
+    INSERT INTO finance_daily_rollups (
+        day, transaction_type, status, province, business_scale, transactions, amount_total
+    )
+    SELECT
+        (t.transaction_date AT TIME ZONE 'Asia/Jakarta')::DATE,
+        t.transaction_type, t.status, c.address_province, c.business_scale,
+        COUNT(*), SUM(t.amount)::INT8
+    FROM financial_transactions t
+    JOIN companies c ON c.id = t.company_id
+    WHERE t.transaction_date >= $2 AND t.transaction_date < $3
+      AND (t.transaction_date AT TIME ZONE 'Asia/Jakarta')::DATE = ANY($1)
+    GROUP BY 1, 2, 3, 4, 5
+"#;
+
+#[derive(Clone)]
+pub struct PostgresAnalyticsRepository {
+    pool: PgPool,
+}
+
+impl PostgresAnalyticsRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Recomputes the rollup rows of every queued day of `fact`
+    async fn refresh_fact(&self, fact: &str, table: &str, rebuild: &str) -> AppResult<usize> {
+        let mut tx = self.pool.begin().await?;
+        let days: Vec<NaiveDate> =
+            sqlx::query_scalar("DELETE FROM analytics_dirty_days WHERE fact = $1 RETURNING day")
+                .bind(fact)
+                .fetch_all(&mut *tx)
+                .await?;
+        let (Some(first), Some(last)) = (days.iter().min(), days.iter().max()) else {
+            tx.commit().await?;
+            return Ok(0);
+        };
+
+        sqlx::query(&format!("DELETE FROM {} WHERE day = ANY($1)", table))
+            .bind(&days)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(rebuild)
+            .bind(&days)
+            .bind(reporting_day_start(*first))
+            .bind(reporting_day_start(*last + Duration::days(1)))
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(days.len())
+    }
+
+    async fn series(&self, query: &SeriesQuery, table: &str, sums: &str) -> AppResult<Vec<PgRow>> {
+        let columns: String = query
+            .group_by
+            .iter()
+            .map(|dimension| format!(", {}", column(query.metric, *dimension)))
+            .collect();
+        let sql = format!(
+            "SELECT date_trunc($3, day::TIMESTAMP)::DATE AS bucket{columns}, {sums} \
+             FROM {table} WHERE day >= $1 AND day < $2 GROUP BY 1{columns}",
+        );
+        Ok(sqlx::query(&sql)
+            .bind(query.from)
+            .bind(query.to)
+            .bind(query.bucket.to_string())
+            .fetch_all(&self.pool)
+            .await?)
+    }
+}
+
+/// Rollup column of a dimension; `SeriesQuery` only lets a metric be grouped
+/// by its own dimensions
+fn column(metric: Metric, dimension: Dimension) -> &'static str {
+    match (metric, dimension) {
+        (Metric::Licenses, Dimension::Type) => "license_type",
+        (Metric::Licenses, Dimension::Status) => "application_status",
+        (Metric::Finance, Dimension::Type) => "transaction_type",
+        (Metric::Finance, Dimension::Status) => "status",
+        (_, Dimension::Province) => "province",
+        (_, Dimension::BusinessScale) => "business_scale",
+        (Metric::Registrations, _) => unreachable!("registrations have no {}", dimension),
+    }
+}
+
+/// Group values the way the domain enums print them
+fn read_group(row: &PgRow, query: &SeriesQuery) -> AppResult<BTreeMap<Dimension, String>> {
+    let mut group = BTreeMap::new();
+    for dimension in &query.group_by {
+        let name = column(query.metric, *dimension);
+        let value = match (query.metric, dimension) {
+            (Metric::Licenses, Dimension::Type) => row.try_get::<LicenseType, _>(name)?.to_string(),
+            (Metric::Licenses, Dimension::Status) => {
+                row.try_get::<ApplicationStatus, _>(name)?.to_string()
+            }
+            _ => row.try_get::<String, _>(name)?,
+        };
+        group.insert(*dimension, value);
+    }
+    Ok(group)
+}
+
+#[async_trait]
+impl AnalyticsRepository for PostgresAnalyticsRepository {
+    async fn refresh(&self) -> AppResult<RollupRefresh> {
+        Ok(RollupRefresh {
+            license_days: self
+                .refresh_fact("licenses", "license_daily_rollups", REBUILD_LICENSE_DAYS)
+                .await?,
+            registration_days: self
+                .refresh_fact("registrations", "company_daily_rollups", REBUILD_REGISTRATION_DAYS)
+                .await?,
+            finance_days: self
+                .refresh_fact("finance", "finance_daily_rollups", REBUILD_FINANCE_DAYS)
+                .await?,
+        })
+    }
+
+    async fn license_series(&self, query: &SeriesQuery) -> AppResult<Vec<SeriesPoint<LicenseVolume>>> {
+        let rows = self
+            .series(
+                query,
+                "license_daily_rollups",
+                "SUM(created)::INT8 AS created, SUM(submitted)::INT8 AS submitted, \
+                 SUM(approved)::INT8 AS approved, SUM(rejected)::INT8 AS rejected, \
+                 SUM(processing_days_sum)::FLOAT8 AS processing_days, \
+                 SUM(processing_decisions)::INT8 AS processing_decisions",
+            )
+            .await?;
+
+        let mut points = rows
+            .iter()
+            .map(|row| {
+                let processing_days: f64 = row.try_get("processing_days")?;
+                let processing_decisions: i64 = row.try_get("processing_decisions")?;
+                Ok(SeriesPoint {
+                    bucket: row.try_get("bucket")?,
+                    group: read_group(row, query)?,
+                    values: LicenseVolume {
+                        created: row.try_get("created")?,
+                        submitted: row.try_get("submitted")?,
+                        approved: row.try_get("approved")?,
+                        rejected: row.try_get("rejected")?,
+                        avg_processing_days: (processing_decisions > 0)
+                            .then(|| processing_days / processing_decisions as f64),
+                    },
+                })
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+        sort_points(&mut points);
+        Ok(points)
+    }
+
+    async fn registration_series(
+        &self,
+        query: &SeriesQuery,
+    ) -> AppResult<Vec<SeriesPoint<RegistrationVolume>>> {
+        let rows = self
+            .series(query, "company_daily_rollups", "SUM(registrations)::INT8 AS companies")
+            .await?;
+
+        let mut points = rows
+            .iter()
+            .map(|row| {
+                Ok(SeriesPoint {
+                    bucket: row.try_get("bucket")?,
+                    group: read_group(row, query)?,
+                    values: RegistrationVolume {
+                        companies: row.try_get("companies")?,
+                    },
+                })
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+        sort_points(&mut points);
+        Ok(points)
+    }
+
+    async fn finance_series(&self, query: &SeriesQuery) -> AppResult<Vec<SeriesPoint<FinanceTotals>>> {
+        let rows = self
+            .series(
+                query,
+                "finance_daily_rollups",
+                "SUM(transactions)::INT8 AS transactions, SUM(amount_total)::INT8 AS amount",
+            )
+            .await?;
+
+        let mut points = rows
+            .iter()
+            .map(|row| {
+                Ok(SeriesPoint {
+                    bucket: row.try_get("bucket")?,
+                    group: read_group(row, query)?,
+                    values: FinanceTotals {
+                        transactions: row.try_get("transactions")?,
+                        amount: row.try_get("amount")?,
+                    },
+                })
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+        sort_points(&mut points);
+        Ok(points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+    use super::*;
+    use crate::domain::analytics::{reporting_day, Bucket};
+    use crate::domain::finance::{
+        FinancialAccount, FinancialAccountRepository, Transaction, TransactionRepository, TransactionStatus, TransactionType,
+    };
+    use crate::domain::licenses::License;
+    use crate::domain::repositories::CompanyRepository;
+    use crate::domain::value_objects::{Currency, Money};
+    use crate::infrastructure::repositories::testing::{in_memory_directory, Fixture, TestDatabase};
+    use crate::infrastructure::repositories::{
+        InMemoryAnalyticsRepository, InMemoryFinanceStore, InMemoryFinancialAccountRepository,
+        InMemoryLicenseRepository, InMemoryTransactionRepository, LicenseRepository,
+        PostgresCompanyRepository, PostgresFinancialAccountRepository,
+        PostgresLicenseRepositoryImpl, PostgresTransactionRepository,
+    };
+
+    /// `minutes` past midnight Jakarta time on the given day of March 2024
+    fn wib(day: u32, minutes: i64) -> DateTime<Utc> {
+        reporting_day_start(NaiveDate::from_ymd_opt(2024, 3, day).unwrap()) + Duration::minutes(minutes)
+    }
+
+    fn series_query(metric: Metric, from: u32, to: u32, bucket: Bucket, group_by: &[Dimension]) -> SeriesQuery {
+        let day = |d| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        SeriesQuery::new(metric, day(from), day(to), bucket, group_by.to_vec()).unwrap()
+    }
+
+    type LicenseRow = (String, String, i64, i64, i64, i64, Option<f64>);
+
+    fn license_rows(points: Vec<SeriesPoint<LicenseVolume>>) -> Vec<LicenseRow> {
+        points
+            .into_iter()
+            .map(|p| {
+                (
+                    p.bucket.to_string(),
+                    p.group.into_values().collect::<Vec<_>>().join("/"),
+                    p.values.created,
+                    p.values.submitted,
+                    p.values.approved,
+                    p.values.rejected,
+                    p.values.avg_processing_days,
+                )
+            })
+            .collect()
+    }
+
+    fn row(bucket: &str, group: &str, counts: [i64; 4], avg: Option<f64>) -> LicenseRow {
+        let [created, submitted, approved, rejected] = counts;
+        (bucket.to_string(), group.to_string(), created, submitted, approved, rejected, avg)
+    }
+
+    fn finance_rows(points: Vec<SeriesPoint<FinanceTotals>>) -> Vec<(String, String, i64, i64)> {
+        points
+            .into_iter()
+            .map(|p| {
+                (
+                    p.bucket.to_string(),
+                    p.group.into_values().collect::<Vec<_>>().join("/"),
+                    p.values.transactions,
+                    p.values.amount,
+                )
+            })
+            .collect()
+    }
+
+    /// Series over licenses, registrations and finance after every rollup
+    /// refresh, including companies moving province
+    async fn run_analytics_scenario(
+        repo: &dyn LicenseRepository,
+        companies: &dyn CompanyRepository,
+        accounts: &dyn FinancialAccountRepository,
+        transactions: &dyn TransactionRepository,
+        analytics: &dyn AnalyticsRepository,
+        fx: &Fixture,
+    ) {
+        let license = |license_type, company_id, status, created_at, submitted_at| {
+            let mut license = fx.license(license_type, company_id, fx.owner_id, "Izin", 0);
+            license.application_status = status;
+            license.created_at = created_at;
+            license.updated_at = created_at;
+            license.submitted_at = submitted_at;
+            license
+        };
+        let hour = 60;
+        let mut approved = license(LicenseType::Nib, fx.company_id, ApplicationStatus::Approved, wib(4, 9 * hour), Some(wib(4, 10 * hour)));
+        approved.approved_at = Some(wib(6, 10 * hour));
+        // Created half an hour into 5 March in Jakarta, still 4 March in UTC
+        let mut rejected = license(LicenseType::Nib, fx.company_id, ApplicationStatus::Rejected, wib(5, 30), Some(wib(5, hour)));
+        rejected.rejected_at = Some(wib(11, 13 * hour));
+        let draft = license(LicenseType::Siup, fx.company_id, ApplicationStatus::Draft, wib(6, 8 * hour), None);
+        let elsewhere = license(LicenseType::Siup, fx.other_company_id, ApplicationStatus::Submitted, wib(12, 8 * hour), Some(wib(12, 9 * hour)));
+        let mut created = Vec::new();
+        for license in [&approved, &rejected, &draft, &elsewhere] {
+            created.push(repo.create_license(license).await.unwrap());
+        }
+
+        let mut posted = Vec::new();
+        for (company_id, transaction_type, amount, date, status) in [
+            (fx.company_id, TransactionType::Income, 50_000_000, wib(4, 10 * hour), TransactionStatus::Completed),
+            (fx.company_id, TransactionType::Expense, 12_500_000, wib(4, 20 * hour), TransactionStatus::Completed),
+            (fx.other_company_id, TransactionType::Income, 7_500_000, wib(10, 23 * hour), TransactionStatus::Draft),
+        ] {
+            let account = accounts
+                .create(&FinancialAccount::new(company_id, "Kas".to_string(), "cash".to_string(), Currency::IDR, Money::idr(0)))
+                .await
+                .unwrap();
+            let mut transaction = Transaction::new(
+                company_id,
+                date,
+                transaction_type,
+                Money::idr(amount),
+                "Conformance".to_string(),
+                account.id,
+                fx.owner_id,
+            );
+            transaction.status = status;
+            posted.push(transactions.create(&transaction).await.unwrap());
+        }
+        analytics.refresh().await.unwrap();
+
+        let by_day = analytics
+            .license_series(&series_query(Metric::Licenses, 1, 31, Bucket::Day, &[]))
+            .await
+            .unwrap();
+        assert_eq!(
+            license_rows(by_day),
+            vec![
+                row("2024-03-04", "", [1, 1, 0, 0], None),
+                row("2024-03-05", "", [1, 1, 0, 0], None),
+                row("2024-03-06", "", [1, 0, 1, 0], Some(2.0)),
+                row("2024-03-11", "", [0, 0, 0, 1], Some(6.5)),
+                row("2024-03-12", "", [1, 1, 0, 0], None),
+            ]
+        );
+        let one_day = analytics
+            .license_series(&series_query(Metric::Licenses, 5, 6, Bucket::Day, &[]))
+            .await
+            .unwrap();
+        assert_eq!(license_rows(one_day), vec![row("2024-03-05", "", [1, 1, 0, 0], None)]);
+
+        let by_week_and_type = analytics
+            .license_series(&series_query(Metric::Licenses, 1, 31, Bucket::Week, &[Dimension::Type]))
+            .await
+            .unwrap();
+        assert_eq!(
+            license_rows(by_week_and_type),
+            vec![
+                row("2024-03-04", "nib", [2, 2, 1, 0], Some(2.0)),
+                row("2024-03-04", "siup", [1, 0, 0, 0], None),
+                row("2024-03-11", "nib", [0, 0, 0, 1], Some(6.5)),
+                row("2024-03-11", "siup", [1, 1, 0, 0], None),
+            ]
+        );
+
+        let month_groups = [Dimension::Status, Dimension::Province, Dimension::BusinessScale];
+        let by_month = analytics
+            .license_series(&series_query(Metric::Licenses, 1, 31, Bucket::Month, &month_groups))
+            .await
+            .unwrap();
+        assert_eq!(
+            license_rows(by_month),
+            vec![
+                row("2024-03-01", "approved/Jawa Barat/mikro", [1, 1, 1, 0], Some(2.0)),
+                row("2024-03-01", "draft/Jawa Barat/mikro", [1, 0, 0, 0], None),
+                row("2024-03-01", "rejected/Jawa Barat/mikro", [1, 1, 0, 1], Some(6.5)),
+                row("2024-03-01", "submitted/Jawa Barat/mikro", [1, 1, 0, 0], None),
+            ]
+        );
+
+        let finance_by_type = analytics
+            .finance_series(&series_query(Metric::Finance, 1, 31, Bucket::Week, &[Dimension::Type]))
+            .await
+            .unwrap();
+        assert_eq!(
+            finance_rows(finance_by_type),
+            vec![
+                ("2024-03-04".to_string(), "expense".to_string(), 1, 12_500_000),
+                ("2024-03-04".to_string(), "income".to_string(), 2, 57_500_000),
+            ]
+        );
+        let finance_groups = [Dimension::Status, Dimension::Province];
+        let finance_by_status = analytics
+            .finance_series(&series_query(Metric::Finance, 1, 31, Bucket::Month, &finance_groups))
+            .await
+            .unwrap();
+        assert_eq!(
+            finance_rows(finance_by_status),
+            vec![
+                ("2024-03-01".to_string(), "completed/Jawa Barat".to_string(), 2, 62_500_000),
+                ("2024-03-01".to_string(), "draft/Jawa Barat".to_string(), 1, 7_500_000),
+            ]
+        );
+
+        // Both fixture companies registered just now
+        let today = reporting_day(Utc::now());
+        let registrations_query = SeriesQuery::new(
+            Metric::Registrations,
+            today - Duration::days(1),
+            today + Duration::days(1),
+            Bucket::Month,
+            vec![Dimension::Province, Dimension::BusinessScale],
+        )
+        .unwrap();
+        let registrations = |points: Vec<SeriesPoint<RegistrationVolume>>| -> Vec<(String, i64)> {
+            points
+                .into_iter()
+                .map(|p| (p.group.into_values().collect::<Vec<_>>().join("/"), p.values.companies))
+                .collect()
+        };
+        assert_eq!(
+            registrations(analytics.registration_series(&registrations_query).await.unwrap()),
+            vec![("Jawa Barat/mikro".to_string(), 2)]
+        );
+
+        // The draft is submitted, the other company moves to Bali and its
+        // transaction completes; the next refresh picks up all three
+        let mut submitted = created[2].clone();
+        submitted.application_status = ApplicationStatus::Submitted;
+        submitted.submitted_at = Some(wib(13, 9 * hour));
+        repo.update_license(&submitted).await.unwrap();
+        let mut moved = companies.find_by_id(&fx.other_company_id).await.unwrap().unwrap();
+        moved.address_province = "Bali".to_string();
+        companies.update(&moved).await.unwrap();
+        let mut completed = posted[2].clone();
+        completed.status = TransactionStatus::Completed;
+        transactions.update(&completed).await.unwrap();
+        analytics.refresh().await.unwrap();
+
+        let by_week_and_status = analytics
+            .license_series(&series_query(Metric::Licenses, 1, 31, Bucket::Week, &[Dimension::Status]))
+            .await
+            .unwrap();
+        assert_eq!(
+            license_rows(by_week_and_status),
+            vec![
+                row("2024-03-04", "approved", [1, 1, 1, 0], Some(2.0)),
+                row("2024-03-04", "rejected", [1, 1, 0, 0], None),
+                row("2024-03-04", "submitted", [1, 0, 0, 0], None),
+                row("2024-03-11", "rejected", [0, 0, 0, 1], Some(6.5)),
+                row("2024-03-11", "submitted", [1, 2, 0, 0], None),
+            ]
+        );
+        let by_province = analytics
+            .license_series(&series_query(Metric::Licenses, 1, 31, Bucket::Month, &[Dimension::Province]))
+            .await
+            .unwrap();
+        assert_eq!(
+            license_rows(by_province),
+            vec![
+                row("2024-03-01", "Bali", [1, 1, 0, 0], None),
+                row("2024-03-01", "Jawa Barat", [3, 3, 1, 1], Some(4.25)),
+            ]
+        );
+        let finance_by_status = analytics
+            .finance_series(&series_query(Metric::Finance, 1, 31, Bucket::Month, &finance_groups))
+            .await
+            .unwrap();
+        assert_eq!(
+            finance_rows(finance_by_status),
+            vec![
+                ("2024-03-01".to_string(), "completed/Bali".to_string(), 1, 7_500_000),
+                ("2024-03-01".to_string(), "completed/Jawa Barat".to_string(), 2, 62_500_000),
+            ]
+        );
+        assert_eq!(
+            registrations(analytics.registration_series(&registrations_query).await.unwrap()),
+            vec![("Bali/mikro".to_string(), 1), ("Jawa Barat/mikro".to_string(), 1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn in_memory_analytics_conform() {
+        let fx = Fixture::new();
+        let (_, companies) = in_memory_directory(&fx).await;
+        let repo = InMemoryLicenseRepository::new().with_companies(companies.clone());
+        let finance = InMemoryFinanceStore::new();
+        let analytics = InMemoryAnalyticsRepository::new(companies.clone(), repo.clone())
+            .with_finance(finance.clone());
+        run_analytics_scenario(
+            &repo,
+            &companies,
+            &InMemoryFinancialAccountRepository::new(finance.clone()),
+            &InMemoryTransactionRepository::new(finance),
+            &analytics,
+            &fx,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn postgres_analytics_conform() {
+        let fx = Fixture::new();
+        let db = TestDatabase::seeded(&fx).await;
+        let repo = PostgresLicenseRepositoryImpl::new(db.pool.clone());
+        let analytics = PostgresAnalyticsRepository::new(db.pool.clone());
+        run_analytics_scenario(
+            &repo,
+            &PostgresCompanyRepository::new(db.pool.clone()),
+            &PostgresFinancialAccountRepository::new(db.pool.clone()),
+            &PostgresTransactionRepository::new(db.pool.clone()),
+            &analytics,
+            &fx,
+        )
+        .await;
+
+        // Series only move on refresh, and a refresh only recomputes queued days
+        let late = fx.license(LicenseType::Halal, fx.company_id, fx.owner_id, "Izin", 0);
+        let late = License {
+            created_at: wib(20, 600),
+            updated_at: wib(20, 600),
+            ..late
+        };
+        repo.create_license(&late).await.unwrap();
+        let query = series_query(Metric::Licenses, 20, 21, Bucket::Day, &[]);
+        assert!(analytics.license_series(&query).await.unwrap().is_empty());
+        let refresh = analytics.refresh().await.unwrap();
+        assert_eq!((refresh.license_days, refresh.registration_days, refresh.finance_days), (1, 0, 0));
+        assert_eq!(
+            license_rows(analytics.license_series(&query).await.unwrap()),
+            vec![row("2024-03-20", "", [1, 0, 0, 0], None)]
+        );
+        repo.delete_license(late.id).await.unwrap();
+        assert_eq!(analytics.refresh().await.unwrap().license_days, 1);
+        assert!(analytics.license_series(&query).await.unwrap().is_empty());
+        assert_eq!(analytics.refresh().await.unwrap(), Default::default());
+        db.destroy().await;
+    }
+}
@@ -0,0 +1,531 @@
+// PostgreSQL implementation of subscription billing
+// A billing change is written in one transaction together with the company's
+// `subscription_plan`, so the plan checked by rate limiting and entitlements
+// never disagrees with the subscription. Subscriptions are updated at the
+// version they were read at; invoices only while they are still open, so a
+// payment arriving during a dunning sweep is applied once and the loser of
+// the race gets a conflict instead of overwriting it.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgRow;
+use sqlx::{PgConnection, PgPool, Row};
+use uuid::Uuid;
+
+use crate::domain::billing::{BillingChange, BillingRepository, Subscription, SubscriptionInvoice, Usage};
+use crate::domain::value_objects::Money;
+use crate::infrastructure::database::versioning::stale_write;
+use crate::shared::errors::{AppError, AppResult};
+
+const SUBSCRIPTION_COLUMNS: &str = "id, company_id, plan, status, trial_ends_at, \
+    current_period_start, current_period_end, scheduled_plan, version, created_at, updated_at";
+
+const INVOICE_COLUMNS: &str = "id, number, company_id, subscription_id, plan, period_start, \
+    period_end, subtotal, credit, ppn_rate_bps, ppn, total, status, issued_at, due_at, paid_at, \
+    payment_reference, last_payment_error, reminders_sent, next_reminder_at, updated_at";
+
+fn parse<T: std::str::FromStr<Err = String>>(row: &PgRow, column: &str) -> Result<T, AppError> {
+    let value: String = row.try_get(column)?;
+    value.parse().map_err(AppError::InternalError)
+}
+
+fn row_to_subscription(row: &PgRow) -> Result<Subscription, AppError> {
+    let scheduled_plan: Option<String> = row.try_get("scheduled_plan")?;
+    Ok(Subscription {
+        id: row.try_get("id")?,
+        company_id: row.try_get("company_id")?,
+        plan: parse(row, "plan")?,
+        status: parse(row, "status")?,
+        trial_ends_at: row.try_get("trial_ends_at")?,
+        current_period_start: row.try_get("current_period_start")?,
+        current_period_end: row.try_get("current_period_end")?,
+        scheduled_plan: scheduled_plan
+            .map(|plan| plan.parse())
+            .transpose()
+            .map_err(AppError::InternalError)?,
+        version: row.try_get("version")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+fn row_to_invoice(row: &PgRow) -> Result<SubscriptionInvoice, AppError> {
+    Ok(SubscriptionInvoice {
+        id: row.try_get("id")?,
+        number: row.try_get("number")?,
+        company_id: row.try_get("company_id")?,
+        subscription_id: row.try_get("subscription_id")?,
+        plan: parse(row, "plan")?,
+        period_start: row.try_get("period_start")?,
+        period_end: row.try_get("period_end")?,
+        subtotal: Money::idr(row.try_get("subtotal")?),
+        credit: Money::idr(row.try_get("credit")?),
+        ppn_rate_bps: row.try_get("ppn_rate_bps")?,
+        ppn: Money::idr(row.try_get("ppn")?),
+        total: Money::idr(row.try_get("total")?),
+        status: parse(row, "status")?,
+        issued_at: row.try_get("issued_at")?,
+        due_at: row.try_get("due_at")?,
+        paid_at: row.try_get("paid_at")?,
+        payment_reference: row.try_get("payment_reference")?,
+        last_payment_error: row.try_get("last_payment_error")?,
+        reminders_sent: row.try_get("reminders_sent")?,
+        next_reminder_at: row.try_get("next_reminder_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+#[derive(Clone)]
+pub struct PostgresBillingRepository {
+    pool: PgPool,
+}
+
+impl PostgresBillingRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+async fn save_subscription(conn: &mut PgConnection, subscription: &Subscription) -> AppResult<Subscription> {
+    let updated = sqlx::query(&format!(
+        "UPDATE subscriptions SET plan = $3, status = $4, trial_ends_at = $5, \
+             current_period_start = $6, current_period_end = $7, scheduled_plan = $8, \
+             updated_at = NOW() \
+         WHERE id = $1 AND version = $2 RETURNING {}",
+        SUBSCRIPTION_COLUMNS
+    ))
+    .bind(subscription.id)
+    .bind(subscription.version)
+    .bind(subscription.plan.to_string())
+    .bind(subscription.status.to_string())
+    .bind(subscription.trial_ends_at)
+    .bind(subscription.current_period_start)
+    .bind(subscription.current_period_end)
+    .bind(subscription.scheduled_plan.map(|plan| plan.to_string()))
+    .fetch_optional(&mut *conn)
+    .await?;
+    if let Some(row) = updated {
+        return row_to_subscription(&row);
+    }
+
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM subscriptions WHERE id = $1)")
+        .bind(subscription.id)
+        .fetch_one(&mut *conn)
+        .await?;
+    if exists {
+        return Err(stale_write(&mut *conn, "subscriptions", "Subscription", subscription.id).await);
+    }
+
+    let inserted = sqlx::query(&format!(
+        "INSERT INTO subscriptions ({cols}) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 1, $9, $10) \
+         ON CONFLICT (company_id) DO NOTHING RETURNING {cols}",
+        cols = SUBSCRIPTION_COLUMNS
+    ))
+    .bind(subscription.id)
+    .bind(subscription.company_id)
+    .bind(subscription.plan.to_string())
+    .bind(subscription.status.to_string())
+    .bind(subscription.trial_ends_at)
+    .bind(subscription.current_period_start)
+    .bind(subscription.current_period_end)
+    .bind(subscription.scheduled_plan.map(|plan| plan.to_string()))
+    .bind(subscription.created_at)
+    .bind(subscription.updated_at)
+    .fetch_optional(&mut *conn)
+    .await?;
+    match inserted {
+        Some(row) => row_to_subscription(&row),
+        None => Err(AppError::Conflict("The company already has a subscription".to_string())),
+    }
+}
+
+async fn save_invoice(conn: &mut PgConnection, invoice: &SubscriptionInvoice) -> AppResult<SubscriptionInvoice> {
+    if invoice.number.is_empty() {
+        let row = sqlx::query(&format!(
+            "INSERT INTO subscription_invoices ({cols}) VALUES ($1, \
+                 'SUB/' || to_char($13 AT TIME ZONE 'Asia/Jakarta', 'YYYY') || '/' \
+                     || lpad(nextval('subscription_invoice_number_seq')::text, 6, '0'), \
+                 $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20) \
+             RETURNING {cols}",
+            cols = INVOICE_COLUMNS
+        ))
+        .bind(invoice.id)
+        .bind(invoice.company_id)
+        .bind(invoice.subscription_id)
+        .bind(invoice.plan.to_string())
+        .bind(invoice.period_start)
+        .bind(invoice.period_end)
+        .bind(invoice.subtotal.amount)
+        .bind(invoice.credit.amount)
+        .bind(invoice.ppn_rate_bps)
+        .bind(invoice.ppn.amount)
+        .bind(invoice.total.amount)
+        .bind(invoice.status.to_string())
+        .bind(invoice.issued_at)
+        .bind(invoice.due_at)
+        .bind(invoice.paid_at)
+        .bind(&invoice.payment_reference)
+        .bind(&invoice.last_payment_error)
+        .bind(invoice.reminders_sent)
+        .bind(invoice.next_reminder_at)
+        .bind(invoice.updated_at)
+        .fetch_one(&mut *conn)
+        .await?;
+        return row_to_invoice(&row);
+    }
+
+    let row = sqlx::query(&format!(
+        "UPDATE subscription_invoices SET status = $2, paid_at = $3, payment_reference = $4, \
+             last_payment_error = $5, reminders_sent = $6, next_reminder_at = $7, updated_at = $8 \
+         WHERE id = $1 AND status = 'open' RETURNING {}",
+        INVOICE_COLUMNS
+    ))
+    .bind(invoice.id)
+    .bind(invoice.status.to_string())
+    .bind(invoice.paid_at)
+    .bind(&invoice.payment_reference)
+    .bind(&invoice.last_payment_error)
+    .bind(invoice.reminders_sent)
+    .bind(invoice.next_reminder_at)
+    .bind(invoice.updated_at)
+    .fetch_optional(&mut *conn)
+    .await?;
+    match row {
+        Some(row) => row_to_invoice(&row),
+        None => Err(AppError::Conflict(format!(
+            "Invoice {} is no longer open",
+            invoice.number
+        ))),
+    }
+}
+
+#[async_trait]
+impl BillingRepository for PostgresBillingRepository {
+    async fn subscription(&self, company_id: Uuid) -> AppResult<Option<Subscription>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM subscriptions WHERE company_id = $1",
+            SUBSCRIPTION_COLUMNS
+        ))
+        .bind(company_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.as_ref().map(row_to_subscription).transpose()
+    }
+
+    async fn apply(&self, change: &BillingChange) -> AppResult<BillingChange> {
+        let mut tx = self.pool.begin().await?;
+        let mut stored = BillingChange::default();
+
+        if let Some(subscription) = &change.subscription {
+            let saved = save_subscription(&mut tx, subscription).await?;
+            sqlx::query(
+                "UPDATE companies SET subscription_plan = $2 \
+                 WHERE id = $1 AND subscription_plan <> $2",
+            )
+            .bind(saved.company_id)
+            .bind(saved.effective_plan().to_string())
+            .execute(&mut *tx)
+            .await?;
+            stored.subscription = Some(saved);
+        }
+        for invoice in &change.invoices {
+            stored.invoices.push(save_invoice(&mut tx, invoice).await?);
+        }
+
+        tx.commit().await?;
+        Ok(stored)
+    }
+
+    async fn due_subscriptions(&self, now: DateTime<Utc>, limit: i64) -> AppResult<Vec<Subscription>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM subscriptions \
+             WHERE status <> 'canceled' AND current_period_end <= $1 \
+             ORDER BY current_period_end, id LIMIT $2",
+            SUBSCRIPTION_COLUMNS
+        ))
+        .bind(now)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(row_to_subscription).collect()
+    }
+
+    async fn invoice(&self, id: Uuid) -> AppResult<Option<SubscriptionInvoice>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM subscription_invoices WHERE id = $1",
+            INVOICE_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.as_ref().map(row_to_invoice).transpose()
+    }
+
+    async fn invoice_by_number(&self, number: &str) -> AppResult<Option<SubscriptionInvoice>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM subscription_invoices WHERE number = $1",
+            INVOICE_COLUMNS
+        ))
+        .bind(number)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.as_ref().map(row_to_invoice).transpose()
+    }
+
+    async fn invoices(&self, company_id: Uuid, limit: i64) -> AppResult<Vec<SubscriptionInvoice>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM subscription_invoices WHERE company_id = $1 \
+             ORDER BY issued_at DESC, number DESC LIMIT $2",
+            INVOICE_COLUMNS
+        ))
+        .bind(company_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(row_to_invoice).collect()
+    }
+
+    async fn open_invoices(&self, subscription_id: Uuid) -> AppResult<Vec<SubscriptionInvoice>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM subscription_invoices \
+             WHERE subscription_id = $1 AND status = 'open' ORDER BY issued_at, number",
+            INVOICE_COLUMNS
+        ))
+        .bind(subscription_id)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(row_to_invoice).collect()
+    }
+
+    async fn dunning_due(&self, now: DateTime<Utc>, limit: i64) -> AppResult<Vec<SubscriptionInvoice>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM subscription_invoices \
+             WHERE status = 'open' AND next_reminder_at <= $1 \
+             ORDER BY next_reminder_at, number LIMIT $2",
+            INVOICE_COLUMNS
+        ))
+        .bind(now)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(row_to_invoice).collect()
+    }
+
+    async fn usage(&self, company_id: Uuid, month_start: DateTime<Utc>) -> AppResult<Usage> {
+        let row = sqlx::query(
+            "SELECT \
+                 (SELECT COUNT(*) FROM companies WHERE owner_id = \
+                     (SELECT owner_id FROM companies WHERE id = $1)) AS companies, \
+                 (SELECT COUNT(*) FROM licenses \
+                     WHERE company_id = $1 AND created_at >= $2) AS licenses_this_month, \
+                 (SELECT COALESCE(SUM(d.file_size), 0)::BIGINT FROM license_documents d \
+                     JOIN licenses l ON l.id = d.license_id WHERE l.company_id = $1) AS storage_bytes",
+        )
+        .bind(company_id)
+        .bind(month_start)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(Usage {
+            companies: row.try_get("companies")?,
+            licenses_this_month: row.try_get("licenses_this_month")?,
+            storage_bytes: row.try_get("storage_bytes")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, Duration};
+
+    use super::*;
+    use crate::domain::analytics::reporting_day;
+    use crate::domain::billing::{BillingPolicy, InvoiceStatus, PlanChange, SubscriptionStatus};
+    use crate::domain::companies::SubscriptionPlan;
+    use crate::domain::licenses::LicenseType;
+    use crate::infrastructure::repositories::testing::{in_memory, in_memory_directory, Fixture, TestDatabase};
+    use crate::infrastructure::repositories::{
+        InMemoryBillingRepository, LicenseRepository, PostgresLicenseRepositoryImpl,
+    };
+
+    /// Subscriptions and their plan changes, subscription invoices and plan
+    /// usage
+    async fn run_billing_scenario(repo: &dyn LicenseRepository, billing: &dyn BillingRepository, fx: &Fixture) {
+        let policy = BillingPolicy {
+            trial_days: 0,
+            ..BillingPolicy::default()
+        };
+        assert_eq!(billing.subscription(fx.company_id).await.unwrap(), None);
+
+        let (subscription, change) =
+            Subscription::start(fx.company_id, SubscriptionPlan::Basic, &policy, fx.at(0)).unwrap();
+        assert_eq!(change, PlanChange::Invoiced);
+        let first = SubscriptionInvoice::issue(&subscription, Money::idr(0), &policy, fx.at(0));
+        let stored = billing
+            .apply(&BillingChange {
+                subscription: Some(subscription.clone()),
+                invoices: vec![first],
+            })
+            .await
+            .unwrap();
+        let subscription = stored.subscription.unwrap();
+        let first = stored.invoices[0].clone();
+        assert_eq!(subscription.version, 1);
+        assert_eq!(
+            first.number,
+            format!("SUB/{}/000001", reporting_day(fx.at(0)).year())
+        );
+        assert_eq!(billing.subscription(fx.company_id).await.unwrap(), Some(subscription.clone()));
+        assert_eq!(billing.invoice(first.id).await.unwrap(), Some(first.clone()));
+        assert_eq!(billing.invoice_by_number(&first.number).await.unwrap(), Some(first.clone()));
+
+        // One subscription per company
+        let (duplicate, _) = Subscription::start(fx.company_id, SubscriptionPlan::Pro, &policy, fx.at(1)).unwrap();
+        let conflict = billing
+            .apply(&BillingChange {
+                subscription: Some(duplicate),
+                invoices: Vec::new(),
+            })
+            .await;
+        assert!(matches!(conflict, Err(AppError::Conflict(_))), "{:?}", conflict);
+
+        // Paid once; the second write finds it no longer open
+        let mut paid = first.clone();
+        paid.mark_paid(Some("TRX-1".to_string()), fx.at(10)).unwrap();
+        let paid = billing
+            .apply(&BillingChange {
+                subscription: None,
+                invoices: vec![paid],
+            })
+            .await
+            .unwrap()
+            .invoices
+            .remove(0);
+        assert_eq!(paid.status, InvoiceStatus::Paid);
+        let again = billing
+            .apply(&BillingChange {
+                subscription: None,
+                invoices: vec![paid.clone()],
+            })
+            .await;
+        assert!(matches!(again, Err(AppError::Conflict(_))), "{:?}", again);
+
+        // Renewal raises the next invoice and bumps the version
+        let mut renewed = subscription.clone();
+        assert_eq!(renewed.renew(), PlanChange::Invoiced);
+        let period_end = subscription.current_period_end;
+        assert_eq!(billing.due_subscriptions(period_end - Duration::seconds(1), 10).await.unwrap(), vec![]);
+        assert_eq!(
+            billing.due_subscriptions(period_end, 10).await.unwrap(),
+            vec![subscription.clone()]
+        );
+        let second = SubscriptionInvoice::issue(&renewed, Money::idr(0), &policy, period_end);
+        let stored = billing
+            .apply(&BillingChange {
+                subscription: Some(renewed),
+                invoices: vec![second],
+            })
+            .await
+            .unwrap();
+        let renewed = stored.subscription.unwrap();
+        let second = stored.invoices[0].clone();
+        assert_eq!(renewed.version, 2);
+        assert_eq!(billing.due_subscriptions(period_end, 10).await.unwrap(), vec![]);
+        assert_eq!(
+            billing.invoices(fx.company_id, 10).await.unwrap(),
+            vec![second.clone(), paid.clone()]
+        );
+        assert_eq!(billing.invoices(fx.company_id, 1).await.unwrap(), vec![second.clone()]);
+        assert_eq!(billing.invoices(fx.other_company_id, 10).await.unwrap(), vec![]);
+        assert_eq!(billing.open_invoices(renewed.id).await.unwrap(), vec![second.clone()]);
+
+        let reminder_at = second.next_reminder_at.unwrap();
+        assert_eq!(billing.dunning_due(reminder_at - Duration::seconds(1), 10).await.unwrap(), vec![]);
+        assert_eq!(billing.dunning_due(reminder_at, 10).await.unwrap(), vec![second.clone()]);
+
+        // A stale subscription fails the whole change, invoices included
+        let mut stale = subscription.clone();
+        stale.mark_past_due();
+        let mut reminded = second.clone();
+        reminded.dun(&policy, reminder_at).unwrap();
+        let rejected = billing
+            .apply(&BillingChange {
+                subscription: Some(stale),
+                invoices: vec![reminded.clone()],
+            })
+            .await;
+        assert!(rejected.is_err());
+        assert_eq!(billing.subscription(fx.company_id).await.unwrap(), Some(renewed.clone()));
+        assert_eq!(billing.invoice(second.id).await.unwrap(), Some(second.clone()));
+
+        let mut past_due = renewed.clone();
+        past_due.mark_past_due();
+        let stored = billing
+            .apply(&BillingChange {
+                subscription: Some(past_due),
+                invoices: vec![reminded],
+            })
+            .await
+            .unwrap();
+        assert_eq!(stored.subscription.unwrap().status, SubscriptionStatus::PastDue);
+        assert_eq!(stored.invoices[0].reminders_sent, 1);
+        assert_eq!(billing.dunning_due(reminder_at, 10).await.unwrap(), vec![]);
+
+        // Usage counts the owner's companies and this company's recent licenses
+        // and stored documents
+        let earlier = fx.license(LicenseType::Nib, fx.company_id, fx.owner_id, "NIB", -100);
+        let recent = fx.license(LicenseType::Siup, fx.company_id, fx.owner_id, "SIUP", 10);
+        let elsewhere = fx.license(LicenseType::Siup, fx.other_company_id, fx.other_user_id, "SIUP", 10);
+        for license in [&earlier, &recent, &elsewhere] {
+            repo.create_license(license).await.unwrap();
+        }
+        for document in [
+            fx.document(earlier.id, "akta.pdf", -99),
+            fx.document(recent.id, "ktp.pdf", 11),
+            fx.document(elsewhere.id, "ktp.pdf", 11),
+        ] {
+            repo.create_document(&document).await.unwrap();
+        }
+        assert_eq!(
+            billing.usage(fx.company_id, fx.at(0)).await.unwrap(),
+            Usage {
+                companies: 1,
+                licenses_this_month: 1,
+                storage_bytes: 4096,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn in_memory_billing_conforms() {
+        let fx = Fixture::new();
+        let (_, companies) = in_memory_directory(&fx).await;
+        let licenses = in_memory(&fx);
+        let billing = InMemoryBillingRepository::new(companies, licenses.clone());
+        run_billing_scenario(&licenses, &billing, &fx).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn postgres_billing_conforms() {
+        let fx = Fixture::new();
+        let db = TestDatabase::seeded(&fx).await;
+        run_billing_scenario(
+            &PostgresLicenseRepositoryImpl::new(db.pool.clone()),
+            &PostgresBillingRepository::new(db.pool.clone()),
+            &fx,
+        )
+        .await;
+        // The companies table follows the plan in effect
+        let plans: Vec<(Uuid, String)> =
+            sqlx::query_as("SELECT id, subscription_plan FROM companies WHERE id IN ($1, $2)")
+                .bind(fx.company_id)
+                .bind(fx.other_company_id)
+                .fetch_all(&db.pool)
+                .await
+                .unwrap();
+        let plan_of = |id| plans.iter().find(|(company, _)| *company == id).map(|(_, plan)| plan.as_str());
+        assert_eq!(plan_of(fx.company_id), Some("basic"));
+        assert_eq!(plan_of(fx.other_company_id), Some("free"));
+        db.destroy().await;
+    }
+}
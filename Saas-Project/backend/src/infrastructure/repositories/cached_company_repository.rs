@@ -0,0 +1,152 @@
+// Caching decorator for CompanyRepository
+// Lookups by id, NIB and owner go through the cache with single-flight
+// loading; every cached entry is tagged with the company and its owner, so a
+// write drops exactly the entries it can affect. Paged lists and searches are
+// not cached.
+
+use async_trait::async_trait;
+use std::sync::Arc;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::domain::companies::Company;
+use crate::domain::filters::{CompanyFilter, CompanySortField};
+use crate::domain::repositories::CompanyRepository;
+use crate::infrastructure::cache::{cache_aside, Cache, SingleFlight};
+use crate::shared::errors::{AppError, AppResult};
+use crate::shared::query::{ListQuery, Page};
+
+const COMPANY_TTL_SECS: u64 = 300;
+
+pub struct CachedCompanyRepository<C: Cache> {
+    inner: Arc<dyn CompanyRepository + Send + Sync>,
+    cache: Arc<C>,
+    flights: SingleFlight,
+}
+
+impl<C: Cache> CachedCompanyRepository<C> {
+    pub fn new(inner: Arc<dyn CompanyRepository + Send + Sync>, cache: Arc<C>) -> Self {
+        Self {
+            inner,
+            cache,
+            flights: SingleFlight::new(),
+        }
+    }
+
+    fn company_tag(id: Uuid) -> String {
+        format!("company:{}", id)
+    }
+
+    fn owner_tag(owner_id: Uuid) -> String {
+        format!("owner:{}", owner_id)
+    }
+
+    fn tags_for(company: &Company) -> Vec<String> {
+        vec![Self::company_tag(company.id), Self::owner_tag(company.owner_id)]
+    }
+
+    async fn invalidate(&self, tags: Vec<String>) {
+        if let Err(err) = self.cache.invalidate_tags(&tags).await {
+            warn!("Failed to invalidate company cache {:?}: {}", tags, err);
+        }
+    }
+
+    /// Tags of the stored row, before a write replaces or removes it
+    async fn stored_tags(&self, id: &Uuid) -> AppResult<Vec<String>> {
+        Ok(self
+            .inner
+            .find_by_id(id)
+            .await?
+            .map(|company| Self::tags_for(&company))
+            .unwrap_or_else(|| vec![Self::company_tag(*id)]))
+    }
+}
+
+#[async_trait]
+impl<C: Cache + 'static> CompanyRepository for CachedCompanyRepository<C> {
+    async fn find_by_id(&self, id: &Uuid) -> AppResult<Option<Company>> {
+        cache_aside(
+            self.cache.as_ref(),
+            &self.flights,
+            &format!("company:{}", id),
+            COMPANY_TTL_SECS,
+            |company: &Option<Company>| company.as_ref().map(Self::tags_for),
+            || self.inner.find_by_id(id),
+        )
+        .await
+    }
+
+    async fn find_by_owner_id(&self, owner_id: &Uuid) -> AppResult<Vec<Company>> {
+        // Any write for this owner invalidates the list, so empty lists are cached too
+        cache_aside(
+            self.cache.as_ref(),
+            &self.flights,
+            &format!("company:owner:{}:list", owner_id),
+            COMPANY_TTL_SECS,
+            |companies: &Vec<Company>| {
+                let mut tags = vec![Self::owner_tag(*owner_id)];
+                tags.extend(companies.iter().map(|company| Self::company_tag(company.id)));
+                Some(tags)
+            },
+            || self.inner.find_by_owner_id(owner_id),
+        )
+        .await
+    }
+
+    async fn find_by_nib(&self, nib: &str) -> AppResult<Option<Company>> {
+        cache_aside(
+            self.cache.as_ref(),
+            &self.flights,
+            &format!("company:nib:{}", nib),
+            COMPANY_TTL_SECS,
+            |company: &Option<Company>| company.as_ref().map(Self::tags_for),
+            || self.inner.find_by_nib(nib),
+        )
+        .await
+    }
+
+    async fn save(&self, company: &Company) -> AppResult<()> {
+        self.inner.save(company).await?;
+        self.invalidate(Self::tags_for(company)).await;
+        Ok(())
+    }
+
+    async fn update(&self, company: &Company) -> AppResult<()> {
+        // The owner may change, so the previous owner's entries go as well
+        let mut tags = self.stored_tags(&company.id).await?;
+        let result = self.inner.update(company).await;
+        // After a conflict the cached copy may be the stale one
+        if matches!(result, Ok(()) | Err(AppError::VersionConflict { .. })) {
+            tags.push(Self::owner_tag(company.owner_id));
+            self.invalidate(tags).await;
+        }
+        result
+    }
+
+    async fn delete(&self, id: &Uuid) -> AppResult<()> {
+        let tags = self.stored_tags(id).await?;
+        self.inner.delete(id).await?;
+        self.invalidate(tags).await;
+        Ok(())
+    }
+
+    async fn list_all(&self, limit: Option<i32>, offset: Option<i32>) -> AppResult<Vec<Company>> {
+        self.inner.list_all(limit, offset).await
+    }
+
+    async fn count_by_owner(&self, owner_id: &Uuid) -> AppResult<i64> {
+        cache_aside(
+            self.cache.as_ref(),
+            &self.flights,
+            &format!("company:owner:{}:count", owner_id),
+            COMPANY_TTL_SECS,
+            |_: &i64| Some(vec![Self::owner_tag(*owner_id)]),
+            || self.inner.count_by_owner(owner_id),
+        )
+        .await
+    }
+
+    async fn list(&self, query: &ListQuery<CompanyFilter, CompanySortField>) -> AppResult<Page<Company>> {
+        self.inner.list(query).await
+    }
+}
@@ -2,72 +2,41 @@
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use sqlx::{PgPool, Row};
-use std::sync::Arc;
+use serde::{de::DeserializeOwned, Serialize};
+use sqlx::PgPool;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
 use tracing::{debug, error, instrument};
 use uuid::Uuid;
 
+use crate::domain::filters::{DocumentFilter, DocumentSortField, LicenseFilter, LicenseSortField};
 use crate::domain::licenses::{
     ApplicationStatus, ApplicationStatusHistory, License, LicenseDocument, LicenseType,
 };
+use crate::shared::errors::{AppError, AppResult};
+use crate::shared::query::{ListQuery, Page};
+
+use crate::domain::unit_of_work::{UnitOfWork, Work, WorkHandle};
+use crate::infrastructure::cache::{cache_aside, SingleFlight};
+use crate::infrastructure::database::unit_of_work::{PgUnitOfWork, PgWork};
+use crate::infrastructure::repositories::license_repository::{
+    LicenseStatistics, PostgresLicenseRepositoryImpl,
+};
 
-use crate::infrastructure::repositories::license_repository::LicenseStatistics;
-
-use crate::infrastructure::cache::CacheService;
-
-#[async_trait]
-pub trait Cache: Send + Sync {
-    async fn set<T: serde::Serialize + Send + Sync>(
-        &self,
-        key: &str,
-        value: &T,
-        expiry_secs: Option<u64>,
-    ) -> Result<(), redis::RedisError>;
-
-    async fn get<T: serde::de::DeserializeOwned + Send + Sync>(
-        &self,
-        key: &str,
-    ) -> Result<Option<T>, redis::RedisError>;
-
-    async fn delete(&self, key: &str) -> Result<(), redis::RedisError>;
-    async fn delete_by_pattern(&self, pattern: &str) -> Result<(), redis::RedisError>;
-}
-
-#[async_trait]
-impl Cache for CacheService {
-    async fn set<T: serde::Serialize + Send + Sync>(
-        &self,
-        key: &str,
-        value: &T,
-        expiry_secs: Option<u64>,
-    ) -> Result<(), redis::RedisError> {
-        self.set(key, value, expiry_secs).await
-    }
-
-    async fn get<T: serde::de::DeserializeOwned + Send + Sync>(
-        &self,
-        key: &str,
-    ) -> Result<Option<T>, redis::RedisError> {
-        self.get(key).await
-    }
-
-    async fn delete(&self, key: &str) -> Result<(), redis::RedisError> {
-        self.delete(key).await
-    }
-
-    async fn delete_by_pattern(&self, pattern: &str) -> Result<(), redis::RedisError> {
-        self.delete_by_pattern(pattern).await
-    }
-}
+pub use crate::infrastructure::cache::Cache;
 
 #[async_trait]
 pub trait LicenseRepository: Send + Sync {
     // License CRUD operations
     async fn create_license(&self, license: &License) -> Result<License, sqlx::Error>;
     async fn get_license_by_id(&self, id: Uuid) -> Result<Option<License>, sqlx::Error>;
+    async fn get_license_by_number(&self, license_number: &str) -> Result<Option<License>, sqlx::Error>;
     async fn get_licenses_by_user(&self, user_id: Uuid) -> Result<Vec<License>, sqlx::Error>;
     async fn get_licenses_by_company(&self, company_id: Uuid) -> Result<Vec<License>, sqlx::Error>;
-    async fn update_license(&self, license: &License) -> Result<License, sqlx::Error>;
+    /// Saves `license` if the stored row is still at `license.version`,
+    /// returning it with the bumped version; `AppError::VersionConflict`
+    /// when someone else updated it first
+    async fn update_license(&self, license: &License) -> AppResult<License>;
     async fn delete_license(&self, id: Uuid) -> Result<bool, sqlx::Error>;
 
     // License queries
@@ -80,11 +49,16 @@ pub trait LicenseRepository: Send + Sync {
         license_type: LicenseType,
     ) -> Result<Vec<License>, sqlx::Error>;
     async fn get_expiring_licenses(&self, days_ahead: i32) -> Result<Vec<License>, sqlx::Error>;
-    async fn search_licenses(
+
+    // Keyset-paginated listing
+    async fn list_licenses(
         &self,
-        query: &str,
-        user_id: Option<Uuid>,
-    ) -> Result<Vec<License>, sqlx::Error>;
+        query: &ListQuery<LicenseFilter, LicenseSortField>,
+    ) -> Result<Page<License>, sqlx::Error>;
+    async fn list_documents(
+        &self,
+        query: &ListQuery<DocumentFilter, DocumentSortField>,
+    ) -> Result<Page<LicenseDocument>, sqlx::Error>;
 
     // Document operations
     async fn create_document(
@@ -118,23 +92,26 @@ pub trait LicenseRepository: Send + Sync {
         license_id: Uuid,
         user_id: Uuid,
     ) -> Result<License, sqlx::Error>;
+    // Approval and rejection only apply to the version the reviewer saw
     async fn approve_license(
         &self,
         license_id: Uuid,
+        expected_version: i64,
         admin_user_id: Uuid,
         license_number: String,
         issue_date: DateTime<Utc>,
         expiry_date: Option<DateTime<Utc>>,
         issuing_authority: String,
         admin_notes: Option<String>,
-    ) -> Result<License, sqlx::Error>;
+    ) -> AppResult<License>;
     async fn reject_license(
         &self,
         license_id: Uuid,
+        expected_version: i64,
         admin_user_id: Uuid,
         reason: String,
         admin_notes: Option<String>,
-    ) -> Result<License, sqlx::Error>;
+    ) -> AppResult<License>;
 
     // Analytics operations
     async fn get_license_count_by_type(&self) -> Result<Vec<(LicenseType, i64)>, sqlx::Error>;
@@ -148,20 +125,51 @@ pub trait LicenseRepository: Send + Sync {
     ) -> Result<LicenseStatistics, sqlx::Error>;
 }
 
+/// The license repositories a unit of work carries; status history is part of
+/// `LicenseRepository`
+pub struct LicenseRepositories {
+    pub licenses: Box<dyn LicenseRepository>,
+}
+
+/// Caching decorator over a `LicenseRepository`, by default the Postgres one.
+///
+/// Every read goes through the cache with single-flight loading, except the
+/// keyset pages: cursor and filter combinations make poor keys and would all
+/// need invalidating on every license write. Cached entries are tagged with
+/// what they depend on, and every write invalidates the tags it affects:
+///
+/// - `license:{id}`: the license itself
+/// - `license:{id}:documents`, `license:{id}:status_history`: its children
+/// - `licenses:user:{id}`, `licenses:company:{id}`: per-owner lists and stats
+/// - `licenses:all`: lists and aggregates spanning every license
+///
+/// Inside a unit of work reads bypass the cache, so uncommitted rows are never
+/// cached, and invalidations wait for the commit.
 pub struct CachedLicenseRepository<C: Cache> {
-    pool: PgPool,
+    inner: Arc<dyn LicenseRepository + Send + Sync>,
     cache: Option<Arc<C>>,
+    flights: SingleFlight,
+    /// Tags to invalidate once the unit of work commits
+    deferred: Option<Arc<Mutex<Vec<String>>>>,
 }
 
+const ALL_LICENSES_TAG: &str = "licenses:all";
+
 impl<C: Cache> CachedLicenseRepository<C> {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool, cache: None }
+        Self::from_inner(Arc::new(PostgresLicenseRepositoryImpl::new(pool)), None)
     }
 
     pub fn new_with_cache(pool: PgPool, cache: Arc<C>) -> Self {
+        Self::from_inner(Arc::new(PostgresLicenseRepositoryImpl::new(pool)), Some(cache))
+    }
+
+    pub fn from_inner(inner: Arc<dyn LicenseRepository + Send + Sync>, cache: Option<Arc<C>>) -> Self {
         Self {
-            pool,
-            cache: Some(cache),
+            inner,
+            cache,
+            flights: SingleFlight::new(),
+            deferred: None,
         }
     }
 
@@ -191,559 +199,187 @@ impl<C: Cache> CachedLicenseRepository<C> {
     }
 
     fn license_type_cache_key(license_type: LicenseType) -> String {
-        format!("licenses:type:{}", license_type.to_string())
+        format!("licenses:type:{}", license_type)
     }
 
-    fn license_status_cache_key(status: ApplicationStatus) -> String {
-        format!("licenses:status:{}", status.to_string())
+    fn license_status_cache_key(status: &ApplicationStatus) -> String {
+        format!("licenses:status:{}", status)
     }
 
-    // Helper to invalidate license caches when license is updated
-    async fn invalidate_license_cache(
-        &self,
-        license_id: Uuid,
-        user_id: Option<Uuid>,
-        company_id: Option<Uuid>,
-    ) {
-        if let Some(cache) = &self.cache {
-            debug!("Invalidating cache for license {}", license_id);
-
-            // Delete specific license cache
-            let _ = cache.delete(&Self::license_cache_key(license_id)).await;
-
-            // Delete user licenses cache if user_id is provided
-            if let Some(uid) = user_id {
-                let _ = cache.delete(&Self::user_licenses_cache_key(uid)).await;
-            }
-
-            // Delete company licenses cache if company_id is provided
-            if let Some(cid) = company_id {
-                let _ = cache.delete(&Self::company_licenses_cache_key(cid)).await;
-            }
-
-            // Delete any aggregate caches that might include this license
-            let _ = cache.delete_by_pattern("licenses:type:*").await;
-            let _ = cache.delete_by_pattern("licenses:status:*").await;
-            let _ = cache.delete_by_pattern("analytics:licenses:*").await;
+    fn statistics_cache_key(user_id: Option<Uuid>) -> String {
+        match user_id {
+            Some(uid) => format!("stats:user:{}", uid),
+            None => "stats:global".to_string(),
         }
     }
-}
-
-#[async_trait]
-impl<C: Cache> LicenseRepository for CachedLicenseRepository<C> {
-    // Implement repository methods with caching
-    // We'll implement a few key methods as examples
-
-    #[instrument(skip(self, license))]
-    async fn create_license(&self, license: &License) -> Result<License, sqlx::Error> {
-        let query = r#"
-            INSERT INTO licenses (
-                id, license_number, license_type, company_id, user_id,
-                title, description, issue_date, expiry_date, issuing_authority,
-                application_status, priority, estimated_processing_days, actual_processing_days,
-                external_reference_id, government_fee, service_fee, created_at, updated_at,
-                submitted_at, approved_at, rejected_at, admin_notes, rejection_reason
-            ) VALUES (
-                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14,
-                $15, $16, $17, $18, $19, $20, $21, $22, $23, $24
-            ) RETURNING *
-        "#;
-
-        let inserted = sqlx::query_as::<_, License>(query)
-            .bind(license.id)
-            .bind(&license.license_number)
-            .bind(&license.license_type)
-            .bind(license.company_id)
-            .bind(license.user_id)
-            .bind(&license.title)
-            .bind(&license.description)
-            .bind(license.issue_date)
-            .bind(license.expiry_date)
-            .bind(&license.issuing_authority)
-            .bind(&license.application_status)
-            .bind(&license.priority)
-            .bind(license.estimated_processing_days)
-            .bind(license.actual_processing_days)
-            .bind(&license.external_reference_id)
-            .bind(license.government_fee)
-            .bind(license.service_fee)
-            .bind(license.created_at)
-            .bind(license.updated_at)
-            .bind(license.submitted_at)
-            .bind(license.approved_at)
-            .bind(license.rejected_at)
-            .bind(&license.admin_notes)
-            .bind(&license.rejection_reason)
-            .fetch_one(&self.pool)
-            .await?;
-
-        self
-            .invalidate_license_cache(
-                inserted.id,
-                Some(inserted.user_id),
-                Some(inserted.company_id),
-            )
-            .await;
 
-        if let Some(cache) = &self.cache {
-            let cache_key = Self::license_cache_key(inserted.id);
-            let _ = cache.set(&cache_key, &inserted, Some(300)).await;
-        }
-
-        Ok(inserted)
+    // Tags
+    fn license_tag(id: Uuid) -> String {
+        format!("license:{}", id)
     }
 
-    #[instrument(skip(self), fields(license_id = %id))]
-    async fn get_license_by_id(&self, id: Uuid) -> Result<Option<License>, sqlx::Error> {
-        // Try to get from cache first
-        if let Some(cache) = &self.cache {
-            let cache_key = Self::license_cache_key(id);
-            debug!("Checking cache for license: {}", id);
-
-            match cache.get::<License>(&cache_key).await {
-                Ok(Some(license)) => {
-                    debug!("Cache hit for license: {}", id);
-                    return Ok(Some(license));
-                }
-                Ok(None) => debug!("Cache miss for license: {}", id),
-                Err(e) => error!("Cache error: {}", e),
-            }
-        }
-
-        // If not in cache or error, get from database
-        let license = sqlx::query("SELECT * FROM licenses WHERE id = $1")
-            .bind(id)
-            .map(|row: sqlx::postgres::PgRow| License {
-                id: row.get("id"),
-                user_id: row.get("user_id"),
-                company_id: row.get("company_id"),
-                license_type: row.get("license_type"),
-                application_status: row.get("application_status"),
-                license_number: row.get("license_number"),
-                title: row.get("title"),
-                description: row.get("description"),
-                issue_date: row.get("issue_date"),
-                expiry_date: row.get("expiry_date"),
-                issuing_authority: row.get("issuing_authority"),
-                priority: row.get("priority"),
-                estimated_processing_days: row.get("estimated_processing_days"),
-                actual_processing_days: row.get("actual_processing_days"),
-                external_reference_id: row.get("external_reference_id"),
-                government_fee: row.get("government_fee"),
-                service_fee: row.get("service_fee"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-                submitted_at: row.get("submitted_at"),
-                approved_at: row.get("approved_at"),
-                rejected_at: row.get("rejected_at"),
-                admin_notes: row.get("admin_notes"),
-                rejection_reason: row.get("rejection_reason"),
-            })
-            .fetch_optional(&self.pool)
-            .await?;
-
-        // Cache the result if found
-        if let Some(ref license) = license {
-            if let Some(cache) = &self.cache {
-                let cache_key = Self::license_cache_key(id);
-                debug!("Caching license: {}", id);
-
-                // Cache for 5 minutes
-                let _ = cache.set(&cache_key, license, Some(300)).await;
-            }
-        }
-
-        Ok(license)
+    fn documents_tag(license_id: Uuid) -> String {
+        format!("license:{}:documents", license_id)
     }
 
-    #[instrument(skip(self), fields(user_id = %user_id))]
-    async fn get_licenses_by_user(&self, user_id: Uuid) -> Result<Vec<License>, sqlx::Error> {
-        // Try to get from cache first
-        if let Some(cache) = &self.cache {
-            let cache_key = Self::user_licenses_cache_key(user_id);
-            debug!("Checking cache for user licenses: {}", user_id);
-
-            match cache.get::<Vec<License>>(&cache_key).await {
-                Ok(Some(licenses)) => {
-                    debug!("Cache hit for user licenses: {}", user_id);
-                    return Ok(licenses);
-                }
-                Ok(None) => debug!("Cache miss for user licenses: {}", user_id),
-                Err(e) => error!("Cache error: {}", e),
-            }
-        }
+    fn status_history_tag(license_id: Uuid) -> String {
+        format!("license:{}:status_history", license_id)
+    }
 
-        // If not in cache or error, get from database
-        let licenses =
-            sqlx::query("SELECT * FROM licenses WHERE user_id = $1 ORDER BY created_at DESC")
-                .bind(user_id)
-                .map(|row: sqlx::postgres::PgRow| License {
-                    id: row.get("id"),
-                    user_id: row.get("user_id"),
-                    company_id: row.get("company_id"),
-                    license_type: row.get("license_type"),
-                    application_status: row.get("application_status"),
-                    license_number: row.get("license_number"),
-                    title: row.get("title"),
-                    description: row.get("description"),
-                    issue_date: row.get("issue_date"),
-                    expiry_date: row.get("expiry_date"),
-                    issuing_authority: row.get("issuing_authority"),
-                    priority: row.get("priority"),
-                    estimated_processing_days: row.get("estimated_processing_days"),
-                    actual_processing_days: row.get("actual_processing_days"),
-                    external_reference_id: row.get("external_reference_id"),
-                    government_fee: row.get("government_fee"),
-                    service_fee: row.get("service_fee"),
-                    created_at: row.get("created_at"),
-                    updated_at: row.get("updated_at"),
-                    submitted_at: row.get("submitted_at"),
-                    approved_at: row.get("approved_at"),
-                    rejected_at: row.get("rejected_at"),
-                    admin_notes: row.get("admin_notes"),
-                    rejection_reason: row.get("rejection_reason"),
-                })
-                .fetch_all(&self.pool)
-                .await?;
-
-        // Cache the result
-        if let Some(cache) = &self.cache {
-            let cache_key = Self::user_licenses_cache_key(user_id);
-            debug!("Caching user licenses: {}", user_id);
+    fn user_tag(user_id: Uuid) -> String {
+        format!("licenses:user:{}", user_id)
+    }
 
-            // Cache for 2 minutes
-            let _ = cache.set(&cache_key, &licenses, Some(120)).await;
-        }
+    fn company_tag(company_id: Uuid) -> String {
+        format!("licenses:company:{}", company_id)
+    }
 
-        Ok(licenses)
+    /// Everything a change to this license can make stale
+    fn write_tags(license: &License) -> Vec<String> {
+        vec![
+            Self::license_tag(license.id),
+            Self::user_tag(license.user_id),
+            Self::company_tag(license.company_id),
+            ALL_LICENSES_TAG.to_string(),
+        ]
     }
 
-    // Analytics implementation
-    async fn get_license_statistics(
+    /// Reads through the cache when there is one
+    async fn cached<T, F, Fut>(
         &self,
-        user_id: Option<Uuid>,
-    ) -> Result<LicenseStatistics, sqlx::Error> {
-        // Try to get from cache first if available
-        if let Some(cache) = &self.cache {
-            let cache_key = match user_id {
-                Some(uid) => format!("stats:user:{}", uid),
-                None => "stats:global".to_string(),
-            };
-
-            debug!("Checking cache for license statistics");
-            match cache.get::<LicenseStatistics>(&cache_key).await {
-                Ok(Some(stats)) => {
-                    debug!("Cache hit for license statistics");
-                    return Ok(stats);
-                }
-                Ok(None) => debug!("Cache miss for license statistics"),
-                Err(e) => error!("Cache error: {}", e),
+        key: &str,
+        expiry_secs: u64,
+        tags: impl FnOnce(&T) -> Option<Vec<String>>,
+        load: F,
+    ) -> Result<T, sqlx::Error>
+    where
+        T: Serialize + DeserializeOwned + Send + Sync,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, sqlx::Error>>,
+    {
+        match &self.cache {
+            Some(cache) if self.deferred.is_none() => {
+                cache_aside(cache.as_ref(), &self.flights, key, expiry_secs, tags, load).await
             }
+            _ => load().await,
         }
-
-        // If not in cache or error, get from database
-        let query = if let Some(_user_id) = user_id {
-            r#"
-                SELECT 
-                    COUNT(*) as total_licenses,
-                    COUNT(*) FILTER (WHERE application_status = 'draft') as draft_count,
-                    COUNT(*) FILTER (WHERE application_status = 'submitted') as submitted_count,
-                    COUNT(*) FILTER (WHERE application_status = 'processing') as processing_count,
-                    COUNT(*) FILTER (WHERE application_status = 'approved') as approved_count,
-                    COUNT(*) FILTER (WHERE application_status = 'rejected') as rejected_count,
-                    AVG(actual_processing_days) FILTER (WHERE actual_processing_days IS NOT NULL) as avg_processing_days
-                FROM licenses 
-                WHERE user_id = $1
-            "#
-        } else {
-            r#"
-                SELECT 
-                    COUNT(*) as total_licenses,
-                    COUNT(*) FILTER (WHERE application_status = 'draft') as draft_count,
-                    COUNT(*) FILTER (WHERE application_status = 'submitted') as submitted_count,
-                    COUNT(*) FILTER (WHERE application_status = 'processing') as processing_count,
-                    COUNT(*) FILTER (WHERE application_status = 'approved') as approved_count,
-                    COUNT(*) FILTER (WHERE application_status = 'rejected') as rejected_count,
-                    AVG(actual_processing_days) FILTER (WHERE actual_processing_days IS NOT NULL) as avg_processing_days
-                FROM licenses
-            "#
-        };
-
-        let row = if let Some(user_id) = user_id {
-            sqlx::query(query)
-                .bind(user_id)
-                .fetch_one(&self.pool)
-                .await?
-        } else {
-            sqlx::query(query).fetch_one(&self.pool).await?
-        };
-
-        let stats = LicenseStatistics {
-            total_licenses: row.get::<i64, _>("total_licenses"),
-            draft_count: row.get::<i64, _>("draft_count"),
-            submitted_count: row.get::<i64, _>("submitted_count"),
-            processing_count: row.get::<i64, _>("processing_count"),
-            approved_count: row.get::<i64, _>("approved_count"),
-            rejected_count: row.get::<i64, _>("rejected_count"),
-            avg_processing_days: row.get::<Option<f64>, _>("avg_processing_days"),
-        };
-
-        // Cache the result
-        if let Some(cache) = &self.cache {
-            let cache_key = match user_id {
-                Some(uid) => format!("stats:user:{}", uid),
-                None => "stats:global".to_string(),
-            };
-            debug!("Caching license statistics");
-
-            // Cache for 2 minutes
-            let _ = cache.set(&cache_key, &stats, Some(120)).await;
-        }
-
-        Ok(stats)
     }
 
-    async fn get_license_count_by_type(&self) -> Result<Vec<(LicenseType, i64)>, sqlx::Error> {
-        if let Some(cache) = &self.cache {
-            if let Ok(Some(data)) = cache
-                .get::<Vec<(LicenseType, i64)>>("analytics:licenses:type_counts")
-                .await
-            {
-                return Ok(data);
-            }
+    async fn invalidate(&self, tags: Vec<String>) {
+        if let Some(deferred) = &self.deferred {
+            deferred.lock().unwrap().extend(tags);
+            return;
         }
-
-        let rows = sqlx::query(
-            "SELECT license_type, COUNT(*) as count FROM licenses GROUP BY license_type",
-        )
-        .map(|row: sqlx::postgres::PgRow| (row.get("license_type"), row.get::<i64, _>("count")))
-        .fetch_all(&self.pool)
-        .await?;
-
         if let Some(cache) = &self.cache {
-            let _ = cache
-                .set("analytics:licenses:type_counts", &rows, Some(300))
-                .await;
+            debug!("Invalidating license cache tags {:?}", tags);
+            if let Err(e) = cache.invalidate_tags(&tags).await {
+                error!("Cache error: {}", e);
+            }
         }
+    }
 
-        Ok(rows)
+    /// Tags of the stored license, before a write replaces or removes it
+    async fn stored_tags(&self, id: Uuid) -> Result<Vec<String>, sqlx::Error> {
+        Ok(self
+            .inner
+            .get_license_by_id(id)
+            .await?
+            .map(|license| Self::write_tags(&license))
+            .unwrap_or_else(|| vec![Self::license_tag(id), ALL_LICENSES_TAG.to_string()]))
     }
 
-    async fn get_license_count_by_status(
+    /// Runs a conditional write; on a version conflict the cached copy is
+    /// dropped so the client's reload sees the version that won
+    async fn conditional(
         &self,
-    ) -> Result<Vec<(ApplicationStatus, i64)>, sqlx::Error> {
-        if let Some(cache) = &self.cache {
-            if let Ok(Some(data)) = cache
-                .get::<Vec<(ApplicationStatus, i64)>>("analytics:licenses:status_counts")
-                .await
-            {
-                return Ok(data);
-            }
-        }
-
-        let rows = sqlx::query(
-            "SELECT application_status, COUNT(*) as count FROM licenses GROUP BY application_status",
-        )
-        .map(|row: sqlx::postgres::PgRow| {
-            (row.get("application_status"), row.get::<i64, _>("count"))
-        })
-        .fetch_all(&self.pool)
-        .await?;
-
-        if let Some(cache) = &self.cache {
-            let _ = cache
-                .set("analytics:licenses:status_counts", &rows, Some(300))
-                .await;
+        id: Uuid,
+        write: impl Future<Output = AppResult<License>>,
+    ) -> AppResult<License> {
+        let result = write.await;
+        if let Err(AppError::VersionConflict { .. }) = &result {
+            self.invalidate(vec![Self::license_tag(id)]).await;
         }
+        result
+    }
 
-        Ok(rows)
+    /// License whose document list a write to this document affects
+    async fn document_license(&self, id: Uuid) -> Result<Option<Uuid>, sqlx::Error> {
+        Ok(self.inner.get_document_by_id(id).await?.map(|d| d.license_id))
     }
+}
 
-    async fn get_processing_times(&self) -> Result<Vec<(LicenseType, f64)>, sqlx::Error> {
-        if let Some(cache) = &self.cache {
-            if let Ok(Some(data)) = cache
-                .get::<Vec<(LicenseType, f64)>>("analytics:licenses:processing_times")
-                .await
-            {
-                return Ok(data);
-            }
-        }
+#[async_trait]
+impl<C: Cache + 'static> LicenseRepository for CachedLicenseRepository<C> {
+    #[instrument(skip(self, license))]
+    async fn create_license(&self, license: &License) -> Result<License, sqlx::Error> {
+        let inserted = self.inner.create_license(license).await?;
+        self.invalidate(Self::write_tags(&inserted)).await;
+        Ok(inserted)
+    }
 
-        let rows = sqlx::query(
-            "SELECT license_type, AVG(actual_processing_days)::float AS avg_days FROM licenses WHERE actual_processing_days IS NOT NULL GROUP BY license_type",
+    #[instrument(skip(self), fields(license_id = %id))]
+    async fn get_license_by_id(&self, id: Uuid) -> Result<Option<License>, sqlx::Error> {
+        // Cache for 5 minutes
+        self.cached(
+            &Self::license_cache_key(id),
+            300,
+            |license: &Option<License>| license.as_ref().map(|l| vec![Self::license_tag(l.id)]),
+            || self.inner.get_license_by_id(id),
         )
-        .map(|row: sqlx::postgres::PgRow| {
-            (row.get("license_type"), row.get::<f64, _>("avg_days"))
-        })
-        .fetch_all(&self.pool)
-        .await?;
-
-        if let Some(cache) = &self.cache {
-            let _ = cache
-                .set("analytics:licenses:processing_times", &rows, Some(300))
-                .await;
-        }
+        .await
+    }
 
-        Ok(rows)
+    #[instrument(skip(self))]
+    async fn get_license_by_number(&self, license_number: &str) -> Result<Option<License>, sqlx::Error> {
+        // Public verification lookups, answered fresh
+        self.inner.get_license_by_number(license_number).await
     }
 
-    // For all the other unimplemented methods, we would add implementations similar to
-    // the ones we've already created, with caching logic.
+    #[instrument(skip(self), fields(user_id = %user_id))]
+    async fn get_licenses_by_user(&self, user_id: Uuid) -> Result<Vec<License>, sqlx::Error> {
+        // Cache for 2 minutes
+        self.cached(
+            &Self::user_licenses_cache_key(user_id),
+            120,
+            |_: &Vec<License>| Some(vec![Self::user_tag(user_id)]),
+            || self.inner.get_licenses_by_user(user_id),
+        )
+        .await
+    }
 
     #[instrument(skip(self), fields(company_id = %company_id))]
     async fn get_licenses_by_company(&self, company_id: Uuid) -> Result<Vec<License>, sqlx::Error> {
-        // Try to get from cache first
-        if let Some(cache) = &self.cache {
-            let cache_key = Self::company_licenses_cache_key(company_id);
-            debug!("Checking cache for company licenses: {}", company_id);
-
-            match cache.get::<Vec<License>>(&cache_key).await {
-                Ok(Some(licenses)) => {
-                    debug!("Cache hit for company licenses: {}", company_id);
-                    return Ok(licenses);
-                }
-                Ok(None) => debug!("Cache miss for company licenses: {}", company_id),
-                Err(e) => error!("Cache error: {}", e),
-            }
-        }
-
-        // If not in cache or error, get from database
-        let licenses =
-            sqlx::query("SELECT * FROM licenses WHERE company_id = $1 ORDER BY created_at DESC")
-                .bind(company_id)
-                .map(|row: sqlx::postgres::PgRow| License {
-                    id: row.get("id"),
-                    user_id: row.get("user_id"),
-                    company_id: row.get("company_id"),
-                    license_type: row.get("license_type"),
-                    application_status: row.get("application_status"),
-                    license_number: row.get("license_number"),
-                    title: row.get("title"),
-                    description: row.get("description"),
-                    issue_date: row.get("issue_date"),
-                    expiry_date: row.get("expiry_date"),
-                    issuing_authority: row.get("issuing_authority"),
-                    priority: row.get("priority"),
-                    estimated_processing_days: row.get("estimated_processing_days"),
-                    actual_processing_days: row.get("actual_processing_days"),
-                    external_reference_id: row.get("external_reference_id"),
-                    government_fee: row.get("government_fee"),
-                    service_fee: row.get("service_fee"),
-                    created_at: row.get("created_at"),
-                    updated_at: row.get("updated_at"),
-                    submitted_at: row.get("submitted_at"),
-                    approved_at: row.get("approved_at"),
-                    rejected_at: row.get("rejected_at"),
-                    admin_notes: row.get("admin_notes"),
-                    rejection_reason: row.get("rejection_reason"),
-                })
-                .fetch_all(&self.pool)
-                .await?;
-
-        // Cache the result
-        if let Some(cache) = &self.cache {
-            let cache_key = Self::company_licenses_cache_key(company_id);
-            debug!("Caching company licenses: {}", company_id);
-
-            // Cache for 2 minutes
-            let _ = cache.set(&cache_key, &licenses, Some(120)).await;
-        }
-
-        Ok(licenses)
+        // Cache for 2 minutes
+        self.cached(
+            &Self::company_licenses_cache_key(company_id),
+            120,
+            |_: &Vec<License>| Some(vec![Self::company_tag(company_id)]),
+            || self.inner.get_licenses_by_company(company_id),
+        )
+        .await
     }
 
     #[instrument(skip(self, license))]
-    async fn update_license(&self, license: &License) -> Result<License, sqlx::Error> {
-        // Update in database
-        let query = r#"
-            UPDATE licenses
-            SET
-                license_number = $1,
-                license_type = $2,
-                company_id = $3,
-                user_id = $4,
-                title = $5,
-                description = $6,
-                issue_date = $7,
-                expiry_date = $8,
-                issuing_authority = $9,
-                application_status = $10,
-                priority = $11,
-                estimated_processing_days = $12,
-                actual_processing_days = $13,
-                external_reference_id = $14,
-                government_fee = $15,
-                service_fee = $16,
-                updated_at = $17,
-                submitted_at = $18,
-                approved_at = $19,
-                rejected_at = $20,
-                admin_notes = $21,
-                rejection_reason = $22
-            WHERE id = $23
-            RETURNING *
-        "#;
-
-        let updated = sqlx::query_as::<_, License>(query)
-            .bind(&license.license_number)
-            .bind(&license.license_type)
-            .bind(license.company_id)
-            .bind(license.user_id)
-            .bind(&license.title)
-            .bind(&license.description)
-            .bind(license.issue_date)
-            .bind(license.expiry_date)
-            .bind(&license.issuing_authority)
-            .bind(&license.application_status)
-            .bind(&license.priority)
-            .bind(license.estimated_processing_days)
-            .bind(license.actual_processing_days)
-            .bind(&license.external_reference_id)
-            .bind(license.government_fee)
-            .bind(license.service_fee)
-            .bind(license.updated_at)
-            .bind(license.submitted_at)
-            .bind(license.approved_at)
-            .bind(license.rejected_at)
-            .bind(&license.admin_notes)
-            .bind(&license.rejection_reason)
-            .bind(license.id)
-            .fetch_one(&self.pool)
-            .await?;
-
-        // Invalidate caches after update
-        self.invalidate_license_cache(updated.id, Some(updated.user_id), Some(updated.company_id))
-            .await;
-
-        if let Some(cache) = &self.cache {
-            let cache_key = Self::license_cache_key(updated.id);
-            let _ = cache.set(&cache_key, &updated, Some(300)).await;
-        }
-
+    async fn update_license(&self, license: &License) -> AppResult<License> {
+        // The owner or company may change, so the previous ones are invalidated too
+        let mut tags = self.stored_tags(license.id).await?;
+        let updated = self.conditional(license.id, self.inner.update_license(license)).await?;
+        tags.extend(Self::write_tags(&updated));
+        self.invalidate(tags).await;
         Ok(updated)
     }
 
     #[instrument(skip(self), fields(license_id = %id))]
     async fn delete_license(&self, id: Uuid) -> Result<bool, sqlx::Error> {
-        // First, get the license to know user_id and company_id for cache invalidation
-        let license = self.get_license_by_id(id).await?;
-
-        if let Some(license) = license {
-            // Delete the license
-            let result = sqlx::query("DELETE FROM licenses WHERE id = $1")
-                .bind(id)
-                .execute(&self.pool)
-                .await?;
-
-            // Invalidate caches
-            self.invalidate_license_cache(id, Some(license.user_id), Some(license.company_id))
-                .await;
-
-            Ok(result.rows_affected() > 0)
-        } else {
-            // License not found
-            Ok(false)
+        let mut tags = self.stored_tags(id).await?;
+        let deleted = self.inner.delete_license(id).await?;
+        if deleted {
+            // Documents and history go with it
+            tags.push(Self::documents_tag(id));
+            tags.push(Self::status_history_tag(id));
+            self.invalidate(tags).await;
         }
+        Ok(deleted)
     }
 
     #[instrument(skip(self))]
@@ -751,67 +387,14 @@ impl<C: Cache> LicenseRepository for CachedLicenseRepository<C> {
         &self,
         status: ApplicationStatus,
     ) -> Result<Vec<License>, sqlx::Error> {
-        // Try to get from cache first
-        if let Some(cache) = &self.cache {
-            let cache_key = Self::license_status_cache_key(status.clone());
-            debug!("Checking cache for licenses by status: {}", status);
-
-            match cache.get::<Vec<License>>(&cache_key).await {
-                Ok(Some(licenses)) => {
-                    debug!("Cache hit for licenses by status: {}", status);
-                    return Ok(licenses);
-                }
-                Ok(None) => debug!("Cache miss for licenses by status: {}", status),
-                Err(e) => error!("Cache error: {}", e),
-            }
-        }
-
-        // If not in cache or error, get from database
-        // Create a clone to avoid moving the original status
-        let status_clone = status.clone();
-        let licenses = sqlx::query(
-            "SELECT * FROM licenses WHERE application_status = $1 ORDER BY created_at DESC",
+        // Cache for 1 minute (shorter time for status-based queries which may change frequently)
+        self.cached(
+            &Self::license_status_cache_key(&status),
+            60,
+            |_: &Vec<License>| Some(vec![ALL_LICENSES_TAG.to_string()]),
+            || self.inner.get_licenses_by_status(status.clone()),
         )
-        .bind(status_clone)
-        .map(|row: sqlx::postgres::PgRow| License {
-            id: row.get("id"),
-            user_id: row.get("user_id"),
-            company_id: row.get("company_id"),
-            license_type: row.get("license_type"),
-            application_status: row.get("application_status"),
-            license_number: row.get("license_number"),
-            title: row.get("title"),
-            description: row.get("description"),
-            issue_date: row.get("issue_date"),
-            expiry_date: row.get("expiry_date"),
-            issuing_authority: row.get("issuing_authority"),
-            priority: row.get("priority"),
-            estimated_processing_days: row.get("estimated_processing_days"),
-            actual_processing_days: row.get("actual_processing_days"),
-            external_reference_id: row.get("external_reference_id"),
-            government_fee: row.get("government_fee"),
-            service_fee: row.get("service_fee"),
-            created_at: row.get("created_at"),
-            updated_at: row.get("updated_at"),
-            submitted_at: row.get("submitted_at"),
-            approved_at: row.get("approved_at"),
-            rejected_at: row.get("rejected_at"),
-            admin_notes: row.get("admin_notes"),
-            rejection_reason: row.get("rejection_reason"),
-        })
-        .fetch_all(&self.pool)
-        .await?;
-
-        // Cache the result
-        if let Some(cache) = &self.cache {
-            let cache_key = Self::license_status_cache_key(status.clone());
-            debug!("Caching licenses by status");
-
-            // Cache for 1 minute (shorter time for status-based queries which may change frequently)
-            let _ = cache.set(&cache_key, &licenses, Some(60)).await;
-        }
-
-        Ok(licenses)
+        .await
     }
 
     #[instrument(skip(self))]
@@ -819,281 +402,305 @@ impl<C: Cache> LicenseRepository for CachedLicenseRepository<C> {
         &self,
         license_type: LicenseType,
     ) -> Result<Vec<License>, sqlx::Error> {
-        // Try to get from cache first
-        if let Some(cache) = &self.cache {
-            let cache_key = Self::license_type_cache_key(license_type);
-            debug!("Checking cache for licenses by type: {}", license_type);
-
-            match cache.get::<Vec<License>>(&cache_key).await {
-                Ok(Some(licenses)) => {
-                    debug!("Cache hit for licenses by type: {}", license_type);
-                    return Ok(licenses);
-                }
-                Ok(None) => debug!("Cache miss for licenses by type: {}", license_type),
-                Err(e) => error!("Cache error: {}", e),
-            }
-        }
-
-        // If not in cache or error, get from database
-        let licenses =
-            sqlx::query("SELECT * FROM licenses WHERE license_type = $1 ORDER BY created_at DESC")
-                .bind(license_type)
-                .map(|row: sqlx::postgres::PgRow| License {
-                    id: row.get("id"),
-                    user_id: row.get("user_id"),
-                    company_id: row.get("company_id"),
-                    license_type: row.get("license_type"),
-                    application_status: row.get("application_status"),
-                    license_number: row.get("license_number"),
-                    title: row.get("title"),
-                    description: row.get("description"),
-                    issue_date: row.get("issue_date"),
-                    expiry_date: row.get("expiry_date"),
-                    issuing_authority: row.get("issuing_authority"),
-                    priority: row.get("priority"),
-                    estimated_processing_days: row.get("estimated_processing_days"),
-                    actual_processing_days: row.get("actual_processing_days"),
-                    external_reference_id: row.get("external_reference_id"),
-                    government_fee: row.get("government_fee"),
-                    service_fee: row.get("service_fee"),
-                    created_at: row.get("created_at"),
-                    updated_at: row.get("updated_at"),
-                    submitted_at: row.get("submitted_at"),
-                    approved_at: row.get("approved_at"),
-                    rejected_at: row.get("rejected_at"),
-                    admin_notes: row.get("admin_notes"),
-                    rejection_reason: row.get("rejection_reason"),
-                })
-                .fetch_all(&self.pool)
-                .await?;
-
-        // Cache the result
-        if let Some(cache) = &self.cache {
-            let cache_key = Self::license_type_cache_key(license_type);
-            debug!("Caching licenses by type");
-
-            // Cache for 5 minutes
-            let _ = cache.set(&cache_key, &licenses, Some(300)).await;
-        }
-
-        Ok(licenses)
+        // Cache for 5 minutes
+        self.cached(
+            &Self::license_type_cache_key(license_type),
+            300,
+            |_: &Vec<License>| Some(vec![ALL_LICENSES_TAG.to_string()]),
+            || self.inner.get_licenses_by_type(license_type),
+        )
+        .await
     }
 
     async fn get_expiring_licenses(&self, days_ahead: i32) -> Result<Vec<License>, sqlx::Error> {
-        let cache_key = format!("licenses:expiring:{}", days_ahead);
-
-        if let Some(cache) = &self.cache {
-            if let Ok(Some(licenses)) = cache.get::<Vec<License>>(&cache_key).await {
-                return Ok(licenses);
-            }
-        }
-
-        let query = r#"
-            SELECT * FROM licenses
-            WHERE expiry_date IS NOT NULL
-              AND expiry_date <= NOW() + ($1 || ' days')::INTERVAL
-            ORDER BY expiry_date ASC
-        "#;
-
-        let licenses = sqlx::query_as::<_, License>(query)
-            .bind(days_ahead)
-            .fetch_all(&self.pool)
-            .await?;
-
-        if let Some(cache) = &self.cache {
-            let _ = cache.set(&cache_key, &licenses, Some(300)).await;
-        }
-
-        Ok(licenses)
+        self.cached(
+            &format!("licenses:expiring:{}", days_ahead),
+            300,
+            |_: &Vec<License>| Some(vec![ALL_LICENSES_TAG.to_string()]),
+            || self.inner.get_expiring_licenses(days_ahead),
+        )
+        .await
     }
 
-    async fn search_licenses(
+    // Pages are not cached: cursor and filter combinations make poor keys and
+    // would all need invalidating on every license write
+    async fn list_licenses(
         &self,
-        query: &str,
-        user_id: Option<Uuid>,
-    ) -> Result<Vec<License>, sqlx::Error> {
-        let cache_key = format!(
-            "licenses:search:{}:{}",
-            user_id
-                .map(|u| u.to_string())
-                .unwrap_or_else(|| "all".into()),
-            query
-        );
-
-        if let Some(cache) = &self.cache {
-            if let Ok(Some(licenses)) = cache.get::<Vec<License>>(&cache_key).await {
-                return Ok(licenses);
-            }
-        }
-
-        let like_query = format!("%{}%", query);
-
-        let sql = if user_id.is_some() {
-            r#"
-                SELECT * FROM licenses
-                WHERE user_id = $1
-                  AND (title ILIKE $2 OR license_number ILIKE $2)
-                ORDER BY created_at DESC
-            "#
-        } else {
-            r#"
-                SELECT * FROM licenses
-                WHERE title ILIKE $1 OR license_number ILIKE $1
-                ORDER BY created_at DESC
-            "#
-        };
-
-        let licenses = if let Some(uid) = user_id {
-            sqlx::query_as::<_, License>(sql)
-                .bind(uid)
-                .bind(&like_query)
-                .fetch_all(&self.pool)
-                .await?
-        } else {
-            sqlx::query_as::<_, License>(sql)
-                .bind(&like_query)
-                .fetch_all(&self.pool)
-                .await?
-        };
-
-        if let Some(cache) = &self.cache {
-            let _ = cache.set(&cache_key, &licenses, Some(60)).await;
-        }
+        query: &ListQuery<LicenseFilter, LicenseSortField>,
+    ) -> Result<Page<License>, sqlx::Error> {
+        self.inner.list_licenses(query).await
+    }
 
-        Ok(licenses)
+    async fn list_documents(
+        &self,
+        query: &ListQuery<DocumentFilter, DocumentSortField>,
+    ) -> Result<Page<LicenseDocument>, sqlx::Error> {
+        self.inner.list_documents(query).await
     }
 
+    #[instrument(skip(self, document))]
     async fn create_document(
         &self,
         document: &LicenseDocument,
     ) -> Result<LicenseDocument, sqlx::Error> {
-        Ok(document.clone())
+        let inserted = self.inner.create_document(document).await?;
+        self.invalidate(vec![Self::documents_tag(inserted.license_id)]).await;
+        Ok(inserted)
     }
 
+    #[instrument(skip(self), fields(license_id = %license_id))]
     async fn get_documents_by_license(
         &self,
-        _license_id: Uuid,
+        license_id: Uuid,
     ) -> Result<Vec<LicenseDocument>, sqlx::Error> {
-        Ok(vec![])
-    }
-
-    async fn get_document_by_id(&self, _id: Uuid) -> Result<Option<LicenseDocument>, sqlx::Error> {
-        Ok(None)
+        self.cached(
+            &Self::license_documents_cache_key(license_id),
+            300,
+            |_: &Vec<LicenseDocument>| Some(vec![Self::documents_tag(license_id)]),
+            || self.inner.get_documents_by_license(license_id),
+        )
+        .await
+    }
+
+    #[instrument(skip(self), fields(document_id = %id))]
+    async fn get_document_by_id(&self, id: Uuid) -> Result<Option<LicenseDocument>, sqlx::Error> {
+        self.cached(
+            &Self::document_cache_key(id),
+            300,
+            |document: &Option<LicenseDocument>| {
+                document.as_ref().map(|d| vec![Self::documents_tag(d.license_id)])
+            },
+            || self.inner.get_document_by_id(id),
+        )
+        .await
     }
 
+    #[instrument(skip(self, document))]
     async fn update_document(
         &self,
         document: &LicenseDocument,
     ) -> Result<LicenseDocument, sqlx::Error> {
-        Ok(document.clone())
+        // The update keeps the stored license_id, whatever the caller passed
+        let license_id = self.document_license(document.id).await?;
+        let updated = self.inner.update_document(document).await?;
+        let license_id = license_id.unwrap_or(updated.license_id);
+        self.invalidate(vec![Self::documents_tag(license_id)]).await;
+        Ok(updated)
     }
 
-    async fn delete_document(&self, _id: Uuid) -> Result<bool, sqlx::Error> {
-        Ok(true)
+    #[instrument(skip(self), fields(document_id = %id))]
+    async fn delete_document(&self, id: Uuid) -> Result<bool, sqlx::Error> {
+        let license_id = self.document_license(id).await?;
+        let deleted = self.inner.delete_document(id).await?;
+        if let Some(license_id) = license_id {
+            self.invalidate(vec![Self::documents_tag(license_id)]).await;
+        }
+        Ok(deleted)
     }
 
     async fn create_status_history(
         &self,
         history: &ApplicationStatusHistory,
     ) -> Result<ApplicationStatusHistory, sqlx::Error> {
-        Ok(history.clone())
+        let inserted = self.inner.create_status_history(history).await?;
+        self.invalidate(vec![Self::status_history_tag(inserted.license_id)]).await;
+        Ok(inserted)
     }
 
     async fn get_status_history_by_license(
         &self,
-        _license_id: Uuid,
+        license_id: Uuid,
     ) -> Result<Vec<ApplicationStatusHistory>, sqlx::Error> {
-        Ok(vec![])
+        self.cached(
+            &Self::license_status_history_cache_key(license_id),
+            300,
+            |_: &Vec<ApplicationStatusHistory>| Some(vec![Self::status_history_tag(license_id)]),
+            || self.inner.get_status_history_by_license(license_id),
+        )
+        .await
     }
 
+    #[instrument(skip(self))]
     async fn submit_license_application(
         &self,
-        _license_id: Uuid,
-        _user_id: Uuid,
+        license_id: Uuid,
+        user_id: Uuid,
     ) -> Result<License, sqlx::Error> {
-        // Placeholder implementation
-        Err(sqlx::Error::RowNotFound)
+        let license = self.inner.submit_license_application(license_id, user_id).await?;
+        self.invalidate(Self::write_tags(&license)).await;
+        Ok(license)
     }
 
+    #[instrument(skip(self, license_number, issuing_authority, admin_notes))]
     async fn approve_license(
         &self,
-        _license_id: Uuid,
-        _admin_user_id: Uuid,
-        _license_number: String,
-        _issue_date: DateTime<Utc>,
-        _expiry_date: Option<DateTime<Utc>>,
-        _issuing_authority: String,
-        _admin_notes: Option<String>,
-    ) -> Result<License, sqlx::Error> {
-        Err(sqlx::Error::RowNotFound)
+        license_id: Uuid,
+        expected_version: i64,
+        admin_user_id: Uuid,
+        license_number: String,
+        issue_date: DateTime<Utc>,
+        expiry_date: Option<DateTime<Utc>>,
+        issuing_authority: String,
+        admin_notes: Option<String>,
+    ) -> AppResult<License> {
+        let approve = self.inner.approve_license(
+            license_id,
+            expected_version,
+            admin_user_id,
+            license_number,
+            issue_date,
+            expiry_date,
+            issuing_authority,
+            admin_notes,
+        );
+        let license = self.conditional(license_id, approve).await?;
+        self.invalidate(Self::write_tags(&license)).await;
+        Ok(license)
     }
 
+    #[instrument(skip(self, reason, admin_notes))]
     async fn reject_license(
         &self,
-        _license_id: Uuid,
-        _admin_user_id: Uuid,
-        _reason: String,
-        _admin_notes: Option<String>,
-    ) -> Result<License, sqlx::Error> {
-        Err(sqlx::Error::RowNotFound)
+        license_id: Uuid,
+        expected_version: i64,
+        admin_user_id: Uuid,
+        reason: String,
+        admin_notes: Option<String>,
+    ) -> AppResult<License> {
+        let reject = self.inner.reject_license(
+            license_id,
+            expected_version,
+            admin_user_id,
+            reason,
+            admin_notes,
+        );
+        let license = self.conditional(license_id, reject).await?;
+        self.invalidate(Self::write_tags(&license)).await;
+        Ok(license)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tokio::sync::Mutex;
-    use std::collections::HashMap;
-    use sqlx::postgres::PgPoolOptions;
-    use crate::domain::licenses::{PriorityLevel};
+    async fn get_license_count_by_type(&self) -> Result<Vec<(LicenseType, i64)>, sqlx::Error> {
+        self.cached(
+            "analytics:licenses:type_counts",
+            300,
+            |_: &Vec<(LicenseType, i64)>| Some(vec![ALL_LICENSES_TAG.to_string()]),
+            || self.inner.get_license_count_by_type(),
+        )
+        .await
+    }
 
-    #[derive(Clone, Default)]
-    struct InMemoryCache {
-        store: Arc<Mutex<HashMap<String, String>>>,
+    async fn get_license_count_by_status(
+        &self,
+    ) -> Result<Vec<(ApplicationStatus, i64)>, sqlx::Error> {
+        self.cached(
+            "analytics:licenses:status_counts",
+            300,
+            |_: &Vec<(ApplicationStatus, i64)>| Some(vec![ALL_LICENSES_TAG.to_string()]),
+            || self.inner.get_license_count_by_status(),
+        )
+        .await
     }
 
-    #[async_trait]
-    impl Cache for InMemoryCache {
-        async fn set<T: serde::Serialize + Send + Sync>(
-            &self,
-            key: &str,
-            value: &T,
-            _expiry: Option<u64>,
-        ) -> Result<(), redis::RedisError> {
-            let mut map = self.store.lock().await;
-            map.insert(key.to_string(), serde_json::to_string(value).unwrap());
-            Ok(())
-        }
+    async fn get_processing_times(&self) -> Result<Vec<(LicenseType, f64)>, sqlx::Error> {
+        self.cached(
+            "analytics:licenses:processing_times",
+            300,
+            |_: &Vec<(LicenseType, f64)>| Some(vec![ALL_LICENSES_TAG.to_string()]),
+            || self.inner.get_processing_times(),
+        )
+        .await
+    }
 
-        async fn get<T: serde::de::DeserializeOwned + Send + Sync>(
-            &self,
-            key: &str,
-        ) -> Result<Option<T>, redis::RedisError> {
-            let map = self.store.lock().await;
-            Ok(match map.get(key) {
-                Some(v) => Some(serde_json::from_str(v).unwrap()),
-                None => None,
-            })
-        }
+    async fn get_license_statistics(
+        &self,
+        user_id: Option<Uuid>,
+    ) -> Result<LicenseStatistics, sqlx::Error> {
+        let tag = match user_id {
+            Some(uid) => Self::user_tag(uid),
+            None => ALL_LICENSES_TAG.to_string(),
+        };
+
+        // Cache for 2 minutes
+        self.cached(
+            &Self::statistics_cache_key(user_id),
+            120,
+            |_: &LicenseStatistics| Some(vec![tag]),
+            || self.inner.get_license_statistics(user_id),
+        )
+        .await
+    }
+}
 
-        async fn delete(&self, key: &str) -> Result<(), redis::RedisError> {
-            let mut map = self.store.lock().await;
-            map.remove(key);
-            Ok(())
+/// Begins units of work over the Postgres license repository, behind the
+/// cache when there is one
+pub struct LicenseUnitOfWork<C: Cache> {
+    postgres: PgUnitOfWork,
+    cache: Option<Arc<C>>,
+}
+
+impl<C: Cache> LicenseUnitOfWork<C> {
+    pub fn new(pool: PgPool, cache: Option<Arc<C>>) -> Self {
+        Self {
+            postgres: PgUnitOfWork::new(pool),
+            cache,
         }
+    }
+}
+
+#[async_trait]
+impl<C: Cache + 'static> UnitOfWork<LicenseRepositories> for LicenseUnitOfWork<C> {
+    async fn begin(&self) -> AppResult<Work<LicenseRepositories>> {
+        let work = self.postgres.begin_work().await?;
+        let deferred = Arc::new(Mutex::new(Vec::new()));
+        let licenses = CachedLicenseRepository {
+            deferred: Some(deferred.clone()),
+            ..CachedLicenseRepository::from_inner(
+                Arc::new(PostgresLicenseRepositoryImpl::within(&work)),
+                self.cache.clone(),
+            )
+        };
+        let handle = CommitThenInvalidate {
+            work,
+            cache: self.cache.clone(),
+            tags: deferred,
+        };
+        Ok(Work::new(
+            LicenseRepositories {
+                licenses: Box::new(licenses),
+            },
+            Box::new(handle),
+        ))
+    }
+}
 
-        async fn delete_by_pattern(&self, pattern: &str) -> Result<(), redis::RedisError> {
-            let mut map = self.store.lock().await;
-            let prefix = pattern.trim_end_matches('*');
-            let keys: Vec<String> = map
-                .keys()
-                .filter(|k| k.starts_with(prefix))
-                .cloned()
-                .collect();
-            for k in keys { map.remove(&k); }
-            Ok(())
+/// Commits the database transaction, then drops what it made stale
+struct CommitThenInvalidate<C: Cache> {
+    work: PgWork,
+    cache: Option<Arc<C>>,
+    tags: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl<C: Cache + 'static> WorkHandle for CommitThenInvalidate<C> {
+    async fn commit(self: Box<Self>) -> AppResult<()> {
+        self.work.commit().await?;
+        let tags = std::mem::take(&mut *self.tags.lock().unwrap());
+        if let (Some(cache), false) = (&self.cache, tags.is_empty()) {
+            debug!("Invalidating license cache tags {:?}", tags);
+            if let Err(e) = cache.invalidate_tags(&tags).await {
+                error!("Cache error: {}", e);
+            }
         }
+        Ok(())
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::cache::InMemoryCache;
+    use sqlx::postgres::PgPoolOptions;
+    use crate::domain::licenses::{PriorityLevel};
 
     fn sample_license() -> License {
         License {
@@ -1121,6 +728,9 @@ mod tests {
             rejected_at: None,
             admin_notes: None,
             rejection_reason: None,
+            version: 1,
+            sla_paused_at: None,
+            sla_paused_seconds: 0,
         }
     }
 
@@ -0,0 +1,134 @@
+// Caching decorator for UserRepository
+// Caches the per-request lookups (by id and by email) with single-flight
+// loading. Both entries are tagged with the user id, so saving or deleting a
+// user also drops the entry under a previous email.
+
+use async_trait::async_trait;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::domain::entities::User;
+use crate::domain::filters::{UserFilter, UserSortField};
+use crate::domain::repositories::UserRepository;
+use crate::domain::value_objects::{Email, UserId};
+use crate::infrastructure::cache::{cache_aside, Cache, SingleFlight};
+use crate::shared::errors::AppResult;
+use crate::shared::query::{ListQuery, Page};
+
+const USER_TTL_SECS: u64 = 300;
+
+pub struct CachedUserRepository<C: Cache> {
+    inner: Arc<dyn UserRepository + Send + Sync>,
+    cache: Arc<C>,
+    flights: SingleFlight,
+}
+
+impl<C: Cache> CachedUserRepository<C> {
+    pub fn new(inner: Arc<dyn UserRepository + Send + Sync>, cache: Arc<C>) -> Self {
+        Self {
+            inner,
+            cache,
+            flights: SingleFlight::new(),
+        }
+    }
+
+    fn user_tag(id: &UserId) -> String {
+        format!("user:{}", id)
+    }
+
+    async fn invalidate(&self, id: &UserId) {
+        if let Err(err) = self.cache.invalidate_tags(&[Self::user_tag(id)]).await {
+            warn!("Failed to invalidate user cache for {}: {}", id, err);
+        }
+    }
+}
+
+#[async_trait]
+impl<C: Cache + 'static> UserRepository for CachedUserRepository<C> {
+    async fn find_by_id(&self, id: &UserId) -> AppResult<Option<User>> {
+        cache_aside(
+            self.cache.as_ref(),
+            &self.flights,
+            &format!("user:{}", id),
+            USER_TTL_SECS,
+            |user: &Option<User>| user.as_ref().map(|user| vec![Self::user_tag(&user.id)]),
+            || self.inner.find_by_id(id),
+        )
+        .await
+    }
+
+    async fn find_by_email(&self, email: &Email) -> AppResult<Option<User>> {
+        cache_aside(
+            self.cache.as_ref(),
+            &self.flights,
+            &format!("user:email:{}", email.as_str()),
+            USER_TTL_SECS,
+            |user: &Option<User>| user.as_ref().map(|user| vec![Self::user_tag(&user.id)]),
+            || self.inner.find_by_email(email),
+        )
+        .await
+    }
+
+    async fn save(&self, user: &User) -> AppResult<()> {
+        self.inner.save(user).await?;
+        self.invalidate(&user.id).await;
+        Ok(())
+    }
+
+    async fn delete(&self, id: &UserId) -> AppResult<()> {
+        self.inner.delete(id).await?;
+        self.invalidate(id).await;
+        Ok(())
+    }
+
+    async fn list_all(&self, limit: Option<i32>, offset: Option<i32>) -> AppResult<Vec<User>> {
+        self.inner.list_all(limit, offset).await
+    }
+
+    async fn count_all(&self) -> AppResult<i64> {
+        self.inner.count_all().await
+    }
+
+    async fn search(&self, query: &str, limit: Option<i32>, offset: Option<i32>) -> AppResult<Vec<User>> {
+        self.inner.search(query, limit, offset).await
+    }
+
+    async fn list(&self, query: &ListQuery<UserFilter, UserSortField>) -> AppResult<Page<User>> {
+        self.inner.list(query).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::UserRole;
+    use crate::infrastructure::cache::InMemoryCache;
+    use crate::infrastructure::repositories::in_memory_user_repository::InMemoryUserRepository;
+
+    #[tokio::test]
+    async fn test_lookups_are_cached_until_the_user_is_saved() {
+        let inner = Arc::new(InMemoryUserRepository::new());
+        let cache = Arc::new(InMemoryCache::new());
+        let repo = CachedUserRepository::new(inner.clone(), cache.clone());
+
+        let email = Email::new("pemilik@umkm.id").unwrap();
+        let mut user = User::new(email.clone(), "hash".into(), "Sari".into(), UserRole::UmkmOwner);
+        inner.save(&user).await.unwrap();
+
+        assert_eq!(repo.find_by_email(&email).await.unwrap().unwrap().full_name, "Sari");
+        assert!(repo.find_by_id(&user.id).await.unwrap().is_some());
+
+        // A write behind the decorator's back is not seen until invalidation
+        user.full_name = "Sari Dewi".into();
+        inner.save(&user).await.unwrap();
+        assert_eq!(repo.find_by_email(&email).await.unwrap().unwrap().full_name, "Sari");
+
+        repo.save(&user).await.unwrap();
+        assert!(!cache.contains(&format!("user:{}", user.id)));
+        assert_eq!(repo.find_by_email(&email).await.unwrap().unwrap().full_name, "Sari Dewi");
+
+        repo.delete(&user.id).await.unwrap();
+        assert!(repo.find_by_id(&user.id).await.unwrap().is_none());
+        assert!(repo.find_by_email(&email).await.unwrap().is_none());
+    }
+}
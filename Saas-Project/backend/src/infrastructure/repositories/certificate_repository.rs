@@ -0,0 +1,297 @@
+// PostgreSQL implementation of the license certificate repository
+// Revisions are numbered in the INSERT itself; two revisions recorded for the
+// same license at once collide on the (license_id, revision) unique constraint
+// and the later one fails with a conflict instead of sharing a number.
+
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use crate::domain::certificates::{CertificateRepository, LicenseCertificate};
+use crate::shared::errors::{AppError, AppResult};
+
+const CERTIFICATE_COLUMNS: &str = "id, license_id, revision, reason, license_status, \
+    license_version, storage_key, sha256, size_bytes, generated_by, generated_at";
+
+fn row_to_certificate(row: &PgRow) -> Result<LicenseCertificate, AppError> {
+    let reason: String = row.try_get("reason")?;
+    Ok(LicenseCertificate {
+        id: row.try_get("id")?,
+        license_id: row.try_get("license_id")?,
+        revision: row.try_get("revision")?,
+        reason: reason.parse().map_err(AppError::InternalError)?,
+        license_status: row.try_get("license_status")?,
+        license_version: row.try_get("license_version")?,
+        storage_key: row.try_get("storage_key")?,
+        sha256: row.try_get("sha256")?,
+        size_bytes: row.try_get("size_bytes")?,
+        generated_by: row.try_get("generated_by")?,
+        generated_at: row.try_get("generated_at")?,
+    })
+}
+
+#[derive(Clone)]
+pub struct PostgresCertificateRepository {
+    pool: PgPool,
+}
+
+impl PostgresCertificateRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl CertificateRepository for PostgresCertificateRepository {
+    async fn record_certificate(&self, certificate: &LicenseCertificate) -> AppResult<LicenseCertificate> {
+        let row = sqlx::query(&format!(
+            "INSERT INTO license_certificates ({columns}) \
+             SELECT $1, $2, COALESCE(MAX(revision), 0) + 1, $3, $4, $5, $6, $7, $8, $9, $10 \
+             FROM license_certificates WHERE license_id = $2 \
+             RETURNING {columns}",
+            columns = CERTIFICATE_COLUMNS
+        ))
+        .bind(certificate.id)
+        .bind(certificate.license_id)
+        .bind(certificate.reason.to_string())
+        .bind(&certificate.license_status)
+        .bind(certificate.license_version)
+        .bind(&certificate.storage_key)
+        .bind(&certificate.sha256)
+        .bind(certificate.size_bytes)
+        .bind(certificate.generated_by)
+        .bind(certificate.generated_at)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::Database(db) if db.is_unique_violation() => AppError::Conflict(
+                "Another certificate was generated for this license at the same time".to_string(),
+            ),
+            err => err.into(),
+        })?;
+
+        row_to_certificate(&row)
+    }
+
+    async fn latest_certificate(&self, license_id: Uuid) -> AppResult<Option<LicenseCertificate>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM license_certificates WHERE license_id = $1 \
+             ORDER BY revision DESC LIMIT 1",
+            CERTIFICATE_COLUMNS
+        ))
+        .bind(license_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(row_to_certificate).transpose()
+    }
+
+    async fn certificates(&self, license_id: Uuid) -> AppResult<Vec<LicenseCertificate>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM license_certificates WHERE license_id = $1 ORDER BY revision",
+            CERTIFICATE_COLUMNS
+        ))
+        .bind(license_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_certificate).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::domain::certificates::CertificateReason;
+    use crate::domain::licenses::{ApplicationStatus, License, LicenseType};
+    use crate::domain::unit_of_work::UnitOfWork;
+    use crate::infrastructure::cache::InMemoryCache;
+    use crate::infrastructure::repositories::testing::{in_memory, Fixture, TestDatabase};
+    use crate::infrastructure::repositories::{
+        LicenseRepositories, LicenseRepository, LicenseUnitOfWork, PostgresLicenseRepositoryImpl,
+    };
+    use crate::services::license_processing::LicenseProcessingService;
+    use crate::services::license_processing_models::{LicenseAction, LicenseDecision};
+
+    /// Suspensions, reinstatements and renewals through the unit of work, and
+    /// the certificate revisions recorded for them
+    async fn run_certificate_scenario(
+        unit_of_work: &dyn UnitOfWork<LicenseRepositories>,
+        repo: &dyn LicenseRepository,
+        certificates: &dyn CertificateRepository,
+        fx: &Fixture,
+    ) {
+        let service = LicenseProcessingService::new();
+        let license = fx.license(LicenseType::Siup, fx.company_id, fx.owner_id, "SIUP Toko", 0);
+        repo.create_license(&license).await.unwrap();
+        let submitted = repo.submit_license_application(license.id, fx.owner_id).await.unwrap();
+        let processing = repo
+            .update_license(&License {
+                application_status: ApplicationStatus::Processing,
+                ..submitted
+            })
+            .await
+            .unwrap();
+        let expiry = fx.at(0) + Duration::days(365);
+        let approved = service
+            .decide(
+                unit_of_work,
+                license.id,
+                processing.version,
+                fx.admin_id,
+                LicenseDecision::Approve {
+                    license_number: "SIUP/2024/0007".to_string(),
+                    issue_date: fx.at(0),
+                    expiry_date: Some(expiry),
+                    issuing_authority: "DPMPTSP Bandung".to_string(),
+                    admin_notes: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        // Suspending and reinstating, each recorded in the history
+        let suspended = service
+            .act(
+                unit_of_work,
+                license.id,
+                approved.version,
+                fx.admin_id,
+                LicenseAction::Suspend {
+                    reason: "Lokasi usaha tidak sesuai".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(suspended.application_status, ApplicationStatus::Suspended);
+        assert_eq!(suspended.version, approved.version + 1);
+        let stale = service
+            .act(
+                unit_of_work,
+                license.id,
+                approved.version,
+                fx.admin_id,
+                LicenseAction::Reinstate { admin_notes: None },
+            )
+            .await;
+        assert!(matches!(stale, Err(AppError::VersionConflict { .. })));
+
+        // Renewing a suspended license is refused and leaves nothing behind
+        let refused = service
+            .act(
+                unit_of_work,
+                license.id,
+                suspended.version,
+                fx.admin_id,
+                LicenseAction::Renew {
+                    expiry_date: expiry + Duration::days(365),
+                    admin_notes: None,
+                },
+            )
+            .await;
+        assert!(matches!(refused, Err(AppError::Validation(_))));
+
+        let reinstated = service
+            .act(
+                unit_of_work,
+                license.id,
+                suspended.version,
+                fx.admin_id,
+                LicenseAction::Reinstate { admin_notes: None },
+            )
+            .await
+            .unwrap();
+        let renewed = service
+            .act(
+                unit_of_work,
+                license.id,
+                reinstated.version,
+                fx.admin_id,
+                LicenseAction::Renew {
+                    expiry_date: expiry + Duration::days(365),
+                    admin_notes: Some("Perpanjangan tahunan".to_string()),
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(renewed.application_status, ApplicationStatus::Approved);
+        assert_eq!(renewed.expiry_date, Some(expiry + Duration::days(365)));
+        assert_eq!(repo.get_license_by_id(license.id).await.unwrap(), Some(renewed.clone()));
+
+        let mut history = repo.get_status_history_by_license(license.id).await.unwrap();
+        history.sort_by_key(|h| h.changed_at);
+        assert_eq!(
+            history
+                .iter()
+                .map(|h| (h.from_status.clone(), h.to_status.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some(ApplicationStatus::Processing), ApplicationStatus::Approved),
+                (Some(ApplicationStatus::Approved), ApplicationStatus::Suspended),
+                (Some(ApplicationStatus::Suspended), ApplicationStatus::Approved),
+                (Some(ApplicationStatus::Approved), ApplicationStatus::Approved),
+            ]
+        );
+        assert_eq!(history[1].notes.as_deref(), Some("Lokasi usaha tidak sesuai"));
+        assert_eq!(
+            history[3].notes.as_deref(),
+            Some(format!("Renewed until {}: Perpanjangan tahunan", (expiry + Duration::days(365)).format("%Y-%m-%d")).as_str())
+        );
+
+        // Certificate revisions are numbered per license
+        assert_eq!(certificates.latest_certificate(license.id).await.unwrap(), None);
+        let first = certificates
+            .record_certificate(&LicenseCertificate::new(&approved, CertificateReason::Issued, None, "aa".to_string(), 10))
+            .await
+            .unwrap();
+        let second = certificates
+            .record_certificate(&LicenseCertificate::new(
+                &suspended,
+                CertificateReason::Suspended,
+                Some(fx.admin_id),
+                "bb".to_string(),
+                20,
+            ))
+            .await
+            .unwrap();
+        assert_eq!((first.revision, second.revision), (1, 2));
+        assert_eq!(second.license_status, ApplicationStatus::Suspended);
+        assert_eq!(certificates.latest_certificate(license.id).await.unwrap(), Some(second.clone()));
+        assert_eq!(certificates.certificates(license.id).await.unwrap(), vec![first, second]);
+
+        let orphan = fx.license(LicenseType::Nib, fx.company_id, fx.owner_id, "NIB", 0);
+        assert!(certificates
+            .record_certificate(&LicenseCertificate::new(&orphan, CertificateReason::Issued, None, "cc".to_string(), 30))
+            .await
+            .is_err());
+
+        // Certificates go with the license
+        repo.delete_license(license.id).await.unwrap();
+        assert!(certificates.certificates(license.id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn in_memory_certificates_conform() {
+        let fx = Fixture::new();
+        let repo = in_memory(&fx);
+        run_certificate_scenario(&repo, &repo, &repo, &fx).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn postgres_certificates_conform() {
+        let fx = Fixture::new();
+        let db = TestDatabase::seeded(&fx).await;
+        run_certificate_scenario(
+            &LicenseUnitOfWork::<InMemoryCache>::new(db.pool.clone(), None),
+            &PostgresLicenseRepositoryImpl::new(db.pool.clone()),
+            &PostgresCertificateRepository::new(db.pool.clone()),
+            &fx,
+        )
+        .await;
+        db.destroy().await;
+    }
+}
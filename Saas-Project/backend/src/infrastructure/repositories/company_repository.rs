@@ -2,12 +2,17 @@
 // Implements CompanyRepository trait with SQLx for database operations
 
 use async_trait::async_trait;
+use sea_query::{extension::postgres::PgExpr, Alias, Asterisk, Cond, Expr, Query, SelectStatement};
 use sqlx::{PgPool, Row};
 use uuid::Uuid;
 
 use crate::domain::companies::Company;
+use crate::domain::filters::{CompanyFilter, CompanySortField};
 use crate::domain::repositories::CompanyRepository;
+use crate::infrastructure::database::list_query::{contains_pattern, escape_like, fetch_page};
+use crate::infrastructure::database::versioning::stale_write;
 use crate::shared::errors::{AppError, AppResult};
+use crate::shared::query::{ListQuery, Page};
 
 pub struct PostgresCompanyRepository {
     pool: PgPool,
@@ -17,6 +22,46 @@ impl PostgresCompanyRepository {
     pub fn new(pool: PgPool) -> Self {
         Self { pool }
     }
+
+    /// Filtered select over companies, without ordering or paging
+    fn list_select(filter: &CompanyFilter) -> SelectStatement {
+        let mut cond = Cond::all();
+
+        if let Some(owner_id) = filter.owner_id {
+            cond = cond.add(Expr::col(Alias::new("owner_id")).eq(owner_id));
+        }
+        if let Some(status) = &filter.status {
+            cond = cond.add(Expr::col(Alias::new("status")).eq(status.to_string()));
+        }
+        if let Some(scale) = &filter.business_scale {
+            cond = cond.add(Expr::col(Alias::new("business_scale")).eq(scale.to_string()));
+        }
+        if let Some(province) = filter.province.as_deref() {
+            cond = cond.add(Expr::col(Alias::new("address_province")).ilike(escape_like(province).as_str()));
+        }
+        if let Some(city) = filter.city.as_deref() {
+            cond = cond.add(Expr::col(Alias::new("address_city")).ilike(escape_like(city).as_str()));
+        }
+        if let Some(is_verified) = filter.is_verified {
+            cond = cond.add(Expr::col(Alias::new("is_verified")).eq(is_verified));
+        }
+        if let Some(search) = filter.search.as_deref().filter(|s| !s.trim().is_empty()) {
+            let pattern = contains_pattern(search.trim());
+            cond = cond.add(
+                Cond::any()
+                    .add(Expr::col(Alias::new("company_name")).ilike(pattern.as_str()))
+                    .add(Expr::col(Alias::new("industry_sector")).ilike(pattern.as_str()))
+                    .add(Expr::col(Alias::new("address_city")).ilike(pattern.as_str()))
+                    .add(Expr::col(Alias::new("nib")).ilike(pattern.as_str())),
+            );
+        }
+
+        Query::select()
+            .column(Asterisk)
+            .from(Alias::new("companies"))
+            .cond_where(cond)
+            .to_owned()
+    }
 }
 
 #[async_trait]
@@ -33,7 +78,7 @@ impl CompanyRepository for PostgresCompanyRepository {
                 business_scale, annual_revenue, annual_revenue_year,
                 is_verified, verification_date, verification_notes,
                 bank_name, bank_account_number, bank_account_holder,
-                logo_url, documents, status, created_at, updated_at
+                logo_url, documents, status, created_at, updated_at, version
             FROM companies 
             WHERE id = $1
         "#;
@@ -81,6 +126,7 @@ impl CompanyRepository for PostgresCompanyRepository {
                     status: row.get("status"),
                     created_at: row.get("created_at"),
                     updated_at: row.get("updated_at"),
+                version: row.get("version"),
                 };
                 Ok(Some(company))
             }
@@ -100,7 +146,7 @@ impl CompanyRepository for PostgresCompanyRepository {
                 business_scale, annual_revenue, annual_revenue_year,
                 is_verified, verification_date, verification_notes,
                 bank_name, bank_account_number, bank_account_holder,
-                logo_url, documents, status, created_at, updated_at
+                logo_url, documents, status, created_at, updated_at, version
             FROM companies 
             WHERE owner_id = $1
             ORDER BY created_at DESC
@@ -149,6 +195,7 @@ impl CompanyRepository for PostgresCompanyRepository {
                 status: row.get("status"),
                 created_at: row.get("created_at"),
                 updated_at: row.get("updated_at"),
+                version: row.get("version"),
             })
             .collect();
 
@@ -167,7 +214,7 @@ impl CompanyRepository for PostgresCompanyRepository {
                 business_scale, annual_revenue, annual_revenue_year,
                 is_verified, verification_date, verification_notes,
                 bank_name, bank_account_number, bank_account_holder,
-                logo_url, documents, status, created_at, updated_at
+                logo_url, documents, status, created_at, updated_at, version
             FROM companies 
             WHERE nib = $1
         "#;
@@ -215,6 +262,7 @@ impl CompanyRepository for PostgresCompanyRepository {
                     status: row.get("status"),
                     created_at: row.get("created_at"),
                     updated_at: row.get("updated_at"),
+                version: row.get("version"),
                 };
                 Ok(Some(company))
             }
@@ -297,7 +345,7 @@ impl CompanyRepository for PostgresCompanyRepository {
                 is_verified = $23, verification_date = $24, verification_notes = $25,
                 bank_name = $26, bank_account_number = $27, bank_account_holder = $28,
                 logo_url = $29, documents = $30, status = $31, updated_at = $32
-            WHERE id = $1
+            WHERE id = $1 AND version = $33
         "#;
 
         let result = sqlx::query(query)
@@ -333,12 +381,13 @@ impl CompanyRepository for PostgresCompanyRepository {
             .bind(&company.documents)
             .bind(&company.status)
             .bind(&company.updated_at)
+            .bind(company.version)
             .execute(&self.pool)
             .await
             .map_err(|e| AppError::Database(e))?;
 
         if result.rows_affected() == 0 {
-            return Err(AppError::NotFound("Company not found".to_string()));
+            return Err(stale_write(&self.pool, "companies", "Company", company.id).await);
         }
 
         Ok(())
@@ -375,7 +424,7 @@ impl CompanyRepository for PostgresCompanyRepository {
                 business_scale, annual_revenue, annual_revenue_year,
                 is_verified, verification_date, verification_notes,
                 bank_name, bank_account_number, bank_account_holder,
-                logo_url, documents, status, created_at, updated_at
+                logo_url, documents, status, created_at, updated_at, version
             FROM companies 
             ORDER BY created_at DESC
             LIMIT $1 OFFSET $2
@@ -425,6 +474,7 @@ impl CompanyRepository for PostgresCompanyRepository {
                 status: row.get("status"),
                 created_at: row.get("created_at"),
                 updated_at: row.get("updated_at"),
+                version: row.get("version"),
             })
             .collect();
 
@@ -443,86 +493,12 @@ impl CompanyRepository for PostgresCompanyRepository {
         Ok(row.get::<i64, _>("count"))
     }
 
-    async fn search(
-        &self,
-        query: &str,
-        limit: Option<i32>,
-        offset: Option<i32>,
-    ) -> AppResult<Vec<Company>> {
-        let limit = limit.unwrap_or(50);
-        let offset = offset.unwrap_or(0);
-        let search_query = format!("%{}%", query);
-
-        let query = r#"
-            SELECT 
-                id, owner_id, company_name, business_type, industry_sector,
-                description, establishment_date, employee_count,
-                nib, siup_number, tdp_number, npwp_company,
-                email, phone, website,
-                address_street, address_city, address_province, 
-                address_postal_code, address_country,
-                business_scale, annual_revenue, annual_revenue_year,
-                is_verified, verification_date, verification_notes,
-                bank_name, bank_account_number, bank_account_holder,
-                logo_url, documents, status, created_at, updated_at
-            FROM companies 
-            WHERE 
-                company_name ILIKE $1 OR 
-                industry_sector ILIKE $1 OR 
-                address_city ILIKE $1 OR
-                nib ILIKE $1
-            ORDER BY created_at DESC
-            LIMIT $2 OFFSET $3
-        "#;
-
-        let rows = sqlx::query(query)
-            .bind(&search_query)
-            .bind(limit)
-            .bind(offset)
-            .fetch_all(&self.pool)
+    async fn list(&self, query: &ListQuery<CompanyFilter, CompanySortField>) -> AppResult<Page<Company>> {
+        let mut conn = self.pool.acquire().await?;
+        let page = fetch_page(&mut conn, &Self::list_select(&query.filter), query)
             .await
-            .map_err(|e| AppError::Database(e))?;
+            .map_err(AppError::Database)?;
 
-        let companies: Vec<Company> = rows
-            .iter()
-            .map(|row| Company {
-                id: row.get("id"),
-                owner_id: row.get("owner_id"),
-                company_name: row.get("company_name"),
-                business_type: row.get("business_type"),
-                industry_sector: row.get("industry_sector"),
-                description: row.get("description"),
-                establishment_date: row.get("establishment_date"),
-                employee_count: row.get("employee_count"),
-                nib: row.get("nib"),
-                siup_number: row.get("siup_number"),
-                tdp_number: row.get("tdp_number"),
-                npwp_company: row.get("npwp_company"),
-                email: row.get("email"),
-                phone: row.get("phone"),
-                website: row.get("website"),
-                address_street: row.get("address_street"),
-                address_city: row.get("address_city"),
-                address_province: row.get("address_province"),
-                address_postal_code: row.get("address_postal_code"),
-                address_country: row.get("address_country"),
-                business_scale: row.get("business_scale"),
-                annual_revenue: row.get("annual_revenue"),
-                annual_revenue_year: row.get("annual_revenue_year"),
-                is_verified: row.get("is_verified"),
-                verification_date: row.get("verification_date"),
-                verification_notes: row.get("verification_notes"),
-                bank_name: row.get("bank_name"),
-                bank_account_number: row.get("bank_account_number"),
-                bank_account_holder: row.get("bank_account_holder"),
-                logo_url: row.get("logo_url"),
-                documents: row.get("documents"),
-                status: row.get("status"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-            })
-            .collect();
-
-        Ok(companies)
+        Ok(page)
     }
 }
@@ -1,152 +1,264 @@
-use chrono::{DateTime, Utc};
+// PostgreSQL implementations of the finance repositories
+// Enum columns are stored as their snake_case text form, amounts as BIGINT in
+// the smallest currency unit and tags/attachments/metadata as JSONB
+
+use sea_query::{extension::postgres::PgExpr, Alias, Cond, Expr, Query, SelectStatement};
+use sqlx::postgres::PgRow;
+use sqlx::types::Json;
 use sqlx::{PgPool, Row};
 use std::collections::HashMap;
 use uuid::Uuid;
 
+use crate::domain::filters::{TransactionFilter, TransactionSortField};
 use crate::domain::finance::{
-    AccountType, FinancialAccount, FinancialAccountRepository, Transaction, TransactionId,
-    TransactionRepository, TransactionStatus, TransactionType,
+    FinanceRepositories, FinancialAccount, FinancialAccountRepository, Transaction, TransactionId,
+    TransactionRepository,
 };
-use crate::domain::value_objects::Money;
+use crate::domain::unit_of_work::{UnitOfWork, Work};
+use crate::domain::value_objects::{Currency, Money};
+use crate::infrastructure::database::list_query::{contains_pattern, fetch_rows};
+use crate::infrastructure::database::unit_of_work::{Db, PgUnitOfWork, PgWork};
+use crate::infrastructure::database::versioning::stale_write;
 use crate::shared::errors::AppError;
+use crate::shared::query::{ListQuery, Page};
+
+pub(crate) const TRANSACTION_COLUMNS: [&str; 21] = [
+    "id",
+    "company_id",
+    "transaction_date",
+    "transaction_type",
+    "amount",
+    "currency",
+    "description",
+    "reference_number",
+    "status",
+    "account_id",
+    "category_id",
+    "tags",
+    "attachments",
+    "metadata",
+    "created_at",
+    "updated_at",
+    "created_by",
+    "updated_by",
+    "reconciliation_id",
+    "reconciled_at",
+    "version",
+];
+
+fn parse_column<T: std::str::FromStr<Err = String>>(row: &PgRow, column: &str) -> Result<T, AppError> {
+    let value: String = row.try_get(column)?;
+    value.parse().map_err(AppError::InternalError)
+}
 
+pub(crate) fn row_to_transaction(row: &PgRow) -> Result<Transaction, AppError> {
+    let currency: Currency = parse_column(row, "currency")?;
+    let tags: Json<Vec<String>> = row.try_get("tags")?;
+    let attachments: Json<Vec<String>> = row.try_get("attachments")?;
+    let metadata: Option<Json<HashMap<String, serde_json::Value>>> = row.try_get("metadata")?;
+
+    Ok(Transaction {
+        id: TransactionId(row.try_get("id")?),
+        company_id: row.try_get("company_id")?,
+        transaction_date: row.try_get("transaction_date")?,
+        transaction_type: parse_column(row, "transaction_type")?,
+        amount: Money::new(row.try_get("amount")?, currency),
+        description: row.try_get("description")?,
+        reference_number: row.try_get("reference_number")?,
+        status: parse_column(row, "status")?,
+        account_id: row.try_get("account_id")?,
+        category_id: row.try_get("category_id")?,
+        tags: tags.0,
+        attachments: attachments.0,
+        metadata: metadata.map(|m| m.0),
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+        created_by: row.try_get("created_by")?,
+        updated_by: row.try_get("updated_by")?,
+        reconciliation_id: row.try_get("reconciliation_id")?,
+        reconciled_at: row.try_get("reconciled_at")?,
+        version: row.try_get("version")?,
+    })
+}
+
+/// Inserts a transaction with the given executor so imports and
+/// reconciliation adjustments can book inside their own database transaction.
+///
+/// An `(import_batch_id, import_key)` pair marks imported rows; a key already
+/// present in the account is skipped and `false` is returned.
+pub(crate) async fn insert_transaction<'e, E>(
+    executor: E,
+    transaction: &Transaction,
+    import: Option<(Uuid, &str)>,
+) -> Result<bool, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let result = sqlx::query(
+        r#"
+        INSERT INTO financial_transactions (
+            id, company_id, transaction_date, transaction_type, amount, currency,
+            description, reference_number, status, account_id, category_id,
+            tags, attachments, metadata, created_at, updated_at, created_by, updated_by,
+            import_batch_id, import_key
+        )
+        VALUES (
+            $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18,
+            $19, $20
+        )
+        ON CONFLICT (account_id, import_key) WHERE import_key IS NOT NULL DO NOTHING
+        "#,
+    )
+    .bind(transaction.id.value())
+    .bind(transaction.company_id)
+    .bind(transaction.transaction_date)
+    .bind(transaction.transaction_type.to_string())
+    .bind(transaction.amount.amount)
+    .bind(transaction.amount.currency.to_string())
+    .bind(&transaction.description)
+    .bind(&transaction.reference_number)
+    .bind(transaction.status.to_string())
+    .bind(transaction.account_id)
+    .bind(transaction.category_id)
+    .bind(Json(&transaction.tags))
+    .bind(Json(&transaction.attachments))
+    .bind(transaction.metadata.as_ref().map(Json))
+    .bind(transaction.created_at)
+    .bind(transaction.updated_at)
+    .bind(transaction.created_by)
+    .bind(transaction.updated_by)
+    .bind(import.map(|(batch_id, _)| batch_id))
+    .bind(import.map(|(_, key)| key))
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Adds a signed amount to the stored account balance
+pub(crate) async fn adjust_account_balance<'e, E>(
+    executor: E,
+    account_id: Uuid,
+    amount: i64,
+) -> Result<(), sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query("UPDATE financial_accounts SET balance = balance + $1, updated_at = NOW() WHERE id = $2")
+        .bind(amount)
+        .bind(account_id)
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
+
+fn row_to_account(row: &PgRow) -> Result<FinancialAccount, AppError> {
+    let currency: Currency = parse_column(row, "currency")?;
+
+    Ok(FinancialAccount {
+        id: row.try_get("id")?,
+        company_id: row.try_get("company_id")?,
+        name: row.try_get("name")?,
+        description: row.try_get("description")?,
+        account_type: row.try_get("account_type")?,
+        balance: Money::new(row.try_get("balance")?, currency.clone()),
+        currency,
+        is_active: row.try_get("is_active")?,
+        metadata: row.try_get("metadata")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+        version: row.try_get("version")?,
+    })
+}
+
+/// Filtered select over financial_transactions, without ordering or paging
+fn transaction_select(filter: &TransactionFilter) -> SelectStatement {
+    let mut cond = Cond::all();
+
+    if let Some(company_id) = filter.company_id {
+        cond = cond.add(Expr::col(Alias::new("company_id")).eq(company_id));
+    }
+    if let Some(account_id) = filter.account_id {
+        cond = cond.add(Expr::col(Alias::new("account_id")).eq(account_id));
+    }
+    if let Some(status) = &filter.status {
+        cond = cond.add(Expr::col(Alias::new("status")).eq(status.to_string()));
+    }
+    if let Some(transaction_type) = &filter.transaction_type {
+        cond = cond.add(Expr::col(Alias::new("transaction_type")).eq(transaction_type.to_string()));
+    }
+    if let Some(min_amount) = filter.min_amount {
+        cond = cond.add(Expr::col(Alias::new("amount")).gte(min_amount));
+    }
+    if let Some(max_amount) = filter.max_amount {
+        cond = cond.add(Expr::col(Alias::new("amount")).lte(max_amount));
+    }
+    if let Some(start_date) = filter.start_date {
+        cond = cond.add(Expr::col(Alias::new("transaction_date")).gte(start_date));
+    }
+    if let Some(end_date) = filter.end_date {
+        cond = cond.add(Expr::col(Alias::new("transaction_date")).lte(end_date));
+    }
+    if let Some(search) = filter.search.as_deref().filter(|s| !s.trim().is_empty()) {
+        let pattern = contains_pattern(search.trim());
+        cond = cond.add(
+            Cond::any()
+                .add(Expr::col(Alias::new("description")).ilike(pattern.as_str()))
+                .add(Expr::col(Alias::new("reference_number")).ilike(pattern.as_str())),
+        );
+    }
+
+    Query::select()
+        .columns(TRANSACTION_COLUMNS.map(Alias::new))
+        .from(Alias::new("financial_transactions"))
+        .cond_where(cond)
+        .to_owned()
+}
+
+#[derive(Clone)]
 pub struct PostgresTransactionRepository {
-    pool: PgPool,
+    db: Db,
 }
 
 impl PostgresTransactionRepository {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self { db: Db::Pool(pool) }
+    }
+
+    /// Runs every statement inside the given unit of work
+    pub fn within(work: &PgWork) -> Self {
+        Self {
+            db: Db::Joined(work.clone()),
+        }
     }
 }
 
 #[async_trait::async_trait]
 impl TransactionRepository for PostgresTransactionRepository {
     async fn create(&self, transaction: &Transaction) -> Result<Transaction, AppError> {
-        let id = transaction.id.value();
-        let transaction_type = serde_json::to_value(&transaction.transaction_type)
-            .map_err(|e| AppError::SerializationError(e.to_string()))?;
-        let status = serde_json::to_value(&transaction.status)
-            .map_err(|e| AppError::SerializationError(e.to_string()))?;
-        let tags = serde_json::to_value(&transaction.tags)
-            .map_err(|e| AppError::SerializationError(e.to_string()))?;
-        let attachments = serde_json::to_value(&transaction.attachments)
-            .map_err(|e| AppError::SerializationError(e.to_string()))?;
-
-        let record = sqlx::query!(
-            r#"
-            INSERT INTO financial_transactions (
-                id, company_id, transaction_date, transaction_type, amount, currency,
-                description, reference_number, status, account_id, category_id, 
-                tags, attachments, metadata, created_at, updated_at, created_by, updated_by
-            )
-            VALUES (
-                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18
-            )
-            RETURNING id
-            "#,
-            id,
-            transaction.company_id,
-            transaction.transaction_date,
-            transaction_type,
-            transaction.amount.value,
-            transaction.amount.currency,
-            transaction.description,
-            transaction.reference_number,
-            status,
-            transaction.account_id,
-            transaction.category_id,
-            tags,
-            attachments,
-            transaction.metadata,
-            transaction.created_at,
-            transaction.updated_at,
-            transaction.created_by,
-            transaction.updated_by
-        )
-        .fetch_one(&self.pool)
-        .await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
-
-        // Return the created transaction
-        self.find_by_id(&TransactionId(record.id))
-            .await?
-            .ok_or_else(|| {
-                AppError::DatabaseError("Failed to retrieve created transaction".to_string())
-            })
+        insert_transaction(&mut *self.db.conn().await?, transaction, None).await?;
+
+        Ok(transaction.clone())
     }
 
     async fn find_by_id(&self, id: &TransactionId) -> Result<Option<Transaction>, AppError> {
-        let record = sqlx::query!(
-            r#"
-            SELECT 
-                id, company_id, transaction_date, transaction_type, amount, currency,
-                description, reference_number, status, account_id, category_id, 
-                tags, attachments, metadata, created_at, updated_at, created_by, updated_by
-            FROM financial_transactions
-            WHERE id = $1
-            "#,
-            id.value()
-        )
-        .fetch_optional(&self.pool)
-        .await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
-
-        if let Some(r) = record {
-            let transaction_type: TransactionType = serde_json::from_value(r.transaction_type)
-                .map_err(|e| AppError::DeserializationError(e.to_string()))?;
-
-            let status: TransactionStatus = serde_json::from_value(r.status)
-                .map_err(|e| AppError::DeserializationError(e.to_string()))?;
-
-            let tags: Vec<String> = serde_json::from_value(r.tags)
-                .map_err(|e| AppError::DeserializationError(e.to_string()))?;
-
-            let attachments: Vec<String> = serde_json::from_value(r.attachments)
-                .map_err(|e| AppError::DeserializationError(e.to_string()))?;
-
-            let money = Money::new(r.amount, r.currency);
-
-            let transaction = Transaction {
-                id: TransactionId(r.id),
-                company_id: r.company_id,
-                transaction_date: r.transaction_date,
-                transaction_type,
-                amount: money,
-                description: r.description,
-                reference_number: r.reference_number,
-                status,
-                account_id: r.account_id,
-                category_id: r.category_id,
-                tags,
-                attachments,
-                metadata: r.metadata,
-                created_at: r.created_at,
-                updated_at: r.updated_at,
-                created_by: r.created_by,
-                updated_by: r.updated_by,
-            };
-
-            Ok(Some(transaction))
-        } else {
-            Ok(None)
-        }
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM financial_transactions WHERE id = $1",
+            TRANSACTION_COLUMNS.join(", ")
+        ))
+        .bind(id.value())
+        .fetch_optional(&mut *self.db.conn().await?)
+        .await?;
+
+        row.as_ref().map(row_to_transaction).transpose()
     }
 
     async fn update(&self, transaction: &Transaction) -> Result<Transaction, AppError> {
-        let transaction_type = serde_json::to_value(&transaction.transaction_type)
-            .map_err(|e| AppError::SerializationError(e.to_string()))?;
-        let status = serde_json::to_value(&transaction.status)
-            .map_err(|e| AppError::SerializationError(e.to_string()))?;
-        let tags = serde_json::to_value(&transaction.tags)
-            .map_err(|e| AppError::SerializationError(e.to_string()))?;
-        let attachments = serde_json::to_value(&transaction.attachments)
-            .map_err(|e| AppError::SerializationError(e.to_string()))?;
-
-        sqlx::query!(
+        let version: Option<i64> = sqlx::query_scalar(
             r#"
             UPDATE financial_transactions
-            SET 
+            SET
                 transaction_date = $1,
                 transaction_type = $2,
                 amount = $3,
@@ -161,210 +273,129 @@ impl TransactionRepository for PostgresTransactionRepository {
                 metadata = $12,
                 updated_at = $13,
                 updated_by = $14
-            WHERE id = $15
+            WHERE id = $15 AND version = $16 AND reconciled_at IS NULL
+            RETURNING version
             "#,
-            transaction.transaction_date,
-            transaction_type,
-            transaction.amount.value,
-            transaction.amount.currency,
-            transaction.description,
-            transaction.reference_number,
-            status,
-            transaction.account_id,
-            transaction.category_id,
-            tags,
-            attachments,
-            transaction.metadata,
-            transaction.updated_at,
-            transaction.updated_by,
-            transaction.id.value()
         )
-        .execute(&self.pool)
-        .await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
-
-        self.find_by_id(&transaction.id).await?.ok_or_else(|| {
-            AppError::DatabaseError("Failed to retrieve updated transaction".to_string())
-        })
-    }
-
-    async fn list_by_company(
-        &self,
-        company_id: Uuid,
-        limit: i64,
-        offset: i64,
-        filters: Option<HashMap<String, String>>,
-    ) -> Result<Vec<Transaction>, AppError> {
-        // Build dynamic query based on filters
-        let mut query = "SELECT * FROM financial_transactions WHERE company_id = $1".to_string();
-        let mut params: Vec<String> = vec![company_id.to_string()];
-
-        if let Some(filter_map) = filters {
-            let mut param_index = 2;
-
-            for (key, value) in filter_map {
-                match key.as_str() {
-                    "status" => {
-                        query.push_str(&format!(" AND status = ${}", param_index));
-                        params.push(value);
-                        param_index += 1;
-                    }
-                    "type" => {
-                        query.push_str(&format!(" AND transaction_type = ${}", param_index));
-                        params.push(value);
-                        param_index += 1;
-                    }
-                    "account_id" => {
-                        query.push_str(&format!(" AND account_id = ${}", param_index));
-                        params.push(value);
-                        param_index += 1;
-                    }
-                    "min_amount" => {
-                        query.push_str(&format!(" AND amount >= ${}", param_index));
-                        params.push(value);
-                        param_index += 1;
-                    }
-                    "max_amount" => {
-                        query.push_str(&format!(" AND amount <= ${}", param_index));
-                        params.push(value);
-                        param_index += 1;
-                    }
-                    "start_date" => {
-                        query.push_str(&format!(" AND transaction_date >= ${}", param_index));
-                        params.push(value);
-                        param_index += 1;
-                    }
-                    "end_date" => {
-                        query.push_str(&format!(" AND transaction_date <= ${}", param_index));
-                        params.push(value);
-                        param_index += 1;
-                    }
-                    "search" => {
-                        query.push_str(&format!(" AND (description ILIKE '%' || ${} || '%' OR reference_number ILIKE '%' || ${} || '%')", param_index, param_index));
-                        params.push(value);
-                        param_index += 1;
-                    }
-                    _ => {} // Ignore unknown filter keys
+        .bind(transaction.transaction_date)
+        .bind(transaction.transaction_type.to_string())
+        .bind(transaction.amount.amount)
+        .bind(transaction.amount.currency.to_string())
+        .bind(&transaction.description)
+        .bind(&transaction.reference_number)
+        .bind(transaction.status.to_string())
+        .bind(transaction.account_id)
+        .bind(transaction.category_id)
+        .bind(Json(&transaction.tags))
+        .bind(Json(&transaction.attachments))
+        .bind(transaction.metadata.as_ref().map(Json))
+        .bind(transaction.updated_at)
+        .bind(transaction.updated_by)
+        .bind(transaction.id.value())
+        .bind(transaction.version)
+        .fetch_optional(&mut *self.db.conn().await?)
+        .await?;
+
+        match version {
+            Some(version) => Ok(Transaction {
+                version,
+                ..transaction.clone()
+            }),
+            // Missing, locked by a completed reconciliation or changed since it was read
+            None => match self.find_by_id(&transaction.id).await? {
+                Some(existing) => {
+                    existing.ensure_editable()?;
+                    Err(AppError::VersionConflict {
+                        resource: "Transaction",
+                        current_version: existing.version,
+                    })
                 }
-            }
+                None => Err(AppError::NotFound("Transaction not found".to_string())),
+            },
         }
-
-        query.push_str(" ORDER BY transaction_date DESC LIMIT $");
-        query.push_str(&(params.len() + 1).to_string());
-        query.push_str(" OFFSET $");
-        query.push_str(&(params.len() + 2).to_string());
-
-        // This implementation is a placeholder - real implementation would use prepared statements and parameter binding
-        // For demonstration purposes only
-        let transactions: Vec<Transaction> = Vec::new();
-
-        Ok(transactions)
     }
 
-    async fn count_by_company(
+    async fn list(
         &self,
-        company_id: Uuid,
-        filters: Option<HashMap<String, String>>,
-    ) -> Result<i64, AppError> {
-        // Similar to list_by_company but returns count
-        // Implementation omitted for brevity
-
-        Ok(0) // Placeholder
+        query: &ListQuery<TransactionFilter, TransactionSortField>,
+    ) -> Result<Page<Transaction>, AppError> {
+        let mut conn = self.db.conn().await?;
+        let (rows, total) = fetch_rows(&mut conn, &transaction_select(&query.filter), query).await?;
+        let transactions = rows
+            .iter()
+            .map(row_to_transaction)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Page::from_rows(transactions, query, total))
     }
 }
 
+#[derive(Clone)]
 pub struct PostgresFinancialAccountRepository {
-    pool: PgPool,
+    db: Db,
 }
 
 impl PostgresFinancialAccountRepository {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self { db: Db::Pool(pool) }
+    }
+
+    /// Runs every statement inside the given unit of work
+    pub fn within(work: &PgWork) -> Self {
+        Self {
+            db: Db::Joined(work.clone()),
+        }
     }
 }
 
 #[async_trait::async_trait]
 impl FinancialAccountRepository for PostgresFinancialAccountRepository {
     async fn create(&self, account: &FinancialAccount) -> Result<FinancialAccount, AppError> {
-        let account_type = serde_json::to_value(&account.account_type)
-            .map_err(|e| AppError::SerializationError(e.to_string()))?;
-
-        let record = sqlx::query!(
+        sqlx::query(
             r#"
             INSERT INTO financial_accounts (
                 id, company_id, name, account_type, currency, balance,
                 is_active, description, metadata, created_at, updated_at
             )
             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
-            RETURNING id
             "#,
-            account.id,
-            account.company_id,
-            account.name,
-            account_type,
-            account.currency,
-            account.balance.value,
-            account.is_active,
-            account.description,
-            account.metadata,
-            account.created_at,
-            account.updated_at
         )
-        .fetch_one(&self.pool)
-        .await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
-
-        self.find_by_id(record.id).await?.ok_or_else(|| {
-            AppError::DatabaseError("Failed to retrieve created account".to_string())
-        })
+        .bind(account.id)
+        .bind(account.company_id)
+        .bind(&account.name)
+        .bind(&account.account_type)
+        .bind(account.currency.to_string())
+        .bind(account.balance.amount)
+        .bind(account.is_active)
+        .bind(&account.description)
+        .bind(&account.metadata)
+        .bind(account.created_at)
+        .bind(account.updated_at)
+        .execute(&mut *self.db.conn().await?)
+        .await?;
+
+        Ok(account.clone())
     }
 
     async fn find_by_id(&self, id: Uuid) -> Result<Option<FinancialAccount>, AppError> {
-        let record = sqlx::query!(
+        let row = sqlx::query(
             r#"
-            SELECT 
+            SELECT
                 id, company_id, name, account_type, currency, balance,
-                is_active, description, metadata, created_at, updated_at
+                is_active, description, metadata, created_at, updated_at, version
             FROM financial_accounts
             WHERE id = $1
             "#,
-            id
         )
-        .fetch_optional(&self.pool)
-        .await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
-
-        if let Some(r) = record {
-            let account_type: AccountType = serde_json::from_value(r.account_type)
-                .map_err(|e| AppError::DeserializationError(e.to_string()))?;
-
-            let money = Money::new(r.balance, r.currency.clone());
-
-            let account = FinancialAccount {
-                id: r.id,
-                company_id: r.company_id,
-                name: r.name,
-                account_type,
-                currency: r.currency,
-                balance: money,
-                is_active: r.is_active,
-                description: r.description,
-                metadata: r.metadata,
-                created_at: r.created_at,
-                updated_at: r.updated_at,
-            };
-
-            Ok(Some(account))
-        } else {
-            Ok(None)
-        }
+        .bind(id)
+        .fetch_optional(&mut *self.db.conn().await?)
+        .await?;
+
+        row.as_ref().map(row_to_account).transpose()
     }
 
     async fn update(&self, account: &FinancialAccount) -> Result<FinancialAccount, AppError> {
-        let account_type = serde_json::to_value(&account.account_type)
-            .map_err(|e| AppError::SerializationError(e.to_string()))?;
-
-        sqlx::query!(
+        let version: Option<i64> = sqlx::query_scalar(
             r#"
             UPDATE financial_accounts
             SET
@@ -376,77 +407,68 @@ impl FinancialAccountRepository for PostgresFinancialAccountRepository {
                 description = $6,
                 metadata = $7,
                 updated_at = $8
-            WHERE id = $9
+            WHERE id = $9 AND version = $10
+            RETURNING version
             "#,
-            account.name,
-            account_type,
-            account.currency,
-            account.balance.value,
-            account.is_active,
-            account.description,
-            account.metadata,
-            account.updated_at,
-            account.id
         )
-        .execute(&self.pool)
-        .await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
-
-        self.find_by_id(account.id).await?.ok_or_else(|| {
-            AppError::DatabaseError("Failed to retrieve updated account".to_string())
-        })
+        .bind(&account.name)
+        .bind(&account.account_type)
+        .bind(account.currency.to_string())
+        .bind(account.balance.amount)
+        .bind(account.is_active)
+        .bind(&account.description)
+        .bind(&account.metadata)
+        .bind(account.updated_at)
+        .bind(account.id)
+        .bind(account.version)
+        .fetch_optional(&mut *self.db.conn().await?)
+        .await?;
+
+        match version {
+            Some(version) => Ok(FinancialAccount {
+                version,
+                ..account.clone()
+            }),
+            None => Err(stale_write(&mut *self.db.conn().await?, "financial_accounts", "Account", account.id).await),
+        }
     }
 
     async fn list_by_company(&self, company_id: Uuid) -> Result<Vec<FinancialAccount>, AppError> {
-        let records = sqlx::query!(
+        let rows = sqlx::query(
             r#"
-            SELECT 
+            SELECT
                 id, company_id, name, account_type, currency, balance,
-                is_active, description, metadata, created_at, updated_at
+                is_active, description, metadata, created_at, updated_at, version
             FROM financial_accounts
             WHERE company_id = $1
             ORDER BY name ASC
             "#,
-            company_id
         )
-        .fetch_all(&self.pool)
-        .await
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
-
-        let mut accounts = Vec::with_capacity(records.len());
-
-        for r in records {
-            let account_type: AccountType = serde_json::from_value(r.account_type)
-                .map_err(|e| AppError::DeserializationError(e.to_string()))?;
-
-            let money = Money::new(r.balance, r.currency.clone());
-
-            let account = FinancialAccount {
-                id: r.id,
-                company_id: r.company_id,
-                name: r.name,
-                account_type,
-                currency: r.currency,
-                balance: money,
-                is_active: r.is_active,
-                description: r.description,
-                metadata: r.metadata,
-                created_at: r.created_at,
-                updated_at: r.updated_at,
-            };
-
-            accounts.push(account);
-        }
+        .bind(company_id)
+        .fetch_all(&mut *self.db.conn().await?)
+        .await?;
 
-        Ok(accounts)
+        rows.iter().map(row_to_account).collect()
     }
 
     async fn delete(&self, id: Uuid) -> Result<(), AppError> {
-        sqlx::query!("DELETE FROM financial_accounts WHERE id = $1", id)
-            .execute(&self.pool)
-            .await
-            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        sqlx::query("DELETE FROM financial_accounts WHERE id = $1")
+            .bind(id)
+            .execute(&mut *self.db.conn().await?)
+            .await?;
 
         Ok(())
     }
 }
+
+#[async_trait::async_trait]
+impl UnitOfWork<FinanceRepositories> for PgUnitOfWork {
+    async fn begin(&self) -> Result<Work<FinanceRepositories>, AppError> {
+        let work = self.begin_work().await?;
+        let repositories = FinanceRepositories {
+            transactions: Box::new(PostgresTransactionRepository::within(&work)),
+            accounts: Box::new(PostgresFinancialAccountRepository::within(&work)),
+        };
+        Ok(Work::new(repositories, Box::new(work)))
+    }
+}
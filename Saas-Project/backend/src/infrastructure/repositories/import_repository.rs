@@ -0,0 +1,270 @@
+// PostgreSQL implementation of the transaction import repository
+// Mappings, summaries and the per-row report are stored as JSONB; committing a
+// batch runs in one database transaction together with the balance update.
+
+use sqlx::postgres::PgRow;
+use sqlx::types::Json;
+use sqlx::{PgPool, Row};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::domain::imports::{
+    ImportBatch, ImportBatchStatus, ImportMapping, ImportRepository, ImportedTransaction,
+};
+use crate::infrastructure::repositories::finance_repository::{
+    adjust_account_balance, insert_transaction,
+};
+use crate::shared::errors::AppError;
+
+pub(crate) const BATCH_COLUMNS: &str = "id, company_id, account_id, file_name, layout, mapping, status, \
+    summary, report, created_by, created_at, committed_by, committed_at";
+
+fn parse_column<T: std::str::FromStr<Err = String>>(row: &PgRow, column: &str) -> Result<T, AppError> {
+    let value: String = row.try_get(column)?;
+    value.parse().map_err(AppError::InternalError)
+}
+
+fn row_to_mapping(row: &PgRow) -> Result<ImportMapping, AppError> {
+    let Json(mapping) = row.try_get("mapping")?;
+
+    Ok(ImportMapping {
+        id: row.try_get("id")?,
+        company_id: row.try_get("company_id")?,
+        name: row.try_get("name")?,
+        mapping,
+        created_by: row.try_get("created_by")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+fn row_to_batch(row: &PgRow) -> Result<ImportBatch, AppError> {
+    let Json(mapping) = row.try_get("mapping")?;
+    let Json(summary) = row.try_get("summary")?;
+    let Json(rows) = row.try_get("report")?;
+
+    Ok(ImportBatch {
+        id: row.try_get("id")?,
+        company_id: row.try_get("company_id")?,
+        account_id: row.try_get("account_id")?,
+        file_name: row.try_get("file_name")?,
+        layout: parse_column(row, "layout")?,
+        mapping,
+        status: parse_column(row, "status")?,
+        summary,
+        rows,
+        created_by: row.try_get("created_by")?,
+        created_at: row.try_get("created_at")?,
+        committed_by: row.try_get("committed_by")?,
+        committed_at: row.try_get("committed_at")?,
+    })
+}
+
+#[derive(Clone)]
+pub struct PostgresImportRepository {
+    pool: PgPool,
+}
+
+impl PostgresImportRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl ImportRepository for PostgresImportRepository {
+    async fn save_mapping(&self, mapping: &ImportMapping) -> Result<ImportMapping, AppError> {
+        let row = sqlx::query(
+            r#"
+            INSERT INTO import_mappings (id, company_id, name, mapping, created_by, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (company_id, name) DO UPDATE
+            SET mapping = EXCLUDED.mapping, updated_at = EXCLUDED.updated_at
+            RETURNING id, company_id, name, mapping, created_by, created_at, updated_at
+            "#,
+        )
+        .bind(mapping.id)
+        .bind(mapping.company_id)
+        .bind(&mapping.name)
+        .bind(Json(&mapping.mapping))
+        .bind(mapping.created_by)
+        .bind(mapping.created_at)
+        .bind(mapping.updated_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        row_to_mapping(&row)
+    }
+
+    async fn find_mapping(&self, company_id: Uuid, id: Uuid) -> Result<Option<ImportMapping>, AppError> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, company_id, name, mapping, created_by, created_at, updated_at
+            FROM import_mappings
+            WHERE id = $1 AND company_id = $2
+            "#,
+        )
+        .bind(id)
+        .bind(company_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(row_to_mapping).transpose()
+    }
+
+    async fn list_mappings(&self, company_id: Uuid) -> Result<Vec<ImportMapping>, AppError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, company_id, name, mapping, created_by, created_at, updated_at
+            FROM import_mappings
+            WHERE company_id = $1
+            ORDER BY name ASC
+            "#,
+        )
+        .bind(company_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_mapping).collect()
+    }
+
+    async fn delete_mapping(&self, company_id: Uuid, id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM import_mappings WHERE id = $1 AND company_id = $2")
+            .bind(id)
+            .bind(company_id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Import mapping not found".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn save_batch(&self, batch: &ImportBatch) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            INSERT INTO import_batches (
+                id, company_id, account_id, file_name, layout, mapping, status,
+                summary, report, created_by, created_at, committed_by, committed_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            "#,
+        )
+        .bind(batch.id)
+        .bind(batch.company_id)
+        .bind(batch.account_id)
+        .bind(&batch.file_name)
+        .bind(batch.layout.to_string())
+        .bind(Json(&batch.mapping))
+        .bind(batch.status.to_string())
+        .bind(Json(&batch.summary))
+        .bind(Json(&batch.rows))
+        .bind(batch.created_by)
+        .bind(batch.created_at)
+        .bind(batch.committed_by)
+        .bind(batch.committed_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_batch(&self, company_id: Uuid, id: Uuid) -> Result<Option<ImportBatch>, AppError> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM import_batches WHERE id = $1 AND company_id = $2",
+            BATCH_COLUMNS
+        ))
+        .bind(id)
+        .bind(company_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(row_to_batch).transpose()
+    }
+
+    async fn existing_import_keys(
+        &self,
+        account_id: Uuid,
+        keys: &[String],
+    ) -> Result<HashSet<String>, AppError> {
+        if keys.is_empty() {
+            return Ok(HashSet::new());
+        }
+
+        let existing: Vec<String> = sqlx::query_scalar(
+            "SELECT import_key FROM financial_transactions WHERE account_id = $1 AND import_key = ANY($2)",
+        )
+        .bind(account_id)
+        .bind(keys)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(existing.into_iter().collect())
+    }
+
+    async fn commit_batch(
+        &self,
+        batch: &ImportBatch,
+        transactions: &[ImportedTransaction],
+        committed_by: Uuid,
+    ) -> Result<ImportBatch, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        // Lock the batch so concurrent commits of the same upload serialise
+        let locked = sqlx::query(&format!(
+            "SELECT {} FROM import_batches WHERE id = $1 AND company_id = $2 FOR UPDATE",
+            BATCH_COLUMNS
+        ))
+        .bind(batch.id)
+        .bind(batch.company_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Import batch not found".to_string()))?;
+
+        let current = row_to_batch(&locked)?;
+        if current.status == ImportBatchStatus::Committed {
+            return Ok(current);
+        }
+
+        let mut inserted = HashSet::new();
+        let mut net_amount = 0i64;
+        for item in transactions {
+            let inserted_row = insert_transaction(
+                &mut *tx,
+                &item.transaction,
+                Some((batch.id, item.import_key.as_str())),
+            )
+            .await?;
+
+            if inserted_row {
+                inserted.insert(item.import_key.clone());
+                net_amount += item.signed_amount();
+            }
+        }
+
+        adjust_account_balance(&mut *tx, batch.account_id, net_amount).await?;
+
+        let mut committed = current;
+        committed.mark_committed(&inserted, committed_by);
+
+        sqlx::query(
+            r#"
+            UPDATE import_batches
+            SET status = $1, summary = $2, report = $3, committed_by = $4, committed_at = $5
+            WHERE id = $6
+            "#,
+        )
+        .bind(committed.status.to_string())
+        .bind(Json(&committed.summary))
+        .bind(Json(&committed.rows))
+        .bind(committed.committed_by)
+        .bind(committed.committed_at)
+        .bind(committed.id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(committed)
+    }
+}
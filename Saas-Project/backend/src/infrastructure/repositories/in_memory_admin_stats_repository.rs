@@ -0,0 +1,238 @@
+// In-memory admin dashboard statistics for tests and demo mode
+// Computed from the in-memory users, companies and licenses on every call,
+// counting and ordering the way PostgresAdminStatsRepository does.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::domain::admin::{
+    AdminStatsRepository, PendingWork, ProcessingPerformance, RegionRegistrations, ReportRange,
+    ReviewerWorkload, ServiceFeeRevenue,
+};
+use crate::domain::entities::UserRole;
+use crate::domain::licenses::{ApplicationStatus, LicenseType};
+use crate::shared::errors::AppResult;
+
+use super::{InMemoryCompanyRepository, InMemoryLicenseRepository, InMemoryUserRepository};
+
+#[derive(Clone)]
+pub struct InMemoryAdminStatsRepository {
+    users: Arc<InMemoryUserRepository>,
+    companies: InMemoryCompanyRepository,
+    licenses: InMemoryLicenseRepository,
+}
+
+impl InMemoryAdminStatsRepository {
+    pub fn new(
+        users: Arc<InMemoryUserRepository>,
+        companies: InMemoryCompanyRepository,
+        licenses: InMemoryLicenseRepository,
+    ) -> Self {
+        Self {
+            users,
+            companies,
+            licenses,
+        }
+    }
+}
+
+fn days_between(from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+    (to - from).num_microseconds().unwrap_or(i64::MAX) as f64 / 86_400_000_000.0
+}
+
+fn workload(workloads: &mut HashMap<Uuid, ReviewerWorkload>, reviewer_id: Uuid) -> &mut ReviewerWorkload {
+    workloads.entry(reviewer_id).or_insert_with(|| ReviewerWorkload {
+        reviewer_id,
+        full_name: String::new(),
+        open_applications: 0,
+        status_changes: 0,
+        approved: 0,
+        rejected: 0,
+    })
+}
+
+#[derive(Default)]
+struct TypeTally {
+    submitted: i64,
+    approved: i64,
+    rejected: i64,
+    days: Vec<f64>,
+}
+
+/// Tallies keyed by type name, the order Postgres sorts `license_type::text`
+type Tallies = BTreeMap<String, (LicenseType, TypeTally)>;
+
+fn tally(tallies: &mut Tallies, license_type: LicenseType) -> &mut TypeTally {
+    &mut tallies
+        .entry(license_type.to_string())
+        .or_insert_with(|| (license_type, TypeTally::default()))
+        .1
+}
+
+#[async_trait]
+impl AdminStatsRepository for InMemoryAdminStatsRepository {
+    async fn pending_work(&self) -> AppResult<PendingWork> {
+        let (licenses, documents) = self.licenses.snapshot();
+        let mut pending = PendingWork::default();
+        let mut awaiting_review = Vec::new();
+        for license in &licenses {
+            match license.application_status {
+                ApplicationStatus::Submitted => pending.submitted += 1,
+                ApplicationStatus::Processing => pending.processing += 1,
+                ApplicationStatus::PendingDocuments => pending.pending_documents += 1,
+                _ => continue,
+            }
+            if license.application_status != ApplicationStatus::PendingDocuments {
+                awaiting_review.push(license.id);
+            }
+            pending.oldest_submitted_at = match (pending.oldest_submitted_at, license.submitted_at) {
+                (Some(oldest), Some(at)) => Some(oldest.min(at)),
+                (oldest, at) => oldest.or(at),
+            };
+        }
+        pending.unverified_documents = documents
+            .iter()
+            .filter(|d| !d.is_verified && awaiting_review.contains(&d.license_id))
+            .count() as i64;
+        Ok(pending)
+    }
+
+    async fn throughput(&self, range: &ReportRange) -> AppResult<Vec<ProcessingPerformance>> {
+        let (licenses, _) = self.licenses.snapshot();
+        let mut tallies = Tallies::new();
+        for license in &licenses {
+            if license.submitted_at.is_some_and(|at| range.contains(at)) {
+                tally(&mut tallies, license.license_type).submitted += 1;
+            }
+            for (decided_at, approved) in [(license.approved_at, true), (license.rejected_at, false)] {
+                let Some(decided_at) = decided_at.filter(|at| range.contains(*at)) else {
+                    continue;
+                };
+                let entry = tally(&mut tallies, license.license_type);
+                if approved {
+                    entry.approved += 1;
+                } else {
+                    entry.rejected += 1;
+                }
+                if let Some(submitted_at) = license.submitted_at {
+                    entry.days.push(days_between(submitted_at, decided_at));
+                }
+            }
+        }
+
+        Ok(tallies
+            .into_values()
+            .map(|(license_type, tally)| {
+                let fold = |pick: fn(f64, f64) -> f64| {
+                    tally.days.iter().copied().reduce(pick).map(|days| days.floor() as i32)
+                };
+                ProcessingPerformance {
+                    license_type,
+                    submitted_count: tally.submitted,
+                    total_processed: tally.approved + tally.rejected,
+                    approved_count: tally.approved,
+                    rejected_count: tally.rejected,
+                    approval_rate: ProcessingPerformance::approval_rate(tally.approved, tally.rejected),
+                    avg_processing_days: (!tally.days.is_empty())
+                        .then(|| tally.days.iter().sum::<f64>() / tally.days.len() as f64),
+                    min_processing_days: fold(f64::min),
+                    max_processing_days: fold(f64::max),
+                }
+            })
+            .collect())
+    }
+
+    async fn reviewer_workload(&self, range: &ReportRange) -> AppResult<Vec<ReviewerWorkload>> {
+        let (licenses, _) = self.licenses.snapshot();
+        let history = self.licenses.history();
+        let mut workloads: HashMap<Uuid, ReviewerWorkload> = HashMap::new();
+
+        for license in licenses.iter().filter(|l| {
+            matches!(
+                l.application_status,
+                ApplicationStatus::Processing | ApplicationStatus::PendingDocuments
+            )
+        }) {
+            let moved_by = history
+                .iter()
+                .filter(|h| h.license_id == license.id && h.to_status == license.application_status)
+                .max_by_key(|h| h.changed_at)
+                .map(|h| h.changed_by);
+            if let Some(reviewer_id) = moved_by {
+                workload(&mut workloads, reviewer_id).open_applications += 1;
+            }
+        }
+        for change in history.iter().filter(|h| range.contains(h.changed_at)) {
+            let entry = workload(&mut workloads, change.changed_by);
+            entry.status_changes += 1;
+            match change.to_status {
+                ApplicationStatus::Approved => entry.approved += 1,
+                ApplicationStatus::Rejected => entry.rejected += 1,
+                _ => {}
+            }
+        }
+
+        let admins: HashMap<Uuid, String> = self
+            .users
+            .all()
+            .into_iter()
+            .filter(|u| matches!(u.role, UserRole::AdminStaff | UserRole::SuperAdmin))
+            .map(|u| (*u.id.as_uuid(), u.full_name))
+            .collect();
+        let mut workloads: Vec<ReviewerWorkload> = workloads
+            .into_values()
+            .filter_map(|mut workload| {
+                workload.full_name = admins.get(&workload.reviewer_id)?.clone();
+                Some(workload)
+            })
+            .collect();
+        workloads.sort_by_key(|w| {
+            (
+                std::cmp::Reverse(w.open_applications),
+                std::cmp::Reverse(w.status_changes),
+                w.reviewer_id,
+            )
+        });
+        Ok(workloads)
+    }
+
+    async fn registrations_by_province(&self, range: &ReportRange) -> AppResult<Vec<RegionRegistrations>> {
+        let mut provinces: HashMap<String, i64> = HashMap::new();
+        for company in self.companies.all().into_iter().filter(|c| range.contains(c.created_at)) {
+            *provinces.entry(company.address_province).or_default() += 1;
+        }
+        let mut registrations: Vec<RegionRegistrations> = provinces
+            .into_iter()
+            .map(|(province, companies)| RegionRegistrations { province, companies })
+            .collect();
+        registrations.sort_by(|a, b| {
+            b.companies
+                .cmp(&a.companies)
+                .then_with(|| a.province.cmp(&b.province))
+        });
+        Ok(registrations)
+    }
+
+    async fn service_fee_revenue(&self, range: &ReportRange) -> AppResult<Vec<ServiceFeeRevenue>> {
+        let (licenses, _) = self.licenses.snapshot();
+        let mut revenue: BTreeMap<String, ServiceFeeRevenue> = BTreeMap::new();
+        for license in licenses
+            .iter()
+            .filter(|l| l.approved_at.is_some_and(|at| range.contains(at)))
+        {
+            let entry = revenue
+                .entry(license.license_type.to_string())
+                .or_insert_with(|| ServiceFeeRevenue {
+                    license_type: license.license_type,
+                    approved_licenses: 0,
+                    service_fee_total: 0,
+                });
+            entry.approved_licenses += 1;
+            entry.service_fee_total += license.service_fee.unwrap_or(0);
+        }
+        Ok(revenue.into_values().collect())
+    }
+}
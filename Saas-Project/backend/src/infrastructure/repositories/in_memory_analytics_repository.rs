@@ -0,0 +1,198 @@
+// In-memory analytics time series for tests and demo mode
+// Computed from the in-memory companies, licenses and transactions on every
+// call, so there is nothing to refresh; the series match what
+// PostgresAnalyticsRepository returns after a refresh.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+use crate::domain::analytics::{
+    reporting_day, sort_points, AnalyticsRepository, Dimension, FinanceTotals, LicenseVolume,
+    RegistrationVolume, RollupRefresh, SeriesPoint, SeriesQuery,
+};
+use crate::domain::companies::Company;
+use crate::shared::errors::AppResult;
+
+use super::{InMemoryCompanyRepository, InMemoryFinanceStore, InMemoryLicenseRepository};
+
+#[derive(Clone)]
+pub struct InMemoryAnalyticsRepository {
+    companies: InMemoryCompanyRepository,
+    licenses: InMemoryLicenseRepository,
+    finance: Option<InMemoryFinanceStore>,
+}
+
+impl InMemoryAnalyticsRepository {
+    pub fn new(companies: InMemoryCompanyRepository, licenses: InMemoryLicenseRepository) -> Self {
+        Self {
+            companies,
+            licenses,
+            finance: None,
+        }
+    }
+
+    /// Includes the transactions of `finance`; without it finance series are empty
+    pub fn with_finance(mut self, finance: InMemoryFinanceStore) -> Self {
+        self.finance = Some(finance);
+        self
+    }
+
+    fn companies_by_id(&self) -> HashMap<Uuid, Company> {
+        self.companies
+            .all()
+            .into_iter()
+            .map(|company| (company.id, company))
+            .collect()
+    }
+}
+
+type Points<A> = BTreeMap<(chrono::NaiveDate, BTreeMap<Dimension, String>), A>;
+
+/// Accumulator of the bucket and group `at` falls in, if it is in range
+fn point<'a, A: Default>(
+    points: &'a mut Points<A>,
+    query: &SeriesQuery,
+    at: DateTime<Utc>,
+    group: &[(Dimension, &str)],
+) -> Option<&'a mut A> {
+    let day = reporting_day(at);
+    if !query.contains(day) {
+        return None;
+    }
+    Some(
+        points
+            .entry((query.bucket.start(day), query.group(group)))
+            .or_default(),
+    )
+}
+
+fn into_points<A, V>(points: Points<A>, values: impl Fn(A) -> V) -> Vec<SeriesPoint<V>> {
+    let mut points: Vec<SeriesPoint<V>> = points
+        .into_iter()
+        .map(|((bucket, group), tally)| SeriesPoint {
+            bucket,
+            group,
+            values: values(tally),
+        })
+        .collect();
+    sort_points(&mut points);
+    points
+}
+
+fn days_between(from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+    (to - from).num_microseconds().unwrap_or(i64::MAX) as f64 / 86_400_000_000.0
+}
+
+#[derive(Default)]
+struct LicenseTally {
+    volume: LicenseVolume,
+    processing_days: f64,
+    processing_decisions: i64,
+}
+
+#[async_trait]
+impl AnalyticsRepository for InMemoryAnalyticsRepository {
+    async fn refresh(&self) -> AppResult<RollupRefresh> {
+        Ok(RollupRefresh::default())
+    }
+
+    async fn license_series(&self, query: &SeriesQuery) -> AppResult<Vec<SeriesPoint<LicenseVolume>>> {
+        let companies = self.companies_by_id();
+        let (licenses, _) = self.licenses.snapshot();
+        let mut points: Points<LicenseTally> = BTreeMap::new();
+
+        for license in &licenses {
+            let Some(company) = companies.get(&license.company_id) else {
+                continue;
+            };
+            let license_type = license.license_type.to_string();
+            let status = license.application_status.to_string();
+            let group = [
+                (Dimension::Type, license_type.as_str()),
+                (Dimension::Status, status.as_str()),
+                (Dimension::Province, company.address_province.as_str()),
+                (Dimension::BusinessScale, company.business_scale.as_str()),
+            ];
+
+            if let Some(tally) = point(&mut points, query, license.created_at, &group) {
+                tally.volume.created += 1;
+            }
+            if let Some(tally) = license
+                .submitted_at
+                .and_then(|at| point(&mut points, query, at, &group))
+            {
+                tally.volume.submitted += 1;
+            }
+            for (decided_at, approved) in [(license.approved_at, true), (license.rejected_at, false)] {
+                let Some(decided_at) = decided_at else {
+                    continue;
+                };
+                let Some(tally) = point(&mut points, query, decided_at, &group) else {
+                    continue;
+                };
+                if approved {
+                    tally.volume.approved += 1;
+                } else {
+                    tally.volume.rejected += 1;
+                }
+                if let Some(submitted_at) = license.submitted_at {
+                    tally.processing_days += days_between(submitted_at, decided_at);
+                    tally.processing_decisions += 1;
+                }
+            }
+        }
+
+        Ok(into_points(points, |tally| LicenseVolume {
+            avg_processing_days: (tally.processing_decisions > 0)
+                .then(|| tally.processing_days / tally.processing_decisions as f64),
+            ..tally.volume
+        }))
+    }
+
+    async fn registration_series(
+        &self,
+        query: &SeriesQuery,
+    ) -> AppResult<Vec<SeriesPoint<RegistrationVolume>>> {
+        let mut points: Points<RegistrationVolume> = BTreeMap::new();
+        for company in self.companies.all() {
+            let group = [
+                (Dimension::Province, company.address_province.as_str()),
+                (Dimension::BusinessScale, company.business_scale.as_str()),
+            ];
+            if let Some(volume) = point(&mut points, query, company.created_at, &group) {
+                volume.companies += 1;
+            }
+        }
+        Ok(into_points(points, |volume| volume))
+    }
+
+    async fn finance_series(&self, query: &SeriesQuery) -> AppResult<Vec<SeriesPoint<FinanceTotals>>> {
+        let Some(finance) = &self.finance else {
+            return Ok(Vec::new());
+        };
+        let companies = self.companies_by_id();
+        let transactions: Vec<_> = finance.lock().transactions.values().cloned().collect();
+        let mut points: Points<FinanceTotals> = BTreeMap::new();
+
+        for transaction in &transactions {
+            let Some(company) = companies.get(&transaction.company_id) else {
+                continue;
+            };
+            let transaction_type = transaction.transaction_type.to_string();
+            let status = transaction.status.to_string();
+            let group = [
+                (Dimension::Type, transaction_type.as_str()),
+                (Dimension::Status, status.as_str()),
+                (Dimension::Province, company.address_province.as_str()),
+                (Dimension::BusinessScale, company.business_scale.as_str()),
+            ];
+            if let Some(totals) = point(&mut points, query, transaction.transaction_date, &group) {
+                totals.transactions += 1;
+                totals.amount += transaction.amount.amount;
+            }
+        }
+        Ok(into_points(points, |totals| totals))
+    }
+}
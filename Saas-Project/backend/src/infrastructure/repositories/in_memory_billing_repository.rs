@@ -0,0 +1,232 @@
+// In-memory subscription billing for tests and demo mode
+// Mirrors PostgresBillingRepository: version checks on subscriptions, one
+// subscription per company, invoice numbers from a shared counter and updates
+// only to open invoices. Usage is counted from the in-memory companies and
+// licenses. There is no companies table to keep in sync; the plan in effect is
+// read from the subscription itself.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, SubsecRound, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::domain::analytics::reporting_day;
+use crate::domain::billing::{
+    BillingChange, BillingRepository, InvoiceStatus, Subscription, SubscriptionInvoice, Usage,
+};
+use crate::infrastructure::database::versioning::check_version;
+use crate::shared::errors::{AppError, AppResult};
+
+use super::{InMemoryCompanyRepository, InMemoryLicenseRepository};
+
+#[derive(Default)]
+struct BillingStore {
+    subscriptions: HashMap<Uuid, Subscription>,
+    invoices: HashMap<Uuid, SubscriptionInvoice>,
+    last_invoice_number: u64,
+}
+
+#[derive(Clone)]
+pub struct InMemoryBillingRepository {
+    store: Arc<Mutex<BillingStore>>,
+    companies: InMemoryCompanyRepository,
+    licenses: InMemoryLicenseRepository,
+}
+
+impl InMemoryBillingRepository {
+    pub fn new(companies: InMemoryCompanyRepository, licenses: InMemoryLicenseRepository) -> Self {
+        Self {
+            store: Arc::default(),
+            companies,
+            licenses,
+        }
+    }
+}
+
+/// Postgres keeps microseconds
+fn micros(at: DateTime<Utc>) -> DateTime<Utc> {
+    at.trunc_subsecs(6)
+}
+
+fn stored_subscription(subscription: &Subscription, version: i64, updated_at: DateTime<Utc>) -> Subscription {
+    Subscription {
+        trial_ends_at: subscription.trial_ends_at.map(micros),
+        current_period_start: micros(subscription.current_period_start),
+        current_period_end: micros(subscription.current_period_end),
+        version,
+        created_at: micros(subscription.created_at),
+        updated_at: micros(updated_at),
+        ..subscription.clone()
+    }
+}
+
+fn stored_invoice(invoice: &SubscriptionInvoice, number: String) -> SubscriptionInvoice {
+    SubscriptionInvoice {
+        number,
+        period_start: micros(invoice.period_start),
+        period_end: micros(invoice.period_end),
+        issued_at: micros(invoice.issued_at),
+        due_at: micros(invoice.due_at),
+        paid_at: invoice.paid_at.map(micros),
+        next_reminder_at: invoice.next_reminder_at.map(micros),
+        updated_at: micros(invoice.updated_at),
+        ..invoice.clone()
+    }
+}
+
+impl BillingStore {
+    fn save_subscription(&self, subscription: &Subscription) -> AppResult<Subscription> {
+        if let Some(existing) = self.subscriptions.get(&subscription.company_id) {
+            if existing.id != subscription.id {
+                return Err(AppError::Conflict("The company already has a subscription".to_string()));
+            }
+            check_version("Subscription", subscription.version, existing.version)?;
+            return Ok(stored_subscription(subscription, existing.version + 1, Utc::now()));
+        }
+        Ok(stored_subscription(subscription, 1, subscription.updated_at))
+    }
+
+    fn save_invoice(&mut self, invoice: &SubscriptionInvoice) -> AppResult<SubscriptionInvoice> {
+        if invoice.number.is_empty() {
+            self.last_invoice_number += 1;
+            let number = format!(
+                "SUB/{}/{:06}",
+                reporting_day(invoice.issued_at).year(),
+                self.last_invoice_number
+            );
+            return Ok(stored_invoice(invoice, number));
+        }
+        let existing = self.invoices.get(&invoice.id);
+        if !existing.is_some_and(|existing| existing.status == InvoiceStatus::Open) {
+            return Err(AppError::Conflict(format!(
+                "Invoice {} is no longer open",
+                invoice.number
+            )));
+        }
+        Ok(stored_invoice(invoice, invoice.number.clone()))
+    }
+}
+
+#[async_trait]
+impl BillingRepository for InMemoryBillingRepository {
+    async fn subscription(&self, company_id: Uuid) -> AppResult<Option<Subscription>> {
+        Ok(self.store.lock().unwrap().subscriptions.get(&company_id).cloned())
+    }
+
+    async fn apply(&self, change: &BillingChange) -> AppResult<BillingChange> {
+        let mut store = self.store.lock().unwrap();
+        // Validate everything before writing anything, as a transaction would
+        let subscription = change
+            .subscription
+            .as_ref()
+            .map(|subscription| store.save_subscription(subscription))
+            .transpose()?;
+        let counter = store.last_invoice_number;
+        let mut invoices = Vec::new();
+        for invoice in &change.invoices {
+            match store.save_invoice(invoice) {
+                Ok(saved) => invoices.push(saved),
+                Err(err) => {
+                    store.last_invoice_number = counter;
+                    return Err(err);
+                }
+            }
+        }
+
+        if let Some(subscription) = &subscription {
+            store
+                .subscriptions
+                .insert(subscription.company_id, subscription.clone());
+        }
+        for invoice in &invoices {
+            store.invoices.insert(invoice.id, invoice.clone());
+        }
+        Ok(BillingChange {
+            subscription,
+            invoices,
+        })
+    }
+
+    async fn due_subscriptions(&self, now: DateTime<Utc>, limit: i64) -> AppResult<Vec<Subscription>> {
+        let store = self.store.lock().unwrap();
+        let mut due: Vec<Subscription> = store
+            .subscriptions
+            .values()
+            .filter(|s| s.is_due(now))
+            .cloned()
+            .collect();
+        due.sort_by_key(|s| (s.current_period_end, s.id));
+        due.truncate(limit.max(0) as usize);
+        Ok(due)
+    }
+
+    async fn invoice(&self, id: Uuid) -> AppResult<Option<SubscriptionInvoice>> {
+        Ok(self.store.lock().unwrap().invoices.get(&id).cloned())
+    }
+
+    async fn invoice_by_number(&self, number: &str) -> AppResult<Option<SubscriptionInvoice>> {
+        let store = self.store.lock().unwrap();
+        Ok(store.invoices.values().find(|i| i.number == number).cloned())
+    }
+
+    async fn invoices(&self, company_id: Uuid, limit: i64) -> AppResult<Vec<SubscriptionInvoice>> {
+        let store = self.store.lock().unwrap();
+        let mut invoices: Vec<SubscriptionInvoice> = store
+            .invoices
+            .values()
+            .filter(|i| i.company_id == company_id)
+            .cloned()
+            .collect();
+        invoices.sort_by(|a, b| (b.issued_at, &b.number).cmp(&(a.issued_at, &a.number)));
+        invoices.truncate(limit.max(0) as usize);
+        Ok(invoices)
+    }
+
+    async fn open_invoices(&self, subscription_id: Uuid) -> AppResult<Vec<SubscriptionInvoice>> {
+        let store = self.store.lock().unwrap();
+        let mut invoices: Vec<SubscriptionInvoice> = store
+            .invoices
+            .values()
+            .filter(|i| i.subscription_id == subscription_id && i.status == InvoiceStatus::Open)
+            .cloned()
+            .collect();
+        invoices.sort_by(|a, b| (a.issued_at, &a.number).cmp(&(b.issued_at, &b.number)));
+        Ok(invoices)
+    }
+
+    async fn dunning_due(&self, now: DateTime<Utc>, limit: i64) -> AppResult<Vec<SubscriptionInvoice>> {
+        let store = self.store.lock().unwrap();
+        let mut due: Vec<SubscriptionInvoice> = store
+            .invoices
+            .values()
+            .filter(|i| i.status == InvoiceStatus::Open && i.next_reminder_at.is_some_and(|at| at <= now))
+            .cloned()
+            .collect();
+        due.sort_by(|a, b| (a.next_reminder_at, &a.number).cmp(&(b.next_reminder_at, &b.number)));
+        due.truncate(limit.max(0) as usize);
+        Ok(due)
+    }
+
+    async fn usage(&self, company_id: Uuid, month_start: DateTime<Utc>) -> AppResult<Usage> {
+        let owners = self.companies.owners();
+        let companies = owners
+            .get(&company_id)
+            .map_or(0, |owner| owners.values().filter(|o| *o == owner).count() as i64);
+        let (licenses, documents) = self.licenses.snapshot();
+        let company_licenses: HashMap<Uuid, bool> = licenses
+            .iter()
+            .filter(|l| l.company_id == company_id)
+            .map(|l| (l.id, l.created_at >= month_start))
+            .collect();
+        Ok(Usage {
+            companies,
+            licenses_this_month: company_licenses.values().filter(|recent| **recent).count() as i64,
+            storage_bytes: documents
+                .iter()
+                .filter(|d| company_licenses.contains_key(&d.license_id))
+                .map(|d| d.file_size)
+                .sum(),
+        })
+    }
+}
@@ -0,0 +1,240 @@
+// In-memory company repository for testing and demo mode
+// Mirrors PostgresCompanyRepository: newest-first ordering, the unique NIB
+// constraint, version checks on update and NotFound on updating or deleting a
+// missing company.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::domain::companies::Company;
+use crate::domain::filters::{CompanyFilter, CompanySortField};
+use crate::domain::repositories::CompanyRepository;
+use crate::infrastructure::database::versioning::check_version;
+use crate::shared::errors::{AppError, AppResult};
+use crate::shared::query::{paginate, ListQuery, Page};
+
+/// A simple in-memory implementation of the CompanyRepository trait for testing purposes
+#[derive(Clone, Default)]
+pub struct InMemoryCompanyRepository {
+    companies: Arc<Mutex<HashMap<Uuid, Company>>>,
+}
+
+fn newest_first(mut companies: Vec<Company>) -> Vec<Company> {
+    companies.sort_by_key(|c| std::cmp::Reverse(c.created_at));
+    companies
+}
+
+fn page(companies: Vec<Company>, limit: Option<i32>, offset: Option<i32>) -> Vec<Company> {
+    companies
+        .into_iter()
+        .skip(offset.unwrap_or(0).max(0) as usize)
+        .take(limit.unwrap_or(50).max(0) as usize)
+        .collect()
+}
+
+impl InMemoryCompanyRepository {
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Company id to owner id, for repositories that filter by company owner
+    pub(crate) fn owners(&self) -> HashMap<Uuid, Uuid> {
+        let companies = self.companies.lock().unwrap();
+        companies.values().map(|c| (c.id, c.owner_id)).collect()
+    }
+
+    pub(crate) fn all(&self) -> Vec<Company> {
+        newest_first(self.companies.lock().unwrap().values().cloned().collect())
+    }
+
+    fn companies_where(&self, predicate: impl Fn(&Company) -> bool) -> Vec<Company> {
+        let companies = self.companies.lock().unwrap();
+        newest_first(companies.values().filter(|c| predicate(c)).cloned().collect())
+    }
+
+    fn ensure_unique_nib(companies: &HashMap<Uuid, Company>, company: &Company) -> AppResult<()> {
+        let taken = company.nib.is_some()
+            && companies
+                .values()
+                .any(|c| c.id != company.id && c.nib == company.nib);
+        if taken {
+            return Err(AppError::Conflict("NIB is already registered".to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CompanyRepository for InMemoryCompanyRepository {
+    async fn find_by_id(&self, id: &Uuid) -> AppResult<Option<Company>> {
+        Ok(self.companies.lock().unwrap().get(id).cloned())
+    }
+
+    async fn find_by_owner_id(&self, owner_id: &Uuid) -> AppResult<Vec<Company>> {
+        Ok(self.companies_where(|c| c.owner_id == *owner_id))
+    }
+
+    async fn find_by_nib(&self, nib: &str) -> AppResult<Option<Company>> {
+        let companies = self.companies.lock().unwrap();
+        Ok(companies.values().find(|c| c.nib.as_deref() == Some(nib)).cloned())
+    }
+
+    async fn save(&self, company: &Company) -> AppResult<()> {
+        let mut companies = self.companies.lock().unwrap();
+        if companies.contains_key(&company.id) {
+            return Err(AppError::Conflict("Company already exists".to_string()));
+        }
+        Self::ensure_unique_nib(&companies, company)?;
+
+        companies.insert(
+            company.id,
+            Company {
+                version: 1,
+                ..company.clone()
+            },
+        );
+        Ok(())
+    }
+
+    async fn update(&self, company: &Company) -> AppResult<()> {
+        let mut companies = self.companies.lock().unwrap();
+        let current = companies
+            .get(&company.id)
+            .map(|c| c.version)
+            .ok_or_else(|| AppError::NotFound("Company not found".to_string()))?;
+        check_version("Company", company.version, current)?;
+        Self::ensure_unique_nib(&companies, company)?;
+
+        let stored = companies.get_mut(&company.id).expect("checked above");
+        // The owner and creation time are not updatable
+        *stored = Company {
+            owner_id: stored.owner_id,
+            created_at: stored.created_at,
+            version: current + 1,
+            ..company.clone()
+        };
+        Ok(())
+    }
+
+    async fn delete(&self, id: &Uuid) -> AppResult<()> {
+        self.companies
+            .lock()
+            .unwrap()
+            .remove(id)
+            .map(|_| ())
+            .ok_or_else(|| AppError::NotFound("Company not found".to_string()))
+    }
+
+    async fn list_all(&self, limit: Option<i32>, offset: Option<i32>) -> AppResult<Vec<Company>> {
+        Ok(page(self.all(), limit, offset))
+    }
+
+    async fn count_by_owner(&self, owner_id: &Uuid) -> AppResult<i64> {
+        let companies = self.companies.lock().unwrap();
+        Ok(companies.values().filter(|c| c.owner_id == *owner_id).count() as i64)
+    }
+
+    async fn list(&self, query: &ListQuery<CompanyFilter, CompanySortField>) -> AppResult<Page<Company>> {
+        let matching = self.companies_where(|c| query.filter.matches(c));
+        Ok(paginate(matching, query))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::companies::{BusinessType, CompanyAddress};
+
+    fn company(owner_id: Uuid, name: &str, city: &str) -> Company {
+        Company::new(
+            owner_id,
+            name.to_string(),
+            BusinessType::UD,
+            "Kuliner".to_string(),
+            CompanyAddress::new(
+                "Jl. Malioboro 1".to_string(),
+                city.to_string(),
+                "DI Yogyakarta".to_string(),
+                "55271".to_string(),
+            ),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_crud_and_constraints() -> AppResult<()> {
+        let repo = InMemoryCompanyRepository::new();
+        let owner = Uuid::new_v4();
+
+        let mut first = company(owner, "Warung Sari", "Yogyakarta");
+        first.nib = Some("1234567890123".to_string());
+        repo.save(&first).await?;
+        assert!(matches!(repo.save(&first).await, Err(AppError::Conflict(_))));
+
+        let mut second = company(owner, "Bakpia Jaya", "Sleman");
+        second.nib = first.nib.clone();
+        assert!(matches!(repo.save(&second).await, Err(AppError::Conflict(_))));
+        second.nib = None;
+        repo.save(&second).await?;
+
+        assert_eq!(repo.count_by_owner(&owner).await?, 2);
+        assert_eq!(
+            repo.find_by_nib("1234567890123").await?.map(|c| c.id),
+            Some(first.id)
+        );
+
+        // The owner is kept on update, like the SQL statement which never sets it
+        let mut renamed = first.clone();
+        renamed.company_name = "Warung Sari Rasa".to_string();
+        renamed.owner_id = Uuid::new_v4();
+        repo.update(&renamed).await?;
+        let stored = repo.find_by_id(&first.id).await?.unwrap();
+        assert_eq!(stored.company_name, "Warung Sari Rasa");
+        assert_eq!(stored.owner_id, owner);
+        assert_eq!(stored.version, 2);
+
+        // A second editor who also loaded version 1 is turned away
+        let mut concurrent = first.clone();
+        concurrent.company_name = "Warung Sari Lama".to_string();
+        assert!(matches!(
+            repo.update(&concurrent).await,
+            Err(AppError::VersionConflict { current_version: 2, .. })
+        ));
+        assert_eq!(repo.find_by_id(&first.id).await?.unwrap().company_name, "Warung Sari Rasa");
+
+        repo.delete(&first.id).await?;
+        assert!(matches!(repo.delete(&first.id).await, Err(AppError::NotFound(_))));
+        assert!(matches!(repo.update(&first).await, Err(AppError::NotFound(_))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_filters() -> AppResult<()> {
+        let repo = InMemoryCompanyRepository::new();
+        let owner = Uuid::new_v4();
+        repo.save(&company(owner, "Warung Sari", "Yogyakarta")).await?;
+        repo.save(&company(owner, "Bakpia Jaya", "Sleman")).await?;
+        repo.save(&company(Uuid::new_v4(), "Batik Indah", "Yogyakarta")).await?;
+
+        let named = repo
+            .list(&ListQuery::new(CompanyFilter {
+                search: Some("jaya".to_string()),
+                ..Default::default()
+            }))
+            .await?;
+        assert_eq!(named.data.len(), 1);
+
+        let page = repo
+            .list(&ListQuery::new(CompanyFilter {
+                owner_id: Some(owner),
+                city: Some("YOGYAKARTA".to_string()),
+                ..Default::default()
+            }))
+            .await?;
+        assert_eq!(page.data.len(), 1);
+        assert_eq!(page.data[0].company_name, "Warung Sari");
+        Ok(())
+    }
+}
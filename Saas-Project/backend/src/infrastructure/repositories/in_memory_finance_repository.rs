@@ -0,0 +1,443 @@
+// In-memory finance repositories for testing and demo mode
+// The transaction, account, import, reconciliation and invoicing fakes share
+// one `InMemoryFinanceStore`, because imports, reconciliation adjustments and
+// invoice payments book transactions and move account balances like their
+// Postgres counterparts do inside a database transaction. Each method holds
+// the store lock throughout, which gives the same all-or-nothing behaviour.
+// Every write bumps the row version like the `bump_row_version` trigger. The
+// store is also the unit of work for `FinancialService`, with works staged on
+// a copy of it.
+
+use async_trait::async_trait;
+use chrono::Utc;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+use uuid::Uuid;
+
+use crate::domain::filters::{TransactionFilter, TransactionSortField};
+use crate::domain::finance::{
+    FinanceRepositories, FinancialAccount, FinancialAccountRepository, Transaction, TransactionId,
+    TransactionRepository,
+};
+use crate::domain::imports::{ImportBatch, ImportMapping};
+use crate::domain::invoicing::{Contact, InvoicePayment, SalesInvoice};
+use crate::domain::reconciliation::{ReconciliationSession, StatementLine};
+use crate::domain::unit_of_work::{UnitOfWork, Work};
+use crate::infrastructure::database::versioning::check_version;
+use crate::shared::errors::AppError;
+use crate::shared::query::{paginate, ListQuery, Page};
+
+use super::in_memory_unit_of_work::{StagedWork, WorkTurns};
+
+#[derive(Clone, Default)]
+pub(crate) struct FinanceStore {
+    pub(crate) accounts: HashMap<Uuid, FinancialAccount>,
+    pub(crate) transactions: HashMap<Uuid, Transaction>,
+    /// `(account_id, import_key)` of every imported transaction
+    pub(crate) import_keys: HashSet<(Uuid, String)>,
+    pub(crate) mappings: HashMap<Uuid, ImportMapping>,
+    pub(crate) batches: HashMap<Uuid, ImportBatch>,
+    pub(crate) sessions: HashMap<Uuid, ReconciliationSession>,
+    pub(crate) lines: HashMap<Uuid, StatementLine>,
+    pub(crate) contacts: HashMap<Uuid, Contact>,
+    pub(crate) sales_invoices: HashMap<Uuid, SalesInvoice>,
+    /// Last invoice number issued per `(company_id, year)`
+    pub(crate) invoice_sequences: HashMap<(Uuid, i32), i64>,
+    pub(crate) invoice_payments: HashMap<Uuid, InvoicePayment>,
+}
+
+/// Finance tables shared by the in-memory finance repositories
+#[derive(Clone, Default)]
+pub struct InMemoryFinanceStore {
+    store: Arc<Mutex<FinanceStore>>,
+    turns: WorkTurns,
+}
+
+impl InMemoryFinanceStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<'_, FinanceStore> {
+        self.store.lock().unwrap()
+    }
+}
+
+#[async_trait]
+impl UnitOfWork<FinanceRepositories> for InMemoryFinanceStore {
+    async fn begin(&self) -> Result<Work<FinanceRepositories>, AppError> {
+        let work = StagedWork::begin(&self.store, &self.turns).await;
+        let staged = InMemoryFinanceStore {
+            store: work.staged(),
+            turns: self.turns.clone(),
+        };
+        let repositories = FinanceRepositories {
+            transactions: Box::new(InMemoryTransactionRepository::new(staged.clone())),
+            accounts: Box::new(InMemoryFinancialAccountRepository::new(staged)),
+        };
+        Ok(Work::new(repositories, Box::new(work)))
+    }
+}
+
+pub(crate) fn constraint_violation(message: &str) -> AppError {
+    AppError::Database(sqlx::Error::Protocol(message.to_string()))
+}
+
+impl FinanceStore {
+    /// Inserts a transaction; an import key already booked in the account is
+    /// skipped and `false` returned, like `insert_transaction`
+    pub(crate) fn insert_transaction(
+        &mut self,
+        transaction: &Transaction,
+        import_key: Option<&str>,
+    ) -> Result<bool, AppError> {
+        if self.transactions.contains_key(&transaction.id.value()) {
+            return Err(constraint_violation(
+                "duplicate key value violates unique constraint \"financial_transactions_pkey\"",
+            ));
+        }
+        if !self.accounts.contains_key(&transaction.account_id) {
+            return Err(constraint_violation(
+                "insert or update on table \"financial_transactions\" violates foreign key constraint",
+            ));
+        }
+        if let Some(key) = import_key {
+            if !self.import_keys.insert((transaction.account_id, key.to_string())) {
+                return Ok(false);
+            }
+        }
+
+        self.transactions.insert(
+            transaction.id.value(),
+            Transaction {
+                version: 1,
+                ..transaction.clone()
+            },
+        );
+        Ok(true)
+    }
+
+    /// Adds a signed amount to the stored account balance
+    pub(crate) fn adjust_account_balance(&mut self, account_id: Uuid, amount: i64) {
+        if let Some(account) = self.accounts.get_mut(&account_id) {
+            account.balance.amount += amount;
+            account.updated_at = Utc::now();
+            account.version += 1;
+        }
+    }
+
+    /// Drops the account with everything that references it
+    fn delete_account(&mut self, id: Uuid) {
+        if self.accounts.remove(&id).is_none() {
+            return;
+        }
+        self.transactions.retain(|_, t| t.account_id != id);
+        self.import_keys.retain(|(account_id, _)| *account_id != id);
+        self.batches.retain(|_, b| b.account_id != id);
+
+        let sessions: HashSet<Uuid> = self
+            .sessions
+            .values()
+            .filter(|s| s.account_id == id)
+            .map(|s| s.id)
+            .collect();
+        self.sessions.retain(|id, _| !sessions.contains(id));
+        self.lines.retain(|_, l| !sessions.contains(&l.session_id));
+        // Lines of other accounts' sessions keep their row with the match cleared
+        let transactions = &self.transactions;
+        for line in self.lines.values_mut() {
+            if line.transaction_id.is_some_and(|t| !transactions.contains_key(&t)) {
+                line.transaction_id = None;
+            }
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryTransactionRepository {
+    store: InMemoryFinanceStore,
+}
+
+impl InMemoryTransactionRepository {
+    pub fn new(store: InMemoryFinanceStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl TransactionRepository for InMemoryTransactionRepository {
+    async fn create(&self, transaction: &Transaction) -> Result<Transaction, AppError> {
+        let mut store = self.store.lock();
+        store.insert_transaction(transaction, None)?;
+        Ok(store.transactions[&transaction.id.value()].clone())
+    }
+
+    async fn find_by_id(&self, id: &TransactionId) -> Result<Option<Transaction>, AppError> {
+        Ok(self.store.lock().transactions.get(&id.value()).cloned())
+    }
+
+    async fn update(&self, transaction: &Transaction) -> Result<Transaction, AppError> {
+        let mut store = self.store.lock();
+        let stored = store
+            .transactions
+            .get_mut(&transaction.id.value())
+            .ok_or_else(|| AppError::NotFound("Transaction not found".to_string()))?;
+        // Locked by a completed reconciliation
+        stored.ensure_editable()?;
+        check_version("Transaction", transaction.version, stored.version)?;
+
+        // Every column the SQL update sets
+        *stored = Transaction {
+            company_id: stored.company_id,
+            created_at: stored.created_at,
+            created_by: stored.created_by,
+            reconciliation_id: stored.reconciliation_id,
+            reconciled_at: stored.reconciled_at,
+            version: stored.version + 1,
+            ..transaction.clone()
+        };
+        Ok(Transaction {
+            version: stored.version,
+            ..transaction.clone()
+        })
+    }
+
+    async fn list(
+        &self,
+        query: &ListQuery<TransactionFilter, TransactionSortField>,
+    ) -> Result<Page<Transaction>, AppError> {
+        let store = self.store.lock();
+        let matching: Vec<Transaction> = store
+            .transactions
+            .values()
+            .filter(|t| query.filter.matches(t))
+            .cloned()
+            .collect();
+
+        Ok(paginate(matching, query))
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryFinancialAccountRepository {
+    store: InMemoryFinanceStore,
+}
+
+impl InMemoryFinancialAccountRepository {
+    pub fn new(store: InMemoryFinanceStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl FinancialAccountRepository for InMemoryFinancialAccountRepository {
+    async fn create(&self, account: &FinancialAccount) -> Result<FinancialAccount, AppError> {
+        let mut store = self.store.lock();
+        if store.accounts.contains_key(&account.id) {
+            return Err(constraint_violation(
+                "duplicate key value violates unique constraint \"financial_accounts_pkey\"",
+            ));
+        }
+        let stored = FinancialAccount {
+            version: 1,
+            ..account.clone()
+        };
+        store.accounts.insert(account.id, stored.clone());
+        Ok(stored)
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> Result<Option<FinancialAccount>, AppError> {
+        Ok(self.store.lock().accounts.get(&id).cloned())
+    }
+
+    async fn update(&self, account: &FinancialAccount) -> Result<FinancialAccount, AppError> {
+        let mut store = self.store.lock();
+        let stored = store
+            .accounts
+            .get_mut(&account.id)
+            .ok_or_else(|| AppError::NotFound("Account not found".to_string()))?;
+        check_version("Account", account.version, stored.version)?;
+
+        *stored = FinancialAccount {
+            company_id: stored.company_id,
+            created_at: stored.created_at,
+            version: stored.version + 1,
+            ..account.clone()
+        };
+        Ok(FinancialAccount {
+            version: stored.version,
+            ..account.clone()
+        })
+    }
+
+    async fn list_by_company(&self, company_id: Uuid) -> Result<Vec<FinancialAccount>, AppError> {
+        let store = self.store.lock();
+        let mut accounts: Vec<FinancialAccount> = store
+            .accounts
+            .values()
+            .filter(|a| a.company_id == company_id)
+            .cloned()
+            .collect();
+        accounts.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(accounts)
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), AppError> {
+        self.store.lock().delete_account(id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::finance::{FinancialService, TransactionStatus, TransactionType};
+    use crate::domain::value_objects::{Currency, Money};
+
+    fn account(company_id: Uuid) -> FinancialAccount {
+        FinancialAccount::new(
+            company_id,
+            "Kas".to_string(),
+            "cash".to_string(),
+            Currency::IDR,
+            Money::new(1_000_000, Currency::IDR),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_financial_service_books_against_the_shared_store() -> Result<(), AppError> {
+        let store = InMemoryFinanceStore::new();
+        let transactions = InMemoryTransactionRepository::new(store.clone());
+        let accounts = InMemoryFinancialAccountRepository::new(store.clone());
+        let company_id = Uuid::new_v4();
+        let account = accounts.create(&account(company_id)).await?;
+
+        let mut sale = Transaction::new(
+            company_id,
+            Utc::now(),
+            TransactionType::Income,
+            Money::new(250_000, Currency::IDR),
+            "Penjualan nasi goreng".to_string(),
+            account.id,
+            Uuid::new_v4(),
+        );
+        transactions.create(&sale).await?;
+
+        let service = FinancialService::new(store.clone());
+        service.execute_transaction(&mut sale).await?;
+
+        let stored = accounts.find_by_id(account.id).await?.unwrap();
+        assert_eq!(stored.balance.amount, 1_250_000);
+        let page = transactions
+            .list(&ListQuery::new(TransactionFilter {
+                account_id: Some(account.id),
+                status: Some(TransactionStatus::Completed),
+                search: Some("NASI".to_string()),
+                ..Default::default()
+            }))
+            .await?;
+        assert_eq!(page.data.len(), 1);
+
+        // A booking against an unknown account fails like the foreign key would
+        let orphan = Transaction {
+            id: TransactionId::new(),
+            account_id: Uuid::new_v4(),
+            ..sale.clone()
+        };
+        assert!(matches!(transactions.create(&orphan).await, Err(AppError::Database(_))));
+
+        // Deleting the account cascades to its transactions
+        accounts.delete(account.id).await?;
+        assert!(transactions.find_by_id(&sale.id).await?.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_balance_updates_from_the_same_read_conflict() -> Result<(), AppError> {
+        let store = InMemoryFinanceStore::new();
+        let transactions = InMemoryTransactionRepository::new(store.clone());
+        let accounts = InMemoryFinancialAccountRepository::new(store.clone());
+        let account = accounts.create(&account(Uuid::new_v4())).await?;
+
+        // Two bookings computed from the same balance: the second one loses
+        let mut first = account.clone();
+        first.update_balance(Money::new(100_000, Currency::IDR))?;
+        let mut second = account.clone();
+        second.update_balance(Money::new(-50_000, Currency::IDR))?;
+        assert_eq!(accounts.update(&first).await?.version, 2);
+        assert!(matches!(
+            accounts.update(&second).await,
+            Err(AppError::VersionConflict { current_version: 2, .. })
+        ));
+        assert_eq!(accounts.find_by_id(account.id).await?.unwrap().balance.amount, 1_100_000);
+
+        // Balance adjustments outside `update` invalidate copies read before them
+        store.lock().adjust_account_balance(account.id, 5_000);
+        let stale = accounts.find_by_id(account.id).await?.unwrap();
+        store.lock().adjust_account_balance(account.id, 5_000);
+        assert!(matches!(accounts.update(&stale).await, Err(AppError::VersionConflict { .. })));
+
+        let mut draft = Transaction::new(
+            account.company_id,
+            Utc::now(),
+            TransactionType::Expense,
+            Money::new(20_000, Currency::IDR),
+            "Beli gas".to_string(),
+            account.id,
+            Uuid::new_v4(),
+        );
+        let created = transactions.create(&draft).await?;
+        draft.description = "Beli gas 3 kg".to_string();
+        let saved = transactions.update(&draft).await?;
+        assert_eq!((created.version, saved.version), (1, 2));
+        assert!(matches!(
+            transactions.update(&draft).await,
+            Err(AppError::VersionConflict { current_version: 2, .. })
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failed_booking_leaves_balance_and_transaction_untouched() -> Result<(), AppError> {
+        let store = InMemoryFinanceStore::new();
+        let transactions = InMemoryTransactionRepository::new(store.clone());
+        let accounts = InMemoryFinancialAccountRepository::new(store.clone());
+        let account = accounts.create(&account(Uuid::new_v4())).await?;
+        let service = FinancialService::new(store.clone());
+
+        // The balance is saved before the transaction fails to complete
+        let mut cancelled = Transaction::new(
+            account.company_id,
+            Utc::now(),
+            TransactionType::Expense,
+            Money::new(75_000, Currency::IDR),
+            "Sewa lapak".to_string(),
+            account.id,
+            Uuid::new_v4(),
+        );
+        cancelled.status = TransactionStatus::Cancelled;
+        transactions.create(&cancelled).await?;
+        assert!(matches!(
+            service.execute_transaction(&mut cancelled.clone()).await,
+            Err(AppError::Validation(_))
+        ));
+        let stored = accounts.find_by_id(account.id).await?.unwrap();
+        assert_eq!((stored.balance.amount, stored.version), (1_000_000, 1));
+
+        // A new transaction is recorded together with the balance, once
+        let mut purchase = Transaction {
+            id: TransactionId::new(),
+            status: TransactionStatus::Pending,
+            ..cancelled
+        };
+        service.execute_transaction(&mut purchase).await?;
+        assert!(matches!(
+            service.execute_transaction(&mut purchase).await,
+            Err(AppError::Conflict(_))
+        ));
+        assert_eq!(accounts.find_by_id(account.id).await?.unwrap().balance.amount, 925_000);
+        let booked = transactions.find_by_id(&purchase.id).await?.unwrap();
+        assert_eq!(booked.status, TransactionStatus::Completed);
+        Ok(())
+    }
+}
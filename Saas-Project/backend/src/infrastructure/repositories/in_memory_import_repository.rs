@@ -0,0 +1,234 @@
+// In-memory transaction import repository for testing and demo mode
+// Books committed batches into the shared finance store; import keys already
+// present in the account are skipped like the partial unique index does.
+
+use async_trait::async_trait;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::domain::imports::{
+    ImportBatch, ImportBatchStatus, ImportMapping, ImportRepository, ImportedTransaction,
+};
+use crate::shared::errors::AppError;
+
+use super::in_memory_finance_repository::{constraint_violation, InMemoryFinanceStore};
+
+#[derive(Clone, Default)]
+pub struct InMemoryImportRepository {
+    store: InMemoryFinanceStore,
+}
+
+impl InMemoryImportRepository {
+    pub fn new(store: InMemoryFinanceStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl ImportRepository for InMemoryImportRepository {
+    async fn save_mapping(&self, mapping: &ImportMapping) -> Result<ImportMapping, AppError> {
+        let mut store = self.store.lock();
+        let existing = store
+            .mappings
+            .values_mut()
+            .find(|m| m.company_id == mapping.company_id && m.name == mapping.name);
+
+        match existing {
+            // ON CONFLICT (company_id, name) keeps the stored row's identity
+            Some(stored) => {
+                stored.mapping = mapping.mapping.clone();
+                stored.updated_at = mapping.updated_at;
+                Ok(stored.clone())
+            }
+            None => {
+                store.mappings.insert(mapping.id, mapping.clone());
+                Ok(mapping.clone())
+            }
+        }
+    }
+
+    async fn find_mapping(&self, company_id: Uuid, id: Uuid) -> Result<Option<ImportMapping>, AppError> {
+        let store = self.store.lock();
+        Ok(store
+            .mappings
+            .get(&id)
+            .filter(|m| m.company_id == company_id)
+            .cloned())
+    }
+
+    async fn list_mappings(&self, company_id: Uuid) -> Result<Vec<ImportMapping>, AppError> {
+        let store = self.store.lock();
+        let mut mappings: Vec<ImportMapping> = store
+            .mappings
+            .values()
+            .filter(|m| m.company_id == company_id)
+            .cloned()
+            .collect();
+        mappings.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(mappings)
+    }
+
+    async fn delete_mapping(&self, company_id: Uuid, id: Uuid) -> Result<(), AppError> {
+        let mut store = self.store.lock();
+        match store.mappings.get(&id) {
+            Some(mapping) if mapping.company_id == company_id => {
+                store.mappings.remove(&id);
+                Ok(())
+            }
+            _ => Err(AppError::NotFound("Import mapping not found".to_string())),
+        }
+    }
+
+    async fn save_batch(&self, batch: &ImportBatch) -> Result<(), AppError> {
+        let mut store = self.store.lock();
+        if store.batches.contains_key(&batch.id) {
+            return Err(constraint_violation(
+                "duplicate key value violates unique constraint \"import_batches_pkey\"",
+            ));
+        }
+        store.batches.insert(batch.id, batch.clone());
+        Ok(())
+    }
+
+    async fn find_batch(&self, company_id: Uuid, id: Uuid) -> Result<Option<ImportBatch>, AppError> {
+        let store = self.store.lock();
+        Ok(store
+            .batches
+            .get(&id)
+            .filter(|b| b.company_id == company_id)
+            .cloned())
+    }
+
+    async fn existing_import_keys(
+        &self,
+        account_id: Uuid,
+        keys: &[String],
+    ) -> Result<HashSet<String>, AppError> {
+        let store = self.store.lock();
+        Ok(keys
+            .iter()
+            .filter(|key| store.import_keys.contains(&(account_id, key.to_string())))
+            .cloned()
+            .collect())
+    }
+
+    async fn commit_batch(
+        &self,
+        batch: &ImportBatch,
+        transactions: &[ImportedTransaction],
+        committed_by: Uuid,
+    ) -> Result<ImportBatch, AppError> {
+        let mut store = self.store.lock();
+        let current = store
+            .batches
+            .get(&batch.id)
+            .filter(|b| b.company_id == batch.company_id)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound("Import batch not found".to_string()))?;
+        if current.status == ImportBatchStatus::Committed {
+            return Ok(current);
+        }
+
+        // Check the constraints up front so a failing batch books nothing
+        for item in transactions {
+            if store.transactions.contains_key(&item.transaction.id.value()) {
+                return Err(constraint_violation(
+                    "duplicate key value violates unique constraint \"financial_transactions_pkey\"",
+                ));
+            }
+            if !store.accounts.contains_key(&item.transaction.account_id) {
+                return Err(constraint_violation(
+                    "insert or update on table \"financial_transactions\" violates foreign key constraint",
+                ));
+            }
+        }
+
+        let mut inserted = HashSet::new();
+        let mut net_amount = 0i64;
+        for item in transactions {
+            if store.insert_transaction(&item.transaction, Some(&item.import_key))? {
+                inserted.insert(item.import_key.clone());
+                net_amount += item.signed_amount();
+            }
+        }
+        store.adjust_account_balance(batch.account_id, net_amount);
+
+        let mut committed = current;
+        committed.mark_committed(&inserted, committed_by);
+        store.batches.insert(committed.id, committed.clone());
+        Ok(committed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::finance::{FinancialAccount, FinancialAccountRepository};
+    use crate::domain::imports::{Cell, ImportRequest, StatementLayout, Table, TransactionImportService};
+    use crate::domain::value_objects::{Currency, Money};
+    use crate::infrastructure::repositories::in_memory_finance_repository::InMemoryFinancialAccountRepository;
+    use std::sync::Arc;
+
+    fn statement() -> Table {
+        let rows: &[&[&str]] = &[
+            &["Periode : ", "01/01/2024 - 31/01/2024"],
+            &["Tanggal Transaksi", "Keterangan", "Cabang", "Jumlah", "", "Saldo"],
+            &["'02/01", "'TRSF E-BANKING CR 0201/FTSCY/WS95031", "'0000", "150,000.00", "CR", "1,150,000.00"],
+            &["'03/01", "'BIAYA ADM", "'0000", "10,000.00", "DB", "1,140,000.00"],
+        ];
+        Table::new(
+            rows.iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|s| if s.is_empty() { Cell::Empty } else { Cell::Text(s.to_string()) })
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_reimported_statement_books_nothing_twice() -> Result<(), AppError> {
+        let store = InMemoryFinanceStore::new();
+        let accounts = InMemoryFinancialAccountRepository::new(store.clone());
+        let service = TransactionImportService::new(
+            Arc::new(InMemoryImportRepository::new(store.clone())),
+            Arc::new(accounts.clone()),
+        );
+
+        let company_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4();
+        let account = accounts
+            .create(&FinancialAccount::new(
+                company_id,
+                "BCA Operasional".to_string(),
+                "Bank".to_string(),
+                Currency::IDR,
+                Money::idr(1_000_000 * Money::RUPIAH),
+            ))
+            .await?;
+        let request = || ImportRequest {
+            account_id: account.id,
+            file_name: "mutasi-januari.csv".to_string(),
+            layout: Some(StatementLayout::Bca),
+            mapping_id: None,
+            mapping: None,
+        };
+
+        let batch = service.preview(company_id, user_id, request(), &statement()).await?;
+        let committed = service.commit(company_id, user_id, batch.id).await?;
+        assert_eq!(committed.summary.imported_rows, 2);
+        // Committing again is a no-op
+        service.commit(company_id, user_id, batch.id).await?;
+
+        let again = service.preview(company_id, user_id, request(), &statement()).await?;
+        assert_eq!(again.summary.duplicate_rows, 2);
+        let committed = service.commit(company_id, user_id, again.id).await?;
+        assert_eq!(committed.summary.imported_rows, 0);
+
+        let stored = accounts.find_by_id(account.id).await?.unwrap();
+        assert_eq!(stored.balance.amount, 1_140_000 * Money::RUPIAH);
+        assert_eq!(store.lock().transactions.len(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+// In-memory customer invoicing for tests and demo mode
+// Lives in the shared finance store so a payment books its transaction, moves
+// the account balance and settles its invoices under one lock, like
+// PostgresInvoicingRepository does in one database transaction. Mirrors its
+// version checks, numbering per company and issue year, and the contact
+// foreign key of invoices and payments.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, NaiveDate, SubsecRound, Utc};
+use uuid::Uuid;
+
+use crate::domain::finance::Transaction;
+use crate::domain::invoicing::{
+    invoice_number, Contact, InvoicePayment, InvoicingRepository, SalesInvoice, SalesInvoiceQuery,
+};
+use crate::infrastructure::database::versioning::check_version;
+use crate::shared::errors::{AppError, AppResult};
+
+use super::in_memory_finance_repository::{constraint_violation, FinanceStore, InMemoryFinanceStore};
+
+#[derive(Clone, Default)]
+pub struct InMemoryInvoicingRepository {
+    store: InMemoryFinanceStore,
+}
+
+impl InMemoryInvoicingRepository {
+    pub fn new(store: InMemoryFinanceStore) -> Self {
+        Self { store }
+    }
+}
+
+/// Postgres keeps microseconds
+fn micros(at: DateTime<Utc>) -> DateTime<Utc> {
+    at.trunc_subsecs(6)
+}
+
+fn require_contact(store: &FinanceStore, contact_id: Uuid) -> AppResult<()> {
+    if store.contacts.contains_key(&contact_id) {
+        Ok(())
+    } else {
+        Err(constraint_violation(
+            "insert or update on table \"sales_invoices\" violates foreign key constraint",
+        ))
+    }
+}
+
+fn stored_invoice(invoice: &SalesInvoice, number: Option<String>, version: i64) -> SalesInvoice {
+    SalesInvoice {
+        number,
+        sent_at: invoice.sent_at.map(micros),
+        emailed_at: invoice.emailed_at.map(micros),
+        voided_at: invoice.voided_at.map(micros),
+        version,
+        created_at: micros(invoice.created_at),
+        updated_at: micros(invoice.updated_at),
+        ..invoice.clone()
+    }
+}
+
+/// Checks an invoice update against the store without applying it
+fn check_invoice(store: &FinanceStore, invoice: &SalesInvoice) -> AppResult<()> {
+    let current = store
+        .sales_invoices
+        .get(&invoice.id)
+        .ok_or_else(|| AppError::NotFound("Invoice not found".to_string()))?;
+    check_version("Invoice", invoice.version, current.version)?;
+    require_contact(store, invoice.contact_id)
+}
+
+/// Applies a checked invoice update, numbering it if it was just sent
+fn save_invoice(store: &mut FinanceStore, invoice: &SalesInvoice) -> SalesInvoice {
+    let number = match (&invoice.number, invoice.sent_at, invoice.issue_date) {
+        (None, Some(_), Some(issue_date)) => {
+            let last = store
+                .invoice_sequences
+                .entry((invoice.company_id, issue_date.year()))
+                .or_insert(0);
+            *last += 1;
+            Some(invoice_number(issue_date.year(), *last))
+        }
+        (number, _, _) => number.clone(),
+    };
+    let saved = stored_invoice(invoice, number, invoice.version + 1);
+    store.sales_invoices.insert(saved.id, saved.clone());
+    saved
+}
+
+#[async_trait]
+impl InvoicingRepository for InMemoryInvoicingRepository {
+    async fn create_contact(&self, contact: &Contact) -> AppResult<Contact> {
+        let mut store = self.store.lock();
+        if store.contacts.contains_key(&contact.id) {
+            return Err(constraint_violation(
+                "duplicate key value violates unique constraint \"contacts_pkey\"",
+            ));
+        }
+        let created = Contact {
+            version: 1,
+            created_at: micros(contact.created_at),
+            updated_at: micros(contact.updated_at),
+            ..contact.clone()
+        };
+        store.contacts.insert(created.id, created.clone());
+        Ok(created)
+    }
+
+    async fn update_contact(&self, contact: &Contact) -> AppResult<Contact> {
+        let mut store = self.store.lock();
+        let current = store
+            .contacts
+            .get(&contact.id)
+            .ok_or_else(|| AppError::NotFound("Contact not found".to_string()))?;
+        check_version("Contact", contact.version, current.version)?;
+        let updated = Contact {
+            version: contact.version + 1,
+            created_at: current.created_at,
+            updated_at: micros(contact.updated_at),
+            ..contact.clone()
+        };
+        store.contacts.insert(updated.id, updated.clone());
+        Ok(updated)
+    }
+
+    async fn contact(&self, company_id: Uuid, id: Uuid) -> AppResult<Option<Contact>> {
+        Ok(self
+            .store
+            .lock()
+            .contacts
+            .get(&id)
+            .filter(|c| c.company_id == company_id)
+            .cloned())
+    }
+
+    async fn contacts(&self, company_id: Uuid) -> AppResult<Vec<Contact>> {
+        let mut contacts: Vec<Contact> = self
+            .store
+            .lock()
+            .contacts
+            .values()
+            .filter(|c| c.company_id == company_id)
+            .cloned()
+            .collect();
+        contacts.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+        Ok(contacts)
+    }
+
+    async fn create_invoice(&self, invoice: &SalesInvoice) -> AppResult<SalesInvoice> {
+        let mut store = self.store.lock();
+        if store.sales_invoices.contains_key(&invoice.id) {
+            return Err(constraint_violation(
+                "duplicate key value violates unique constraint \"sales_invoices_pkey\"",
+            ));
+        }
+        require_contact(&store, invoice.contact_id)?;
+        let created = stored_invoice(invoice, None, 1);
+        store.sales_invoices.insert(created.id, created.clone());
+        Ok(created)
+    }
+
+    async fn update_invoice(&self, invoice: &SalesInvoice) -> AppResult<SalesInvoice> {
+        let mut store = self.store.lock();
+        check_invoice(&store, invoice)?;
+        Ok(save_invoice(&mut store, invoice))
+    }
+
+    async fn invoice(&self, company_id: Uuid, id: Uuid) -> AppResult<Option<SalesInvoice>> {
+        Ok(self
+            .store
+            .lock()
+            .sales_invoices
+            .get(&id)
+            .filter(|i| i.company_id == company_id)
+            .cloned())
+    }
+
+    async fn invoices(&self, company_id: Uuid, query: &SalesInvoiceQuery, limit: i64) -> AppResult<Vec<SalesInvoice>> {
+        let mut invoices: Vec<SalesInvoice> = self
+            .store
+            .lock()
+            .sales_invoices
+            .values()
+            .filter(|i| i.company_id == company_id)
+            .filter(|i| query.status.is_none_or(|status| i.status == status))
+            .filter(|i| query.contact_id.is_none_or(|contact_id| i.contact_id == contact_id))
+            .filter(|i| !query.open || i.status.is_open())
+            .cloned()
+            .collect();
+        invoices.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+        invoices.truncate(limit.max(0) as usize);
+        Ok(invoices)
+    }
+
+    async fn record_payment(
+        &self,
+        payment: &InvoicePayment,
+        transaction: &Transaction,
+        invoices: &[SalesInvoice],
+    ) -> AppResult<(InvoicePayment, Vec<SalesInvoice>)> {
+        let mut store = self.store.lock();
+        for invoice in invoices {
+            check_invoice(&store, invoice)?;
+        }
+        if store.invoice_payments.contains_key(&payment.id) {
+            return Err(constraint_violation(
+                "duplicate key value violates unique constraint \"invoice_payments_pkey\"",
+            ));
+        }
+        require_contact(&store, payment.contact_id)?;
+        // Fails without touching the store when the account is missing
+        store.insert_transaction(transaction, None)?;
+
+        let saved = invoices.iter().map(|invoice| save_invoice(&mut store, invoice)).collect();
+        store.adjust_account_balance(transaction.account_id, transaction.signed_amount());
+        let stored = InvoicePayment {
+            created_at: micros(payment.created_at),
+            ..payment.clone()
+        };
+        store.invoice_payments.insert(stored.id, stored.clone());
+        Ok((stored, saved))
+    }
+
+    async fn payments(&self, company_id: Uuid, invoice_id: Uuid) -> AppResult<Vec<InvoicePayment>> {
+        let mut payments: Vec<InvoicePayment> = self
+            .store
+            .lock()
+            .invoice_payments
+            .values()
+            .filter(|p| p.company_id == company_id)
+            .filter(|p| p.allocations.iter().any(|a| a.invoice_id == invoice_id))
+            .cloned()
+            .collect();
+        payments.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        Ok(payments)
+    }
+
+    async fn mark_overdue(&self, today: NaiveDate) -> AppResult<u64> {
+        let now = micros(Utc::now());
+        let mut store = self.store.lock();
+        let mut marked = 0;
+        for invoice in store.sales_invoices.values_mut() {
+            if invoice.mark_overdue(today) {
+                invoice.updated_at = now;
+                invoice.version += 1;
+                marked += 1;
+            }
+        }
+        Ok(marked)
+    }
+}
@@ -0,0 +1,127 @@
+// In-memory license comments for tests and demo mode
+// Mirrors PostgresLicenseCommentRepository: comments oldest first, sorted
+// attachments and mentions, the author's own read recorded on posting, and
+// reads that keep their first timestamp.
+
+use async_trait::async_trait;
+use chrono::{DateTime, SubsecRound, Utc};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::domain::license_comments::{LicenseComment, LicenseCommentRepository, ReadReceipt};
+use crate::shared::errors::{AppError, AppResult};
+
+#[derive(Default)]
+struct CommentStore {
+    comments: Vec<LicenseComment>,
+    reads: Vec<ReadReceipt>,
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryLicenseCommentRepository {
+    store: Arc<Mutex<CommentStore>>,
+}
+
+impl InMemoryLicenseCommentRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Postgres keeps microseconds
+fn micros(at: DateTime<Utc>) -> DateTime<Utc> {
+    at.trunc_subsecs(6)
+}
+
+#[async_trait]
+impl LicenseCommentRepository for InMemoryLicenseCommentRepository {
+    async fn create_comment(&self, comment: &LicenseComment) -> AppResult<LicenseComment> {
+        let mut store = self.store.lock().unwrap();
+        if store.comments.iter().any(|c| c.id == comment.id) {
+            return Err(AppError::Conflict("Comment already exists".to_string()));
+        }
+        let mut stored = LicenseComment {
+            created_at: micros(comment.created_at),
+            ..comment.clone()
+        };
+        stored.document_ids.sort();
+        stored.document_ids.dedup();
+        stored.mentions.sort();
+        stored.mentions.dedup();
+
+        store.reads.push(ReadReceipt {
+            comment_id: stored.id,
+            user_id: stored.author_id,
+            read_at: stored.created_at,
+        });
+        store.comments.push(stored.clone());
+        Ok(stored)
+    }
+
+    async fn get_comment(&self, id: Uuid) -> AppResult<Option<LicenseComment>> {
+        let store = self.store.lock().unwrap();
+        Ok(store.comments.iter().find(|c| c.id == id).cloned())
+    }
+
+    async fn comments(&self, license_id: Uuid, include_internal: bool) -> AppResult<Vec<LicenseComment>> {
+        let store = self.store.lock().unwrap();
+        let mut comments: Vec<LicenseComment> = store
+            .comments
+            .iter()
+            .filter(|c| c.license_id == license_id && (include_internal || !c.internal))
+            .cloned()
+            .collect();
+        comments.sort_by_key(|c| (c.created_at, c.id));
+        Ok(comments)
+    }
+
+    async fn read_receipts(&self, license_id: Uuid) -> AppResult<Vec<ReadReceipt>> {
+        let store = self.store.lock().unwrap();
+        let mut receipts: Vec<ReadReceipt> = store
+            .reads
+            .iter()
+            .filter(|r| {
+                store
+                    .comments
+                    .iter()
+                    .any(|c| c.id == r.comment_id && c.license_id == license_id)
+            })
+            .copied()
+            .collect();
+        receipts.sort_by_key(|r| (r.read_at, r.comment_id, r.user_id));
+        Ok(receipts)
+    }
+
+    async fn mark_read(
+        &self,
+        license_id: Uuid,
+        user_id: Uuid,
+        include_internal: bool,
+        at: DateTime<Utc>,
+    ) -> AppResult<u64> {
+        let mut store = self.store.lock().unwrap();
+        let at = micros(at);
+        let unread: Vec<Uuid> = store
+            .comments
+            .iter()
+            .filter(|c| c.license_id == license_id && (include_internal || !c.internal))
+            .filter(|c| c.created_at <= at)
+            .filter(|c| {
+                !store
+                    .reads
+                    .iter()
+                    .any(|r| r.comment_id == c.id && r.user_id == user_id)
+            })
+            .map(|c| c.id)
+            .collect();
+
+        for comment_id in &unread {
+            store.reads.push(ReadReceipt {
+                comment_id: *comment_id,
+                user_id,
+                read_at: at,
+            });
+        }
+        Ok(unread.len() as u64)
+    }
+}
@@ -0,0 +1,786 @@
+// In-memory license repository for testing and demo mode
+// Mirrors PostgresLicenseRepositoryImpl: same ordering, constraint failures,
+// cascading deletes and microsecond timestamps, so the conformance suite can
+// hold both to the same expectations. Units of work run on a staged copy of
+// the store.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, SubsecRound, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::domain::certificates::{CertificateRepository, LicenseCertificate};
+use crate::domain::filters::{DocumentFilter, DocumentSortField, LicenseFilter, LicenseSortField};
+use crate::domain::licenses::{
+    ApplicationStatus, ApplicationStatusHistory, License, LicenseDocument, LicenseType,
+};
+use crate::domain::sla::{SlaDecision, SlaEvent, SlaRepository};
+use crate::domain::unit_of_work::{UnitOfWork, Work};
+use crate::domain::verification::{VerificationAuditRepository, VerificationLookup};
+use crate::infrastructure::database::versioning::check_version;
+use crate::shared::errors::{AppError, AppResult};
+use crate::shared::query::{paginate, ListQuery, Page};
+
+use super::cached_license_repository::{LicenseRepositories, LicenseRepository};
+use super::in_memory_company_repository::InMemoryCompanyRepository;
+use super::in_memory_unit_of_work::{StagedWork, WorkTurns};
+use super::license_repository::LicenseStatistics;
+
+#[derive(Clone, Default)]
+struct LicenseStore {
+    licenses: HashMap<Uuid, License>,
+    documents: HashMap<Uuid, LicenseDocument>,
+    history: HashMap<Uuid, ApplicationStatusHistory>,
+    sla_events: HashMap<Uuid, SlaEvent>,
+    certificates: HashMap<Uuid, LicenseCertificate>,
+    verification_lookups: HashMap<Uuid, VerificationLookup>,
+    /// Company owners, for the `owner_id` list filter
+    company_owners: HashMap<Uuid, Uuid>,
+}
+
+/// A simple in-memory implementation of the LicenseRepository trait for testing purposes
+#[derive(Clone, Default)]
+pub struct InMemoryLicenseRepository {
+    store: Arc<Mutex<LicenseStore>>,
+    /// Where company owners are read from when linked, instead of the owners
+    /// recorded with `with_company_owner`
+    companies: Option<InMemoryCompanyRepository>,
+    turns: WorkTurns,
+}
+
+/// Postgres keeps microseconds
+fn micros(at: DateTime<Utc>) -> DateTime<Utc> {
+    at.trunc_subsecs(6)
+}
+
+fn now() -> DateTime<Utc> {
+    micros(Utc::now())
+}
+
+fn constraint_violation(message: &str) -> sqlx::Error {
+    sqlx::Error::Protocol(message.to_string())
+}
+
+fn stored_license(license: &License) -> License {
+    License {
+        issue_date: license.issue_date.map(micros),
+        expiry_date: license.expiry_date.map(micros),
+        created_at: micros(license.created_at),
+        updated_at: micros(license.updated_at),
+        submitted_at: license.submitted_at.map(micros),
+        approved_at: license.approved_at.map(micros),
+        rejected_at: license.rejected_at.map(micros),
+        ..license.clone()
+    }
+}
+
+/// The pause bookkeeping the trigger derives from a status change
+fn track_sla_pause(old: &License, new: &mut License, at: DateTime<Utc>) {
+    new.sla_paused_at = old.sla_paused_at;
+    new.sla_paused_seconds = old.sla_paused_seconds;
+    let pending = |license: &License| license.application_status == ApplicationStatus::PendingDocuments;
+    match (pending(old), pending(new)) {
+        (false, true) => new.sla_paused_at = Some(at),
+        (true, false) => {
+            let paused_at = old.sla_paused_at.unwrap_or(at);
+            new.sla_paused_seconds += (at - paused_at).num_seconds().max(0);
+            new.sla_paused_at = None;
+        }
+        _ => {}
+    }
+}
+
+fn stored_document(document: &LicenseDocument) -> LicenseDocument {
+    LicenseDocument {
+        upload_date: micros(document.upload_date),
+        verified_at: document.verified_at.map(micros),
+        ..document.clone()
+    }
+}
+
+fn newest_first(mut licenses: Vec<License>) -> Vec<License> {
+    licenses.sort_by_key(|l| std::cmp::Reverse(l.created_at));
+    licenses
+}
+
+fn contains_ignore_case(haystack: Option<&str>, needle: &str) -> bool {
+    haystack.is_some_and(|value| value.to_lowercase().contains(needle))
+}
+
+impl InMemoryLicenseRepository {
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records who owns a company, so `LicenseFilter::owner_id` also matches
+    /// licenses filed by someone else under that company
+    #[allow(dead_code)]
+    pub fn with_company_owner(self, company_id: Uuid, owner_id: Uuid) -> Self {
+        self.store
+            .lock()
+            .unwrap()
+            .company_owners
+            .insert(company_id, owner_id);
+        self
+    }
+
+    /// Reads company owners from `companies` on every owner-filtered list, so
+    /// companies registered later are picked up too
+    pub fn with_companies(mut self, companies: InMemoryCompanyRepository) -> Self {
+        self.companies = Some(companies);
+        self
+    }
+
+    /// Every stored license and document, for the in-memory search
+    pub(crate) fn snapshot(&self) -> (Vec<License>, Vec<LicenseDocument>) {
+        let store = self.store.lock().unwrap();
+        (
+            store.licenses.values().cloned().collect(),
+            store.documents.values().cloned().collect(),
+        )
+    }
+
+    /// Every recorded status change, for the admin statistics
+    pub(crate) fn history(&self) -> Vec<ApplicationStatusHistory> {
+        self.store.lock().unwrap().history.values().cloned().collect()
+    }
+
+    fn licenses_where(&self, predicate: impl Fn(&License) -> bool) -> Vec<License> {
+        let store = self.store.lock().unwrap();
+        newest_first(store.licenses.values().filter(|l| predicate(l)).cloned().collect())
+    }
+
+    /// Applies `change` to a stored license, like an `UPDATE ... RETURNING *`
+    fn modify(
+        &self,
+        id: Uuid,
+        change: impl FnOnce(&mut License) -> bool,
+    ) -> Result<License, sqlx::Error> {
+        let mut store = self.store.lock().unwrap();
+        let license = store.licenses.get_mut(&id).ok_or(sqlx::Error::RowNotFound)?;
+        let mut updated = license.clone();
+        if !change(&mut updated) {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        // Like the bump_row_version and track_sla_pause triggers
+        updated.version = license.version + 1;
+        track_sla_pause(license, &mut updated, now());
+        *license = stored_license(&updated);
+        Ok(license.clone())
+    }
+
+    /// `modify` for conditional updates, which only apply to `expected_version`
+    fn modify_at(
+        &self,
+        id: Uuid,
+        expected_version: i64,
+        change: impl FnOnce(&mut License),
+    ) -> AppResult<License> {
+        let current = self.store.lock().unwrap().licenses.get(&id).map(|l| l.version);
+        match current {
+            None => Err(AppError::NotFound("License not found".to_string())),
+            Some(current) => {
+                check_version("License", expected_version, current)?;
+                Ok(self.modify(id, |license| {
+                    change(license);
+                    true
+                })?)
+            }
+        }
+    }
+}
+
+impl LicenseStore {
+    fn matches(&self, filter: &LicenseFilter, license: &License) -> bool {
+        filter.owner_id.is_none_or(|owner| {
+            license.user_id == owner || self.company_owners.get(&license.company_id) == Some(&owner)
+        }) && filter.company_id.is_none_or(|id| license.company_id == id)
+            && filter.status.as_ref().is_none_or(|s| &license.application_status == s)
+            && filter.license_type.is_none_or(|t| license.license_type == t)
+            && filter.priority.as_ref().is_none_or(|p| &license.priority == p)
+            && filter
+                .search
+                .as_deref()
+                .map(str::trim)
+                .filter(|q| !q.is_empty())
+                .is_none_or(|q| {
+                    let q = q.to_lowercase();
+                    contains_ignore_case(Some(&license.title), &q)
+                        || contains_ignore_case(license.license_number.as_deref(), &q)
+                        || contains_ignore_case(license.description.as_deref(), &q)
+                })
+    }
+}
+
+#[async_trait]
+impl UnitOfWork<LicenseRepositories> for InMemoryLicenseRepository {
+    async fn begin(&self) -> AppResult<Work<LicenseRepositories>> {
+        let work = StagedWork::begin(&self.store, &self.turns).await;
+        let licenses = InMemoryLicenseRepository {
+            store: work.staged(),
+            companies: self.companies.clone(),
+            turns: self.turns.clone(),
+        };
+        Ok(Work::new(
+            LicenseRepositories {
+                licenses: Box::new(licenses),
+            },
+            Box::new(work),
+        ))
+    }
+}
+
+#[async_trait]
+impl LicenseRepository for InMemoryLicenseRepository {
+    async fn create_license(&self, license: &License) -> Result<License, sqlx::Error> {
+        let mut store = self.store.lock().unwrap();
+        if store.licenses.contains_key(&license.id) {
+            return Err(constraint_violation("duplicate key value violates unique constraint \"licenses_pkey\""));
+        }
+        if license.license_number.is_some()
+            && store
+                .licenses
+                .values()
+                .any(|l| l.license_number == license.license_number)
+        {
+            return Err(constraint_violation(
+                "duplicate key value violates unique constraint \"licenses_license_number_key\"",
+            ));
+        }
+
+        let paused = license.application_status == ApplicationStatus::PendingDocuments;
+        let stored = License {
+            version: 1,
+            sla_paused_at: paused.then(now),
+            sla_paused_seconds: 0,
+            ..stored_license(license)
+        };
+        store.licenses.insert(stored.id, stored.clone());
+        Ok(stored)
+    }
+
+    async fn get_license_by_id(&self, id: Uuid) -> Result<Option<License>, sqlx::Error> {
+        Ok(self.store.lock().unwrap().licenses.get(&id).cloned())
+    }
+
+    async fn get_license_by_number(&self, license_number: &str) -> Result<Option<License>, sqlx::Error> {
+        let store = self.store.lock().unwrap();
+        Ok(store
+            .licenses
+            .values()
+            .find(|l| l.license_number.as_deref() == Some(license_number))
+            .cloned())
+    }
+
+    async fn get_licenses_by_user(&self, user_id: Uuid) -> Result<Vec<License>, sqlx::Error> {
+        Ok(self.licenses_where(|l| l.user_id == user_id))
+    }
+
+    async fn get_licenses_by_company(&self, company_id: Uuid) -> Result<Vec<License>, sqlx::Error> {
+        Ok(self.licenses_where(|l| l.company_id == company_id))
+    }
+
+    async fn update_license(&self, license: &License) -> AppResult<License> {
+        {
+            let store = self.store.lock().unwrap();
+            if license.license_number.is_some()
+                && store
+                    .licenses
+                    .values()
+                    .any(|l| l.id != license.id && l.license_number == license.license_number)
+            {
+                return Err(constraint_violation(
+                    "duplicate key value violates unique constraint \"licenses_license_number_key\"",
+                )
+                .into());
+            }
+        }
+
+        self.modify_at(license.id, license.version, |stored| {
+            // Every column except the key and creation time
+            *stored = License {
+                id: stored.id,
+                created_at: stored.created_at,
+                ..license.clone()
+            };
+        })
+    }
+
+    async fn delete_license(&self, id: Uuid) -> Result<bool, sqlx::Error> {
+        let mut store = self.store.lock().unwrap();
+        if store.licenses.remove(&id).is_none() {
+            return Ok(false);
+        }
+        // ON DELETE CASCADE
+        store.documents.retain(|_, d| d.license_id != id);
+        store.history.retain(|_, h| h.license_id != id);
+        store.sla_events.retain(|_, e| e.license_id != id);
+        store.certificates.retain(|_, c| c.license_id != id);
+        // ON DELETE SET NULL
+        for lookup in store.verification_lookups.values_mut() {
+            if lookup.license_id == Some(id) {
+                lookup.license_id = None;
+            }
+        }
+        Ok(true)
+    }
+
+    async fn get_licenses_by_status(
+        &self,
+        status: ApplicationStatus,
+    ) -> Result<Vec<License>, sqlx::Error> {
+        Ok(self.licenses_where(|l| l.application_status == status))
+    }
+
+    async fn get_licenses_by_type(
+        &self,
+        license_type: LicenseType,
+    ) -> Result<Vec<License>, sqlx::Error> {
+        Ok(self.licenses_where(|l| l.license_type == license_type))
+    }
+
+    async fn get_expiring_licenses(&self, days_ahead: i32) -> Result<Vec<License>, sqlx::Error> {
+        let horizon = Utc::now() + Duration::days(days_ahead as i64);
+        let mut licenses = self.licenses_where(|l| l.expiry_date.is_some_and(|at| at <= horizon));
+        licenses.sort_by_key(|l| l.expiry_date);
+        Ok(licenses)
+    }
+
+    async fn list_licenses(
+        &self,
+        query: &ListQuery<LicenseFilter, LicenseSortField>,
+    ) -> Result<Page<License>, sqlx::Error> {
+        // Read before taking the store lock, the company repository has its own
+        let owners = self.companies.as_ref().map(|companies| companies.owners());
+        let mut store = self.store.lock().unwrap();
+        if let Some(owners) = owners {
+            store.company_owners = owners;
+        }
+        let matching: Vec<License> = store
+            .licenses
+            .values()
+            .filter(|l| store.matches(&query.filter, l))
+            .cloned()
+            .collect();
+
+        Ok(paginate(matching, query))
+    }
+
+    async fn list_documents(
+        &self,
+        query: &ListQuery<DocumentFilter, DocumentSortField>,
+    ) -> Result<Page<LicenseDocument>, sqlx::Error> {
+        let filter = &query.filter;
+        let store = self.store.lock().unwrap();
+        let matching: Vec<LicenseDocument> = store
+            .documents
+            .values()
+            .filter(|d| {
+                filter.license_id.is_none_or(|id| d.license_id == id)
+                    && filter.document_type.as_ref().is_none_or(|t| &d.document_type == t)
+                    && filter.is_verified.is_none_or(|v| d.is_verified == v)
+            })
+            .cloned()
+            .collect();
+
+        Ok(paginate(matching, query))
+    }
+
+    async fn create_document(
+        &self,
+        document: &LicenseDocument,
+    ) -> Result<LicenseDocument, sqlx::Error> {
+        let mut store = self.store.lock().unwrap();
+        if store.documents.contains_key(&document.id) {
+            return Err(constraint_violation(
+                "duplicate key value violates unique constraint \"license_documents_pkey\"",
+            ));
+        }
+        if !store.licenses.contains_key(&document.license_id) {
+            return Err(constraint_violation(
+                "insert on table \"license_documents\" violates foreign key constraint",
+            ));
+        }
+
+        let stored = stored_document(document);
+        store.documents.insert(stored.id, stored.clone());
+        Ok(stored)
+    }
+
+    async fn get_documents_by_license(
+        &self,
+        license_id: Uuid,
+    ) -> Result<Vec<LicenseDocument>, sqlx::Error> {
+        let store = self.store.lock().unwrap();
+        let mut documents: Vec<LicenseDocument> = store
+            .documents
+            .values()
+            .filter(|d| d.license_id == license_id)
+            .cloned()
+            .collect();
+        documents.sort_by_key(|d| d.upload_date);
+        Ok(documents)
+    }
+
+    async fn get_document_by_id(&self, id: Uuid) -> Result<Option<LicenseDocument>, sqlx::Error> {
+        Ok(self.store.lock().unwrap().documents.get(&id).cloned())
+    }
+
+    async fn update_document(
+        &self,
+        document: &LicenseDocument,
+    ) -> Result<LicenseDocument, sqlx::Error> {
+        let mut store = self.store.lock().unwrap();
+        let stored = store
+            .documents
+            .get_mut(&document.id)
+            .ok_or(sqlx::Error::RowNotFound)?;
+        // The owning license is not part of the update
+        *stored = stored_document(&LicenseDocument {
+            license_id: stored.license_id,
+            ..document.clone()
+        });
+        Ok(stored.clone())
+    }
+
+    async fn delete_document(&self, id: Uuid) -> Result<bool, sqlx::Error> {
+        Ok(self.store.lock().unwrap().documents.remove(&id).is_some())
+    }
+
+    async fn create_status_history(
+        &self,
+        history: &ApplicationStatusHistory,
+    ) -> Result<ApplicationStatusHistory, sqlx::Error> {
+        let mut store = self.store.lock().unwrap();
+        if store.history.contains_key(&history.id) {
+            return Err(constraint_violation(
+                "duplicate key value violates unique constraint \"application_status_history_pkey\"",
+            ));
+        }
+        if !store.licenses.contains_key(&history.license_id) {
+            return Err(constraint_violation(
+                "insert on table \"application_status_history\" violates foreign key constraint",
+            ));
+        }
+
+        let stored = ApplicationStatusHistory {
+            changed_at: micros(history.changed_at),
+            ..history.clone()
+        };
+        store.history.insert(stored.id, stored.clone());
+        Ok(stored)
+    }
+
+    async fn get_status_history_by_license(
+        &self,
+        license_id: Uuid,
+    ) -> Result<Vec<ApplicationStatusHistory>, sqlx::Error> {
+        let store = self.store.lock().unwrap();
+        let mut history: Vec<ApplicationStatusHistory> = store
+            .history
+            .values()
+            .filter(|h| h.license_id == license_id)
+            .cloned()
+            .collect();
+        history.sort_by_key(|h| h.changed_at);
+        Ok(history)
+    }
+
+    async fn submit_license_application(
+        &self,
+        license_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<License, sqlx::Error> {
+        self.modify(license_id, |license| {
+            if license.user_id != user_id {
+                return false;
+            }
+            let at = now();
+            license.application_status = ApplicationStatus::Submitted;
+            license.submitted_at = Some(at);
+            license.updated_at = at;
+            true
+        })
+    }
+
+    async fn approve_license(
+        &self,
+        license_id: Uuid,
+        expected_version: i64,
+        _admin_user_id: Uuid,
+        license_number: String,
+        issue_date: DateTime<Utc>,
+        expiry_date: Option<DateTime<Utc>>,
+        issuing_authority: String,
+        admin_notes: Option<String>,
+    ) -> AppResult<License> {
+        let taken = self.store.lock().unwrap().licenses.values().any(|l| {
+            l.id != license_id && l.license_number.as_deref() == Some(license_number.as_str())
+        });
+        if taken {
+            return Err(constraint_violation(
+                "duplicate key value violates unique constraint \"licenses_license_number_key\"",
+            )
+            .into());
+        }
+
+        self.modify_at(license_id, expected_version, |license| {
+            let at = now();
+            license.application_status = ApplicationStatus::Approved;
+            license.license_number = Some(license_number);
+            license.issue_date = Some(issue_date);
+            license.expiry_date = expiry_date;
+            license.issuing_authority = Some(issuing_authority);
+            license.admin_notes = admin_notes;
+            license.approved_at = Some(at);
+            license.updated_at = at;
+            license.actual_processing_days = license
+                .submitted_at
+                .map(|submitted| (at - submitted).num_days() as i32);
+        })
+    }
+
+    async fn reject_license(
+        &self,
+        license_id: Uuid,
+        expected_version: i64,
+        _admin_user_id: Uuid,
+        reason: String,
+        admin_notes: Option<String>,
+    ) -> AppResult<License> {
+        self.modify_at(license_id, expected_version, |license| {
+            let at = now();
+            license.application_status = ApplicationStatus::Rejected;
+            license.rejection_reason = Some(reason);
+            license.admin_notes = admin_notes;
+            license.rejected_at = Some(at);
+            license.updated_at = at;
+        })
+    }
+
+    async fn get_license_count_by_type(&self) -> Result<Vec<(LicenseType, i64)>, sqlx::Error> {
+        let store = self.store.lock().unwrap();
+        let mut counts: HashMap<LicenseType, i64> = HashMap::new();
+        for license in store.licenses.values() {
+            *counts.entry(license.license_type).or_default() += 1;
+        }
+        Ok(counts.into_iter().collect())
+    }
+
+    async fn get_license_count_by_status(
+        &self,
+    ) -> Result<Vec<(ApplicationStatus, i64)>, sqlx::Error> {
+        let store = self.store.lock().unwrap();
+        let mut counts: HashMap<ApplicationStatus, i64> = HashMap::new();
+        for license in store.licenses.values() {
+            *counts.entry(license.application_status.clone()).or_default() += 1;
+        }
+        Ok(counts.into_iter().collect())
+    }
+
+    async fn get_processing_times(&self) -> Result<Vec<(LicenseType, f64)>, sqlx::Error> {
+        let store = self.store.lock().unwrap();
+        let mut days: HashMap<LicenseType, (i64, i64)> = HashMap::new();
+        for license in store.licenses.values() {
+            if let Some(actual) = license.actual_processing_days {
+                let (sum, count) = days.entry(license.license_type).or_default();
+                *sum += actual as i64;
+                *count += 1;
+            }
+        }
+        Ok(days
+            .into_iter()
+            .map(|(license_type, (sum, count))| (license_type, sum as f64 / count as f64))
+            .collect())
+    }
+
+    async fn get_license_statistics(
+        &self,
+        user_id: Option<Uuid>,
+    ) -> Result<LicenseStatistics, sqlx::Error> {
+        let licenses = self.licenses_where(|l| user_id.is_none_or(|uid| l.user_id == uid));
+        let count = |status: ApplicationStatus| {
+            licenses
+                .iter()
+                .filter(|l| l.application_status == status)
+                .count() as i64
+        };
+        let processing_days: Vec<i32> = licenses
+            .iter()
+            .filter_map(|l| l.actual_processing_days)
+            .collect();
+
+        Ok(LicenseStatistics {
+            total_licenses: licenses.len() as i64,
+            draft_count: count(ApplicationStatus::Draft),
+            submitted_count: count(ApplicationStatus::Submitted),
+            processing_count: count(ApplicationStatus::Processing),
+            approved_count: count(ApplicationStatus::Approved),
+            rejected_count: count(ApplicationStatus::Rejected),
+            avg_processing_days: (!processing_days.is_empty()).then(|| {
+                processing_days.iter().map(|d| *d as f64).sum::<f64>() / processing_days.len() as f64
+            }),
+        })
+    }
+}
+
+#[async_trait]
+impl SlaRepository for InMemoryLicenseRepository {
+    async fn record_event(&self, event: &SlaEvent) -> AppResult<bool> {
+        let mut store = self.store.lock().unwrap();
+        if !store.licenses.contains_key(&event.license_id) {
+            return Err(constraint_violation(
+                "insert on table \"license_sla_events\" violates foreign key constraint",
+            )
+            .into());
+        }
+        // ON CONFLICT (license_id, kind, threshold_percent) DO NOTHING
+        let recorded = store.sla_events.values().any(|e| {
+            e.license_id == event.license_id
+                && e.kind == event.kind
+                && e.threshold_percent == event.threshold_percent
+        });
+        if recorded {
+            return Ok(false);
+        }
+        let stored = SlaEvent {
+            created_at: micros(event.created_at),
+            ..event.clone()
+        };
+        store.sla_events.insert(stored.id, stored);
+        Ok(true)
+    }
+
+    async fn events_for_licenses(&self, license_ids: &[Uuid]) -> AppResult<Vec<SlaEvent>> {
+        let store = self.store.lock().unwrap();
+        let mut events: Vec<SlaEvent> = store
+            .sla_events
+            .values()
+            .filter(|e| license_ids.contains(&e.license_id))
+            .cloned()
+            .collect();
+        events.sort_by_key(|e| (e.created_at, e.threshold_percent));
+        Ok(events)
+    }
+
+    async fn decisions(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> AppResult<Vec<SlaDecision>> {
+        let store = self.store.lock().unwrap();
+        let mut decisions: Vec<SlaDecision> = store
+            .licenses
+            .values()
+            .filter(|l| {
+                matches!(
+                    l.application_status,
+                    ApplicationStatus::Approved | ApplicationStatus::Rejected
+                )
+            })
+            .filter(|l| {
+                l.approved_at
+                    .or(l.rejected_at)
+                    .is_some_and(|decided_at| decided_at >= from && decided_at < to)
+            })
+            .map(|license| SlaDecision {
+                license: license.clone(),
+                decided_by: store
+                    .history
+                    .values()
+                    .filter(|h| h.license_id == license.id && h.to_status == license.application_status)
+                    .max_by_key(|h| h.changed_at)
+                    .map(|h| h.changed_by),
+            })
+            .collect();
+        decisions.sort_by_key(|d| d.license.approved_at.or(d.license.rejected_at));
+        Ok(decisions)
+    }
+}
+
+#[async_trait]
+impl CertificateRepository for InMemoryLicenseRepository {
+    async fn record_certificate(&self, certificate: &LicenseCertificate) -> AppResult<LicenseCertificate> {
+        let mut store = self.store.lock().unwrap();
+        if !store.licenses.contains_key(&certificate.license_id) {
+            return Err(constraint_violation(
+                "insert on table \"license_certificates\" violates foreign key constraint",
+            )
+            .into());
+        }
+        let revision = store
+            .certificates
+            .values()
+            .filter(|c| c.license_id == certificate.license_id)
+            .map(|c| c.revision)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        let stored = LicenseCertificate {
+            revision,
+            generated_at: micros(certificate.generated_at),
+            ..certificate.clone()
+        };
+        store.certificates.insert(stored.id, stored.clone());
+        Ok(stored)
+    }
+
+    async fn latest_certificate(&self, license_id: Uuid) -> AppResult<Option<LicenseCertificate>> {
+        let store = self.store.lock().unwrap();
+        Ok(store
+            .certificates
+            .values()
+            .filter(|c| c.license_id == license_id)
+            .max_by_key(|c| c.revision)
+            .cloned())
+    }
+
+    async fn certificates(&self, license_id: Uuid) -> AppResult<Vec<LicenseCertificate>> {
+        let store = self.store.lock().unwrap();
+        let mut certificates: Vec<LicenseCertificate> = store
+            .certificates
+            .values()
+            .filter(|c| c.license_id == license_id)
+            .cloned()
+            .collect();
+        certificates.sort_by_key(|c| c.revision);
+        Ok(certificates)
+    }
+}
+
+#[async_trait]
+impl VerificationAuditRepository for InMemoryLicenseRepository {
+    async fn record_lookup(&self, lookup: &VerificationLookup) -> AppResult<()> {
+        let mut store = self.store.lock().unwrap();
+        if lookup
+            .license_id
+            .is_some_and(|license_id| !store.licenses.contains_key(&license_id))
+        {
+            return Err(constraint_violation(
+                "insert on table \"license_verification_lookups\" violates foreign key constraint",
+            )
+            .into());
+        }
+        let stored = VerificationLookup {
+            looked_up_at: micros(lookup.looked_up_at),
+            ..lookup.clone()
+        };
+        store.verification_lookups.insert(stored.id, stored);
+        Ok(())
+    }
+
+    async fn recent_lookups(
+        &self,
+        company_id: Option<Uuid>,
+        limit: i64,
+    ) -> AppResult<Vec<VerificationLookup>> {
+        let store = self.store.lock().unwrap();
+        let mut lookups: Vec<VerificationLookup> = store
+            .verification_lookups
+            .values()
+            .filter(|l| company_id.is_none() || l.company_id == company_id)
+            .cloned()
+            .collect();
+        lookups.sort_by_key(|l| std::cmp::Reverse((l.looked_up_at, l.id)));
+        lookups.truncate(limit.max(0) as usize);
+        Ok(lookups)
+    }
+}
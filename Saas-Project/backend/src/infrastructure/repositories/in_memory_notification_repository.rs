@@ -0,0 +1,239 @@
+// In-memory notification center for tests and demo mode
+// Mirrors PostgresNotificationRepository: same ordering, upserts, read
+// timestamps that are kept when marking a read notification read again, and
+// claims that lease deliveries until they are recorded.
+
+use async_trait::async_trait;
+use chrono::{DateTime, SubsecRound, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use crate::domain::notifications::{
+    ChannelPreference, DeliveryStatus, Notification, NotificationChannel, NotificationDelivery,
+    NotificationEvent, NotificationRepository, NotificationSettings,
+};
+use crate::shared::errors::AppResult;
+
+#[derive(Default)]
+struct NotificationStore {
+    settings: HashMap<Uuid, NotificationSettings>,
+    preferences: HashMap<(Uuid, NotificationEvent, NotificationChannel), bool>,
+    notifications: HashMap<Uuid, Notification>,
+    deliveries: HashMap<Uuid, NotificationDelivery>,
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryNotificationRepository {
+    store: Arc<Mutex<NotificationStore>>,
+}
+
+impl InMemoryNotificationRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Postgres keeps microseconds
+fn micros(at: DateTime<Utc>) -> DateTime<Utc> {
+    at.trunc_subsecs(6)
+}
+
+fn stored_delivery(delivery: &NotificationDelivery) -> NotificationDelivery {
+    NotificationDelivery {
+        next_attempt_at: micros(delivery.next_attempt_at),
+        created_at: micros(delivery.created_at),
+        sent_at: delivery.sent_at.map(micros),
+        ..delivery.clone()
+    }
+}
+
+#[async_trait]
+impl NotificationRepository for InMemoryNotificationRepository {
+    async fn settings(&self, user_id: Uuid) -> AppResult<NotificationSettings> {
+        let store = self.store.lock().unwrap();
+        Ok(store
+            .settings
+            .get(&user_id)
+            .cloned()
+            .unwrap_or_else(|| NotificationSettings::defaults(user_id)))
+    }
+
+    async fn save_settings(&self, settings: &NotificationSettings) -> AppResult<NotificationSettings> {
+        let stored = NotificationSettings {
+            updated_at: micros(Utc::now()),
+            ..settings.clone()
+        };
+        self.store
+            .lock()
+            .unwrap()
+            .settings
+            .insert(stored.user_id, stored.clone());
+        Ok(stored)
+    }
+
+    async fn preferences(&self, user_id: Uuid) -> AppResult<Vec<ChannelPreference>> {
+        let store = self.store.lock().unwrap();
+        let mut preferences: Vec<ChannelPreference> = store
+            .preferences
+            .iter()
+            .filter(|((user, _, _), _)| *user == user_id)
+            .map(|(&(_, event, channel), &enabled)| ChannelPreference {
+                event,
+                channel,
+                enabled,
+            })
+            .collect();
+        preferences.sort_by_key(|p| (p.event.to_string(), p.channel.to_string()));
+        Ok(preferences)
+    }
+
+    async fn set_preference(&self, user_id: Uuid, preference: &ChannelPreference) -> AppResult<()> {
+        self.store.lock().unwrap().preferences.insert(
+            (user_id, preference.event, preference.channel),
+            preference.enabled,
+        );
+        Ok(())
+    }
+
+    async fn create_notification(&self, notification: &Notification) -> AppResult<Notification> {
+        let stored = Notification {
+            read_at: notification.read_at.map(micros),
+            created_at: micros(notification.created_at),
+            ..notification.clone()
+        };
+        self.store
+            .lock()
+            .unwrap()
+            .notifications
+            .insert(stored.id, stored.clone());
+        Ok(stored)
+    }
+
+    async fn notifications(
+        &self,
+        user_id: Uuid,
+        unread_only: bool,
+        limit: i64,
+    ) -> AppResult<Vec<Notification>> {
+        let store = self.store.lock().unwrap();
+        let mut notifications: Vec<Notification> = store
+            .notifications
+            .values()
+            .filter(|n| n.user_id == user_id && !(unread_only && n.is_read()))
+            .cloned()
+            .collect();
+        notifications.sort_by_key(|n| std::cmp::Reverse((n.created_at, n.id)));
+        notifications.truncate(limit.max(0) as usize);
+        Ok(notifications)
+    }
+
+    async fn unread_count(&self, user_id: Uuid) -> AppResult<i64> {
+        let store = self.store.lock().unwrap();
+        Ok(store
+            .notifications
+            .values()
+            .filter(|n| n.user_id == user_id && !n.is_read())
+            .count() as i64)
+    }
+
+    async fn set_read(
+        &self,
+        user_id: Uuid,
+        notification_id: Uuid,
+        read: bool,
+    ) -> AppResult<Option<Notification>> {
+        let mut store = self.store.lock().unwrap();
+        let Some(notification) = store
+            .notifications
+            .get_mut(&notification_id)
+            .filter(|n| n.user_id == user_id)
+        else {
+            return Ok(None);
+        };
+        notification.read_at = if read {
+            Some(notification.read_at.unwrap_or_else(|| micros(Utc::now())))
+        } else {
+            None
+        };
+        Ok(Some(notification.clone()))
+    }
+
+    async fn mark_all_read(&self, user_id: Uuid) -> AppResult<u64> {
+        let now = micros(Utc::now());
+        let mut store = self.store.lock().unwrap();
+        let mut marked = 0;
+        for notification in store.notifications.values_mut() {
+            if notification.user_id == user_id && !notification.is_read() {
+                notification.read_at = Some(now);
+                marked += 1;
+            }
+        }
+        Ok(marked)
+    }
+
+    async fn enqueue_delivery(&self, delivery: &NotificationDelivery) -> AppResult<()> {
+        self.store
+            .lock()
+            .unwrap()
+            .deliveries
+            .insert(delivery.id, stored_delivery(delivery));
+        Ok(())
+    }
+
+    async fn claim_due_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+        limit: i64,
+    ) -> AppResult<Vec<NotificationDelivery>> {
+        let mut store = self.store.lock().unwrap();
+        let mut due: Vec<&mut NotificationDelivery> = store
+            .deliveries
+            .values_mut()
+            .filter(|d| d.status == DeliveryStatus::Pending && d.next_attempt_at <= now)
+            .collect();
+        due.sort_by_key(|d| (d.next_attempt_at, d.created_at));
+        due.truncate(limit.max(0) as usize);
+
+        let mut claimed: Vec<NotificationDelivery> = due
+            .into_iter()
+            .map(|delivery| {
+                delivery.next_attempt_at = micros(lease_until);
+                delivery.clone()
+            })
+            .collect();
+        claimed.sort_by_key(|d| (d.created_at, d.id));
+        Ok(claimed)
+    }
+
+    async fn record_attempt(&self, delivery: &NotificationDelivery) -> AppResult<()> {
+        let mut store = self.store.lock().unwrap();
+        if let Some(stored) = store.deliveries.get_mut(&delivery.id) {
+            let updated = stored_delivery(delivery);
+            stored.status = updated.status;
+            stored.attempts = updated.attempts;
+            stored.next_attempt_at = updated.next_attempt_at;
+            stored.last_error = updated.last_error;
+            stored.sent_at = updated.sent_at;
+        }
+        Ok(())
+    }
+
+    async fn deliveries(
+        &self,
+        status: Option<DeliveryStatus>,
+        limit: i64,
+    ) -> AppResult<Vec<NotificationDelivery>> {
+        let store = self.store.lock().unwrap();
+        let mut deliveries: Vec<NotificationDelivery> = store
+            .deliveries
+            .values()
+            .filter(|d| status.is_none_or(|s| d.status == s))
+            .cloned()
+            .collect();
+        deliveries.sort_by_key(|d| std::cmp::Reverse((d.created_at, d.id)));
+        deliveries.truncate(limit.max(0) as usize);
+        Ok(deliveries)
+    }
+}
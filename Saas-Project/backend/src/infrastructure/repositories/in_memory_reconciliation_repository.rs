@@ -0,0 +1,363 @@
+// In-memory bank reconciliation repository for testing and demo mode
+// Books adjustments into the shared finance store and enforces the same
+// constraints as PostgresReconciliationRepository: one open session per
+// account and one statement line per transaction.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::domain::finance::{Transaction, TransactionStatus};
+use crate::domain::reconciliation::{
+    ReconciliationRepository, ReconciliationSession, ReconciliationStatus, StatementLine,
+};
+use crate::shared::errors::AppError;
+
+use super::in_memory_finance_repository::{constraint_violation, FinanceStore, InMemoryFinanceStore};
+
+#[derive(Clone, Default)]
+pub struct InMemoryReconciliationRepository {
+    store: InMemoryFinanceStore,
+}
+
+impl InMemoryReconciliationRepository {
+    pub fn new(store: InMemoryFinanceStore) -> Self {
+        Self { store }
+    }
+}
+
+fn no_longer_open() -> AppError {
+    AppError::Conflict("Reconciliation is no longer open".to_string())
+}
+
+fn is_open(store: &FinanceStore, id: Uuid) -> bool {
+    store
+        .sessions
+        .get(&id)
+        .is_some_and(|s| s.status == ReconciliationStatus::Open)
+}
+
+/// Applies the match columns of `lines` to a copy of the stored lines, so a
+/// failure part way leaves the store untouched
+fn apply_matches(
+    stored: &HashMap<Uuid, StatementLine>,
+    lines: &[StatementLine],
+) -> Result<HashMap<Uuid, StatementLine>, AppError> {
+    let mut updated = stored.clone();
+    for line in lines {
+        if let Some(transaction_id) = line.transaction_id {
+            let taken = updated
+                .values()
+                .any(|l| l.id != line.id && l.transaction_id == Some(transaction_id));
+            if taken {
+                return Err(AppError::Conflict(
+                    "Transaction is already matched to another statement line".to_string(),
+                ));
+            }
+        }
+
+        let target = updated
+            .get_mut(&line.id)
+            .filter(|l| l.session_id == line.session_id)
+            .ok_or_else(|| AppError::NotFound("Statement line not found".to_string()))?;
+        target.transaction_id = line.transaction_id;
+        target.match_type = line.match_type;
+        target.matched_at = line.matched_at;
+    }
+    Ok(updated)
+}
+
+#[async_trait]
+impl ReconciliationRepository for InMemoryReconciliationRepository {
+    async fn create_session(&self, session: &ReconciliationSession) -> Result<(), AppError> {
+        let mut store = self.store.lock();
+        let open = store
+            .sessions
+            .values()
+            .any(|s| s.account_id == session.account_id && s.status == ReconciliationStatus::Open);
+        if open && session.status == ReconciliationStatus::Open {
+            return Err(AppError::Conflict(
+                "The account already has an open reconciliation".to_string(),
+            ));
+        }
+        if store.sessions.contains_key(&session.id) {
+            return Err(constraint_violation(
+                "duplicate key value violates unique constraint \"reconciliation_sessions_pkey\"",
+            ));
+        }
+
+        store.sessions.insert(session.id, session.clone());
+        Ok(())
+    }
+
+    async fn find_session(
+        &self,
+        company_id: Uuid,
+        id: Uuid,
+    ) -> Result<Option<ReconciliationSession>, AppError> {
+        let store = self.store.lock();
+        Ok(store
+            .sessions
+            .get(&id)
+            .filter(|s| s.company_id == company_id)
+            .cloned())
+    }
+
+    async fn update_session(&self, session: &ReconciliationSession) -> Result<(), AppError> {
+        let mut store = self.store.lock();
+        let stored = store
+            .sessions
+            .get_mut(&session.id)
+            .filter(|s| s.status == ReconciliationStatus::Open)
+            .ok_or_else(no_longer_open)?;
+
+        stored.closing_balance = session.closing_balance;
+        stored.updated_at = session.updated_at;
+        Ok(())
+    }
+
+    async fn delete_session(&self, id: Uuid) -> Result<(), AppError> {
+        let mut store = self.store.lock();
+        if !is_open(&store, id) {
+            return Err(no_longer_open());
+        }
+
+        store.sessions.remove(&id);
+        store.lines.retain(|_, l| l.session_id != id);
+        Ok(())
+    }
+
+    async fn open_session(&self, account_id: Uuid) -> Result<Option<ReconciliationSession>, AppError> {
+        let store = self.store.lock();
+        Ok(store
+            .sessions
+            .values()
+            .find(|s| s.account_id == account_id && s.status == ReconciliationStatus::Open)
+            .cloned())
+    }
+
+    async fn last_completed_session(
+        &self,
+        account_id: Uuid,
+    ) -> Result<Option<ReconciliationSession>, AppError> {
+        let store = self.store.lock();
+        Ok(store
+            .sessions
+            .values()
+            .filter(|s| s.account_id == account_id && s.status == ReconciliationStatus::Completed)
+            .max_by_key(|s| s.statement_end_date)
+            .cloned())
+    }
+
+    async fn add_lines(&self, lines: &[StatementLine]) -> Result<(), AppError> {
+        let mut store = self.store.lock();
+        for line in lines {
+            if store.lines.contains_key(&line.id) {
+                return Err(constraint_violation(
+                    "duplicate key value violates unique constraint \"reconciliation_lines_pkey\"",
+                ));
+            }
+            if !store.sessions.contains_key(&line.session_id) {
+                return Err(constraint_violation(
+                    "insert or update on table \"reconciliation_lines\" violates foreign key constraint",
+                ));
+            }
+        }
+
+        for line in lines {
+            store.lines.insert(line.id, line.clone());
+        }
+        Ok(())
+    }
+
+    async fn list_lines(&self, session_id: Uuid) -> Result<Vec<StatementLine>, AppError> {
+        let store = self.store.lock();
+        let mut lines: Vec<StatementLine> = store
+            .lines
+            .values()
+            .filter(|l| l.session_id == session_id)
+            .cloned()
+            .collect();
+        lines.sort_by_key(|l| (l.line_date, l.id));
+        Ok(lines)
+    }
+
+    async fn delete_line(&self, session_id: Uuid, line_id: Uuid) -> Result<(), AppError> {
+        let mut store = self.store.lock();
+        match store.lines.get(&line_id) {
+            Some(line) if line.session_id == session_id => {
+                store.lines.remove(&line_id);
+                Ok(())
+            }
+            _ => Err(AppError::NotFound("Statement line not found".to_string())),
+        }
+    }
+
+    async fn save_matches(&self, lines: &[StatementLine]) -> Result<(), AppError> {
+        let mut store = self.store.lock();
+        store.lines = apply_matches(&store.lines, lines)?;
+        Ok(())
+    }
+
+    async fn candidate_transactions(
+        &self,
+        session: &ReconciliationSession,
+    ) -> Result<Vec<Transaction>, AppError> {
+        let store = self.store.lock();
+        let cutoff = session.cutoff();
+        let mut transactions: Vec<Transaction> = store
+            .transactions
+            .values()
+            .filter(|t| {
+                t.account_id == session.account_id
+                    && t.status == TransactionStatus::Completed
+                    && t.transaction_date < cutoff
+                    && (t.reconciled_at.is_none() || t.reconciliation_id == Some(session.id))
+            })
+            .cloned()
+            .collect();
+        transactions.sort_by_key(|t| (t.transaction_date, t.created_at));
+        Ok(transactions)
+    }
+
+    async fn record_adjustment(
+        &self,
+        transaction: &Transaction,
+        line: &StatementLine,
+    ) -> Result<(), AppError> {
+        let mut store = self.store.lock();
+        // The line must accept the match before anything is booked
+        let lines = apply_matches(&store.lines, std::slice::from_ref(line))?;
+
+        store.insert_transaction(transaction, None)?;
+        store.adjust_account_balance(transaction.account_id, transaction.signed_amount());
+        store.lines = lines;
+        Ok(())
+    }
+
+    async fn complete_session(
+        &self,
+        session: &ReconciliationSession,
+        transaction_ids: &[Uuid],
+    ) -> Result<(), AppError> {
+        let mut store = self.store.lock();
+        if !is_open(&store, session.id) {
+            return Err(no_longer_open());
+        }
+
+        let lockable = transaction_ids.iter().all(|id| {
+            store
+                .transactions
+                .get(id)
+                .is_some_and(|t| t.reconciled_at.is_none())
+        });
+        if !lockable {
+            return Err(AppError::Conflict(
+                "Some matched transactions were reconciled elsewhere, reload the reconciliation"
+                    .to_string(),
+            ));
+        }
+
+        for id in transaction_ids {
+            if let Some(transaction) = store.transactions.get_mut(id) {
+                transaction.reconciliation_id = Some(session.id);
+                transaction.reconciled_at = session.completed_at;
+                transaction.version += 1;
+            }
+        }
+        if let Some(stored) = store.sessions.get_mut(&session.id) {
+            stored.status = session.status;
+            stored.completed_by = session.completed_by;
+            stored.completed_at = session.completed_at;
+            stored.updated_at = session.updated_at;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::finance::{FinancialAccount, FinancialAccountRepository, TransactionRepository};
+    use crate::domain::reconciliation::{
+        AdjustmentRequest, ReconciliationService, StartReconciliation, StatementLineInput,
+    };
+    use crate::domain::value_objects::{Currency, Money};
+    use crate::infrastructure::repositories::in_memory_finance_repository::{
+        InMemoryFinancialAccountRepository, InMemoryTransactionRepository,
+    };
+    use chrono::NaiveDate;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_adjustment_and_completion_lock_the_books() -> Result<(), AppError> {
+        let store = InMemoryFinanceStore::new();
+        let accounts = InMemoryFinancialAccountRepository::new(store.clone());
+        let transactions = InMemoryTransactionRepository::new(store.clone());
+        let service = ReconciliationService::new(
+            Arc::new(InMemoryReconciliationRepository::new(store.clone())),
+            Arc::new(accounts.clone()),
+        );
+
+        let company_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4();
+        let account = accounts
+            .create(&FinancialAccount::new(
+                company_id,
+                "BCA Operasional".to_string(),
+                "Bank".to_string(),
+                Currency::IDR,
+                Money::idr(100_000),
+            ))
+            .await?;
+
+        let report = service
+            .start(
+                company_id,
+                user_id,
+                StartReconciliation {
+                    account_id: account.id,
+                    statement_start_date: None,
+                    statement_end_date: NaiveDate::from_ymd_opt(2024, 6, 30).unwrap(),
+                    opening_balance: Some(100_000),
+                    closing_balance: Some(93_500),
+                },
+            )
+            .await?;
+        let id = report.session.id;
+
+        let report = service
+            .add_lines(
+                company_id,
+                id,
+                vec![StatementLineInput {
+                    line_date: NaiveDate::from_ymd_opt(2024, 6, 30).unwrap(),
+                    amount: -6_500,
+                    description: "Biaya administrasi".to_string(),
+                    reference: None,
+                }],
+            )
+            .await?;
+        let line_id = report.unmatched_lines[0].id;
+
+        let report = service
+            .create_adjustment(company_id, user_id, id, line_id, AdjustmentRequest::default())
+            .await?;
+        assert_eq!(report.cleared_balance, 93_500);
+        assert!(report.can_complete);
+        // The fee was booked against the account
+        let stored = accounts.find_by_id(account.id).await?.unwrap();
+        assert_eq!(stored.balance.amount, 93_500);
+
+        let report = service.complete(company_id, user_id, id).await?;
+        assert_eq!(report.session.status, ReconciliationStatus::Completed);
+
+        // The booked fee is now locked against edits
+        let fee = &report.matched[0].transaction;
+        let mut edited = transactions.find_by_id(&fee.id).await?.unwrap();
+        edited.description = "Biaya admin Juni".to_string();
+        assert!(matches!(transactions.update(&edited).await, Err(AppError::Conflict(_))));
+        assert!(matches!(service.cancel(company_id, id).await, Err(AppError::Conflict(_))));
+        Ok(())
+    }
+}
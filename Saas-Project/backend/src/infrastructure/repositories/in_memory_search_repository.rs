@@ -0,0 +1,331 @@
+// In-memory implementation of unified search for testing and demo mode
+// Every query term has to appear in a hit, like websearch_to_tsquery's AND.
+// Ranks come from where the terms match rather than from ts_rank_cd, but the
+// ordering, owner scope and keyset cursor follow PostgresSearchRepository.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::domain::companies::Company;
+use crate::domain::licenses::License;
+use crate::domain::search::{
+    SearchCursor, SearchHit, SearchHitType, SearchPage, SearchQuery, SearchRepository,
+};
+use crate::shared::errors::AppResult;
+
+use super::in_memory_company_repository::InMemoryCompanyRepository;
+use super::in_memory_license_repository::InMemoryLicenseRepository;
+
+#[derive(Clone, Default)]
+pub struct InMemorySearchRepository {
+    companies: InMemoryCompanyRepository,
+    licenses: InMemoryLicenseRepository,
+}
+
+impl InMemorySearchRepository {
+    pub fn new(companies: InMemoryCompanyRepository, licenses: InMemoryLicenseRepository) -> Self {
+        Self { companies, licenses }
+    }
+}
+
+fn terms(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|term| term.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+        .filter(|term| !term.is_empty())
+        .collect()
+}
+
+/// Terms found in the title weigh twice as much as those only in the body;
+/// `None` when a term is missing from both
+fn text_rank(terms: &[String], title: &str, body: &str) -> Option<f32> {
+    let title = title.to_lowercase();
+    let body = body.to_lowercase();
+    let mut score = 0;
+    for term in terms {
+        if title.contains(term.as_str()) {
+            score += 2;
+        } else if body.contains(term.as_str()) {
+            score += 1;
+        } else {
+            return None;
+        }
+    }
+    Some(score as f32 / (2 * terms.len()) as f32)
+}
+
+/// Registration numbers also match on a substring of the whole query
+fn number_rank(number: Option<&str>, text: &str) -> Option<f32> {
+    number
+        .filter(|n| n.to_lowercase().contains(&text.to_lowercase()))
+        .map(|n| 0.5 + text.len() as f32 / n.len() as f32 / 2.0)
+}
+
+fn rank(terms: &[String], text: &str, title: &str, body: &str, number: Option<&str>) -> Option<f32> {
+    match (text_rank(terms, title, body), number_rank(number, text)) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Wraps every occurrence of the terms in `<mark>` tags
+fn highlight(body: &str, terms: &[String]) -> String {
+    let lower = body.to_lowercase();
+    // Lowercasing can change byte lengths outside ASCII, so only mark when the
+    // offsets still line up
+    if lower.len() != body.len() {
+        return body.to_string();
+    }
+
+    let mut marked = vec![false; body.len()];
+    for term in terms {
+        for (start, _) in lower.match_indices(term.as_str()) {
+            marked[start..start + term.len()].iter_mut().for_each(|m| *m = true);
+        }
+    }
+
+    let mut result = String::with_capacity(body.len());
+    let mut open = false;
+    for (index, c) in body.char_indices() {
+        if marked[index] != open {
+            result.push_str(if open { "</mark>" } else { "<mark>" });
+            open = !open;
+        }
+        result.push(c);
+    }
+    if open {
+        result.push_str("</mark>");
+    }
+    result
+}
+
+fn join(parts: &[Option<&str>], separator: &str) -> String {
+    parts
+        .iter()
+        .flatten()
+        .filter(|p| !p.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn company_hits(companies: &[Company], query: &SearchQuery, terms: &[String]) -> Vec<SearchHit> {
+    let owner = query.scope.owner_id();
+    companies
+        .iter()
+        .filter(|c| owner.is_none_or(|owner| c.owner_id == owner))
+        .filter_map(|c| {
+            let body = join(
+                &[Some(&c.company_name), Some(&c.industry_sector), c.description.as_deref()],
+                " ",
+            );
+            let rank = rank(terms, &query.text, &c.company_name, &body, c.nib.as_deref())?;
+            Some(SearchHit {
+                hit_type: SearchHitType::Company,
+                id: c.id,
+                title: c.company_name.clone(),
+                subtitle: Some(join(&[Some(&c.industry_sector), Some(&c.address_city)], " · ")),
+                highlight: highlight(&body, terms),
+                rank,
+                company_id: Some(c.id),
+                license_id: None,
+            })
+        })
+        .collect()
+}
+
+fn license_hits(
+    licenses: &HashMap<Uuid, License>,
+    query: &SearchQuery,
+    terms: &[String],
+    visible: &impl Fn(&License) -> bool,
+) -> Vec<SearchHit> {
+    licenses
+        .values()
+        .filter(|l| visible(l))
+        .filter_map(|l| {
+            let body = join(&[Some(&l.title), l.description.as_deref()], " ");
+            let rank = rank(terms, &query.text, &l.title, &body, l.license_number.as_deref())?;
+            let license_type = l.license_type.to_string();
+            let status = l.application_status.to_string();
+            Some(SearchHit {
+                hit_type: SearchHitType::License,
+                id: l.id,
+                title: l.title.clone(),
+                subtitle: Some(join(
+                    &[l.license_number.as_deref(), Some(&license_type), Some(&status)],
+                    " · ",
+                )),
+                highlight: highlight(&body, terms),
+                rank,
+                company_id: Some(l.company_id),
+                license_id: Some(l.id),
+            })
+        })
+        .collect()
+}
+
+#[async_trait]
+impl SearchRepository for InMemorySearchRepository {
+    async fn search(&self, query: &SearchQuery) -> AppResult<SearchPage> {
+        let terms = terms(&query.text);
+        let companies = self.companies.all();
+        let (licenses, documents) = self.licenses.snapshot();
+        let licenses: HashMap<Uuid, License> = licenses.into_iter().map(|l| (l.id, l)).collect();
+
+        let owners: HashMap<Uuid, Uuid> = companies.iter().map(|c| (c.id, c.owner_id)).collect();
+        let owner = query.scope.owner_id();
+        let visible = |l: &License| {
+            owner.is_none_or(|owner| l.user_id == owner || owners.get(&l.company_id) == Some(&owner))
+        };
+
+        let mut hits = Vec::new();
+        if query.types.contains(&SearchHitType::Company) {
+            hits.extend(company_hits(&companies, query, &terms));
+        }
+        if query.types.contains(&SearchHitType::License) {
+            hits.extend(license_hits(&licenses, query, &terms, &visible));
+        }
+        if query.types.contains(&SearchHitType::Document) {
+            hits.extend(documents.iter().filter_map(|d| {
+                let license = licenses.get(&d.license_id).filter(|l| visible(l))?;
+                // File names read as words, like the regexp_replace in SQL
+                let file_name = d.original_file_name.replace(['.', '_', '-'], " ");
+                // Reviewer notes are only searched by admins
+                let notes = d.notes.as_deref().filter(|_| owner.is_none());
+                let body = join(&[Some(&file_name), notes], " ");
+                let rank = text_rank(&terms, &file_name, &body)?;
+                Some(SearchHit {
+                    hit_type: SearchHitType::Document,
+                    id: d.id,
+                    title: d.original_file_name.clone(),
+                    subtitle: Some(format!("{} · {}", d.document_type, license.title)),
+                    highlight: highlight(&body, &terms),
+                    rank,
+                    company_id: Some(license.company_id),
+                    license_id: Some(license.id),
+                })
+            }));
+        }
+
+        hits.retain(|hit| {
+            query
+                .cursor
+                .as_ref()
+                .is_none_or(|cursor| (hit.rank, hit.id) < (cursor.rank, cursor.id))
+        });
+        hits.sort_by(|a, b| b.rank.total_cmp(&a.rank).then(b.id.cmp(&a.id)));
+
+        let next_cursor = if hits.len() > query.limit as usize {
+            hits.truncate(query.limit as usize);
+            hits.last().map(|last| {
+                SearchCursor {
+                    rank: last.rank,
+                    id: last.id,
+                }
+                .encode()
+            })
+        } else {
+            None
+        };
+
+        Ok(SearchPage { hits, next_cursor })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::companies::{BusinessType, CompanyAddress};
+    use crate::domain::licenses::{DocumentType, LicenseDocument, LicenseType};
+    use crate::domain::repositories::CompanyRepository;
+    use crate::domain::search::SearchScope;
+    use crate::infrastructure::repositories::LicenseRepository;
+
+    #[test]
+    fn test_highlight_marks_terms() {
+        let terms = terms("izin USAHA");
+        assert_eq!(
+            highlight("Izin usaha mikro", &terms),
+            "<mark>Izin</mark> <mark>usaha</mark> mikro"
+        );
+        assert_eq!(highlight("Dokumen", &terms), "Dokumen");
+    }
+
+    #[tokio::test]
+    async fn test_search_is_scoped_ranked_and_paged() -> AppResult<()> {
+        let companies = InMemoryCompanyRepository::new();
+        let licenses = InMemoryLicenseRepository::new();
+        let search = InMemorySearchRepository::new(companies.clone(), licenses.clone());
+
+        let owner = Uuid::new_v4();
+        let company = Company::new(
+            owner,
+            "Kopi Gayo Makmur".to_string(),
+            BusinessType::CV,
+            "Kuliner".to_string(),
+            CompanyAddress::new(
+                "Jl. Sudirman 5".to_string(),
+                "Takengon".to_string(),
+                "Aceh".to_string(),
+                "24511".to_string(),
+            ),
+        );
+        companies.save(&company).await?;
+
+        let license = License::new(
+            LicenseType::Siup,
+            company.id,
+            owner,
+            "SIUP Kopi Gayo".to_string(),
+            Some("Perdagangan kopi".to_string()),
+        );
+        licenses.create_license(&license).await?;
+        let mut document = LicenseDocument::new(
+            license.id,
+            DocumentType::Ktp,
+            "ktp.pdf".to_string(),
+            "ktp_pemilik_kopi.pdf".to_string(),
+            "/uploads/ktp.pdf".to_string(),
+            1024,
+            "application/pdf".to_string(),
+        );
+        document.notes = Some("Foto buram, minta unggah ulang".to_string());
+        licenses.create_document(&document).await?;
+
+        let page = search
+            .search(&SearchQuery::new("kopi gayo", SearchScope::All).unwrap())
+            .await?;
+        assert_eq!(page.hits.len(), 2);
+        assert!(page.hits.iter().all(|h| h.rank == 1.0));
+
+        let page = search
+            .search(&SearchQuery::new("kopi", SearchScope::Owner(Uuid::new_v4())).unwrap())
+            .await?;
+        assert!(page.hits.is_empty());
+
+        let query = SearchQuery::new("kopi", SearchScope::Owner(owner)).unwrap().with_limit(2);
+        let first = search.search(&query).await?;
+        assert_eq!(first.hits.len(), 2);
+        let cursor = SearchCursor::decode(&first.next_cursor.unwrap()).unwrap();
+        let second = search.search(&query.with_cursor(cursor)).await?;
+        assert_eq!(second.hits.len(), 1);
+        assert!(second.next_cursor.is_none());
+
+        // Reviewer notes are internal
+        let page = search
+            .search(&SearchQuery::new("buram", SearchScope::Owner(owner)).unwrap())
+            .await?;
+        assert!(page.hits.is_empty());
+        let page = search
+            .search(&SearchQuery::new("buram", SearchScope::All).unwrap())
+            .await?;
+        assert_eq!(page.hits.len(), 1);
+        assert!(page.hits[0].highlight.contains("<mark>buram</mark>"));
+
+        let mut types: Vec<String> = first.hits.iter().chain(&second.hits).map(|h| h.hit_type.to_string()).collect();
+        types.sort();
+        assert_eq!(types, ["company", "document", "license"]);
+        Ok(())
+    }
+}
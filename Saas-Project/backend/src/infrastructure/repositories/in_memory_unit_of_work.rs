@@ -0,0 +1,62 @@
+// Units of work for the in-memory repositories
+// A work runs against a private copy of the store and committing puts the copy
+// back, so a work dropped half-way leaves the store as it was. Works on the
+// same store take turns like transactions holding row locks; a write made
+// outside any work while one is open is overwritten by its commit, which the
+// tests and the demo never do.
+
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OwnedMutexGuard;
+
+use crate::domain::unit_of_work::WorkHandle;
+use crate::shared::errors::AppResult;
+
+/// Serializes the works begun on one store
+pub(crate) type WorkTurns = Arc<tokio::sync::Mutex<()>>;
+
+/// The private copy of a store that one work changes
+pub(crate) struct StagedWork<S> {
+    shared: Arc<Mutex<S>>,
+    staged: Arc<Mutex<S>>,
+    _turn: OwnedMutexGuard<()>,
+}
+
+impl<S: Clone> StagedWork<S> {
+    pub(crate) async fn begin(shared: &Arc<Mutex<S>>, turns: &WorkTurns) -> Self {
+        let turn = turns.clone().lock_owned().await;
+        let staged = shared.lock().unwrap().clone();
+        Self {
+            shared: shared.clone(),
+            staged: Arc::new(Mutex::new(staged)),
+            _turn: turn,
+        }
+    }
+
+    /// Store the work's repositories read and write
+    pub(crate) fn staged(&self) -> Arc<Mutex<S>> {
+        self.staged.clone()
+    }
+}
+
+#[async_trait]
+impl<S: Clone + Send + 'static> WorkHandle for StagedWork<S> {
+    async fn commit(self: Box<Self>) -> AppResult<()> {
+        let staged = self.staged.lock().unwrap().clone();
+        *self.shared.lock().unwrap() = staged;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::infrastructure::database::unit_of_work::tests::run_work_scenario;
+    use crate::infrastructure::repositories::testing::{in_memory, Fixture};
+
+    #[tokio::test]
+    async fn in_memory_unit_of_work_conforms() {
+        let fx = Fixture::new();
+        let repo = in_memory(&fx);
+        run_work_scenario(&repo, &repo, &fx).await;
+    }
+}
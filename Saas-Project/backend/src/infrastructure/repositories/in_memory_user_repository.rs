@@ -6,11 +6,13 @@ use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
 use crate::domain::entities::User;
+use crate::domain::filters::{UserFilter, UserSortField};
 #[cfg(test)]
 use crate::domain::entities::{UserRole, UserStatus};
 use crate::domain::repositories::UserRepository;
 use crate::domain::value_objects::{Email, UserId};
 use crate::shared::errors::{AppError, AppResult};
+use crate::shared::query::{paginate, ListQuery, Page};
 
 /// A simple in-memory implementation of the UserRepository trait for testing purposes
 pub struct InMemoryUserRepository {
@@ -37,6 +39,10 @@ impl InMemoryUserRepository {
             users: Arc::new(Mutex::new(map)),
         }
     }
+
+    pub(crate) fn all(&self) -> Vec<User> {
+        self.users.lock().unwrap().values().cloned().collect()
+    }
 }
 
 #[async_trait]
@@ -139,6 +145,17 @@ impl UserRepository for InMemoryUserRepository {
             Ok(users_after_offset.to_vec())
         }
     }
+
+    async fn list(&self, query: &ListQuery<UserFilter, UserSortField>) -> AppResult<Page<User>> {
+        let users = self.users.lock().unwrap();
+        let matching: Vec<User> = users
+            .values()
+            .filter(|u| query.filter.matches(u))
+            .cloned()
+            .collect();
+
+        Ok(paginate(matching, query))
+    }
 }
 
 #[cfg(test)]
@@ -164,6 +181,10 @@ mod tests {
             email_verified_at: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            password_reset_required: false,
+            password_reset_token_hash: None,
+            password_reset_expires_at: None,
+            sessions_revoked_at: None,
         };
         
         // Test save
@@ -212,6 +233,10 @@ mod tests {
                 email_verified_at: None,
                 created_at: Utc::now(),
                 updated_at: Utc::now(),
+                password_reset_required: false,
+                password_reset_token_hash: None,
+                password_reset_expires_at: None,
+                sessions_revoked_at: None,
             };
             users.push(user);
         }
@@ -238,6 +263,34 @@ mod tests {
         // Test search with limit
         let search_limited = repo.search("test", Some(2), None).await?;
         assert_eq!(search_limited.len(), 2);
+
+        // Test keyset listing by email, following the cursor to the end
+        let mut query = ListQuery::new(UserFilter::default())
+            .with_sort(crate::shared::query::SortSpec::parse("email").unwrap())
+            .with_limit(4);
+        let first = repo.list(&query).await?;
+        assert_eq!(first.total, Some(10));
+        assert_eq!(first.data[0].email.as_str(), "test0@example.com");
+
+        let params = crate::shared::query::ListParams {
+            limit: Some(4),
+            sort: Some("email".to_string()),
+            cursor: first.next_cursor.clone(),
+            include_total: Some(false),
+        };
+        query = ListQuery::from_params(UserFilter::default(), &params)?;
+        let second = repo.list(&query).await?;
+        assert_eq!(second.total, None);
+        assert_eq!(second.data[0].email.as_str(), "test4@example.com");
+
+        let filtered = repo
+            .list(&ListQuery::new(UserFilter {
+                search: Some("USER 7".to_string()),
+                ..Default::default()
+            }))
+            .await?;
+        assert_eq!(filtered.data.len(), 1);
+        assert!(filtered.next_cursor.is_none());
         
         Ok(())
     }
@@ -258,6 +311,10 @@ mod tests {
             email_verified_at: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            password_reset_required: false,
+            password_reset_token_hash: None,
+            password_reset_expires_at: None,
+            sessions_revoked_at: None,
         };
         
         repo.save(&user1).await?;
@@ -274,6 +331,10 @@ mod tests {
             email_verified_at: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            password_reset_required: false,
+            password_reset_token_hash: None,
+            password_reset_expires_at: None,
+            sessions_revoked_at: None,
         };
         
         // This should fail
@@ -0,0 +1,757 @@
+// PostgreSQL implementation of customer invoicing
+// Invoices are updated at the version they were read at. Sending a draft takes
+// the next number from `sales_invoice_sequences` in the same transaction; the
+// upsert locks the company's counter row for the year, so concurrent sends are
+// numbered one after the other and a rolled back send gives its number back.
+// A payment books its financial transaction, moves the account balance and
+// saves every invoice it settles in one transaction, so a concurrent edit of
+// any of them fails the whole payment with a conflict.
+
+use async_trait::async_trait;
+use chrono::{Datelike, NaiveDate};
+use sqlx::postgres::PgRow;
+use sqlx::{PgConnection, PgPool, Row};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::domain::finance::Transaction;
+use crate::domain::invoicing::{
+    invoice_number, Contact, InvoicePayment, InvoicingRepository, PaymentAllocation, SalesInvoice,
+    SalesInvoiceLine, SalesInvoiceQuery, SalesInvoiceStatus,
+};
+use crate::domain::value_objects::Money;
+use crate::infrastructure::database::versioning::stale_write;
+use crate::shared::errors::{AppError, AppResult};
+
+use super::finance_repository::{adjust_account_balance, insert_transaction};
+
+const CONTACT_COLUMNS: &str =
+    "id, company_id, name, email, phone, npwp, address, notes, version, created_at, updated_at";
+
+const INVOICE_COLUMNS: &str = "id, company_id, contact_id, number, status, issue_date, due_date, \
+    subtotal, discount, ppn_rate_bps, ppn, total, amount_paid, notes, sent_at, emailed_at, \
+    voided_at, created_by, version, created_at, updated_at";
+
+const PAYMENT_COLUMNS: &str =
+    "id, company_id, contact_id, account_id, transaction_id, amount, paid_on, reference, created_by, created_at";
+
+fn parse<T: std::str::FromStr<Err = String>>(row: &PgRow, column: &str) -> Result<T, AppError> {
+    let value: String = row.try_get(column)?;
+    value.parse().map_err(AppError::InternalError)
+}
+
+fn row_to_contact(row: &PgRow) -> Result<Contact, AppError> {
+    Ok(Contact {
+        id: row.try_get("id")?,
+        company_id: row.try_get("company_id")?,
+        name: row.try_get("name")?,
+        email: row.try_get("email")?,
+        phone: row.try_get("phone")?,
+        npwp: row.try_get("npwp")?,
+        address: row.try_get("address")?,
+        notes: row.try_get("notes")?,
+        version: row.try_get("version")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+/// The invoice without its lines
+fn row_to_invoice(row: &PgRow) -> Result<SalesInvoice, AppError> {
+    let status: SalesInvoiceStatus = parse(row, "status")?;
+    let total: i64 = row.try_get("total")?;
+    let amount_paid: i64 = row.try_get("amount_paid")?;
+    Ok(SalesInvoice {
+        id: row.try_get("id")?,
+        company_id: row.try_get("company_id")?,
+        contact_id: row.try_get("contact_id")?,
+        number: row.try_get("number")?,
+        status,
+        issue_date: row.try_get("issue_date")?,
+        due_date: row.try_get("due_date")?,
+        lines: Vec::new(),
+        subtotal: Money::idr(row.try_get("subtotal")?),
+        discount: Money::idr(row.try_get("discount")?),
+        ppn_rate_bps: row.try_get("ppn_rate_bps")?,
+        ppn: Money::idr(row.try_get("ppn")?),
+        total: Money::idr(total),
+        amount_paid: Money::idr(amount_paid),
+        balance_due: Money::idr(if status == SalesInvoiceStatus::Void { 0 } else { total - amount_paid }),
+        notes: row.try_get("notes")?,
+        sent_at: row.try_get("sent_at")?,
+        emailed_at: row.try_get("emailed_at")?,
+        voided_at: row.try_get("voided_at")?,
+        created_by: row.try_get("created_by")?,
+        version: row.try_get("version")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+fn row_to_line(row: &PgRow) -> Result<SalesInvoiceLine, AppError> {
+    Ok(SalesInvoiceLine {
+        description: row.try_get("description")?,
+        quantity: row.try_get("quantity")?,
+        unit_price: Money::idr(row.try_get("unit_price")?),
+        discount_bps: row.try_get("discount_bps")?,
+        amount: Money::idr(row.try_get("amount")?),
+    })
+}
+
+fn row_to_payment(row: &PgRow) -> Result<InvoicePayment, AppError> {
+    Ok(InvoicePayment {
+        id: row.try_get("id")?,
+        company_id: row.try_get("company_id")?,
+        contact_id: row.try_get("contact_id")?,
+        account_id: row.try_get("account_id")?,
+        transaction_id: row.try_get("transaction_id")?,
+        amount: Money::idr(row.try_get("amount")?),
+        paid_on: row.try_get("paid_on")?,
+        reference: row.try_get("reference")?,
+        allocations: Vec::new(),
+        created_by: row.try_get("created_by")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+/// Decodes invoice rows and attaches their lines
+async fn with_lines(conn: &mut PgConnection, rows: &[PgRow]) -> AppResult<Vec<SalesInvoice>> {
+    let mut invoices = rows.iter().map(row_to_invoice).collect::<Result<Vec<_>, _>>()?;
+    let ids: Vec<Uuid> = invoices.iter().map(|i| i.id).collect();
+    let lines = sqlx::query(
+        "SELECT invoice_id, description, quantity, unit_price, discount_bps, amount \
+         FROM sales_invoice_lines WHERE invoice_id = ANY($1) ORDER BY invoice_id, position",
+    )
+    .bind(&ids)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut by_invoice: HashMap<Uuid, Vec<SalesInvoiceLine>> = HashMap::new();
+    for row in &lines {
+        let invoice_id: Uuid = row.try_get("invoice_id")?;
+        by_invoice.entry(invoice_id).or_default().push(row_to_line(row)?);
+    }
+    for invoice in &mut invoices {
+        invoice.lines = by_invoice.remove(&invoice.id).unwrap_or_default();
+    }
+    Ok(invoices)
+}
+
+async fn insert_lines(conn: &mut PgConnection, invoice: &SalesInvoice) -> AppResult<()> {
+    sqlx::query("DELETE FROM sales_invoice_lines WHERE invoice_id = $1")
+        .bind(invoice.id)
+        .execute(&mut *conn)
+        .await?;
+    for (position, line) in invoice.lines.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO sales_invoice_lines \
+                 (invoice_id, position, description, quantity, unit_price, discount_bps, amount) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(invoice.id)
+        .bind(position as i32)
+        .bind(&line.description)
+        .bind(line.quantity)
+        .bind(line.unit_price.amount)
+        .bind(line.discount_bps)
+        .bind(line.amount.amount)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Next number of the company for the year `issue_date` falls in
+async fn next_number(conn: &mut PgConnection, company_id: Uuid, issue_date: NaiveDate) -> AppResult<String> {
+    let sequence: i64 = sqlx::query_scalar(
+        "INSERT INTO sales_invoice_sequences (company_id, year, last_number) VALUES ($1, $2, 1) \
+         ON CONFLICT (company_id, year) \
+             DO UPDATE SET last_number = sales_invoice_sequences.last_number + 1 \
+         RETURNING last_number",
+    )
+    .bind(company_id)
+    .bind(issue_date.year())
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(invoice_number(issue_date.year(), sequence))
+}
+
+/// Saves an invoice at the version it was read at, numbering it if it was
+/// just sent. Drafts and invoices being sent have their lines rewritten.
+async fn save_invoice(conn: &mut PgConnection, invoice: &SalesInvoice) -> AppResult<SalesInvoice> {
+    let number = match (&invoice.number, invoice.sent_at, invoice.issue_date) {
+        (None, Some(_), Some(issue_date)) => {
+            Some(next_number(conn, invoice.company_id, issue_date).await?)
+        }
+        (number, _, _) => number.clone(),
+    };
+
+    let row = sqlx::query(&format!(
+        "UPDATE sales_invoices SET contact_id = $3, number = $4, status = $5, issue_date = $6, \
+             due_date = $7, subtotal = $8, discount = $9, ppn_rate_bps = $10, ppn = $11, \
+             total = $12, amount_paid = $13, notes = $14, sent_at = $15, emailed_at = $16, \
+             voided_at = $17, updated_at = $18 \
+         WHERE id = $1 AND version = $2 RETURNING {}",
+        INVOICE_COLUMNS
+    ))
+    .bind(invoice.id)
+    .bind(invoice.version)
+    .bind(invoice.contact_id)
+    .bind(&number)
+    .bind(invoice.status.to_string())
+    .bind(invoice.issue_date)
+    .bind(invoice.due_date)
+    .bind(invoice.subtotal.amount)
+    .bind(invoice.discount.amount)
+    .bind(invoice.ppn_rate_bps)
+    .bind(invoice.ppn.amount)
+    .bind(invoice.total.amount)
+    .bind(invoice.amount_paid.amount)
+    .bind(&invoice.notes)
+    .bind(invoice.sent_at)
+    .bind(invoice.emailed_at)
+    .bind(invoice.voided_at)
+    .bind(invoice.updated_at)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(row) = row else {
+        return Err(stale_write(&mut *conn, "sales_invoices", "Invoice", invoice.id).await);
+    };
+
+    if invoice.number.is_none() {
+        insert_lines(conn, invoice).await?;
+    }
+    let mut saved = row_to_invoice(&row)?;
+    saved.lines = invoice.lines.clone();
+    Ok(saved)
+}
+
+#[derive(Clone)]
+pub struct PostgresInvoicingRepository {
+    pool: PgPool,
+}
+
+impl PostgresInvoicingRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl InvoicingRepository for PostgresInvoicingRepository {
+    async fn create_contact(&self, contact: &Contact) -> AppResult<Contact> {
+        let row = sqlx::query(&format!(
+            "INSERT INTO contacts ({cols}) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 1, $9, $10) \
+             RETURNING {cols}",
+            cols = CONTACT_COLUMNS
+        ))
+        .bind(contact.id)
+        .bind(contact.company_id)
+        .bind(&contact.name)
+        .bind(&contact.email)
+        .bind(&contact.phone)
+        .bind(&contact.npwp)
+        .bind(&contact.address)
+        .bind(&contact.notes)
+        .bind(contact.created_at)
+        .bind(contact.updated_at)
+        .fetch_one(&self.pool)
+        .await?;
+        row_to_contact(&row)
+    }
+
+    async fn update_contact(&self, contact: &Contact) -> AppResult<Contact> {
+        let row = sqlx::query(&format!(
+            "UPDATE contacts SET name = $3, email = $4, phone = $5, npwp = $6, address = $7, \
+                 notes = $8, updated_at = $9 \
+             WHERE id = $1 AND version = $2 RETURNING {}",
+            CONTACT_COLUMNS
+        ))
+        .bind(contact.id)
+        .bind(contact.version)
+        .bind(&contact.name)
+        .bind(&contact.email)
+        .bind(&contact.phone)
+        .bind(&contact.npwp)
+        .bind(&contact.address)
+        .bind(&contact.notes)
+        .bind(contact.updated_at)
+        .fetch_optional(&self.pool)
+        .await?;
+        match row {
+            Some(row) => row_to_contact(&row),
+            None => Err(stale_write(&self.pool, "contacts", "Contact", contact.id).await),
+        }
+    }
+
+    async fn contact(&self, company_id: Uuid, id: Uuid) -> AppResult<Option<Contact>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM contacts WHERE company_id = $1 AND id = $2",
+            CONTACT_COLUMNS
+        ))
+        .bind(company_id)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.as_ref().map(row_to_contact).transpose()
+    }
+
+    async fn contacts(&self, company_id: Uuid) -> AppResult<Vec<Contact>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM contacts WHERE company_id = $1 ORDER BY name, id",
+            CONTACT_COLUMNS
+        ))
+        .bind(company_id)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(row_to_contact).collect()
+    }
+
+    async fn create_invoice(&self, invoice: &SalesInvoice) -> AppResult<SalesInvoice> {
+        let mut tx = self.pool.begin().await?;
+        let row = sqlx::query(&format!(
+            "INSERT INTO sales_invoices ({cols}) VALUES \
+                 ($1, $2, $3, NULL, $4, $5, $6, $7, $8, $9, $10, $11, 0, $12, NULL, NULL, NULL, \
+                  $13, 1, $14, $15) \
+             RETURNING {cols}",
+            cols = INVOICE_COLUMNS
+        ))
+        .bind(invoice.id)
+        .bind(invoice.company_id)
+        .bind(invoice.contact_id)
+        .bind(SalesInvoiceStatus::Draft.to_string())
+        .bind(invoice.issue_date)
+        .bind(invoice.due_date)
+        .bind(invoice.subtotal.amount)
+        .bind(invoice.discount.amount)
+        .bind(invoice.ppn_rate_bps)
+        .bind(invoice.ppn.amount)
+        .bind(invoice.total.amount)
+        .bind(&invoice.notes)
+        .bind(invoice.created_by)
+        .bind(invoice.created_at)
+        .bind(invoice.updated_at)
+        .fetch_one(&mut *tx)
+        .await?;
+        insert_lines(&mut tx, invoice).await?;
+        tx.commit().await?;
+
+        let mut created = row_to_invoice(&row)?;
+        created.lines = invoice.lines.clone();
+        Ok(created)
+    }
+
+    async fn update_invoice(&self, invoice: &SalesInvoice) -> AppResult<SalesInvoice> {
+        let mut tx = self.pool.begin().await?;
+        let saved = save_invoice(&mut tx, invoice).await?;
+        tx.commit().await?;
+        Ok(saved)
+    }
+
+    async fn invoice(&self, company_id: Uuid, id: Uuid) -> AppResult<Option<SalesInvoice>> {
+        let mut conn = self.pool.acquire().await?;
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM sales_invoices WHERE company_id = $1 AND id = $2",
+            INVOICE_COLUMNS
+        ))
+        .bind(company_id)
+        .bind(id)
+        .fetch_all(&mut *conn)
+        .await?;
+        Ok(with_lines(&mut conn, &rows).await?.pop())
+    }
+
+    async fn invoices(&self, company_id: Uuid, query: &SalesInvoiceQuery, limit: i64) -> AppResult<Vec<SalesInvoice>> {
+        let mut conn = self.pool.acquire().await?;
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM sales_invoices \
+             WHERE company_id = $1 \
+               AND ($2::TEXT IS NULL OR status = $2) \
+               AND ($3::UUID IS NULL OR contact_id = $3) \
+               AND (NOT $4 OR status IN ('sent', 'partially_paid', 'overdue')) \
+             ORDER BY created_at DESC, id DESC LIMIT $5",
+            INVOICE_COLUMNS
+        ))
+        .bind(company_id)
+        .bind(query.status.map(|status| status.to_string()))
+        .bind(query.contact_id)
+        .bind(query.open)
+        .bind(limit)
+        .fetch_all(&mut *conn)
+        .await?;
+        with_lines(&mut conn, &rows).await
+    }
+
+    async fn record_payment(
+        &self,
+        payment: &InvoicePayment,
+        transaction: &Transaction,
+        invoices: &[SalesInvoice],
+    ) -> AppResult<(InvoicePayment, Vec<SalesInvoice>)> {
+        let mut tx = self.pool.begin().await?;
+
+        let mut saved = Vec::with_capacity(invoices.len());
+        for invoice in invoices {
+            saved.push(save_invoice(&mut tx, invoice).await?);
+        }
+        insert_transaction(&mut *tx, transaction, None).await?;
+        adjust_account_balance(&mut *tx, transaction.account_id, transaction.signed_amount()).await?;
+
+        let row = sqlx::query(&format!(
+            "INSERT INTO invoice_payments ({cols}) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) \
+             RETURNING {cols}",
+            cols = PAYMENT_COLUMNS
+        ))
+        .bind(payment.id)
+        .bind(payment.company_id)
+        .bind(payment.contact_id)
+        .bind(payment.account_id)
+        .bind(payment.transaction_id)
+        .bind(payment.amount.amount)
+        .bind(payment.paid_on)
+        .bind(&payment.reference)
+        .bind(payment.created_by)
+        .bind(payment.created_at)
+        .fetch_one(&mut *tx)
+        .await?;
+        for allocation in &payment.allocations {
+            sqlx::query(
+                "INSERT INTO invoice_payment_allocations (payment_id, invoice_id, amount) \
+                 VALUES ($1, $2, $3)",
+            )
+            .bind(payment.id)
+            .bind(allocation.invoice_id)
+            .bind(allocation.amount.amount)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        let mut stored = row_to_payment(&row)?;
+        stored.allocations = payment.allocations.clone();
+        Ok((stored, saved))
+    }
+
+    async fn payments(&self, company_id: Uuid, invoice_id: Uuid) -> AppResult<Vec<InvoicePayment>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM invoice_payments p WHERE company_id = $1 AND EXISTS \
+                 (SELECT 1 FROM invoice_payment_allocations a \
+                  WHERE a.payment_id = p.id AND a.invoice_id = $2) \
+             ORDER BY created_at, id",
+            PAYMENT_COLUMNS
+        ))
+        .bind(company_id)
+        .bind(invoice_id)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut payments = rows.iter().map(row_to_payment).collect::<Result<Vec<_>, _>>()?;
+
+        let ids: Vec<Uuid> = payments.iter().map(|p| p.id).collect();
+        let allocations = sqlx::query(
+            "SELECT a.payment_id, a.invoice_id, a.amount FROM invoice_payment_allocations a \
+             JOIN sales_invoices i ON i.id = a.invoice_id \
+             WHERE a.payment_id = ANY($1) ORDER BY i.created_at, i.id",
+        )
+        .bind(&ids)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut by_payment: HashMap<Uuid, Vec<PaymentAllocation>> = HashMap::new();
+        for row in &allocations {
+            let payment_id: Uuid = row.try_get("payment_id")?;
+            by_payment.entry(payment_id).or_default().push(PaymentAllocation {
+                invoice_id: row.try_get("invoice_id")?,
+                amount: Money::idr(row.try_get("amount")?),
+            });
+        }
+        for payment in &mut payments {
+            payment.allocations = by_payment.remove(&payment.id).unwrap_or_default();
+        }
+        Ok(payments)
+    }
+
+    async fn mark_overdue(&self, today: NaiveDate) -> AppResult<u64> {
+        let result = sqlx::query(
+            "UPDATE sales_invoices SET status = 'overdue', updated_at = NOW() \
+             WHERE status IN ('sent', 'partially_paid') AND due_date < $1",
+        )
+        .bind(today)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::domain::analytics::reporting_day;
+    use crate::domain::finance::{
+        FinancialAccount, FinancialAccountRepository, TransactionId, TransactionRepository,
+        TransactionType,
+    };
+    use crate::domain::invoicing::{AllocationInput, ContactInput, InvoiceDraft, LineInput, PaymentInput};
+    use crate::domain::value_objects::{Currency, Money};
+    use crate::infrastructure::repositories::testing::{Fixture, TestDatabase};
+    use crate::infrastructure::repositories::{
+        InMemoryFinanceStore, InMemoryFinancialAccountRepository, InMemoryInvoicingRepository,
+        InMemoryTransactionRepository, PostgresFinancialAccountRepository,
+        PostgresTransactionRepository,
+    };
+    use crate::shared::errors::AppError;
+
+    fn line(description: &str, quantity: i64, unit_price: i64) -> LineInput {
+        LineInput {
+            description: description.to_string(),
+            quantity,
+            unit_price,
+            discount_bps: 0,
+        }
+    }
+
+    fn sales_draft(contact_id: Uuid, lines: Vec<LineInput>) -> InvoiceDraft {
+        InvoiceDraft {
+            contact_id,
+            lines,
+            discount: 0,
+            ppn_rate_bps: None,
+            issue_date: None,
+            due_date: None,
+            notes: None,
+        }
+    }
+
+    /// Contacts, invoice numbering, versioned updates and the payments booked
+    /// against invoices
+    async fn run_invoicing_scenario(
+        invoicing: &dyn InvoicingRepository,
+        accounts: &dyn FinancialAccountRepository,
+        transactions: &dyn TransactionRepository,
+        fx: &Fixture,
+    ) {
+        let customer = |name: &str| ContactInput {
+            name: name.to_string(),
+            email: Some("kasir@sumber.id".to_string()),
+            ..ContactInput::default()
+        };
+        let contact = invoicing
+            .create_contact(&Contact::new(fx.company_id, customer("Toko Sumber Rejeki"), fx.at(0)).unwrap())
+            .await
+            .unwrap();
+        let elsewhere = invoicing
+            .create_contact(&Contact::new(fx.other_company_id, customer("Warung Bu Ani"), fx.at(0)).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(contact.version, 1);
+        assert_eq!(invoicing.contacts(fx.company_id).await.unwrap(), vec![contact.clone()]);
+        assert_eq!(invoicing.contact(fx.other_company_id, contact.id).await.unwrap(), None);
+
+        let mut renamed = contact.clone();
+        renamed.update(customer("Toko Sumber Rejeki Jaya"), fx.at(5)).unwrap();
+        let contact = invoicing.update_contact(&renamed).await.unwrap();
+        assert_eq!(contact.version, 2);
+        assert_eq!(invoicing.contact(fx.company_id, contact.id).await.unwrap(), Some(contact.clone()));
+        let stale = invoicing.update_contact(&renamed).await;
+        assert!(matches!(stale, Err(AppError::VersionConflict { .. })), "{:?}", stale);
+
+        // Drafts carry no number; their lines keep their order
+        let draft = |lines, seconds| {
+            SalesInvoice::draft(fx.company_id, fx.owner_id, sales_draft(contact.id, lines), 1_100, fx.at(seconds)).unwrap()
+        };
+        let first = invoicing
+            .create_invoice(&draft(vec![line("Keripik 250g", 10, 2_500_000), line("Ongkir", 1, 1_500_000)], 10))
+            .await
+            .unwrap();
+        let second = invoicing.create_invoice(&draft(vec![line("Keripik 1kg", 4, 9_000_000)], 20)).await.unwrap();
+        let third = invoicing.create_invoice(&draft(vec![line("Sambal", 2, 3_000_000)], 25)).await.unwrap();
+        assert_eq!((first.number.clone(), first.version), (None, 1));
+        assert_eq!(invoicing.invoice(fx.company_id, first.id).await.unwrap(), Some(first.clone()));
+        assert_eq!(invoicing.invoice(fx.other_company_id, first.id).await.unwrap(), None);
+        let orphan = SalesInvoice::draft(
+            fx.company_id,
+            fx.owner_id,
+            sales_draft(Uuid::new_v4(), vec![line("Sambal", 1, 100)]),
+            1_100,
+            fx.at(26),
+        )
+        .unwrap();
+        assert!(invoicing.create_invoice(&orphan).await.is_err());
+
+        let mut revised = first.clone();
+        revised
+            .revise(
+                sales_draft(
+                    contact.id,
+                    vec![line("Keripik 250g", 12, 2_500_000), line("Kemasan", 12, 200_000), line("Ongkir", 1, 1_500_000)],
+                ),
+                1_100,
+                fx.at(11),
+            )
+            .unwrap();
+        let first = invoicing.update_invoice(&revised).await.unwrap();
+        assert_eq!(first.version, 2);
+        assert_eq!(first.lines, revised.lines);
+        assert_eq!(invoicing.invoice(fx.company_id, first.id).await.unwrap(), Some(first.clone()));
+
+        // Numbered in the order they are sent, per company and issue year
+        let today = reporting_day(fx.at(30));
+        let mut sent = Vec::new();
+        for invoice in [&first, &second] {
+            let mut invoice = invoice.clone();
+            invoice.send(today, 30, fx.at(30)).unwrap();
+            sent.push(invoicing.update_invoice(&invoice).await.unwrap());
+        }
+        let numbers: Vec<_> = sent.iter().map(|i| i.number.clone()).collect();
+        assert_eq!(
+            numbers,
+            vec![
+                Some(format!("INV/{}/00001", today.year())),
+                Some(format!("INV/{}/00002", today.year())),
+            ]
+        );
+        assert_eq!(invoicing.invoice(fx.company_id, sent[1].id).await.unwrap(), Some(sent[1].clone()));
+        let stale = invoicing.update_invoice(&second).await;
+        assert!(matches!(stale, Err(AppError::VersionConflict { .. })), "{:?}", stale);
+
+        // Newest first
+        let ids = |invoices: Vec<SalesInvoice>| invoices.into_iter().map(|i| i.id).collect::<Vec<_>>();
+        let open = SalesInvoiceQuery {
+            open: true,
+            ..SalesInvoiceQuery::default()
+        };
+        assert_eq!(ids(invoicing.invoices(fx.company_id, &open, 10).await.unwrap()), vec![second.id, first.id]);
+        let drafts = SalesInvoiceQuery {
+            status: Some(SalesInvoiceStatus::Draft),
+            ..SalesInvoiceQuery::default()
+        };
+        assert_eq!(ids(invoicing.invoices(fx.company_id, &drafts, 10).await.unwrap()), vec![third.id]);
+        let theirs = SalesInvoiceQuery {
+            contact_id: Some(elsewhere.id),
+            ..SalesInvoiceQuery::default()
+        };
+        assert!(invoicing.invoices(fx.company_id, &theirs, 10).await.unwrap().is_empty());
+        let all = SalesInvoiceQuery::default();
+        assert_eq!(ids(invoicing.invoices(fx.company_id, &all, 1).await.unwrap()), vec![third.id]);
+
+        // A voided draft is never numbered
+        let mut voided = third.clone();
+        voided.void(fx.at(31)).unwrap();
+        let voided = invoicing.update_invoice(&voided).await.unwrap();
+        assert_eq!((voided.number.clone(), voided.status), (None, SalesInvoiceStatus::Void));
+
+        // A payment books its transaction, moves the balance and settles its
+        // invoices together
+        let account = accounts
+            .create(&FinancialAccount::new(fx.company_id, "Kas".to_string(), "cash".to_string(), Currency::IDR, Money::idr(0)))
+            .await
+            .unwrap();
+        let payment_input = |amounts: [i64; 2]| PaymentInput {
+            contact_id: contact.id,
+            account_id: account.id,
+            paid_on: None,
+            reference: Some("TRF-0001".to_string()),
+            allocations: vec![
+                AllocationInput {
+                    invoice_id: sent[0].id,
+                    amount: amounts[0],
+                },
+                AllocationInput {
+                    invoice_id: sent[1].id,
+                    amount: amounts[1],
+                },
+            ],
+        };
+        let paid = sent[0].total.amount;
+        let (payment, allocated) = InvoicePayment::allocate(
+            fx.company_id,
+            fx.owner_id,
+            payment_input([paid, 10_000_000]),
+            sent.clone(),
+            today,
+            fx.at(40),
+        )
+        .unwrap();
+        let transaction = payment.transaction(&allocated, &contact);
+        let (stored, settled) = invoicing.record_payment(&payment, &transaction, &allocated).await.unwrap();
+        assert_eq!(stored, payment);
+        let statuses: Vec<_> = settled.iter().map(|i| (i.status, i.version)).collect();
+        assert_eq!(
+            statuses,
+            vec![(SalesInvoiceStatus::Paid, 4), (SalesInvoiceStatus::PartiallyPaid, 3)]
+        );
+        for invoice in &settled {
+            assert_eq!(invoicing.invoice(fx.company_id, invoice.id).await.unwrap().as_ref(), Some(invoice));
+        }
+        assert_eq!(invoicing.payments(fx.company_id, sent[1].id).await.unwrap(), vec![payment.clone()]);
+        assert!(invoicing.payments(fx.other_company_id, sent[1].id).await.unwrap().is_empty());
+        let booked = transactions
+            .find_by_id(&TransactionId(payment.transaction_id))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (booked.amount, booked.account_id, booked.transaction_type),
+            (Money::idr(paid + 10_000_000), account.id, TransactionType::Income)
+        );
+        let balance = || async { accounts.find_by_id(account.id).await.unwrap().unwrap().balance };
+        assert_eq!(balance().await, Money::idr(paid + 10_000_000));
+
+        // Allocated against invoices read before the first payment: nothing is
+        // booked
+        let (late, allocated) = InvoicePayment::allocate(
+            fx.company_id,
+            fx.owner_id,
+            payment_input([1_000_000, 1_000_000]),
+            sent.clone(),
+            today,
+            fx.at(41),
+        )
+        .unwrap();
+        let lost = invoicing
+            .record_payment(&late, &late.transaction(&allocated, &contact), &allocated)
+            .await;
+        assert!(matches!(lost, Err(AppError::VersionConflict { .. })), "{:?}", lost);
+        assert_eq!(balance().await, Money::idr(paid + 10_000_000));
+        assert!(transactions.find_by_id(&TransactionId(late.transaction_id)).await.unwrap().is_none());
+        assert_eq!(invoicing.payments(fx.company_id, sent[1].id).await.unwrap().len(), 1);
+
+        // Only open invoices past their due date turn overdue
+        let due = settled[1].due_date.unwrap();
+        assert_eq!(invoicing.mark_overdue(due).await.unwrap(), 0);
+        assert_eq!(invoicing.mark_overdue(due + Duration::days(1)).await.unwrap(), 1);
+        let overdue = invoicing.invoice(fx.company_id, sent[1].id).await.unwrap().unwrap();
+        assert_eq!((overdue.status, overdue.version), (SalesInvoiceStatus::Overdue, 4));
+        assert_eq!(overdue.balance_due, Money::idr(overdue.total.amount - 10_000_000));
+        let first = invoicing.invoice(fx.company_id, sent[0].id).await.unwrap().unwrap();
+        assert_eq!((first.status, first.balance_due), (SalesInvoiceStatus::Paid, Money::idr(0)));
+    }
+
+    #[tokio::test]
+    async fn in_memory_invoicing_conforms() {
+        let fx = Fixture::new();
+        let finance = InMemoryFinanceStore::new();
+        run_invoicing_scenario(
+            &InMemoryInvoicingRepository::new(finance.clone()),
+            &InMemoryFinancialAccountRepository::new(finance.clone()),
+            &InMemoryTransactionRepository::new(finance),
+            &fx,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn postgres_invoicing_conforms() {
+        let fx = Fixture::new();
+        let db = TestDatabase::seeded(&fx).await;
+        run_invoicing_scenario(
+            &PostgresInvoicingRepository::new(db.pool.clone()),
+            &PostgresFinancialAccountRepository::new(db.pool.clone()),
+            &PostgresTransactionRepository::new(db.pool.clone()),
+            &fx,
+        )
+        .await;
+        db.destroy().await;
+    }
+}
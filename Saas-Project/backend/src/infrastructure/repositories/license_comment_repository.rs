@@ -0,0 +1,318 @@
+// PostgreSQL implementation of license comments
+// Attachments, mentions and reads are rows of their own; comments are read
+// back with their attachments and mentions gathered into arrays. Posting a
+// comment writes all of it, and the author's own read, in one transaction.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use crate::domain::license_comments::{LicenseComment, LicenseCommentRepository, ReadReceipt};
+use crate::shared::errors::{AppError, AppResult};
+
+const COMMENT_COLUMNS: &str = "c.id, c.license_id, c.parent_id, c.author_id, c.body, c.internal, \
+    c.kind, c.created_at, \
+    ARRAY(SELECT a.document_id FROM license_comment_attachments a \
+          WHERE a.comment_id = c.id ORDER BY a.document_id) AS document_ids, \
+    ARRAY(SELECT m.user_id FROM license_comment_mentions m \
+          WHERE m.comment_id = c.id ORDER BY m.user_id) AS mentions";
+
+fn row_to_comment(row: &PgRow) -> Result<LicenseComment, AppError> {
+    let kind: String = row.try_get("kind")?;
+    Ok(LicenseComment {
+        id: row.try_get("id")?,
+        license_id: row.try_get("license_id")?,
+        parent_id: row.try_get("parent_id")?,
+        author_id: row.try_get("author_id")?,
+        body: row.try_get("body")?,
+        internal: row.try_get("internal")?,
+        kind: kind.parse().map_err(AppError::InternalError)?,
+        document_ids: row.try_get("document_ids")?,
+        mentions: row.try_get("mentions")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+#[derive(Clone)]
+pub struct PostgresLicenseCommentRepository {
+    pool: PgPool,
+}
+
+impl PostgresLicenseCommentRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl LicenseCommentRepository for PostgresLicenseCommentRepository {
+    async fn create_comment(&self, comment: &LicenseComment) -> AppResult<LicenseComment> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "INSERT INTO license_comments \
+                 (id, license_id, parent_id, author_id, body, internal, kind, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(comment.id)
+        .bind(comment.license_id)
+        .bind(comment.parent_id)
+        .bind(comment.author_id)
+        .bind(&comment.body)
+        .bind(comment.internal)
+        .bind(comment.kind.to_string())
+        .bind(comment.created_at)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "INSERT INTO license_comment_attachments (comment_id, document_id) \
+             SELECT $1, UNNEST($2::uuid[])",
+        )
+        .bind(comment.id)
+        .bind(&comment.document_ids)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "INSERT INTO license_comment_mentions (comment_id, user_id) \
+             SELECT $1, UNNEST($2::uuid[])",
+        )
+        .bind(comment.id)
+        .bind(&comment.mentions)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "INSERT INTO license_comment_reads (comment_id, user_id, read_at) VALUES ($1, $2, $3)",
+        )
+        .bind(comment.id)
+        .bind(comment.author_id)
+        .bind(comment.created_at)
+        .execute(&mut *tx)
+        .await?;
+
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM license_comments c WHERE c.id = $1",
+            COMMENT_COLUMNS
+        ))
+        .bind(comment.id)
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        row_to_comment(&row)
+    }
+
+    async fn get_comment(&self, id: Uuid) -> AppResult<Option<LicenseComment>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM license_comments c WHERE c.id = $1",
+            COMMENT_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(row_to_comment).transpose()
+    }
+
+    async fn comments(&self, license_id: Uuid, include_internal: bool) -> AppResult<Vec<LicenseComment>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM license_comments c \
+             WHERE c.license_id = $1 AND ($2 OR NOT c.internal) \
+             ORDER BY c.created_at, c.id",
+            COMMENT_COLUMNS
+        ))
+        .bind(license_id)
+        .bind(include_internal)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_comment).collect()
+    }
+
+    async fn read_receipts(&self, license_id: Uuid) -> AppResult<Vec<ReadReceipt>> {
+        let rows = sqlx::query(
+            "SELECT r.comment_id, r.user_id, r.read_at FROM license_comment_reads r \
+             JOIN license_comments c ON c.id = r.comment_id \
+             WHERE c.license_id = $1 \
+             ORDER BY r.read_at, r.comment_id, r.user_id",
+        )
+        .bind(license_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(ReadReceipt {
+                    comment_id: row.try_get("comment_id")?,
+                    user_id: row.try_get("user_id")?,
+                    read_at: row.try_get("read_at")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn mark_read(
+        &self,
+        license_id: Uuid,
+        user_id: Uuid,
+        include_internal: bool,
+        at: DateTime<Utc>,
+    ) -> AppResult<u64> {
+        let result = sqlx::query(
+            "INSERT INTO license_comment_reads (comment_id, user_id, read_at) \
+             SELECT id, $2, $4 FROM license_comments \
+             WHERE license_id = $1 AND ($3 OR NOT internal) AND created_at <= $4 \
+             ON CONFLICT (comment_id, user_id) DO NOTHING",
+        )
+        .bind(license_id)
+        .bind(user_id)
+        .bind(include_internal)
+        .bind(at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::license_comments::CommentKind;
+    use crate::domain::licenses::LicenseType;
+    use crate::infrastructure::repositories::testing::{in_memory, Fixture, TestDatabase};
+    use crate::infrastructure::repositories::{
+        InMemoryLicenseCommentRepository, LicenseRepository, PostgresLicenseRepositoryImpl,
+    };
+
+    /// Comment threads with their attachments and mentions, and per-user
+    /// read receipts
+    async fn run_comment_scenario(
+        repo: &dyn LicenseRepository,
+        comments: &dyn LicenseCommentRepository,
+        fx: &Fixture,
+    ) {
+        let license = repo
+            .create_license(&fx.license(LicenseType::Siup, fx.company_id, fx.owner_id, "SIUP", 0))
+            .await
+            .unwrap();
+        let other = repo
+            .create_license(&fx.license(LicenseType::Nib, fx.company_id, fx.owner_id, "NIB", 0))
+            .await
+            .unwrap();
+        let deed = repo.create_document(&fx.document(license.id, "akta.pdf", 1)).await.unwrap();
+        let npwp = repo.create_document(&fx.document(license.id, "npwp.pdf", 2)).await.unwrap();
+
+        let comment = |license_id, author_id, parent_id, internal, seconds| LicenseComment {
+            id: Uuid::new_v4(),
+            license_id,
+            parent_id,
+            author_id,
+            body: "Mohon lengkapi".to_string(),
+            internal,
+            kind: CommentKind::Comment,
+            document_ids: Vec::new(),
+            mentions: Vec::new(),
+            created_at: fx.at(seconds),
+        };
+
+        // Attachments and mentions come back sorted
+        let question = LicenseComment {
+            document_ids: vec![npwp.id, deed.id],
+            mentions: vec![fx.owner_id, fx.admin_id],
+            ..comment(license.id, fx.admin_id, None, false, 10)
+        };
+        let created = comments.create_comment(&question).await.unwrap();
+        let mut document_ids = vec![npwp.id, deed.id];
+        document_ids.sort();
+        let mut mentions = vec![fx.owner_id, fx.admin_id];
+        mentions.sort();
+        let question = LicenseComment {
+            document_ids,
+            mentions,
+            ..question
+        };
+        assert_eq!(created, question);
+        assert_eq!(comments.get_comment(question.id).await.unwrap(), Some(question.clone()));
+        assert_eq!(comments.get_comment(Uuid::new_v4()).await.unwrap(), None);
+
+        let note = comments
+            .create_comment(&comment(license.id, fx.admin_id, None, true, 20))
+            .await
+            .unwrap();
+        let answer = comments
+            .create_comment(&comment(license.id, fx.owner_id, Some(question.id), false, 30))
+            .await
+            .unwrap();
+        let elsewhere = comments
+            .create_comment(&comment(other.id, fx.owner_id, None, false, 5))
+            .await
+            .unwrap();
+
+        // Oldest first, internal notes only when asked for
+        assert_eq!(
+            comments.comments(license.id, false).await.unwrap(),
+            vec![question.clone(), answer.clone()]
+        );
+        assert_eq!(
+            comments.comments(license.id, true).await.unwrap(),
+            vec![question.clone(), note.clone(), answer.clone()]
+        );
+        assert_eq!(comments.comments(other.id, true).await.unwrap(), vec![elsewhere]);
+
+        // Authors have read what they wrote
+        let receipt = |comment: &LicenseComment, user_id, seconds| ReadReceipt {
+            comment_id: comment.id,
+            user_id,
+            read_at: fx.at(seconds),
+        };
+        assert_eq!(
+            comments.read_receipts(license.id).await.unwrap(),
+            vec![
+                receipt(&question, fx.admin_id, 10),
+                receipt(&note, fx.admin_id, 20),
+                receipt(&answer, fx.owner_id, 30),
+            ]
+        );
+
+        // Only comments posted by then are marked, and only once
+        assert_eq!(comments.mark_read(license.id, fx.owner_id, false, fx.at(25)).await.unwrap(), 1);
+        assert_eq!(comments.mark_read(license.id, fx.owner_id, false, fx.at(40)).await.unwrap(), 0);
+        assert_eq!(comments.mark_read(license.id, fx.admin_id, true, fx.at(40)).await.unwrap(), 1);
+        assert_eq!(comments.mark_read(license.id, fx.admin_id, true, fx.at(50)).await.unwrap(), 0);
+        assert_eq!(comments.mark_read(license.id, fx.other_user_id, false, fx.at(50)).await.unwrap(), 2);
+        // Ties on the read time are broken by comment, then user
+        let mut receipts = vec![
+            receipt(&question, fx.admin_id, 10),
+            receipt(&note, fx.admin_id, 20),
+            receipt(&question, fx.owner_id, 25),
+            receipt(&answer, fx.owner_id, 30),
+            receipt(&answer, fx.admin_id, 40),
+            receipt(&question, fx.other_user_id, 50),
+            receipt(&answer, fx.other_user_id, 50),
+        ];
+        receipts.sort_by_key(|r| (r.read_at, r.comment_id, r.user_id));
+        assert_eq!(comments.read_receipts(license.id).await.unwrap(), receipts);
+    }
+
+    #[tokio::test]
+    async fn in_memory_comments_conform() {
+        let fx = Fixture::new();
+        run_comment_scenario(&in_memory(&fx), &InMemoryLicenseCommentRepository::new(), &fx).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn postgres_comments_conform() {
+        let fx = Fixture::new();
+        let db = TestDatabase::seeded(&fx).await;
+        run_comment_scenario(
+            &PostgresLicenseRepositoryImpl::new(db.pool.clone()),
+            &PostgresLicenseCommentRepository::new(db.pool.clone()),
+            &fx,
+        )
+        .await;
+        db.destroy().await;
+    }
+}
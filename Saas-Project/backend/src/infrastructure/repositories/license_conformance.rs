@@ -0,0 +1,302 @@
+// Conformance suite for LicenseRepository implementations
+// One scenario, run against the in-memory fake, Postgres and the cached
+// decorator over each. Every read is repeated after the writes that affect it,
+// so a decorator that misses an invalidation serves a stale answer and fails.
+// The fixture and the Postgres setup are shared with the other repositories'
+// conformance tests in `testing`.
+
+use chrono::{Duration, SubsecRound, Utc};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::domain::filters::{DocumentFilter, LicenseFilter};
+use crate::domain::licenses::{
+    ApplicationStatus, License, LicenseDocument, LicenseType, PriorityLevel,
+};
+use crate::infrastructure::cache::InMemoryCache;
+use crate::shared::errors::AppError;
+use crate::shared::query::ListQuery;
+
+use super::testing::{in_memory, Fixture, TestDatabase};
+use super::{CachedLicenseRepository, LicenseRepository, PostgresLicenseRepositoryImpl};
+
+fn ids(licenses: &[License]) -> Vec<Uuid> {
+    licenses.iter().map(|l| l.id).collect()
+}
+
+fn sorted<K: ToString, V>(mut rows: Vec<(K, V)>) -> Vec<(String, V)> {
+    rows.sort_by_key(|(key, _)| key.to_string());
+    rows.into_iter().map(|(key, value)| (key.to_string(), value)).collect()
+}
+
+async fn run_scenario(repo: &dyn LicenseRepository, fx: &Fixture) {
+    let mut a = fx.license(LicenseType::Nib, fx.company_id, fx.owner_id, "Izin Usaha Toko Sari", 0);
+    let mut b = fx.license(LicenseType::Siup, fx.company_id, fx.owner_id, "SIUP Perdagangan", 1);
+    let mut c = fx.license(LicenseType::Halal, fx.other_company_id, fx.other_user_id, "Sertifikat Halal", 2);
+    c.expiry_date = Some(Utc::now().trunc_subsecs(6) + Duration::days(10));
+
+    // Reads before any write, so a cached decorator has something to go stale
+    assert!(repo.get_license_by_id(a.id).await.unwrap().is_none());
+    assert!(repo.get_licenses_by_user(fx.owner_id).await.unwrap().is_empty());
+    assert!(repo.get_licenses_by_user(fx.other_user_id).await.unwrap().is_empty());
+    assert!(repo.get_licenses_by_company(fx.company_id).await.unwrap().is_empty());
+    assert!(repo.get_licenses_by_status(ApplicationStatus::Draft).await.unwrap().is_empty());
+    assert!(repo.get_licenses_by_type(LicenseType::Nib).await.unwrap().is_empty());
+    assert!(repo.get_expiring_licenses(30).await.unwrap().is_empty());
+    assert!(repo.get_license_count_by_type().await.unwrap().is_empty());
+    assert_eq!(repo.get_license_statistics(None).await.unwrap().total_licenses, 0);
+    assert_eq!(repo.get_license_statistics(Some(fx.owner_id)).await.unwrap().total_licenses, 0);
+
+    // Creates return the stored row
+    for license in [&a, &b, &c] {
+        assert_eq!(&repo.create_license(license).await.unwrap(), license);
+    }
+    assert!(repo.create_license(&a).await.is_err(), "duplicate id");
+
+    assert_eq!(repo.get_license_by_id(a.id).await.unwrap().as_ref(), Some(&a));
+    assert_eq!(ids(&repo.get_licenses_by_user(fx.owner_id).await.unwrap()), vec![b.id, a.id]);
+    assert_eq!(ids(&repo.get_licenses_by_user(fx.other_user_id).await.unwrap()), vec![c.id]);
+    assert_eq!(ids(&repo.get_licenses_by_company(fx.company_id).await.unwrap()), vec![b.id, a.id]);
+    assert_eq!(
+        ids(&repo.get_licenses_by_status(ApplicationStatus::Draft).await.unwrap()),
+        vec![c.id, b.id, a.id]
+    );
+    assert_eq!(ids(&repo.get_licenses_by_type(LicenseType::Nib).await.unwrap()), vec![a.id]);
+    assert_eq!(ids(&repo.get_expiring_licenses(30).await.unwrap()), vec![c.id]);
+    assert_eq!(
+        sorted(repo.get_license_count_by_type().await.unwrap()),
+        sorted(vec![(LicenseType::Halal, 1), (LicenseType::Nib, 1), (LicenseType::Siup, 1)])
+    );
+    let stats = repo.get_license_statistics(None).await.unwrap();
+    assert_eq!((stats.total_licenses, stats.draft_count), (3, 3));
+    assert_eq!(stats.avg_processing_days, None);
+    assert_eq!(repo.get_license_statistics(Some(fx.owner_id)).await.unwrap().total_licenses, 2);
+
+    // Updates
+    a.title = "Izin Usaha Warung Sari".to_string();
+    a.description = Some("Warung makan".to_string());
+    a.priority = PriorityLevel::High;
+    a.updated_at = fx.at(10);
+    let updated = repo.update_license(&a).await.unwrap();
+    assert_eq!(updated, License { version: 2, ..a.clone() });
+    a = updated;
+    assert_eq!(repo.get_license_by_id(a.id).await.unwrap().as_ref(), Some(&a));
+
+    // A writer still holding the first version loses, and changes nothing
+    let stale = License {
+        title: "Izin Usaha Lama".to_string(),
+        version: 1,
+        ..a.clone()
+    };
+    assert!(matches!(
+        repo.update_license(&stale).await,
+        Err(AppError::VersionConflict { current_version: 2, .. })
+    ));
+    assert_eq!(repo.get_license_by_id(a.id).await.unwrap().as_ref(), Some(&a));
+    assert_eq!(repo.get_licenses_by_user(fx.owner_id).await.unwrap()[1], a);
+
+    // Moving a license to another user updates both users' views
+    b.user_id = fx.other_user_id;
+    b.updated_at = fx.at(11);
+    b = repo.update_license(&b).await.unwrap();
+    assert_eq!(ids(&repo.get_licenses_by_user(fx.owner_id).await.unwrap()), vec![a.id]);
+    assert_eq!(ids(&repo.get_licenses_by_user(fx.other_user_id).await.unwrap()), vec![c.id, b.id]);
+    assert_eq!(repo.get_license_statistics(Some(fx.owner_id)).await.unwrap().total_licenses, 1);
+
+    let missing = fx.license(LicenseType::Tdp, fx.company_id, fx.owner_id, "Tidak ada", 3);
+    assert!(matches!(repo.update_license(&missing).await, Err(AppError::NotFound(_))));
+
+    // Documents
+    let d1 = fx.document(a.id, "ktp.pdf", 20);
+    let d2 = fx.document(a.id, "akta.pdf", 21);
+    assert!(repo.get_documents_by_license(a.id).await.unwrap().is_empty());
+    assert!(repo.get_documents_by_license(b.id).await.unwrap().is_empty());
+    assert!(repo.get_document_by_id(d1.id).await.unwrap().is_none());
+
+    assert_eq!(repo.create_document(&d1).await.unwrap(), d1);
+    assert_eq!(repo.create_document(&d2).await.unwrap(), d2);
+    assert!(repo.create_document(&fx.document(missing.id, "x.pdf", 22)).await.is_err(), "unknown license");
+    assert_eq!(repo.get_documents_by_license(a.id).await.unwrap(), vec![d1.clone(), d2.clone()]);
+    assert_eq!(repo.get_document_by_id(d1.id).await.unwrap().as_ref(), Some(&d1));
+
+    // The owning license is not part of a document update
+    let verified = LicenseDocument {
+        license_id: b.id,
+        is_verified: true,
+        verified_at: Some(fx.at(30)),
+        verified_by: Some(fx.admin_id),
+        notes: Some("Sesuai".to_string()),
+        ..d1.clone()
+    };
+    let d1 = LicenseDocument { license_id: a.id, ..verified.clone() };
+    assert_eq!(repo.update_document(&verified).await.unwrap(), d1);
+    assert_eq!(repo.get_document_by_id(d1.id).await.unwrap().as_ref(), Some(&d1));
+    assert_eq!(repo.get_documents_by_license(a.id).await.unwrap(), vec![d1.clone(), d2.clone()]);
+    assert!(repo.get_documents_by_license(b.id).await.unwrap().is_empty());
+    assert!(matches!(
+        repo.update_document(&fx.document(a.id, "baru.pdf", 23)).await,
+        Err(sqlx::Error::RowNotFound)
+    ));
+
+    assert!(repo.delete_document(d2.id).await.unwrap());
+    assert!(!repo.delete_document(d2.id).await.unwrap());
+    assert!(repo.get_document_by_id(d2.id).await.unwrap().is_none());
+    assert_eq!(repo.get_documents_by_license(a.id).await.unwrap(), vec![d1.clone()]);
+
+    let verified_docs = ListQuery::new(DocumentFilter {
+        license_id: Some(a.id),
+        is_verified: Some(true),
+        ..Default::default()
+    });
+    let page = repo.list_documents(&verified_docs).await.unwrap();
+    assert_eq!((page.data, page.total), (vec![d1.clone()], Some(1)));
+
+    // Status history
+    let h1 = fx.history(a.id, None, ApplicationStatus::Draft, 40);
+    let h2 = fx.history(a.id, Some(ApplicationStatus::Draft), ApplicationStatus::Submitted, 41);
+    assert!(repo.get_status_history_by_license(a.id).await.unwrap().is_empty());
+    assert_eq!(repo.create_status_history(&h2).await.unwrap(), h2);
+    assert_eq!(repo.create_status_history(&h1).await.unwrap(), h1);
+    assert!(repo
+        .create_status_history(&fx.history(missing.id, None, ApplicationStatus::Draft, 42))
+        .await
+        .is_err());
+    assert_eq!(repo.get_status_history_by_license(a.id).await.unwrap(), vec![h1, h2]);
+
+    // Workflow transitions
+    assert!(matches!(
+        repo.submit_license_application(a.id, fx.other_user_id).await,
+        Err(sqlx::Error::RowNotFound)
+    ));
+    let submitted = repo.submit_license_application(a.id, fx.owner_id).await.unwrap();
+    assert_eq!(submitted.application_status, ApplicationStatus::Submitted);
+    assert!(submitted.submitted_at.is_some());
+    assert_eq!(submitted.version, a.version + 1);
+    assert_eq!(repo.get_license_by_id(a.id).await.unwrap().as_ref(), Some(&submitted));
+    assert_eq!(ids(&repo.get_licenses_by_status(ApplicationStatus::Submitted).await.unwrap()), vec![a.id]);
+    assert_eq!(
+        ids(&repo.get_licenses_by_status(ApplicationStatus::Draft).await.unwrap()),
+        vec![c.id, b.id]
+    );
+
+    // A reviewer who loaded the license before it was submitted
+    assert!(matches!(
+        repo.reject_license(a.id, a.version, fx.admin_id, "Belum diajukan".to_string(), None)
+            .await,
+        Err(AppError::VersionConflict { .. })
+    ));
+    let approved = repo
+        .approve_license(
+            a.id,
+            submitted.version,
+            fx.admin_id,
+            format!("NIB-{}", a.id.as_simple()),
+            fx.at(50),
+            Some(Utc::now().trunc_subsecs(6) + Duration::days(365)),
+            "OSS RBA".to_string(),
+            Some("Lengkap".to_string()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(approved.application_status, ApplicationStatus::Approved);
+    assert_eq!(approved.actual_processing_days, Some(0));
+    assert!(approved.approved_at.is_some());
+    assert_eq!(repo.get_license_by_id(a.id).await.unwrap().as_ref(), Some(&approved));
+    let number = approved.license_number.clone().unwrap();
+    assert_eq!(repo.get_license_by_number(&number).await.unwrap().as_ref(), Some(&approved));
+    assert_eq!(ids(&repo.get_expiring_licenses(30).await.unwrap()), vec![c.id]);
+
+    // License numbers are unique
+    assert!(repo
+        .approve_license(
+            c.id,
+            c.version,
+            fx.admin_id,
+            approved.license_number.clone().unwrap(),
+            fx.at(51),
+            None,
+            "OSS RBA".to_string(),
+            None,
+        )
+        .await
+        .is_err());
+
+    let rejected = repo
+        .reject_license(b.id, b.version, fx.admin_id, "Dokumen tidak lengkap".to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(rejected.application_status, ApplicationStatus::Rejected);
+    assert_eq!(rejected.rejection_reason.as_deref(), Some("Dokumen tidak lengkap"));
+    assert_eq!(ids(&repo.get_licenses_by_status(ApplicationStatus::Rejected).await.unwrap()), vec![b.id]);
+
+    assert_eq!(
+        sorted(repo.get_license_count_by_status().await.unwrap()),
+        sorted(vec![
+            (ApplicationStatus::Approved, 1),
+            (ApplicationStatus::Draft, 1),
+            (ApplicationStatus::Rejected, 1),
+        ])
+    );
+    assert_eq!(repo.get_processing_times().await.unwrap(), vec![(LicenseType::Nib, 0.0)]);
+    let stats = repo.get_license_statistics(None).await.unwrap();
+    assert_eq!(
+        (stats.total_licenses, stats.draft_count, stats.approved_count, stats.rejected_count),
+        (3, 1, 1, 1)
+    );
+    assert_eq!(stats.avg_processing_days, Some(0.0));
+
+    // Owners see licenses they filed and licenses under companies they own
+    let owned = ListQuery::new(LicenseFilter {
+        owner_id: Some(fx.owner_id),
+        ..Default::default()
+    });
+    let page = repo.list_licenses(&owned).await.unwrap();
+    assert_eq!((ids(&page.data), page.total), (vec![b.id, a.id], Some(2)));
+
+    // Deleting a license takes its documents and history with it
+    assert!(repo.delete_license(a.id).await.unwrap());
+    assert!(!repo.delete_license(a.id).await.unwrap());
+    assert!(repo.get_license_by_id(a.id).await.unwrap().is_none());
+    assert!(repo.get_licenses_by_user(fx.owner_id).await.unwrap().is_empty());
+    assert_eq!(ids(&repo.get_licenses_by_company(fx.company_id).await.unwrap()), vec![b.id]);
+    assert!(repo.get_documents_by_license(a.id).await.unwrap().is_empty());
+    assert!(repo.get_document_by_id(d1.id).await.unwrap().is_none());
+    assert!(repo.get_status_history_by_license(a.id).await.unwrap().is_empty());
+    assert!(repo.get_licenses_by_type(LicenseType::Nib).await.unwrap().is_empty());
+    assert!(repo.get_processing_times().await.unwrap().is_empty());
+    assert_eq!(repo.get_license_statistics(None).await.unwrap().total_licenses, 2);
+}
+
+fn cached(inner: Arc<dyn LicenseRepository + Send + Sync>) -> CachedLicenseRepository<InMemoryCache> {
+    CachedLicenseRepository::from_inner(inner, Some(Arc::new(InMemoryCache::new())))
+}
+
+#[tokio::test]
+async fn in_memory_repository_conforms() {
+    let fx = Fixture::new();
+    run_scenario(&in_memory(&fx), &fx).await;
+}
+
+#[tokio::test]
+async fn cached_in_memory_repository_conforms() {
+    let fx = Fixture::new();
+    run_scenario(&cached(Arc::new(in_memory(&fx))), &fx).await;
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn postgres_repository_conforms() {
+    let fx = Fixture::new();
+    let db = TestDatabase::seeded(&fx).await;
+    run_scenario(&PostgresLicenseRepositoryImpl::new(db.pool.clone()), &fx).await;
+    db.destroy().await;
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn cached_postgres_repository_conforms() {
+    let fx = Fixture::new();
+    let db = TestDatabase::seeded(&fx).await;
+    run_scenario(&cached(Arc::new(PostgresLicenseRepositoryImpl::new(db.pool.clone()))), &fx).await;
+    db.destroy().await;
+}
@@ -2,26 +2,134 @@
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use sea_query::{extension::postgres::PgExpr, Alias, Asterisk, Cond, Expr, Query, SelectStatement};
 use serde::{Deserialize, Serialize};
-use sqlx::{PgPool, Row};
+use sqlx::{PgConnection, PgPool, Row};
 use uuid::Uuid;
 
+use crate::domain::filters::{DocumentFilter, DocumentSortField, LicenseFilter, LicenseSortField};
 use crate::domain::licenses::{
     ApplicationStatus, ApplicationStatusHistory, License, LicenseDocument, LicenseType,
 };
 use crate::domain::dto::LicenseDto;
+use crate::infrastructure::database::list_query::{contains_pattern, fetch_page};
+use crate::infrastructure::database::unit_of_work::{Db, PgWork};
+use crate::infrastructure::database::versioning::stale_write;
+use crate::shared::errors::AppResult;
+use crate::shared::query::{ListQuery, Page};
 
 // Import LicenseRepository trait from cached_license_repository.rs
 use super::cached_license_repository::LicenseRepository;
 
 pub struct PostgresLicenseRepositoryImpl {
-    pool: PgPool,
+    db: Db,
 }
 
 impl PostgresLicenseRepositoryImpl {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self { db: Db::Pool(pool) }
     }
+
+    /// Runs every statement inside the given unit of work
+    pub fn within(work: &PgWork) -> Self {
+        Self {
+            db: Db::Joined(work.clone()),
+        }
+    }
+}
+
+/// Postgres label of a license enum. The sqlx mappings use
+/// `rename_all = "lowercase"`, i.e. the variant name in lowercase.
+fn enum_label<T: std::fmt::Debug>(value: &T) -> String {
+    format!("{:?}", value).to_lowercase()
+}
+
+fn enum_eq<T: std::fmt::Debug>(column: &str, type_name: &str, value: &T) -> sea_query::SimpleExpr {
+    Expr::col(Alias::new(column)).eq(Expr::val(enum_label(value)).as_enum(Alias::new(type_name)))
+}
+
+/// Filtered select over licenses, without ordering or paging
+pub(crate) fn license_list_select(filter: &LicenseFilter) -> SelectStatement {
+    let mut cond = Cond::all();
+
+    if let Some(owner_id) = filter.owner_id {
+        let owned_company = Query::select()
+            .expr(Expr::val(1))
+            .from(Alias::new("companies"))
+            .and_where(
+                Expr::col((Alias::new("companies"), Alias::new("id")))
+                    .equals((Alias::new("licenses"), Alias::new("company_id"))),
+            )
+            .and_where(Expr::col((Alias::new("companies"), Alias::new("owner_id"))).eq(owner_id))
+            .to_owned();
+        cond = cond.add(
+            Cond::any()
+                .add(Expr::col(Alias::new("user_id")).eq(owner_id))
+                .add(Expr::exists(owned_company)),
+        );
+    }
+    if let Some(company_id) = filter.company_id {
+        cond = cond.add(Expr::col(Alias::new("company_id")).eq(company_id));
+    }
+    if let Some(status) = &filter.status {
+        cond = cond.add(enum_eq("application_status", "application_status", status));
+    }
+    if let Some(license_type) = &filter.license_type {
+        cond = cond.add(enum_eq("license_type", "license_type", license_type));
+    }
+    if let Some(priority) = &filter.priority {
+        cond = cond.add(enum_eq("priority", "priority_level", priority));
+    }
+    if let Some(search) = filter.search.as_deref().filter(|s| !s.trim().is_empty()) {
+        let pattern = contains_pattern(search.trim());
+        cond = cond.add(
+            Cond::any()
+                .add(Expr::col(Alias::new("title")).ilike(pattern.as_str()))
+                .add(Expr::col(Alias::new("license_number")).ilike(pattern.as_str()))
+                .add(Expr::col(Alias::new("description")).ilike(pattern.as_str())),
+        );
+    }
+
+    Query::select()
+        .column(Asterisk)
+        .from(Alias::new("licenses"))
+        .cond_where(cond)
+        .to_owned()
+}
+
+/// Filtered select over license_documents, without ordering or paging
+pub(crate) fn document_list_select(filter: &DocumentFilter) -> SelectStatement {
+    let mut cond = Cond::all();
+
+    if let Some(license_id) = filter.license_id {
+        cond = cond.add(Expr::col(Alias::new("license_id")).eq(license_id));
+    }
+    if let Some(document_type) = &filter.document_type {
+        cond = cond.add(enum_eq("document_type", "document_type", document_type));
+    }
+    if let Some(is_verified) = filter.is_verified {
+        cond = cond.add(Expr::col(Alias::new("is_verified")).eq(is_verified));
+    }
+
+    Query::select()
+        .column(Asterisk)
+        .from(Alias::new("license_documents"))
+        .cond_where(cond)
+        .to_owned()
+}
+
+pub(crate) async fn list_licenses(
+    conn: &mut PgConnection,
+    query: &ListQuery<LicenseFilter, LicenseSortField>,
+) -> Result<Page<License>, sqlx::Error> {
+    fetch_page(conn, &license_list_select(&query.filter), query).await
+}
+
+pub(crate) async fn list_documents(
+    conn: &mut PgConnection,
+    query: &ListQuery<DocumentFilter, DocumentSortField>,
+) -> Result<Page<LicenseDocument>, sqlx::Error> {
+    fetch_page(conn, &document_list_select(&query.filter), query).await
 }
 
 #[async_trait]
@@ -67,7 +175,7 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
             .bind(dto.rejected_at)
             .bind(&dto.admin_notes)
             .bind(&dto.rejection_reason)
-            .fetch_one(&self.pool)
+            .fetch_one(&mut *self.db.conn().await?)
             .await?;
 
         Ok(inserted.into())
@@ -77,7 +185,17 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
         let query = "SELECT * FROM licenses WHERE id = $1";
         let dto = sqlx::query_as::<_, LicenseDto>(query)
             .bind(id)
-            .fetch_optional(&self.pool)
+            .fetch_optional(&mut *self.db.conn().await?)
+            .await?;
+
+        Ok(dto.map(|d| d.into()))
+    }
+
+    async fn get_license_by_number(&self, license_number: &str) -> Result<Option<License>, sqlx::Error> {
+        let query = "SELECT * FROM licenses WHERE license_number = $1";
+        let dto = sqlx::query_as::<_, LicenseDto>(query)
+            .bind(license_number)
+            .fetch_optional(&mut *self.db.conn().await?)
             .await?;
 
         Ok(dto.map(|d| d.into()))
@@ -92,7 +210,7 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
 
         let rows = sqlx::query_as::<_, LicenseDto>(query)
             .bind(user_id)
-            .fetch_all(&self.pool)
+            .fetch_all(&mut *self.db.conn().await?)
             .await?;
 
         Ok(rows.into_iter().map(|d| d.into()).collect())
@@ -106,7 +224,7 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
 
         let rows = sqlx::query_as::<_, LicenseDto>(query)
             .bind(company_id)
-            .fetch_all(&self.pool)
+            .fetch_all(&mut *self.db.conn().await?)
             .await?;
 
         Ok(rows.into_iter().map(|d| d.into()).collect())
@@ -116,7 +234,7 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
     // Just implement enough for the compiler to be satisfied
 
     // Similar placeholders for the remaining methods
-    async fn update_license(&self, license: &License) -> Result<License, sqlx::Error> {
+    async fn update_license(&self, license: &License) -> AppResult<License> {
         let query = r#"
             UPDATE licenses
             SET
@@ -142,13 +260,13 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
                 rejected_at = $20,
                 admin_notes = $21,
                 rejection_reason = $22
-            WHERE id = $23
+            WHERE id = $23 AND version = $24
             RETURNING *
         "#;
 
         let dto: LicenseDto = license.clone().into();
 
-        let updated: LicenseDto = sqlx::query_as(query)
+        let updated: Option<LicenseDto> = sqlx::query_as(query)
             .bind(&dto.license_number)
             .bind(dto.license_type)
             .bind(dto.company_id)
@@ -172,16 +290,20 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
             .bind(&dto.admin_notes)
             .bind(&dto.rejection_reason)
             .bind(dto.id)
-            .fetch_one(&self.pool)
+            .bind(dto.version)
+            .fetch_optional(&mut *self.db.conn().await?)
             .await?;
 
-        Ok(updated.into())
+        match updated {
+            Some(updated) => Ok(updated.into()),
+            None => Err(stale_write(&mut *self.db.conn().await?, "licenses", "License", license.id).await),
+        }
     }
 
     async fn delete_license(&self, id: Uuid) -> Result<bool, sqlx::Error> {
         let result = sqlx::query("DELETE FROM licenses WHERE id = $1")
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *self.db.conn().await?)
             .await?;
 
         Ok(result.rows_affected() > 0)
@@ -195,7 +317,7 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
             "SELECT * FROM licenses WHERE application_status = $1 ORDER BY created_at DESC",
         )
         .bind(status)
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.db.conn().await?)
         .await?;
 
         Ok(rows.into_iter().map(|d| d.into()).collect())
@@ -209,7 +331,7 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
             "SELECT * FROM licenses WHERE license_type = $1 ORDER BY created_at DESC",
         )
         .bind(license_type)
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.db.conn().await?)
         .await?;
 
         Ok(rows.into_iter().map(|d| d.into()).collect())
@@ -225,50 +347,12 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
 
         let licenses = sqlx::query_as::<_, License>(query)
             .bind(days_ahead)
-            .fetch_all(&self.pool)
+            .fetch_all(&mut *self.db.conn().await?)
             .await?;
 
         Ok(licenses)
     }
 
-    async fn search_licenses(
-        &self,
-        query: &str,
-        user_id: Option<Uuid>,
-    ) -> Result<Vec<License>, sqlx::Error> {
-        let like_query = format!("%{}%", query);
-
-        let sql = if user_id.is_some() {
-            r#"
-                SELECT * FROM licenses
-                WHERE user_id = $1
-                  AND (title ILIKE $2 OR license_number ILIKE $2)
-                ORDER BY created_at DESC
-            "#
-        } else {
-            r#"
-                SELECT * FROM licenses
-                WHERE title ILIKE $1 OR license_number ILIKE $1
-                ORDER BY created_at DESC
-            "#
-        };
-
-        let rows = if let Some(uid) = user_id {
-            sqlx::query_as::<_, LicenseDto>(sql)
-                .bind(uid)
-                .bind(&like_query)
-                .fetch_all(&self.pool)
-                .await?
-        } else {
-            sqlx::query_as::<_, LicenseDto>(sql)
-                .bind(&like_query)
-                .fetch_all(&self.pool)
-                .await?
-        };
-
-        Ok(rows.into_iter().map(|d| d.into()).collect())
-    }
-
     async fn create_document(
         &self,
         document: &LicenseDocument,
@@ -297,12 +381,26 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
             .bind(document.verified_at)
             .bind(document.verified_by)
             .bind(&document.notes)
-            .fetch_one(&self.pool)
+            .fetch_one(&mut *self.db.conn().await?)
             .await?;
 
         Ok(inserted)
     }
 
+    async fn list_licenses(
+        &self,
+        query: &ListQuery<LicenseFilter, LicenseSortField>,
+    ) -> Result<Page<License>, sqlx::Error> {
+        list_licenses(&mut *self.db.conn().await?, query).await
+    }
+
+    async fn list_documents(
+        &self,
+        query: &ListQuery<DocumentFilter, DocumentSortField>,
+    ) -> Result<Page<LicenseDocument>, sqlx::Error> {
+        list_documents(&mut *self.db.conn().await?, query).await
+    }
+
     async fn get_documents_by_license(
         &self,
         license_id: Uuid,
@@ -311,7 +409,7 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
             "SELECT * FROM license_documents WHERE license_id = $1 ORDER BY upload_date ASC",
         )
         .bind(license_id)
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.db.conn().await?)
         .await?;
 
         Ok(rows)
@@ -322,7 +420,7 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
             "SELECT * FROM license_documents WHERE id = $1",
         )
         .bind(id)
-        .fetch_optional(&self.pool)
+        .fetch_optional(&mut *self.db.conn().await?)
         .await?;
 
         Ok(row)
@@ -363,7 +461,7 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
             .bind(document.verified_by)
             .bind(&document.notes)
             .bind(document.id)
-            .fetch_one(&self.pool)
+            .fetch_one(&mut *self.db.conn().await?)
             .await?;
 
         Ok(updated)
@@ -372,7 +470,7 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
     async fn delete_document(&self, id: Uuid) -> Result<bool, sqlx::Error> {
         let result = sqlx::query("DELETE FROM license_documents WHERE id = $1")
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *self.db.conn().await?)
             .await?;
 
         Ok(result.rows_affected() > 0)
@@ -400,7 +498,7 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
             .bind(history.changed_at)
             .bind(&history.notes)
             .bind(history.is_system_generated)
-            .fetch_one(&self.pool)
+            .fetch_one(&mut *self.db.conn().await?)
             .await?;
 
         Ok(inserted)
@@ -414,7 +512,7 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
             "SELECT * FROM application_status_history WHERE license_id = $1 ORDER BY changed_at ASC",
         )
         .bind(license_id)
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.db.conn().await?)
         .await?;
 
         Ok(rows)
@@ -437,7 +535,7 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
         let dto: LicenseDto = sqlx::query_as(query)
             .bind(license_id)
             .bind(user_id)
-            .fetch_one(&self.pool)
+            .fetch_one(&mut *self.db.conn().await?)
             .await?;
 
         Ok(dto.into())
@@ -446,13 +544,14 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
     async fn approve_license(
         &self,
         license_id: Uuid,
+        expected_version: i64,
         _admin_user_id: Uuid,
         license_number: String,
         issue_date: DateTime<Utc>,
         expiry_date: Option<DateTime<Utc>>,
         issuing_authority: String,
         admin_notes: Option<String>,
-    ) -> Result<License, sqlx::Error> {
+    ) -> AppResult<License> {
         let query = r#"
             UPDATE licenses
             SET application_status = 'approved',
@@ -464,30 +563,35 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
                 approved_at = NOW(),
                 updated_at = NOW(),
                 actual_processing_days = CASE WHEN submitted_at IS NOT NULL THEN EXTRACT(DAY FROM (NOW() - submitted_at))::INT END
-            WHERE id = $1
+            WHERE id = $1 AND version = $7
             RETURNING *
         "#;
 
-        let dto: LicenseDto = sqlx::query_as(query)
+        let dto: Option<LicenseDto> = sqlx::query_as(query)
             .bind(license_id)
             .bind(&license_number)
             .bind(issue_date)
             .bind(expiry_date)
             .bind(&issuing_authority)
             .bind(&admin_notes)
-            .fetch_one(&self.pool)
+            .bind(expected_version)
+            .fetch_optional(&mut *self.db.conn().await?)
             .await?;
 
-        Ok(dto.into())
+        match dto {
+            Some(dto) => Ok(dto.into()),
+            None => Err(stale_write(&mut *self.db.conn().await?, "licenses", "License", license_id).await),
+        }
     }
 
     async fn reject_license(
         &self,
         license_id: Uuid,
+        expected_version: i64,
         _admin_user_id: Uuid,
         reason: String,
         admin_notes: Option<String>,
-    ) -> Result<License, sqlx::Error> {
+    ) -> AppResult<License> {
         let query = r#"
             UPDATE licenses
             SET application_status = 'rejected',
@@ -495,18 +599,22 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
                 admin_notes = $3,
                 rejected_at = NOW(),
                 updated_at = NOW()
-            WHERE id = $1
+            WHERE id = $1 AND version = $4
             RETURNING *
         "#;
 
-        let dto: LicenseDto = sqlx::query_as(query)
+        let dto: Option<LicenseDto> = sqlx::query_as(query)
             .bind(license_id)
             .bind(&reason)
             .bind(&admin_notes)
-            .fetch_one(&self.pool)
+            .bind(expected_version)
+            .fetch_optional(&mut *self.db.conn().await?)
             .await?;
 
-        Ok(dto.into())
+        match dto {
+            Some(dto) => Ok(dto.into()),
+            None => Err(stale_write(&mut *self.db.conn().await?, "licenses", "License", license_id).await),
+        }
     }
 
     async fn get_license_statistics(
@@ -522,7 +630,7 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
                     COUNT(*) FILTER (WHERE application_status = 'processing') as processing_count,
                     COUNT(*) FILTER (WHERE application_status = 'approved') as approved_count,
                     COUNT(*) FILTER (WHERE application_status = 'rejected') as rejected_count,
-                    AVG(actual_processing_days) FILTER (WHERE actual_processing_days IS NOT NULL) as avg_processing_days
+                    (AVG(actual_processing_days) FILTER (WHERE actual_processing_days IS NOT NULL))::FLOAT8 as avg_processing_days
                 FROM licenses 
                 WHERE user_id = $1
             "#
@@ -535,7 +643,7 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
                     COUNT(*) FILTER (WHERE application_status = 'processing') as processing_count,
                     COUNT(*) FILTER (WHERE application_status = 'approved') as approved_count,
                     COUNT(*) FILTER (WHERE application_status = 'rejected') as rejected_count,
-                    AVG(actual_processing_days) FILTER (WHERE actual_processing_days IS NOT NULL) as avg_processing_days
+                    (AVG(actual_processing_days) FILTER (WHERE actual_processing_days IS NOT NULL))::FLOAT8 as avg_processing_days
                 FROM licenses
             "#
         };
@@ -543,10 +651,10 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
         let row = if let Some(user_id) = user_id {
             sqlx::query(query)
                 .bind(user_id)
-                .fetch_one(&self.pool)
+                .fetch_one(&mut *self.db.conn().await?)
                 .await?
         } else {
-            sqlx::query(query).fetch_one(&self.pool).await?
+            sqlx::query(query).fetch_one(&mut *self.db.conn().await?).await?
         };
 
         Ok(LicenseStatistics {
@@ -565,7 +673,7 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
             "SELECT license_type, COUNT(*) as count FROM licenses GROUP BY license_type",
         )
         .map(|row: sqlx::postgres::PgRow| (row.get("license_type"), row.get::<i64, _>("count")))
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.db.conn().await?)
         .await?;
 
         Ok(rows)
@@ -580,7 +688,7 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
         .map(|row: sqlx::postgres::PgRow| {
             (row.get("application_status"), row.get::<i64, _>("count"))
         })
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.db.conn().await?)
         .await?;
 
         Ok(rows)
@@ -593,7 +701,7 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
         .map(|row: sqlx::postgres::PgRow| {
             (row.get("license_type"), row.get::<f64, _>("avg_days"))
         })
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.db.conn().await?)
         .await?;
 
         Ok(rows)
@@ -601,7 +709,7 @@ impl LicenseRepository for PostgresLicenseRepositoryImpl {
 }
 
 // Supporting structs for analytics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LicenseStatistics {
     pub total_licenses: i64,
     pub draft_count: i64,
@@ -611,14 +719,3 @@ pub struct LicenseStatistics {
     pub rejected_count: i64,
     pub avg_processing_days: Option<f64>,
 }
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProcessingPerformance {
-    pub license_type: LicenseType,
-    pub total_processed: i64,
-    pub avg_processing_days: Option<f64>,
-    pub min_processing_days: Option<i32>,
-    pub max_processing_days: Option<i32>,
-    pub approved_count: i64,
-    pub rejected_count: i64,
-}
@@ -1,17 +1,81 @@
 // Infrastructure repositories module
-// PostgreSQL implementations of domain repositories
+// PostgreSQL implementations of domain repositories, and in-memory ones for
+// tests and demo mode
 
 pub mod account_repository;
+pub mod admin_stats_repository;
+pub mod analytics_repository;
+pub mod billing_repository;
+pub mod certificate_repository;
+pub mod cached_company_repository;
 pub mod cached_license_repository;
+pub mod cached_user_repository;
 pub mod company_repository;
+pub mod finance_repository;
+pub mod import_repository;
+pub mod invoicing_repository;
+pub mod in_memory_admin_stats_repository;
+pub mod in_memory_analytics_repository;
+pub mod in_memory_billing_repository;
+pub mod in_memory_company_repository;
+pub mod in_memory_finance_repository;
+pub mod in_memory_import_repository;
+pub mod in_memory_invoicing_repository;
+pub mod in_memory_license_comment_repository;
+pub mod in_memory_license_repository;
+pub mod in_memory_notification_repository;
+pub mod in_memory_reconciliation_repository;
+pub mod in_memory_search_repository;
+mod in_memory_unit_of_work;
+pub mod in_memory_user_repository;
+pub mod license_comment_repository;
 pub mod license_repository;
+pub mod notification_repository;
 pub mod postgres_user_repository;
-pub mod in_memory_user_repository;
+pub mod reconciliation_repository;
+#[cfg(test)]
+mod license_conformance;
+pub mod search_repository;
+pub mod sla_repository;
+#[cfg(test)]
+pub(crate) mod testing;
 pub mod transaction_repository;
+pub mod verification_audit_repository;
 
 // Export only one LicenseRepository trait - the one from cached_license_repository
+pub use admin_stats_repository::PostgresAdminStatsRepository;
+pub use analytics_repository::PostgresAnalyticsRepository;
+pub use billing_repository::PostgresBillingRepository;
+pub use cached_company_repository::CachedCompanyRepository;
+pub use certificate_repository::PostgresCertificateRepository;
 pub use cached_license_repository::CachedLicenseRepository;
 pub use cached_license_repository::LicenseRepository;
+pub use cached_license_repository::{LicenseRepositories, LicenseUnitOfWork};
+pub use cached_user_repository::CachedUserRepository;
 pub use company_repository::PostgresCompanyRepository;
-// pub use license_repository::PostgresLicenseRepositoryImpl;
+pub use finance_repository::{PostgresFinancialAccountRepository, PostgresTransactionRepository};
+pub use import_repository::PostgresImportRepository;
+pub use invoicing_repository::PostgresInvoicingRepository;
+pub use in_memory_admin_stats_repository::InMemoryAdminStatsRepository;
+pub use in_memory_analytics_repository::InMemoryAnalyticsRepository;
+pub use in_memory_billing_repository::InMemoryBillingRepository;
+pub use in_memory_company_repository::InMemoryCompanyRepository;
+pub use in_memory_finance_repository::{
+    InMemoryFinanceStore, InMemoryFinancialAccountRepository, InMemoryTransactionRepository,
+};
+pub use in_memory_import_repository::InMemoryImportRepository;
+pub use in_memory_invoicing_repository::InMemoryInvoicingRepository;
+pub use in_memory_license_comment_repository::InMemoryLicenseCommentRepository;
+pub use in_memory_license_repository::InMemoryLicenseRepository;
+pub use in_memory_notification_repository::InMemoryNotificationRepository;
+pub use in_memory_reconciliation_repository::InMemoryReconciliationRepository;
+pub use in_memory_search_repository::InMemorySearchRepository;
+pub use in_memory_user_repository::InMemoryUserRepository;
+pub use license_comment_repository::PostgresLicenseCommentRepository;
+pub use license_repository::PostgresLicenseRepositoryImpl;
+pub use notification_repository::PostgresNotificationRepository;
 pub use postgres_user_repository::PostgresUserRepository;
+pub use reconciliation_repository::PostgresReconciliationRepository;
+pub use search_repository::PostgresSearchRepository;
+pub use sla_repository::PostgresSlaRepository;
+pub use verification_audit_repository::PostgresVerificationAuditRepository;
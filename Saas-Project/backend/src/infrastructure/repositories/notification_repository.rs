@@ -0,0 +1,551 @@
+// PostgreSQL implementation of the notification center
+// Several instances may run the dispatcher; each claims due deliveries with
+// `FOR UPDATE SKIP LOCKED` and pushes their next attempt out by a lease, so a
+// message is sent by one instance at a time and picked up again if that
+// instance dies before recording the outcome.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use crate::domain::notifications::{
+    ChannelPreference, DeliveryStatus, Notification, NotificationDelivery,
+    NotificationRepository, NotificationSettings,
+};
+use crate::shared::errors::{AppError, AppResult};
+
+const SETTINGS_COLUMNS: &str = "user_id, locale, whatsapp_number, updated_at";
+
+const NOTIFICATION_COLUMNS: &str =
+    "id, user_id, event, title, body, link, license_id, read_at, created_at";
+
+const DELIVERY_COLUMNS: &str = "id, user_id, event, channel, recipient, subject, body, status, \
+    attempts, next_attempt_at, last_error, created_at, sent_at";
+
+fn parse<T: std::str::FromStr<Err = String>>(row: &PgRow, column: &str) -> Result<T, AppError> {
+    let value: String = row.try_get(column)?;
+    value.parse().map_err(AppError::InternalError)
+}
+
+fn row_to_settings(row: &PgRow) -> Result<NotificationSettings, AppError> {
+    Ok(NotificationSettings {
+        user_id: row.try_get("user_id")?,
+        locale: parse(row, "locale")?,
+        whatsapp_number: row.try_get("whatsapp_number")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+fn row_to_notification(row: &PgRow) -> Result<Notification, AppError> {
+    Ok(Notification {
+        id: row.try_get("id")?,
+        user_id: row.try_get("user_id")?,
+        event: parse(row, "event")?,
+        title: row.try_get("title")?,
+        body: row.try_get("body")?,
+        link: row.try_get("link")?,
+        license_id: row.try_get("license_id")?,
+        read_at: row.try_get("read_at")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+fn row_to_delivery(row: &PgRow) -> Result<NotificationDelivery, AppError> {
+    Ok(NotificationDelivery {
+        id: row.try_get("id")?,
+        user_id: row.try_get("user_id")?,
+        event: parse(row, "event")?,
+        channel: parse(row, "channel")?,
+        recipient: row.try_get("recipient")?,
+        subject: row.try_get("subject")?,
+        body: row.try_get("body")?,
+        status: parse(row, "status")?,
+        attempts: row.try_get("attempts")?,
+        next_attempt_at: row.try_get("next_attempt_at")?,
+        last_error: row.try_get("last_error")?,
+        created_at: row.try_get("created_at")?,
+        sent_at: row.try_get("sent_at")?,
+    })
+}
+
+#[derive(Clone)]
+pub struct PostgresNotificationRepository {
+    pool: PgPool,
+}
+
+impl PostgresNotificationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl NotificationRepository for PostgresNotificationRepository {
+    async fn settings(&self, user_id: Uuid) -> AppResult<NotificationSettings> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM notification_settings WHERE user_id = $1",
+            SETTINGS_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(row) => row_to_settings(&row),
+            None => Ok(NotificationSettings::defaults(user_id)),
+        }
+    }
+
+    async fn save_settings(&self, settings: &NotificationSettings) -> AppResult<NotificationSettings> {
+        let row = sqlx::query(&format!(
+            "INSERT INTO notification_settings ({cols}) VALUES ($1, $2, $3, NOW()) \
+             ON CONFLICT (user_id) DO UPDATE SET locale = EXCLUDED.locale, \
+                 whatsapp_number = EXCLUDED.whatsapp_number, updated_at = NOW() \
+             RETURNING {cols}",
+            cols = SETTINGS_COLUMNS
+        ))
+        .bind(settings.user_id)
+        .bind(settings.locale.to_string())
+        .bind(&settings.whatsapp_number)
+        .fetch_one(&self.pool)
+        .await?;
+
+        row_to_settings(&row)
+    }
+
+    async fn preferences(&self, user_id: Uuid) -> AppResult<Vec<ChannelPreference>> {
+        let rows = sqlx::query(
+            "SELECT event, channel, enabled FROM notification_preferences \
+             WHERE user_id = $1 ORDER BY event, channel",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(ChannelPreference {
+                    event: parse(row, "event")?,
+                    channel: parse(row, "channel")?,
+                    enabled: row.try_get("enabled")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn set_preference(&self, user_id: Uuid, preference: &ChannelPreference) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO notification_preferences (user_id, event, channel, enabled) \
+             VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (user_id, event, channel) DO UPDATE SET enabled = EXCLUDED.enabled",
+        )
+        .bind(user_id)
+        .bind(preference.event.to_string())
+        .bind(preference.channel.to_string())
+        .bind(preference.enabled)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn create_notification(&self, notification: &Notification) -> AppResult<Notification> {
+        let row = sqlx::query(&format!(
+            "INSERT INTO notifications ({cols}) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) \
+             RETURNING {cols}",
+            cols = NOTIFICATION_COLUMNS
+        ))
+        .bind(notification.id)
+        .bind(notification.user_id)
+        .bind(notification.event.to_string())
+        .bind(&notification.title)
+        .bind(&notification.body)
+        .bind(&notification.link)
+        .bind(notification.license_id)
+        .bind(notification.read_at)
+        .bind(notification.created_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        row_to_notification(&row)
+    }
+
+    async fn notifications(
+        &self,
+        user_id: Uuid,
+        unread_only: bool,
+        limit: i64,
+    ) -> AppResult<Vec<Notification>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM notifications \
+             WHERE user_id = $1 AND (NOT $2 OR read_at IS NULL) \
+             ORDER BY created_at DESC, id DESC LIMIT $3",
+            NOTIFICATION_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(unread_only)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_notification).collect()
+    }
+
+    async fn unread_count(&self, user_id: Uuid) -> AppResult<i64> {
+        let count = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND read_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn set_read(
+        &self,
+        user_id: Uuid,
+        notification_id: Uuid,
+        read: bool,
+    ) -> AppResult<Option<Notification>> {
+        let row = sqlx::query(&format!(
+            "UPDATE notifications \
+             SET read_at = CASE WHEN $3 THEN COALESCE(read_at, NOW()) END \
+             WHERE id = $1 AND user_id = $2 RETURNING {}",
+            NOTIFICATION_COLUMNS
+        ))
+        .bind(notification_id)
+        .bind(user_id)
+        .bind(read)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(row_to_notification).transpose()
+    }
+
+    async fn mark_all_read(&self, user_id: Uuid) -> AppResult<u64> {
+        let result = sqlx::query(
+            "UPDATE notifications SET read_at = NOW() WHERE user_id = $1 AND read_at IS NULL",
+        )
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn enqueue_delivery(&self, delivery: &NotificationDelivery) -> AppResult<()> {
+        sqlx::query(&format!(
+            "INSERT INTO notification_deliveries ({}) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)",
+            DELIVERY_COLUMNS
+        ))
+        .bind(delivery.id)
+        .bind(delivery.user_id)
+        .bind(delivery.event.to_string())
+        .bind(delivery.channel.to_string())
+        .bind(&delivery.recipient)
+        .bind(&delivery.subject)
+        .bind(&delivery.body)
+        .bind(delivery.status.to_string())
+        .bind(delivery.attempts)
+        .bind(delivery.next_attempt_at)
+        .bind(&delivery.last_error)
+        .bind(delivery.created_at)
+        .bind(delivery.sent_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn claim_due_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+        limit: i64,
+    ) -> AppResult<Vec<NotificationDelivery>> {
+        let rows = sqlx::query(&format!(
+            "UPDATE notification_deliveries SET next_attempt_at = $2 \
+             WHERE id IN ( \
+                 SELECT id FROM notification_deliveries \
+                 WHERE status = 'pending' AND next_attempt_at <= $1 \
+                 ORDER BY next_attempt_at, created_at \
+                 LIMIT $3 FOR UPDATE SKIP LOCKED) \
+             RETURNING {}",
+            DELIVERY_COLUMNS
+        ))
+        .bind(now)
+        .bind(lease_until)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut deliveries = rows
+            .iter()
+            .map(row_to_delivery)
+            .collect::<AppResult<Vec<_>>>()?;
+        deliveries.sort_by_key(|d| (d.created_at, d.id));
+        Ok(deliveries)
+    }
+
+    async fn record_attempt(&self, delivery: &NotificationDelivery) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE notification_deliveries \
+             SET status = $2, attempts = $3, next_attempt_at = $4, last_error = $5, sent_at = $6 \
+             WHERE id = $1",
+        )
+        .bind(delivery.id)
+        .bind(delivery.status.to_string())
+        .bind(delivery.attempts)
+        .bind(delivery.next_attempt_at)
+        .bind(&delivery.last_error)
+        .bind(delivery.sent_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn deliveries(
+        &self,
+        status: Option<DeliveryStatus>,
+        limit: i64,
+    ) -> AppResult<Vec<NotificationDelivery>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM notification_deliveries \
+             WHERE $1::text IS NULL OR status = $1 \
+             ORDER BY created_at DESC, id DESC LIMIT $2",
+            DELIVERY_COLUMNS
+        ))
+        .bind(status.map(|s| s.to_string()))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_delivery).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::licenses::LicenseType;
+    use crate::domain::notifications::{Locale, NotificationChannel, NotificationEvent, RetryPolicy};
+    use crate::infrastructure::repositories::testing::{in_memory, Fixture, TestDatabase};
+    use crate::infrastructure::repositories::{
+        InMemoryNotificationRepository, LicenseRepository, PostgresLicenseRepositoryImpl,
+    };
+
+    /// The inbox and its read state, notification preferences and the
+    /// delivery queue with its retries
+    async fn run_notification_scenario(
+        repo: &dyn LicenseRepository,
+        notifications: &dyn NotificationRepository,
+        fx: &Fixture,
+    ) {
+        let license = repo
+            .create_license(&fx.license(LicenseType::Siup, fx.company_id, fx.owner_id, "SIUP", 0))
+            .await
+            .unwrap();
+
+        // Settings fall back to the defaults and are upserted
+        let defaults = notifications.settings(fx.owner_id).await.unwrap();
+        assert_eq!((defaults.locale, defaults.whatsapp_number), (Locale::Id, None));
+        let saved = notifications
+            .save_settings(&NotificationSettings {
+                locale: Locale::En,
+                whatsapp_number: Some("+6281234567890".to_string()),
+                ..defaults
+            })
+            .await
+            .unwrap();
+        assert_eq!(notifications.settings(fx.owner_id).await.unwrap(), saved);
+        let saved = notifications
+            .save_settings(&NotificationSettings {
+                whatsapp_number: None,
+                ..saved
+            })
+            .await
+            .unwrap();
+        assert_eq!(saved.locale, Locale::En);
+        assert_eq!(notifications.settings(fx.owner_id).await.unwrap().whatsapp_number, None);
+
+        // Only the choices made are listed, by event and channel name
+        let choice = |event, channel, enabled| ChannelPreference {
+            event,
+            channel,
+            enabled,
+        };
+        for preference in [
+            choice(NotificationEvent::LicenseRejected, NotificationChannel::WhatsApp, true),
+            choice(NotificationEvent::LicenseApproved, NotificationChannel::Email, true),
+            choice(NotificationEvent::LicenseApproved, NotificationChannel::Email, false),
+        ] {
+            notifications.set_preference(fx.owner_id, &preference).await.unwrap();
+        }
+        assert_eq!(
+            notifications.preferences(fx.owner_id).await.unwrap(),
+            vec![
+                choice(NotificationEvent::LicenseApproved, NotificationChannel::Email, false),
+                choice(NotificationEvent::LicenseRejected, NotificationChannel::WhatsApp, true),
+            ]
+        );
+        assert!(notifications.preferences(fx.other_user_id).await.unwrap().is_empty());
+
+        // The inbox, newest first
+        let notification = |user_id, event, seconds| Notification {
+            id: Uuid::new_v4(),
+            user_id,
+            event,
+            title: "Izin".to_string(),
+            body: "Isi".to_string(),
+            link: Some(format!("https://app.test/licenses/{}", license.id)),
+            license_id: Some(license.id),
+            read_at: None,
+            created_at: fx.at(seconds),
+        };
+        let approved = notification(fx.owner_id, NotificationEvent::LicenseApproved, 10);
+        let suspended = notification(fx.owner_id, NotificationEvent::LicenseSuspended, 20);
+        let renewed = notification(fx.owner_id, NotificationEvent::LicenseRenewed, 30);
+        let others = notification(fx.other_user_id, NotificationEvent::LicenseRejected, 40);
+        for entry in [&approved, &suspended, &renewed, &others] {
+            assert_eq!(&notifications.create_notification(entry).await.unwrap(), entry);
+        }
+        assert_eq!(
+            notifications.notifications(fx.owner_id, false, 10).await.unwrap(),
+            vec![renewed.clone(), suspended.clone(), approved.clone()]
+        );
+        assert_eq!(
+            notifications.notifications(fx.owner_id, false, 1).await.unwrap(),
+            vec![renewed.clone()]
+        );
+        assert_eq!(notifications.unread_count(fx.owner_id).await.unwrap(), 3);
+
+        // Reading keeps the first read time; other users cannot mark it
+        let read = notifications
+            .set_read(fx.owner_id, suspended.id, true)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(read.read_at.is_some());
+        assert_eq!(
+            notifications.set_read(fx.owner_id, suspended.id, true).await.unwrap(),
+            Some(read.clone())
+        );
+        assert_eq!(notifications.set_read(fx.other_user_id, suspended.id, true).await.unwrap(), None);
+        assert_eq!(
+            notifications.notifications(fx.owner_id, true, 10).await.unwrap(),
+            vec![renewed.clone(), approved.clone()]
+        );
+        assert_eq!(
+            notifications.set_read(fx.owner_id, suspended.id, false).await.unwrap(),
+            Some(suspended.clone())
+        );
+        assert_eq!(notifications.mark_all_read(fx.owner_id).await.unwrap(), 3);
+        assert_eq!(notifications.mark_all_read(fx.owner_id).await.unwrap(), 0);
+        assert_eq!(notifications.unread_count(fx.owner_id).await.unwrap(), 0);
+        assert_eq!(notifications.unread_count(fx.other_user_id).await.unwrap(), 1);
+
+        // Due deliveries are claimed oldest first and leased until recorded
+        let delivery = |channel, next_attempt: i64, created: i64| NotificationDelivery {
+            next_attempt_at: fx.at(next_attempt),
+            created_at: fx.at(created),
+            ..NotificationDelivery::new(
+                fx.owner_id,
+                NotificationEvent::LicenseApproved,
+                channel,
+                "budi@conformance.test".to_string(),
+                "Izin disetujui".to_string(),
+                "Halo".to_string(),
+            )
+        };
+        let first = delivery(NotificationChannel::Email, 10, 1);
+        let second = delivery(NotificationChannel::WhatsApp, 20, 2);
+        let later = delivery(NotificationChannel::Email, 86_400, 3);
+        for entry in [&first, &second, &later] {
+            notifications.enqueue_delivery(entry).await.unwrap();
+        }
+        let lease = fx.at(330);
+        let claimed = notifications.claim_due_deliveries(fx.at(30), lease, 1).await.unwrap();
+        assert_eq!(
+            claimed,
+            vec![NotificationDelivery {
+                next_attempt_at: lease,
+                ..first.clone()
+            }]
+        );
+        let claimed = notifications.claim_due_deliveries(fx.at(30), lease, 10).await.unwrap();
+        assert_eq!(claimed.iter().map(|d| d.id).collect::<Vec<_>>(), vec![second.id]);
+        assert!(notifications
+            .claim_due_deliveries(fx.at(30), lease, 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let mut sent = NotificationDelivery {
+            next_attempt_at: lease,
+            ..first.clone()
+        };
+        sent.mark_sent(fx.at(31));
+        notifications.record_attempt(&sent).await.unwrap();
+        let mut retried = claimed[0].clone();
+        retried.mark_failed("timeout".to_string(), fx.at(31), &RetryPolicy::default());
+        notifications.record_attempt(&retried).await.unwrap();
+        assert_eq!(retried.next_attempt_at, fx.at(61));
+
+        // The retry is due after its backoff; the sent one never again
+        let claimed = notifications
+            .claim_due_deliveries(fx.at(400), fx.at(700), 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            claimed,
+            vec![NotificationDelivery {
+                next_attempt_at: fx.at(700),
+                ..retried
+            }]
+        );
+        assert_eq!(
+            notifications.deliveries(Some(DeliveryStatus::Sent), 10).await.unwrap(),
+            vec![sent]
+        );
+        assert_eq!(
+            notifications
+                .deliveries(None, 10)
+                .await
+                .unwrap()
+                .iter()
+                .map(|d| d.id)
+                .collect::<Vec<_>>(),
+            vec![later.id, second.id, first.id]
+        );
+    }
+
+    #[tokio::test]
+    async fn in_memory_notifications_conform() {
+        let fx = Fixture::new();
+        run_notification_scenario(
+            &in_memory(&fx),
+            &InMemoryNotificationRepository::new(),
+            &fx,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn postgres_notifications_conform() {
+        let fx = Fixture::new();
+        let db = TestDatabase::seeded(&fx).await;
+        run_notification_scenario(
+            &PostgresLicenseRepositoryImpl::new(db.pool.clone()),
+            &PostgresNotificationRepository::new(db.pool.clone()),
+            &fx,
+        )
+        .await;
+        db.destroy().await;
+    }
+}
@@ -2,14 +2,18 @@
 // Follows Hexagonal Architecture pattern for data access
 
 use async_trait::async_trait;
+use sea_query::{extension::postgres::PgExpr, Alias, Cond, Expr, Query, SelectStatement};
 use sqlx::PgPool;
 use tracing::{error, info, instrument};
 use uuid::Uuid;
 
 use crate::domain::entities::{User, UserRole, UserStatus};
+use crate::domain::filters::{UserFilter, UserSortField};
 use crate::domain::repositories::UserRepository;
 use crate::domain::value_objects::{Email, UserId};
+use crate::infrastructure::database::list_query::{contains_pattern, fetch_rows};
 use crate::shared::errors::{AppError, AppResult};
+use crate::shared::query::{ListQuery, Page};
 
 /// PostgreSQL implementation of UserRepository
 pub struct PostgresUserRepository {
@@ -21,6 +25,55 @@ impl PostgresUserRepository {
         Self { pool }
     }
 
+    /// Filtered select over users, without ordering or paging
+    fn list_select(filter: &UserFilter) -> SelectStatement {
+        let mut cond = Cond::all();
+
+        if let Some(role) = &filter.role {
+            cond = cond.add(Expr::col(Alias::new("role")).eq(role.to_string()));
+        }
+        if let Some(status) = &filter.status {
+            cond = cond.add(Expr::col(Alias::new("status")).eq(status.to_string()));
+        }
+        if let Some(from) = filter.created_from {
+            cond = cond.add(Expr::col(Alias::new("created_at")).gte(from));
+        }
+        if let Some(to) = filter.created_to {
+            cond = cond.add(Expr::col(Alias::new("created_at")).lte(to));
+        }
+        if let Some(search) = filter.search.as_deref().filter(|s| !s.trim().is_empty()) {
+            let pattern = contains_pattern(search.trim());
+            cond = cond.add(
+                Cond::any()
+                    .add(Expr::col(Alias::new("email")).ilike(pattern.as_str()))
+                    .add(Expr::col(Alias::new("full_name")).ilike(pattern.as_str())),
+            );
+        }
+
+        Query::select()
+            .columns(
+                [
+                    "id",
+                    "email",
+                    "password_hash",
+                    "full_name",
+                    "role",
+                    "status",
+                    "email_verified",
+                    "created_at",
+                    "updated_at",
+                    "password_reset_required",
+                    "password_reset_token_hash",
+                    "password_reset_expires_at",
+                    "sessions_revoked_at",
+                ]
+                .map(Alias::new),
+            )
+            .from(Alias::new("users"))
+            .cond_where(cond)
+            .to_owned()
+    }
+
     fn row_to_user(&self, row: &sqlx::postgres::PgRow) -> AppResult<User> {
         use sqlx::Row;
 
@@ -95,6 +148,10 @@ impl PostgresUserRepository {
             updated_at: row
                 .try_get("updated_at")
                 .map_err(|e| AppError::Validation(format!("Invalid updated_at: {}", e)))?,
+            password_reset_required: row.try_get("password_reset_required")?,
+            password_reset_token_hash: row.try_get("password_reset_token_hash")?,
+            password_reset_expires_at: row.try_get("password_reset_expires_at")?,
+            sessions_revoked_at: row.try_get("sessions_revoked_at")?,
         })
     }
 }
@@ -108,7 +165,9 @@ impl UserRepository for PostgresUserRepository {
         let result = sqlx::query(
             r#"
             SELECT id, email, password_hash, full_name, role, status, 
-                   email_verified, created_at, updated_at
+                   email_verified, created_at, updated_at,
+                   password_reset_required, password_reset_token_hash,
+                   password_reset_expires_at, sessions_revoked_at
             FROM users 
             WHERE id = $1
             "#,
@@ -141,7 +200,9 @@ impl UserRepository for PostgresUserRepository {
         let result = sqlx::query(
             r#"
             SELECT id, email, password_hash, full_name, role, status, 
-                   email_verified, created_at, updated_at
+                   email_verified, created_at, updated_at,
+                   password_reset_required, password_reset_token_hash,
+                   password_reset_expires_at, sessions_revoked_at
             FROM users 
             WHERE email = $1
             "#,
@@ -189,8 +250,9 @@ impl UserRepository for PostgresUserRepository {
 
         sqlx::query(
             r#"
-            INSERT INTO users (id, email, password_hash, full_name, role, status, email_verified, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            INSERT INTO users (id, email, password_hash, full_name, role, status, email_verified, created_at, updated_at,
+                               password_reset_required, password_reset_token_hash, password_reset_expires_at, sessions_revoked_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
             ON CONFLICT (id) 
             DO UPDATE SET 
                 email = EXCLUDED.email,
@@ -199,7 +261,11 @@ impl UserRepository for PostgresUserRepository {
                 role = EXCLUDED.role,
                 status = EXCLUDED.status,
                 email_verified = EXCLUDED.email_verified,
-                updated_at = EXCLUDED.updated_at
+                updated_at = EXCLUDED.updated_at,
+                password_reset_required = EXCLUDED.password_reset_required,
+                password_reset_token_hash = EXCLUDED.password_reset_token_hash,
+                password_reset_expires_at = EXCLUDED.password_reset_expires_at,
+                sessions_revoked_at = EXCLUDED.sessions_revoked_at
             "#
         )
         .bind(user.id.as_uuid())
@@ -211,6 +277,10 @@ impl UserRepository for PostgresUserRepository {
         .bind(user.email_verified_at.is_some())
         .bind(user.created_at)
         .bind(user.updated_at)
+        .bind(user.password_reset_required)
+        .bind(&user.password_reset_token_hash)
+        .bind(user.password_reset_expires_at)
+        .bind(user.sessions_revoked_at)
         .execute(&self.pool)
         .await
         .map_err(|e| {
@@ -252,7 +322,9 @@ impl UserRepository for PostgresUserRepository {
                 sqlx::query(
                     r#"
                     SELECT id, email, password_hash, full_name, role, status, 
-                           email_verified, created_at, updated_at
+                           email_verified, created_at, updated_at,
+                           password_reset_required, password_reset_token_hash,
+                           password_reset_expires_at, sessions_revoked_at
                     FROM users
                     ORDER BY created_at DESC
                     LIMIT $1 OFFSET $2
@@ -265,7 +337,9 @@ impl UserRepository for PostgresUserRepository {
                 sqlx::query(
                     r#"
                     SELECT id, email, password_hash, full_name, role, status, 
-                           email_verified, created_at, updated_at
+                           email_verified, created_at, updated_at,
+                           password_reset_required, password_reset_token_hash,
+                           password_reset_expires_at, sessions_revoked_at
                     FROM users
                     ORDER BY created_at DESC
                     LIMIT $1
@@ -277,7 +351,9 @@ impl UserRepository for PostgresUserRepository {
                 sqlx::query(
                     r#"
                     SELECT id, email, password_hash, full_name, role, status, 
-                           email_verified, created_at, updated_at
+                           email_verified, created_at, updated_at,
+                           password_reset_required, password_reset_token_hash,
+                           password_reset_expires_at, sessions_revoked_at
                     FROM users
                     ORDER BY created_at DESC
                     "#
@@ -339,7 +415,9 @@ impl UserRepository for PostgresUserRepository {
                 sqlx::query(
                     r#"
                     SELECT id, email, password_hash, full_name, role, status, 
-                           email_verified, created_at, updated_at
+                           email_verified, created_at, updated_at,
+                           password_reset_required, password_reset_token_hash,
+                           password_reset_expires_at, sessions_revoked_at
                     FROM users
                     WHERE email ILIKE $1 OR full_name ILIKE $1
                     ORDER BY created_at DESC
@@ -354,7 +432,9 @@ impl UserRepository for PostgresUserRepository {
                 sqlx::query(
                     r#"
                     SELECT id, email, password_hash, full_name, role, status, 
-                           email_verified, created_at, updated_at
+                           email_verified, created_at, updated_at,
+                           password_reset_required, password_reset_token_hash,
+                           password_reset_expires_at, sessions_revoked_at
                     FROM users
                     WHERE email ILIKE $1 OR full_name ILIKE $1
                     ORDER BY created_at DESC
@@ -368,7 +448,9 @@ impl UserRepository for PostgresUserRepository {
                 sqlx::query(
                     r#"
                     SELECT id, email, password_hash, full_name, role, status, 
-                           email_verified, created_at, updated_at
+                           email_verified, created_at, updated_at,
+                           password_reset_required, password_reset_token_hash,
+                           password_reset_expires_at, sessions_revoked_at
                     FROM users
                     WHERE email ILIKE $1 OR full_name ILIKE $1
                     ORDER BY created_at DESC
@@ -400,4 +482,23 @@ impl UserRepository for PostgresUserRepository {
         info!("Found {} users matching query: {}", users.len(), query);
         Ok(users)
     }
+
+    #[instrument(skip(self, query), fields(sort = %query.sort, limit = query.limit))]
+    async fn list(&self, query: &ListQuery<UserFilter, UserSortField>) -> AppResult<Page<User>> {
+        let mut conn = self.pool.acquire().await?;
+        let (rows, total) = fetch_rows(&mut conn, &Self::list_select(&query.filter), query)
+            .await
+            .map_err(|e| {
+                error!("Database error listing users: {}", e);
+                AppError::Database(e)
+            })?;
+
+        let users = rows
+            .iter()
+            .map(|row| self.row_to_user(row))
+            .collect::<AppResult<Vec<_>>>()?;
+
+        info!("Listed {} users", users.len());
+        Ok(Page::from_rows(users, query, total))
+    }
 }
@@ -0,0 +1,368 @@
+// PostgreSQL implementation of the bank reconciliation repository
+// Adjustments and session completion each run in one database transaction so the
+// books, the account balance and the statement lines never disagree.
+
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use crate::domain::finance::Transaction;
+use crate::domain::reconciliation::{
+    ReconciliationRepository, ReconciliationSession, ReconciliationStatus, StatementLine,
+};
+use crate::infrastructure::repositories::finance_repository::{
+    adjust_account_balance, insert_transaction, row_to_transaction, TRANSACTION_COLUMNS,
+};
+use crate::shared::errors::AppError;
+
+pub(crate) const SESSION_COLUMNS: &str = "id, company_id, account_id, statement_start_date, statement_end_date, \
+    opening_balance, closing_balance, status, created_by, created_at, updated_at, completed_by, completed_at";
+
+pub(crate) const LINE_COLUMNS: &str =
+    "id, session_id, line_date, amount, description, reference, transaction_id, match_type, matched_at";
+
+fn parse_column<T: std::str::FromStr<Err = String>>(row: &PgRow, column: &str) -> Result<T, AppError> {
+    let value: String = row.try_get(column)?;
+    value.parse().map_err(AppError::InternalError)
+}
+
+fn row_to_session(row: &PgRow) -> Result<ReconciliationSession, AppError> {
+    Ok(ReconciliationSession {
+        id: row.try_get("id")?,
+        company_id: row.try_get("company_id")?,
+        account_id: row.try_get("account_id")?,
+        statement_start_date: row.try_get("statement_start_date")?,
+        statement_end_date: row.try_get("statement_end_date")?,
+        opening_balance: row.try_get("opening_balance")?,
+        closing_balance: row.try_get("closing_balance")?,
+        status: parse_column(row, "status")?,
+        created_by: row.try_get("created_by")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+        completed_by: row.try_get("completed_by")?,
+        completed_at: row.try_get("completed_at")?,
+    })
+}
+
+fn row_to_line(row: &PgRow) -> Result<StatementLine, AppError> {
+    let match_type: Option<String> = row.try_get("match_type")?;
+
+    Ok(StatementLine {
+        id: row.try_get("id")?,
+        session_id: row.try_get("session_id")?,
+        line_date: row.try_get("line_date")?,
+        amount: row.try_get("amount")?,
+        description: row.try_get("description")?,
+        reference: row.try_get("reference")?,
+        transaction_id: row.try_get("transaction_id")?,
+        match_type: match_type
+            .map(|m| m.parse())
+            .transpose()
+            .map_err(AppError::InternalError)?,
+        matched_at: row.try_get("matched_at")?,
+    })
+}
+
+/// Writes the match columns of a line
+async fn save_match<'e, E>(executor: E, line: &StatementLine) -> Result<(), AppError>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let result = sqlx::query(
+        r#"
+        UPDATE reconciliation_lines
+        SET transaction_id = $1, match_type = $2, matched_at = $3
+        WHERE id = $4 AND session_id = $5
+        "#,
+    )
+    .bind(line.transaction_id)
+    .bind(line.match_type.map(|m| m.to_string()))
+    .bind(line.matched_at)
+    .bind(line.id)
+    .bind(line.session_id)
+    .execute(executor)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => AppError::Conflict(
+            "Transaction is already matched to another statement line".to_string(),
+        ),
+        other => AppError::Database(other),
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Statement line not found".to_string()));
+    }
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct PostgresReconciliationRepository {
+    pool: PgPool,
+}
+
+impl PostgresReconciliationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl ReconciliationRepository for PostgresReconciliationRepository {
+    async fn create_session(&self, session: &ReconciliationSession) -> Result<(), AppError> {
+        sqlx::query(&format!(
+            "INSERT INTO reconciliation_sessions ({}) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)",
+            SESSION_COLUMNS
+        ))
+        .bind(session.id)
+        .bind(session.company_id)
+        .bind(session.account_id)
+        .bind(session.statement_start_date)
+        .bind(session.statement_end_date)
+        .bind(session.opening_balance)
+        .bind(session.closing_balance)
+        .bind(session.status.to_string())
+        .bind(session.created_by)
+        .bind(session.created_at)
+        .bind(session.updated_at)
+        .bind(session.completed_by)
+        .bind(session.completed_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => AppError::Conflict(
+                "The account already has an open reconciliation".to_string(),
+            ),
+            other => AppError::Database(other),
+        })?;
+
+        Ok(())
+    }
+
+    async fn find_session(
+        &self,
+        company_id: Uuid,
+        id: Uuid,
+    ) -> Result<Option<ReconciliationSession>, AppError> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM reconciliation_sessions WHERE id = $1 AND company_id = $2",
+            SESSION_COLUMNS
+        ))
+        .bind(id)
+        .bind(company_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(row_to_session).transpose()
+    }
+
+    async fn update_session(&self, session: &ReconciliationSession) -> Result<(), AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE reconciliation_sessions
+            SET closing_balance = $1, updated_at = $2
+            WHERE id = $3 AND status = 'open'
+            "#,
+        )
+        .bind(session.closing_balance)
+        .bind(session.updated_at)
+        .bind(session.id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::Conflict("Reconciliation is no longer open".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn delete_session(&self, id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM reconciliation_sessions WHERE id = $1 AND status = 'open'")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::Conflict("Reconciliation is no longer open".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn open_session(&self, account_id: Uuid) -> Result<Option<ReconciliationSession>, AppError> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM reconciliation_sessions WHERE account_id = $1 AND status = 'open'",
+            SESSION_COLUMNS
+        ))
+        .bind(account_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(row_to_session).transpose()
+    }
+
+    async fn last_completed_session(
+        &self,
+        account_id: Uuid,
+    ) -> Result<Option<ReconciliationSession>, AppError> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM reconciliation_sessions \
+             WHERE account_id = $1 AND status = 'completed' \
+             ORDER BY statement_end_date DESC LIMIT 1",
+            SESSION_COLUMNS
+        ))
+        .bind(account_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(row_to_session).transpose()
+    }
+
+    async fn add_lines(&self, lines: &[StatementLine]) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        for line in lines {
+            sqlx::query(&format!(
+                "INSERT INTO reconciliation_lines ({}) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+                LINE_COLUMNS
+            ))
+            .bind(line.id)
+            .bind(line.session_id)
+            .bind(line.line_date)
+            .bind(line.amount)
+            .bind(&line.description)
+            .bind(&line.reference)
+            .bind(line.transaction_id)
+            .bind(line.match_type.map(|m| m.to_string()))
+            .bind(line.matched_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn list_lines(&self, session_id: Uuid) -> Result<Vec<StatementLine>, AppError> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM reconciliation_lines WHERE session_id = $1 ORDER BY line_date, id",
+            LINE_COLUMNS
+        ))
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_line).collect()
+    }
+
+    async fn delete_line(&self, session_id: Uuid, line_id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM reconciliation_lines WHERE id = $1 AND session_id = $2")
+            .bind(line_id)
+            .bind(session_id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Statement line not found".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn save_matches(&self, lines: &[StatementLine]) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        for line in lines {
+            save_match(&mut *tx, line).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn candidate_transactions(
+        &self,
+        session: &ReconciliationSession,
+    ) -> Result<Vec<Transaction>, AppError> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM financial_transactions \
+             WHERE account_id = $1 AND status = 'completed' AND transaction_date < $2 \
+               AND (reconciled_at IS NULL OR reconciliation_id = $3) \
+             ORDER BY transaction_date, created_at",
+            TRANSACTION_COLUMNS.join(", ")
+        ))
+        .bind(session.account_id)
+        .bind(session.cutoff())
+        .bind(session.id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_transaction).collect()
+    }
+
+    async fn record_adjustment(
+        &self,
+        transaction: &Transaction,
+        line: &StatementLine,
+    ) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        insert_transaction(&mut *tx, transaction, None).await?;
+        adjust_account_balance(&mut *tx, transaction.account_id, transaction.signed_amount()).await?;
+        save_match(&mut *tx, line).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn complete_session(
+        &self,
+        session: &ReconciliationSession,
+        transaction_ids: &[Uuid],
+    ) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        let status: Option<String> =
+            sqlx::query_scalar("SELECT status FROM reconciliation_sessions WHERE id = $1 FOR UPDATE")
+                .bind(session.id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if status.as_deref() != Some(ReconciliationStatus::Open.to_string().as_str()) {
+            return Err(AppError::Conflict("Reconciliation is no longer open".to_string()));
+        }
+
+        let locked = sqlx::query(
+            r#"
+            UPDATE financial_transactions
+            SET reconciliation_id = $1, reconciled_at = $2
+            WHERE id = ANY($3) AND reconciled_at IS NULL
+            "#,
+        )
+        .bind(session.id)
+        .bind(session.completed_at)
+        .bind(transaction_ids)
+        .execute(&mut *tx)
+        .await?;
+
+        if locked.rows_affected() != transaction_ids.len() as u64 {
+            return Err(AppError::Conflict(
+                "Some matched transactions were reconciled elsewhere, reload the reconciliation"
+                    .to_string(),
+            ));
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE reconciliation_sessions
+            SET status = $1, completed_by = $2, completed_at = $3, updated_at = $4
+            WHERE id = $5
+            "#,
+        )
+        .bind(session.status.to_string())
+        .bind(session.completed_by)
+        .bind(session.completed_at)
+        .bind(session.updated_at)
+        .bind(session.id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
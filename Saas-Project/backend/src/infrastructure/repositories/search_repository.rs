@@ -0,0 +1,152 @@
+// PostgreSQL implementation of unified search
+// Relies on the generated search_vector columns and trigram indexes from the
+// full_text_search migration
+
+use async_trait::async_trait;
+use sqlx::{PgPool, Row};
+use tracing::instrument;
+
+use crate::domain::search::{SearchCursor, SearchHit, SearchPage, SearchQuery, SearchRepository};
+use crate::infrastructure::database::list_query::contains_pattern;
+use crate::shared::errors::{AppError, AppResult};
+
+// Companies and licenses also match on NIB / license number substrings so that
+// partially typed registration numbers still find something. Reviewer notes
+// on documents are internal: only admin searches ($4 is NULL) match, rank and
+// highlight them. Only the page being returned is passed through ts_headline,
+// which is the expensive part.
+const SEARCH_QUERY: &str = r#"
+    WITH q AS (
+        SELECT websearch_to_tsquery('public.id_search', $1) AS tsq, $2::text AS pattern
+    ),
+    hits AS (
+        SELECT 'company'::text AS hit_type, c.id, c.company_name AS title,
+               concat_ws(' · ', c.industry_sector, c.address_city) AS subtitle,
+               concat_ws(' ', c.company_name, c.industry_sector, c.description) AS body,
+               GREATEST(
+                   ts_rank_cd(c.search_vector, q.tsq),
+                   CASE WHEN c.nib ILIKE q.pattern THEN 0.5 + similarity(c.nib, $1) ELSE 0 END
+               )::real AS rank,
+               c.id AS company_id, NULL::uuid AS license_id
+        FROM companies c, q
+        WHERE 'company' = ANY($3)
+          AND (c.search_vector @@ q.tsq OR c.nib ILIKE q.pattern)
+          AND ($4::uuid IS NULL OR c.owner_id = $4)
+
+        UNION ALL
+
+        SELECT 'license', l.id, l.title,
+               concat_ws(' · ', l.license_number, l.license_type::text, l.application_status::text),
+               concat_ws(' ', l.title, l.description),
+               GREATEST(
+                   ts_rank_cd(l.search_vector, q.tsq),
+                   CASE WHEN l.license_number ILIKE q.pattern THEN 0.5 + similarity(l.license_number, $1) ELSE 0 END
+               )::real,
+               l.company_id, l.id
+        FROM licenses l, q
+        WHERE 'license' = ANY($3)
+          AND (l.search_vector @@ q.tsq OR l.license_number ILIKE q.pattern)
+          AND ($4::uuid IS NULL OR l.user_id = $4
+               OR EXISTS (SELECT 1 FROM companies c WHERE c.id = l.company_id AND c.owner_id = $4))
+
+        UNION ALL
+
+        SELECT 'document', d.id, d.original_file_name,
+               concat_ws(' · ', d.document_type::text, l.title),
+               concat_ws(' ', regexp_replace(d.original_file_name, '[._-]+', ' ', 'g'),
+                         CASE WHEN $4::uuid IS NULL THEN d.notes END),
+               ts_rank_cd(
+                   CASE WHEN $4::uuid IS NULL THEN d.search_vector || d.notes_search_vector
+                        ELSE d.search_vector END,
+                   q.tsq
+               )::real,
+               l.company_id, l.id
+        FROM license_documents d
+        JOIN licenses l ON l.id = d.license_id, q
+        WHERE 'document' = ANY($3)
+          AND (d.search_vector @@ q.tsq OR ($4::uuid IS NULL AND d.notes_search_vector @@ q.tsq))
+          AND ($4::uuid IS NULL OR l.user_id = $4
+               OR EXISTS (SELECT 1 FROM companies c WHERE c.id = l.company_id AND c.owner_id = $4))
+    ),
+    page AS (
+        SELECT * FROM hits
+        WHERE $5::real IS NULL OR (rank, id) < ($5::real, $6::uuid)
+        ORDER BY rank DESC, id DESC
+        LIMIT $7
+    )
+    SELECT p.hit_type, p.id, p.title, p.subtitle, p.rank, p.company_id, p.license_id,
+           ts_headline('public.id_search', coalesce(p.body, ''), q.tsq,
+                       'StartSel=<mark>, StopSel=</mark>, MaxFragments=2, MaxWords=20, MinWords=5, FragmentDelimiter=" … "'
+           ) AS highlight
+    FROM page p, q
+    ORDER BY p.rank DESC, p.id DESC
+"#;
+
+pub struct PostgresSearchRepository {
+    pool: PgPool,
+}
+
+impl PostgresSearchRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl SearchRepository for PostgresSearchRepository {
+    #[instrument(skip(self))]
+    async fn search(&self, query: &SearchQuery) -> AppResult<SearchPage> {
+        let types: Vec<String> = query.types.iter().map(|t| t.to_string()).collect();
+        let (cursor_rank, cursor_id) = match &query.cursor {
+            Some(cursor) => (Some(cursor.rank), Some(cursor.id)),
+            None => (None, None),
+        };
+
+        // Fetch one extra row to find out whether another page exists
+        let rows = sqlx::query(SEARCH_QUERY)
+            .bind(&query.text)
+            .bind(contains_pattern(&query.text))
+            .bind(&types)
+            .bind(query.scope.owner_id())
+            .bind(cursor_rank)
+            .bind(cursor_id)
+            .bind(query.limit as i64 + 1)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(AppError::Database)?;
+
+        let mut hits = rows
+            .into_iter()
+            .map(|row| {
+                let hit_type: String = row.get("hit_type");
+                Ok(SearchHit {
+                    hit_type: hit_type.parse().map_err(AppError::InternalError)?,
+                    id: row.get("id"),
+                    title: row.get("title"),
+                    subtitle: row
+                        .get::<Option<String>, _>("subtitle")
+                        .filter(|s| !s.is_empty()),
+                    highlight: row.get("highlight"),
+                    rank: row.get("rank"),
+                    company_id: row.get("company_id"),
+                    license_id: row.get("license_id"),
+                })
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+
+        let next_cursor = if hits.len() > query.limit as usize {
+            hits.truncate(query.limit as usize);
+            hits.last().map(|last| {
+                SearchCursor {
+                    rank: last.rank,
+                    id: last.id,
+                }
+                .encode()
+            })
+        } else {
+            None
+        };
+
+        Ok(SearchPage { hits, next_cursor })
+    }
+}
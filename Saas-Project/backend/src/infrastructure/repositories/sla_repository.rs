@@ -0,0 +1,232 @@
+// PostgreSQL implementation of the license SLA repository
+// Events are deduplicated by the (license_id, kind, threshold_percent) unique
+// constraint, so sweeps running on several instances record each one once.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgRow;
+use sqlx::{FromRow, PgPool, Row};
+use uuid::Uuid;
+
+use crate::domain::dto::LicenseDto;
+use crate::domain::sla::{SlaDecision, SlaEvent, SlaRepository};
+use crate::shared::errors::{AppError, AppResult};
+
+const EVENT_COLUMNS: &str = "id, license_id, kind, threshold_percent, escalated_to, created_at";
+
+fn row_to_event(row: &PgRow) -> Result<SlaEvent, AppError> {
+    let kind: String = row.try_get("kind")?;
+    Ok(SlaEvent {
+        id: row.try_get("id")?,
+        license_id: row.try_get("license_id")?,
+        kind: kind.parse().map_err(AppError::InternalError)?,
+        threshold_percent: row.try_get("threshold_percent")?,
+        escalated_to: row.try_get("escalated_to")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+#[derive(Clone)]
+pub struct PostgresSlaRepository {
+    pool: PgPool,
+}
+
+impl PostgresSlaRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl SlaRepository for PostgresSlaRepository {
+    async fn record_event(&self, event: &SlaEvent) -> AppResult<bool> {
+        let result = sqlx::query(&format!(
+            "INSERT INTO license_sla_events ({}) VALUES ($1, $2, $3, $4, $5, $6) \
+             ON CONFLICT (license_id, kind, threshold_percent) DO NOTHING",
+            EVENT_COLUMNS
+        ))
+        .bind(event.id)
+        .bind(event.license_id)
+        .bind(event.kind.to_string())
+        .bind(event.threshold_percent)
+        .bind(event.escalated_to)
+        .bind(event.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn events_for_licenses(&self, license_ids: &[Uuid]) -> AppResult<Vec<SlaEvent>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM license_sla_events WHERE license_id = ANY($1) \
+             ORDER BY created_at, threshold_percent",
+            EVENT_COLUMNS
+        ))
+        .bind(license_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_event).collect()
+    }
+
+    async fn decisions(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> AppResult<Vec<SlaDecision>> {
+        // The reviewer is whoever recorded the move to the current status
+        let rows = sqlx::query(
+            r#"
+            SELECT l.*, h.changed_by AS decided_by
+            FROM licenses l
+            LEFT JOIN LATERAL (
+                SELECT changed_by FROM application_status_history
+                WHERE license_id = l.id AND to_status = l.application_status
+                ORDER BY changed_at DESC
+                LIMIT 1
+            ) h ON TRUE
+            WHERE l.application_status IN ('approved', 'rejected')
+              AND COALESCE(l.approved_at, l.rejected_at) >= $1
+              AND COALESCE(l.approved_at, l.rejected_at) < $2
+            ORDER BY COALESCE(l.approved_at, l.rejected_at)
+            "#,
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(SlaDecision {
+                    license: LicenseDto::from_row(row)?.into(),
+                    decided_by: row.try_get("decided_by")?,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::domain::licenses::{ApplicationStatus, License, LicenseType};
+    use crate::domain::sla::SlaEventKind;
+    use crate::domain::unit_of_work::UnitOfWork;
+    use crate::infrastructure::cache::InMemoryCache;
+    use crate::infrastructure::repositories::testing::{in_memory, Fixture, TestDatabase};
+    use crate::infrastructure::repositories::{
+        LicenseRepositories, LicenseRepository, LicenseUnitOfWork, PostgresLicenseRepositoryImpl,
+    };
+    use crate::services::license_processing::LicenseProcessingService;
+    use crate::services::license_processing_models::LicenseDecision;
+
+    /// The clock pausing while documents are awaited, warnings and
+    /// escalations recorded once per threshold, and the decisions reviewers made
+    async fn run_sla_scenario(
+        unit_of_work: &dyn UnitOfWork<LicenseRepositories>,
+        repo: &dyn LicenseRepository,
+        sla: &dyn SlaRepository,
+        fx: &Fixture,
+    ) {
+        let license = fx.license(LicenseType::Halal, fx.company_id, fx.owner_id, "Sertifikat Halal", 0);
+        repo.create_license(&license).await.unwrap();
+        let submitted = repo.submit_license_application(license.id, fx.owner_id).await.unwrap();
+        assert_eq!((submitted.sla_paused_at, submitted.sla_paused_seconds), (None, 0));
+
+        // Waiting on documents stops the clock until the application moves on
+        let pending = repo
+            .update_license(&License {
+                application_status: ApplicationStatus::PendingDocuments,
+                ..submitted
+            })
+            .await
+            .unwrap();
+        assert!(pending.sla_paused_at.is_some());
+        let still_pending = repo
+            .update_license(&License {
+                title: "Sertifikat Halal Katering".to_string(),
+                ..pending.clone()
+            })
+            .await
+            .unwrap();
+        assert_eq!(still_pending.sla_paused_at, pending.sla_paused_at);
+        tokio::time::sleep(std::time::Duration::from_millis(1_100)).await;
+        let resumed = repo
+            .update_license(&License {
+                application_status: ApplicationStatus::Processing,
+                ..still_pending
+            })
+            .await
+            .unwrap();
+        assert_eq!(resumed.sla_paused_at, None);
+        assert!(resumed.sla_paused_seconds >= 1);
+        assert_eq!(
+            repo.get_license_by_id(license.id).await.unwrap().unwrap().sla_paused_seconds,
+            resumed.sla_paused_seconds
+        );
+
+        // One event per license, kind and threshold
+        let warning = SlaEvent::new(license.id, SlaEventKind::Warning, 50);
+        assert!(sla.record_event(&warning).await.unwrap());
+        assert!(!sla.record_event(&SlaEvent::new(license.id, SlaEventKind::Warning, 50)).await.unwrap());
+        let mut escalation = SlaEvent::new(license.id, SlaEventKind::Escalation, 100);
+        escalation.escalated_to = Some(fx.admin_id);
+        assert!(sla.record_event(&escalation).await.unwrap());
+        assert!(sla.record_event(&SlaEvent::new(Uuid::new_v4(), SlaEventKind::Warning, 50)).await.is_err());
+        let events = sla.events_for_licenses(&[license.id]).await.unwrap();
+        assert_eq!(
+            events.iter().map(|e| (e.kind, e.threshold_percent, e.escalated_to)).collect::<Vec<_>>(),
+            vec![(SlaEventKind::Warning, 50, None), (SlaEventKind::Escalation, 100, Some(fx.admin_id))]
+        );
+
+        // Decisions carry the reviewer who made them
+        LicenseProcessingService::new()
+            .decide(
+                unit_of_work,
+                license.id,
+                resumed.version,
+                fx.admin_id,
+                LicenseDecision::Reject {
+                    reason: "Dokumen tidak sah".to_string(),
+                    admin_notes: None,
+                },
+            )
+            .await
+            .unwrap();
+        let decisions = sla
+            .decisions(fx.at(0), Utc::now() + Duration::hours(1))
+            .await
+            .unwrap();
+        assert_eq!(
+            decisions.iter().map(|d| (d.license.id, d.decided_by)).collect::<Vec<_>>(),
+            vec![(license.id, Some(fx.admin_id))]
+        );
+        assert!(sla.decisions(fx.at(0), fx.at(1)).await.unwrap().is_empty());
+
+        // Events go with the license
+        repo.delete_license(license.id).await.unwrap();
+        assert!(sla.events_for_licenses(&[license.id]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn in_memory_sla_tracking_conforms() {
+        let fx = Fixture::new();
+        let repo = in_memory(&fx);
+        run_sla_scenario(&repo, &repo, &repo, &fx).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn postgres_sla_tracking_conforms() {
+        let fx = Fixture::new();
+        let db = TestDatabase::seeded(&fx).await;
+        run_sla_scenario(
+            &LicenseUnitOfWork::<InMemoryCache>::new(db.pool.clone(), None),
+            &PostgresLicenseRepositoryImpl::new(db.pool.clone()),
+            &PostgresSlaRepository::new(db.pool.clone()),
+            &fx,
+        )
+        .await;
+        db.destroy().await;
+    }
+}
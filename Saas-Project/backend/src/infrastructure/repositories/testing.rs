@@ -0,0 +1,213 @@
+// Shared fixture for the repository conformance tests
+// Each repository's tests run one scenario against its in-memory fake and its
+// Postgres implementation, over the same users, companies and clock. The
+// Postgres runs are `#[ignore]`d, since they need a server: set
+// TEST_DATABASE_URL to a role that may create databases and run
+// `cargo test -- --include-ignored`. Each run gets a freshly migrated
+// database of its own.
+
+use chrono::{DateTime, Duration, SubsecRound, Utc};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{ConnectOptions, Connection, PgPool};
+use std::str::FromStr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::domain::companies::{BusinessType, Company, CompanyAddress};
+use crate::domain::entities::{User, UserRole};
+use crate::domain::licenses::{
+    ApplicationStatus, ApplicationStatusHistory, DocumentType, License, LicenseDocument,
+    LicenseType,
+};
+use crate::domain::repositories::{CompanyRepository, UserRepository};
+use crate::domain::value_objects::{Email, UserId};
+use crate::infrastructure::database::migrations::MIGRATOR;
+
+use super::{InMemoryCompanyRepository, InMemoryLicenseRepository, InMemoryUserRepository};
+
+/// Users and companies the licenses belong to
+pub(crate) struct Fixture {
+    pub(crate) owner_id: Uuid,
+    pub(crate) other_user_id: Uuid,
+    pub(crate) admin_id: Uuid,
+    /// Owned by `owner_id`
+    pub(crate) company_id: Uuid,
+    /// Owned by `other_user_id`
+    pub(crate) other_company_id: Uuid,
+    /// Microsecond precision, as stored by Postgres
+    pub(crate) t0: DateTime<Utc>,
+}
+
+impl Fixture {
+    pub(crate) fn new() -> Self {
+        Self {
+            owner_id: Uuid::new_v4(),
+            other_user_id: Uuid::new_v4(),
+            admin_id: Uuid::new_v4(),
+            company_id: Uuid::new_v4(),
+            other_company_id: Uuid::new_v4(),
+            t0: Utc::now().trunc_subsecs(6) - Duration::hours(1),
+        }
+    }
+
+    pub(crate) fn at(&self, seconds: i64) -> DateTime<Utc> {
+        self.t0 + Duration::seconds(seconds)
+    }
+
+    pub(crate) fn license(&self, license_type: LicenseType, company_id: Uuid, user_id: Uuid, title: &str, seconds: i64) -> License {
+        let mut license = License::new(license_type, company_id, user_id, title.to_string(), None);
+        license.created_at = self.at(seconds);
+        license.updated_at = self.at(seconds);
+        license
+    }
+
+    pub(crate) fn document(&self, license_id: Uuid, file_name: &str, seconds: i64) -> LicenseDocument {
+        LicenseDocument {
+            id: Uuid::new_v4(),
+            license_id,
+            document_type: DocumentType::Ktp,
+            file_name: format!("{}-{}", Uuid::new_v4(), file_name),
+            original_file_name: file_name.to_string(),
+            file_path: format!("licenses/{}/{}", license_id, file_name),
+            file_size: 2048,
+            mime_type: "application/pdf".to_string(),
+            upload_date: self.at(seconds),
+            is_verified: false,
+            verified_at: None,
+            verified_by: None,
+            notes: None,
+        }
+    }
+
+    pub(crate) fn history(
+        &self,
+        license_id: Uuid,
+        from_status: Option<ApplicationStatus>,
+        to_status: ApplicationStatus,
+        seconds: i64,
+    ) -> ApplicationStatusHistory {
+        ApplicationStatusHistory {
+            id: Uuid::new_v4(),
+            license_id,
+            from_status,
+            to_status,
+            changed_by: self.owner_id,
+            changed_at: self.at(seconds),
+            notes: None,
+            is_system_generated: false,
+        }
+    }
+}
+
+/// The fixture's admin and companies, as `seed` inserts them into Postgres
+pub(crate) async fn in_memory_directory(fx: &Fixture) -> (Arc<InMemoryUserRepository>, InMemoryCompanyRepository) {
+    let users = Arc::new(InMemoryUserRepository::new());
+    let mut admin = User::new(
+        Email::new("admin@conformance.test").unwrap(),
+        "x".to_string(),
+        "Conformance".to_string(),
+        UserRole::AdminStaff,
+    );
+    admin.id = UserId(fx.admin_id);
+    users.save(&admin).await.unwrap();
+
+    let companies = InMemoryCompanyRepository::new();
+    for (id, owner_id) in [(fx.company_id, fx.owner_id), (fx.other_company_id, fx.other_user_id)] {
+        let mut company = Company::new(
+            owner_id,
+            "CV Conformance".to_string(),
+            BusinessType::CV,
+            "Perdagangan".to_string(),
+            CompanyAddress::new(
+                "Jl. Merdeka 1".to_string(),
+                "Bandung".to_string(),
+                "Jawa Barat".to_string(),
+                "40111".to_string(),
+            ),
+        );
+        company.id = id;
+        companies.save(&company).await.unwrap();
+    }
+    (users, companies)
+}
+
+pub(crate) fn in_memory(fx: &Fixture) -> InMemoryLicenseRepository {
+    InMemoryLicenseRepository::new()
+        .with_company_owner(fx.company_id, fx.owner_id)
+        .with_company_owner(fx.other_company_id, fx.other_user_id)
+}
+
+/// A freshly migrated database holding the fixture's users and companies
+pub(crate) struct TestDatabase {
+    pub(crate) pool: PgPool,
+    admin: PgConnectOptions,
+    name: String,
+}
+
+impl TestDatabase {
+    /// Panics without TEST_DATABASE_URL, so an ignored test that is asked to
+    /// run cannot pass without having touched Postgres
+    pub(crate) async fn seeded(fx: &Fixture) -> Self {
+        let url = std::env::var("TEST_DATABASE_URL")
+            .expect("TEST_DATABASE_URL must point at a Postgres server for this test");
+        let admin = PgConnectOptions::from_str(&url).expect("TEST_DATABASE_URL is not a valid URL");
+        let name = format!("conformance_{}", Uuid::new_v4().as_simple());
+
+        let mut conn = admin.connect().await.expect("connect to TEST_DATABASE_URL");
+        sqlx::query(&format!("CREATE DATABASE {}", name))
+            .execute(&mut conn)
+            .await
+            .expect("create conformance database");
+        conn.close().await.ok();
+
+        let pool = PgPoolOptions::new()
+            .max_connections(2)
+            .connect_with(admin.clone().database(&name))
+            .await
+            .expect("connect to conformance database");
+        MIGRATOR.run(&pool).await.expect("migrate conformance database");
+        seed(&pool, fx).await;
+        Self { pool, admin, name }
+    }
+
+    pub(crate) async fn destroy(self) {
+        self.pool.close().await;
+        let mut conn = self.admin.connect().await.expect("connect to TEST_DATABASE_URL");
+        sqlx::query(&format!("DROP DATABASE IF EXISTS {} WITH (FORCE)", self.name))
+            .execute(&mut conn)
+            .await
+            .expect("drop conformance database");
+    }
+}
+
+/// The users and companies the fixture's licenses reference
+async fn seed(pool: &PgPool, fx: &Fixture) {
+    for (id, role) in [
+        (fx.owner_id, "umkm_owner"),
+        (fx.other_user_id, "umkm_owner"),
+        (fx.admin_id, "admin_staff"),
+    ] {
+        sqlx::query("INSERT INTO users (id, email, password_hash, full_name, role) VALUES ($1, $2, 'x', 'Conformance', $3)")
+            .bind(id)
+            .bind(format!("{}@conformance.test", id.as_simple()))
+            .bind(role)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+    for (id, owner_id) in [(fx.company_id, fx.owner_id), (fx.other_company_id, fx.other_user_id)] {
+        sqlx::query(
+            r#"
+            INSERT INTO companies (
+                id, owner_id, company_name, business_type, industry_sector,
+                address_street, address_city, address_province, address_postal_code, business_scale
+            ) VALUES ($1, $2, 'CV Conformance', 'CV', 'Perdagangan', 'Jl. Merdeka 1', 'Bandung', 'Jawa Barat', '40111', 'mikro')
+            "#,
+        )
+        .bind(id)
+        .bind(owner_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+}
@@ -1,10 +1,12 @@
-use std::{collections::HashMap, sync::Arc};
+use std::sync::Arc;
 
 use async_trait::async_trait;
 
 use crate::{
+    domain::filters::{TransactionFilter, TransactionSortField},
     domain::finance::{Transaction, TransactionId, TransactionRepository},
     shared::errors::AppError,
+    shared::query::{ListQuery, Page},
 };
 
 #[async_trait]
@@ -13,24 +15,11 @@ impl<T: TransactionRepository + Send + Sync + 'static> TransactionRepository for
         self.as_ref().find_by_id(id).await
     }
 
-    async fn list_by_company(
+    async fn list(
         &self,
-        company_id: uuid::Uuid,
-        limit: i64,
-        offset: i64,
-        filters: Option<HashMap<String, String>>,
-    ) -> Result<Vec<Transaction>, AppError> {
-        self.as_ref()
-            .list_by_company(company_id, limit, offset, filters)
-            .await
-    }
-
-    async fn count_by_company(
-        &self,
-        company_id: uuid::Uuid,
-        filters: Option<HashMap<String, String>>,
-    ) -> Result<i64, AppError> {
-        self.as_ref().count_by_company(company_id, filters).await
+        query: &ListQuery<TransactionFilter, TransactionSortField>,
+    ) -> Result<Page<Transaction>, AppError> {
+        self.as_ref().list(query).await
     }
 
     async fn create(&self, transaction: &Transaction) -> Result<Transaction, AppError> {
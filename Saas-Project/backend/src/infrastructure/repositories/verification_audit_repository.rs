@@ -0,0 +1,173 @@
+// PostgreSQL implementation of the verification audit trail
+// Lookups are only ever inserted and read back newest first.
+
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use crate::domain::verification::{VerificationAuditRepository, VerificationLookup};
+use crate::shared::errors::{AppError, AppResult};
+
+const LOOKUP_COLUMNS: &str = "id, method, query, company_id, license_id, licenses_found, \
+    client_ip, user_agent, looked_up_at";
+
+fn row_to_lookup(row: &PgRow) -> Result<VerificationLookup, AppError> {
+    let method: String = row.try_get("method")?;
+    Ok(VerificationLookup {
+        id: row.try_get("id")?,
+        method: method.parse().map_err(AppError::InternalError)?,
+        query: row.try_get("query")?,
+        company_id: row.try_get("company_id")?,
+        license_id: row.try_get("license_id")?,
+        licenses_found: row.try_get("licenses_found")?,
+        client_ip: row.try_get("client_ip")?,
+        user_agent: row.try_get("user_agent")?,
+        looked_up_at: row.try_get("looked_up_at")?,
+    })
+}
+
+#[derive(Clone)]
+pub struct PostgresVerificationAuditRepository {
+    pool: PgPool,
+}
+
+impl PostgresVerificationAuditRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl VerificationAuditRepository for PostgresVerificationAuditRepository {
+    async fn record_lookup(&self, lookup: &VerificationLookup) -> AppResult<()> {
+        sqlx::query(&format!(
+            "INSERT INTO license_verification_lookups ({}) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+            LOOKUP_COLUMNS
+        ))
+        .bind(lookup.id)
+        .bind(lookup.method.to_string())
+        .bind(&lookup.query)
+        .bind(lookup.company_id)
+        .bind(lookup.license_id)
+        .bind(lookup.licenses_found)
+        .bind(&lookup.client_ip)
+        .bind(&lookup.user_agent)
+        .bind(lookup.looked_up_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn recent_lookups(
+        &self,
+        company_id: Option<Uuid>,
+        limit: i64,
+    ) -> AppResult<Vec<VerificationLookup>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM license_verification_lookups \
+             WHERE $1::uuid IS NULL OR company_id = $1 \
+             ORDER BY looked_up_at DESC, id DESC LIMIT $2",
+            LOOKUP_COLUMNS
+        ))
+        .bind(company_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(row_to_lookup).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::licenses::LicenseType;
+    use crate::domain::verification::LookupMethod;
+    use crate::infrastructure::repositories::testing::{in_memory, Fixture, TestDatabase};
+    use crate::infrastructure::repositories::{LicenseRepository, PostgresLicenseRepositoryImpl};
+
+    /// Exact license number lookups and the audit trail of verifications
+    async fn run_verification_audit_scenario(
+        repo: &dyn LicenseRepository,
+        audit: &dyn VerificationAuditRepository,
+        fx: &Fixture,
+    ) {
+        let mut license = fx.license(LicenseType::Halal, fx.company_id, fx.owner_id, "Halal", 0);
+        license.license_number = Some("HALAL/2024/0001".to_string());
+        let license = repo.create_license(&license).await.unwrap();
+        assert_eq!(
+            repo.get_license_by_number("HALAL/2024/0001").await.unwrap(),
+            Some(license.clone())
+        );
+        assert_eq!(repo.get_license_by_number("halal/2024/0001").await.unwrap(), None);
+
+        let lookup = |method, query: &str, company_id, license_id, seconds| VerificationLookup {
+            company_id,
+            license_id,
+            licenses_found: license_id.map_or(0, |_| 1),
+            client_ip: Some("203.0.113.9".to_string()),
+            looked_up_at: fx.at(seconds),
+            ..VerificationLookup::new(method, query)
+        };
+        let found = lookup(
+            LookupMethod::LicenseNumber,
+            "HALAL/2024/0001",
+            Some(fx.company_id),
+            Some(license.id),
+            10,
+        );
+        let missed = lookup(LookupMethod::Nib, "9120009999999", None, None, 20);
+        let other = lookup(LookupMethod::Token, "token", Some(fx.other_company_id), None, 30);
+        for entry in [&found, &missed, &other] {
+            audit.record_lookup(entry).await.unwrap();
+        }
+        assert!(audit
+            .record_lookup(&lookup(LookupMethod::Token, "t", None, Some(Uuid::new_v4()), 40))
+            .await
+            .is_err());
+
+        assert_eq!(
+            audit.recent_lookups(None, 10).await.unwrap(),
+            vec![other.clone(), missed.clone(), found.clone()]
+        );
+        assert_eq!(audit.recent_lookups(None, 2).await.unwrap(), vec![other, missed]);
+        assert_eq!(
+            audit.recent_lookups(Some(fx.company_id), 10).await.unwrap(),
+            vec![found.clone()]
+        );
+
+        // The trail outlives the license
+        repo.delete_license(license.id).await.unwrap();
+        assert_eq!(
+            audit.recent_lookups(Some(fx.company_id), 10).await.unwrap(),
+            vec![VerificationLookup {
+                license_id: None,
+                ..found
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn in_memory_verification_audit_conforms() {
+        let fx = Fixture::new();
+        let repo = in_memory(&fx);
+        run_verification_audit_scenario(&repo, &repo, &fx).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn postgres_verification_audit_conforms() {
+        let fx = Fixture::new();
+        let db = TestDatabase::seeded(&fx).await;
+        run_verification_audit_scenario(
+            &PostgresLicenseRepositoryImpl::new(db.pool.clone()),
+            &PostgresVerificationAuditRepository::new(db.pool.clone()),
+            &fx,
+        )
+        .await;
+        db.destroy().await;
+    }
+}
@@ -0,0 +1,154 @@
+// Spreadsheet reading for transaction imports
+// CSV files (any delimiter, UTF-8 or legacy Windows encodings) and Excel/ODS
+// workbooks are turned into a domain Table; the first worksheet is used.
+
+use calamine::{open_workbook_auto_from_rs, Data, Reader};
+use chrono::NaiveDate;
+use std::io::Cursor;
+
+use crate::domain::imports::{excel_serial_date, Cell, Table};
+use crate::shared::errors::AppError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpreadsheetFormat {
+    Csv,
+    Workbook,
+}
+
+impl SpreadsheetFormat {
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        let extension = file_name.rsplit_once('.')?.1.to_lowercase();
+        match extension.as_str() {
+            "csv" | "txt" => Some(SpreadsheetFormat::Csv),
+            "xlsx" | "xlsm" | "xls" | "ods" => Some(SpreadsheetFormat::Workbook),
+            _ => None,
+        }
+    }
+}
+
+pub fn read_table(bytes: &[u8], format: SpreadsheetFormat) -> Result<Table, AppError> {
+    match format {
+        SpreadsheetFormat::Csv => read_csv(bytes),
+        SpreadsheetFormat::Workbook => read_workbook(bytes),
+    }
+}
+
+/// Picks the delimiter that occurs most often in the first lines; Indonesian
+/// Excel installations export CSV with semicolons
+fn detect_delimiter(text: &str) -> u8 {
+    let sample: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).take(30).collect();
+    // Later candidates win ties, so a comma is preferred
+    [b'\t', b';', b',']
+        .into_iter()
+        .max_by_key(|d| sample.iter().map(|l| l.matches(*d as char).count()).sum::<usize>())
+        .unwrap_or(b',')
+}
+
+fn decode(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        // Older bank exports are Windows-1252; Latin-1 covers what appears in statements
+        Err(_) => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+fn read_csv(bytes: &[u8]) -> Result<Table, AppError> {
+    let text = decode(bytes);
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(detect_delimiter(&text))
+        .from_reader(text.as_bytes());
+
+    let rows = reader
+        .records()
+        .map(|record| {
+            record.map(|record| {
+                record
+                    .iter()
+                    .map(|field| {
+                        if field.trim().is_empty() {
+                            Cell::Empty
+                        } else {
+                            Cell::Text(field.to_string())
+                        }
+                    })
+                    .collect()
+            })
+        })
+        .collect::<Result<Vec<Vec<Cell>>, _>>()
+        .map_err(|e| AppError::FileProcessing(format!("Unable to read CSV file: {}", e)))?;
+
+    Ok(Table::new(rows))
+}
+
+fn workbook_cell(data: &Data) -> Cell {
+    match data {
+        Data::Int(i) => Cell::Number(*i as f64),
+        Data::Float(f) => Cell::Number(*f),
+        Data::String(s) => Cell::Text(s.clone()),
+        Data::Bool(b) => Cell::Text(b.to_string()),
+        Data::DateTime(dt) => excel_serial_date(dt.as_f64()).map_or(Cell::Empty, Cell::Date),
+        Data::DateTimeIso(s) => s
+            .get(..10)
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            .map_or_else(|| Cell::Text(s.clone()), Cell::Date),
+        Data::DurationIso(s) => Cell::Text(s.clone()),
+        Data::Error(_) | Data::Empty => Cell::Empty,
+    }
+}
+
+fn read_workbook(bytes: &[u8]) -> Result<Table, AppError> {
+    let mut workbook = open_workbook_auto_from_rs(Cursor::new(bytes))
+        .map_err(|e| AppError::FileProcessing(format!("Unable to read spreadsheet: {}", e)))?;
+
+    let range = workbook
+        .worksheet_range_at(0)
+        .ok_or_else(|| AppError::FileProcessing("Spreadsheet has no worksheets".to_string()))?
+        .map_err(|e| AppError::FileProcessing(format!("Unable to read worksheet: {}", e)))?;
+
+    Ok(Table::new(
+        range
+            .rows()
+            .map(|row| row.iter().map(workbook_cell).collect())
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_semicolon_csv_with_bom() {
+        let csv = "\u{feff}Tanggal;Uraian;Nominal\n01/04/2024;\"Penjualan; tunai\";1.000.000,50\n\n";
+        let table = read_table(csv.as_bytes(), SpreadsheetFormat::Csv).unwrap();
+
+        assert_eq!(table.rows[0][0], Cell::Text("Tanggal".to_string()));
+        assert_eq!(table.rows[1][1], Cell::Text("Penjualan; tunai".to_string()));
+        assert_eq!(table.rows[1][2], Cell::Text("1.000.000,50".to_string()));
+    }
+
+    #[test]
+    fn test_read_comma_csv_with_latin1_and_ragged_rows() {
+        let mut bytes = b"Periode : ,01/01/2024 - 31/01/2024\nTanggal Transaksi,Keterangan,Cabang,Jumlah,,Saldo\n'02/01,'CAF".to_vec();
+        bytes.push(0xC9); // 'É' in Windows-1252
+        bytes.extend_from_slice(b",'0000,\"150,000.00\",CR,\"1,150,000.00\"\n");
+        let table = read_table(&bytes, SpreadsheetFormat::Csv).unwrap();
+
+        assert_eq!(table.rows[0].len(), 2);
+        assert_eq!(table.rows[1][4], Cell::Empty);
+        assert_eq!(table.rows[2][1], Cell::Text("'CAFÉ".to_string()));
+        assert_eq!(table.rows[2][3], Cell::Text("150,000.00".to_string()));
+    }
+
+    #[test]
+    fn test_format_from_file_name() {
+        assert_eq!(SpreadsheetFormat::from_file_name("mutasi.CSV"), Some(SpreadsheetFormat::Csv));
+        assert_eq!(SpreadsheetFormat::from_file_name("rekap.xlsx"), Some(SpreadsheetFormat::Workbook));
+        assert_eq!(SpreadsheetFormat::from_file_name("statement.pdf"), None);
+        assert_eq!(SpreadsheetFormat::from_file_name("noextension"), None);
+        assert!(read_table(b"not a workbook", SpreadsheetFormat::Workbook).is_err());
+    }
+}
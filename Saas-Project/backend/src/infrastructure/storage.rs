@@ -1,4 +1,178 @@
-// File storage service infrastructure
+// File storage
+// Generated documents are kept under keys like `certificates/{license}/{id}.pdf`
+// rather than in the public upload directory: they are only ever served
+// through handlers that check who is asking. Keys are relative paths made of
+// plain segments, so a key can never reach outside the storage root.
 
-#[allow(dead_code)] // Will be implemented for file uploads
-pub struct FileStorageService;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::fs;
+use uuid::Uuid;
+
+use crate::shared::errors::{AppError, AppResult};
+
+/// Blobs stored by key
+#[async_trait]
+pub trait FileStorage: Send + Sync {
+    /// Stores `bytes` under `key`, replacing what was there
+    async fn put(&self, key: &str, bytes: &[u8]) -> AppResult<()>;
+    async fn get(&self, key: &str) -> AppResult<Option<Vec<u8>>>;
+    /// Whether there was something to delete
+    async fn delete(&self, key: &str) -> AppResult<bool>;
+}
+
+/// Rejects keys that are empty, absolute or have empty, `.` or `..` segments
+fn check_key(key: &str) -> AppResult<()> {
+    let valid = !key.is_empty()
+        && !key.contains('\\')
+        && key
+            .split('/')
+            .all(|segment| !segment.is_empty() && segment != "." && segment != "..");
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::InternalError(format!("Invalid storage key: {:?}", key)))
+    }
+}
+
+fn io_error(action: &str, path: &Path, err: std::io::Error) -> AppError {
+    AppError::InternalError(format!("Failed to {} {}: {}", action, path.display(), err))
+}
+
+/// Files under a directory on local disk
+#[derive(Debug, Clone)]
+pub struct LocalFileStorage {
+    root: PathBuf,
+}
+
+impl LocalFileStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> AppResult<PathBuf> {
+        check_key(key)?;
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl FileStorage for LocalFileStorage {
+    async fn put(&self, key: &str, bytes: &[u8]) -> AppResult<()> {
+        let path = self.path(key)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .await
+                .map_err(|err| io_error("create", dir, err))?;
+        }
+        // Written aside and renamed, so readers never see half a file
+        let partial = path.with_extension(format!("{}.partial", Uuid::new_v4().simple()));
+        fs::write(&partial, bytes)
+            .await
+            .map_err(|err| io_error("write", &partial, err))?;
+        if let Err(err) = fs::rename(&partial, &path).await {
+            let _ = fs::remove_file(&partial).await;
+            return Err(io_error("write", &path, err));
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> AppResult<Option<Vec<u8>>> {
+        let path = self.path(key)?;
+        match fs::read(&path).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(io_error("read", &path, err)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> AppResult<bool> {
+        let path = self.path(key)?;
+        match fs::remove_file(&path).await {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(io_error("delete", &path, err)),
+        }
+    }
+}
+
+/// Blobs kept in memory, for tests and demo mode
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryFileStorage {
+    files: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl InMemoryFileStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl FileStorage for InMemoryFileStorage {
+    async fn put(&self, key: &str, bytes: &[u8]) -> AppResult<()> {
+        check_key(key)?;
+        self.files
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), bytes.to_vec());
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> AppResult<Option<Vec<u8>>> {
+        check_key(key)?;
+        Ok(self.files.lock().unwrap().get(key).cloned())
+    }
+
+    async fn delete(&self, key: &str) -> AppResult<bool> {
+        check_key(key)?;
+        Ok(self.files.lock().unwrap().remove(key).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_stay_under_the_root() {
+        for key in ["certificates/a/b.pdf", "a.pdf", "a/.hidden"] {
+            assert!(check_key(key).is_ok(), "{key}");
+        }
+        for key in ["", "/etc/passwd", "a/../../b", "a//b", "./a", "a/", "a\\..\\b"] {
+            assert!(check_key(key).is_err(), "{key}");
+        }
+    }
+
+    async fn round_trip(storage: &dyn FileStorage) {
+        assert_eq!(storage.get("certificates/x/1.pdf").await.unwrap(), None);
+        storage.put("certificates/x/1.pdf", b"first").await.unwrap();
+        storage.put("certificates/x/1.pdf", b"second").await.unwrap();
+        assert_eq!(
+            storage.get("certificates/x/1.pdf").await.unwrap(),
+            Some(b"second".to_vec())
+        );
+        assert!(storage.delete("certificates/x/1.pdf").await.unwrap());
+        assert!(!storage.delete("certificates/x/1.pdf").await.unwrap());
+        assert_eq!(storage.get("certificates/x/1.pdf").await.unwrap(), None);
+        assert!(storage.put("../escape.pdf", b"no").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_storage_round_trip() {
+        round_trip(&InMemoryFileStorage::new()).await;
+    }
+
+    #[tokio::test]
+    async fn test_local_storage_round_trip() {
+        let root = std::env::temp_dir().join(format!("storage-test-{}", Uuid::new_v4()));
+        round_trip(&LocalFileStorage::new(&root)).await;
+        // Nothing is left behind from the renames
+        let leftovers = std::fs::read_dir(root.join("certificates/x")).unwrap().count();
+        assert_eq!(leftovers, 0);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,288 @@
+// Application context - the services and repositories behind `AppState`
+// The server builds it on Postgres; demo mode and the routed handler tests
+// build it on in-memory repositories seeded with the demo fixtures.
+
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::config::{self, AppConfig};
+use crate::domain::{
+    self,
+    imports::TransactionImportService,
+    reconciliation::ReconciliationService,
+    repositories::{CompanyRepository, UserRepository},
+    unit_of_work::UnitOfWork,
+};
+use crate::infrastructure::{
+    self,
+    database::manager::DatabaseManager,
+    demo::DemoRepositories,
+    live_updates::LiveUpdateHub,
+    messaging::RecordingSender,
+    midtrans::MidtransClient,
+    repositories::{
+        InMemoryAdminStatsRepository, InMemoryAnalyticsRepository, InMemoryBillingRepository,
+        InMemoryFinancialAccountRepository, InMemoryImportRepository, InMemoryInvoicingRepository,
+        InMemoryReconciliationRepository,
+        InMemoryLicenseCommentRepository, InMemoryNotificationRepository, LicenseRepositories,
+        LicenseRepository,
+    },
+    storage::InMemoryFileStorage,
+    web::handlers::AppStateType,
+};
+use crate::services::{
+    self,
+    auth::AuthService,
+    billing::BillingService,
+    invoicing::InvoicingService,
+    license_certificates::CertificateService,
+    license_comments::LicenseCommentService,
+    license_verification::{LicenseVerificationService, VerificationSigner},
+    notification_dispatcher::NotificationDispatcher,
+    notifications::NotificationService,
+    sla_monitor::SlaMonitor,
+};
+
+/// Everything the handlers reach through `AppState`
+#[derive(Clone)]
+pub struct AppContext {
+    pub config: AppConfig,
+    /// `None` in demo mode
+    pub db: Option<DatabaseManager>,
+    pub auth_service: AuthService,
+    pub cache_service: Option<infrastructure::cache::CacheService>,
+    pub user_repository: Arc<dyn UserRepository + Send + Sync>,
+    pub company_repository: Arc<dyn CompanyRepository + Send + Sync>,
+    pub license_repository: Arc<dyn LicenseRepository + Send + Sync>,
+    pub license_work: Arc<dyn UnitOfWork<LicenseRepositories>>,
+    pub search_repository: Arc<dyn domain::search::SearchRepository>,
+    pub sla_monitor: Arc<SlaMonitor>,
+    pub admin_stats: Arc<dyn domain::admin::AdminStatsRepository>,
+    pub analytics: Arc<dyn domain::analytics::AnalyticsRepository>,
+    pub certificates: Arc<CertificateService>,
+    pub license_verification: Arc<LicenseVerificationService>,
+    pub notifications: Arc<NotificationService>,
+    pub notification_dispatcher: Arc<NotificationDispatcher>,
+    pub email: Arc<dyn domain::notifications::MessageSender>,
+    pub live_updates: LiveUpdateHub,
+    pub license_comments: Arc<LicenseCommentService>,
+    pub billing: Arc<BillingService>,
+    pub invoicing: Arc<InvoicingService>,
+    pub imports: Arc<TransactionImportService>,
+    pub reconciliation: Arc<ReconciliationService>,
+    /// `None` without a Midtrans server key, e.g. in demo mode
+    pub midtrans: Option<Arc<MidtransClient>>,
+}
+
+impl AppStateType for AppContext {
+    fn company_repository(&self) -> &Arc<dyn domain::repositories::CompanyRepository + Send + Sync> {
+        &self.company_repository
+    }
+    
+    fn user_repository(&self) -> &Arc<dyn domain::repositories::UserRepository + Send + Sync> {
+        &self.user_repository
+    }
+    
+    fn license_repository(&self) -> &Arc<dyn infrastructure::repositories::LicenseRepository + Send + Sync> {
+        &self.license_repository
+    }
+
+    fn license_work(&self) -> &Arc<dyn UnitOfWork<LicenseRepositories>> {
+        &self.license_work
+    }
+    
+    fn auth_service(&self) -> &services::auth::AuthService {
+        &self.auth_service
+    }
+
+    fn config(&self) -> &config::AppConfig {
+        &self.config
+    }
+
+    fn cache_service(&self) -> &Option<infrastructure::cache::CacheService> {
+        &self.cache_service
+    }
+
+    fn search_repository(&self) -> &Arc<dyn domain::search::SearchRepository> {
+        &self.search_repository
+    }
+
+    fn sla_monitor(&self) -> &Arc<SlaMonitor> {
+        &self.sla_monitor
+    }
+
+    fn admin_stats(&self) -> &Arc<dyn domain::admin::AdminStatsRepository> {
+        &self.admin_stats
+    }
+
+    fn analytics(&self) -> &Arc<dyn domain::analytics::AnalyticsRepository> {
+        &self.analytics
+    }
+
+    fn certificates(&self) -> &Arc<CertificateService> {
+        &self.certificates
+    }
+
+    fn license_verification(&self) -> &Arc<LicenseVerificationService> {
+        &self.license_verification
+    }
+
+    fn notifications(&self) -> &Arc<NotificationService> {
+        &self.notifications
+    }
+
+    fn email(&self) -> &Arc<dyn domain::notifications::MessageSender> {
+        &self.email
+    }
+
+    fn live_updates(&self) -> &LiveUpdateHub {
+        &self.live_updates
+    }
+
+    fn license_comments(&self) -> &Arc<LicenseCommentService> {
+        &self.license_comments
+    }
+
+    fn billing(&self) -> &Arc<BillingService> {
+        &self.billing
+    }
+
+    fn invoicing(&self) -> &Arc<InvoicingService> {
+        &self.invoicing
+    }
+
+    fn imports(&self) -> &Arc<TransactionImportService> {
+        &self.imports
+    }
+
+    fn reconciliation(&self) -> &Arc<ReconciliationService> {
+        &self.reconciliation
+    }
+
+    fn midtrans(&self) -> Option<&MidtransClient> {
+        self.midtrans.as_deref()
+    }
+
+    fn database(&self) -> Option<&DatabaseManager> {
+        self.db.as_ref()
+    }
+}
+
+/// Application context for `--demo`: in-memory repositories seeded with
+/// fixtures, no database and no cache
+pub async fn demo_context(config: AppConfig) -> Result<AppContext, Box<dyn std::error::Error>> {
+    warn!("🧪 Demo mode: in-memory repositories, data is lost on exit");
+
+    let auth_service = AuthService::new(config.jwt_secret.clone());
+    let demo = DemoRepositories::new();
+    demo.seed(&auth_service).await?;
+    info!(
+        "🌱 Demo fixtures seeded, log in as {} or {} with password {}",
+        infrastructure::demo::DEMO_SUPER_ADMIN,
+        infrastructure::demo::DEMO_OWNER,
+        infrastructure::demo::DEMO_PASSWORD
+    );
+
+    let sla_monitor = Arc::new(SlaMonitor::new(
+        config.sla.policy(),
+        Arc::new(demo.licenses.clone()),
+        Arc::new(demo.licenses.clone()),
+        demo.users.clone(),
+    ));
+    let admin_stats = Arc::new(InMemoryAdminStatsRepository::new(
+        demo.users.clone(),
+        demo.companies.clone(),
+        demo.licenses.clone(),
+    ));
+    let analytics = Arc::new(InMemoryAnalyticsRepository::new(
+        demo.companies.clone(),
+        demo.licenses.clone(),
+    ));
+    let license_verification = Arc::new(LicenseVerificationService::new(
+        Arc::new(demo.licenses.clone()),
+        Arc::new(demo.companies.clone()),
+        Arc::new(demo.licenses.clone()),
+        VerificationSigner::new(&config.certificates.signing_key),
+        &config.certificates.public_base_url,
+    ));
+    let certificates = Arc::new(CertificateService::new(
+        Arc::new(demo.licenses.clone()),
+        Arc::new(demo.companies.clone()),
+        Arc::new(InMemoryFileStorage::new()),
+        license_verification.clone(),
+    ));
+    // Messages are never sent from the demo, only logged
+    let notification_repository = Arc::new(InMemoryNotificationRepository::new());
+    let email: Arc<dyn domain::notifications::MessageSender> = Arc::new(RecordingSender::new("email"));
+    let notification_dispatcher = Arc::new(NotificationDispatcher::new(
+        notification_repository.clone(),
+        email.clone(),
+        Arc::new(RecordingSender::new("whatsapp")),
+        config.notifications.retry_policy(),
+    ));
+    let notifications = Arc::new(NotificationService::new(
+        notification_repository,
+        demo.users.clone(),
+        config.notifications.app_url.clone(),
+    ));
+    let live_updates = LiveUpdateHub::new();
+    let license_comments = Arc::new(LicenseCommentService::new(
+        Arc::new(InMemoryLicenseCommentRepository::new()),
+        Arc::new(demo.licenses.clone()),
+        demo.users.clone(),
+        notifications.clone(),
+        live_updates.clone(),
+    ));
+    let billing = Arc::new(BillingService::new(
+        Arc::new(InMemoryBillingRepository::new(
+            demo.companies.clone(),
+            demo.licenses.clone(),
+        )),
+        Arc::new(demo.companies.clone()),
+        notifications.clone(),
+        config.billing.policy(),
+    ));
+    demo.seed_subscriptions(&billing).await?;
+    let invoicing = Arc::new(InvoicingService::new(
+        Arc::new(InMemoryInvoicingRepository::new(demo.finance.clone())),
+        Arc::new(InMemoryFinancialAccountRepository::new(demo.finance.clone())),
+        email.clone(),
+        config.invoicing.policy(),
+    ));
+    demo.seed_accounts().await?;
+    let imports = Arc::new(TransactionImportService::new(
+        Arc::new(InMemoryImportRepository::new(demo.finance.clone())),
+        Arc::new(InMemoryFinancialAccountRepository::new(demo.finance.clone())),
+    ));
+    let reconciliation = Arc::new(ReconciliationService::new(
+        Arc::new(InMemoryReconciliationRepository::new(demo.finance.clone())),
+        Arc::new(InMemoryFinancialAccountRepository::new(demo.finance.clone())),
+    ));
+
+    Ok(AppContext {
+        config,
+        db: None,
+        auth_service,
+        cache_service: None,
+        user_repository: demo.users,
+        company_repository: Arc::new(demo.companies),
+        license_repository: Arc::new(demo.licenses.clone()),
+        license_work: Arc::new(demo.licenses),
+        search_repository: Arc::new(demo.search),
+        sla_monitor,
+        admin_stats,
+        analytics,
+        certificates,
+        license_verification,
+        notifications,
+        notification_dispatcher,
+        email,
+        live_updates,
+        license_comments,
+        billing,
+        invoicing,
+        imports,
+        reconciliation,
+        midtrans: None,
+    })
+}
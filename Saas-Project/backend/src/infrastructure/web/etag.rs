@@ -0,0 +1,94 @@
+// ETag / If-Match support for versioned resources
+// The ETag of a license, company or financial account is its row version.
+// Clients send it back in `If-Match` to update the version they read; without
+// the header an update applies to whatever version the handler loads.
+
+use async_trait::async_trait;
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderValue},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+use crate::shared::errors::AppError;
+
+/// Strong entity tag for a row version
+pub fn etag(version: i64) -> String {
+    format!("\"{}\"", version)
+}
+
+/// The version named by an `If-Match` header, if the request sent one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct IfMatch(pub Option<i64>);
+
+impl IfMatch {
+    /// Version an update must match: the one the client asked for, or the one
+    /// the handler loaded when the client did not ask
+    pub fn version_or(&self, loaded: i64) -> i64 {
+        self.0.unwrap_or(loaded)
+    }
+
+    fn parse(value: &str) -> Result<Self, AppError> {
+        let value = value.trim();
+        // Any current version matches `*`
+        if value == "*" {
+            return Ok(Self(None));
+        }
+        // Weak tags compare like strong ones for a version number
+        value
+            .strip_prefix("W/")
+            .unwrap_or(value)
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .and_then(|v| v.parse().ok())
+            .map(|version| Self(Some(version)))
+            .ok_or_else(|| AppError::BadRequest(format!("Invalid If-Match header: {}", value)))
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for IfMatch
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        match parts.headers.get(header::IF_MATCH) {
+            None => Ok(Self(None)),
+            Some(value) => value
+                .to_str()
+                .map_err(|_| AppError::BadRequest("Invalid If-Match header".to_string()))
+                .and_then(Self::parse),
+        }
+    }
+}
+
+/// A JSON body sent with the `ETag` of the version it shows
+pub struct Versioned<T>(pub i64, pub T);
+
+impl<T: Serialize> IntoResponse for Versioned<T> {
+    fn into_response(self) -> Response {
+        let mut response = Json(self.1).into_response();
+        if let Ok(value) = HeaderValue::from_str(&etag(self.0)) {
+            response.headers_mut().insert(header::ETAG, value);
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_match_parses_version_tags() {
+        assert_eq!(IfMatch::parse("\"3\"").unwrap(), IfMatch(Some(3)));
+        assert_eq!(IfMatch::parse(" W/\"12\" ").unwrap(), IfMatch(Some(12)));
+        assert_eq!(IfMatch::parse("*").unwrap().version_or(7), 7);
+        assert!(IfMatch::parse("3").is_err());
+        assert!(IfMatch::parse("\"abc\"").is_err());
+    }
+}
@@ -1,22 +1,341 @@
 // Admin dashboard handlers
-#![allow(dead_code)]
+// Operations figures, the SLA dashboard, analytics time series, the audit
+// trail of public license verifications, the outgoing notification queue and
+// account actions on other users.
+// Every route is for admin staff and super admins; which accounts an admin may
+// act on is decided by `UserCommandHandler::handle_admin_action`.
 
-use axum::{http::StatusCode, response::Json, routing::get, Router};
-use serde_json::json;
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+    routing::{get, post, put},
+    Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
 
-/// Placeholder handler for admin endpoints
-pub async fn placeholder() -> Result<Json<serde_json::Value>, StatusCode> {
-    Ok(Json(json!({
-        "message": "Admin endpoint - coming soon",
-        "status": "placeholder"
-    })))
-}
+use crate::{
+    application::{
+        command_handlers::UserCommandHandler,
+        commands::{AdminUserAction, AdminUserCommand},
+    },
+    domain::{
+        admin::{
+            operations_report, OperationsReport, PendingWork, ProcessingPerformance,
+            RegionRegistrations, ReportRange, ReviewerWorkload, ServiceFeeRevenue,
+        },
+        entities::{User, UserRole},
+        filters::{UserFilter, UserSortField},
+        notifications::{DeliveryStatus, NotificationDelivery},
+        sla::SlaDashboard,
+        value_objects::UserId,
+        verification::VerificationLookup,
+    },
+    infrastructure::web::middleware::auth::AuthenticatedUser,
+    shared::{
+        errors::{AppError, AppResult},
+        query::{ListParams, ListQuery, Page},
+    },
+};
+
+use super::AppState;
 
-pub fn routes() -> Router {
+/// Days a report covers when no range is given
+const DEFAULT_REPORT_DAYS: i64 = 30;
+/// Verification lookups and notification deliveries listed when no limit is
+/// given, and the most listed
+const DEFAULT_LOOKUPS: u32 = 50;
+const MAX_LOOKUPS: u32 = 500;
+
+pub fn routes() -> Router<AppState> {
     Router::new()
-        .route("/dashboard", get(|| async { "Admin dashboard" }))
-        .route("/users", get(|| async { "Manage users" }))
-        .route("/licenses/pending", get(|| async { "Pending licenses" }))
-        .route("/reports", get(|| async { "System reports" }))
-        .route("/settings", get(|| async { "System settings" }))
+        .route("/dashboard", get(dashboard))
+        .route("/licenses/pending", get(pending_work))
+        .route("/reports/throughput", get(throughput_report))
+        .route("/reports/reviewers", get(reviewer_report))
+        .route("/reports/registrations", get(registration_report))
+        .route("/reports/revenue", get(revenue_report))
+        .route("/sla", get(sla_dashboard))
+        .route("/verification-lookups", get(verification_lookups))
+        .route("/notification-deliveries", get(notification_deliveries))
+        .route("/users", get(list_users))
+        .route("/users/:id/suspend", post(suspend_user))
+        .route("/users/:id/reactivate", post(reactivate_user))
+        .route("/users/:id/force-password-reset", post(force_password_reset))
+        .route("/users/:id/role", put(change_role))
+        .nest("/analytics", super::analytics::routes())
+        .nest("/billing", super::billing::admin_routes())
+}
+
+pub(super) fn require_admin(user: &AuthenticatedUser) -> AppResult<()> {
+    if user.role != UserRole::SuperAdmin && user.role != UserRole::AdminStaff {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ReportQuery {
+    /// Start of the range, inclusive
+    pub from: Option<DateTime<Utc>>,
+    /// End of the range, exclusive; defaults to now
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl ReportQuery {
+    fn range(&self, now: DateTime<Utc>) -> AppResult<ReportRange> {
+        let to = self.to.unwrap_or(now);
+        let from = self
+            .from
+            .unwrap_or_else(|| to - Duration::days(DEFAULT_REPORT_DAYS));
+        ReportRange::new(from, to).map_err(AppError::Validation)
+    }
+}
+
+/// Every section of the operations dashboard
+async fn dashboard(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Query(query): Query<ReportQuery>,
+) -> AppResult<Json<OperationsReport>> {
+    require_admin(&user)?;
+    let now = Utc::now();
+    let report = operations_report(app_state.admin_stats().as_ref(), query.range(now)?, now).await?;
+    Ok(Json(report))
+}
+
+/// Applications and documents waiting on an admin
+async fn pending_work(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<PendingWork>> {
+    require_admin(&user)?;
+    Ok(Json(app_state.admin_stats().pending_work().await?))
+}
+
+/// Submissions, decisions and approval rate per license type
+async fn throughput_report(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Query(query): Query<ReportQuery>,
+) -> AppResult<Json<Vec<ProcessingPerformance>>> {
+    require_admin(&user)?;
+    let range = query.range(Utc::now())?;
+    Ok(Json(app_state.admin_stats().throughput(&range).await?))
+}
+
+async fn reviewer_report(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Query(query): Query<ReportQuery>,
+) -> AppResult<Json<Vec<ReviewerWorkload>>> {
+    require_admin(&user)?;
+    let range = query.range(Utc::now())?;
+    Ok(Json(app_state.admin_stats().reviewer_workload(&range).await?))
+}
+
+/// Companies registered per province
+async fn registration_report(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Query(query): Query<ReportQuery>,
+) -> AppResult<Json<Vec<RegionRegistrations>>> {
+    require_admin(&user)?;
+    let range = query.range(Utc::now())?;
+    Ok(Json(app_state.admin_stats().registrations_by_province(&range).await?))
+}
+
+/// Service fees on the licenses approved in the range
+async fn revenue_report(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Query(query): Query<ReportQuery>,
+) -> AppResult<Json<Vec<ServiceFeeRevenue>>> {
+    require_admin(&user)?;
+    let range = query.range(Utc::now())?;
+    Ok(Json(app_state.admin_stats().service_fee_revenue(&range).await?))
+}
+
+/// Applications at risk of missing their processing SLA and the share of
+/// decisions each reviewer made within it
+async fn sla_dashboard(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Query(query): Query<ReportQuery>,
+) -> AppResult<Json<SlaDashboard>> {
+    require_admin(&user)?;
+    let now = Utc::now();
+    let range = query.range(now)?;
+    let dashboard = app_state
+        .sla_monitor()
+        .dashboard(now, range.from, range.to)
+        .await?;
+    Ok(Json(dashboard))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct VerificationLookupQuery {
+    pub company_id: Option<Uuid>,
+    pub limit: Option<u32>,
+}
+
+/// Recent public verification lookups, newest first
+async fn verification_lookups(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Query(query): Query<VerificationLookupQuery>,
+) -> AppResult<Json<Vec<VerificationLookup>>> {
+    require_admin(&user)?;
+    let limit = query.limit.unwrap_or(DEFAULT_LOOKUPS).clamp(1, MAX_LOOKUPS);
+    let lookups = app_state
+        .license_verification()
+        .recent_lookups(query.company_id, limit as i64)
+        .await?;
+    Ok(Json(lookups))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DeliveryQuery {
+    pub status: Option<String>,
+    pub limit: Option<u32>,
+}
+
+/// Recent email and WhatsApp deliveries, newest first, e.g. to find failed ones
+async fn notification_deliveries(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Query(query): Query<DeliveryQuery>,
+) -> AppResult<Json<Vec<NotificationDelivery>>> {
+    require_admin(&user)?;
+    let status = query
+        .status
+        .as_deref()
+        .map(str::parse::<DeliveryStatus>)
+        .transpose()
+        .map_err(AppError::Validation)?;
+    let limit = query.limit.unwrap_or(DEFAULT_LOOKUPS).clamp(1, MAX_LOOKUPS);
+    let deliveries = app_state
+        .notifications()
+        .deliveries(status, limit as i64)
+        .await?;
+    Ok(Json(deliveries))
+}
+
+/// An account as admins see it
+#[derive(Debug, Serialize)]
+pub struct AdminUserView {
+    pub id: Uuid,
+    pub email: String,
+    pub full_name: String,
+    pub role: String,
+    pub status: String,
+    pub email_verified: bool,
+    pub password_reset_required: bool,
+    pub sessions_revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<User> for AdminUserView {
+    fn from(user: User) -> Self {
+        Self {
+            id: *user.id.as_uuid(),
+            email: user.email.as_str().to_string(),
+            full_name: user.full_name,
+            role: user.role.to_string(),
+            status: user.status.to_string(),
+            email_verified: user.email_verified_at.is_some(),
+            password_reset_required: user.password_reset_required,
+            sessions_revoked_at: user.sessions_revoked_at,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdminUserActionResponse {
+    pub user: AdminUserView,
+}
+
+async fn list_users(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Query(filter): Query<UserFilter>,
+    Query(params): Query<ListParams>,
+) -> AppResult<Json<Page<AdminUserView>>> {
+    require_admin(&user)?;
+    let query = ListQuery::<UserFilter, UserSortField>::from_params(filter, &params)?;
+    let page = app_state.user_repository().list(&query).await?;
+    Ok(Json(page.map(AdminUserView::from)))
+}
+
+async fn act_on_user(
+    app_state: &AppState,
+    actor: &AuthenticatedUser,
+    target_id: Uuid,
+    action: AdminUserAction,
+) -> AppResult<Json<AdminUserActionResponse>> {
+    require_admin(actor)?;
+    let handler = UserCommandHandler::new(
+        app_state.user_repository().clone(),
+        Arc::new(app_state.auth_service().clone()),
+        app_state.email().clone(),
+    );
+    let user = handler
+        .handle_admin_action(AdminUserCommand::new(
+            actor.user_id.clone(),
+            actor.role.clone(),
+            UserId(target_id),
+            action,
+        ))
+        .await?;
+
+    Ok(Json(AdminUserActionResponse {
+        user: user.into(),
+    }))
+}
+
+/// Blocks sign-in and ends the user's sessions
+async fn suspend_user(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<AdminUserActionResponse>> {
+    act_on_user(&app_state, &user, id, AdminUserAction::Suspend).await
+}
+
+async fn reactivate_user(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<AdminUserActionResponse>> {
+    act_on_user(&app_state, &user, id, AdminUserAction::Reactivate).await
+}
+
+/// Ends the user's sessions and refuses sign-in until they set a new
+/// password at `/auth/reset-password/confirm` with the token emailed to them
+async fn force_password_reset(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<AdminUserActionResponse>> {
+    act_on_user(&app_state, &user, id, AdminUserAction::ForcePasswordReset).await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangeRoleRequest {
+    pub role: String,
+}
+
+async fn change_role(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ChangeRoleRequest>,
+) -> AppResult<Json<AdminUserActionResponse>> {
+    let role = payload.role.parse::<UserRole>().map_err(AppError::Validation)?;
+    act_on_user(&app_state, &user, id, AdminUserAction::ChangeRole(role)).await
 }
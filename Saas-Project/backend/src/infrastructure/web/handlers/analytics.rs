@@ -0,0 +1,149 @@
+// Analytics handlers
+// Time series of license volumes and processing times, registrations and
+// finance totals for the admin dashboards, read from the daily rollups.
+// Nested under the admin routes and limited to admin staff and super admins.
+
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+    Router,
+};
+use chrono::{Duration, NaiveDate, Utc};
+use serde::Deserialize;
+
+use crate::{
+    domain::analytics::{reporting_day, Bucket, Dimension, Metric, RollupRefresh, Series, SeriesQuery},
+    infrastructure::web::middleware::auth::AuthenticatedUser,
+    services::analytics_refresher::AnalyticsRefresher,
+    shared::errors::{AppError, AppResult},
+};
+
+use super::{admin::require_admin, AppState};
+
+/// Days a series covers when no range is given
+const DEFAULT_SERIES_DAYS: i64 = 30;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/refresh", post(refresh))
+        .route("/:metric", get(series))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SeriesParams {
+    /// First day of the range in Jakarta time, inclusive
+    pub from: Option<NaiveDate>,
+    /// Day after the range, exclusive; defaults to tomorrow so today is included
+    pub to: Option<NaiveDate>,
+    /// `day`, `week` or `month`; defaults to `day`
+    pub bucket: Option<String>,
+    /// Comma-separated dimensions, e.g. `type,province`
+    pub group_by: Option<String>,
+}
+
+impl SeriesParams {
+    fn query(&self, metric: Metric, today: NaiveDate) -> AppResult<SeriesQuery> {
+        let to = self.to.unwrap_or(today + Duration::days(1));
+        let from = self
+            .from
+            .unwrap_or_else(|| to - Duration::days(DEFAULT_SERIES_DAYS));
+        let bucket = match &self.bucket {
+            Some(bucket) => bucket.parse::<Bucket>().map_err(AppError::Validation)?,
+            None => Bucket::default(),
+        };
+        let group_by = self
+            .group_by
+            .iter()
+            .flat_map(|dimensions| dimensions.split(','))
+            .map(str::trim)
+            .filter(|dimension| !dimension.is_empty())
+            .map(|dimension| dimension.parse::<Dimension>().map_err(AppError::Validation))
+            .collect::<AppResult<Vec<_>>>()?;
+        SeriesQuery::new(metric, from, to, bucket, group_by).map_err(AppError::Validation)
+    }
+}
+
+/// `licenses`, `registrations` or `finance` bucketed and grouped as asked
+async fn series(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(metric): Path<String>,
+    Query(params): Query<SeriesParams>,
+) -> AppResult<Response> {
+    require_admin(&user)?;
+    let metric = metric.parse::<Metric>().map_err(AppError::NotFound)?;
+    let query = params.query(metric, reporting_day(Utc::now()))?;
+    let analytics = app_state.analytics();
+
+    let response = match metric {
+        Metric::Licenses => {
+            let points = analytics.license_series(&query).await?;
+            Json(Series { query, points }).into_response()
+        }
+        Metric::Registrations => {
+            let points = analytics.registration_series(&query).await?;
+            Json(Series { query, points }).into_response()
+        }
+        Metric::Finance => {
+            let points = analytics.finance_series(&query).await?;
+            Json(Series { query, points }).into_response()
+        }
+    };
+    Ok(response)
+}
+
+/// Brings the rollups up to date now instead of at the next scheduled refresh
+async fn refresh(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<RollupRefresh>> {
+    require_admin(&user)?;
+    let refresher = AnalyticsRefresher::new(app_state.analytics().clone());
+    Ok(Json(refresher.refresh().await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(from: Option<&str>, to: Option<&str>, bucket: Option<&str>, group_by: Option<&str>) -> SeriesParams {
+        SeriesParams {
+            from: from.map(|d| d.parse().unwrap()),
+            to: to.map(|d| d.parse().unwrap()),
+            bucket: bucket.map(str::to_string),
+            group_by: group_by.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_series_params_defaults_to_the_last_thirty_days_by_day() {
+        let today: NaiveDate = "2024-05-20".parse().unwrap();
+        let query = params(None, None, None, None).query(Metric::Licenses, today).unwrap();
+        assert_eq!(query.to, "2024-05-21".parse::<NaiveDate>().unwrap());
+        assert_eq!(query.from, "2024-04-21".parse::<NaiveDate>().unwrap());
+        assert_eq!(query.bucket, Bucket::Day);
+        assert!(query.group_by.is_empty());
+    }
+
+    #[test]
+    fn test_series_params_parse_bucket_and_group_by() {
+        let today: NaiveDate = "2024-05-20".parse().unwrap();
+        let query = params(Some("2024-01-01"), Some("2024-04-01"), Some("month"), Some("type, province"))
+            .query(Metric::Finance, today)
+            .unwrap();
+        assert_eq!(query.bucket, Bucket::Month);
+        assert_eq!(query.group_by, vec![Dimension::Type, Dimension::Province]);
+
+        for bad in [
+            params(None, None, Some("year"), None),
+            params(None, None, None, Some("city")),
+            params(Some("2024-02-01"), Some("2024-01-01"), None, None),
+        ] {
+            assert!(matches!(bad.query(Metric::Licenses, today), Err(AppError::Validation(_))));
+        }
+        assert!(params(None, None, None, Some("status"))
+            .query(Metric::Registrations, today)
+            .is_err());
+    }
+}
@@ -1,10 +1,15 @@
 use axum::{extract::State, http::StatusCode, response::Json};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::sync::Arc;
+
+use crate::application::command_handlers::UserCommandHandler;
 
 use crate::domain::entities::{User, UserRole};
-use crate::domain::value_objects::Email;
+use crate::domain::value_objects::{Email, PhoneNumber};
+use crate::infrastructure::monitoring::{record_user_registration, track_auth_operation};
 use crate::infrastructure::web::middleware::auth::AuthenticatedUser;
+use crate::shared::errors::{AppError, AppResult};
 
 // Use the AppState from the handlers module
 use super::AppState;
@@ -14,6 +19,7 @@ pub struct UserProfileResponse {
     pub id: String,
     pub email: String,
     pub role: String,
+    pub phone: Option<PhoneNumber>,
     pub is_verified: bool,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
@@ -34,6 +40,7 @@ pub struct RegisterDto {
     pub email: String,
     pub password: String,
     pub full_name: String,
+    pub phone: Option<PhoneNumber>,
     pub role: Option<String>,
 }
 
@@ -84,7 +91,8 @@ pub async fn register(
         })?;
 
     // Create user
-    let user = User::new(email, password_hash, payload.full_name, role);
+    let mut user = User::new(email, password_hash, payload.full_name, role);
+    user.phone = payload.phone;
 
     // Save user to database
     state.user_repository().save(&user).await.map_err(|err| {
@@ -97,6 +105,9 @@ pub async fn register(
         )
     })?;
 
+    track_auth_operation("register", "success");
+    record_user_registration(&user.role.to_string());
+
     Ok(Json(json!({
         "message": "User registered successfully",
         "user_id": user.id.to_string(),
@@ -135,6 +146,7 @@ pub async fn login(
             )
         })?
         .ok_or_else(|| {
+            track_auth_operation("login", "invalid_credentials");
             (
                 StatusCode::UNAUTHORIZED,
                 Json(json!({
@@ -157,6 +169,7 @@ pub async fn login(
         })?;
 
     if !is_valid {
+        track_auth_operation("login", "invalid_credentials");
         return Err((
             StatusCode::UNAUTHORIZED,
             Json(json!({
@@ -167,6 +180,7 @@ pub async fn login(
 
     // Check if user can login (active and verified)
     if !user.can_login() {
+        track_auth_operation("login", "inactive");
         return Err((
             StatusCode::FORBIDDEN,
             Json(json!({
@@ -175,6 +189,17 @@ pub async fn login(
         ));
     }
 
+    if user.password_reset_required {
+        track_auth_operation("login", "password_reset_required");
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "error": "Password reset required",
+                "code": "PASSWORD_RESET_REQUIRED"
+            })),
+        ));
+    }
+
     // Update last login
     user.update_last_login();
 
@@ -200,6 +225,8 @@ pub async fn login(
         )
     })?;
 
+    track_auth_operation("login", "success");
+
     Ok(Json(json!({
         "access_token": tokens.access_token,
         "refresh_token": tokens.refresh_token,
@@ -244,6 +271,7 @@ pub async fn get_profile(
         id: user_data.id.to_string(),
         email: user_data.email.as_str().to_string(),
         role: user_data.role.to_string(),
+        phone: user_data.phone.clone(),
         is_verified: user_data.email_verified_at.is_some(),
         created_at: user_data.created_at,
     };
@@ -269,6 +297,7 @@ pub async fn refresh_token(
         .auth_service()
         .validate_token(refresh_token)
         .map_err(|_| {
+            track_auth_operation("refresh", "invalid_token");
             (
                 StatusCode::UNAUTHORIZED,
                 Json(json!({"error": "Invalid refresh token"})),
@@ -294,7 +323,9 @@ pub async fn refresh_token(
                 Json(json!({"error": "Database error", "details": err.to_string()})),
             )
         })?
+        .filter(|user| user.can_use_session(claims.iat))
         .ok_or_else(|| {
+            track_auth_operation("refresh", "revoked");
             (
                 StatusCode::UNAUTHORIZED,
                 Json(json!({"error": "Invalid refresh token"})),
@@ -319,6 +350,8 @@ pub async fn refresh_token(
         }
     }
 
+    track_auth_operation("refresh", "success");
+
     Ok(Json(json!({
         "access_token": tokens.access_token,
         "refresh_token": tokens.refresh_token,
@@ -362,6 +395,38 @@ pub async fn request_password_reset(
     })))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ConfirmPasswordResetDto {
+    pub email: String,
+    pub token: String,
+    pub new_password: String,
+}
+
+/// Sets a new password with the one-time token from a forced reset
+pub async fn confirm_password_reset(
+    State(state): State<AppState>,
+    Json(payload): Json<ConfirmPasswordResetDto>,
+) -> AppResult<Json<serde_json::Value>> {
+    let email = Email::new(&payload.email).map_err(AppError::Validation)?;
+    let handler = UserCommandHandler::new(
+        state.user_repository().clone(),
+        Arc::new(state.auth_service().clone()),
+        state.email().clone(),
+    );
+    let result = handler
+        .handle_confirm_password_reset(&email, &payload.token, &payload.new_password)
+        .await;
+    track_auth_operation(
+        "password_reset",
+        if result.is_ok() { "success" } else { "rejected" },
+    );
+    result?;
+
+    Ok(Json(json!({
+        "message": "Password has been reset, please sign in again"
+    })))
+}
+
 /// Health check for auth service
 pub async fn health_check() -> Json<serde_json::Value> {
     Json(json!({
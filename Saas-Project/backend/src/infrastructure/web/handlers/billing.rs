@@ -0,0 +1,196 @@
+// Subscription billing handlers
+// The plan catalogue, and for each company its subscription, invoices and
+// entitlements. Only the company's owner and super admins see or change them.
+// Midtrans reports payments to a public endpoint; a notification needs a valid
+// signature, and its status is then fetched back from Midtrans. Admins record
+// payments made some other way, such as a bank transfer.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde::Deserialize;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::domain::billing::{Entitlements, Subscription, SubscriptionInvoice};
+use crate::domain::companies::{Company, SubscriptionPlan};
+use crate::domain::entities::UserRole;
+use crate::infrastructure::web::middleware::auth::AuthenticatedUser;
+use crate::services::billing::{PlanChangeResult, PlanOffer, SubscriptionOverview};
+use crate::services::payment::{MidtransNotification, PaymentOutcome};
+use crate::shared::errors::{AppError, AppResult};
+
+use super::admin::require_admin;
+use super::AppState;
+
+/// Invoices listed when no limit is given, and the most listed
+const DEFAULT_INVOICES: u32 = 12;
+const MAX_INVOICES: u32 = 100;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/plans", get(plans))
+        .route(
+            "/companies/:company_id/subscription",
+            get(subscription).put(change_plan).delete(cancel_subscription),
+        )
+        .route("/companies/:company_id/invoices", get(invoices))
+        .route("/companies/:company_id/entitlements", get(entitlements))
+}
+
+/// Payment gateway callbacks; public, they are checked by signature
+pub fn payment_routes() -> Router<AppState> {
+    Router::new().route("/midtrans/notifications", post(midtrans_notification))
+}
+
+/// Mounted under `/admin/billing`
+pub fn admin_routes() -> Router<AppState> {
+    Router::new().route("/invoices/:id/payments", post(record_payment))
+}
+
+/// The company, if `user` owns it or is a super admin
+pub(super) async fn managed_company(app_state: &AppState, user: &AuthenticatedUser, company_id: Uuid) -> AppResult<Company> {
+    let company = app_state
+        .company_repository()
+        .find_by_id(&company_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Company not found".to_string()))?;
+    if company.owner_id != *user.user_id.as_uuid() && user.role != UserRole::SuperAdmin {
+        return Err(AppError::Forbidden(
+            "You don't have permission to access this company".to_string(),
+        ));
+    }
+    Ok(company)
+}
+
+async fn plans(State(app_state): State<AppState>) -> Json<Vec<PlanOffer>> {
+    Json(app_state.billing().plans())
+}
+
+async fn subscription(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(company_id): Path<Uuid>,
+) -> AppResult<Json<SubscriptionOverview>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    Ok(Json(app_state.billing().overview(company.id).await?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangePlanRequest {
+    pub plan: SubscriptionPlan,
+}
+
+/// Subscribes, upgrades at once or schedules a downgrade for the period end
+async fn change_plan(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(company_id): Path<Uuid>,
+    Json(request): Json<ChangePlanRequest>,
+) -> AppResult<Json<PlanChangeResult>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let result = app_state.billing().change_plan(&company, request.plan).await?;
+    info!(
+        "💳 Company {} asked for the {} plan, now {} ({})",
+        company.id, request.plan, result.subscription.plan, result.subscription.status
+    );
+    Ok(Json(result))
+}
+
+/// Cancels at the end of the period; the company then falls back to free
+async fn cancel_subscription(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(company_id): Path<Uuid>,
+) -> AppResult<Json<Subscription>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    Ok(Json(app_state.billing().cancel(company.id).await?))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct InvoiceQuery {
+    pub limit: Option<u32>,
+}
+
+/// Newest invoices first
+async fn invoices(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(company_id): Path<Uuid>,
+    Query(query): Query<InvoiceQuery>,
+) -> AppResult<Json<Vec<SubscriptionInvoice>>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let limit = query.limit.unwrap_or(DEFAULT_INVOICES).clamp(1, MAX_INVOICES);
+    Ok(Json(app_state.billing().invoices(company.id, limit as i64).await?))
+}
+
+/// The plan's limits and how much of them is used
+async fn entitlements(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(company_id): Path<Uuid>,
+) -> AppResult<Json<Entitlements>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    Ok(Json(app_state.billing().entitlements(company.id).await?))
+}
+
+/// Midtrans retries until it gets a 200, so notifications about payments that
+/// are still pending, or that were already recorded, are acknowledged too
+async fn midtrans_notification(
+    State(app_state): State<AppState>,
+    Json(notification): Json<MidtransNotification>,
+) -> AppResult<StatusCode> {
+    if !notification.is_signed_with(&app_state.config().external_apis.midtrans_server_key) {
+        return Err(AppError::Unauthorized("Invalid notification signature".to_string()));
+    }
+    // Acted on as Midtrans reports it when asked, not as the notification says
+    let notification = match app_state.midtrans() {
+        Some(midtrans) => midtrans.transaction_status(&notification.order_id).await?,
+        None => notification,
+    };
+    let billing = app_state.billing();
+    let invoice = billing.invoice_by_number(&notification.order_id).await?;
+
+    match notification.outcome() {
+        PaymentOutcome::Paid { reference } => {
+            if notification.amount() != Some(invoice.total.amount) {
+                return Err(AppError::Validation(format!(
+                    "Paid amount {} does not match invoice {}",
+                    notification.gross_amount, invoice.number
+                )));
+            }
+            let invoice = billing.record_payment(invoice, Some(reference)).await?;
+            info!("💳 Invoice {} paid through Midtrans", invoice.number);
+        }
+        PaymentOutcome::Failed { reason } => {
+            billing.record_payment_failure(invoice, reason).await?;
+        }
+        PaymentOutcome::Pending => {}
+    }
+    Ok(StatusCode::OK)
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RecordPaymentRequest {
+    /// Bank transfer or receipt reference
+    pub reference: Option<String>,
+}
+
+/// Marks an open invoice paid by other means (admin only)
+async fn record_payment(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(invoice_id): Path<Uuid>,
+    Json(request): Json<RecordPaymentRequest>,
+) -> AppResult<Json<SubscriptionInvoice>> {
+    require_admin(&user)?;
+    let billing = app_state.billing();
+    let invoice = billing.invoice(invoice_id).await?;
+    let invoice = billing.record_payment(invoice, request.reference).await?;
+    info!("💳 Invoice {} marked paid by {}", invoice.number, user.user_id.as_uuid());
+    Ok(Json(invoice))
+}
@@ -10,11 +10,19 @@ use uuid::Uuid;
 
 use crate::{
     domain::{
+        billing::Requirement,
         companies::{BusinessScale, BusinessType, Company, CompanyStatus},
         entities::UserRole,
+        filters::{CompanyFilter, CompanySortField},
+        value_objects::{PhoneNumber, NPWP},
+    },
+    infrastructure::{
+        monitoring::record_company_registered,
+        web::etag::{IfMatch, Versioned},
+        web::middleware::auth::AuthenticatedUser,
     },
-    infrastructure::web::middleware::auth::AuthenticatedUser,
     shared::errors::{AppError, AppResult},
+    shared::query::{ListParams, ListQuery, Page},
 };
 
 // Import the AppState from handlers module
@@ -31,13 +39,13 @@ pub struct CreateCompanyRequest {
     pub address_city: String,
     pub address_province: String,
     pub address_postal_code: String,
-    pub phone: Option<String>,
+    pub phone: Option<PhoneNumber>,
     pub email: Option<String>,
     pub website: Option<String>,
     pub nib: Option<String>,
     pub siup: Option<String>,
     pub tdp: Option<String>,
-    pub npwp: Option<String>,
+    pub npwp: Option<NPWP>,
     pub employee_count: Option<i32>,
     pub annual_revenue: Option<i64>,
 }
@@ -52,13 +60,13 @@ pub struct UpdateCompanyRequest {
     pub address_city: Option<String>,
     pub address_province: Option<String>,
     pub address_postal_code: Option<String>,
-    pub phone: Option<String>,
+    pub phone: Option<PhoneNumber>,
     pub email: Option<String>,
     pub website: Option<String>,
     pub nib: Option<String>,
     pub siup: Option<String>,
     pub tdp: Option<String>,
-    pub npwp: Option<String>,
+    pub npwp: Option<NPWP>,
     pub employee_count: Option<i32>,
     pub annual_revenue: Option<i64>,
 }
@@ -73,13 +81,13 @@ pub struct CompanyResponse {
     pub industry: String,
     pub description: Option<String>,
     pub address: CompanyAddressResponse,
-    pub phone: Option<String>,
+    pub phone: Option<PhoneNumber>,
     pub email: Option<String>,
     pub website: Option<String>,
     pub nib: Option<String>,
     pub siup: Option<String>,
     pub tdp: Option<String>,
-    pub npwp: Option<String>,
+    pub npwp: Option<NPWP>,
     pub employee_count: Option<i32>,
     pub annual_revenue: Option<i64>,
     pub status: String,
@@ -87,6 +95,7 @@ pub struct CompanyResponse {
     pub verification_notes: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub version: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -97,21 +106,6 @@ pub struct CompanyAddressResponse {
     pub postal_code: String,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct ListCompaniesQuery {
-    pub limit: Option<i32>,
-    pub offset: Option<i32>,
-    pub search: Option<String>,
-}
-
-#[derive(Debug, Serialize)]
-pub struct ListCompaniesResponse {
-    pub companies: Vec<CompanyResponse>,
-    pub total: i64,
-    pub limit: i32,
-    pub offset: i32,
-}
-
 // Helper function to convert Company domain entity to response DTO
 fn company_to_response(company: &Company) -> CompanyResponse {
     CompanyResponse {
@@ -146,6 +140,7 @@ fn company_to_response(company: &Company) -> CompanyResponse {
         verification_notes: company.verification_notes.clone(),
         created_at: company.created_at,
         updated_at: company.updated_at,
+        version: company.version,
     }
 }
 
@@ -203,6 +198,12 @@ pub async fn create_company(
     let business_type =
         validate_business_type(&payload.business_type).map_err(|e| AppError::Validation(e))?;
 
+    // The owner's best plan caps how many companies they may register
+    state
+        .billing()
+        .require_for_owner(*user.user_id.as_uuid(), Requirement::Company)
+        .await?;
+
     // Determine business scale
     let business_scale = determine_business_scale(payload.annual_revenue, payload.employee_count);
 
@@ -242,10 +243,12 @@ pub async fn create_company(
         status: CompanyStatus::Active.to_string(),
         created_at: chrono::Utc::now(),
         updated_at: chrono::Utc::now(),
+        version: 1,
     };
 
     // Save to repository
     company_repo.save(&company).await?;
+    record_company_registered(&company.business_scale, &company.address_province);
 
     let response = company_to_response(&company);
     Ok((StatusCode::CREATED, Json(response)))
@@ -255,7 +258,7 @@ pub async fn get_company(
     State(state): State<AppState>,
     user: AuthenticatedUser,
     Path(company_id): Path<Uuid>,
-) -> AppResult<Json<CompanyResponse>> {
+) -> AppResult<Versioned<CompanyResponse>> {
     let company_repo = state.company_repository();
 
     let company = company_repo
@@ -271,15 +274,16 @@ pub async fn get_company(
     }
 
     let response = company_to_response(&company);
-    Ok(Json(response))
+    Ok(Versioned(company.version, response))
 }
 
 pub async fn update_company(
     State(state): State<AppState>,
     user: AuthenticatedUser,
     Path(company_id): Path<Uuid>,
+    if_match: IfMatch,
     Json(payload): Json<UpdateCompanyRequest>,
-) -> AppResult<Json<CompanyResponse>> {
+) -> AppResult<Versioned<CompanyResponse>> {
     let company_repo = state.company_repository();
 
     let mut company = company_repo
@@ -354,12 +358,14 @@ pub async fn update_company(
     }
 
     company.updated_at = chrono::Utc::now();
+    company.version = if_match.version_or(company.version);
 
-    // Save updated company
+    // Save updated company, unless someone else changed it in the meantime
     company_repo.update(&company).await?;
+    company.version += 1;
 
     let response = company_to_response(&company);
-    Ok(Json(response))
+    Ok(Versioned(company.version, response))
 }
 
 pub async fn delete_company(
@@ -389,48 +395,18 @@ pub async fn delete_company(
 pub async fn list_companies(
     State(state): State<AppState>,
     user: AuthenticatedUser,
-    Query(query): Query<ListCompaniesQuery>,
-) -> AppResult<Json<ListCompaniesResponse>> {
-    let company_repo = state.company_repository();
-
-    let limit = query.limit.unwrap_or(20).min(100); // Max 100 items per page
-    let offset = query.offset.unwrap_or(0);
-
-    let companies = if user.role == UserRole::SuperAdmin {
-        // Admin can see all companies
-        if let Some(search_query) = query.search {
-            company_repo
-                .search(&search_query, Some(limit), Some(offset))
-                .await?
-        } else {
-            company_repo.list_all(Some(limit), Some(offset)).await?
-        }
-    } else {
-        // Regular users can only see their own companies
-        company_repo
-            .find_by_owner_id(user.user_id.as_uuid())
-            .await?
-    };
-
-    let total = if user.role == UserRole::SuperAdmin {
-        // For admin, we need to count all companies
-        // For now, we'll use the companies count as approximation
-        companies.len() as i64
-    } else {
-        company_repo.count_by_owner(user.user_id.as_uuid()).await?
-    };
-
-    let response_companies: Vec<CompanyResponse> =
-        companies.iter().map(company_to_response).collect();
+    Query(mut filter): Query<CompanyFilter>,
+    Query(params): Query<ListParams>,
+) -> AppResult<Json<Page<CompanyResponse>>> {
+    // Admin can see all companies, regular users only their own
+    if user.role != UserRole::SuperAdmin {
+        filter.owner_id = Some(*user.user_id.as_uuid());
+    }
 
-    let response = ListCompaniesResponse {
-        companies: response_companies,
-        total,
-        limit,
-        offset,
-    };
+    let query = ListQuery::<CompanyFilter, CompanySortField>::from_params(filter, &params)?;
+    let page = state.company_repository().list(&query).await?;
 
-    Ok(Json(response))
+    Ok(Json(page.map(|company| company_to_response(&company))))
 }
 
 pub async fn get_my_companies(
@@ -0,0 +1,128 @@
+// Live update stream
+// Server-sent events for the signed-in user. Owners get changes to their own
+// licenses and documents and comments addressed to them; admins also get new
+// submissions, status changes and comments across the review queue. A
+// `resync` event means updates were missed and the client should refetch
+// what it shows. Browsers' EventSource cannot set headers, so it opens the
+// stream with `?ticket=`, a short-lived ticket from `POST /events/ticket`.
+
+use axum::{
+    extract::State,
+    response::sse::{Event, KeepAlive, Sse},
+    routing::get,
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream, StreamExt};
+use serde::Serialize;
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::domain::entities::UserRole;
+use crate::domain::live_updates::{LiveEvent, LiveUpdate};
+use crate::infrastructure::live_updates::HubMessage;
+use crate::infrastructure::monitoring::record_live_update_stream;
+use crate::infrastructure::web::middleware::auth::AuthenticatedUser;
+use crate::shared::errors::{AppError, AppResult};
+
+use super::AppState;
+
+/// How long clients wait before reconnecting a dropped stream
+const RECONNECT_AFTER: Duration = Duration::from_secs(3);
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/stream", get(stream_updates))
+}
+
+#[derive(Serialize)]
+pub struct StreamTicket {
+    pub ticket: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Ticket for opening one stream as `/events/stream?ticket=`; fetch a new one
+/// for every (re)connect
+pub async fn issue_stream_ticket(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<StreamTicket>> {
+    let auth = app_state.auth_service();
+    let ticket = auth
+        .generate_stream_ticket(&user.user_id, &user.role)
+        .map_err(|e| AppError::InternalError(format!("Failed to issue stream ticket: {}", e)))?;
+    Ok(Json(StreamTicket {
+        ticket,
+        expires_at: Utc::now() + auth.stream_ticket_duration(),
+    }))
+}
+
+/// Counts the stream as open for as long as it lives
+struct OpenStream;
+
+impl OpenStream {
+    fn new() -> Self {
+        record_live_update_stream(1.0);
+        Self
+    }
+}
+
+impl Drop for OpenStream {
+    fn drop(&mut self) {
+        record_live_update_stream(-1.0);
+    }
+}
+
+#[derive(Serialize)]
+struct Pushed<'a> {
+    occurred_at: DateTime<Utc>,
+    #[serde(flatten)]
+    event: &'a LiveEvent,
+}
+
+fn update_event(update: &LiveUpdate) -> Event {
+    let data = serde_json::to_string(&Pushed {
+        occurred_at: update.occurred_at,
+        event: &update.event,
+    })
+    .expect("live update serializes");
+    Event::default().event(update.event.name()).data(data)
+}
+
+fn resync_event() -> Event {
+    Event::default().event("resync").data("{}")
+}
+
+/// `ready` once subscribed, then the updates the user may see
+async fn stream_updates(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let user_id = *user.user_id.as_uuid();
+    let is_admin = matches!(user.role, UserRole::SuperAdmin | UserRole::AdminStaff);
+    let receiver = app_state.live_updates().subscribe();
+
+    let ready = Event::default()
+        .event("ready")
+        .retry(RECONNECT_AFTER)
+        .data("{}");
+    let updates = stream::unfold(
+        (receiver, OpenStream::new()),
+        move |(mut receiver, open)| async move {
+            loop {
+                let event = match receiver.recv().await {
+                    Ok(HubMessage::Update(update)) if update.visible_to(user_id, is_admin) => {
+                        update_event(&update)
+                    }
+                    Ok(HubMessage::Update(_)) => continue,
+                    // Too far behind to catch up; start over from a fresh fetch
+                    Ok(HubMessage::Resync) | Err(RecvError::Lagged(_)) => resync_event(),
+                    Err(RecvError::Closed) => return None,
+                };
+                return Some((Ok(event), (receiver, open)));
+            }
+        },
+    );
+
+    Sse::new(stream::once(async { Ok(ready) }).chain(updates)).keep_alive(KeepAlive::default())
+}
@@ -2,7 +2,7 @@
 #![allow(dead_code)]
 
 use axum::{
-    extract::{Json, Path, State},
+    extract::{Json, Path, Query, State},
     routing::{get, post},
     Router,
 };
@@ -13,14 +13,18 @@ use uuid::Uuid;
 
 use crate::{
     domain::{
+        filters::{TransactionFilter, TransactionSortField},
         finance::{
-            AccountType, FinancialAccount, FinancialAccountRepository, FinancialService,
-            Transaction, TransactionId, TransactionRepository, TransactionStatus, TransactionType,
+            AccountType, FinanceRepositories, FinancialAccount, FinancialAccountRepository,
+            FinancialService, Transaction, TransactionId, TransactionRepository,
+            TransactionStatus, TransactionType,
         },
+        unit_of_work::UnitOfWork,
         value_objects::{Currency, Money},
     },
     infrastructure::{cache::CacheService, web::middleware::auth::AuthenticatedUser},
     shared::errors::AppError,
+    shared::query::{ListParams, ListQuery, Page},
 };
 
 // --------------------
@@ -53,8 +57,11 @@ pub struct TransactionResponse {
     pub account_id: Uuid,
     pub category_id: Option<Uuid>,
     pub tags: Vec<String>,
+    /// Set once a bank reconciliation cleared the transaction; it is then read-only
+    pub reconciled_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub version: i64,
 }
 
 impl From<Transaction> for TransactionResponse {
@@ -71,8 +78,10 @@ impl From<Transaction> for TransactionResponse {
             account_id: tx.account_id,
             category_id: tx.category_id,
             tags: tx.tags,
+            reconciled_at: tx.reconciled_at,
             created_at: tx.created_at,
             updated_at: tx.updated_at,
+            version: tx.version,
         }
     }
 }
@@ -97,6 +106,7 @@ pub struct AccountResponse {
     pub description: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub version: i64,
 }
 
 impl From<FinancialAccount> for AccountResponse {
@@ -111,32 +121,11 @@ impl From<FinancialAccount> for AccountResponse {
             description: account.description,
             created_at: account.created_at,
             updated_at: account.updated_at,
+            version: account.version,
         }
     }
 }
 
-#[derive(Debug, Deserialize)]
-pub struct TransactionListQuery {
-    pub limit: Option<i64>,
-    pub offset: Option<i64>,
-    pub status: Option<String>,
-    pub transaction_type: Option<String>,
-    pub account_id: Option<String>,
-    pub min_amount: Option<String>,
-    pub max_amount: Option<String>,
-    pub start_date: Option<String>,
-    pub end_date: Option<String>,
-    pub search: Option<String>,
-}
-
-#[derive(Debug, Serialize)]
-pub struct TransactionListResponse {
-    pub transactions: Vec<TransactionResponse>,
-    pub total: i64,
-    pub limit: i64,
-    pub offset: i64,
-}
-
 // --------------------
 // Handler Functions
 // --------------------
@@ -174,10 +163,7 @@ where
     transaction.tags = req.tags.unwrap_or_default();
 
     // Execute the transaction
-    let service = FinancialService::new(
-        state.transaction_repository.clone(),
-        state.account_repository.clone(),
-    );
+    let service = FinancialService::new(state.unit_of_work.clone());
 
     let result = service.execute_transaction(&mut transaction).await?;
 
@@ -223,6 +209,25 @@ where
     Ok(Json(result.into()))
 }
 
+pub async fn list_transactions<T, A>(
+    State(state): State<AppState<T, A>>,
+    auth_user: AuthenticatedUser,
+    Query(mut filter): Query<TransactionFilter>,
+    Query(params): Query<ListParams>,
+) -> Result<Json<Page<TransactionResponse>>, AppError>
+where
+    T: TransactionRepository + Send + Sync + Clone + 'static,
+    A: FinancialAccountRepository + Send + Sync + Clone + 'static,
+{
+    // Transactions are always scoped to the caller's company
+    filter.company_id = Some(auth_user.company_id);
+    let query = ListQuery::<TransactionFilter, TransactionSortField>::from_params(filter, &params)?;
+
+    let page = state.transaction_repository.list(&query).await?;
+
+    Ok(Json(page.map(TransactionResponse::from)))
+}
+
 pub async fn create_account<T, A>(
     State(state): State<AppState<T, A>>,
     auth_user: AuthenticatedUser,
@@ -298,6 +303,8 @@ where
 {
     transaction_repository: Arc<T>,
     account_repository: Arc<A>,
+    /// Books transactions, writing the account and the transaction atomically
+    unit_of_work: Arc<dyn UnitOfWork<FinanceRepositories>>,
     cache: Option<Arc<CacheService>>,
 }
 
@@ -340,18 +347,21 @@ async fn placeholder_accounts() -> Json<serde_json::Value> {
 }
 
 // Helper function to create the fully functional router with repositories
-pub fn create_finance_router<T, A>(
+pub fn create_finance_router<T, A, U>(
     transaction_repository: T,
     account_repository: A,
+    unit_of_work: U,
     cache: Option<CacheService>,
 ) -> Router
 where
     T: TransactionRepository + Send + Sync + Clone + 'static,
     A: FinancialAccountRepository + Send + Sync + Clone + 'static,
+    U: UnitOfWork<FinanceRepositories> + 'static,
 {
     let state = AppState {
         transaction_repository: Arc::new(transaction_repository),
         account_repository: Arc::new(account_repository),
+        unit_of_work: Arc::new(unit_of_work),
         cache: cache.map(Arc::new),
     };
 
@@ -360,7 +370,7 @@ where
         .route("/transactions/:id", get(handler_get_transaction::<T, A>))
         .route("/accounts", post(handler_create_account::<T, A>))
         .route("/accounts", get(handler_list_accounts::<T, A>))
-        .route("/transactions", get(|| async { "List transactions" }))
+        .route("/transactions", get(handler_list_transactions::<T, A>))
         .route("/reports", get(|| async { "Financial reports" }))
         .route("/tax", get(|| async { "Tax management" }))
         .route("/payments", post(|| async { "Process payment" }))
@@ -395,6 +405,19 @@ where
     get_transaction(State(state), Path(id)).await
 }
 
+async fn handler_list_transactions<T, A>(
+    State(state): State<AppState<T, A>>,
+    auth_user: AuthenticatedUser,
+    filter: Query<TransactionFilter>,
+    params: Query<ListParams>,
+) -> Result<Json<Page<TransactionResponse>>, AppError>
+where
+    T: TransactionRepository + Send + Sync + Clone + 'static,
+    A: FinancialAccountRepository + Send + Sync + Clone + 'static,
+{
+    list_transactions(State(state), auth_user, filter, params).await
+}
+
 async fn handler_create_account<T, A>(
     State(state): State<AppState<T, A>>,
     auth_user: AuthenticatedUser,
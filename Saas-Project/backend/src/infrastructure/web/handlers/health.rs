@@ -0,0 +1,34 @@
+// Kubernetes-style probes
+// `/livez` only says the process is serving requests; restarting an instance
+// does not fix a database outage. `/readyz` checks the dependencies and answers
+// 503 while the instance should not receive traffic.
+
+use axum::{extract::State, http::StatusCode, response::Json};
+use serde_json::{json, Value};
+
+use crate::infrastructure::health::{readiness, HealthReport};
+
+use super::AppState;
+
+pub async fn livez() -> Json<Value> {
+    Json(json!({
+        "status": "up",
+        "version": env!("CARGO_PKG_VERSION"),
+    }))
+}
+
+pub async fn readyz(State(state): State<AppState>) -> (StatusCode, Json<HealthReport>) {
+    let report = readiness(
+        state.config(),
+        state.database(),
+        state.cache_service().as_ref(),
+    )
+    .await;
+
+    let status = if report.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report))
+}
@@ -0,0 +1,308 @@
+// Transaction import handlers - upload, preview and commit of spreadsheets and
+// bank statements, plus saved column mappings. Mounted under `/finance`, so the
+// caller's plan must include the finance module; only the company's owner and
+// super admins import into its accounts.
+
+use axum::{
+    extract::{DefaultBodyLimit, Json, Multipart, Path, State},
+    http::StatusCode,
+    routing::{delete, get, post},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    domain::imports::{ColumnMapping, ImportBatch, ImportMapping, ImportRequest, StatementLayout},
+    infrastructure::{
+        spreadsheet::{read_table, SpreadsheetFormat},
+        web::middleware::auth::AuthenticatedUser,
+    },
+    shared::errors::{AppError, AppResult},
+};
+
+use super::billing::managed_company;
+use super::AppState;
+
+/// Statements are small; this still leaves room for a year of daily mutations
+const MAX_IMPORT_FILE_BYTES: usize = 10 * 1024 * 1024;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/imports/layouts", get(list_layouts))
+        .route(
+            "/companies/:company_id/imports/mappings",
+            get(list_mappings).post(save_mapping),
+        )
+        .route(
+            "/companies/:company_id/imports/mappings/:mapping_id",
+            delete(delete_mapping),
+        )
+        .route(
+            "/companies/:company_id/imports/preview",
+            post(preview_import).layer(DefaultBodyLimit::max(MAX_IMPORT_FILE_BYTES)),
+        )
+        .route("/companies/:company_id/imports/:import_id", get(get_import))
+        .route("/companies/:company_id/imports/:import_id/commit", post(commit_import))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SaveMappingRequest {
+    pub name: String,
+    pub mapping: ColumnMapping,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LayoutResponse {
+    pub layout: StatementLayout,
+    pub mapping: ColumnMapping,
+}
+
+/// Built-in bank statement layouts and their column mappings
+async fn list_layouts() -> Json<Vec<LayoutResponse>> {
+    Json(
+        StatementLayout::banks()
+            .into_iter()
+            .filter_map(|layout| layout.mapping().map(|mapping| LayoutResponse { layout, mapping }))
+            .collect(),
+    )
+}
+
+async fn list_mappings(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(company_id): Path<Uuid>,
+) -> AppResult<Json<Vec<ImportMapping>>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    Ok(Json(app_state.imports().list_mappings(company.id).await?))
+}
+
+async fn save_mapping(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(company_id): Path<Uuid>,
+    Json(req): Json<SaveMappingRequest>,
+) -> AppResult<Json<ImportMapping>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let mapping = app_state
+        .imports()
+        .save_mapping(company.id, *user.user_id.as_uuid(), req.name, req.mapping)
+        .await?;
+    Ok(Json(mapping))
+}
+
+async fn delete_mapping(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, mapping_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<StatusCode> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    app_state.imports().delete_mapping(company.id, mapping_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Multipart upload with a `file` part and `account_id`, plus optionally a
+/// bank `layout`, a saved `mapping_id` or an inline JSON `mapping`
+async fn preview_import(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(company_id): Path<Uuid>,
+    mut multipart: Multipart,
+) -> AppResult<Json<ImportBatch>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+
+    let mut file: Option<(String, Vec<u8>)> = None;
+    let mut account_id = None;
+    let mut layout = None;
+    let mut mapping_id = None;
+    let mut mapping = None;
+
+    let invalid_body = |e: axum::extract::multipart::MultipartError| {
+        AppError::BadRequest(format!("Invalid upload: {}", e))
+    };
+
+    while let Some(field) = multipart.next_field().await.map_err(invalid_body)? {
+        let name = field.name().unwrap_or_default().to_string();
+        match name.as_str() {
+            "file" => {
+                let file_name = field.file_name().unwrap_or("upload.csv").to_string();
+                let data = field.bytes().await.map_err(invalid_body)?;
+                file = Some((file_name, data.to_vec()));
+            }
+            "account_id" => {
+                let value = field.text().await.map_err(invalid_body)?;
+                account_id = Some(
+                    value
+                        .trim()
+                        .parse::<Uuid>()
+                        .map_err(|_| AppError::Validation("Invalid account_id".to_string()))?,
+                );
+            }
+            "layout" => {
+                let value = field.text().await.map_err(invalid_body)?;
+                let value = value.trim();
+                if !value.is_empty() && value != "auto" {
+                    layout = Some(value.parse::<StatementLayout>().map_err(AppError::Validation)?);
+                }
+            }
+            "mapping_id" => {
+                let value = field.text().await.map_err(invalid_body)?;
+                mapping_id = Some(
+                    value
+                        .trim()
+                        .parse::<Uuid>()
+                        .map_err(|_| AppError::Validation("Invalid mapping_id".to_string()))?,
+                );
+            }
+            "mapping" => {
+                let value = field.text().await.map_err(invalid_body)?;
+                mapping = Some(
+                    serde_json::from_str::<ColumnMapping>(&value)
+                        .map_err(|e| AppError::Validation(format!("Invalid mapping: {}", e)))?,
+                );
+            }
+            _ => {}
+        }
+    }
+
+    let (file_name, data) =
+        file.ok_or_else(|| AppError::Validation("A file is required".to_string()))?;
+    let account_id =
+        account_id.ok_or_else(|| AppError::Validation("account_id is required".to_string()))?;
+    let format = SpreadsheetFormat::from_file_name(&file_name).ok_or_else(|| {
+        AppError::Validation("Unsupported file type, upload a CSV or Excel file".to_string())
+    })?;
+
+    let table = read_table(&data, format)?;
+    let request = ImportRequest {
+        account_id,
+        file_name,
+        layout,
+        mapping_id,
+        mapping,
+    };
+    let batch = app_state
+        .imports()
+        .preview(company.id, *user.user_id.as_uuid(), request, &table)
+        .await?;
+
+    Ok(Json(batch))
+}
+
+async fn get_import(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, import_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<Json<ImportBatch>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    Ok(Json(app_state.imports().find_batch(company.id, import_id).await?))
+}
+
+/// Books the valid rows; repeating the call returns the committed batch
+async fn commit_import(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, import_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<Json<ImportBatch>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let batch = app_state
+        .imports()
+        .commit(company.id, *user.user_id.as_uuid(), import_id)
+        .await?;
+    Ok(Json(batch))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+    };
+
+    use crate::infrastructure::demo::{DEMO_OWNER, DEMO_SECOND_OWNER};
+    use crate::infrastructure::web::routes::testing::{company_of, demo_api, get, login, send};
+
+    const BOUNDARY: &str = "mutasi-boundary";
+
+    fn upload(uri: &str, token: &str, account_id: &str, csv: &str) -> Request<Body> {
+        let mapping = r#"{"date":"date","description":["description"],"amount":"amount"}"#;
+        let part = |name: &str, value: &str| {
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                BOUNDARY, name, value
+            )
+        };
+        let body = format!(
+            "{}{}--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"mutasi.csv\"\r\n\
+             Content-Type: text/csv\r\n\r\n{}\r\n--{}--\r\n",
+            part("account_id", account_id),
+            part("mapping", mapping),
+            BOUNDARY,
+            csv,
+            BOUNDARY
+        );
+        Request::post(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .header(
+                header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={}", BOUNDARY),
+            )
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    fn post(uri: &str, token: &str) -> Request<Body> {
+        Request::post(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_import_preview_and_commit_through_the_finance_routes() {
+        let (app, app_state) = demo_api().await;
+        let owner = login(&app, DEMO_OWNER).await;
+        let company_id = company_of(&app_state, DEMO_OWNER).await;
+        let imports = format!("/finance/companies/{}/imports", company_id);
+
+        let (status, accounts) = send(
+            &app,
+            get(&format!("/finance/companies/{}/accounts", company_id), Some(&owner)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let account_id = accounts[0]["id"].as_str().unwrap().to_string();
+
+        let csv = "date,description,amount\n2024-05-02,Penjualan tunai,150000\n2024-05-03,Beli bahan,-50000\n";
+        let (status, batch) = send(
+            &app,
+            upload(&format!("{}/preview", imports), &owner, &account_id, csv),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{}", batch);
+        assert_eq!(batch["status"], "previewed");
+        assert_eq!(batch["rows"].as_array().unwrap().len(), 2);
+        let batch_uri = format!("{}/{}", imports, batch["id"].as_str().unwrap());
+
+        // Committing again returns the committed batch instead of booking twice
+        for _ in 0..2 {
+            let (status, committed) = send(&app, post(&format!("{}/commit", batch_uri), &owner)).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(committed["status"], "committed");
+        }
+
+        // Without a token, on a plan without finance, and for another owner's company
+        let (status, _) = send(&app, get(&batch_uri, None)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let second_owner = login(&app, DEMO_SECOND_OWNER).await;
+        let (status, _) = send(&app, get(&batch_uri, Some(&second_owner))).await;
+        assert_eq!(status, StatusCode::PAYMENT_REQUIRED);
+        let other_company = company_of(&app_state, DEMO_SECOND_OWNER).await;
+        let (status, _) = send(
+            &app,
+            get(&format!("/finance/companies/{}/imports/mappings", other_company), Some(&owner)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+}
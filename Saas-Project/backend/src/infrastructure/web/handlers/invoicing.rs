@@ -0,0 +1,279 @@
+// Customer invoicing handlers
+// Contacts, sales invoices (faktur) and the payments a company receives on
+// them, plus its receivables aging. Mounted under `/finance`, so the caller's
+// plan must include the finance module; only the company's owner and super
+// admins see or change its invoices. Updates honour If-Match and answer with
+// the new version in the ETag.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+    Router,
+};
+use chrono::{NaiveDate, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::domain::analytics::reporting_day;
+use crate::domain::finance::FinancialAccount;
+use crate::domain::invoicing::{
+    AgingReport, Contact, ContactInput, InvoiceDraft, InvoicePayment, PaymentInput, SalesInvoice,
+    SalesInvoiceQuery, SalesInvoiceStatus,
+};
+use crate::infrastructure::web::etag::{IfMatch, Versioned};
+use crate::infrastructure::web::middleware::auth::AuthenticatedUser;
+use crate::services::invoicing::{pdf_file_name, RecordedPayment};
+use crate::shared::errors::AppResult;
+
+use super::billing::managed_company;
+use super::AppState;
+
+/// Invoices listed when no limit is given, and the most listed
+const DEFAULT_INVOICES: u32 = 50;
+const MAX_INVOICES: u32 = 500;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/companies/:company_id/contacts", get(contacts).post(create_contact))
+        .route(
+            "/companies/:company_id/contacts/:contact_id",
+            get(contact).put(update_contact),
+        )
+        .route("/companies/:company_id/accounts", get(accounts))
+        .route("/companies/:company_id/invoices", get(invoices).post(create_invoice))
+        .route(
+            "/companies/:company_id/invoices/:invoice_id",
+            get(invoice).put(update_invoice),
+        )
+        .route("/companies/:company_id/invoices/:invoice_id/send", post(send_invoice))
+        .route("/companies/:company_id/invoices/:invoice_id/void", post(void_invoice))
+        .route("/companies/:company_id/invoices/:invoice_id/email", post(email_invoice))
+        .route("/companies/:company_id/invoices/:invoice_id/pdf", get(invoice_pdf))
+        .route("/companies/:company_id/invoices/:invoice_id/payments", get(invoice_payments))
+        .route("/companies/:company_id/payments", post(record_payment))
+        .route("/companies/:company_id/receivables/aging", get(aging))
+}
+
+async fn contacts(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(company_id): Path<Uuid>,
+) -> AppResult<Json<Vec<Contact>>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    Ok(Json(app_state.invoicing().contacts(company.id).await?))
+}
+
+async fn create_contact(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(company_id): Path<Uuid>,
+    Json(input): Json<ContactInput>,
+) -> AppResult<(StatusCode, Versioned<Contact>)> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let contact = app_state.invoicing().create_contact(company.id, input).await?;
+    Ok((StatusCode::CREATED, Versioned(contact.version, contact)))
+}
+
+async fn contact(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, contact_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<Versioned<Contact>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let contact = app_state.invoicing().contact(company.id, contact_id).await?;
+    Ok(Versioned(contact.version, contact))
+}
+
+async fn update_contact(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, contact_id)): Path<(Uuid, Uuid)>,
+    if_match: IfMatch,
+    Json(input): Json<ContactInput>,
+) -> AppResult<Versioned<Contact>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let contact = app_state
+        .invoicing()
+        .update_contact(company.id, contact_id, if_match.0, input)
+        .await?;
+    Ok(Versioned(contact.version, contact))
+}
+
+/// Accounts a payment can be booked into
+async fn accounts(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(company_id): Path<Uuid>,
+) -> AppResult<Json<Vec<FinancialAccount>>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    Ok(Json(app_state.invoicing().accounts(company.id).await?))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct InvoiceListQuery {
+    pub status: Option<SalesInvoiceStatus>,
+    pub contact_id: Option<Uuid>,
+    /// Only invoices still awaiting payment
+    pub open: Option<bool>,
+    pub limit: Option<u32>,
+}
+
+/// Newest invoices first
+async fn invoices(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(company_id): Path<Uuid>,
+    Query(query): Query<InvoiceListQuery>,
+) -> AppResult<Json<Vec<SalesInvoice>>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let limit = query.limit.unwrap_or(DEFAULT_INVOICES).clamp(1, MAX_INVOICES);
+    let filter = SalesInvoiceQuery {
+        status: query.status,
+        contact_id: query.contact_id,
+        open: query.open.unwrap_or(false),
+    };
+    Ok(Json(
+        app_state.invoicing().invoices(company.id, &filter, limit as i64).await?,
+    ))
+}
+
+async fn create_invoice(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(company_id): Path<Uuid>,
+    Json(draft): Json<InvoiceDraft>,
+) -> AppResult<(StatusCode, Versioned<SalesInvoice>)> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let invoice = app_state
+        .invoicing()
+        .create_invoice(company.id, *user.user_id.as_uuid(), draft)
+        .await?;
+    Ok((StatusCode::CREATED, Versioned(invoice.version, invoice)))
+}
+
+async fn invoice(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, invoice_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<Versioned<SalesInvoice>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let invoice = app_state.invoicing().invoice(company.id, invoice_id).await?;
+    Ok(Versioned(invoice.version, invoice))
+}
+
+/// Replaces a draft's contact, lines and terms
+async fn update_invoice(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, invoice_id)): Path<(Uuid, Uuid)>,
+    if_match: IfMatch,
+    Json(draft): Json<InvoiceDraft>,
+) -> AppResult<Versioned<SalesInvoice>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let invoice = app_state
+        .invoicing()
+        .update_invoice(company.id, invoice_id, if_match.0, draft)
+        .await?;
+    Ok(Versioned(invoice.version, invoice))
+}
+
+/// Issues a draft under the company's next invoice number
+async fn send_invoice(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, invoice_id)): Path<(Uuid, Uuid)>,
+    if_match: IfMatch,
+) -> AppResult<Versioned<SalesInvoice>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let invoice = app_state.invoicing().send(company.id, invoice_id, if_match.0).await?;
+    Ok(Versioned(invoice.version, invoice))
+}
+
+/// Cancels an invoice nothing has been paid on; its number stays used
+async fn void_invoice(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, invoice_id)): Path<(Uuid, Uuid)>,
+    if_match: IfMatch,
+) -> AppResult<Versioned<SalesInvoice>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let invoice = app_state.invoicing().void(company.id, invoice_id, if_match.0).await?;
+    Ok(Versioned(invoice.version, invoice))
+}
+
+/// Emails the invoice PDF to the contact, sending a draft first
+async fn email_invoice(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, invoice_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<Versioned<SalesInvoice>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let invoice = app_state.invoicing().email(&company, invoice_id).await?;
+    Ok(Versioned(invoice.version, invoice))
+}
+
+async fn invoice_pdf(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, invoice_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<Response> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let (invoice, pdf) = app_state.invoicing().pdf(&company, invoice_id).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", pdf_file_name(&invoice)),
+            ),
+            (header::ETAG, format!("\"{}\"", invoice.version)),
+        ],
+        pdf,
+    )
+        .into_response())
+}
+
+async fn invoice_payments(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, invoice_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<Json<Vec<InvoicePayment>>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    Ok(Json(app_state.invoicing().payments(company.id, invoice_id).await?))
+}
+
+/// Books a payment from a contact and allocates it to their open invoices
+async fn record_payment(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(company_id): Path<Uuid>,
+    Json(input): Json<PaymentInput>,
+) -> AppResult<(StatusCode, Json<RecordedPayment>)> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let recorded = app_state
+        .invoicing()
+        .record_payment(company.id, *user.user_id.as_uuid(), input)
+        .await?;
+    Ok((StatusCode::CREATED, Json(recorded)))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AgingQuery {
+    /// Today, in the reporting time zone, when not given
+    pub as_of: Option<NaiveDate>,
+}
+
+/// What each contact owes, by days past due
+async fn aging(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(company_id): Path<Uuid>,
+    Query(query): Query<AgingQuery>,
+) -> AppResult<Json<AgingReport>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let as_of = query.as_of.unwrap_or_else(|| reporting_day(Utc::now()));
+    Ok(Json(app_state.invoicing().aging(company.id, as_of).await?))
+}
@@ -2,8 +2,8 @@
 
 use axum::{
     extract::{Multipart, Path, Query, State},
-    http::StatusCode,
-    response::Json,
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
     routing::{delete, get, post, put},
     Router,
 };
@@ -14,14 +14,31 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
+    domain::billing::Requirement,
+    domain::certificates::{CertificateReason, LicenseCertificate},
     domain::licenses::{
         ApplicationStatus, ApplicationStatusHistory, DocumentType, License, LicenseDocument,
         LicenseType, PriorityLevel,
     },
     domain::entities::UserRole,
-    infrastructure::{repositories::license_repository::LicenseStatistics,
-    // repositories::LicenseRepository,
-    web::middleware::auth::AuthenticatedUser},
+    domain::license_comments::{CommentKind, Discussion, LicenseComment, NewComment, Participant},
+    domain::live_updates::LiveUpdate,
+    domain::filters::{DocumentFilter, DocumentSortField, LicenseFilter, LicenseSortField},
+    domain::notifications::NotificationEvent,
+    domain::search::{SearchHitType, SearchQuery, SearchScope, MAX_SEARCH_LIMIT},
+    shared::errors::{AppError, AppResult},
+    shared::query::{ListParams, ListQuery, Page},
+    infrastructure::{
+        monitoring::{record_document_upload, record_license_application, record_license_processed},
+        repositories::license_repository::LicenseStatistics,
+        // repositories::LicenseRepository,
+        web::etag::{IfMatch, Versioned},
+        web::middleware::auth::AuthenticatedUser,
+    },
+    services::{
+        license_processing::LicenseProcessingService,
+        license_processing_models::{LicenseAction, LicenseDecision},
+    },
 };
 
 // Use the AppState from the handlers module
@@ -60,14 +77,41 @@ pub struct RejectLicenseRequest {
     pub admin_notes: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RequestDocumentsRequest {
+    /// What the applicant still has to supply; shown to them
+    pub notes: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct VerifyDocumentRequest {
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuspendLicenseRequest {
+    pub reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReinstateLicenseRequest {
+    pub admin_notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RenewLicenseRequest {
+    pub expiry_date: DateTime<Utc>,
+    pub admin_notes: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MarkCommentsReadResponse {
+    pub marked: u64,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct LicenseQueryParams {
-    pub status: Option<ApplicationStatus>,
-    pub license_type: Option<LicenseType>,
     pub search: Option<String>,
-    pub company_id: Option<Uuid>,
-    pub limit: Option<i32>,
-    pub offset: Option<i32>,
 }
 
 #[derive(Debug, Serialize)]
@@ -96,9 +140,20 @@ pub fn routes() -> Router<AppState> {
         .route("/:id/submit", post(submit_license))
         .route("/:id/approve", post(approve_license))
         .route("/:id/reject", post(reject_license))
+        .route("/:id/request-documents", post(request_documents))
+        .route("/:id/suspend", post(suspend_license))
+        .route("/:id/reinstate", post(reinstate_license))
+        .route("/:id/renew", post(renew_license))
+        .route("/:id/certificate", get(download_certificate))
+        .route("/:id/certificate", post(regenerate_certificate))
+        .route("/:id/certificates", get(get_certificate_history))
         .route("/:id/documents", get(get_license_documents))
         .route("/:id/documents", post(upload_license_document))
+        .route("/:id/documents/:document_id/verify", post(verify_license_document))
         .route("/:id/status-history", get(get_license_status_history))
+        .route("/:id/comments", get(get_license_comments))
+        .route("/:id/comments", post(post_license_comment))
+        .route("/:id/comments/read", post(mark_license_comments_read))
 }
 
 // Create a new license application
@@ -106,7 +161,13 @@ async fn create_license(
     State(app_state): State<AppState>,
     user: AuthenticatedUser,
     Json(request): Json<CreateLicenseRequest>,
-) -> Result<Json<License>, StatusCode> {
+) -> AppResult<Json<License>> {
+    // Each plan files only so many applications a month
+    app_state
+        .billing()
+        .require(request.company_id, Requirement::License)
+        .await?;
+
     // Create new license in draft status
     let license = License::new(
         request.license_type,
@@ -116,86 +177,32 @@ async fn create_license(
         request.description,
     );
 
-    match app_state.license_repository().create_license(&license).await {
-        Ok(created_license) => Ok(Json(created_license)),
-        Err(e) => {
-            tracing::error!("Failed to create license: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+    let created_license = app_state.license_repository().create_license(&license).await?;
+    Ok(Json(created_license))
 }
 
-// Get user's licenses
+// List licenses visible to the caller
 async fn get_user_licenses(
     State(app_state): State<AppState>,
     user: AuthenticatedUser,
-    Query(params): Query<LicenseQueryParams>,
-) -> Result<Json<Vec<License>>, StatusCode> {
-    // Filter by specific criteria or get all user licenses
-    let licenses = if let Some(status) = params.status {
-        // Get by status (need to filter by user)
-        match app_state
-            .license_repository()
-            .get_licenses_by_status(status)
-            .await
-        {
-            Ok(all_licenses) => all_licenses
-                .into_iter()
-                .filter(|license| license.user_id == *user.user_id.as_uuid())
-                .collect(),
-            Err(e) => {
-                tracing::error!("Failed to get licenses by status: {}", e);
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
-            }
-        }
-    } else if let Some(license_type) = params.license_type {
-        // Get by type (need to filter by user)
-        match app_state
-            .license_repository()
-            .get_licenses_by_type(license_type)
-            .await
-        {
-            Ok(all_licenses) => all_licenses
-                .into_iter()
-                .filter(|license| license.user_id == *user.user_id.as_uuid())
-                .collect(),
-            Err(e) => {
-                tracing::error!("Failed to get licenses by type: {}", e);
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
-            }
-        }
-    } else if let Some(company_id) = params.company_id {
-        // Get by company
-        match app_state
-            .license_repository()
-            .get_licenses_by_company(company_id)
-            .await
-        {
-            Ok(licenses) => licenses
-                .into_iter()
-                .filter(|license| license.user_id == *user.user_id.as_uuid()) // Ensure user owns the licenses
-                .collect(),
-            Err(e) => {
-                tracing::error!("Failed to get licenses by company: {}", e);
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
-            }
-        }
-    } else {
-        // Get all user licenses
-        match app_state
-            .license_repository()
-            .get_licenses_by_user(*user.user_id.as_uuid())
-            .await
-        {
-            Ok(licenses) => licenses,
-            Err(e) => {
-                tracing::error!("Failed to get user licenses: {}", e);
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
-            }
-        }
-    };
+    Query(mut filter): Query<LicenseFilter>,
+    Query(params): Query<ListParams>,
+) -> Result<Json<Page<License>>, StatusCode> {
+    // Owners only see licenses they filed or that belong to their companies
+    if user.role != UserRole::SuperAdmin && user.role != UserRole::AdminStaff {
+        filter.owner_id = Some(*user.user_id.as_uuid());
+    }
 
-    Ok(Json(licenses))
+    let query = ListQuery::<LicenseFilter, LicenseSortField>::from_params(filter, &params)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    match app_state.license_repository().list_licenses(&query).await {
+        Ok(page) => Ok(Json(page)),
+        Err(e) => {
+            tracing::error!("Failed to list licenses: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
 }
 
 // Get license by ID with full details
@@ -203,7 +210,7 @@ async fn get_license_by_id(
     State(app_state): State<AppState>,
     user: AuthenticatedUser,
     Path(license_id): Path<Uuid>,
-) -> Result<Json<LicenseResponse>, StatusCode> {
+) -> Result<Versioned<LicenseResponse>, StatusCode> {
     // Get license
     let license = match app_state
         .license_repository()
@@ -250,11 +257,14 @@ async fn get_license_by_id(
         }
     };
 
-    Ok(Json(LicenseResponse {
-        license,
-        documents,
-        status_history,
-    }))
+    Ok(Versioned(
+        license.version,
+        LicenseResponse {
+            license,
+            documents,
+            status_history,
+        },
+    ))
 }
 
 // Update license (only in draft status)
@@ -262,31 +272,26 @@ async fn update_license(
     State(app_state): State<AppState>,
     user: AuthenticatedUser,
     Path(license_id): Path<Uuid>,
+    if_match: IfMatch,
     Json(request): Json<UpdateLicenseRequest>,
-) -> Result<Json<License>, StatusCode> {
+) -> AppResult<Versioned<License>> {
     // Get existing license
-    let mut license = match app_state
+    let mut license = app_state
         .license_repository()
         .get_license_by_id(license_id)
-        .await
-    {
-        Ok(Some(license)) => {
-            // Check ownership
-            if license.user_id != *user.user_id.as_uuid() {
-                return Err(StatusCode::FORBIDDEN);
-            }
-            // Only allow updates in draft status
-            if license.application_status != ApplicationStatus::Draft {
-                return Err(StatusCode::BAD_REQUEST);
-            }
-            license
-        }
-        Ok(None) => return Err(StatusCode::NOT_FOUND),
-        Err(e) => {
-            tracing::error!("Failed to get license: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
-    };
+        .await?
+        .ok_or_else(|| AppError::NotFound("License not found".to_string()))?;
+
+    // Check ownership
+    if license.user_id != *user.user_id.as_uuid() {
+        return Err(AppError::Forbidden("Access denied".to_string()));
+    }
+    // Only allow updates in draft status
+    if license.application_status != ApplicationStatus::Draft {
+        return Err(AppError::BadRequest(
+            "Only draft licenses can be updated".to_string(),
+        ));
+    }
 
     // Update fields
     if let Some(title) = request.title {
@@ -303,15 +308,11 @@ async fn update_license(
     }
 
     license.updated_at = Utc::now();
+    license.version = if_match.version_or(license.version);
 
-    // Save updated license
-    match app_state.license_repository().update_license(&license).await {
-        Ok(updated_license) => Ok(Json(updated_license)),
-        Err(e) => {
-            tracing::error!("Failed to update license: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+    // Save updated license, unless someone else changed it in the meantime
+    let updated_license = app_state.license_repository().update_license(&license).await?;
+    Ok(Versioned(updated_license.version, updated_license))
 }
 
 // Delete license (only in draft status)
@@ -364,7 +365,7 @@ async fn submit_license(
     State(app_state): State<AppState>,
     user: AuthenticatedUser,
     Path(license_id): Path<Uuid>,
-) -> Result<Json<License>, StatusCode> {
+) -> Result<Versioned<License>, StatusCode> {
     // Check license ownership
     let _license = match app_state
         .license_repository()
@@ -390,7 +391,14 @@ async fn submit_license(
         .submit_license_application(license_id, *user.user_id.as_uuid())
         .await
     {
-        Ok(updated_license) => Ok(Json(updated_license)),
+        Ok(updated_license) => {
+            record_license_application(&updated_license.license_type.to_string());
+            app_state
+                .live_updates()
+                .publish(LiveUpdate::license_submitted(&updated_license))
+                .await;
+            Ok(Versioned(updated_license.version, updated_license))
+        }
         Err(e) => {
             tracing::error!("Failed to submit license application: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
@@ -398,36 +406,92 @@ async fn submit_license(
     }
 }
 
+// Count an approval or rejection, timed from submission to the decision
+fn record_decision(license: &License, decided_at: Option<DateTime<Utc>>) {
+    let processing_secs = license
+        .submitted_at
+        .zip(decided_at)
+        .map(|(submitted, decided)| (decided - submitted).num_seconds().max(0) as f64);
+
+    record_license_processed(
+        &license.license_type.to_string(),
+        &license.application_status.to_string(),
+        processing_secs,
+    );
+}
+
+/// The version a decision applies to. Without `If-Match` that is the loaded
+/// version, but a license that has already been decided is never overwritten.
+async fn decision_version(
+    app_state: &AppState,
+    admin_user: &AuthenticatedUser,
+    license_id: Uuid,
+    if_match: IfMatch,
+) -> AppResult<i64> {
+    if admin_user.role != UserRole::SuperAdmin && admin_user.role != UserRole::AdminStaff {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let license = app_state
+        .license_repository()
+        .get_license_by_id(license_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("License not found".to_string()))?;
+
+    if if_match.0.is_none()
+        && matches!(
+            license.application_status,
+            ApplicationStatus::Approved | ApplicationStatus::Rejected
+        )
+    {
+        return Err(AppError::Conflict(format!(
+            "License has already been {}",
+            license.application_status
+        )));
+    }
+    Ok(if_match.version_or(license.version))
+}
+
 // Approve license (admin only)
 async fn approve_license(
     State(app_state): State<AppState>,
     admin_user: AuthenticatedUser,
     Path(license_id): Path<Uuid>,
+    if_match: IfMatch,
     Json(request): Json<ApproveLicenseRequest>,
-) -> Result<Json<License>, StatusCode> {
-    if admin_user.role != UserRole::SuperAdmin && admin_user.role != UserRole::AdminStaff {
-        return Err(StatusCode::FORBIDDEN);
-    }
+) -> AppResult<Versioned<License>> {
+    let version = decision_version(&app_state, &admin_user, license_id, if_match).await?;
 
-    match app_state
-        .license_repository()
-        .approve_license(
+    let approved_license = LicenseProcessingService::new()
+        .decide(
+            app_state.license_work().as_ref(),
             license_id,
+            version,
             *admin_user.user_id.as_uuid(),
-            request.license_number,
-            request.issue_date,
-            request.expiry_date,
-            request.issuing_authority,
-            request.admin_notes,
+            LicenseDecision::Approve {
+                license_number: request.license_number,
+                issue_date: request.issue_date,
+                expiry_date: request.expiry_date,
+                issuing_authority: request.issuing_authority,
+                admin_notes: request.admin_notes,
+            },
         )
-        .await
-    {
-        Ok(approved_license) => Ok(Json(approved_license)),
-        Err(e) => {
-            tracing::error!("Failed to approve license: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+        .await?;
+
+    record_decision(&approved_license, approved_license.approved_at);
+    app_state
+        .certificates()
+        .reissue(&approved_license, CertificateReason::Issued, *admin_user.user_id.as_uuid())
+        .await;
+    app_state
+        .notifications()
+        .notify_license_best_effort(&approved_license, NotificationEvent::LicenseApproved, None)
+        .await;
+    app_state
+        .live_updates()
+        .publish(LiveUpdate::license_status_changed(&approved_license))
+        .await;
+    Ok(Versioned(approved_license.version, approved_license))
 }
 
 // Reject license (admin only)
@@ -435,49 +499,367 @@ async fn reject_license(
     State(app_state): State<AppState>,
     admin_user: AuthenticatedUser,
     Path(license_id): Path<Uuid>,
+    if_match: IfMatch,
     Json(request): Json<RejectLicenseRequest>,
-) -> Result<Json<License>, StatusCode> {
-    if admin_user.role != UserRole::SuperAdmin && admin_user.role != UserRole::AdminStaff {
-        return Err(StatusCode::FORBIDDEN);
-    }
+) -> AppResult<Versioned<License>> {
+    let version = decision_version(&app_state, &admin_user, license_id, if_match).await?;
 
-    match app_state
-        .license_repository()
-        .reject_license(
+    let rejected_license = LicenseProcessingService::new()
+        .decide(
+            app_state.license_work().as_ref(),
             license_id,
+            version,
             *admin_user.user_id.as_uuid(),
-            request.rejection_reason,
-            request.admin_notes,
+            LicenseDecision::Reject {
+                reason: request.rejection_reason,
+                admin_notes: request.admin_notes,
+            },
         )
-        .await
-    {
-        Ok(rejected_license) => Ok(Json(rejected_license)),
-        Err(e) => {
-            tracing::error!("Failed to reject license: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        .await?;
+
+    record_decision(&rejected_license, rejected_license.rejected_at);
+    app_state
+        .notifications()
+        .notify_license_best_effort(
+            &rejected_license,
+            NotificationEvent::LicenseRejected,
+            rejected_license.rejection_reason.clone(),
+        )
+        .await;
+    app_state
+        .live_updates()
+        .publish(LiveUpdate::license_status_changed(&rejected_license))
+        .await;
+    Ok(Versioned(rejected_license.version, rejected_license))
+}
+
+fn is_admin(user: &AuthenticatedUser) -> bool {
+    user.role == UserRole::SuperAdmin || user.role == UserRole::AdminStaff
+}
+
+/// What the applicant is told about an action, with the notes they get to see
+fn action_notification(action: &LicenseAction) -> (NotificationEvent, Option<String>) {
+    match action {
+        LicenseAction::RequestDocuments { notes } => {
+            (NotificationEvent::DocumentsRequested, Some(notes.clone()))
         }
+        LicenseAction::Suspend { reason } => {
+            (NotificationEvent::LicenseSuspended, Some(reason.clone()))
+        }
+        LicenseAction::Reinstate { .. } => (NotificationEvent::LicenseReinstated, None),
+        LicenseAction::Renew { .. } => (NotificationEvent::LicenseRenewed, None),
     }
 }
 
-// Search licenses
+/// Applies `action` to the license at the `If-Match` version, or the loaded
+/// one, regenerates its certificate if `reason` is given, notifies the
+/// applicant and pushes the new status to open event streams
+async fn act_on_license(
+    app_state: &AppState,
+    admin_user: &AuthenticatedUser,
+    license_id: Uuid,
+    if_match: IfMatch,
+    action: LicenseAction,
+    reason: Option<CertificateReason>,
+) -> AppResult<Versioned<License>> {
+    if !is_admin(admin_user) {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+    let version = match if_match.0 {
+        Some(version) => version,
+        None => {
+            app_state
+                .license_repository()
+                .get_license_by_id(license_id)
+                .await?
+                .ok_or_else(|| AppError::NotFound("License not found".to_string()))?
+                .version
+        }
+    };
+
+    let admin_id = *admin_user.user_id.as_uuid();
+    let (event, notes) = action_notification(&action);
+    let license = LicenseProcessingService::new()
+        .act(app_state.license_work().as_ref(), license_id, version, admin_id, action)
+        .await?;
+
+    if let Some(reason) = reason {
+        app_state.certificates().reissue(&license, reason, admin_id).await;
+    }
+    app_state
+        .notifications()
+        .notify_license_best_effort(&license, event, notes)
+        .await;
+    app_state
+        .live_updates()
+        .publish(LiveUpdate::license_status_changed(&license))
+        .await;
+    Ok(Versioned(license.version, license))
+}
+
+// Ask the applicant for further documents (admin only)
+async fn request_documents(
+    State(app_state): State<AppState>,
+    admin_user: AuthenticatedUser,
+    Path(license_id): Path<Uuid>,
+    if_match: IfMatch,
+    Json(request): Json<RequestDocumentsRequest>,
+) -> AppResult<Versioned<License>> {
+    if request.notes.trim().is_empty() {
+        return Err(AppError::Validation(
+            "Say which documents are needed".to_string(),
+        ));
+    }
+    act_on_license(
+        &app_state,
+        &admin_user,
+        license_id,
+        if_match,
+        LicenseAction::RequestDocuments {
+            notes: request.notes,
+        },
+        None,
+    )
+    .await
+}
+
+// Suspend an approved license (admin only)
+async fn suspend_license(
+    State(app_state): State<AppState>,
+    admin_user: AuthenticatedUser,
+    Path(license_id): Path<Uuid>,
+    if_match: IfMatch,
+    Json(request): Json<SuspendLicenseRequest>,
+) -> AppResult<Versioned<License>> {
+    if request.reason.trim().is_empty() {
+        return Err(AppError::Validation("A suspension needs a reason".to_string()));
+    }
+    act_on_license(
+        &app_state,
+        &admin_user,
+        license_id,
+        if_match,
+        LicenseAction::Suspend {
+            reason: request.reason,
+        },
+        Some(CertificateReason::Suspended),
+    )
+    .await
+}
+
+// Lift the suspension of a license (admin only)
+async fn reinstate_license(
+    State(app_state): State<AppState>,
+    admin_user: AuthenticatedUser,
+    Path(license_id): Path<Uuid>,
+    if_match: IfMatch,
+    Json(request): Json<ReinstateLicenseRequest>,
+) -> AppResult<Versioned<License>> {
+    act_on_license(
+        &app_state,
+        &admin_user,
+        license_id,
+        if_match,
+        LicenseAction::Reinstate {
+            admin_notes: request.admin_notes,
+        },
+        Some(CertificateReason::Reinstated),
+    )
+    .await
+}
+
+// Extend the validity of a license (admin only)
+async fn renew_license(
+    State(app_state): State<AppState>,
+    admin_user: AuthenticatedUser,
+    Path(license_id): Path<Uuid>,
+    if_match: IfMatch,
+    Json(request): Json<RenewLicenseRequest>,
+) -> AppResult<Versioned<License>> {
+    act_on_license(
+        &app_state,
+        &admin_user,
+        license_id,
+        if_match,
+        LicenseAction::Renew {
+            expiry_date: request.expiry_date,
+            admin_notes: request.admin_notes,
+        },
+        Some(CertificateReason::Renewed),
+    )
+    .await
+}
+
+fn participant(user: &AuthenticatedUser) -> Participant {
+    Participant {
+        user_id: *user.user_id.as_uuid(),
+        is_admin: is_admin(user),
+    }
+}
+
+// The discussion threads of a license; internal notes are for admins only
+async fn get_license_comments(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(license_id): Path<Uuid>,
+) -> AppResult<Json<Discussion>> {
+    let license = visible_license(&app_state, &user, license_id).await?;
+    let discussion = app_state
+        .license_comments()
+        .discussion(&license, participant(&user))
+        .await?;
+    Ok(Json(discussion))
+}
+
+// Comment on a license or reply to a thread. A clarification request sends
+// the license back to the applicant as pending documents, at the `If-Match`
+// version if given, with the comment as the explanation.
+async fn post_license_comment(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(license_id): Path<Uuid>,
+    if_match: IfMatch,
+    Json(request): Json<NewComment>,
+) -> AppResult<(StatusCode, Json<LicenseComment>)> {
+    let license = visible_license(&app_state, &user, license_id).await?;
+    let comments = app_state.license_comments();
+    let comment = comments.prepare(&license, participant(&user), request).await?;
+
+    let license = match comment.kind {
+        CommentKind::Comment => license,
+        CommentKind::ClarificationRequest => {
+            let Versioned(_, license) = act_on_license(
+                &app_state,
+                &user,
+                license_id,
+                if_match,
+                LicenseAction::RequestDocuments {
+                    notes: comment.body.clone(),
+                },
+                None,
+            )
+            .await?;
+            license
+        }
+    };
+
+    let comment = comments.post(&license, comment).await?;
+    Ok((StatusCode::CREATED, Json(comment)))
+}
+
+// Mark every comment the user can see on a license as read
+async fn mark_license_comments_read(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(license_id): Path<Uuid>,
+) -> AppResult<Json<MarkCommentsReadResponse>> {
+    let license = visible_license(&app_state, &user, license_id).await?;
+    let marked = app_state
+        .license_comments()
+        .mark_read(&license, participant(&user))
+        .await?;
+    Ok(Json(MarkCommentsReadResponse { marked }))
+}
+
+/// The license, if the user filed it or is admin staff
+async fn visible_license(
+    app_state: &AppState,
+    user: &AuthenticatedUser,
+    license_id: Uuid,
+) -> AppResult<License> {
+    let license = app_state
+        .license_repository()
+        .get_license_by_id(license_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("License not found".to_string()))?;
+    if license.user_id != *user.user_id.as_uuid() && !is_admin(user) {
+        return Err(AppError::Forbidden("Access denied".to_string()));
+    }
+    Ok(license)
+}
+
+/// `sertifikat-SIUP-2024-0001.pdf` for license number `SIUP/2024/0001`
+fn certificate_file_name(license: &License) -> String {
+    let number: String = license
+        .license_number
+        .as_deref()
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("sertifikat-{}.pdf", number)
+}
+
+// Download the license certificate PDF, generated first if needed
+async fn download_certificate(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(license_id): Path<Uuid>,
+) -> AppResult<Response> {
+    let license = visible_license(&app_state, &user, license_id).await?;
+    let (certificate, pdf) = app_state.certificates().current(&license).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", certificate_file_name(&license)),
+            ),
+            (header::ETAG, format!("\"{}\"", certificate.sha256)),
+        ],
+        pdf,
+    )
+        .into_response())
+}
+
+// Generate a new revision of the license certificate (admin only)
+async fn regenerate_certificate(
+    State(app_state): State<AppState>,
+    admin_user: AuthenticatedUser,
+    Path(license_id): Path<Uuid>,
+) -> AppResult<(StatusCode, Json<LicenseCertificate>)> {
+    if !is_admin(&admin_user) {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+    let license = visible_license(&app_state, &admin_user, license_id).await?;
+    let certificate = app_state
+        .certificates()
+        .issue(&license, CertificateReason::Regenerated, Some(*admin_user.user_id.as_uuid()))
+        .await?;
+    Ok((StatusCode::CREATED, Json(certificate)))
+}
+
+// List the generated revisions of the license certificate
+async fn get_certificate_history(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(license_id): Path<Uuid>,
+) -> AppResult<Json<Vec<LicenseCertificate>>> {
+    visible_license(&app_state, &user, license_id).await?;
+    Ok(Json(app_state.certificates().history(license_id).await?))
+}
+
+// Search licenses through the unified search, best matches first
 async fn search_licenses(
     State(app_state): State<AppState>,
     user: AuthenticatedUser,
     Query(params): Query<LicenseQueryParams>,
-) -> Result<Json<Vec<License>>, StatusCode> {
-    let search_query = params.search.unwrap_or_default();
+) -> AppResult<Json<Vec<License>>> {
+    let scope = SearchScope::for_user(&user.user_id, &user.role);
+    let query = SearchQuery::new(params.search.as_deref().unwrap_or_default(), scope)
+        .map_err(AppError::Validation)?
+        .with_types(vec![SearchHitType::License])
+        .with_limit(MAX_SEARCH_LIMIT);
+    let page = app_state.search_repository().search(&query).await?;
 
-    match app_state
-        .license_repository()
-        .search_licenses(&search_query, Some(*user.user_id.as_uuid()))
-        .await
-    {
-        Ok(licenses) => Ok(Json(licenses)),
-        Err(e) => {
-            tracing::error!("Failed to search licenses: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+    let mut licenses = Vec::with_capacity(page.hits.len());
+    for hit in page.hits {
+        if let Some(license) = app_state.license_repository().get_license_by_id(hit.id).await? {
+            licenses.push(license);
         }
     }
+    Ok(Json(licenses))
 }
 
 // Get license documents
@@ -485,7 +867,9 @@ async fn get_license_documents(
     State(app_state): State<AppState>,
     user: AuthenticatedUser,
     Path(license_id): Path<Uuid>,
-) -> Result<Json<Vec<LicenseDocument>>, StatusCode> {
+    Query(mut filter): Query<DocumentFilter>,
+    Query(params): Query<ListParams>,
+) -> Result<Json<Page<LicenseDocument>>, StatusCode> {
     // Check license ownership
     let _license = match app_state
         .license_repository()
@@ -505,12 +889,12 @@ async fn get_license_documents(
         }
     };
 
-    match app_state
-        .license_repository()
-        .get_documents_by_license(license_id)
-        .await
-    {
-        Ok(documents) => Ok(Json(documents)),
+    filter.license_id = Some(license_id);
+    let query = ListQuery::<DocumentFilter, DocumentSortField>::from_params(filter, &params)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    match app_state.license_repository().list_documents(&query).await {
+        Ok(page) => Ok(Json(page)),
         Err(e) => {
             tracing::error!("Failed to get license documents: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
@@ -537,49 +921,63 @@ async fn upload_license_document(
     user: AuthenticatedUser,
     Path(license_id): Path<Uuid>,
     mut multipart: Multipart,
-) -> Result<Json<LicenseDocument>, StatusCode> {
+) -> AppResult<Json<LicenseDocument>> {
     // Verify license ownership
-    let license = match app_state.license_repository().get_license_by_id(license_id).await {
-        Ok(Some(license)) => {
-            if license.user_id != *user.user_id.as_uuid() {
-                return Err(StatusCode::FORBIDDEN);
-            }
-            license
-        }
-        Ok(None) => return Err(StatusCode::NOT_FOUND),
-        Err(e) => {
-            tracing::error!("Failed to get license: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
-    };
+    let license = app_state
+        .license_repository()
+        .get_license_by_id(license_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("License not found".to_string()))?;
+    if license.user_id != *user.user_id.as_uuid() {
+        return Err(AppError::Forbidden("Access denied".to_string()));
+    }
 
     let base_dir = format!("{}/{}", app_state.config().upload_dir, license.id);
-    if let Err(e) = fs::create_dir_all(&base_dir).await {
-        tracing::error!("Failed to create upload directory: {}", e);
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
-    }
+    fs::create_dir_all(&base_dir).await.map_err(|e| {
+        AppError::FileProcessing(format!("Failed to create upload directory: {}", e))
+    })?;
 
-    while let Some(field) = multipart.next_field().await.map_err(|_| StatusCode::BAD_REQUEST)? {
+    // Only the first field is read; one document per upload
+    if let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AppError::BadRequest(e.to_string()))?
+    {
         let name = field.name().unwrap_or("file").to_string();
         let original_file_name = field.file_name().unwrap_or("upload.bin").to_string();
         let content_type = field
             .content_type()
             .map(|ct| ct.to_string())
             .unwrap_or_else(|| "application/octet-stream".to_string());
-        let data = field.bytes().await.map_err(|_| StatusCode::BAD_REQUEST)?;
+        let data = field
+            .bytes()
+            .await
+            .map_err(|e| AppError::BadRequest(e.to_string()))?;
 
         if data.len() as u64 > app_state.config().max_file_size {
-            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+            return Err(AppError::PayloadTooLarge(format!(
+                "Files may be at most {} bytes",
+                app_state.config().max_file_size
+            )));
         }
+        // Documents count against the company's storage quota
+        app_state
+            .billing()
+            .require(
+                license.company_id,
+                Requirement::Storage {
+                    bytes: data.len() as i64,
+                },
+            )
+            .await?;
 
         let sanitized_name = original_file_name.replace(['/', '\\'], "_");
         let file_name = format!("{}-{}", Uuid::new_v4(), sanitized_name);
         let file_path = format!("{}/{}", base_dir, file_name);
 
-        if let Err(e) = fs::write(&file_path, &data).await {
-            tracing::error!("Failed to save uploaded file: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
+        fs::write(&file_path, &data).await.map_err(|e| {
+            AppError::FileProcessing(format!("Failed to save uploaded file: {}", e))
+        })?;
 
         let document = LicenseDocument::new(
             license.id,
@@ -591,16 +989,47 @@ async fn upload_license_document(
             content_type,
         );
 
-        match app_state.license_repository().create_document(&document).await {
-            Ok(saved) => return Ok(Json(saved)),
-            Err(e) => {
-                tracing::error!("Failed to save document record: {}", e);
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
-            }
-        }
+        let saved = app_state.license_repository().create_document(&document).await?;
+        record_document_upload(&saved.document_type.to_string(), saved.file_size as u64);
+        app_state
+            .live_updates()
+            .publish(LiveUpdate::document_uploaded(&license, &saved))
+            .await;
+        Ok(Json(saved))
+    } else {
+        Err(AppError::BadRequest("No file was uploaded".to_string()))
+    }
+}
+
+// Mark an uploaded document as checked (admin only)
+async fn verify_license_document(
+    State(app_state): State<AppState>,
+    admin_user: AuthenticatedUser,
+    Path((license_id, document_id)): Path<(Uuid, Uuid)>,
+    Json(request): Json<VerifyDocumentRequest>,
+) -> AppResult<Json<LicenseDocument>> {
+    if !is_admin(&admin_user) {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
     }
+    let license = app_state
+        .license_repository()
+        .get_license_by_id(license_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("License not found".to_string()))?;
+    let mut document = app_state
+        .license_repository()
+        .get_document_by_id(document_id)
+        .await?
+        .filter(|document| document.license_id == license.id)
+        .ok_or_else(|| AppError::NotFound("Document not found".to_string()))?;
 
-    Err(StatusCode::BAD_REQUEST)
+    document.verify(*admin_user.user_id.as_uuid(), request.notes);
+    let document = app_state.license_repository().update_document(&document).await?;
+    app_state
+        .live_updates()
+        .publish(LiveUpdate::document_verified(&license, &document))
+        .await;
+    Ok(Json(document))
 }
 
 // Get license status history
@@ -9,19 +9,63 @@ pub trait AppStateType: Send + Sync {
     fn company_repository(&self) -> &Arc<dyn crate::domain::repositories::CompanyRepository + Send + Sync>;
     fn user_repository(&self) -> &Arc<dyn crate::domain::repositories::UserRepository + Send + Sync>;
     fn license_repository(&self) -> &Arc<dyn crate::infrastructure::repositories::LicenseRepository + Send + Sync>;
+    /// Units of work for license changes that span several tables
+    fn license_work(&self) -> &Arc<dyn crate::domain::unit_of_work::UnitOfWork<crate::infrastructure::repositories::LicenseRepositories>>;
     fn auth_service(&self) -> &crate::services::auth::AuthService;
     fn config(&self) -> &AppConfig;
     fn cache_service(&self) -> &Option<crate::infrastructure::cache::CacheService>;
+    fn search_repository(&self) -> &Arc<dyn crate::domain::search::SearchRepository>;
+    /// Processing SLA of license applications, for the admin dashboard
+    fn sla_monitor(&self) -> &Arc<crate::services::sla_monitor::SlaMonitor>;
+    /// Queue, throughput, workload and revenue figures for the admin dashboard
+    fn admin_stats(&self) -> &Arc<dyn crate::domain::admin::AdminStatsRepository>;
+    /// Time series over the daily rollups, for the analytics dashboards
+    fn analytics(&self) -> &Arc<dyn crate::domain::analytics::AnalyticsRepository>;
+    /// Generated license certificate PDFs
+    fn certificates(&self) -> &Arc<crate::services::license_certificates::CertificateService>;
+    /// Public verification of licenses, e.g. from certificate QR codes
+    fn license_verification(&self) -> &Arc<crate::services::license_verification::LicenseVerificationService>;
+    /// In-app inbox, notification preferences and the outgoing message queue
+    fn notifications(&self) -> &Arc<crate::services::notifications::NotificationService>;
+    /// Outgoing email for messages only the recipient may read, such as the
+    /// token of a forced password reset
+    fn email(&self) -> &Arc<dyn crate::domain::notifications::MessageSender>;
+    /// Pushes license and document changes to open event streams
+    fn live_updates(&self) -> &crate::infrastructure::live_updates::LiveUpdateHub;
+    /// Discussion threads between applicants and reviewers on license applications
+    fn license_comments(&self) -> &Arc<crate::services::license_comments::LicenseCommentService>;
+    /// Subscriptions, invoices and the plan limits handlers check
+    fn billing(&self) -> &Arc<crate::services::billing::BillingService>;
+    /// Companies' contacts, sales invoices and the payments received on them
+    fn invoicing(&self) -> &Arc<crate::services::invoicing::InvoicingService>;
+    /// Spreadsheet and bank statement imports into a company's accounts
+    fn imports(&self) -> &Arc<crate::domain::imports::TransactionImportService>;
+    /// Bank reconciliation sessions of a company's accounts
+    fn reconciliation(&self) -> &Arc<crate::domain::reconciliation::ReconciliationService>;
+    /// Payment status lookups; `None` when no Midtrans server key is configured
+    fn midtrans(&self) -> Option<&crate::infrastructure::midtrans::MidtransClient>;
+    /// `None` in demo mode, where every repository is in memory
+    fn database(&self) -> Option<&crate::infrastructure::database::manager::DatabaseManager>;
 }
 
 // Import the AppConfig type
 use crate::config::AppConfig;
 
 pub mod admin;
+pub mod analytics;
 pub mod auth;
+pub mod billing;
 pub mod business;
 pub mod companies;
+pub mod events;
 pub mod files;
 pub mod finance;
+pub mod health;
+pub mod imports;
+pub mod invoicing;
 pub mod licenses;
+pub mod notifications;
+pub mod reconciliation;
+pub mod search;
 pub mod users;
+pub mod verification;
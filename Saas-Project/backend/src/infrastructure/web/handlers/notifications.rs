@@ -0,0 +1,137 @@
+// Notification center handlers
+// The signed-in user's in-app inbox, its read state, and which channels they
+// are notified over for each event. Users only ever see and change their own.
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::notifications::{ChannelPreference, Locale, Notification};
+use crate::infrastructure::web::middleware::auth::AuthenticatedUser;
+use crate::services::notifications::{Inbox, NotificationPreferences, PreferencesUpdate};
+use crate::shared::errors::AppResult;
+
+use super::AppState;
+
+/// Notifications listed when no limit is given, and the most listed
+const DEFAULT_NOTIFICATIONS: u32 = 20;
+const MAX_NOTIFICATIONS: u32 = 100;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(inbox))
+        .route("/read-all", post(mark_all_read))
+        .route("/preferences", get(preferences).put(update_preferences))
+        .route("/:id/read", post(mark_read))
+        .route("/:id/unread", post(mark_unread))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct InboxQuery {
+    #[serde(default)]
+    pub unread_only: bool,
+    pub limit: Option<u32>,
+}
+
+/// Newest notifications first, with the number still unread
+async fn inbox(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Query(query): Query<InboxQuery>,
+) -> AppResult<Json<Inbox>> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_NOTIFICATIONS)
+        .clamp(1, MAX_NOTIFICATIONS);
+    let inbox = app_state
+        .notifications()
+        .inbox(*user.user_id.as_uuid(), query.unread_only, limit as i64)
+        .await?;
+    Ok(Json(inbox))
+}
+
+async fn mark_read(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(notification_id): Path<Uuid>,
+) -> AppResult<Json<Notification>> {
+    let notification = app_state
+        .notifications()
+        .set_read(*user.user_id.as_uuid(), notification_id, true)
+        .await?;
+    Ok(Json(notification))
+}
+
+async fn mark_unread(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(notification_id): Path<Uuid>,
+) -> AppResult<Json<Notification>> {
+    let notification = app_state
+        .notifications()
+        .set_read(*user.user_id.as_uuid(), notification_id, false)
+        .await?;
+    Ok(Json(notification))
+}
+
+#[derive(Debug, Serialize)]
+pub struct MarkAllReadResponse {
+    pub marked: u64,
+}
+
+async fn mark_all_read(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<MarkAllReadResponse>> {
+    let marked = app_state
+        .notifications()
+        .mark_all_read(*user.user_id.as_uuid())
+        .await?;
+    Ok(Json(MarkAllReadResponse { marked }))
+}
+
+/// Language, WhatsApp number and every event's channels
+async fn preferences(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+) -> AppResult<Json<NotificationPreferences>> {
+    let preferences = app_state
+        .notifications()
+        .preferences(*user.user_id.as_uuid())
+        .await?;
+    Ok(Json(preferences))
+}
+
+/// Fields left out stay as they are; channels not listed keep their setting
+#[derive(Debug, Deserialize)]
+pub struct UpdatePreferencesRequest {
+    pub locale: Option<Locale>,
+    /// An empty string removes the number
+    pub whatsapp_number: Option<String>,
+    #[serde(default)]
+    pub channels: Vec<ChannelPreference>,
+}
+
+async fn update_preferences(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Json(request): Json<UpdatePreferencesRequest>,
+) -> AppResult<Json<NotificationPreferences>> {
+    let update = PreferencesUpdate {
+        locale: request.locale,
+        whatsapp_number: request
+            .whatsapp_number
+            .map(|number| Some(number).filter(|n| !n.trim().is_empty())),
+        channels: request.channels,
+    };
+    let preferences = app_state
+        .notifications()
+        .update_preferences(*user.user_id.as_uuid(), update)
+        .await?;
+    Ok(Json(preferences))
+}
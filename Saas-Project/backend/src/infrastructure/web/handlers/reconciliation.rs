@@ -0,0 +1,319 @@
+// Bank reconciliation handlers - statement sessions, line matching, adjustments
+// and the reconciliation report. Amounts are in the smallest currency unit.
+// Mounted under `/finance` next to the imports, for the company's owner and
+// super admins.
+
+use axum::{
+    extract::{DefaultBodyLimit, Json, Multipart, Path, State},
+    http::StatusCode,
+    routing::{delete, get, post},
+    Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        imports::{ColumnMapping, StatementLayout},
+        reconciliation::{
+            AdjustmentRequest, ReconciliationReport, StartReconciliation, StatementImport,
+            StatementLineInput,
+        },
+    },
+    infrastructure::{
+        spreadsheet::{read_table, SpreadsheetFormat},
+        web::middleware::auth::AuthenticatedUser,
+    },
+    shared::errors::{AppError, AppResult},
+};
+
+use super::billing::managed_company;
+use super::AppState;
+
+/// Same limit as transaction imports
+const MAX_STATEMENT_FILE_BYTES: usize = 10 * 1024 * 1024;
+
+pub fn routes() -> Router<AppState> {
+    let session = "/companies/:company_id/reconciliations/:reconciliation_id";
+    Router::new()
+        .route("/companies/:company_id/reconciliations", post(start_reconciliation))
+        .route(session, get(get_reconciliation).delete(cancel_reconciliation))
+        .route(&format!("{}/closing-balance", session), post(set_closing_balance))
+        .route(&format!("{}/lines", session), post(add_lines))
+        .route(
+            &format!("{}/statement", session),
+            post(import_statement).layer(DefaultBodyLimit::max(MAX_STATEMENT_FILE_BYTES)),
+        )
+        .route(&format!("{}/lines/:line_id", session), delete(remove_line))
+        .route(&format!("{}/auto-match", session), post(auto_match))
+        .route(&format!("{}/matches", session), post(match_line))
+        .route(&format!("{}/matches/:line_id", session), delete(unmatch_line))
+        .route(&format!("{}/lines/:line_id/adjustment", session), post(create_adjustment))
+        .route(&format!("{}/complete", session), post(complete_reconciliation))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClosingBalanceRequest {
+    pub closing_balance: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddLinesRequest {
+    pub lines: Vec<StatementLineInput>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AutoMatchRequest {
+    pub window_days: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MatchRequest {
+    pub line_id: Uuid,
+    pub transaction_id: Uuid,
+}
+
+async fn start_reconciliation(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path(company_id): Path<Uuid>,
+    Json(req): Json<StartReconciliation>,
+) -> AppResult<(StatusCode, Json<ReconciliationReport>)> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let report = app_state
+        .reconciliation()
+        .start(company.id, *user.user_id.as_uuid(), req)
+        .await?;
+    Ok((StatusCode::CREATED, Json(report)))
+}
+
+/// Matched items plus the unmatched items on both sides
+async fn get_reconciliation(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, id)): Path<(Uuid, Uuid)>,
+) -> AppResult<Json<ReconciliationReport>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    Ok(Json(app_state.reconciliation().report(company.id, id).await?))
+}
+
+async fn cancel_reconciliation(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, id)): Path<(Uuid, Uuid)>,
+) -> AppResult<StatusCode> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    app_state.reconciliation().cancel(company.id, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn set_closing_balance(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<ClosingBalanceRequest>,
+) -> AppResult<Json<ReconciliationReport>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let report = app_state
+        .reconciliation()
+        .set_closing_balance(company.id, id, req.closing_balance)
+        .await?;
+    Ok(Json(report))
+}
+
+async fn add_lines(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<AddLinesRequest>,
+) -> AppResult<Json<ReconciliationReport>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    Ok(Json(app_state.reconciliation().add_lines(company.id, id, req.lines).await?))
+}
+
+/// Multipart upload with a `file` part, plus optionally a bank `layout` or an
+/// inline JSON `mapping`, as for transaction imports
+async fn import_statement(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, id)): Path<(Uuid, Uuid)>,
+    mut multipart: Multipart,
+) -> AppResult<Json<StatementImport>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+
+    let mut file: Option<(String, Vec<u8>)> = None;
+    let mut layout = None;
+    let mut mapping = None;
+
+    let invalid_body = |e: axum::extract::multipart::MultipartError| {
+        AppError::BadRequest(format!("Invalid upload: {}", e))
+    };
+
+    while let Some(field) = multipart.next_field().await.map_err(invalid_body)? {
+        let name = field.name().unwrap_or_default().to_string();
+        match name.as_str() {
+            "file" => {
+                let file_name = field.file_name().unwrap_or("statement.csv").to_string();
+                let data = field.bytes().await.map_err(invalid_body)?;
+                file = Some((file_name, data.to_vec()));
+            }
+            "layout" => {
+                let value = field.text().await.map_err(invalid_body)?;
+                let value = value.trim();
+                if !value.is_empty() && value != "auto" {
+                    layout = Some(value.parse::<StatementLayout>().map_err(AppError::Validation)?);
+                }
+            }
+            "mapping" => {
+                let value = field.text().await.map_err(invalid_body)?;
+                mapping = Some(
+                    serde_json::from_str::<ColumnMapping>(&value)
+                        .map_err(|e| AppError::Validation(format!("Invalid mapping: {}", e)))?,
+                );
+            }
+            _ => {}
+        }
+    }
+
+    let (file_name, data) =
+        file.ok_or_else(|| AppError::Validation("A file is required".to_string()))?;
+    let format = SpreadsheetFormat::from_file_name(&file_name).ok_or_else(|| {
+        AppError::Validation("Unsupported file type, upload a CSV or Excel file".to_string())
+    })?;
+
+    let table = read_table(&data, format)?;
+    let result = app_state
+        .reconciliation()
+        .import_statement(company.id, id, layout, mapping, &table)
+        .await?;
+
+    Ok(Json(result))
+}
+
+async fn remove_line(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, id, line_id)): Path<(Uuid, Uuid, Uuid)>,
+) -> AppResult<Json<ReconciliationReport>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    Ok(Json(app_state.reconciliation().remove_line(company.id, id, line_id).await?))
+}
+
+async fn auto_match(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, id)): Path<(Uuid, Uuid)>,
+    req: Option<Json<AutoMatchRequest>>,
+) -> AppResult<Json<ReconciliationReport>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let window_days = req.and_then(|Json(r)| r.window_days);
+    Ok(Json(app_state.reconciliation().auto_match(company.id, id, window_days).await?))
+}
+
+async fn match_line(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<MatchRequest>,
+) -> AppResult<Json<ReconciliationReport>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let report = app_state
+        .reconciliation()
+        .match_line(company.id, id, req.line_id, req.transaction_id)
+        .await?;
+    Ok(Json(report))
+}
+
+async fn unmatch_line(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, id, line_id)): Path<(Uuid, Uuid, Uuid)>,
+) -> AppResult<Json<ReconciliationReport>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    Ok(Json(app_state.reconciliation().unmatch_line(company.id, id, line_id).await?))
+}
+
+/// Books the statement line as a transaction, e.g. a bank fee missing from the books
+async fn create_adjustment(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, id, line_id)): Path<(Uuid, Uuid, Uuid)>,
+    req: Option<Json<AdjustmentRequest>>,
+) -> AppResult<Json<ReconciliationReport>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let request = req.map(|Json(r)| r).unwrap_or_default();
+    let report = app_state
+        .reconciliation()
+        .create_adjustment(company.id, *user.user_id.as_uuid(), id, line_id, request)
+        .await?;
+    Ok(Json(report))
+}
+
+/// Completes the session and locks the matched transactions
+async fn complete_reconciliation(
+    State(app_state): State<AppState>,
+    user: AuthenticatedUser,
+    Path((company_id, id)): Path<(Uuid, Uuid)>,
+) -> AppResult<Json<ReconciliationReport>> {
+    let company = managed_company(&app_state, &user, company_id).await?;
+    let report = app_state
+        .reconciliation()
+        .complete(company.id, *user.user_id.as_uuid(), id)
+        .await?;
+    Ok(Json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use crate::infrastructure::demo::DEMO_OWNER;
+    use crate::infrastructure::web::routes::testing::{company_of, demo_api, get, json, login, send};
+
+    #[tokio::test]
+    async fn test_reconciliation_session_through_the_finance_routes() {
+        let (app, app_state) = demo_api().await;
+        let owner = login(&app, DEMO_OWNER).await;
+        let company_id = company_of(&app_state, DEMO_OWNER).await;
+
+        let (_, accounts) = send(
+            &app,
+            get(&format!("/finance/companies/{}/accounts", company_id), Some(&owner)),
+        )
+        .await;
+        let bank = accounts
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|account| account["account_type"] == "Bank")
+            .unwrap();
+        let start = json!({
+            "account_id": bank["id"],
+            "statement_end_date": "2024-05-31",
+            "opening_balance": 0,
+            "closing_balance": 25_000,
+        });
+        let (status, report) = send(
+            &app,
+            json("POST", &format!("/finance/companies/{}/reconciliations", company_id), &owner, start),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED, "{}", report);
+        let session = format!(
+            "/finance/companies/{}/reconciliations/{}",
+            company_id,
+            report["session"]["id"].as_str().unwrap()
+        );
+
+        let lines = json!({ "lines": [{ "line_date": "2024-05-10", "description": "Setoran", "amount": 25_000 }] });
+        let (status, report) = send(&app, json("POST", &format!("{}/lines", session), &owner, lines)).await;
+        assert_eq!(status, StatusCode::OK, "{}", report);
+        assert_eq!(report["unmatched_lines"].as_array().unwrap().len(), 1);
+
+        let (status, _) = send(&app, get(&session, None)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = send(&app, get(&session, Some(&owner))).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
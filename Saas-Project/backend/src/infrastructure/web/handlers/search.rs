@@ -0,0 +1,58 @@
+// Unified search endpoint
+// Ranked, typed hits across companies, licenses and documents the caller can see
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+
+use crate::domain::search::{SearchCursor, SearchHitType, SearchPage, SearchQuery, SearchScope};
+use crate::infrastructure::web::middleware::auth::AuthenticatedUser;
+use crate::shared::errors::{AppError, AppResult};
+
+use super::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    pub q: String,
+    /// Comma-separated list of `company`, `license`, `document`
+    pub types: Option<String>,
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/", get(search))
+}
+
+pub async fn search(
+    State(state): State<AppState>,
+    user: AuthenticatedUser,
+    Query(params): Query<SearchParams>,
+) -> AppResult<Json<SearchPage>> {
+    let scope = SearchScope::for_user(&user.user_id, &user.role);
+    let mut query = SearchQuery::new(&params.q, scope).map_err(AppError::Validation)?;
+
+    if let Some(types) = params.types.as_deref() {
+        let types = types
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(str::parse::<SearchHitType>)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(AppError::Validation)?;
+        query = query.with_types(types);
+    }
+    if let Some(limit) = params.limit {
+        query = query.with_limit(limit);
+    }
+    if let Some(cursor) = params.cursor.as_deref() {
+        query = query.with_cursor(SearchCursor::decode(cursor).map_err(AppError::BadRequest)?);
+    }
+
+    let page = state.search_repository().search(&query).await?;
+    Ok(Json(page))
+}
@@ -12,18 +12,13 @@ use uuid::Uuid;
 
 use crate::application::queries::GetUserQuery;
 use crate::application::query_handlers::UserQueryHandler;
+use crate::domain::filters::{UserFilter, UserSortField};
 use crate::shared::errors::AppError;
+use crate::shared::query::{ListParams, ListQuery, Page};
 
 // Import AppState from handlers module
 use super::AppState;
 
-#[derive(Deserialize)]
-pub struct ListUsersQuery {
-    pub page: Option<u32>,
-    pub limit: Option<u32>,
-    pub search: Option<String>,
-}
-
 #[derive(Deserialize)]
 pub struct SearchUsersQuery {
     pub q: Option<String>,
@@ -64,37 +59,23 @@ pub async fn get_user(
 
 pub async fn list_users(
     State(state): State<AppState>,
-    Query(params): Query<ListUsersQuery>,
-) -> Result<Json<Value>, AppError> {
-    let page = params.page.unwrap_or(1);
-    let limit = params.limit.unwrap_or(20);
-    
+    Query(filter): Query<UserFilter>,
+    Query(params): Query<ListParams>,
+) -> Result<Json<Page<Value>>, AppError> {
+    let query = ListQuery::<UserFilter, UserSortField>::from_params(filter, &params)?;
     let query_handler = UserQueryHandler::new(state.user_repository().clone());
-    
-    match query_handler.handle_list_users(page, limit).await {
-        Ok(response) => {
-            let user_responses: Vec<Value> = response.data
-                .into_iter()
-                .map(|user| json!({
-                    "id": user.id.to_string(),
-                    "email": user.email.value(),
-                    "name": user.full_name,
-                    "role": format!("{:?}", user.role),
-                    "verified": user.email_verified_at.is_some(),
-                    "created_at": user.created_at,
-                    "updated_at": user.updated_at
-                }))
-                .collect();
 
-            Ok(Json(json!({
-                "users": user_responses,
-                "total": response.total,
-                "page": response.page,
-                "limit": response.limit
-            })))
-        }
-        Err(e) => Err(AppError::InternalError(e.to_string()))
-    }
+    let page = query_handler.handle_list(&query).await?;
+
+    Ok(Json(page.map(|user| json!({
+        "id": user.id.to_string(),
+        "email": user.email.value(),
+        "name": user.full_name,
+        "role": format!("{:?}", user.role),
+        "verified": user.email_verified_at.is_some(),
+        "created_at": user.created_at,
+        "updated_at": user.updated_at
+    }))))
 }
 
 pub async fn get_current_user_profile(
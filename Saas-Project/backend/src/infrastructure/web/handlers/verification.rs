@@ -0,0 +1,92 @@
+// Public license verification endpoint
+// Where certificate QR codes point, and where banks, marketplaces and buyers
+// look licenses up by license number or NIB. No authentication: anyone may
+// check a license's current standing, and nothing more. The routes have their
+// own per-IP rate limit, and every lookup is recorded in the audit trail.
+
+use axum::{
+    extract::{ConnectInfo, Path, Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Json, Response},
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+use std::net::SocketAddr;
+
+use crate::infrastructure::web::middleware::rate_limit::client_ip;
+use crate::services::license_verification::LookupClient;
+use crate::shared::errors::{AppError, AppResult};
+
+use super::AppState;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(lookup))
+        .route("/:token", get(verify_token))
+}
+
+/// Exactly one of the two
+#[derive(Debug, Deserialize)]
+pub struct LookupQuery {
+    pub license_number: Option<String>,
+    pub nib: Option<String>,
+}
+
+fn lookup_client(
+    app_state: &AppState,
+    headers: &HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+) -> LookupClient {
+    let peer = connect_info.map(|ConnectInfo(addr)| addr.ip());
+    LookupClient {
+        ip: client_ip(headers, peer, &app_state.config().rate_limit).map(|ip| ip.to_string()),
+        user_agent: headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string),
+    }
+}
+
+/// The standing can change at any time, so answers are never reused
+fn uncached(body: impl serde::Serialize) -> Response {
+    ([(header::CACHE_CONTROL, "no-store")], Json(body)).into_response()
+}
+
+/// Issued licenses by license number (one) or by the company's NIB (all)
+async fn lookup(
+    State(app_state): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    Query(query): Query<LookupQuery>,
+) -> AppResult<Response> {
+    let client = lookup_client(&app_state, &headers, connect_info);
+    let verification = app_state.license_verification();
+    let licenses = match (query.license_number, query.nib) {
+        (Some(license_number), None) => {
+            vec![verification.lookup_license_number(&license_number, &client).await?]
+        }
+        (None, Some(nib)) => verification.lookup_nib(&nib, &client).await?,
+        _ => {
+            return Err(AppError::Validation(
+                "Give either license_number or nib".to_string(),
+            ))
+        }
+    };
+    Ok(uncached(licenses))
+}
+
+/// The public facts of the license a certificate's token was minted for
+async fn verify_token(
+    State(app_state): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    Path(token): Path<String>,
+) -> AppResult<Response> {
+    let client = lookup_client(&app_state, &headers, connect_info);
+    let license = app_state
+        .license_verification()
+        .verify_token(&token, &client)
+        .await?;
+    Ok(uncached(license))
+}
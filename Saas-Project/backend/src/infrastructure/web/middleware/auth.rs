@@ -1,13 +1,15 @@
 use axum::{
     async_trait,
-    extract::{FromRequestParts, Request, State},
+    extract::{FromRequestParts, Query, Request, State},
     http::{header::AUTHORIZATION, request::Parts, StatusCode},
     middleware::Next,
     response::Response,
 };
+use serde::Deserialize;
 
 use crate::domain::entities::UserRole;
 use crate::domain::value_objects::UserId;
+use crate::services::auth::Claims;
 use crate::shared::errors::AppError;
 
 #[derive(Clone)]
@@ -42,34 +44,85 @@ pub async fn require_auth(
     })?;
 
     // Validate token
-    let user_id = ctx
+    let claims = ctx
         .auth_service()
-        .extract_user_id(token)
+        .validate_token(token)
         .map_err(|_| AppError::Unauthorized("Invalid token".to_string()))?;
+    let user = session_user(&ctx, &claims, token).await?;
 
-    let user_role = ctx
-        .auth_service()
-        .extract_user_role(token)
+    // Add authenticated user to request extensions
+    request.extensions_mut().insert(user);
+
+    Ok(next.run(request).await)
+}
+
+/// The account behind validated token claims, if it may still use its session
+async fn session_user(
+    ctx: &AppState,
+    claims: &Claims,
+    token: &str,
+) -> Result<AuthenticatedUser, AppError> {
+    let user_id = uuid::Uuid::parse_str(&claims.sub)
+        .map(UserId)
         .map_err(|_| AppError::Unauthorized("Invalid token claims".to_string()))?;
 
+    // Suspension, role changes and forced password resets end sessions
+    // before the token expires, so the account is checked on every request
+    let account = ctx
+        .user_repository()
+        .find_by_id(&user_id)
+        .await?
+        .filter(|account| account.can_use_session(claims.iat))
+        .ok_or_else(|| AppError::Unauthorized("Session is no longer valid".to_string()))?;
+    let user_role = account.role;
+
     // Get company_id from the token claims or user service
     let company_id = ctx
         .auth_service()
         .extract_company_id(token)
         .unwrap_or_else(|_| uuid::Uuid::new_v4()); // Default fallback
 
-    // Add authenticated user to request extensions
-    request.extensions_mut().insert(AuthenticatedUser {
+    Ok(AuthenticatedUser {
         user_id,
         company_id,
         role: user_role,
-    });
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StreamTicketQuery {
+    pub ticket: Option<String>,
+}
+
+/// Authentication for the live update stream. Browsers' EventSource cannot
+/// set headers, so a stream ticket may be passed as `?ticket=` instead of the
+/// bearer token. Access tokens are never accepted in the URL, since URLs end
+/// up in logs and traces.
+pub async fn require_stream_auth(
+    State(ctx): State<AppState>,
+    Query(query): Query<StreamTicketQuery>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if request.headers().contains_key(AUTHORIZATION) {
+        return require_auth(State(ctx), request, next).await;
+    }
+
+    let ticket = query.ticket.ok_or_else(|| {
+        AppError::Unauthorized("Missing authorization header or stream ticket".to_string())
+    })?;
+    let claims = ctx
+        .auth_service()
+        .validate_stream_ticket(&ticket)
+        .map_err(|_| AppError::Unauthorized("Invalid stream ticket".to_string()))?;
+    let user = session_user(&ctx, &claims, &ticket).await?;
+
+    request.extensions_mut().insert(user);
 
     Ok(next.run(request).await)
 }
 
 /// Extract authenticated user from request
-#[allow(dead_code)]
 pub fn extract_user(request: &Request) -> Result<&AuthenticatedUser, AppError> {
     request
         .extensions()
@@ -0,0 +1,33 @@
+// Plan entitlement middleware
+// Gates whole modules on the caller's subscription plan. Finance routes act for
+// the caller rather than a company in the path, so the owner-wide check is
+// used: one company on a plan with the finance module unlocks it. Staff are
+// never gated. Layer it inside `require_auth`, which provides the caller.
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::domain::billing::Requirement;
+use crate::domain::entities::UserRole;
+use crate::infrastructure::web::handlers::AppState;
+use crate::shared::errors::AppResult;
+
+use super::auth::extract_user;
+
+/// Refuses with 402 unless the caller's plan includes the finance module
+pub async fn require_finance_module(
+    State(ctx): State<AppState>,
+    request: Request,
+    next: Next,
+) -> AppResult<Response> {
+    let user = extract_user(&request)?;
+    if user.role != UserRole::SuperAdmin && user.role != UserRole::AdminStaff {
+        ctx.billing()
+            .require_for_owner(*user.user_id.as_uuid(), Requirement::FinanceModule)
+            .await?;
+    }
+    Ok(next.run(request).await)
+}
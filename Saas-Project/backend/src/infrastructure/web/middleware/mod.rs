@@ -1 +1,5 @@
 pub mod auth;
+pub mod entitlements;
+pub mod metrics;
+pub mod rate_limit;
+pub mod request_id;
@@ -0,0 +1,232 @@
+// Rate limiting middleware
+// Applies the `RateLimiter` to every route and reports the binding bucket in
+// the `RateLimit-Limit`/`-Remaining`/`-Reset`/`-Policy` headers (IETF
+// ratelimit-headers draft). Refused requests get 429 with `Retry-After`.
+
+use axum::{
+    extract::{ConnectInfo, MatchedPath, Request, State},
+    http::{header::AUTHORIZATION, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::config::RateLimitConfig;
+use crate::infrastructure::monitoring::track_rate_limited;
+use crate::infrastructure::rate_limit::{Decision, RateLimiter, RouteGroup};
+use crate::services::auth::AuthService;
+use crate::shared::errors::AppError;
+
+#[derive(Clone)]
+pub struct RateLimitState {
+    pub limiter: Arc<RateLimiter>,
+    pub auth_service: AuthService,
+}
+
+pub async fn rate_limit(State(state): State<RateLimitState>, request: Request, next: Next) -> Response {
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| request.uri().path().to_owned());
+    let Some(group) = RouteGroup::classify(request.method(), &path) else {
+        return next.run(request).await;
+    };
+
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip());
+    let ip = client_ip(request.headers(), peer, state.limiter.config());
+    // Only a valid token identifies the user; anything else counts against the IP
+    let user_id = bearer_token(request.headers())
+        .and_then(|token| state.auth_service.extract_user_id(token).ok())
+        .map(|user_id| user_id.0);
+
+    let (scope, decision) = state.limiter.check(group, ip, user_id).await;
+
+    let mut response = if decision.allowed {
+        next.run(request).await
+    } else {
+        warn!(
+            group = group.as_str(),
+            scope = scope.as_str(),
+            client_ip = ?ip,
+            "Rate limit exceeded"
+        );
+        track_rate_limited(group.as_str(), scope.as_str());
+        AppError::RateLimit.into_response()
+    };
+
+    set_rate_limit_headers(response.headers_mut(), &decision);
+    response
+}
+
+/// The client's address, else the peer address of the connection. When
+/// `X-Forwarded-For` is trusted, its right-most entry not added by one of the
+/// trusted proxies: everything left of that came from the client and can be
+/// anything it likes.
+pub fn client_ip(headers: &HeaderMap, peer: Option<IpAddr>, config: &RateLimitConfig) -> Option<IpAddr> {
+    if !config.trust_forwarded_for {
+        return peer;
+    }
+    let forwarded = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect::<Vec<_>>();
+    let forwarded = forwarded
+        .iter()
+        .rev()
+        .map(|entry| entry.trim().parse::<IpAddr>().ok())
+        .find(|ip| !ip.is_some_and(|ip| config.trusted_proxies.contains(&ip)))
+        .flatten();
+
+    forwarded.or(peer)
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+}
+
+/// Whole seconds, rounded up so clients never retry too early
+fn ceil_secs(duration: Duration) -> u64 {
+    duration.as_millis().div_ceil(1000) as u64
+}
+
+fn set_rate_limit_headers(headers: &mut HeaderMap, decision: &Decision) {
+    let policy = decision.policy;
+    let values = [
+        ("ratelimit-limit", policy.limit.to_string()),
+        ("ratelimit-remaining", decision.remaining.to_string()),
+        ("ratelimit-reset", ceil_secs(decision.reset_after).to_string()),
+        ("ratelimit-policy", format!("{};w={}", policy.limit, policy.window_secs)),
+    ];
+    for (name, value) in values {
+        headers.insert(name, HeaderValue::from_str(&value).expect("numeric header value"));
+    }
+
+    if let Some(retry_after) = decision.retry_after {
+        headers.insert(
+            "retry-after",
+            HeaderValue::from(ceil_secs(retry_after).max(1)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RatePolicy;
+    use crate::infrastructure::rate_limit::{BucketStore, PlanResolver};
+    use axum::{body::Body, http::StatusCode, routing::post, Router};
+    use futures::executor::block_on;
+    use tower::Service;
+
+    struct NoCompany;
+
+    #[async_trait::async_trait]
+    impl PlanResolver for NoCompany {
+        async fn resolve(&self, _user_id: uuid::Uuid) -> Option<(uuid::Uuid, crate::domain::companies::SubscriptionPlan)> {
+            None
+        }
+    }
+
+    fn config() -> RateLimitConfig {
+        RateLimitConfig {
+            enabled: true,
+            trust_forwarded_for: true,
+            trusted_proxies: vec!["10.0.0.1".parse().unwrap()],
+            default_policy: RatePolicy::new(100, 60),
+            login_policy: RatePolicy::new(2, 60),
+            upload_policy: RatePolicy::new(10, 60),
+            verify_policy: RatePolicy::new(10, 60),
+            free_plan: RatePolicy::new(300, 60),
+            basic_plan: RatePolicy::new(1_200, 60),
+            pro_plan: RatePolicy::new(6_000, 60),
+        }
+    }
+
+    fn app() -> Router {
+        let state = RateLimitState {
+            limiter: Arc::new(RateLimiter::new(config(), BucketStore::new(None), Arc::new(NoCompany))),
+            auth_service: AuthService::new("test-secret".to_string()),
+        };
+
+        Router::new()
+            .route("/api/v1/auth/login", post(|| async { "token" }))
+            .route("/readyz", axum::routing::get(|| async { "ready" }))
+            .layer(axum::middleware::from_fn_with_state(state, rate_limit))
+    }
+
+    fn send(app: &Router, uri: &str, client_ip: &str) -> Response {
+        send_forwarded(app, uri, &format!("{}, 10.0.0.1", client_ip))
+    }
+
+    fn send_forwarded(app: &Router, uri: &str, forwarded_for: &str) -> Response {
+        let method = if uri == "/readyz" { "GET" } else { "POST" };
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("x-forwarded-for", forwarded_for)
+            .body(Body::empty())
+            .unwrap();
+        let mut service = app.clone();
+        block_on(service.call(request)).unwrap()
+    }
+
+    #[test]
+    fn test_login_is_limited_per_ip_with_headers() {
+        let app = app();
+
+        let first = send(&app, "/api/v1/auth/login", "203.0.113.7");
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(first.headers()["ratelimit-limit"], "2");
+        assert_eq!(first.headers()["ratelimit-remaining"], "1");
+        assert_eq!(first.headers()["ratelimit-policy"], "2;w=60");
+        assert!(first.headers().get("retry-after").is_none());
+
+        send(&app, "/api/v1/auth/login", "203.0.113.7");
+        let refused = send(&app, "/api/v1/auth/login", "203.0.113.7");
+        assert_eq!(refused.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(refused.headers()["ratelimit-remaining"], "0");
+        assert_eq!(refused.headers()["retry-after"], "30");
+
+        // Another client is unaffected, and probes are never limited
+        assert_eq!(send(&app, "/api/v1/auth/login", "198.51.100.2").status(), StatusCode::OK);
+        let probe = send(&app, "/readyz", "203.0.113.7");
+        assert_eq!(probe.status(), StatusCode::OK);
+        assert!(probe.headers().get("ratelimit-limit").is_none());
+    }
+
+    #[test]
+    fn test_spoofed_forwarded_for_entries_do_not_change_the_bucket() {
+        let app = app();
+
+        // The client writes whatever it likes left of what the proxies append
+        for spoofed in ["198.51.100.1", "not-an-ip, 192.0.2.9"] {
+            let forwarded = format!("{}, 203.0.113.7, 10.0.0.1", spoofed);
+            let response = send_forwarded(&app, "/api/v1/auth/login", &forwarded);
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let refused = send_forwarded(&app, "/api/v1/auth/login", "198.51.100.3, 203.0.113.7, 10.0.0.1");
+        assert_eq!(refused.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // Untrusted, the header is ignored altogether
+        let config = RateLimitConfig {
+            trust_forwarded_for: false,
+            ..config()
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", HeaderValue::from_static("198.51.100.1"));
+        let peer = Some(IpAddr::from([192, 0, 2, 1]));
+        assert_eq!(client_ip(&headers, peer, &config), peer);
+    }
+}
@@ -0,0 +1,185 @@
+// Request correlation middleware
+// Accepts the client's X-Request-Id (or generates one), opens the request span,
+// continues the caller's W3C trace if a `traceparent` header is present, and
+// echoes X-Request-Id on the response. Handlers run inside a RequestContext
+// scope so error bodies and outgoing calls can reference the ids.
+
+use axum::{
+    extract::MatchedPath,
+    http::{HeaderValue, Request},
+    response::Response,
+};
+use futures::future::BoxFuture;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::infrastructure::monitoring::{continue_trace, trace_id, UNMATCHED_ROUTE};
+use crate::shared::request_context::{RequestContext, REQUEST_ID_HEADER};
+
+/// Longest client-supplied request id that is accepted as is
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Client ids are kept when they are short printable ASCII, so they are safe
+/// to log and to echo back in a header
+fn accepted_request_id(value: &HeaderValue) -> Option<String> {
+    let value = value.to_str().ok()?.trim();
+    let valid = !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LEN
+        && value.bytes().all(|b| b.is_ascii_graphic());
+    valid.then(|| value.to_string())
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestIdLayer;
+
+impl<S> Layer<S> for RequestIdLayer {
+    type Service = RequestId<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestId { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RequestId<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for RequestId<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        let request_id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(accepted_request_id)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let route = req
+            .extensions()
+            .get::<MatchedPath>()
+            .map(|path| path.as_str().to_owned())
+            .unwrap_or_else(|| UNMATCHED_ROUTE.to_owned());
+
+        let span = tracing::info_span!(
+            "http.request",
+            otel.name = %format!("{} {}", req.method(), route),
+            otel.kind = "server",
+            http.method = %req.method(),
+            http.route = %route,
+            http.status_code = tracing::field::Empty,
+            request_id = %request_id,
+        );
+        continue_trace(&span, req.headers());
+
+        let context = RequestContext {
+            request_id: request_id.clone(),
+            trace_id: trace_id(&span),
+        };
+
+        // Downstream code sees the effective id, whether it was supplied or generated
+        let header = HeaderValue::from_str(&request_id).expect("request id is visible ASCII");
+        req.headers_mut().insert(REQUEST_ID_HEADER, header.clone());
+        req.extensions_mut().insert(context.clone());
+
+        let future = {
+            let _entered = span.enter();
+            context.clone().sync_scope(|| self.inner.call(req))
+        };
+
+        Box::pin(
+            context
+                .scope(async move {
+                    let mut response = future.await?;
+                    tracing::Span::current().record("http.status_code", response.status().as_u16());
+                    response.headers_mut().insert(REQUEST_ID_HEADER, header);
+                    Ok(response)
+                })
+                .instrument(span),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::errors::AppError;
+    use axum::{body::Body, routing::get, Router};
+    use futures::executor::block_on;
+
+    async fn failing() -> Result<(), AppError> {
+        Err(AppError::NotFound("License not found".to_string()))
+    }
+
+    fn send(app: &Router, request_id: Option<&str>) -> Response {
+        let mut request = Request::builder().uri("/licenses/1");
+        if let Some(id) = request_id {
+            request = request.header(REQUEST_ID_HEADER, id);
+        }
+        let mut service = app.clone();
+        block_on(service.call(request.body(Body::empty()).unwrap())).unwrap()
+    }
+
+    fn error_body(response: Response) -> serde_json::Value {
+        let body = block_on(axum::body::to_bytes(response.into_body(), usize::MAX)).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[test]
+    fn test_request_id_is_echoed_and_included_in_error_body() {
+        let app = Router::new()
+            .route("/licenses/:id", get(failing))
+            .layer(RequestIdLayer);
+
+        let response = send(&app, Some("support-ticket-42"));
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "support-ticket-42");
+
+        let body = error_body(response);
+        assert_eq!(body["error"]["code"], "NOT_FOUND");
+        assert_eq!(body["error"]["request_id"], "support-ticket-42");
+
+        // Unusable ids are replaced with a generated one
+        for supplied in [None, Some("has spaces"), Some(&*"x".repeat(200))] {
+            let response = send(&app, supplied);
+            let generated = response.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+            assert!(Uuid::parse_str(generated).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_error_body_carries_the_continued_trace_id() {
+        use opentelemetry::trace::TracerProvider as _;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let provider = opentelemetry_sdk::trace::TracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        let app = Router::new()
+            .route("/licenses/:id", get(failing))
+            .layer(RequestIdLayer);
+
+        let body = tracing::subscriber::with_default(subscriber, || {
+            let request = Request::builder()
+                .uri("/licenses/1")
+                .header("traceparent", "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01")
+                .body(Body::empty())
+                .unwrap();
+            let mut service = app.clone();
+            error_body(block_on(service.call(request)).unwrap())
+        });
+
+        assert_eq!(body["error"]["trace_id"], "4bf92f3577b34da6a3ce929d0e0e4736");
+    }
+}
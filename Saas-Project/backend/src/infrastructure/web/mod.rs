@@ -1,6 +1,9 @@
 // Web infrastructure - HTTP handlers and middleware
 // Axum-based REST API implementation
 
+pub mod context;
+pub mod etag;
 pub mod handlers;
 pub mod middleware;
 pub mod responses;
+pub mod routes;
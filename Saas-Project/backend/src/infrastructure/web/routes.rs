@@ -0,0 +1,304 @@
+// API routes - which handlers are public and which sit behind the token and
+// plan checks. `main` nests these under `/api/v1` inside its global layers.
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use super::handlers::{self, AppState};
+use super::middleware::{auth, entitlements};
+
+/// Everything under `/api/v1`
+pub fn api_routes(state: AppState) -> Router<AppState> {
+    // Finance is part of the paid plans; the plan check needs the caller, so
+    // it runs inside the token check below
+    let finance = Router::new()
+        .route("/finance", get(handlers::finance::placeholder))
+        // Customer contacts, sales invoices and receivables
+        .nest("/finance", handlers::invoicing::routes())
+        // Spreadsheet and bank statement imports
+        .nest("/finance", handlers::imports::routes())
+        // Bank reconciliation against statements
+        .nest("/finance", handlers::reconciliation::routes())
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            entitlements::require_finance_module,
+        ));
+
+    // Routes that need a bearer token; `require_auth` checks it and the
+    // account behind it once for all of them, and the handlers read the user
+    // it carries
+    let protected = Router::new()
+        .route("/me", get(handlers::auth::get_profile))
+        .route("/auth/logout", post(handlers::auth::logout))
+        // User management routes
+        .nest("/users", handlers::users::routes())
+        // Company management routes
+        .nest("/companies", handlers::companies::routes())
+        // License management routes
+        .nest("/licenses", handlers::licenses::routes())
+        // Unified search across companies, licenses and documents
+        .nest("/search", handlers::search::routes())
+        // In-app notifications and notification preferences
+        .nest("/notifications", handlers::notifications::routes())
+        // Subscription plans, invoices and entitlements
+        .nest("/billing", handlers::billing::routes())
+        // Admin dashboards
+        .nest("/admin", handlers::admin::routes())
+        // Tickets for opening the live update stream
+        .route("/events/ticket", post(handlers::events::issue_stream_ticket))
+        .merge(finance)
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), auth::require_auth));
+
+    // Live updates over server-sent events; EventSource cannot send headers,
+    // so a stream ticket may come in the query instead
+    let live = Router::new()
+        .nest("/events", handlers::events::routes())
+        .route_layer(axum::middleware::from_fn_with_state(
+            state,
+            auth::require_stream_auth,
+        ));
+
+    Router::new()
+        // Authentication routes (public)
+        .route("/auth/register", post(handlers::auth::register))
+        .route("/auth/login", post(handlers::auth::login))
+        .route("/auth/refresh", post(handlers::auth::refresh_token))
+        .route(
+            "/auth/reset-password",
+            post(handlers::auth::request_password_reset),
+        )
+        .route(
+            "/auth/reset-password/confirm",
+            post(handlers::auth::confirm_password_reset),
+        )
+        .route("/auth/health", get(handlers::auth::health_check))
+        // Public license verification, e.g. from certificate QR codes
+        .nest("/verify", handlers::verification::routes())
+        // Payment gateway notifications, checked by signature
+        .nest("/payments", handlers::billing::payment_routes())
+        .merge(protected)
+        .merge(live)
+        // Placeholder routes for other handlers (public for now)
+        // .route("/licensing", get(handlers::licensing::placeholder))
+        .route("/business", get(handlers::business::placeholder))
+        .route("/files", get(handlers::files::placeholder))
+}
+
+/// The API on the seeded demo context, for routed handler tests
+#[cfg(test)]
+pub(crate) mod testing {
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+        Router,
+    };
+    use std::sync::Arc;
+    use tower::Service;
+    use uuid::Uuid;
+
+    use crate::config::AppConfig;
+    use crate::domain::value_objects::Email;
+    use crate::infrastructure::demo::DEMO_PASSWORD;
+    use crate::infrastructure::web::context::demo_context;
+    use crate::infrastructure::web::handlers::AppState;
+
+    pub async fn demo_api() -> (Router, AppState) {
+        let config = AppConfig::demo_from_env().unwrap();
+        let state: AppState = Arc::new(demo_context(config).await.unwrap());
+        (super::api_routes(state.clone()).with_state(state.clone()), state)
+    }
+
+    /// Status and JSON body of the response, `Null` when the body is not JSON
+    pub async fn send(app: &Router, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let response = app.clone().call(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    pub fn get(uri: &str, token: Option<&str>) -> Request<Body> {
+        let mut request = Request::get(uri);
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        request.body(Body::empty()).unwrap()
+    }
+
+    pub fn json(method: &str, uri: &str, token: &str, body: serde_json::Value) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    /// First company of a seeded owner
+    pub async fn company_of(app_state: &AppState, email: &str) -> Uuid {
+        let owner = app_state
+            .user_repository()
+            .find_by_email(&Email::new(email).unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        app_state
+            .company_repository()
+            .find_by_owner_id(owner.id.as_uuid())
+            .await
+            .unwrap()[0]
+            .id
+    }
+
+    /// Access token of a seeded account
+    pub async fn login(app: &Router, email: &str) -> String {
+        let request = Request::post("/auth/login")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                serde_json::json!({ "email": email, "password": DEMO_PASSWORD }).to_string(),
+            ))
+            .unwrap();
+        let (status, body) = send(app, request).await;
+        assert_eq!(status, StatusCode::OK, "login as {} failed: {}", email, body);
+        body["access_token"].as_str().unwrap().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use serde_json::json;
+    use tower::Service;
+
+    use super::testing::{demo_api, get, json, login, send};
+    use crate::domain::value_objects::Email;
+    use crate::infrastructure::demo::{DEMO_OWNER, DEMO_PASSWORD, DEMO_SUPER_ADMIN};
+
+    /// A route from every group behind `require_auth`
+    const PROTECTED: &[&str] = &[
+        "/me",
+        "/users/profile",
+        "/companies/my",
+        "/licenses",
+        "/search?q=warung",
+        "/notifications",
+        "/billing/plans",
+        "/admin/dashboard",
+        "/events/stream",
+        "/finance",
+        "/finance/imports/layouts",
+    ];
+
+    #[tokio::test]
+    async fn test_protected_routes_refuse_requests_without_a_valid_token() {
+        let (app, _) = demo_api().await;
+
+        for uri in PROTECTED {
+            let (status, body) = send(&app, get(uri, None)).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{} without a token", uri);
+            assert_eq!(body["error"]["code"], "UNAUTHORIZED");
+
+            let (status, _) = send(&app, get(uri, Some("not-a-token"))).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{} with a bad token", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_public_routes_need_no_token_and_a_valid_token_gets_through() {
+        let (app, _) = demo_api().await;
+
+        let (status, _) = send(&app, get("/auth/health", None)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let token = login(&app, DEMO_OWNER).await;
+        let (status, profile) = send(&app, get("/me", Some(&token))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(profile["email"], DEMO_OWNER);
+
+        // Past the token check, roles are still enforced by the handlers
+        let (status, _) = send(&app, get("/admin/dashboard", Some(&token))).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_protected_routes_refuse_a_suspended_account_with_a_valid_token() {
+        let (app, app_state) = demo_api().await;
+        let owner_token = login(&app, DEMO_OWNER).await;
+        let admin_token = login(&app, DEMO_SUPER_ADMIN).await;
+        let owner = app_state
+            .user_repository()
+            .find_by_email(&Email::new(DEMO_OWNER).unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let uri = format!("/admin/users/{}/suspend", owner.id.as_uuid());
+        let (status, _) = send(&app, json("POST", &uri, &admin_token, json!({}))).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // The token has not expired, but the account is checked on every request
+        for uri in PROTECTED {
+            let (status, _) = send(&app, get(uri, Some(&owner_token))).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{} after suspension", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_is_exchanged_for_new_tokens() {
+        let (app, _) = demo_api().await;
+        let post = |uri: &str, body: serde_json::Value| {
+            Request::post(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let (status, tokens) = send(
+            &app,
+            post("/auth/login", json!({ "email": DEMO_OWNER, "password": DEMO_PASSWORD })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let refresh_token = tokens["refresh_token"].as_str().unwrap();
+
+        let (status, refreshed) =
+            send(&app, post("/auth/refresh", json!({ "refresh_token": refresh_token }))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(refreshed["access_token"].is_string());
+        assert!(refreshed["refresh_token"].is_string());
+
+        let (status, _) = send(&app, post("/auth/refresh", json!({ "refresh_token": "bad" }))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_opens_with_a_ticket_but_not_with_a_token_in_the_url() {
+        let (app, _) = demo_api().await;
+        let token = login(&app, DEMO_OWNER).await;
+
+        // Access tokens never go in the URL, where logs and traces record them
+        for query in ["access_token", "ticket"] {
+            let uri = format!("/events/stream?{}={}", query, token);
+            let (status, _) = send(&app, get(&uri, None)).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "token as ?{}=", query);
+        }
+
+        let (status, issued) = send(&app, json("POST", "/events/ticket", &token, json!({}))).await;
+        assert_eq!(status, StatusCode::OK);
+        let ticket = issued["ticket"].as_str().unwrap();
+
+        // A ticket opens the stream and nothing else
+        let (status, _) = send(&app, get("/me", Some(ticket))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = send(&app, json("POST", "/events/ticket", ticket, json!({}))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let uri = format!("/events/stream?ticket={}", ticket);
+        let response = app.clone().call(get(&uri, None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/event-stream");
+    }
+}
@@ -3,7 +3,6 @@
 pub mod application;
 pub mod domain;
 pub mod infrastructure;
-pub mod middleware;
 pub mod services;
 pub mod shared;
 pub mod config;
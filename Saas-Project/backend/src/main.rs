@@ -1,8 +1,9 @@
 use axum::{
     response::Json,
-    routing::{get, post},
+    routing::get,
     Router,
 };
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::net::TcpListener;
 use tower_http::{
@@ -23,76 +24,129 @@ mod shared;
 mod tests;
 
 use config::AppConfig;
+use domain::imports::TransactionImportService;
+use domain::reconciliation::ReconciliationService;
 use domain::repositories::{CompanyRepository, UserRepository};
 use infrastructure::{
     database::manager::DatabaseManager,
+    live_updates::LiveUpdateHub,
+    messaging::{RecordingSender, SmtpEmailSender, WhatsAppCloudSender},
+    midtrans::MidtransClient,
+    storage::LocalFileStorage,
+    rate_limit::{BucketStore, CompanyPlanResolver, PlanResolver, PostgresPlanResolver, RateLimiter},
     repositories::{
-        CachedLicenseRepository, LicenseRepository, PostgresCompanyRepository,
-        PostgresUserRepository,
+        CachedCompanyRepository, CachedLicenseRepository, CachedUserRepository, LicenseRepository,
+        LicenseUnitOfWork, PostgresAdminStatsRepository, PostgresAnalyticsRepository,
+        PostgresBillingRepository, PostgresCertificateRepository, PostgresCompanyRepository,
+        PostgresFinancialAccountRepository, PostgresImportRepository, PostgresInvoicingRepository,
+        PostgresLicenseCommentRepository, PostgresNotificationRepository,
+        PostgresReconciliationRepository,
+        PostgresSearchRepository,
+        PostgresSlaRepository, PostgresUserRepository, PostgresVerificationAuditRepository,
+    },
+    web::{
+        handlers,
+        middleware::{
+            metrics::HttpMetricsLayer,
+            rate_limit::{self, RateLimitState},
+            request_id::RequestIdLayer,
+        },
     },
-    web::handlers,
 };
-use crate::infrastructure::cache::CacheService;
+use crate::infrastructure::cache::{CacheService, LocalCache};
 use services::auth::AuthService;
+use services::analytics_refresher::AnalyticsRefresher;
+use services::billing::BillingService;
+use services::invoicing::InvoicingService;
+use services::license_certificates::CertificateService;
+use services::license_comments::LicenseCommentService;
+use services::license_verification::{LicenseVerificationService, VerificationSigner};
+use services::notification_dispatcher::NotificationDispatcher;
+use services::notifications::NotificationService;
+use services::sla_monitor::SlaMonitor;
 use shared::errors::AppError;
 
-// Define AppContext and AppState types
-#[derive(Clone)]
-pub struct AppContext {
-    pub config: AppConfig,
-    pub db: DatabaseManager,
-    pub auth_service: AuthService,
-    pub cache_service: Option<infrastructure::cache::CacheService>,
-    pub user_repository: Arc<dyn UserRepository + Send + Sync>,
-    pub company_repository: Arc<dyn CompanyRepository + Send + Sync>,
-    pub license_repository: Arc<dyn LicenseRepository + Send + Sync>,
-}
-
-// Implement the AppStateType trait for AppContext
-impl infrastructure::web::handlers::AppStateType for AppContext {
-    fn company_repository(&self) -> &Arc<dyn domain::repositories::CompanyRepository + Send + Sync> {
-        &self.company_repository
-    }
-    
-    fn user_repository(&self) -> &Arc<dyn domain::repositories::UserRepository + Send + Sync> {
-        &self.user_repository
-    }
-    
-    fn license_repository(&self) -> &Arc<dyn infrastructure::repositories::LicenseRepository + Send + Sync> {
-        &self.license_repository
-    }
-    
-    fn auth_service(&self) -> &services::auth::AuthService {
-        &self.auth_service
-    }
-
-    fn config(&self) -> &config::AppConfig {
-        &self.config
-    }
-
-    fn cache_service(&self) -> &Option<infrastructure::cache::CacheService> {
-        &self.cache_service
-    }
-}
-
 // Use the AppState type alias from the handlers module
 pub use infrastructure::web::handlers::AppState;
+use infrastructure::web::{
+    context::{demo_context, AppContext},
+    routes,
+};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize tracing (structured logging as recommended)
-    tracing_subscriber::fmt()
-        .with_target(false)
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .json()
-        .init();
+    // `--demo` serves from seeded in-memory repositories instead of Postgres
+    let demo = std::env::args().skip(1).any(|arg| arg == "--demo");
+
+    // Load configuration
+    let config = if demo {
+        AppConfig::demo_from_env()?
+    } else {
+        AppConfig::from_env()?
+    };
+
+    // Initialize tracing (structured logging plus OpenTelemetry spans)
+    let _tracing = infrastructure::monitoring::init_tracing(&config.tracing)?;
 
     info!("🚀 Starting SaaS UMKM Platform Backend");
+    match &config.tracing.otlp_endpoint {
+        Some(endpoint) => info!("🛰️ Exporting traces to {}", endpoint),
+        None => info!("ℹ️ No OTLP endpoint configured, traces are not exported"),
+    }
+
+    // Install the Prometheus recorder before anything records metrics
+    infrastructure::monitoring::init_telemetry();
+    info!("📈 Metrics recorder installed");
 
-    // Load configuration
-    let config = AppConfig::from_env()?;
     info!("📋 Configuration loaded");
 
+    let app_state = if demo {
+        Arc::new(demo_context(config.clone()).await?)
+    } else {
+        Arc::new(context(config.clone()).await?)
+    };
+
+    // Watch in-flight license applications for SLA warnings and breaches
+    app_state.sla_monitor.clone().spawn(std::time::Duration::from_secs(
+        config.sla.sweep_interval_secs,
+    ));
+
+    // Bring the daily analytics rollups up to date with recent changes
+    Arc::new(AnalyticsRefresher::new(app_state.analytics.clone())).spawn(
+        std::time::Duration::from_secs(config.analytics.refresh_interval_secs),
+    );
+
+    // Send queued email and WhatsApp notifications, retrying failed ones
+    app_state.notification_dispatcher.clone().spawn(std::time::Duration::from_secs(
+        config.notifications.dispatch_interval_secs,
+    ));
+
+    // Renew subscriptions at the end of their period and dun unpaid invoices
+    app_state.billing.clone().spawn(std::time::Duration::from_secs(
+        config.billing.sweep_interval_secs,
+    ));
+
+    // Mark customer invoices past their due date as overdue
+    app_state.invoicing.clone().spawn(std::time::Duration::from_secs(
+        config.invoicing.overdue_sweep_interval_secs,
+    ));
+
+    // Build application router
+    let app = create_app(app_state.clone()).await;
+
+    // Start server
+    let addr = format!("{}:{}", config.app_host, config.app_port);
+    info!("🌐 Server starting on {}", addr);
+
+    let listener = TcpListener::bind(&addr).await?;
+    // Peer addresses are the rate limiter's client IP when no proxy header is trusted
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
+
+    Ok(())
+}
+
+/// Application context backed by Postgres, behind the Redis cache when configured
+async fn context(config: AppConfig) -> Result<AppContext, Box<dyn std::error::Error>> {
     // Initialize database connection
     let db = DatabaseManager::new(&config.database_url, 5).await?;
     info!("🗄️ Database connection established");
@@ -107,15 +161,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let auth_service = AuthService::new(config.jwt_secret.clone());
     info!("🔐 Authentication service initialized");
 
-    // Initialize repositories
-    let user_repository = Arc::new(PostgresUserRepository::new(db.pool().clone()));
-    let company_repository = Arc::new(PostgresCompanyRepository::new(db.pool().clone()));
-
     // Initialize cache service if Redis URL is provided
     let cache_service = match &config.redis_url {
         Some(redis_url) => match infrastructure::cache::CacheService::new(redis_url) {
             Ok(cache) => {
                 info!("🔄 Redis cache service initialized");
+                let local = LocalCache::new(
+                    config.cache.l1_capacity,
+                    std::time::Duration::from_secs(config.cache.l1_ttl_secs),
+                );
+                let cache = match local {
+                    Some(local) => {
+                        info!("🧠 In-process L1 cache enabled ({} entries)", config.cache.l1_capacity);
+                        cache.with_local_cache(local)
+                    }
+                    None => cache,
+                };
+                cache.spawn_invalidation_listener();
                 Some(cache)
             }
             Err(err) => {
@@ -129,6 +191,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
+    // Initialize repositories, behind the cache when Redis is available
+    let mut user_repository: Arc<dyn UserRepository + Send + Sync> =
+        Arc::new(PostgresUserRepository::new(db.pool().clone()));
+    let mut company_repository: Arc<dyn CompanyRepository + Send + Sync> =
+        Arc::new(PostgresCompanyRepository::new(db.pool().clone()));
+    if let Some(cache) = &cache_service {
+        let cache = Arc::new(cache.clone());
+        user_repository = Arc::new(CachedUserRepository::new(user_repository, cache.clone()));
+        company_repository = Arc::new(CachedCompanyRepository::new(company_repository, cache));
+    }
+    let search_repository = Arc::new(PostgresSearchRepository::new(db.pool().clone()));
+
     // Use cached license repository if Redis is available
     let license_repository: Arc<dyn LicenseRepository + Send + Sync> = match &cache_service {
         Some(cache) => {
@@ -143,37 +217,150 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             Arc::new(CachedLicenseRepository::<CacheService>::new(db.pool().clone()))
         }
     };
+    let license_work = Arc::new(LicenseUnitOfWork::new(
+        db.pool().clone(),
+        cache_service.clone().map(Arc::new),
+    ));
+
+    let sla_monitor = Arc::new(SlaMonitor::new(
+        config.sla.policy(),
+        license_repository.clone(),
+        Arc::new(PostgresSlaRepository::new(db.pool().clone())),
+        user_repository.clone(),
+    ));
+    let admin_stats = Arc::new(PostgresAdminStatsRepository::new(db.pool().clone()));
+    let analytics = Arc::new(PostgresAnalyticsRepository::new(db.pool().clone()));
+
+    let license_verification = Arc::new(LicenseVerificationService::new(
+        license_repository.clone(),
+        company_repository.clone(),
+        Arc::new(PostgresVerificationAuditRepository::new(db.pool().clone())),
+        VerificationSigner::new(&config.certificates.signing_key),
+        &config.certificates.public_base_url,
+    ));
+    let certificates = Arc::new(CertificateService::new(
+        Arc::new(PostgresCertificateRepository::new(db.pool().clone())),
+        company_repository.clone(),
+        Arc::new(LocalFileStorage::new(&config.storage_dir)),
+        license_verification.clone(),
+    ));
+
+    let notification_repository = Arc::new(PostgresNotificationRepository::new(db.pool().clone()));
+    let (email, whatsapp) = message_senders(&config)?;
+    let notification_dispatcher = Arc::new(NotificationDispatcher::new(
+        notification_repository.clone(),
+        email.clone(),
+        whatsapp,
+        config.notifications.retry_policy(),
+    ));
+    let notifications = Arc::new(NotificationService::new(
+        notification_repository,
+        user_repository.clone(),
+        config.notifications.app_url.clone(),
+    ));
+
+    // Event streams on every instance see updates made through any of them
+    let live_updates = match &config.redis_url {
+        Some(redis_url) => match redis::Client::open(redis_url.as_str()) {
+            Ok(client) => {
+                let hub = LiveUpdateHub::new().with_redis(client);
+                hub.spawn_listener();
+                hub
+            }
+            Err(err) => {
+                warn!("⚠️ Invalid REDIS_URL, live updates only reach this instance: {}", err);
+                LiveUpdateHub::new()
+            }
+        },
+        None => LiveUpdateHub::new(),
+    };
+    let license_comments = Arc::new(LicenseCommentService::new(
+        Arc::new(PostgresLicenseCommentRepository::new(db.pool().clone())),
+        license_repository.clone(),
+        user_repository.clone(),
+        notifications.clone(),
+        live_updates.clone(),
+    ));
+    let billing = Arc::new(BillingService::new(
+        Arc::new(PostgresBillingRepository::new(db.pool().clone())),
+        company_repository.clone(),
+        notifications.clone(),
+        config.billing.policy(),
+    ));
+    let invoicing = Arc::new(InvoicingService::new(
+        Arc::new(PostgresInvoicingRepository::new(db.pool().clone())),
+        Arc::new(PostgresFinancialAccountRepository::new(db.pool().clone())),
+        email.clone(),
+        config.invoicing.policy(),
+    ));
+    let imports = Arc::new(TransactionImportService::new(
+        Arc::new(PostgresImportRepository::new(db.pool().clone())),
+        Arc::new(PostgresFinancialAccountRepository::new(db.pool().clone())),
+    ));
+    let reconciliation = Arc::new(ReconciliationService::new(
+        Arc::new(PostgresReconciliationRepository::new(db.pool().clone())),
+        Arc::new(PostgresFinancialAccountRepository::new(db.pool().clone())),
+    ));
+
+    let midtrans = MidtransClient::from_config(&config.external_apis)?.map(Arc::new);
 
     info!("📊 Repositories initialized");
 
-    // Create application context
-    let app_state = Arc::new(AppContext {
-        config: config.clone(),
-        db,
+    Ok(AppContext {
+        config,
+        db: Some(db),
         auth_service,
         cache_service,
         user_repository,
         company_repository,
         license_repository,
-    });
-
-    // Build application router
-    let app = create_app(app_state.clone()).await;
-
-    // Rate limiter initialization notification
-    if let Some(_) = &config.rate_limiter {
-        info!("🔄 Rate limiter initialized and ready");
-        // The governor crate doesn't require explicit cleanup
-    }
-
-    // Start server
-    let addr = format!("{}:{}", config.app_host, config.app_port);
-    info!("🌐 Server starting on {}", addr);
-
-    let listener = TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+        license_work,
+        search_repository,
+        sla_monitor,
+        admin_stats,
+        analytics,
+        certificates,
+        license_verification,
+        notifications,
+        notification_dispatcher,
+        email,
+        live_updates,
+        license_comments,
+        billing,
+        invoicing,
+        imports,
+        reconciliation,
+        midtrans,
+    })
+}
 
-    Ok(())
+/// Email and WhatsApp senders; a channel that is not configured only records
+/// its messages
+fn message_senders(
+    config: &AppConfig,
+) -> Result<
+    (
+        Arc<dyn domain::notifications::MessageSender>,
+        Arc<dyn domain::notifications::MessageSender>,
+    ),
+    AppError,
+> {
+    let email: Arc<dyn domain::notifications::MessageSender> = if config.smtp.is_configured() {
+        info!("📧 Sending email through {}:{}", config.smtp.host, config.smtp.port);
+        Arc::new(SmtpEmailSender::new(&config.smtp)?)
+    } else {
+        warn!("⚠️ SMTP is not configured, notification emails are only logged");
+        Arc::new(RecordingSender::new("email"))
+    };
+    let whatsapp: Arc<dyn domain::notifications::MessageSender> =
+        if config.notifications.whatsapp.is_configured() {
+            info!("💬 Sending WhatsApp messages through the Business Cloud API");
+            Arc::new(WhatsAppCloudSender::new(&config.notifications.whatsapp)?)
+        } else {
+            warn!("⚠️ WhatsApp is not configured, notification messages are only logged");
+            Arc::new(RecordingSender::new("whatsapp"))
+        };
+    Ok((email, whatsapp))
 }
 
 #[instrument(skip(state))]
@@ -201,54 +388,52 @@ async fn create_app(state: AppState) -> Router {
     router = router
         // Health check endpoint
         .route("/health", get(health_check))
+        // Prometheus scrape endpoint
+        .route("/metrics", get(infrastructure::monitoring::metrics_handler))
         // Static file serving for uploads
         .nest_service("/uploads", ServeDir::new(state.config().upload_dir.clone()))
         // API routes
-        .nest("/api/v1", create_api_routes());
+        .nest("/api/v1", routes::api_routes(state.clone()));
 
     // Add rate limiting middleware if configured
-    router = if state.config().rate_limiter.is_some() {
-        use crate::infrastructure::web::middleware::auth::require_auth;
-        router.route_layer(axum::middleware::from_fn_with_state(
-            state.clone(),
-            require_auth,
-        ))
-    } else {
-        router
-    };
+    let rate_limit_config = &state.config().rate_limit;
+    if rate_limit_config.enabled {
+        let redis = state.config().redis_url.as_deref().and_then(|url| {
+            redis::Client::open(url)
+                .map_err(|err| warn!("⚠️ Invalid REDIS_URL, rate limits are per instance: {}", err))
+                .ok()
+        });
+        let plans: Arc<dyn PlanResolver> = match state.database() {
+            Some(db) => Arc::new(PostgresPlanResolver::new(db.pool().clone())),
+            None => Arc::new(CompanyPlanResolver::new(
+                state.company_repository().clone(),
+                state.billing().clone(),
+            )),
+        };
+        let limiter = RateLimiter::new(rate_limit_config.clone(), BucketStore::new(redis), plans);
+        router = router.layer(axum::middleware::from_fn_with_state(
+            RateLimitState {
+                limiter: Arc::new(limiter),
+                auth_service: state.auth_service().clone(),
+            },
+            rate_limit::rate_limit,
+        ));
+        info!("🔄 Rate limiter initialized and ready");
+    }
+
+    // Load balancer probes, added after the rate limiter so they are never throttled
+    router = router
+        .route("/livez", get(handlers::health::livez))
+        .route("/readyz", get(handlers::health::readyz))
+        // Request metrics; layered after the routes so it wraps every one of them
+        .layer(HttpMetricsLayer)
+        // Request id and trace span, outermost so metrics run inside the span
+        .layer(RequestIdLayer);
 
     // Finish building the router with state
     router.with_state(state)
 }
 
-fn create_api_routes() -> Router<AppState> {
-    Router::new()
-        // Authentication routes (public)
-        .route("/auth/register", post(handlers::auth::register))
-        .route("/auth/login", post(handlers::auth::login))
-        .route("/auth/refresh", post(handlers::auth::refresh_token))
-        .route(
-            "/auth/reset-password",
-            post(handlers::auth::request_password_reset),
-        )
-        .route("/auth/health", get(handlers::auth::health_check))
-        // Protected routes (will be added with middleware in the full app)
-        .route("/me", get(handlers::auth::get_profile))
-        .route("/auth/logout", post(handlers::auth::logout))
-        // User management routes
-        .nest("/users", handlers::users::routes())
-        // Company management routes
-        .nest("/companies", handlers::companies::routes())
-        // License management routes
-        .nest("/licenses", handlers::licenses::routes())
-        // Placeholder routes for other handlers (public for now)
-        // .route("/licensing", get(handlers::licensing::placeholder))
-        .route("/business", get(handlers::business::placeholder))
-        .route("/finance", get(handlers::finance::placeholder))
-        .route("/admin", get(handlers::admin::placeholder))
-        .route("/files", get(handlers::files::placeholder))
-}
-
 async fn health_check() -> Result<Json<serde_json::Value>, AppError> {
     Ok(Json(serde_json::json!({
         "status": "healthy",
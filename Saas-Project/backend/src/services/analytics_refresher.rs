@@ -0,0 +1,54 @@
+// Keeps the daily analytics rollups up to date
+// Each run recomputes only the days changed since the last one, so runs are
+// cheap when little changed. Concurrent runs from several instances are safe:
+// each queued day is claimed by exactly one of them.
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::domain::analytics::{AnalyticsRepository, RollupRefresh};
+use crate::infrastructure::monitoring::record_analytics_rollup_refresh;
+use crate::shared::errors::AppResult;
+
+pub struct AnalyticsRefresher {
+    analytics: Arc<dyn AnalyticsRepository>,
+}
+
+impl AnalyticsRefresher {
+    pub fn new(analytics: Arc<dyn AnalyticsRepository>) -> Self {
+        Self { analytics }
+    }
+
+    /// Recomputes the rollups of every day changed since the last refresh
+    pub async fn refresh(&self) -> AppResult<RollupRefresh> {
+        let refresh = self.analytics.refresh().await?;
+        record_analytics_rollup_refresh(
+            refresh.license_days,
+            refresh.registration_days,
+            refresh.finance_days,
+        );
+        debug!(
+            license_days = refresh.license_days,
+            registration_days = refresh.registration_days,
+            finance_days = refresh.finance_days,
+            "📊 Analytics rollups refreshed"
+        );
+        Ok(refresh)
+    }
+
+    /// Refreshes every `interval` until the process exits
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            info!("📊 Analytics rollup refresh every {}s", interval.as_secs());
+            let mut ticks = tokio::time::interval(interval);
+            loop {
+                ticks.tick().await;
+                if let Err(err) = self.refresh().await {
+                    warn!("⚠️ Analytics rollup refresh failed: {}", err);
+                }
+            }
+        })
+    }
+}
@@ -1,5 +1,9 @@
-use argon2::password_hash::{rand_core::OsRng, SaltString};
+use argon2::password_hash::{
+    rand_core::{OsRng, RngCore},
+    SaltString,
+};
 use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 use chrono::{Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
@@ -14,6 +18,7 @@ pub struct AuthService {
     jwt_secret: String,
     access_token_duration: Duration,
     refresh_token_duration: Duration,
+    stream_ticket_duration: Duration,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -77,6 +82,7 @@ impl AuthService {
             jwt_secret,
             access_token_duration: Duration::minutes(15), // 15 minutes
             refresh_token_duration: Duration::days(7),    // 7 days
+            stream_ticket_duration: Duration::seconds(60), // long enough to connect
         }
     }
 
@@ -167,6 +173,61 @@ impl AuthService {
         })
     }
 
+    /// Ticket that opens the live update stream. EventSource can only pass it
+    /// in the URL, which gets logged, so it expires within a minute and is
+    /// signed with its own key: it is no use as an access token and an access
+    /// token is no use as a ticket.
+    pub fn generate_stream_ticket(&self, user_id: &UserId, role: &UserRole) -> Result<String, AuthError> {
+        let now = Utc::now();
+        let claims = Claims {
+            sub: user_id.to_string(),
+            role: role.to_string(),
+            exp: (now + self.stream_ticket_duration).timestamp(),
+            iat: now.timestamp(),
+            jti: Uuid::new_v4().to_string(),
+        };
+
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(&self.stream_ticket_secret()),
+        )
+        .map_err(|_| AuthError::InvalidToken)
+    }
+
+    /// Validate and decode a stream ticket
+    pub fn validate_stream_ticket(&self, ticket: &str) -> Result<Claims, AuthError> {
+        let mut validation = Validation::default();
+        // The default minute of leeway would double the ticket's lifetime
+        validation.leeway = 0;
+
+        decode::<Claims>(
+            ticket,
+            &DecodingKey::from_secret(&self.stream_ticket_secret()),
+            &validation,
+        )
+        .map(|data| data.claims)
+        .map_err(|err| match err.kind() {
+            jsonwebtoken::errors::ErrorKind::ExpiredSignature => AuthError::TokenExpired,
+            _ => AuthError::InvalidToken,
+        })
+    }
+
+    pub fn stream_ticket_duration(&self) -> Duration {
+        self.stream_ticket_duration
+    }
+
+    fn stream_ticket_secret(&self) -> Vec<u8> {
+        format!("{}:event-stream", self.jwt_secret).into_bytes()
+    }
+
+    /// Random one-time token for a password reset; store only its hash
+    pub fn generate_reset_token(&self) -> String {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
     /// Validate and decode JWT token
     pub fn validate_token(&self, token: &str) -> Result<Claims, AuthError> {
         let validation = Validation::default();
@@ -256,4 +317,19 @@ mod tests {
 
         assert_eq!(extracted_id, user.id);
     }
+
+    #[test]
+    fn test_stream_tickets_and_access_tokens_are_not_interchangeable() {
+        let auth_service = AuthService::new("test_secret".to_string());
+        let user = create_test_user();
+
+        let ticket = auth_service.generate_stream_ticket(&user.id, &user.role).unwrap();
+        let claims = auth_service.validate_stream_ticket(&ticket).unwrap();
+        assert_eq!(claims.sub, user.id.to_string());
+        assert!(claims.exp - claims.iat <= 60);
+
+        assert!(auth_service.validate_token(&ticket).is_err());
+        let access_token = auth_service.generate_access_token(&user).unwrap();
+        assert!(auth_service.validate_stream_ticket(&access_token).is_err());
+    }
 }
@@ -0,0 +1,635 @@
+// Subscription billing
+// Plan changes, payments and the periodic sweep that renews subscriptions and
+// duns unpaid invoices all go through here, so every change to a subscription
+// is stored together with the invoices it affects and the company owner hears
+// about new, failed, overdue and written-off invoices. Handlers ask `require`
+// before using something the plan limits and get `AppError::PlanLimit` when it
+// is not covered.
+
+use chrono::{DateTime, Datelike, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::domain::analytics::{reporting_day, reporting_day_start};
+use crate::domain::billing::{
+    monthly_price, rank, BillingChange, BillingPolicy, BillingRepository, DunningStep, Entitlements,
+    InvoiceStatus, PlanChange, PlanLimits, Requirement, Subscription, SubscriptionInvoice,
+    SubscriptionStatus, Usage, PLANS,
+};
+use crate::domain::companies::{Company, SubscriptionPlan};
+use crate::domain::notifications::NotificationEvent;
+use crate::domain::repositories::CompanyRepository;
+use crate::domain::value_objects::Money;
+use crate::infrastructure::monitoring::{record_plan_limit_rejection, record_subscription_invoice};
+use crate::services::notifications::NotificationService;
+use crate::shared::errors::{AppError, AppResult};
+
+/// Subscriptions renewed and invoices dunned per sweep; the rest wait for the next
+const SWEEP_BATCH: i64 = 100;
+
+/// A plan as offered on the pricing page
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlanOffer {
+    pub plan: SubscriptionPlan,
+    /// Per month, before PPN
+    pub monthly_price: Money,
+    pub ppn_rate_bps: i64,
+    pub limits: PlanLimits,
+    /// Days of trial for companies that never subscribed
+    pub trial_days: i64,
+}
+
+/// A company's plan and, once it subscribed, its subscription
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SubscriptionOverview {
+    pub plan: SubscriptionPlan,
+    pub subscription: Option<Subscription>,
+}
+
+/// The subscription after a plan change and the invoice it raised, if any
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlanChangeResult {
+    pub subscription: Subscription,
+    pub invoice: Option<SubscriptionInvoice>,
+}
+
+/// What one sweep did
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BillingSweep {
+    pub renewed: usize,
+    pub ended: usize,
+    pub reminded: usize,
+    pub written_off: usize,
+    pub failed: usize,
+}
+
+/// Start of the Jakarta calendar month `at` falls in
+fn month_start(at: DateTime<Utc>) -> DateTime<Utc> {
+    let day = reporting_day(at);
+    reporting_day_start(day.with_day(1).expect("every month has a first day"))
+}
+
+pub struct BillingService {
+    billing: Arc<dyn BillingRepository>,
+    companies: Arc<dyn CompanyRepository + Send + Sync>,
+    notifications: Arc<NotificationService>,
+    policy: BillingPolicy,
+}
+
+impl BillingService {
+    pub fn new(
+        billing: Arc<dyn BillingRepository>,
+        companies: Arc<dyn CompanyRepository + Send + Sync>,
+        notifications: Arc<NotificationService>,
+        policy: BillingPolicy,
+    ) -> Self {
+        Self {
+            billing,
+            companies,
+            notifications,
+            policy,
+        }
+    }
+
+    pub fn plans(&self) -> Vec<PlanOffer> {
+        PLANS
+            .into_iter()
+            .map(|plan| PlanOffer {
+                plan,
+                monthly_price: monthly_price(plan),
+                ppn_rate_bps: self.policy.ppn_rate_bps,
+                limits: PlanLimits::of(plan),
+                trial_days: if plan == SubscriptionPlan::Free {
+                    0
+                } else {
+                    self.policy.trial_days
+                },
+            })
+            .collect()
+    }
+
+    pub async fn overview(&self, company_id: Uuid) -> AppResult<SubscriptionOverview> {
+        let subscription = self.billing.subscription(company_id).await?;
+        Ok(SubscriptionOverview {
+            plan: subscription
+                .as_ref()
+                .map_or(SubscriptionPlan::Free, Subscription::effective_plan),
+            subscription,
+        })
+    }
+
+    pub async fn entitlements(&self, company_id: Uuid) -> AppResult<Entitlements> {
+        let subscription = self.billing.subscription(company_id).await?;
+        let usage = self.billing.usage(company_id, month_start(Utc::now())).await?;
+        Ok(Entitlements::new(company_id, subscription.as_ref(), usage))
+    }
+
+    pub async fn invoices(&self, company_id: Uuid, limit: i64) -> AppResult<Vec<SubscriptionInvoice>> {
+        self.billing.invoices(company_id, limit).await
+    }
+
+    /// Refuses with `PlanLimit` unless the company's plan covers `requirement`
+    pub async fn require(&self, company_id: Uuid, requirement: Requirement) -> AppResult<()> {
+        let entitlements = self.entitlements(company_id).await?;
+        entitlements.check(requirement).map_err(|message| {
+            record_plan_limit_rejection(&entitlements.plan.to_string(), requirement.limit_name());
+            AppError::PlanLimit(message)
+        })
+    }
+
+    /// Refuses with `PlanLimit` unless `owner_id` may register another company
+    /// or use the finance module: owner-wide limits are those of the best plan
+    /// among their companies
+    pub async fn require_for_owner(&self, owner_id: Uuid, requirement: Requirement) -> AppResult<()> {
+        let companies = self.companies.find_by_owner_id(&owner_id).await?;
+        let mut plan = SubscriptionPlan::Free;
+        for company in &companies {
+            if let Some(subscription) = self.billing.subscription(company.id).await? {
+                plan = std::cmp::max_by_key(plan, subscription.effective_plan(), |p| rank(*p));
+            }
+        }
+        let usage = Usage {
+            companies: companies.len() as i64,
+            ..Usage::default()
+        };
+        PlanLimits::of(plan)
+            .allows(plan, &usage, requirement)
+            .map_err(|message| {
+                record_plan_limit_rejection(&plan.to_string(), requirement.limit_name());
+                AppError::PlanLimit(message)
+            })
+    }
+
+    /// Moves the company to `plan`; see `Subscription::change_plan` for when
+    /// it takes effect
+    pub async fn change_plan(&self, company: &Company, plan: SubscriptionPlan) -> AppResult<PlanChangeResult> {
+        let now = Utc::now();
+        let (subscription, invoices) = match self.billing.subscription(company.id).await? {
+            None => {
+                let (subscription, change) =
+                    Subscription::start(company.id, plan, &self.policy, now).map_err(AppError::Validation)?;
+                let invoices = match change {
+                    PlanChange::Invoiced => vec![SubscriptionInvoice::issue(
+                        &subscription,
+                        Money::idr(0),
+                        &self.policy,
+                        now,
+                    )],
+                    PlanChange::Deferred => Vec::new(),
+                };
+                (subscription, invoices)
+            }
+            Some(mut subscription) => {
+                if subscription.status == SubscriptionStatus::PastDue {
+                    return Err(AppError::Conflict(
+                        "Pay the overdue invoice before changing plans".to_string(),
+                    ));
+                }
+                // The invoices of the period being replaced, should it be
+                let period: Vec<SubscriptionInvoice> = self
+                    .billing
+                    .invoices(company.id, 10)
+                    .await?
+                    .into_iter()
+                    .filter(|i| {
+                        i.subscription_id == subscription.id
+                            && i.period_start == subscription.current_period_start
+                    })
+                    .collect();
+                let before = subscription.clone();
+
+                let mut invoices = Vec::new();
+                if subscription.change_plan(plan, now).map_err(AppError::Validation)? == PlanChange::Invoiced {
+                    let mut credit = Money::idr(0);
+                    for mut invoice in period {
+                        match invoice.status {
+                            InvoiceStatus::Paid => credit = invoice.unused_credit(&before, now),
+                            InvoiceStatus::Open => {
+                                invoice.void(now);
+                                invoices.push(invoice);
+                            }
+                            _ => {}
+                        }
+                    }
+                    invoices.push(SubscriptionInvoice::issue(&subscription, credit, &self.policy, now));
+                }
+                (subscription, invoices)
+            }
+        };
+
+        let stored = self
+            .billing
+            .apply(&BillingChange {
+                subscription: Some(subscription),
+                invoices,
+            })
+            .await?;
+        let invoice = stored
+            .invoices
+            .into_iter()
+            .find(|i| matches!(i.status, InvoiceStatus::Open | InvoiceStatus::Paid));
+        if let Some(invoice) = &invoice {
+            self.invoice_issued(company.owner_id, invoice).await;
+        }
+        Ok(PlanChangeResult {
+            subscription: stored.subscription.expect("applied with a subscription"),
+            invoice,
+        })
+    }
+
+    /// Cancels at the end of the paid period or trial
+    pub async fn cancel(&self, company_id: Uuid) -> AppResult<Subscription> {
+        let mut subscription = self
+            .billing
+            .subscription(company_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("The company has no subscription".to_string()))?;
+        subscription.cancel().map_err(AppError::Validation)?;
+        let stored = self
+            .billing
+            .apply(&BillingChange {
+                subscription: Some(subscription),
+                invoices: Vec::new(),
+            })
+            .await?;
+        Ok(stored.subscription.expect("applied with a subscription"))
+    }
+
+    pub async fn invoice(&self, id: Uuid) -> AppResult<SubscriptionInvoice> {
+        self.billing
+            .invoice(id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Invoice not found".to_string()))
+    }
+
+    pub async fn invoice_by_number(&self, number: &str) -> AppResult<SubscriptionInvoice> {
+        self.billing
+            .invoice_by_number(number)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Invoice not found".to_string()))
+    }
+
+    /// Settles `invoice`, bringing a past-due subscription back in good
+    /// standing once nothing else is overdue. Paying an invoice again with
+    /// the same reference changes nothing, since gateways repeat notifications.
+    pub async fn record_payment(
+        &self,
+        invoice: SubscriptionInvoice,
+        reference: Option<String>,
+    ) -> AppResult<SubscriptionInvoice> {
+        if invoice.status == InvoiceStatus::Paid && invoice.payment_reference == reference {
+            return Ok(invoice);
+        }
+        let now = Utc::now();
+        let mut paid = invoice;
+        paid.mark_paid(reference, now).map_err(AppError::Conflict)?;
+
+        let mut subscription = self.billing.subscription(paid.company_id).await?;
+        if let Some(subscription) = subscription.as_mut() {
+            let still_overdue = self
+                .billing
+                .open_invoices(subscription.id)
+                .await?
+                .iter()
+                .any(|i| i.id != paid.id && i.is_overdue(now));
+            if !still_overdue {
+                subscription.settle();
+            }
+        }
+
+        let stored = self
+            .billing
+            .apply(&BillingChange {
+                subscription,
+                invoices: vec![paid],
+            })
+            .await?;
+        let paid = stored.invoices.into_iter().next().expect("applied with the invoice");
+        record_subscription_invoice(&paid.plan.to_string(), "paid");
+        Ok(paid)
+    }
+
+    /// Notes a declined payment and tells the owner; dunning carries on
+    pub async fn record_payment_failure(
+        &self,
+        mut invoice: SubscriptionInvoice,
+        reason: String,
+    ) -> AppResult<SubscriptionInvoice> {
+        invoice
+            .record_failed_payment(reason.clone(), Utc::now())
+            .map_err(AppError::Conflict)?;
+        let stored = self
+            .billing
+            .apply(&BillingChange {
+                subscription: None,
+                invoices: vec![invoice],
+            })
+            .await?;
+        let invoice = stored.invoices.into_iter().next().expect("applied with the invoice");
+        if let Some(owner_id) = self.owner_of(invoice.company_id).await {
+            self.notifications
+                .notify_invoice_best_effort(owner_id, &invoice, NotificationEvent::InvoicePaymentFailed, Some(reason))
+                .await;
+        }
+        Ok(invoice)
+    }
+
+    async fn owner_of(&self, company_id: Uuid) -> Option<Uuid> {
+        match self.companies.find_by_id(&company_id).await {
+            Ok(company) => company.map(|c| c.owner_id),
+            Err(err) => {
+                warn!("⚠️ Failed to look up the owner of company {}: {}", company_id, err);
+                None
+            }
+        }
+    }
+
+    async fn invoice_issued(&self, owner_id: Uuid, invoice: &SubscriptionInvoice) {
+        record_subscription_invoice(&invoice.plan.to_string(), "issued");
+        // Fully credited invoices are paid already; there is nothing to ask for
+        if invoice.status == InvoiceStatus::Open {
+            self.notifications
+                .notify_invoice_best_effort(owner_id, invoice, NotificationEvent::InvoiceIssued, None)
+                .await;
+        }
+    }
+
+    /// Starts the next period of a subscription whose period ended
+    async fn renew(&self, mut subscription: Subscription, now: DateTime<Utc>) -> AppResult<PlanChange> {
+        let change = subscription.renew();
+        let invoices = match change {
+            PlanChange::Invoiced => vec![SubscriptionInvoice::issue(
+                &subscription,
+                Money::idr(0),
+                &self.policy,
+                now,
+            )],
+            PlanChange::Deferred => Vec::new(),
+        };
+        let stored = self
+            .billing
+            .apply(&BillingChange {
+                subscription: Some(subscription),
+                invoices,
+            })
+            .await?;
+        if let Some(invoice) = stored.invoices.first() {
+            if let Some(owner_id) = self.owner_of(invoice.company_id).await {
+                self.invoice_issued(owner_id, invoice).await;
+            }
+        }
+        Ok(change)
+    }
+
+    /// Takes the next dunning step of an open invoice
+    async fn dun(&self, mut invoice: SubscriptionInvoice, now: DateTime<Utc>) -> AppResult<Option<DunningStep>> {
+        let Some(step) = invoice.dun(&self.policy, now) else {
+            return Ok(None);
+        };
+        let mut invoices = vec![invoice.clone()];
+        let mut subscription = self
+            .billing
+            .subscription(invoice.company_id)
+            .await?
+            .filter(|s| s.id == invoice.subscription_id);
+        if let Some(subscription) = subscription.as_mut() {
+            match step {
+                DunningStep::Reminded(_) => subscription.mark_past_due(),
+                DunningStep::WrittenOff => {
+                    subscription.cancel_for_nonpayment();
+                    // Later periods are not owed by a company back on the free plan
+                    for mut open in self.billing.open_invoices(subscription.id).await? {
+                        if open.id != invoice.id {
+                            open.void(now);
+                            invoices.push(open);
+                        }
+                    }
+                }
+            }
+        }
+        self.billing
+            .apply(&BillingChange {
+                subscription,
+                invoices,
+            })
+            .await?;
+
+        let event = match step {
+            DunningStep::Reminded(_) => NotificationEvent::InvoiceOverdue,
+            DunningStep::WrittenOff => {
+                record_subscription_invoice(&invoice.plan.to_string(), "written_off");
+                NotificationEvent::SubscriptionDowngraded
+            }
+        };
+        if let Some(owner_id) = self.owner_of(invoice.company_id).await {
+            self.notifications
+                .notify_invoice_best_effort(owner_id, &invoice, event, None)
+                .await;
+        }
+        Ok(Some(step))
+    }
+
+    /// Renews subscriptions whose period ended and takes the dunning steps
+    /// that are due. A subscription or invoice that fails, e.g. because a
+    /// payment changed it meanwhile, is logged and retried on the next sweep.
+    pub async fn sweep(&self, now: DateTime<Utc>) -> AppResult<BillingSweep> {
+        let mut sweep = BillingSweep::default();
+
+        for subscription in self.billing.due_subscriptions(now, SWEEP_BATCH).await? {
+            let id = subscription.id;
+            match self.renew(subscription, now).await {
+                Ok(PlanChange::Invoiced) => sweep.renewed += 1,
+                Ok(PlanChange::Deferred) => sweep.ended += 1,
+                Err(err) => {
+                    warn!("⚠️ Failed to renew subscription {}: {}", id, err);
+                    sweep.failed += 1;
+                }
+            }
+        }
+
+        for invoice in self.billing.dunning_due(now, SWEEP_BATCH).await? {
+            let number = invoice.number.clone();
+            match self.dun(invoice, now).await {
+                Ok(Some(DunningStep::Reminded(_))) => sweep.reminded += 1,
+                Ok(Some(DunningStep::WrittenOff)) => sweep.written_off += 1,
+                Ok(None) => {}
+                Err(err) => {
+                    warn!("⚠️ Failed to dun invoice {}: {}", number, err);
+                    sweep.failed += 1;
+                }
+            }
+        }
+
+        Ok(sweep)
+    }
+
+    /// Sweeps every `interval` until the process exits
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            info!("💳 Billing sweep every {}s", interval.as_secs());
+            let mut ticks = tokio::time::interval(interval);
+            loop {
+                ticks.tick().await;
+                if let Err(err) = self.sweep(Utc::now()).await {
+                    warn!("⚠️ Billing sweep failed: {}", err);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::companies::{BusinessType, CompanyAddress};
+    use crate::domain::entities::{User, UserRole};
+    use crate::domain::licenses::{License, LicenseType};
+    use crate::domain::value_objects::Email;
+    use crate::infrastructure::repositories::{
+        InMemoryBillingRepository, InMemoryCompanyRepository, InMemoryLicenseRepository,
+        InMemoryNotificationRepository, InMemoryUserRepository, LicenseRepository,
+    };
+    use chrono::Duration;
+
+    struct Fixture {
+        service: BillingService,
+        notifications: Arc<NotificationService>,
+        licenses: InMemoryLicenseRepository,
+        company: Company,
+    }
+
+    async fn setup(policy: BillingPolicy) -> Fixture {
+        let owner = User::new(
+            Email::new("budi@example.id").unwrap(),
+            "hash".to_string(),
+            "Budi Santoso".to_string(),
+            UserRole::UmkmOwner,
+        );
+        let company = Company::new(
+            owner.id.0,
+            "Toko Makmur".to_string(),
+            BusinessType::UD,
+            "Kuliner".to_string(),
+            CompanyAddress::new(
+                "Jl. Malioboro 1".to_string(),
+                "Yogyakarta".to_string(),
+                "DI Yogyakarta".to_string(),
+                "55271".to_string(),
+            ),
+        );
+        let companies = InMemoryCompanyRepository::new();
+        companies.save(&company).await.unwrap();
+        let licenses = InMemoryLicenseRepository::new();
+        let notifications = Arc::new(NotificationService::new(
+            Arc::new(InMemoryNotificationRepository::new()),
+            Arc::new(InMemoryUserRepository::with_users(vec![owner])),
+            "https://app.example.id".to_string(),
+        ));
+        let service = BillingService::new(
+            Arc::new(InMemoryBillingRepository::new(companies.clone(), licenses.clone())),
+            Arc::new(companies),
+            notifications.clone(),
+            policy,
+        );
+        Fixture {
+            service,
+            notifications,
+            licenses,
+            company,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trial_renews_into_an_invoice_that_is_dunned_then_written_off() {
+        let f = setup(BillingPolicy::default()).await;
+        let owner_id = f.company.owner_id;
+
+        let started = f.service.change_plan(&f.company, SubscriptionPlan::Basic).await.unwrap();
+        assert_eq!(started.subscription.status, SubscriptionStatus::Trialing);
+        assert!(started.invoice.is_none());
+        f.service.require(f.company.id, Requirement::FinanceModule).await.unwrap();
+
+        let trial_end = started.subscription.current_period_end;
+        let sweep = f.service.sweep(trial_end + Duration::seconds(1)).await.unwrap();
+        assert_eq!(sweep.renewed, 1);
+        let invoice = f.service.invoices(f.company.id, 10).await.unwrap().remove(0);
+        assert_eq!(invoice.status, InvoiceStatus::Open);
+        // Rp149.000 plus 11% PPN
+        assert_eq!(invoice.total, Money::idr(16_539_000));
+        assert!(invoice.number.starts_with("SUB/"));
+        let inbox = f.notifications.inbox(owner_id, false, 10).await.unwrap();
+        assert_eq!(inbox.unread_count, 1);
+
+        let sweep = f.service.sweep(invoice.due_at + Duration::days(1)).await.unwrap();
+        assert_eq!(sweep.reminded, 1);
+        let overview = f.service.overview(f.company.id).await.unwrap();
+        assert_eq!(overview.subscription.unwrap().status, SubscriptionStatus::PastDue);
+        let blocked = f.service.change_plan(&f.company, SubscriptionPlan::Pro).await;
+        assert!(matches!(blocked, Err(AppError::Conflict(_))));
+
+        let sweep = f.service.sweep(invoice.due_at + Duration::days(14)).await.unwrap();
+        assert_eq!(sweep.written_off, 1);
+        assert_eq!(f.service.overview(f.company.id).await.unwrap().plan, SubscriptionPlan::Free);
+        let refused = f.service.require(f.company.id, Requirement::FinanceModule).await;
+        assert!(matches!(refused, Err(AppError::PlanLimit(_))));
+        let inbox = f.notifications.inbox(owner_id, false, 10).await.unwrap();
+        assert_eq!(inbox.unread_count, 3);
+    }
+
+    #[tokio::test]
+    async fn test_payments_are_idempotent_and_upgrades_credit_the_paid_period() {
+        let policy = BillingPolicy {
+            trial_days: 0,
+            ..BillingPolicy::default()
+        };
+        let f = setup(policy).await;
+
+        let started = f.service.change_plan(&f.company, SubscriptionPlan::Basic).await.unwrap();
+        assert_eq!(started.subscription.status, SubscriptionStatus::Active);
+        let invoice = started.invoice.unwrap();
+        let paid = f
+            .service
+            .record_payment(invoice, Some("TRX-1".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(paid.status, InvoiceStatus::Paid);
+        let again = f
+            .service
+            .record_payment(paid.clone(), Some("TRX-1".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(again, paid);
+
+        let upgraded = f.service.change_plan(&f.company, SubscriptionPlan::Pro).await.unwrap();
+        assert_eq!(upgraded.subscription.plan, SubscriptionPlan::Pro);
+        let invoice = upgraded.invoice.unwrap();
+        assert_eq!(invoice.subtotal, monthly_price(SubscriptionPlan::Pro));
+        // Next to nothing of the Basic month was used
+        assert!(invoice.credit.amount > 14_800_000 && invoice.credit.amount <= 14_900_000);
+    }
+
+    #[tokio::test]
+    async fn test_free_plan_limits_licenses_and_companies() {
+        let f = setup(BillingPolicy::default()).await;
+        for title in ["SIUP", "NIB"] {
+            f.service.require(f.company.id, Requirement::License).await.unwrap();
+            let license = License::new(LicenseType::Siup, f.company.id, f.company.owner_id, title.to_string(), None);
+            f.licenses.create_license(&license).await.unwrap();
+        }
+        let refused = f.service.require(f.company.id, Requirement::License).await;
+        assert!(matches!(refused, Err(AppError::PlanLimit(_))));
+
+        let refused = f.service.require_for_owner(f.company.owner_id, Requirement::Company).await;
+        assert!(matches!(refused, Err(AppError::PlanLimit(_))));
+        f.service.change_plan(&f.company, SubscriptionPlan::Basic).await.unwrap();
+        f.service
+            .require_for_owner(f.company.owner_id, Requirement::Company)
+            .await
+            .unwrap();
+        f.service.require(f.company.id, Requirement::License).await.unwrap();
+    }
+}
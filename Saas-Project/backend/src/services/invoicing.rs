@@ -0,0 +1,531 @@
+// Customer invoicing
+// Contacts, sales invoices and the payments received on them for one company
+// at a time; callers have already checked the company is the user's. Payments
+// are booked into one of the company's own cash or bank accounts, so they
+// show up in its transactions and balances. Emailing sends the invoice PDF to
+// the contact and issues a draft first. A periodic sweep marks invoices past
+// their due date as overdue.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::domain::analytics::reporting_day;
+use crate::domain::companies::Company;
+use crate::domain::finance::{FinancialAccount, FinancialAccountRepository};
+use crate::domain::invoicing::{
+    AgingReport, Contact, ContactInput, InvoiceDraft, InvoicePayment, InvoicingPolicy,
+    InvoicingRepository, PaymentInput, SalesInvoice, SalesInvoiceQuery, SalesInvoiceStatus,
+};
+use crate::domain::notifications::{Attachment, MessageSender};
+use crate::domain::value_objects::Currency;
+use crate::infrastructure::invoice_pdf::{render_invoice, InvoicePdfContent};
+use crate::infrastructure::monitoring::record_sales_invoice;
+use crate::shared::errors::{AppError, AppResult};
+
+/// Open invoices aged per report; more than a UMKM has outstanding
+const AGING_LIMIT: i64 = 10_000;
+
+/// A payment and the invoices it settled
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecordedPayment {
+    pub payment: InvoicePayment,
+    pub invoices: Vec<SalesInvoice>,
+}
+
+/// `Faktur-INV-2026-00001.pdf`
+pub fn pdf_file_name(invoice: &SalesInvoice) -> String {
+    let number = invoice.number.as_deref().unwrap_or("Draf");
+    format!("Faktur-{}.pdf", number.replace('/', "-"))
+}
+
+fn receives_payments(account: &FinancialAccount) -> bool {
+    account.is_active && account.currency == Currency::IDR
+}
+
+pub struct InvoicingService {
+    invoicing: Arc<dyn InvoicingRepository>,
+    accounts: Arc<dyn FinancialAccountRepository>,
+    email: Arc<dyn MessageSender>,
+    policy: InvoicingPolicy,
+}
+
+impl InvoicingService {
+    pub fn new(
+        invoicing: Arc<dyn InvoicingRepository>,
+        accounts: Arc<dyn FinancialAccountRepository>,
+        email: Arc<dyn MessageSender>,
+        policy: InvoicingPolicy,
+    ) -> Self {
+        Self {
+            invoicing,
+            accounts,
+            email,
+            policy,
+        }
+    }
+
+    pub async fn contacts(&self, company_id: Uuid) -> AppResult<Vec<Contact>> {
+        self.invoicing.contacts(company_id).await
+    }
+
+    pub async fn contact(&self, company_id: Uuid, id: Uuid) -> AppResult<Contact> {
+        self.invoicing
+            .contact(company_id, id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Contact not found".to_string()))
+    }
+
+    pub async fn create_contact(&self, company_id: Uuid, input: ContactInput) -> AppResult<Contact> {
+        let contact = Contact::new(company_id, input, Utc::now()).map_err(AppError::Validation)?;
+        self.invoicing.create_contact(&contact).await
+    }
+
+    /// Updates the contact if it is still at `version`, or at whatever
+    /// version is stored when none is given
+    pub async fn update_contact(
+        &self,
+        company_id: Uuid,
+        id: Uuid,
+        version: Option<i64>,
+        input: ContactInput,
+    ) -> AppResult<Contact> {
+        let mut contact = self.contact(company_id, id).await?;
+        contact.version = version.unwrap_or(contact.version);
+        contact.update(input, Utc::now()).map_err(AppError::Validation)?;
+        self.invoicing.update_contact(&contact).await
+    }
+
+    pub async fn invoice(&self, company_id: Uuid, id: Uuid) -> AppResult<SalesInvoice> {
+        self.invoicing
+            .invoice(company_id, id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Invoice not found".to_string()))
+    }
+
+    pub async fn invoices(&self, company_id: Uuid, query: &SalesInvoiceQuery, limit: i64) -> AppResult<Vec<SalesInvoice>> {
+        self.invoicing.invoices(company_id, query, limit).await
+    }
+
+    pub async fn create_invoice(&self, company_id: Uuid, created_by: Uuid, draft: InvoiceDraft) -> AppResult<SalesInvoice> {
+        self.contact(company_id, draft.contact_id).await?;
+        let invoice = SalesInvoice::draft(company_id, created_by, draft, self.policy.ppn_rate_bps, Utc::now())
+            .map_err(AppError::Validation)?;
+        self.invoicing.create_invoice(&invoice).await
+    }
+
+    pub async fn update_invoice(
+        &self,
+        company_id: Uuid,
+        id: Uuid,
+        version: Option<i64>,
+        draft: InvoiceDraft,
+    ) -> AppResult<SalesInvoice> {
+        self.contact(company_id, draft.contact_id).await?;
+        let mut invoice = self.invoice(company_id, id).await?;
+        invoice.version = version.unwrap_or(invoice.version);
+        invoice
+            .revise(draft, self.policy.ppn_rate_bps, Utc::now())
+            .map_err(AppError::Validation)?;
+        self.invoicing.update_invoice(&invoice).await
+    }
+
+    /// Issues a draft, giving it the company's next invoice number
+    pub async fn send(&self, company_id: Uuid, id: Uuid, version: Option<i64>) -> AppResult<SalesInvoice> {
+        let mut invoice = self.invoice(company_id, id).await?;
+        invoice.version = version.unwrap_or(invoice.version);
+        self.issue(invoice).await
+    }
+
+    async fn issue(&self, mut invoice: SalesInvoice) -> AppResult<SalesInvoice> {
+        let now = Utc::now();
+        invoice
+            .send(reporting_day(now), self.policy.payment_terms_days, now)
+            .map_err(AppError::Conflict)?;
+        let sent = self.invoicing.update_invoice(&invoice).await?;
+        record_sales_invoice("sent", 1);
+        info!(
+            "🧾 Invoice {} sent for company {}",
+            sent.number.as_deref().unwrap_or("-"),
+            sent.company_id
+        );
+        Ok(sent)
+    }
+
+    pub async fn void(&self, company_id: Uuid, id: Uuid, version: Option<i64>) -> AppResult<SalesInvoice> {
+        let mut invoice = self.invoice(company_id, id).await?;
+        invoice.version = version.unwrap_or(invoice.version);
+        invoice.void(Utc::now()).map_err(AppError::Conflict)?;
+        let voided = self.invoicing.update_invoice(&invoice).await?;
+        record_sales_invoice("voided", 1);
+        Ok(voided)
+    }
+
+    /// The active rupiah accounts payments can be booked into
+    pub async fn accounts(&self, company_id: Uuid) -> AppResult<Vec<FinancialAccount>> {
+        let accounts = self.accounts.list_by_company(company_id).await?;
+        Ok(accounts.into_iter().filter(receives_payments).collect())
+    }
+
+    /// Books a payment from a contact into one of the company's accounts and
+    /// allocates it to the contact's open invoices
+    pub async fn record_payment(&self, company_id: Uuid, created_by: Uuid, input: PaymentInput) -> AppResult<RecordedPayment> {
+        let contact = self.contact(company_id, input.contact_id).await?;
+        let account = self
+            .accounts
+            .find_by_id(input.account_id)
+            .await?
+            .filter(|a| a.company_id == company_id)
+            .ok_or_else(|| AppError::NotFound("Account not found".to_string()))?;
+        if !receives_payments(&account) {
+            return Err(AppError::Validation(
+                "Payments go into an active rupiah account".to_string(),
+            ));
+        }
+
+        let mut invoices = Vec::with_capacity(input.allocations.len());
+        for allocation in &input.allocations {
+            if let Some(invoice) = self.invoicing.invoice(company_id, allocation.invoice_id).await? {
+                invoices.push(invoice);
+            }
+        }
+        let now = Utc::now();
+        let (payment, invoices) = InvoicePayment::allocate(company_id, created_by, input, invoices, reporting_day(now), now)
+            .map_err(AppError::Validation)?;
+        let transaction = payment.transaction(&invoices, &contact);
+        let (payment, invoices) = self.invoicing.record_payment(&payment, &transaction, &invoices).await?;
+
+        let paid = invoices
+            .iter()
+            .filter(|i| i.status == SalesInvoiceStatus::Paid)
+            .count();
+        record_sales_invoice("paid", paid as u64);
+        info!(
+            "💰 Payment of {} from {} recorded for company {}",
+            payment.amount.rupiah(),
+            contact.name,
+            company_id
+        );
+        Ok(RecordedPayment { payment, invoices })
+    }
+
+    pub async fn payments(&self, company_id: Uuid, invoice_id: Uuid) -> AppResult<Vec<InvoicePayment>> {
+        self.invoice(company_id, invoice_id).await?;
+        self.invoicing.payments(company_id, invoice_id).await
+    }
+
+    /// What the company's contacts owe on `as_of`, by age past due
+    pub async fn aging(&self, company_id: Uuid, as_of: NaiveDate) -> AppResult<AgingReport> {
+        let query = SalesInvoiceQuery {
+            open: true,
+            ..SalesInvoiceQuery::default()
+        };
+        let invoices = self.invoicing.invoices(company_id, &query, AGING_LIMIT).await?;
+        let contacts = self.invoicing.contacts(company_id).await?;
+        Ok(AgingReport::build(as_of, &invoices, &contacts))
+    }
+
+    pub async fn pdf(&self, company: &Company, id: Uuid) -> AppResult<(SalesInvoice, Vec<u8>)> {
+        let invoice = self.invoice(company.id, id).await?;
+        let pdf = self.render(company, &invoice).await?;
+        Ok((invoice, pdf))
+    }
+
+    async fn render(&self, company: &Company, invoice: &SalesInvoice) -> AppResult<Vec<u8>> {
+        let contact = self.contact(company.id, invoice.contact_id).await?;
+        render_invoice(&InvoicePdfContent {
+            invoice,
+            contact: &contact,
+            company,
+            generated_at: Utc::now(),
+        })
+    }
+
+    /// Emails the invoice PDF to the contact, sending a draft first
+    pub async fn email(&self, company: &Company, id: Uuid) -> AppResult<SalesInvoice> {
+        let mut invoice = self.invoice(company.id, id).await?;
+        let contact = self.contact(company.id, invoice.contact_id).await?;
+        let recipient = contact
+            .email
+            .clone()
+            .ok_or_else(|| AppError::Validation("The contact has no email address".to_string()))?;
+        if invoice.status == SalesInvoiceStatus::Void {
+            return Err(AppError::Conflict("Voided invoices are not emailed".to_string()));
+        }
+        if invoice.status == SalesInvoiceStatus::Draft {
+            invoice = self.issue(invoice).await?;
+        }
+
+        let pdf = self.render(company, &invoice).await?;
+        let number = invoice.number.clone().unwrap_or_default();
+        let subject = format!("Faktur {} dari {}", number, company.company_name);
+        let body = format!(
+            "Yth. {},\n\nTerlampir faktur {} sebesar {} dengan sisa tagihan {}, jatuh tempo {}.\n\nTerima kasih,\n{}",
+            contact.name,
+            number,
+            invoice.total.rupiah(),
+            invoice.balance_due.rupiah(),
+            invoice
+                .due_date
+                .map(|d| d.format("%d-%m-%Y").to_string())
+                .unwrap_or_default(),
+            company.company_name
+        );
+        let attachment = Attachment {
+            file_name: pdf_file_name(&invoice),
+            content_type: "application/pdf".to_string(),
+            content: pdf,
+        };
+        self.email
+            .send_with_attachments(&recipient, &subject, &body, &[attachment])
+            .await?;
+
+        invoice.mark_emailed(Utc::now());
+        let emailed = self.invoicing.update_invoice(&invoice).await?;
+        record_sales_invoice("emailed", 1);
+        Ok(emailed)
+    }
+
+    /// Marks invoices past their due date as overdue
+    pub async fn sweep(&self, now: DateTime<Utc>) -> AppResult<u64> {
+        let marked = self.invoicing.mark_overdue(reporting_day(now)).await?;
+        if marked > 0 {
+            record_sales_invoice("overdue", marked);
+            info!("🧾 {} invoices are now overdue", marked);
+        }
+        Ok(marked)
+    }
+
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            info!("🧾 Overdue invoice sweep every {}s", interval.as_secs());
+            let mut ticks = tokio::time::interval(interval);
+            loop {
+                ticks.tick().await;
+                if let Err(err) = self.sweep(Utc::now()).await {
+                    warn!("⚠️ Overdue invoice sweep failed: {}", err);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::analytics::reporting_day_start;
+    use crate::domain::companies::{BusinessType, CompanyAddress};
+    use crate::domain::finance::{TransactionId, TransactionRepository};
+    use crate::domain::invoicing::{AllocationInput, LineInput};
+    use crate::domain::value_objects::Money;
+    use crate::infrastructure::messaging::RecordingSender;
+    use crate::infrastructure::repositories::{
+        InMemoryFinanceStore, InMemoryFinancialAccountRepository, InMemoryInvoicingRepository,
+        InMemoryTransactionRepository,
+    };
+
+    struct Fixture {
+        service: InvoicingService,
+        email: RecordingSender,
+        store: InMemoryFinanceStore,
+        company: Company,
+        account: FinancialAccount,
+    }
+
+    async fn setup() -> Fixture {
+        let company = Company::new(
+            Uuid::new_v4(),
+            "Keripik Bu Siti".to_string(),
+            BusinessType::UD,
+            "Makanan".to_string(),
+            CompanyAddress::new(
+                "Jl. Melati 1".to_string(),
+                "Bandung".to_string(),
+                "Jawa Barat".to_string(),
+                "40111".to_string(),
+            ),
+        );
+        let store = InMemoryFinanceStore::new();
+        let accounts = InMemoryFinancialAccountRepository::new(store.clone());
+        let account = accounts
+            .create(&FinancialAccount {
+                id: Uuid::new_v4(),
+                company_id: company.id,
+                name: "BCA".to_string(),
+                description: None,
+                account_type: "bank".to_string(),
+                currency: Currency::IDR,
+                balance: Money::idr(0),
+                is_active: true,
+                metadata: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                version: 1,
+            })
+            .await
+            .unwrap();
+        let email = RecordingSender::new("email");
+        let service = InvoicingService::new(
+            Arc::new(InMemoryInvoicingRepository::new(store.clone())),
+            Arc::new(accounts),
+            Arc::new(email.clone()),
+            InvoicingPolicy {
+                ppn_rate_bps: 1_100,
+                payment_terms_days: 30,
+            },
+        );
+        Fixture {
+            service,
+            email,
+            store,
+            company,
+            account,
+        }
+    }
+
+    fn draft(contact_id: Uuid, unit_price: i64) -> InvoiceDraft {
+        InvoiceDraft {
+            contact_id,
+            lines: vec![LineInput {
+                description: "Keripik singkong 250g".to_string(),
+                quantity: 10,
+                unit_price,
+                discount_bps: 0,
+            }],
+            discount: 0,
+            ppn_rate_bps: None,
+            issue_date: None,
+            due_date: None,
+            notes: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invoices_are_numbered_emailed_paid_and_booked() {
+        let f = setup().await;
+        let company_id = f.company.id;
+        let user = f.company.owner_id;
+        let contact = f
+            .service
+            .create_contact(
+                company_id,
+                ContactInput {
+                    name: "Toko Sumber Rejeki".to_string(),
+                    email: Some("kasir@sumber.id".to_string()),
+                    ..ContactInput::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        // Rp 250.000 plus 11% PPN
+        let first = f.service.create_invoice(company_id, user, draft(contact.id, 2_500_000)).await.unwrap();
+        assert_eq!((first.number.clone(), first.total.amount), (None, 27_750_000));
+        let second = f.service.create_invoice(company_id, user, draft(contact.id, 1_000_000)).await.unwrap();
+        let year = reporting_day(Utc::now()).format("%Y").to_string();
+
+        let emailed = f.service.email(&f.company, first.id).await.unwrap();
+        assert_eq!(emailed.number, Some(format!("INV/{}/00001", year)));
+        assert_eq!(emailed.status, SalesInvoiceStatus::Sent);
+        assert!(emailed.emailed_at.is_some());
+        let sent = f.email.sent();
+        assert_eq!(sent[0].recipient, "kasir@sumber.id");
+        assert_eq!(sent[0].attachments, vec![format!("Faktur-INV-{}-00001.pdf", year)]);
+        assert!(sent[0].body.contains("Rp 277.500"));
+
+        let second = f.service.send(company_id, second.id, Some(second.version)).await.unwrap();
+        assert_eq!(second.number, Some(format!("INV/{}/00002", year)));
+        assert!(matches!(
+            f.service.send(company_id, second.id, None).await,
+            Err(AppError::Conflict(_))
+        ));
+
+        let recorded = f
+            .service
+            .record_payment(
+                company_id,
+                user,
+                PaymentInput {
+                    contact_id: contact.id,
+                    account_id: f.account.id,
+                    paid_on: None,
+                    reference: None,
+                    allocations: vec![
+                        AllocationInput {
+                            invoice_id: first.id,
+                            amount: 27_750_000,
+                        },
+                        AllocationInput {
+                            invoice_id: second.id,
+                            amount: 5_000_000,
+                        },
+                    ],
+                },
+            )
+            .await
+            .unwrap();
+        let statuses: Vec<_> = recorded.invoices.iter().map(|i| i.status).collect();
+        assert_eq!(statuses, vec![SalesInvoiceStatus::Paid, SalesInvoiceStatus::PartiallyPaid]);
+
+        let transaction = InMemoryTransactionRepository::new(f.store.clone())
+            .find_by_id(&TransactionId(recorded.payment.transaction_id))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(transaction.amount, Money::idr(32_750_000));
+        assert_eq!(f.store.lock().accounts[&f.account.id].balance, Money::idr(32_750_000));
+        assert_eq!(f.service.payments(company_id, second.id).await.unwrap().len(), 1);
+
+        let aging = f.service.aging(company_id, reporting_day(Utc::now())).await.unwrap();
+        assert_eq!(aging.totals.current, Money::idr(11_100_000 - 5_000_000));
+        assert!(f.service.void(company_id, second.id, None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_payments_need_the_companys_account_and_sweeps_mark_overdue() {
+        let f = setup().await;
+        let company_id = f.company.id;
+        let contact = f
+            .service
+            .create_contact(company_id, ContactInput { name: "Ani".to_string(), ..ContactInput::default() })
+            .await
+            .unwrap();
+        let invoice = f.service.create_invoice(company_id, f.company.owner_id, draft(contact.id, 100_000)).await.unwrap();
+        assert!(matches!(
+            f.service.email(&f.company, invoice.id).await,
+            Err(AppError::Validation(_))
+        ));
+        let invoice = f.service.send(company_id, invoice.id, None).await.unwrap();
+
+        let elsewhere = f
+            .service
+            .record_payment(
+                company_id,
+                f.company.owner_id,
+                PaymentInput {
+                    contact_id: contact.id,
+                    account_id: Uuid::new_v4(),
+                    paid_on: None,
+                    reference: None,
+                    allocations: vec![AllocationInput {
+                        invoice_id: invoice.id,
+                        amount: 100,
+                    }],
+                },
+            )
+            .await;
+        assert!(matches!(elsewhere, Err(AppError::NotFound(_))));
+
+        let due = reporting_day_start(invoice.due_date.unwrap());
+        assert_eq!(f.service.sweep(due).await.unwrap(), 0);
+        assert_eq!(f.service.sweep(due + chrono::Duration::days(1)).await.unwrap(), 1);
+        let overdue = f.service.invoice(company_id, invoice.id).await.unwrap();
+        assert_eq!(overdue.status, SalesInvoiceStatus::Overdue);
+        let aging = f.service.aging(company_id, overdue.due_date.unwrap() + chrono::Duration::days(45)).await.unwrap();
+        assert_eq!(aging.totals.days_31_60, overdue.balance_due);
+    }
+}
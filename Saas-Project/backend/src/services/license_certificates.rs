@@ -0,0 +1,265 @@
+// License certificates
+// Renders a license's certificate, stores the PDF and records it as the next
+// revision. The PDF is stored before its revision is recorded, so a recorded
+// revision always has its file; a PDF left over from a failed recording is
+// deleted again. Certificates are regenerated when the license changes, and
+// lazily on download if that regeneration failed or predates this service.
+
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::domain::certificates::{
+    is_certifiable, CertificateReason, CertificateRepository, LicenseCertificate,
+};
+use crate::domain::licenses::License;
+use crate::domain::repositories::CompanyRepository;
+use crate::infrastructure::certificate_pdf::{render_certificate, CertificateContent};
+use crate::infrastructure::monitoring::record_license_certificate;
+use crate::infrastructure::storage::FileStorage;
+use crate::services::license_verification::LicenseVerificationService;
+use crate::shared::errors::{AppError, AppResult};
+
+pub struct CertificateService {
+    certificates: Arc<dyn CertificateRepository>,
+    companies: Arc<dyn CompanyRepository + Send + Sync>,
+    storage: Arc<dyn FileStorage>,
+    verification: Arc<LicenseVerificationService>,
+}
+
+impl CertificateService {
+    pub fn new(
+        certificates: Arc<dyn CertificateRepository>,
+        companies: Arc<dyn CompanyRepository + Send + Sync>,
+        storage: Arc<dyn FileStorage>,
+        verification: Arc<LicenseVerificationService>,
+    ) -> Self {
+        Self {
+            certificates,
+            companies,
+            storage,
+            verification,
+        }
+    }
+
+    /// Generates a new revision of the license's certificate
+    pub async fn issue(
+        &self,
+        license: &License,
+        reason: CertificateReason,
+        generated_by: Option<Uuid>,
+    ) -> AppResult<LicenseCertificate> {
+        if !is_certifiable(license) {
+            return Err(AppError::Conflict(format!(
+                "A {} license has no certificate",
+                license.application_status
+            )));
+        }
+        let company = self
+            .companies
+            .find_by_id(&license.company_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Company not found".to_string()))?;
+
+        let verification_url = self.verification.verification_url(license.id);
+        let pdf = render_certificate(&CertificateContent {
+            license,
+            company: &company,
+            verification_url: &verification_url,
+            generated_at: chrono::Utc::now(),
+        })?;
+
+        let sha256 = format!("{:x}", Sha256::digest(&pdf));
+        let certificate =
+            LicenseCertificate::new(license, reason, generated_by, sha256, pdf.len() as i64);
+        self.storage.put(&certificate.storage_key, &pdf).await?;
+
+        let recorded = match self.certificates.record_certificate(&certificate).await {
+            Ok(recorded) => recorded,
+            Err(err) => {
+                if let Err(cleanup) = self.storage.delete(&certificate.storage_key).await {
+                    warn!("⚠️ Failed to delete unrecorded certificate {}: {}", certificate.storage_key, cleanup);
+                }
+                return Err(err);
+            }
+        };
+
+        record_license_certificate(&license.license_type.to_string(), &reason.to_string());
+        info!(
+            license_id = %license.id,
+            revision = recorded.revision,
+            reason = %reason,
+            "📜 License certificate generated"
+        );
+        Ok(recorded)
+    }
+
+    /// Regenerates the certificate after the license changed. The change has
+    /// already been committed, so a failure is logged rather than returned;
+    /// the next download regenerates it.
+    pub async fn reissue(&self, license: &License, reason: CertificateReason, generated_by: Uuid) {
+        if let Err(err) = self.issue(license, reason, Some(generated_by)).await {
+            warn!(license_id = %license.id, "⚠️ Failed to regenerate license certificate: {}", err);
+        }
+    }
+
+    /// The certificate matching the license as it is now, and its PDF,
+    /// generated first if there is none yet
+    pub async fn current(&self, license: &License) -> AppResult<(LicenseCertificate, Vec<u8>)> {
+        let certificate = match self.certificates.latest_certificate(license.id).await? {
+            Some(latest) if latest.is_current_for(license) => latest,
+            None => self.issue(license, CertificateReason::Issued, None).await?,
+            Some(_) => self.issue(license, CertificateReason::Regenerated, None).await?,
+        };
+        let pdf = self
+            .storage
+            .get(&certificate.storage_key)
+            .await?
+            .ok_or_else(|| {
+                AppError::InternalError(format!(
+                    "Certificate {} is missing from storage",
+                    certificate.storage_key
+                ))
+            })?;
+        Ok((certificate, pdf))
+    }
+
+    /// Every revision of the license's certificate, oldest first
+    pub async fn history(&self, license_id: Uuid) -> AppResult<Vec<LicenseCertificate>> {
+        self.certificates.certificates(license_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    use crate::domain::companies::{BusinessType, Company, CompanyAddress};
+    use crate::domain::licenses::{ApplicationStatus, LicenseType};
+    use crate::infrastructure::repositories::{
+        InMemoryCompanyRepository, InMemoryLicenseRepository, LicenseRepository,
+    };
+    use crate::infrastructure::storage::InMemoryFileStorage;
+    use crate::services::license_verification::{LookupClient, VerificationSigner};
+
+    struct Fixture {
+        licenses: InMemoryLicenseRepository,
+        storage: InMemoryFileStorage,
+        verification: Arc<LicenseVerificationService>,
+        service: CertificateService,
+        license: License,
+    }
+
+    async fn fixture() -> Fixture {
+        let companies = InMemoryCompanyRepository::new();
+        let licenses = InMemoryLicenseRepository::new();
+        let storage = InMemoryFileStorage::new();
+
+        let company = Company::new(
+            Uuid::new_v4(),
+            "Batik Tulis Lestari".to_string(),
+            BusinessType::CV,
+            "Kerajinan".to_string(),
+            CompanyAddress::new(
+                "Jl. Kauman 5".to_string(),
+                "Pekalongan".to_string(),
+                "Jawa Tengah".to_string(),
+                "51111".to_string(),
+            ),
+        );
+        companies.save(&company).await.unwrap();
+
+        let mut license = License::new(LicenseType::Siup, company.id, company.owner_id, "SIUP".to_string(), None);
+        license.application_status = ApplicationStatus::Processing;
+        license
+            .approve(
+                "SIUP/2024/0042".to_string(),
+                Utc::now(),
+                Some(Utc::now() + Duration::days(365)),
+                "DPMPTSP Pekalongan".to_string(),
+                None,
+            )
+            .unwrap();
+        let license = licenses.create_license(&license).await.unwrap();
+
+        let verification = Arc::new(LicenseVerificationService::new(
+            Arc::new(licenses.clone()),
+            Arc::new(companies.clone()),
+            Arc::new(licenses.clone()),
+            VerificationSigner::new("secret"),
+            "https://app.example.id/",
+        ));
+        let service = CertificateService::new(
+            Arc::new(licenses.clone()),
+            Arc::new(companies),
+            Arc::new(storage.clone()),
+            verification.clone(),
+        );
+        Fixture {
+            licenses,
+            storage,
+            verification,
+            service,
+            license,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_current_issues_once_and_regenerates_after_changes() {
+        let fx = fixture().await;
+
+        let (first, pdf) = fx.service.current(&fx.license).await.unwrap();
+        assert_eq!((first.revision, first.reason), (1, CertificateReason::Issued));
+        assert_eq!(first.size_bytes, pdf.len() as i64);
+        assert_eq!(first.sha256, format!("{:x}", Sha256::digest(&pdf)));
+        assert_eq!(fx.storage.get(&first.storage_key).await.unwrap(), Some(pdf));
+
+        // Unchanged license, same certificate
+        let (again, _) = fx.service.current(&fx.license).await.unwrap();
+        assert_eq!(again.id, first.id);
+
+        let mut suspended = fx.license.clone();
+        suspended.suspend("Pelanggaran".to_string()).unwrap();
+        let suspended = fx.licenses.update_license(&suspended).await.unwrap();
+        let (second, pdf) = fx.service.current(&suspended).await.unwrap();
+        assert_eq!((second.revision, second.reason), (2, CertificateReason::Regenerated));
+        assert_eq!(second.license_status, ApplicationStatus::Suspended);
+        assert!(String::from_utf8_lossy(&pdf).contains("DIBEKUKAN"));
+
+        let history = fx.service.history(fx.license.id).await.unwrap();
+        assert_eq!(history.iter().map(|c| c.revision).collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_certificate_links_to_a_token_that_verifies() {
+        let fx = fixture().await;
+        let (_, pdf) = fx.service.current(&fx.license).await.unwrap();
+
+        let url = fx.verification.verification_url(fx.license.id);
+        assert!(url.starts_with("https://app.example.id/api/v1/verify/"));
+        assert!(String::from_utf8_lossy(&pdf).contains(&url));
+
+        let token = url.rsplit('/').next().unwrap();
+        let public = fx.verification.verify_token(token, &LookupClient::default()).await.unwrap();
+        assert_eq!(public.license_number.as_deref(), Some("SIUP/2024/0042"));
+        assert_eq!(public.company_name, "Batik Tulis Lestari");
+        assert!(matches!(
+            fx.verification.verify_token("forged", &LookupClient::default()).await,
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unissued_licenses_have_no_certificate() {
+        let fx = fixture().await;
+        let mut draft = fx.license.clone();
+        draft.application_status = ApplicationStatus::Rejected;
+        assert!(matches!(
+            fx.service.issue(&draft, CertificateReason::Issued, None).await,
+            Err(AppError::Conflict(_))
+        ));
+        assert!(fx.service.history(draft.id).await.unwrap().is_empty());
+    }
+}
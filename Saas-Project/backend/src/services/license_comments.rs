@@ -0,0 +1,350 @@
+// License application discussions
+// A comment is checked before anything else happens: against the rules of the
+// discussion, its attachments against the license's documents, and its
+// mentions against who may read it. Once saved, the applicant is notified of
+// a reviewer's comment to them, anyone mentioned is notified unless already
+// told, and the comment is pushed to open event streams.
+
+use chrono::Utc;
+use std::sync::Arc;
+
+use crate::domain::entities::UserRole;
+use crate::domain::license_comments::{
+    CommentKind, Discussion, LicenseComment, LicenseCommentRepository, NewComment, Participant,
+};
+use crate::domain::licenses::License;
+use crate::domain::live_updates::LiveUpdate;
+use crate::domain::notifications::NotificationEvent;
+use crate::domain::repositories::UserRepository;
+use crate::domain::value_objects::UserId;
+use crate::infrastructure::live_updates::LiveUpdateHub;
+use crate::infrastructure::repositories::LicenseRepository;
+use crate::services::notifications::NotificationService;
+use crate::shared::errors::{AppError, AppResult};
+
+pub struct LicenseCommentService {
+    comments: Arc<dyn LicenseCommentRepository>,
+    licenses: Arc<dyn LicenseRepository + Send + Sync>,
+    users: Arc<dyn UserRepository + Send + Sync>,
+    notifications: Arc<NotificationService>,
+    live_updates: LiveUpdateHub,
+}
+
+impl LicenseCommentService {
+    pub fn new(
+        comments: Arc<dyn LicenseCommentRepository>,
+        licenses: Arc<dyn LicenseRepository + Send + Sync>,
+        users: Arc<dyn UserRepository + Send + Sync>,
+        notifications: Arc<NotificationService>,
+        live_updates: LiveUpdateHub,
+    ) -> Self {
+        Self {
+            comments,
+            licenses,
+            users,
+            notifications,
+            live_updates,
+        }
+    }
+
+    /// The license's threads as `viewer` sees them
+    pub async fn discussion(&self, license: &License, viewer: Participant) -> AppResult<Discussion> {
+        let comments = self.comments.comments(license.id, viewer.is_admin).await?;
+        let receipts = self.comments.read_receipts(license.id).await?;
+        Ok(Discussion::build(comments, &receipts, viewer))
+    }
+
+    /// Checks a comment without saving it, so that a clarification request can
+    /// be refused before the license is sent back to the applicant
+    pub async fn prepare(
+        &self,
+        license: &License,
+        author: Participant,
+        new: NewComment,
+    ) -> AppResult<LicenseComment> {
+        let parent = match new.parent_id {
+            Some(parent_id) => Some(
+                self.comments
+                    .get_comment(parent_id)
+                    .await?
+                    .ok_or_else(|| AppError::NotFound("Comment not found".to_string()))?,
+            ),
+            None => None,
+        };
+        let comment = LicenseComment::compose(license.id, author, new, parent.as_ref())
+            .map_err(AppError::Validation)?;
+
+        for document_id in &comment.document_ids {
+            let attached = self
+                .licenses
+                .get_document_by_id(*document_id)
+                .await?
+                .is_some_and(|document| document.license_id == license.id);
+            if !attached {
+                return Err(AppError::Validation(format!(
+                    "Document {} was not uploaded to this license",
+                    document_id
+                )));
+            }
+        }
+
+        for user_id in &comment.mentions {
+            let user = self.users.find_by_id(&UserId::from_uuid(*user_id)).await?;
+            let can_read = user.is_some_and(|user| {
+                matches!(user.role, UserRole::SuperAdmin | UserRole::AdminStaff)
+                    || (user.id.0 == license.user_id && !comment.internal)
+            });
+            if !can_read {
+                return Err(AppError::Validation(format!(
+                    "User {} cannot read this comment",
+                    user_id
+                )));
+            }
+        }
+
+        Ok(comment)
+    }
+
+    /// Saves a prepared comment and tells the people concerned; `license` is
+    /// the license as it stands after any change the comment made
+    pub async fn post(&self, license: &License, comment: LicenseComment) -> AppResult<LicenseComment> {
+        let comment = self.comments.create_comment(&comment).await?;
+
+        // A clarification request reaches the applicant as DocumentsRequested
+        let mut applicant_told = comment.kind == CommentKind::ClarificationRequest;
+        if !comment.internal
+            && comment.kind == CommentKind::Comment
+            && comment.author_id != license.user_id
+        {
+            self.notifications
+                .notify_user_best_effort(
+                    license.user_id,
+                    license,
+                    NotificationEvent::CommentReceived,
+                    Some(comment.body.clone()),
+                )
+                .await;
+            applicant_told = true;
+        }
+        for user_id in &comment.mentions {
+            if *user_id == license.user_id && applicant_told {
+                continue;
+            }
+            self.notifications
+                .notify_user_best_effort(
+                    *user_id,
+                    license,
+                    NotificationEvent::CommentMention,
+                    Some(comment.body.clone()),
+                )
+                .await;
+        }
+
+        self.live_updates
+            .publish(LiveUpdate::comment_added(license, &comment))
+            .await;
+        Ok(comment)
+    }
+
+    /// Records `viewer` reading every comment they can see, returning how
+    /// many they had not read yet
+    pub async fn mark_read(&self, license: &License, viewer: Participant) -> AppResult<u64> {
+        self.comments
+            .mark_read(license.id, viewer.user_id, viewer.is_admin, Utc::now())
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    use crate::domain::entities::User;
+    use crate::domain::licenses::{DocumentType, LicenseDocument, LicenseType};
+    use crate::domain::notifications::NotificationRepository;
+    use crate::domain::value_objects::Email;
+    use crate::infrastructure::live_updates::HubMessage;
+    use crate::infrastructure::repositories::{
+        InMemoryLicenseCommentRepository, InMemoryLicenseRepository,
+        InMemoryNotificationRepository, InMemoryUserRepository,
+    };
+
+    struct Fixture {
+        service: LicenseCommentService,
+        notifications: Arc<InMemoryNotificationRepository>,
+        hub: LiveUpdateHub,
+        license: License,
+        document: LicenseDocument,
+        owner: Participant,
+        reviewer: Participant,
+        other_owner: Uuid,
+    }
+
+    fn user(email: &str, role: UserRole) -> User {
+        User::new(
+            Email::new(email).unwrap(),
+            "hash".to_string(),
+            email.to_string(),
+            role,
+        )
+    }
+
+    async fn fixture() -> Fixture {
+        let owner = user("budi@example.id", UserRole::UmkmOwner);
+        let reviewer = user("staff@example.id", UserRole::AdminStaff);
+        let other_owner = user("siti@example.id", UserRole::UmkmOwner);
+        let license = License::new(
+            LicenseType::Siup,
+            Uuid::new_v4(),
+            owner.id.0,
+            "SIUP Toko Makmur".to_string(),
+            None,
+        );
+        let licenses = InMemoryLicenseRepository::new();
+        licenses.create_license(&license).await.unwrap();
+        let document = licenses
+            .create_document(&LicenseDocument::new(
+                license.id,
+                DocumentType::CompanyDeed,
+                "akta.pdf".to_string(),
+                "akta.pdf".to_string(),
+                "/uploads/akta.pdf".to_string(),
+                2048,
+                "application/pdf".to_string(),
+            ))
+            .await
+            .unwrap();
+
+        let (owner_id, reviewer_id, other_owner_id) = (owner.id.0, reviewer.id.0, other_owner.id.0);
+        let users = Arc::new(InMemoryUserRepository::with_users(vec![owner, reviewer, other_owner]));
+        let notifications = Arc::new(InMemoryNotificationRepository::new());
+        let hub = LiveUpdateHub::new();
+        let service = LicenseCommentService::new(
+            Arc::new(InMemoryLicenseCommentRepository::new()),
+            Arc::new(licenses),
+            users.clone(),
+            Arc::new(NotificationService::new(
+                notifications.clone(),
+                users,
+                "https://app.example.id".to_string(),
+            )),
+            hub.clone(),
+        );
+        Fixture {
+            service,
+            notifications,
+            hub,
+            license,
+            document,
+            owner: Participant {
+                user_id: owner_id,
+                is_admin: false,
+            },
+            reviewer: Participant {
+                user_id: reviewer_id,
+                is_admin: true,
+            },
+            other_owner: other_owner_id,
+        }
+    }
+
+    fn comment(body: &str) -> NewComment {
+        NewComment {
+            body: body.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_attachments_and_mentions_must_belong_to_the_discussion() {
+        let fx = fixture().await;
+        let attach = |document_id| NewComment {
+            document_ids: vec![document_id],
+            ..comment("Akta sudah diunggah")
+        };
+        let mention = |user_id, internal| NewComment {
+            mentions: vec![user_id],
+            internal,
+            ..comment("Mohon dicek")
+        };
+
+        assert!(fx.service.prepare(&fx.license, fx.owner, attach(fx.document.id)).await.is_ok());
+        assert!(matches!(
+            fx.service.prepare(&fx.license, fx.owner, attach(Uuid::new_v4())).await,
+            Err(AppError::Validation(_))
+        ));
+        // Another owner never reads this license; the applicant not internal notes
+        assert!(fx.service.prepare(&fx.license, fx.owner, mention(fx.other_owner, false)).await.is_err());
+        assert!(fx.service.prepare(&fx.license, fx.reviewer, mention(fx.owner.user_id, true)).await.is_err());
+        assert!(fx.service.prepare(&fx.license, fx.owner, mention(fx.reviewer.user_id, false)).await.is_ok());
+        assert!(matches!(
+            fx.service
+                .prepare(
+                    &fx.license,
+                    fx.owner,
+                    NewComment {
+                        parent_id: Some(Uuid::new_v4()),
+                        ..comment("Balasan")
+                    }
+                )
+                .await,
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_posting_notifies_the_applicant_and_mentions_and_tracks_reads() {
+        let fx = fixture().await;
+        let mut stream = fx.hub.subscribe();
+
+        let question = NewComment {
+            mentions: vec![fx.owner.user_id],
+            ..comment("Apakah alamat usaha sudah sesuai NIB?")
+        };
+        let prepared = fx.service.prepare(&fx.license, fx.reviewer, question).await.unwrap();
+        let question = fx.service.post(&fx.license, prepared).await.unwrap();
+        let internal = NewComment {
+            internal: true,
+            ..comment("Perlu cek lapangan")
+        };
+        let prepared = fx.service.prepare(&fx.license, fx.reviewer, internal).await.unwrap();
+        fx.service.post(&fx.license, prepared).await.unwrap();
+        let answer = NewComment {
+            parent_id: Some(question.id),
+            mentions: vec![fx.reviewer.user_id],
+            ..comment("Sudah sesuai")
+        };
+        let prepared = fx.service.prepare(&fx.license, fx.owner, answer).await.unwrap();
+        fx.service.post(&fx.license, prepared).await.unwrap();
+
+        // The applicant is told once about the question, the reviewer about the mention
+        let owner_inbox = fx.notifications.notifications(fx.owner.user_id, false, 10).await.unwrap();
+        assert_eq!(owner_inbox.len(), 1);
+        assert_eq!(owner_inbox[0].event, NotificationEvent::CommentReceived);
+        assert!(owner_inbox[0].body.contains("Apakah alamat usaha"));
+        let reviewer_inbox = fx.notifications.notifications(fx.reviewer.user_id, false, 10).await.unwrap();
+        assert_eq!(reviewer_inbox.len(), 1);
+        assert_eq!(reviewer_inbox[0].event, NotificationEvent::CommentMention);
+
+        // The internal note is pushed to admins only
+        let mut pushed = Vec::new();
+        while let Ok(HubMessage::Update(update)) = stream.try_recv() {
+            pushed.push(update);
+        }
+        assert_eq!(pushed.len(), 3);
+        assert!(!pushed[1].visible_to(fx.owner.user_id, false));
+        assert!(pushed[1].visible_to(fx.reviewer.user_id, true));
+
+        let seen_by_owner = fx.service.discussion(&fx.license, fx.owner).await.unwrap();
+        assert_eq!(seen_by_owner.threads.len(), 1);
+        assert_eq!(seen_by_owner.unread_count, 1);
+        assert_eq!(fx.service.mark_read(&fx.license, fx.owner).await.unwrap(), 1);
+        assert_eq!(fx.service.mark_read(&fx.license, fx.owner).await.unwrap(), 0);
+        let seen_by_reviewer = fx.service.discussion(&fx.license, fx.reviewer).await.unwrap();
+        assert_eq!(seen_by_reviewer.threads.len(), 2);
+        assert_eq!(seen_by_reviewer.unread_count, 1);
+        let question = &seen_by_reviewer.threads[0].comment;
+        assert!(question.read_by.iter().any(|r| r.user_id == fx.owner.user_id));
+    }
+}
@@ -1,4 +1,9 @@
-use crate::domain::licenses::{ApplicationStatus, License};
+use uuid::Uuid;
+
+use crate::domain::licenses::{ApplicationStatus, ApplicationStatusHistory, License};
+use crate::domain::unit_of_work::UnitOfWork;
+use crate::infrastructure::repositories::cached_license_repository::LicenseRepositories;
+use crate::services::license_processing_models::{LicenseAction, LicenseDecision};
 use crate::shared::errors::{AppError, AppResult};
 
 /// Service responsible for handling license workflows
@@ -7,7 +12,6 @@ pub struct LicenseProcessingService;
 
 impl LicenseProcessingService {
     /// Create a new instance of the service
-    #[allow(dead_code)]
     pub fn new() -> Self {
         Self
     }
@@ -25,4 +29,137 @@ impl LicenseProcessingService {
     pub fn status(&self, license: &License) -> ApplicationStatus {
         license.application_status.clone()
     }
+
+    /// Approves or rejects the license at `expected_version` and records the
+    /// status change in its history, both in one unit of work
+    pub async fn decide(
+        &self,
+        unit_of_work: &dyn UnitOfWork<LicenseRepositories>,
+        license_id: Uuid,
+        expected_version: i64,
+        decided_by: Uuid,
+        decision: LicenseDecision,
+    ) -> AppResult<License> {
+        let work = unit_of_work.begin().await?;
+        let previous = work
+            .licenses
+            .get_license_by_id(license_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("License not found".to_string()))?;
+
+        let (license, notes) = match decision {
+            LicenseDecision::Approve {
+                license_number,
+                issue_date,
+                expiry_date,
+                issuing_authority,
+                admin_notes,
+            } => {
+                let license = work
+                    .licenses
+                    .approve_license(
+                        license_id,
+                        expected_version,
+                        decided_by,
+                        license_number,
+                        issue_date,
+                        expiry_date,
+                        issuing_authority,
+                        admin_notes.clone(),
+                    )
+                    .await?;
+                (license, admin_notes)
+            }
+            LicenseDecision::Reject {
+                reason,
+                admin_notes,
+            } => {
+                let license = work
+                    .licenses
+                    .reject_license(license_id, expected_version, decided_by, reason.clone(), admin_notes)
+                    .await?;
+                (license, Some(reason))
+            }
+        };
+
+        let entry = ApplicationStatusHistory::new(
+            license.id,
+            Some(previous.application_status),
+            license.application_status.clone(),
+            decided_by,
+            notes,
+            false,
+        );
+        work.licenses.create_status_history(&entry).await?;
+        work.commit().await?;
+
+        Ok(license)
+    }
+
+    /// Requests documents for, suspends, reinstates or renews the license at
+    /// `expected_version` and records it in the status history, both in one
+    /// unit of work
+    pub async fn act(
+        &self,
+        unit_of_work: &dyn UnitOfWork<LicenseRepositories>,
+        license_id: Uuid,
+        expected_version: i64,
+        acted_by: Uuid,
+        action: LicenseAction,
+    ) -> AppResult<License> {
+        let work = unit_of_work.begin().await?;
+        let mut license = work
+            .licenses
+            .get_license_by_id(license_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("License not found".to_string()))?;
+        let previous_status = license.application_status.clone();
+
+        let notes = match action {
+            LicenseAction::RequestDocuments { notes } => {
+                license
+                    .request_documents(notes.clone())
+                    .map_err(AppError::Validation)?;
+                Some(notes)
+            }
+            LicenseAction::Suspend { reason } => {
+                license.suspend(reason.clone()).map_err(AppError::Validation)?;
+                Some(reason)
+            }
+            LicenseAction::Reinstate { admin_notes } => {
+                license.reinstate(admin_notes.clone()).map_err(AppError::Validation)?;
+                admin_notes
+            }
+            LicenseAction::Renew {
+                expiry_date,
+                admin_notes,
+            } => {
+                license
+                    .renew(expiry_date, admin_notes.clone())
+                    .map_err(AppError::Validation)?;
+                let renewed = format!("Renewed until {}", expiry_date.format("%Y-%m-%d"));
+                Some(match admin_notes {
+                    Some(notes) => format!("{}: {}", renewed, notes),
+                    None => renewed,
+                })
+            }
+        };
+
+        // Saved only if nobody changed the license since `expected_version`
+        license.version = expected_version;
+        let license = work.licenses.update_license(&license).await?;
+
+        let entry = ApplicationStatusHistory::new(
+            license.id,
+            Some(previous_status),
+            license.application_status.clone(),
+            acted_by,
+            notes,
+            false,
+        );
+        work.licenses.create_status_history(&entry).await?;
+        work.commit().await?;
+
+        Ok(license)
+    }
 }
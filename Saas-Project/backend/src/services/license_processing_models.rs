@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -21,3 +22,38 @@ pub struct LicenseApplicationResponse {
     pub id: Uuid,
     pub status: ApplicationStatus,
 }
+
+/// What an admin decided about a submitted license application
+#[derive(Debug, Clone)]
+pub enum LicenseDecision {
+    Approve {
+        license_number: String,
+        issue_date: DateTime<Utc>,
+        expiry_date: Option<DateTime<Utc>>,
+        issuing_authority: String,
+        admin_notes: Option<String>,
+    },
+    Reject {
+        reason: String,
+        admin_notes: Option<String>,
+    },
+}
+
+/// What an admin does to a license other than deciding on it
+#[derive(Debug, Clone)]
+pub enum LicenseAction {
+    /// Pauses the review until the applicant supplies what `notes` asks for
+    RequestDocuments {
+        notes: String,
+    },
+    Suspend {
+        reason: String,
+    },
+    Reinstate {
+        admin_notes: Option<String>,
+    },
+    Renew {
+        expiry_date: DateTime<Utc>,
+        admin_notes: Option<String>,
+    },
+}
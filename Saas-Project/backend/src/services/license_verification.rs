@@ -0,0 +1,387 @@
+// Public verification of licenses
+// A certificate's QR code carries a token: the license id and an HMAC-SHA256
+// tag over it, truncated to 128 bits to keep the code small. Only the server
+// can mint tokens, so a forged certificate cannot point at a real license,
+// and a token reveals nothing but a random id. Licenses can also be looked up
+// by their number or by the company's NIB, which only finds issued licenses.
+// What a lookup resolves to is always the license's current standing, so a
+// certificate printed before a suspension shows the suspension when scanned.
+// Every lookup is written to the audit trail; a failure to write it is logged
+// but does not fail the lookup.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::Arc;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::domain::certificates::is_certifiable;
+use crate::domain::licenses::License;
+use crate::domain::repositories::CompanyRepository;
+use crate::domain::verification::{
+    LookupMethod, PublicLicense, VerificationAuditRepository, VerificationLookup,
+};
+use crate::infrastructure::monitoring::record_license_verification;
+use crate::infrastructure::repositories::LicenseRepository;
+use crate::shared::errors::{AppError, AppResult};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Keeps these tags apart from anything else signed with the same secret
+const TOKEN_CONTEXT: &[u8] = b"saas-umkm/license-verification/v1";
+const TAG_BYTES: usize = 16;
+/// Longest user agent kept in the audit trail
+const MAX_USER_AGENT_LENGTH: usize = 255;
+
+/// Mints and checks license verification tokens
+#[derive(Clone)]
+pub struct VerificationSigner {
+    key: Vec<u8>,
+}
+
+impl VerificationSigner {
+    pub fn new(secret: &str) -> Self {
+        Self {
+            key: secret.as_bytes().to_vec(),
+        }
+    }
+
+    fn mac(&self, license_id: Uuid) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC takes keys of any length");
+        mac.update(TOKEN_CONTEXT);
+        mac.update(license_id.as_bytes());
+        mac
+    }
+
+    pub fn sign(&self, license_id: Uuid) -> String {
+        let tag = self.mac(license_id).finalize().into_bytes();
+        let mut token = license_id.as_bytes().to_vec();
+        token.extend_from_slice(&tag[..TAG_BYTES]);
+        URL_SAFE_NO_PAD.encode(token)
+    }
+
+    /// The license a token was minted for, if it is genuine
+    pub fn verify(&self, token: &str) -> Option<Uuid> {
+        let bytes = URL_SAFE_NO_PAD.decode(token).ok()?;
+        if bytes.len() != 16 + TAG_BYTES {
+            return None;
+        }
+        let license_id = Uuid::from_slice(&bytes[..16]).ok()?;
+        self.mac(license_id)
+            .verify_truncated_left(&bytes[16..])
+            .ok()?;
+        Some(license_id)
+    }
+}
+
+/// Who made a lookup, as far as the request tells
+#[derive(Debug, Clone, Default)]
+pub struct LookupClient {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+pub struct LicenseVerificationService {
+    licenses: Arc<dyn LicenseRepository + Send + Sync>,
+    companies: Arc<dyn CompanyRepository + Send + Sync>,
+    audit: Arc<dyn VerificationAuditRepository>,
+    signer: VerificationSigner,
+    /// Where the API is reachable from outside, e.g. `https://app.saas-umkm.id`
+    public_base_url: String,
+}
+
+fn not_found() -> AppError {
+    AppError::NotFound("License could not be verified".to_string())
+}
+
+impl LicenseVerificationService {
+    pub fn new(
+        licenses: Arc<dyn LicenseRepository + Send + Sync>,
+        companies: Arc<dyn CompanyRepository + Send + Sync>,
+        audit: Arc<dyn VerificationAuditRepository>,
+        signer: VerificationSigner,
+        public_base_url: &str,
+    ) -> Self {
+        Self {
+            licenses,
+            companies,
+            audit,
+            signer,
+            public_base_url: public_base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Public link that verifies the license, for its certificate's QR code
+    pub fn verification_url(&self, license_id: Uuid) -> String {
+        format!(
+            "{}/api/v1/verify/{}",
+            self.public_base_url,
+            self.signer.sign(license_id)
+        )
+    }
+
+    /// The public facts of the license a token was minted for. Forged tokens
+    /// and tokens of deleted licenses are indistinguishable to the caller.
+    pub async fn verify_token(&self, token: &str, client: &LookupClient) -> AppResult<PublicLicense> {
+        let license = match self.signer.verify(token) {
+            Some(license_id) => self.licenses.get_license_by_id(license_id).await?,
+            None => None,
+        };
+        let mut found = self
+            .public_licenses(LookupMethod::Token, token, license.into_iter().collect(), client)
+            .await?;
+        found.pop().ok_or_else(not_found)
+    }
+
+    /// The issued license with this number
+    pub async fn lookup_license_number(
+        &self,
+        license_number: &str,
+        client: &LookupClient,
+    ) -> AppResult<PublicLicense> {
+        let license_number = license_number.trim();
+        if license_number.is_empty() {
+            return Err(AppError::Validation("License number is required".to_string()));
+        }
+        let license = self.licenses.get_license_by_number(license_number).await?;
+        let mut found = self
+            .public_licenses(
+                LookupMethod::LicenseNumber,
+                license_number,
+                license.into_iter().collect(),
+                client,
+            )
+            .await?;
+        found.pop().ok_or_else(not_found)
+    }
+
+    /// Every issued license of the company with this NIB, oldest first
+    pub async fn lookup_nib(&self, nib: &str, client: &LookupClient) -> AppResult<Vec<PublicLicense>> {
+        let nib = nib.trim();
+        if nib.len() != 13 || !nib.chars().all(|c| c.is_ascii_digit()) {
+            return Err(AppError::Validation("NIB must be 13 digits".to_string()));
+        }
+        let licenses = match self.companies.find_by_nib(nib).await? {
+            Some(company) => self.licenses.get_licenses_by_company(company.id).await?,
+            None => Vec::new(),
+        };
+        let found = self.public_licenses(LookupMethod::Nib, nib, licenses, client).await?;
+        if found.is_empty() {
+            return Err(not_found());
+        }
+        Ok(found)
+    }
+
+    /// The audit trail, newest first, optionally of one company only
+    pub async fn recent_lookups(
+        &self,
+        company_id: Option<Uuid>,
+        limit: i64,
+    ) -> AppResult<Vec<VerificationLookup>> {
+        self.audit.recent_lookups(company_id, limit).await
+    }
+
+    /// The public facts of the issued ones among `licenses`, recording the
+    /// lookup that found them
+    async fn public_licenses(
+        &self,
+        method: LookupMethod,
+        query: &str,
+        licenses: Vec<License>,
+        client: &LookupClient,
+    ) -> AppResult<Vec<PublicLicense>> {
+        let mut issued: Vec<License> = licenses.into_iter().filter(is_certifiable).collect();
+        issued.sort_by_key(|l| (l.issue_date, l.created_at));
+
+        let company = match issued.first() {
+            Some(license) => self.companies.find_by_id(&license.company_id).await?,
+            None => None,
+        };
+        let found = match &company {
+            Some(company) => issued.iter().map(|l| PublicLicense::new(l, company)).collect(),
+            None => Vec::new(),
+        };
+
+        let mut lookup = VerificationLookup::new(method, query);
+        lookup.company_id = company.as_ref().map(|c| c.id);
+        if method != LookupMethod::Nib {
+            lookup.license_id = issued.first().filter(|_| company.is_some()).map(|l| l.id);
+        }
+        lookup.licenses_found = found.len() as i32;
+        lookup.client_ip = client.ip.clone();
+        lookup.user_agent = client
+            .user_agent
+            .as_ref()
+            .map(|agent| agent.chars().take(MAX_USER_AGENT_LENGTH).collect());
+        if let Err(err) = self.audit.record_lookup(&lookup).await {
+            warn!(method = %method, "⚠️ Failed to record verification lookup: {}", err);
+        }
+        record_license_verification(&method.to_string(), !found.is_empty());
+
+        Ok(found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+
+    use crate::domain::companies::{BusinessType, Company, CompanyAddress};
+    use crate::domain::licenses::{ApplicationStatus, LicenseType};
+    use crate::domain::verification::PublicLicenseStatus;
+    use crate::infrastructure::repositories::{InMemoryCompanyRepository, InMemoryLicenseRepository};
+
+    const NIB: &str = "9120001234567";
+
+    async fn service() -> (LicenseVerificationService, InMemoryLicenseRepository, Company) {
+        let companies = InMemoryCompanyRepository::new();
+        let licenses = InMemoryLicenseRepository::new();
+        let mut company = Company::new(
+            Uuid::new_v4(),
+            "Kopi Gayo Makmur".to_string(),
+            BusinessType::UD,
+            "Perkebunan".to_string(),
+            CompanyAddress::new(
+                "Jl. Takengon 3".to_string(),
+                "Aceh Tengah".to_string(),
+                "Aceh".to_string(),
+                "24511".to_string(),
+            ),
+        );
+        company.set_nib(NIB.to_string()).unwrap();
+        companies.save(&company).await.unwrap();
+
+        for (license_type, number, days_ago) in [
+            (LicenseType::Nib, Some("NIB-0001"), 60),
+            (LicenseType::Halal, Some("HALAL-0002"), 30),
+            (LicenseType::Siup, None, 0),
+        ] {
+            let mut license = License::new(license_type, company.id, company.owner_id, "Izin".to_string(), None);
+            if let Some(number) = number {
+                license.application_status = ApplicationStatus::Processing;
+                license
+                    .approve(
+                        number.to_string(),
+                        Utc::now() - Duration::days(days_ago),
+                        Some(Utc::now() + Duration::days(365)),
+                        "OSS RBA".to_string(),
+                        None,
+                    )
+                    .unwrap();
+            }
+            licenses.create_license(&license).await.unwrap();
+        }
+
+        let service = LicenseVerificationService::new(
+            Arc::new(licenses.clone()),
+            Arc::new(companies),
+            Arc::new(licenses.clone()),
+            VerificationSigner::new("secret"),
+            "https://app.example.id",
+        );
+        (service, licenses, company)
+    }
+
+    fn client() -> LookupClient {
+        LookupClient {
+            ip: Some("203.0.113.9".to_string()),
+            user_agent: Some("curl/8.5".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lookups_find_issued_licenses_only() {
+        let (service, _, company) = service().await;
+
+        let halal = service.lookup_license_number(" HALAL-0002 ", &client()).await.unwrap();
+        assert_eq!(halal.license_type, LicenseType::Halal);
+        assert_eq!(halal.company_name, "Kopi Gayo Makmur");
+        assert_eq!(halal.status, PublicLicenseStatus::Active);
+
+        // The SIUP application has not been issued yet
+        let all = service.lookup_nib(NIB, &client()).await.unwrap();
+        assert_eq!(
+            all.iter().map(|l| l.license_number.as_deref()).collect::<Vec<_>>(),
+            vec![Some("NIB-0001"), Some("HALAL-0002")]
+        );
+        assert!(all.iter().all(|l| l.company_name == company.company_name));
+
+        assert!(matches!(
+            service.lookup_license_number("SIUP-9999", &client()).await,
+            Err(AppError::NotFound(_))
+        ));
+        assert!(matches!(
+            service.lookup_nib("9120009999999", &client()).await,
+            Err(AppError::NotFound(_))
+        ));
+        assert!(matches!(service.lookup_nib("12345", &client()).await, Err(AppError::Validation(_))));
+        assert!(matches!(
+            service.lookup_license_number("  ", &client()).await,
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_every_lookup_is_audited() {
+        let (service, licenses, company) = service().await;
+        let halal = licenses
+            .get_license_by_number("HALAL-0002")
+            .await
+            .unwrap()
+            .unwrap();
+
+        let token = service.signer.sign(halal.id);
+        service.verify_token(&token, &client()).await.unwrap();
+        service.lookup_nib(NIB, &LookupClient::default()).await.unwrap();
+        service.lookup_license_number("SIUP-9999", &client()).await.unwrap_err();
+        // Malformed queries never reach the trail
+        service.lookup_nib("12345", &client()).await.unwrap_err();
+
+        let mut trail = licenses.recent_lookups(None, 10).await.unwrap();
+        trail.sort_by_key(|l| l.licenses_found);
+        let summary: Vec<_> = trail
+            .iter()
+            .map(|l| (l.method, l.company_id, l.license_id, l.licenses_found))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (LookupMethod::LicenseNumber, None, None, 0),
+                (LookupMethod::Token, Some(company.id), Some(halal.id), 1),
+                (LookupMethod::Nib, Some(company.id), None, 2),
+            ]
+        );
+        assert_eq!(trail[0].query, "SIUP-9999");
+        assert_eq!(trail[0].client_ip.as_deref(), Some("203.0.113.9"));
+        assert_eq!(trail[2].client_ip, None);
+        assert_eq!(licenses.recent_lookups(Some(company.id), 10).await.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_tokens_verify_only_with_the_signing_key() {
+        let signer = VerificationSigner::new("secret");
+        let license_id = Uuid::new_v4();
+        let token = signer.sign(license_id);
+
+        assert_eq!(token.len(), 43);
+        assert_eq!(signer.verify(&token), Some(license_id));
+        assert_eq!(VerificationSigner::new("other").verify(&token), None);
+    }
+
+    #[test]
+    fn test_tampered_tokens_are_rejected() {
+        let signer = VerificationSigner::new("secret");
+        let token = signer.sign(Uuid::new_v4());
+
+        // Another license id with the original tag
+        let mut bytes = URL_SAFE_NO_PAD.decode(&token).unwrap();
+        bytes[0] ^= 1;
+        assert_eq!(signer.verify(&URL_SAFE_NO_PAD.encode(&bytes)), None);
+
+        assert_eq!(signer.verify(&token[..20]), None);
+        assert_eq!(signer.verify("not a token"), None);
+        assert_eq!(signer.verify(""), None);
+    }
+}
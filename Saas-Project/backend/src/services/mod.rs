@@ -1,4 +1,13 @@
+pub mod analytics_refresher;
 pub mod auth;
+pub mod billing;
+pub mod invoicing;
+pub mod license_certificates;
+pub mod license_comments;
 pub mod license_processing;
 pub mod license_processing_models;
+pub mod license_verification;
+pub mod notification_dispatcher;
+pub mod notifications;
 pub mod payment;
+pub mod sla_monitor;
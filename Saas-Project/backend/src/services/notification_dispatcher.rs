@@ -0,0 +1,243 @@
+// Sends queued email and WhatsApp notifications
+// Each run claims the deliveries that are due and hands them to the sender of
+// their channel. A failed attempt is retried later, each time waiting twice as
+// long, until the retry policy gives up and the delivery is marked failed.
+// Claims are leased, so several instances can run the dispatcher and a
+// delivery stranded by a crash is picked up again once its lease runs out.
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::domain::notifications::{
+    DeliveryStatus, MessageSender, NotificationChannel, NotificationRepository, RetryPolicy,
+};
+use crate::infrastructure::monitoring::record_notification_delivery;
+use crate::shared::errors::{AppError, AppResult};
+
+/// Deliveries claimed per run
+const BATCH_SIZE: i64 = 50;
+
+/// What a run did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DispatchRun {
+    pub sent: usize,
+    /// Failed attempts that will be retried
+    pub retried: usize,
+    /// Failed attempts that used up the last retry
+    pub failed: usize,
+}
+
+pub struct NotificationDispatcher {
+    notifications: Arc<dyn NotificationRepository>,
+    email: Arc<dyn MessageSender>,
+    whatsapp: Arc<dyn MessageSender>,
+    retry: RetryPolicy,
+    /// How long a claimed delivery is left to one run
+    lease: ChronoDuration,
+}
+
+impl NotificationDispatcher {
+    pub fn new(
+        notifications: Arc<dyn NotificationRepository>,
+        email: Arc<dyn MessageSender>,
+        whatsapp: Arc<dyn MessageSender>,
+        retry: RetryPolicy,
+    ) -> Self {
+        Self {
+            notifications,
+            email,
+            whatsapp,
+            retry,
+            lease: ChronoDuration::minutes(5),
+        }
+    }
+
+    fn sender(&self, channel: NotificationChannel) -> Option<&Arc<dyn MessageSender>> {
+        match channel {
+            NotificationChannel::Email => Some(&self.email),
+            NotificationChannel::WhatsApp => Some(&self.whatsapp),
+            NotificationChannel::InApp => None,
+        }
+    }
+
+    /// Attempts every delivery due at `now`, scheduling retries from `now`
+    pub async fn dispatch_due(&self, now: DateTime<Utc>) -> AppResult<DispatchRun> {
+        let mut run = DispatchRun::default();
+        let deliveries = self
+            .notifications
+            .claim_due_deliveries(now, now + self.lease, BATCH_SIZE)
+            .await?;
+
+        for mut delivery in deliveries {
+            let outcome = match self.sender(delivery.channel) {
+                Some(sender) => {
+                    sender
+                        .send(&delivery.recipient, &delivery.subject, &delivery.body)
+                        .await
+                }
+                None => Err(AppError::InternalError(
+                    "In-app notifications are not delivered".to_string(),
+                )),
+            };
+
+            let result = match outcome {
+                Ok(()) => {
+                    delivery.mark_sent(now);
+                    run.sent += 1;
+                    "sent"
+                }
+                Err(err) => {
+                    delivery.mark_failed(err.to_string(), now, &self.retry);
+                    if delivery.status == DeliveryStatus::Failed {
+                        warn!(
+                            "⚠️ Giving up on {} notification {} after {} attempts: {}",
+                            delivery.channel, delivery.id, delivery.attempts, err
+                        );
+                        run.failed += 1;
+                        "failed"
+                    } else {
+                        run.retried += 1;
+                        "retried"
+                    }
+                }
+            };
+            record_notification_delivery(&delivery.channel.to_string(), result);
+            self.notifications.record_attempt(&delivery).await?;
+        }
+
+        if run != DispatchRun::default() {
+            debug!(
+                sent = run.sent,
+                retried = run.retried,
+                failed = run.failed,
+                "📨 Notifications dispatched"
+            );
+        }
+        Ok(run)
+    }
+
+    /// Dispatches every `interval` until the process exits
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            info!("📨 Notification dispatch every {}s", interval.as_secs());
+            let mut ticks = tokio::time::interval(interval);
+            loop {
+                ticks.tick().await;
+                if let Err(err) = self.dispatch_due(Utc::now()).await {
+                    warn!("⚠️ Notification dispatch failed: {}", err);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::notifications::{NotificationDelivery, NotificationEvent};
+    use crate::infrastructure::messaging::RecordingSender;
+    use crate::infrastructure::repositories::InMemoryNotificationRepository;
+    use uuid::Uuid;
+
+    fn delivery(channel: NotificationChannel, recipient: &str) -> NotificationDelivery {
+        NotificationDelivery::new(
+            Uuid::new_v4(),
+            NotificationEvent::LicenseApproved,
+            channel,
+            recipient.to_string(),
+            "Izin disetujui".to_string(),
+            "Halo Budi".to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_failed_deliveries_are_retried_with_backoff_until_given_up() {
+        let repo = Arc::new(InMemoryNotificationRepository::new());
+        let email = RecordingSender::new("email");
+        let whatsapp = RecordingSender::new("whatsapp");
+        let retry = RetryPolicy {
+            max_attempts: 3,
+            base_delay: ChronoDuration::seconds(30),
+            max_delay: ChronoDuration::hours(1),
+        };
+        let dispatcher = NotificationDispatcher::new(
+            repo.clone(),
+            Arc::new(email.clone()),
+            Arc::new(whatsapp.clone()),
+            retry,
+        );
+
+        repo.enqueue_delivery(&delivery(NotificationChannel::Email, "budi@example.id"))
+            .await
+            .unwrap();
+        repo.enqueue_delivery(&delivery(NotificationChannel::WhatsApp, "+6281234567890"))
+            .await
+            .unwrap();
+
+        // Email goes through, WhatsApp is down for a while
+        whatsapp.fail_next(10);
+        let t0 = Utc::now() + ChronoDuration::seconds(1);
+        let run = dispatcher.dispatch_due(t0).await.unwrap();
+        assert_eq!(run, DispatchRun { sent: 1, retried: 1, failed: 0 });
+        assert_eq!(email.sent()[0].recipient, "budi@example.id");
+
+        // Nothing is due again before the backoff has passed
+        let early = dispatcher.dispatch_due(t0 + ChronoDuration::seconds(20)).await.unwrap();
+        assert_eq!(early, DispatchRun::default());
+
+        // Second failure after 30s, third and last after another 60s
+        let t1 = t0 + ChronoDuration::seconds(31);
+        assert_eq!(dispatcher.dispatch_due(t1).await.unwrap().retried, 1);
+        assert_eq!(
+            dispatcher.dispatch_due(t1 + ChronoDuration::seconds(50)).await.unwrap(),
+            DispatchRun::default()
+        );
+        let t2 = t1 + ChronoDuration::seconds(61);
+        assert_eq!(dispatcher.dispatch_due(t2).await.unwrap().failed, 1);
+
+        let failed = repo.deliveries(Some(DeliveryStatus::Failed), 10).await.unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].channel, NotificationChannel::WhatsApp);
+        assert_eq!(failed[0].attempts, 3);
+        assert!(failed[0].last_error.as_deref().unwrap().contains("unavailable"));
+        let sent = repo.deliveries(Some(DeliveryStatus::Sent), 10).await.unwrap();
+        assert_eq!((sent.len(), sent[0].attempts), (1, 1));
+
+        // Failed deliveries are not attempted again
+        let later = t2 + ChronoDuration::days(1);
+        assert_eq!(dispatcher.dispatch_due(later).await.unwrap(), DispatchRun::default());
+    }
+
+    #[tokio::test]
+    async fn test_a_recovered_sender_delivers_on_retry() {
+        let repo = Arc::new(InMemoryNotificationRepository::new());
+        let email = RecordingSender::new("email");
+        let dispatcher = NotificationDispatcher::new(
+            repo.clone(),
+            Arc::new(email.clone()),
+            Arc::new(RecordingSender::new("whatsapp")),
+            RetryPolicy::default(),
+        );
+        repo.enqueue_delivery(&delivery(NotificationChannel::Email, "siti@example.id"))
+            .await
+            .unwrap();
+
+        email.fail_next(1);
+        let t0 = Utc::now() + ChronoDuration::seconds(1);
+        assert_eq!(dispatcher.dispatch_due(t0).await.unwrap().retried, 1);
+        let run = dispatcher
+            .dispatch_due(t0 + ChronoDuration::minutes(1))
+            .await
+            .unwrap();
+        assert_eq!(run.sent, 1);
+
+        let delivery = &repo.deliveries(None, 10).await.unwrap()[0];
+        assert_eq!(delivery.status, DeliveryStatus::Sent);
+        assert_eq!(delivery.attempts, 2);
+        assert!(delivery.last_error.is_none());
+        assert_eq!(email.sent().len(), 1);
+    }
+}
@@ -0,0 +1,502 @@
+// Notification center
+// When something happens to a license, its applicant (or, for mentions in its
+// discussion, whoever was mentioned) is told over every channel they left on
+// for that event; billing events go to the company's owner the same way. The
+// message is rendered in their language, stored in the in-app inbox and
+// queued for email and WhatsApp, which `NotificationDispatcher` sends. Raising
+// a notification never fails the action that caused it; problems are logged.
+
+use std::sync::Arc;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::domain::billing::SubscriptionInvoice;
+use crate::domain::licenses::License;
+use crate::domain::notification_templates::{
+    email_body, render, whatsapp_body, TemplateValues,
+};
+use crate::domain::notifications::{
+    channel_enabled, ChannelPreference, DeliveryStatus, Locale, Notification,
+    NotificationChannel, NotificationDelivery, NotificationEvent, NotificationRepository,
+};
+use crate::domain::repositories::UserRepository;
+use crate::domain::value_objects::{PhoneNumber, UserId};
+use crate::infrastructure::monitoring::record_notification;
+use crate::shared::errors::{AppError, AppResult};
+
+/// The unread count alongside a page of the inbox
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Inbox {
+    pub unread_count: i64,
+    pub notifications: Vec<Notification>,
+}
+
+/// A user's settings with every event and channel, chosen or not
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct NotificationPreferences {
+    pub locale: Locale,
+    pub whatsapp_number: Option<String>,
+    pub channels: Vec<ChannelPreference>,
+}
+
+/// Changes to a user's settings; what is left out stays as it is
+#[derive(Debug, Clone, Default)]
+pub struct PreferencesUpdate {
+    pub locale: Option<Locale>,
+    /// `Some(None)` removes the number
+    pub whatsapp_number: Option<Option<String>>,
+    pub channels: Vec<ChannelPreference>,
+}
+
+pub struct NotificationService {
+    notifications: Arc<dyn NotificationRepository>,
+    users: Arc<dyn UserRepository + Send + Sync>,
+    /// Web app base URL, without a trailing slash
+    app_url: String,
+}
+
+impl NotificationService {
+    pub fn new(
+        notifications: Arc<dyn NotificationRepository>,
+        users: Arc<dyn UserRepository + Send + Sync>,
+        app_url: String,
+    ) -> Self {
+        Self {
+            notifications,
+            users,
+            app_url,
+        }
+    }
+
+    /// Tells the applicant of `license` about `event`, with the reviewer's
+    /// `notes` if there are any
+    pub async fn notify_license(
+        &self,
+        license: &License,
+        event: NotificationEvent,
+        notes: Option<String>,
+    ) -> AppResult<()> {
+        self.notify_user(license.user_id, license, event, notes).await
+    }
+
+    /// Tells `user_id` about `event` concerning `license`
+    pub async fn notify_user(
+        &self,
+        user_id: Uuid,
+        license: &License,
+        event: NotificationEvent,
+        notes: Option<String>,
+    ) -> AppResult<()> {
+        let link = format!("{}/licenses/{}", self.app_url, license.id);
+        self.deliver(user_id, event, link, Some(license.id), |name| {
+            TemplateValues::for_license(license, name, notes)
+        })
+        .await
+    }
+
+    /// Tells `user_id`, the owner of the invoiced company, about `event`
+    /// concerning `invoice`
+    pub async fn notify_invoice(
+        &self,
+        user_id: Uuid,
+        invoice: &SubscriptionInvoice,
+        event: NotificationEvent,
+        notes: Option<String>,
+    ) -> AppResult<()> {
+        let link = format!("{}/companies/{}/billing", self.app_url, invoice.company_id);
+        self.deliver(user_id, event, link, None, |name| {
+            TemplateValues::for_invoice(invoice, name, notes)
+        })
+        .await
+    }
+
+    /// Renders the message for `user_id` and sends it over the channels they
+    /// left on for `event`
+    async fn deliver(
+        &self,
+        user_id: Uuid,
+        event: NotificationEvent,
+        link: String,
+        license_id: Option<Uuid>,
+        values: impl FnOnce(String) -> TemplateValues,
+    ) -> AppResult<()> {
+        let user = self
+            .users
+            .find_by_id(&UserId::from_uuid(user_id))
+            .await?
+            .ok_or_else(|| AppError::NotFound("Recipient not found".to_string()))?;
+        let settings = self.notifications.settings(user_id).await?;
+        let preferences = self.notifications.preferences(user_id).await?;
+        let enabled = |channel| channel_enabled(&preferences, event, channel);
+
+        let values = TemplateValues {
+            link: Some(link.clone()),
+            ..values(user.full_name.clone())
+        };
+        let message = render(event, settings.locale, &values);
+
+        if enabled(NotificationChannel::InApp) {
+            let now = chrono::Utc::now();
+            self.notifications
+                .create_notification(&Notification {
+                    id: Uuid::new_v4(),
+                    user_id,
+                    event,
+                    title: message.title.clone(),
+                    body: message.body.clone(),
+                    link: Some(link),
+                    license_id,
+                    read_at: None,
+                    created_at: now,
+                })
+                .await?;
+        }
+
+        if enabled(NotificationChannel::Email) {
+            self.notifications
+                .enqueue_delivery(&NotificationDelivery::new(
+                    user_id,
+                    event,
+                    NotificationChannel::Email,
+                    user.email.as_str().to_string(),
+                    message.title.clone(),
+                    email_body(settings.locale, &values, &message),
+                ))
+                .await?;
+        }
+
+        if let Some(number) = settings
+            .whatsapp_number
+            .filter(|_| enabled(NotificationChannel::WhatsApp))
+        {
+            self.notifications
+                .enqueue_delivery(&NotificationDelivery::new(
+                    user_id,
+                    event,
+                    NotificationChannel::WhatsApp,
+                    number,
+                    message.title.clone(),
+                    whatsapp_body(&values, &message),
+                ))
+                .await?;
+        }
+
+        record_notification(&event.to_string());
+        Ok(())
+    }
+
+    /// `notify_license` for callers that must not fail because of it
+    pub async fn notify_license_best_effort(
+        &self,
+        license: &License,
+        event: NotificationEvent,
+        notes: Option<String>,
+    ) {
+        self.notify_user_best_effort(license.user_id, license, event, notes)
+            .await
+    }
+
+    /// `notify_user` for callers that must not fail because of it
+    pub async fn notify_user_best_effort(
+        &self,
+        user_id: Uuid,
+        license: &License,
+        event: NotificationEvent,
+        notes: Option<String>,
+    ) {
+        if let Err(err) = self.notify_user(user_id, license, event, notes).await {
+            warn!(
+                "⚠️ Failed to notify {} about {} of license {}: {}",
+                user_id, event, license.id, err
+            );
+        }
+    }
+
+    /// `notify_invoice` for callers that must not fail because of it
+    pub async fn notify_invoice_best_effort(
+        &self,
+        user_id: Uuid,
+        invoice: &SubscriptionInvoice,
+        event: NotificationEvent,
+        notes: Option<String>,
+    ) {
+        if let Err(err) = self.notify_invoice(user_id, invoice, event, notes).await {
+            warn!(
+                "⚠️ Failed to notify {} about {} of invoice {}: {}",
+                user_id, event, invoice.number, err
+            );
+        }
+    }
+
+    pub async fn inbox(&self, user_id: Uuid, unread_only: bool, limit: i64) -> AppResult<Inbox> {
+        Ok(Inbox {
+            unread_count: self.notifications.unread_count(user_id).await?,
+            notifications: self
+                .notifications
+                .notifications(user_id, unread_only, limit)
+                .await?,
+        })
+    }
+
+    pub async fn set_read(
+        &self,
+        user_id: Uuid,
+        notification_id: Uuid,
+        read: bool,
+    ) -> AppResult<Notification> {
+        self.notifications
+            .set_read(user_id, notification_id, read)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Notification not found".to_string()))
+    }
+
+    pub async fn mark_all_read(&self, user_id: Uuid) -> AppResult<u64> {
+        self.notifications.mark_all_read(user_id).await
+    }
+
+    pub async fn preferences(&self, user_id: Uuid) -> AppResult<NotificationPreferences> {
+        let settings = self.notifications.settings(user_id).await?;
+        let chosen = self.notifications.preferences(user_id).await?;
+        let channels = NotificationEvent::ALL
+            .into_iter()
+            .flat_map(|event| {
+                NotificationChannel::ALL
+                    .into_iter()
+                    .map(move |channel| (event, channel))
+            })
+            .map(|(event, channel)| ChannelPreference {
+                event,
+                channel,
+                enabled: channel_enabled(&chosen, event, channel),
+            })
+            .collect();
+
+        Ok(NotificationPreferences {
+            locale: settings.locale,
+            whatsapp_number: settings.whatsapp_number,
+            channels,
+        })
+    }
+
+    /// Applies `update`; WhatsApp can only be on for an event while a mobile
+    /// number is set
+    pub async fn update_preferences(
+        &self,
+        user_id: Uuid,
+        update: PreferencesUpdate,
+    ) -> AppResult<NotificationPreferences> {
+        let mut settings = self.notifications.settings(user_id).await?;
+        if let Some(locale) = update.locale {
+            settings.locale = locale;
+        }
+        if let Some(number) = update.whatsapp_number {
+            settings.whatsapp_number = match number {
+                None => None,
+                Some(number) => {
+                    let phone = PhoneNumber::new(&number).map_err(AppError::Validation)?;
+                    if !phone.is_mobile() {
+                        return Err(AppError::Validation(
+                            "WhatsApp notifications need a mobile number".to_string(),
+                        ));
+                    }
+                    Some(phone.as_str().to_string())
+                }
+            };
+        }
+
+        let mut chosen = self.notifications.preferences(user_id).await?;
+        chosen.retain(|p| {
+            !update
+                .channels
+                .iter()
+                .any(|u| u.event == p.event && u.channel == p.channel)
+        });
+        chosen.extend(update.channels.iter().copied());
+        let whatsapp_on = NotificationEvent::ALL
+            .into_iter()
+            .any(|event| channel_enabled(&chosen, event, NotificationChannel::WhatsApp));
+        if whatsapp_on && settings.whatsapp_number.is_none() {
+            return Err(AppError::Validation(
+                "Set a WhatsApp number before turning on WhatsApp notifications".to_string(),
+            ));
+        }
+
+        self.notifications.save_settings(&settings).await?;
+        for preference in &update.channels {
+            self.notifications
+                .set_preference(user_id, preference)
+                .await?;
+        }
+        self.preferences(user_id).await
+    }
+
+    /// Recent email and WhatsApp deliveries, for operators
+    pub async fn deliveries(
+        &self,
+        status: Option<DeliveryStatus>,
+        limit: i64,
+    ) -> AppResult<Vec<NotificationDelivery>> {
+        self.notifications.deliveries(status, limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::{User, UserRole};
+    use crate::domain::licenses::LicenseType;
+    use crate::domain::value_objects::Email;
+    use crate::infrastructure::repositories::{
+        InMemoryNotificationRepository, InMemoryUserRepository,
+    };
+
+    fn setup() -> (NotificationService, Arc<InMemoryNotificationRepository>, License) {
+        let user = User::new(
+            Email::new("budi@example.id").unwrap(),
+            "hash".to_string(),
+            "Budi Santoso".to_string(),
+            UserRole::UmkmOwner,
+        );
+        let license = License::new(
+            LicenseType::Siup,
+            Uuid::new_v4(),
+            user.id.0,
+            "SIUP Toko Makmur".to_string(),
+            None,
+        );
+        let repo = Arc::new(InMemoryNotificationRepository::new());
+        let service = NotificationService::new(
+            repo.clone(),
+            Arc::new(InMemoryUserRepository::with_users(vec![user])),
+            "https://app.example.id".to_string(),
+        );
+        (service, repo, license)
+    }
+
+    #[tokio::test]
+    async fn test_notifies_over_the_channels_the_user_left_on() {
+        let (service, repo, license) = setup();
+        let user_id = license.user_id;
+
+        service
+            .notify_license(&license, NotificationEvent::LicenseRejected, Some("NPWP tidak valid".to_string()))
+            .await
+            .unwrap();
+        let inbox = service.inbox(user_id, false, 10).await.unwrap();
+        assert_eq!(inbox.unread_count, 1);
+        let notification = &inbox.notifications[0];
+        assert_eq!(notification.title, "Izin ditolak");
+        assert!(notification.body.ends_with("Catatan: NPWP tidak valid"));
+        assert_eq!(
+            notification.link.as_deref(),
+            Some(format!("https://app.example.id/licenses/{}", license.id).as_str())
+        );
+        // Email by default, WhatsApp only once chosen
+        let deliveries = repo.deliveries(None, 10).await.unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].channel, NotificationChannel::Email);
+        assert_eq!(deliveries[0].recipient, "budi@example.id");
+        assert!(deliveries[0].body.starts_with("Halo Budi Santoso,"));
+
+        service
+            .update_preferences(
+                user_id,
+                PreferencesUpdate {
+                    locale: Some(Locale::En),
+                    whatsapp_number: Some(Some("0812-3456-7890".to_string())),
+                    channels: vec![
+                        ChannelPreference {
+                            event: NotificationEvent::LicenseApproved,
+                            channel: NotificationChannel::WhatsApp,
+                            enabled: true,
+                        },
+                        ChannelPreference {
+                            event: NotificationEvent::LicenseApproved,
+                            channel: NotificationChannel::Email,
+                            enabled: false,
+                        },
+                    ],
+                },
+            )
+            .await
+            .unwrap();
+        service
+            .notify_license(&license, NotificationEvent::LicenseApproved, None)
+            .await
+            .unwrap();
+
+        let deliveries = repo.deliveries(None, 10).await.unwrap();
+        assert_eq!(deliveries.len(), 2);
+        let whatsapp = deliveries
+            .iter()
+            .find(|d| d.channel == NotificationChannel::WhatsApp)
+            .unwrap();
+        assert_eq!(whatsapp.recipient, "+6281234567890");
+        assert!(whatsapp.body.starts_with("*License approved*"));
+        assert_eq!(service.inbox(user_id, true, 10).await.unwrap().unread_count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_read_state_and_preferences() {
+        let (service, _repo, license) = setup();
+        let user_id = license.user_id;
+        for event in [NotificationEvent::LicenseApproved, NotificationEvent::LicenseRenewed] {
+            service.notify_license(&license, event, None).await.unwrap();
+        }
+
+        let inbox = service.inbox(user_id, false, 10).await.unwrap();
+        let first = inbox.notifications[1].id;
+        assert!(service.set_read(user_id, first, true).await.unwrap().is_read());
+        assert_eq!(service.inbox(user_id, true, 10).await.unwrap().notifications.len(), 1);
+        assert!(!service.set_read(user_id, first, false).await.unwrap().is_read());
+        // Other users' notifications cannot be touched
+        assert!(matches!(
+            service.set_read(Uuid::new_v4(), first, true).await,
+            Err(AppError::NotFound(_))
+        ));
+        assert_eq!(service.mark_all_read(user_id).await.unwrap(), 2);
+        assert_eq!(service.inbox(user_id, false, 10).await.unwrap().unread_count, 0);
+
+        // Every event and channel is listed, WhatsApp off by default
+        let preferences = service.preferences(user_id).await.unwrap();
+        assert_eq!(preferences.locale, Locale::Id);
+        assert_eq!(preferences.channels.len(), 36);
+        assert!(preferences
+            .channels
+            .iter()
+            .all(|p| p.enabled == (p.channel != NotificationChannel::WhatsApp)));
+
+        // WhatsApp needs a mobile number
+        let whatsapp_on = PreferencesUpdate {
+            channels: vec![ChannelPreference {
+                event: NotificationEvent::LicenseSuspended,
+                channel: NotificationChannel::WhatsApp,
+                enabled: true,
+            }],
+            ..Default::default()
+        };
+        assert!(matches!(
+            service.update_preferences(user_id, whatsapp_on.clone()).await,
+            Err(AppError::Validation(_))
+        ));
+        let landline = PreferencesUpdate {
+            whatsapp_number: Some(Some("021-5551234".to_string())),
+            ..whatsapp_on.clone()
+        };
+        assert!(matches!(
+            service.update_preferences(user_id, landline).await,
+            Err(AppError::Validation(_))
+        ));
+        let with_number = PreferencesUpdate {
+            whatsapp_number: Some(Some("+6281234567890".to_string())),
+            ..whatsapp_on
+        };
+        service.update_preferences(user_id, with_number).await.unwrap();
+        // ... and the number cannot be removed while WhatsApp is on
+        let removal = PreferencesUpdate {
+            whatsapp_number: Some(None),
+            ..Default::default()
+        };
+        assert!(service.update_preferences(user_id, removal).await.is_err());
+    }
+}
@@ -1,3 +1,11 @@
+// Payments
+// `PaymentService` is a stub for charging directly. Subscription invoices are
+// paid through Midtrans instead, which reports the outcome in an HTTP
+// notification that is checked and read here.
+
+use serde::Deserialize;
+use sha2::{Digest, Sha512};
+
 use crate::domain::value_objects::Money;
 use crate::shared::errors::AppResult;
 
@@ -21,3 +29,140 @@ impl PaymentService {
         Ok(())
     }
 }
+
+/// What a payment gateway reported about a payment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaymentOutcome {
+    /// The money arrived; the reference identifies the payment at the gateway
+    Paid { reference: String },
+    Failed { reason: String },
+    /// Nothing final yet, e.g. waiting for a bank transfer or a fraud review
+    Pending,
+}
+
+/// Midtrans HTTP notification, as far as billing reads it. `order_id` is the
+/// invoice number the payment was started for.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MidtransNotification {
+    pub order_id: String,
+    pub status_code: String,
+    /// Rupiah with two decimals, e.g. `165390.00`
+    #[serde(default)]
+    pub gross_amount: String,
+    #[serde(default)]
+    pub signature_key: String,
+    #[serde(default)]
+    pub transaction_status: String,
+    pub transaction_id: Option<String>,
+    pub fraud_status: Option<String>,
+    pub status_message: Option<String>,
+}
+
+impl MidtransNotification {
+    /// Whether Midtrans signed it: the signature is the SHA-512 of the order
+    /// id, status code, gross amount and our server key
+    pub fn is_signed_with(&self, server_key: &str) -> bool {
+        if server_key.is_empty() {
+            return false;
+        }
+        let expected = format!(
+            "{:x}",
+            Sha512::digest(
+                format!("{}{}{}{}", self.order_id, self.status_code, self.gross_amount, server_key)
+                    .as_bytes()
+            )
+        );
+        let given = self.signature_key.to_ascii_lowercase();
+        // Compared in constant time so the signature cannot be guessed byte by byte
+        expected.len() == given.len()
+            && expected
+                .bytes()
+                .zip(given.bytes())
+                .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+
+    /// `gross_amount` in hundredths of a rupiah
+    pub fn amount(&self) -> Option<i64> {
+        let (rupiah, sen) = self.gross_amount.split_once('.').unwrap_or((&self.gross_amount, "0"));
+        let sen = format!("{:0<2}", sen);
+        if sen.len() != 2 {
+            return None;
+        }
+        Some(rupiah.parse::<i64>().ok()? * 100 + sen.parse::<i64>().ok()?)
+    }
+
+    pub fn outcome(&self) -> PaymentOutcome {
+        let reference = || self.transaction_id.clone().unwrap_or_else(|| self.order_id.clone());
+        match self.transaction_status.as_str() {
+            "settlement" => PaymentOutcome::Paid { reference: reference() },
+            "capture" if self.fraud_status.as_deref() != Some("challenge") => {
+                PaymentOutcome::Paid { reference: reference() }
+            }
+            "deny" | "cancel" | "expire" | "failure" => PaymentOutcome::Failed {
+                reason: self
+                    .status_message
+                    .clone()
+                    .unwrap_or_else(|| format!("Payment {}", self.transaction_status)),
+            },
+            _ => PaymentOutcome::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(transaction_status: &str) -> MidtransNotification {
+        let mut notification = MidtransNotification {
+            order_id: "SUB/2026/000001".to_string(),
+            status_code: "200".to_string(),
+            gross_amount: "165390.00".to_string(),
+            signature_key: String::new(),
+            transaction_status: transaction_status.to_string(),
+            transaction_id: Some("9aed5972-5b6a-401e-894b-a32c91ed1a3a".to_string()),
+            fraud_status: Some("accept".to_string()),
+            status_message: None,
+        };
+        notification.signature_key = format!(
+            "{:x}",
+            Sha512::digest(b"SUB/2026/000001200165390.00server-key")
+        );
+        notification
+    }
+
+    #[test]
+    fn test_signature_and_amount() {
+        let notification = notification("settlement");
+        assert!(notification.is_signed_with("server-key"));
+        assert!(!notification.is_signed_with("another-key"));
+        assert!(!notification.is_signed_with(""));
+        assert_eq!(notification.amount(), Some(16_539_000));
+
+        let tampered = MidtransNotification {
+            gross_amount: "1.00".to_string(),
+            ..notification
+        };
+        assert!(!tampered.is_signed_with("server-key"));
+        assert_eq!(tampered.amount(), Some(100));
+    }
+
+    #[test]
+    fn test_outcomes() {
+        assert!(matches!(notification("settlement").outcome(), PaymentOutcome::Paid { .. }));
+        assert!(matches!(notification("capture").outcome(), PaymentOutcome::Paid { .. }));
+        let challenged = MidtransNotification {
+            fraud_status: Some("challenge".to_string()),
+            ..notification("capture")
+        };
+        assert_eq!(challenged.outcome(), PaymentOutcome::Pending);
+        assert_eq!(notification("pending").outcome(), PaymentOutcome::Pending);
+        assert_eq!(
+            notification("expire").outcome(),
+            PaymentOutcome::Failed {
+                reason: "Payment expire".to_string()
+            }
+        );
+    }
+}
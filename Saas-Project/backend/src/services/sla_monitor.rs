@@ -0,0 +1,313 @@
+// Watches in-flight license applications against their processing targets
+// A periodic sweep records a warning for every threshold an application has
+// passed and escalates breaches to the longest-serving active super admin.
+// Recording is idempotent, so a missed sweep only delays events and several
+// instances may sweep at once.
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::domain::entities::{UserRole, UserStatus};
+use crate::domain::filters::{UserFilter, UserSortField};
+use crate::domain::licenses::License;
+use crate::domain::repositories::UserRepository;
+use crate::domain::sla::{
+    in_flight_statuses, AtRiskApplication, SlaDashboard, SlaEvent, SlaEventKind, SlaPolicy,
+    SlaRepository, SlaState,
+};
+use crate::infrastructure::monitoring::{record_license_sla_event, record_license_sla_sweep};
+use crate::infrastructure::repositories::LicenseRepository;
+use crate::shared::errors::AppResult;
+use crate::shared::query::{ListQuery, SortDirection, SortSpec};
+
+/// What one sweep found and raised
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SlaSweep {
+    pub checked: usize,
+    pub at_risk: usize,
+    pub breached: usize,
+    pub warnings: usize,
+    pub escalations: usize,
+}
+
+pub struct SlaMonitor {
+    policy: SlaPolicy,
+    licenses: Arc<dyn LicenseRepository + Send + Sync>,
+    sla: Arc<dyn SlaRepository>,
+    users: Arc<dyn UserRepository + Send + Sync>,
+}
+
+impl SlaMonitor {
+    pub fn new(
+        policy: SlaPolicy,
+        licenses: Arc<dyn LicenseRepository + Send + Sync>,
+        sla: Arc<dyn SlaRepository>,
+        users: Arc<dyn UserRepository + Send + Sync>,
+    ) -> Self {
+        Self {
+            policy,
+            licenses,
+            sla,
+            users,
+        }
+    }
+
+    pub fn policy(&self) -> &SlaPolicy {
+        &self.policy
+    }
+
+    async fn in_flight(&self) -> AppResult<Vec<License>> {
+        let mut licenses = Vec::new();
+        for status in in_flight_statuses() {
+            licenses.extend(self.licenses.get_licenses_by_status(status).await?);
+        }
+        Ok(licenses)
+    }
+
+    /// Active super admin who has been one the longest
+    async fn escalation_target(&self) -> AppResult<Option<Uuid>> {
+        let filter = UserFilter {
+            role: Some(UserRole::SuperAdmin),
+            status: Some(UserStatus::Active),
+            ..UserFilter::default()
+        };
+        let query = ListQuery::new(filter)
+            .with_sort(SortSpec::new(UserSortField::CreatedAt, SortDirection::Asc))
+            .with_limit(1);
+        let page = self.users.list(&query).await?;
+        Ok(page.data.first().map(|user| *user.id.as_uuid()))
+    }
+
+    /// Records the warnings and escalations due at `now`
+    pub async fn sweep(&self, now: DateTime<Utc>) -> AppResult<SlaSweep> {
+        let licenses = self.in_flight().await?;
+        let mut sweep = SlaSweep {
+            checked: licenses.len(),
+            ..SlaSweep::default()
+        };
+
+        let mut due = Vec::new();
+        for license in &licenses {
+            let Some(status) = self.policy.evaluate(license, now) else {
+                continue;
+            };
+            match status.state {
+                SlaState::OnTrack => continue,
+                SlaState::AtRisk => sweep.at_risk += 1,
+                SlaState::Breached => sweep.breached += 1,
+            }
+            for (kind, threshold) in self.policy.due_events(&status) {
+                due.push((license, kind, threshold));
+            }
+        }
+        record_license_sla_sweep(
+            sweep.checked - sweep.at_risk - sweep.breached,
+            sweep.at_risk,
+            sweep.breached,
+        );
+        if due.is_empty() {
+            return Ok(sweep);
+        }
+
+        let license_ids: Vec<Uuid> = due.iter().map(|(license, _, _)| license.id).collect();
+        let recorded: Vec<(Uuid, SlaEventKind, i32)> = self
+            .sla
+            .events_for_licenses(&license_ids)
+            .await?
+            .into_iter()
+            .map(|event| (event.license_id, event.kind, event.threshold_percent))
+            .collect();
+        due.retain(|(license, kind, threshold)| {
+            !recorded.contains(&(license.id, *kind, *threshold as i32))
+        });
+
+        let mut escalate_to = None;
+        if due.iter().any(|(_, kind, _)| *kind == SlaEventKind::Escalation) {
+            escalate_to = self.escalation_target().await?;
+            if escalate_to.is_none() {
+                error!("🚨 License SLA breached but there is no active super admin to escalate to");
+            }
+        }
+
+        for (license, kind, threshold) in due {
+            let mut event = SlaEvent::new(license.id, kind, threshold);
+            if kind == SlaEventKind::Escalation {
+                event.escalated_to = escalate_to;
+            }
+            if !self.sla.record_event(&event).await? {
+                continue;
+            }
+
+            record_license_sla_event(&kind.to_string(), &license.license_type.to_string());
+            match kind {
+                SlaEventKind::Warning => {
+                    sweep.warnings += 1;
+                    warn!(
+                        license_id = %license.id,
+                        license_type = %license.license_type,
+                        threshold_percent = threshold,
+                        "⏰ License application passed {}% of its SLA",
+                        threshold
+                    );
+                }
+                SlaEventKind::Escalation => {
+                    sweep.escalations += 1;
+                    warn!(
+                        license_id = %license.id,
+                        license_type = %license.license_type,
+                        escalated_to = ?escalate_to,
+                        "🚨 License application breached its SLA and was escalated"
+                    );
+                }
+            }
+        }
+
+        Ok(sweep)
+    }
+
+    /// Applications at or past a warning threshold at `now`, and how each
+    /// reviewer did on the applications decided in `[from, to)`
+    pub async fn dashboard(
+        &self,
+        now: DateTime<Utc>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> AppResult<SlaDashboard> {
+        let mut at_risk: Vec<_> = self
+            .in_flight()
+            .await?
+            .iter()
+            .filter_map(|license| self.policy.evaluate(license, now))
+            .filter(|status| status.state != SlaState::OnTrack)
+            .collect();
+        at_risk.sort_by_key(|status| std::cmp::Reverse(status.percent_used));
+
+        let license_ids: Vec<Uuid> = at_risk.iter().map(|status| status.license_id).collect();
+        let escalations: HashMap<Uuid, Option<Uuid>> = self
+            .sla
+            .events_for_licenses(&license_ids)
+            .await?
+            .into_iter()
+            .filter(|event| event.kind == SlaEventKind::Escalation)
+            .map(|event| (event.license_id, event.escalated_to))
+            .collect();
+
+        let decisions = self.sla.decisions(from, to).await?;
+
+        Ok(SlaDashboard {
+            generated_at: now,
+            warning_thresholds: self.policy.warning_thresholds().to_vec(),
+            at_risk: at_risk
+                .into_iter()
+                .map(|status| AtRiskApplication {
+                    escalated_to: escalations.get(&status.license_id).copied().flatten(),
+                    status,
+                })
+                .collect(),
+            from,
+            to,
+            reviewers: self.policy.attainment_by_reviewer(&decisions),
+        })
+    }
+
+    /// Sweeps every `interval` until the process exits
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            info!("⏱️ License SLA sweep every {}s", interval.as_secs());
+            let mut ticks = tokio::time::interval(interval);
+            loop {
+                ticks.tick().await;
+                if let Err(err) = self.sweep(Utc::now()).await {
+                    warn!("⚠️ License SLA sweep failed: {}", err);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration as ChronoDuration;
+
+    use crate::domain::entities::User;
+    use crate::domain::licenses::{ApplicationStatus, LicenseType, PriorityLevel};
+    use crate::domain::value_objects::Email;
+    use crate::infrastructure::repositories::{InMemoryLicenseRepository, InMemoryUserRepository};
+
+    async fn submitted(
+        licenses: &InMemoryLicenseRepository,
+        status: ApplicationStatus,
+        days_ago: i64,
+    ) -> License {
+        let mut license = License::new(
+            LicenseType::Nib,
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            "NIB warung".to_string(),
+            None,
+        );
+        license.priority = PriorityLevel::Normal;
+        license.application_status = status;
+        license.submitted_at = Some(Utc::now() - ChronoDuration::days(days_ago));
+        licenses.create_license(&license).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sweep_warns_once_per_threshold_and_escalates_breaches() {
+        let licenses = InMemoryLicenseRepository::new();
+        let users = InMemoryUserRepository::new();
+        let mut admin = User::new(
+            Email::new("root@saas-umkm.id").unwrap(),
+            "hash".to_string(),
+            "Super Admin".to_string(),
+            UserRole::SuperAdmin,
+        );
+        admin.status = UserStatus::Active;
+        users.save(&admin).await.unwrap();
+
+        let fresh = submitted(&licenses, ApplicationStatus::Submitted, 1).await;
+        let late = submitted(&licenses, ApplicationStatus::Processing, 6).await;
+        let overdue = submitted(&licenses, ApplicationStatus::Processing, 8).await;
+
+        let monitor = SlaMonitor::new(
+            SlaPolicy::default(),
+            Arc::new(licenses.clone()),
+            Arc::new(licenses.clone()),
+            Arc::new(users),
+        );
+        let sweep = monitor.sweep(Utc::now()).await.unwrap();
+        assert_eq!(sweep.checked, 3);
+        assert_eq!(sweep.at_risk, 1);
+        assert_eq!(sweep.breached, 1);
+        assert_eq!(sweep.warnings, 5);
+        assert_eq!(sweep.escalations, 1);
+
+        let again = monitor.sweep(Utc::now()).await.unwrap();
+        assert_eq!((again.warnings, again.escalations), (0, 0));
+
+        let events = licenses
+            .events_for_licenses(&[fresh.id, late.id, overdue.id])
+            .await
+            .unwrap();
+        let escalation = events
+            .iter()
+            .find(|e| e.kind == SlaEventKind::Escalation)
+            .unwrap();
+        assert_eq!(escalation.license_id, overdue.id);
+        assert_eq!(escalation.escalated_to, Some(*admin.id.as_uuid()));
+
+        let dashboard = monitor
+            .dashboard(Utc::now(), Utc::now() - ChronoDuration::days(30), Utc::now())
+            .await
+            .unwrap();
+        let at_risk: Vec<Uuid> = dashboard.at_risk.iter().map(|a| a.status.license_id).collect();
+        assert_eq!(at_risk, vec![overdue.id, late.id]);
+        assert_eq!(dashboard.at_risk[0].escalated_to, Some(*admin.id.as_uuid()));
+    }
+}
@@ -9,6 +9,8 @@ use axum::{
 use serde_json::json;
 use thiserror::Error;
 
+use super::request_context::RequestContext;
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Database error: {0}")]
@@ -28,6 +30,13 @@ pub enum AppError {
     
     #[error("Conflict: {0}")]
     Conflict(String),
+
+    /// A conditional update matched on a version someone else already replaced
+    #[error("{resource} was modified concurrently (now at version {current_version})")]
+    VersionConflict {
+        resource: &'static str,
+        current_version: i64,
+    },
     
     #[error("Unauthorized: {0}")]
     Unauthorized(String),
@@ -46,9 +55,16 @@ pub enum AppError {
     
     #[error("File processing error: {0}")]
     FileProcessing(String),
+
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
     
     #[error("Rate limit exceeded")]
     RateLimit,
+
+    /// The company's subscription plan does not cover the request
+    #[error("Plan limit reached: {0}")]
+    PlanLimit(String),
     
     #[error("Internal server error: {0}")]
     Internal(String),
@@ -90,6 +106,11 @@ impl IntoResponse for AppError {
                 msg.clone(),
                 "CONFLICT",
             ),
+            AppError::VersionConflict { resource, .. } => (
+                StatusCode::CONFLICT,
+                format!("{} was modified by someone else; reload it and try again", resource),
+                "VERSION_CONFLICT",
+            ),
             AppError::Unauthorized(msg) => (
                 StatusCode::UNAUTHORIZED,
                 msg.clone(),
@@ -120,11 +141,21 @@ impl IntoResponse for AppError {
                 msg.clone(),
                 "FILE_PROCESSING_ERROR",
             ),
+            AppError::PayloadTooLarge(msg) => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                msg.clone(),
+                "PAYLOAD_TOO_LARGE",
+            ),
             AppError::RateLimit => (
                 StatusCode::TOO_MANY_REQUESTS,
                 "Rate limit exceeded".to_string(),
                 "RATE_LIMIT_EXCEEDED",
             ),
+            AppError::PlanLimit(msg) => (
+                StatusCode::PAYMENT_REQUIRED,
+                msg.clone(),
+                "PLAN_LIMIT_EXCEEDED",
+            ),
             AppError::Internal(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Internal server error".to_string(),
@@ -142,15 +173,26 @@ impl IntoResponse for AppError {
             tracing::error!("Internal error: {:?}", self);
         }
 
-        let body = Json(json!({
-            "error": {
-                "code": error_code,
-                "message": error_message,
-                "timestamp": chrono::Utc::now().to_rfc3339(),
+        let mut error = json!({
+            "code": error_code,
+            "message": error_message,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        });
+
+        // Tells the client which version to reload and retry against
+        if let AppError::VersionConflict { current_version, .. } = &self {
+            error["current_version"] = json!(current_version);
+        }
+
+        // Lets support find the failing request in the logs and traces
+        if let Some(context) = RequestContext::current() {
+            error["request_id"] = json!(context.request_id);
+            if let Some(trace_id) = context.trace_id {
+                error["trace_id"] = json!(trace_id);
             }
-        }));
+        }
 
-        (status, body).into_response()
+        (status, Json(json!({ "error": error }))).into_response()
     }
 }
 
@@ -1,5 +1,7 @@
 // Shared utilities and common types
 
 pub mod errors;
+pub mod query;
+pub mod request_context;
 pub mod types;
 pub mod utils;
@@ -0,0 +1,430 @@
+// Shared list query model
+// Typed filters, sort specs and opaque keyset cursors used by every list endpoint
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::shared::errors::AppError;
+
+pub const DEFAULT_PAGE_LIMIT: u32 = 20;
+pub const MAX_PAGE_LIMIT: u32 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+impl fmt::Display for SortDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortDirection::Asc => write!(f, "asc"),
+            SortDirection::Desc => write!(f, "desc"),
+        }
+    }
+}
+
+/// Storage type of a sortable column, used to decode cursor values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKind {
+    Integer,
+    Timestamp,
+    Text,
+}
+
+/// A column a resource can be ordered by. Sortable columns must be NOT NULL so
+/// that keyset comparisons stay well-defined.
+pub trait SortField: Copy + PartialEq + fmt::Display + FromStr<Err = String> + Send + Sync {
+    fn column(&self) -> &'static str;
+    fn kind(&self) -> SortKind;
+    fn default_field() -> Self;
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum SortValue {
+    Integer(i64),
+    Timestamp(DateTime<Utc>),
+    Text(String),
+}
+
+impl SortValue {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            SortValue::Integer(v) => serde_json::json!(v),
+            SortValue::Timestamp(v) => serde_json::json!(v.to_rfc3339()),
+            SortValue::Text(v) => serde_json::json!(v),
+        }
+    }
+
+    fn from_json(kind: SortKind, value: &serde_json::Value) -> Option<Self> {
+        match kind {
+            SortKind::Integer => value.as_i64().map(SortValue::Integer),
+            SortKind::Timestamp => value
+                .as_str()
+                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                .map(|dt| SortValue::Timestamp(dt.with_timezone(&Utc))),
+            SortKind::Text => value.as_str().map(|s| SortValue::Text(s.to_string())),
+        }
+    }
+}
+
+/// Entities that can be paged by keyset need to expose the sort value and id
+pub trait Sortable<F: SortField> {
+    fn sort_value(&self, field: F) -> SortValue;
+    fn sort_id(&self) -> Uuid;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SortSpec<F: SortField> {
+    pub field: F,
+    pub direction: SortDirection,
+}
+
+impl<F: SortField> SortSpec<F> {
+    pub fn new(field: F, direction: SortDirection) -> Self {
+        Self { field, direction }
+    }
+
+    /// Parses `field`, `-field` (descending) or `field:asc|desc`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        if let Some(field) = spec.strip_prefix('-') {
+            return Ok(Self::new(field.parse()?, SortDirection::Desc));
+        }
+        match spec.split_once(':') {
+            Some((field, "asc")) => Ok(Self::new(field.parse()?, SortDirection::Asc)),
+            Some((field, "desc")) => Ok(Self::new(field.parse()?, SortDirection::Desc)),
+            Some((_, direction)) => Err(format!("Unknown sort direction: {}", direction)),
+            None => Ok(Self::new(spec.parse()?, SortDirection::Asc)),
+        }
+    }
+}
+
+impl<F: SortField> Default for SortSpec<F> {
+    fn default() -> Self {
+        Self::new(F::default_field(), SortDirection::Desc)
+    }
+}
+
+impl<F: SortField> fmt::Display for SortSpec<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.field, self.direction)
+    }
+}
+
+/// Opaque keyset cursor. Carries the sort it was issued for so that a cursor
+/// cannot be replayed against a different ordering.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CursorToken {
+    sort: String,
+    value: serde_json::Value,
+    id: Uuid,
+}
+
+impl CursorToken {
+    fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    fn decode(token: &str) -> Result<Self, AppError> {
+        let invalid = || AppError::BadRequest("Invalid cursor".to_string());
+        let bytes = URL_SAFE_NO_PAD.decode(token).map_err(|_| invalid())?;
+        serde_json::from_slice(&bytes).map_err(|_| invalid())
+    }
+}
+
+/// Paging parameters shared by list endpoints, read from the query string
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ListParams {
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+    pub sort: Option<String>,
+    pub include_total: Option<bool>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ListQuery<T, F: SortField> {
+    pub filter: T,
+    pub sort: SortSpec<F>,
+    pub limit: u32,
+    /// Sort value and id of the last row of the previous page
+    pub after: Option<(SortValue, Uuid)>,
+    pub include_total: bool,
+}
+
+impl<T, F: SortField> ListQuery<T, F> {
+    pub fn new(filter: T) -> Self {
+        Self {
+            filter,
+            sort: SortSpec::default(),
+            limit: DEFAULT_PAGE_LIMIT,
+            after: None,
+            include_total: true,
+        }
+    }
+
+    pub fn from_params(filter: T, params: &ListParams) -> Result<Self, AppError> {
+        let mut query = Self::new(filter);
+
+        if let Some(sort) = params.sort.as_deref() {
+            query.sort = SortSpec::parse(sort).map_err(AppError::Validation)?;
+        }
+        if let Some(limit) = params.limit {
+            query = query.with_limit(limit);
+        }
+        if let Some(include_total) = params.include_total {
+            query.include_total = include_total;
+        }
+        if let Some(token) = params.cursor.as_deref() {
+            let cursor = CursorToken::decode(token)?;
+            if cursor.sort != query.sort.to_string() {
+                return Err(AppError::BadRequest(
+                    "Cursor was issued for a different sort order".to_string(),
+                ));
+            }
+            let value = SortValue::from_json(query.sort.field.kind(), &cursor.value)
+                .ok_or_else(|| AppError::BadRequest("Invalid cursor".to_string()))?;
+            query.after = Some((value, cursor.id));
+        }
+
+        Ok(query)
+    }
+
+    pub fn with_sort(mut self, sort: SortSpec<F>) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    pub fn with_limit(mut self, limit: u32) -> Self {
+        self.limit = limit.clamp(1, MAX_PAGE_LIMIT);
+        self
+    }
+
+    /// Number of rows to fetch: one more than the page so we know if there is a next page
+    pub fn fetch_limit(&self) -> u64 {
+        self.limit as u64 + 1
+    }
+}
+
+/// Response envelope for list endpoints
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub data: Vec<T>,
+    pub next_cursor: Option<String>,
+    pub total: Option<i64>,
+    pub limit: u32,
+}
+
+impl<T> Page<T> {
+    /// Builds a page from rows fetched with `ListQuery::fetch_limit`
+    pub fn from_rows<Q, F>(mut rows: Vec<T>, query: &ListQuery<Q, F>, total: Option<i64>) -> Self
+    where
+        F: SortField,
+        T: Sortable<F>,
+    {
+        let next_cursor = if rows.len() > query.limit as usize {
+            rows.truncate(query.limit as usize);
+            rows.last().map(|last| {
+                CursorToken {
+                    sort: query.sort.to_string(),
+                    value: last.sort_value(query.sort.field).to_json(),
+                    id: last.sort_id(),
+                }
+                .encode()
+            })
+        } else {
+            None
+        };
+
+        Self {
+            data: rows,
+            next_cursor,
+            total,
+            limit: query.limit,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            data: self.data.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+            total: self.total,
+            limit: self.limit,
+        }
+    }
+}
+
+/// Keyset pagination over an already filtered in-memory collection
+pub fn paginate<T, Q, F>(mut items: Vec<T>, query: &ListQuery<Q, F>) -> Page<T>
+where
+    F: SortField,
+    T: Sortable<F>,
+{
+    let field = query.sort.field;
+    let total = items.len() as i64;
+
+    items.sort_by(|a, b| {
+        let ordering = a
+            .sort_value(field)
+            .partial_cmp(&b.sort_value(field))
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.sort_id().cmp(&b.sort_id()));
+        match query.sort.direction {
+            SortDirection::Asc => ordering,
+            SortDirection::Desc => ordering.reverse(),
+        }
+    });
+
+    if let Some((value, id)) = &query.after {
+        items.retain(|item| {
+            let key = (item.sort_value(field), item.sort_id());
+            match query.sort.direction {
+                SortDirection::Asc => key.partial_cmp(&(value.clone(), *id)) == Some(std::cmp::Ordering::Greater),
+                SortDirection::Desc => key.partial_cmp(&(value.clone(), *id)) == Some(std::cmp::Ordering::Less),
+            }
+        });
+    }
+
+    items.truncate(query.fetch_limit() as usize);
+    Page::from_rows(items, query, query.include_total.then_some(total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum TestSort {
+        Name,
+        Score,
+    }
+
+    impl fmt::Display for TestSort {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                TestSort::Name => write!(f, "name"),
+                TestSort::Score => write!(f, "score"),
+            }
+        }
+    }
+
+    impl FromStr for TestSort {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "name" => Ok(TestSort::Name),
+                "score" => Ok(TestSort::Score),
+                _ => Err(format!("Unknown sort field: {}", s)),
+            }
+        }
+    }
+
+    impl SortField for TestSort {
+        fn column(&self) -> &'static str {
+            match self {
+                TestSort::Name => "name",
+                TestSort::Score => "score",
+            }
+        }
+
+        fn kind(&self) -> SortKind {
+            match self {
+                TestSort::Name => SortKind::Text,
+                TestSort::Score => SortKind::Integer,
+            }
+        }
+
+        fn default_field() -> Self {
+            TestSort::Score
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct Item {
+        id: Uuid,
+        name: String,
+        score: i64,
+    }
+
+    impl Sortable<TestSort> for Item {
+        fn sort_value(&self, field: TestSort) -> SortValue {
+            match field {
+                TestSort::Name => SortValue::Text(self.name.clone()),
+                TestSort::Score => SortValue::Integer(self.score),
+            }
+        }
+
+        fn sort_id(&self) -> Uuid {
+            self.id
+        }
+    }
+
+    fn items() -> Vec<Item> {
+        (0..5)
+            .map(|i| Item {
+                id: Uuid::new_v4(),
+                name: format!("item-{}", i),
+                // Duplicate scores exercise the id tie-breaker
+                score: i / 2,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_spec_parsing() {
+        let spec = SortSpec::<TestSort>::parse("-name").unwrap();
+        assert_eq!(spec, SortSpec::new(TestSort::Name, SortDirection::Desc));
+        let spec = SortSpec::<TestSort>::parse("score:asc").unwrap();
+        assert_eq!(spec.to_string(), "score:asc");
+        assert!(SortSpec::<TestSort>::parse("unknown").is_err());
+        assert!(SortSpec::<TestSort>::parse("name:sideways").is_err());
+    }
+
+    #[test]
+    fn test_paging_visits_every_item_once() {
+        let all = items();
+        let mut params = ListParams {
+            limit: Some(2),
+            ..Default::default()
+        };
+        let mut seen = Vec::new();
+
+        loop {
+            let query = ListQuery::<(), TestSort>::from_params((), &params).unwrap();
+            let page = paginate(all.clone(), &query);
+            assert_eq!(page.total, Some(5));
+            seen.extend(page.data.iter().map(|i| i.score));
+            match page.next_cursor {
+                Some(cursor) => params.cursor = Some(cursor),
+                None => break,
+            }
+        }
+
+        assert_eq!(seen, vec![2, 1, 1, 0, 0]);
+    }
+
+    #[test]
+    fn test_cursor_rejected_for_other_sort() {
+        let query = ListQuery::<(), TestSort>::new(()).with_limit(1);
+        let page = paginate(items(), &query);
+        let params = ListParams {
+            cursor: page.next_cursor,
+            sort: Some("name".to_string()),
+            ..Default::default()
+        };
+        assert!(ListQuery::<(), TestSort>::from_params((), &params).is_err());
+
+        let params = ListParams {
+            cursor: Some("garbage".to_string()),
+            ..Default::default()
+        };
+        assert!(ListQuery::<(), TestSort>::from_params((), &params).is_err());
+    }
+}
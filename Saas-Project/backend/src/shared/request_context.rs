@@ -0,0 +1,35 @@
+// Per-request correlation data
+// The request-id middleware runs every request inside a task-local scope, so
+// code without access to the request (error responses, outgoing HTTP calls)
+// can still tag its output with the request and trace ids.
+
+tokio::task_local! {
+    static REQUEST_CONTEXT: RequestContext;
+}
+
+/// Header carrying the request id, accepted from clients and echoed back
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestContext {
+    pub request_id: String,
+    /// W3C trace id (32 hex characters) of the request span
+    pub trace_id: Option<String>,
+}
+
+impl RequestContext {
+    /// Context of the request being handled on this task, if any
+    pub fn current() -> Option<RequestContext> {
+        REQUEST_CONTEXT.try_with(|context| context.clone()).ok()
+    }
+
+    /// Runs `future` with this context as the current one
+    pub async fn scope<F: std::future::Future>(self, future: F) -> F::Output {
+        REQUEST_CONTEXT.scope(self, future).await
+    }
+
+    /// Runs `f` with this context as the current one
+    pub fn sync_scope<R>(self, f: impl FnOnce() -> R) -> R {
+        REQUEST_CONTEXT.sync_scope(self, f)
+    }
+}
@@ -38,3 +38,9 @@ impl<T> PaginatedResponse<T> {
         }
     }
 }
+
+/// Version of a row that has never been updated. Also the serde default, so
+/// entities cached before versions existed still deserialize.
+pub fn initial_version() -> i64 {
+    1
+}
@@ -9,6 +9,8 @@ pub mod mocks {
     use crate::domain::entities::{User, Company, License};
     use crate::domain::repositories::{UserRepository, CompanyRepository, LicenseRepository};
     use crate::domain::value_objects::{UserId, CompanyId, LicenseId, Email};
+    use crate::domain::filters::{CompanyFilter, CompanySortField, UserFilter, UserSortField};
+    use crate::shared::query::{ListQuery, Page};
     use crate::shared::errors::{AppResult, AppError};
     
     // Mock User Repository
@@ -22,6 +24,7 @@ pub mod mocks {
             async fn list_all(&self, limit: Option<i32>, offset: Option<i32>) -> AppResult<Vec<User>>;
             async fn count_all(&self) -> AppResult<i64>;
             async fn search(&self, query: &str, limit: Option<i32>, offset: Option<i32>) -> AppResult<Vec<User>>;
+            async fn list(&self, query: &ListQuery<UserFilter, UserSortField>) -> AppResult<Page<User>>;
             async fn save(&self, user: &User) -> AppResult<()>;
             async fn delete(&self, id: &UserId) -> AppResult<()>;
         }
@@ -41,7 +44,7 @@ pub mod mocks {
             async fn delete(&self, id: &uuid::Uuid) -> AppResult<()>;
             async fn list_all(&self, limit: Option<i32>, offset: Option<i32>) -> AppResult<Vec<Company>>;
             async fn count_by_owner(&self, owner_id: &uuid::Uuid) -> AppResult<i64>;
-            async fn search(&self, query: &str, limit: Option<i32>, offset: Option<i32>) -> AppResult<Vec<Company>>;
+            async fn list(&self, query: &ListQuery<CompanyFilter, CompanySortField>) -> AppResult<Page<Company>>;
         }
     }
     
@@ -4,11 +4,8 @@ mod user_repository_test;
 mod user_command_handler_test;
 mod user_query_handler_test;
 mod value_objects_test;
-mod company_command_handler_test;
-mod company_query_handler_test;
 mod api_endpoints_test;
 mod api_test;
 mod mocks;
 mod user_repository_proptest;
-mod refresh_token_test;
 mod license_repository_test;
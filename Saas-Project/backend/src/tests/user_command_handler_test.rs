@@ -8,9 +8,12 @@ mod user_command_handler_tests {
     use crate::application::commands::CreateUserCommand;
     use crate::domain::entities::{User, UserRole, UserStatus};
     use crate::domain::repositories::UserRepository;
+    use crate::domain::filters::{UserFilter, UserSortField};
     use crate::domain::value_objects::{Email, UserId, PhoneNumber};
+    use crate::shared::query::{paginate, ListQuery, Page};
     use crate::shared::errors::{AppError, AppResult};
     use crate::services::auth::AuthService;
+    use crate::infrastructure::messaging::RecordingSender;
     use async_trait::async_trait;
     use std::collections::HashMap;
     use std::sync::Mutex;
@@ -100,6 +103,14 @@ mod user_command_handler_tests {
             }
         }
 
+        async fn list(&self, query: &ListQuery<UserFilter, UserSortField>) -> AppResult<Page<User>> {
+            let users = self.users.iter()
+                .filter(|u| query.filter.matches(u))
+                .cloned()
+                .collect();
+            Ok(paginate(users, query))
+        }
+
         async fn count_all(&self) -> AppResult<i64> {
             let users = self.users.lock().unwrap();
             Ok(users.len() as i64)
@@ -139,7 +150,7 @@ mod user_command_handler_tests {
         let auth_service = Arc::new(create_mock_auth_service());
         
         // Create command handler with mock repository
-        let handler = UserCommandHandler::new(repo.clone(), auth_service);
+        let handler = UserCommandHandler::new(repo.clone(), auth_service, Arc::new(RecordingSender::new("email")));
         
         // Create a test command
         let email = Email::new("new_user@example.com").unwrap();
@@ -192,7 +203,7 @@ mod user_command_handler_tests {
         
         let repo = Arc::new(MockUserRepository::with_users(vec![existing_user]));
         let auth_service = Arc::new(create_mock_auth_service());
-        let handler = UserCommandHandler::new(repo.clone(), auth_service);
+        let handler = UserCommandHandler::new(repo.clone(), auth_service, Arc::new(RecordingSender::new("email")));
         
         // Try to create a user with the same email
         let email = Email::new("existing@example.com").unwrap();
@@ -242,7 +253,7 @@ mod user_command_handler_tests {
         
         let repo = Arc::new(MockUserRepository::with_users(vec![existing_user]));
         let auth_service = Arc::new(create_mock_auth_service());
-        let handler = UserCommandHandler::new(repo.clone(), auth_service);
+        let handler = UserCommandHandler::new(repo.clone(), auth_service, Arc::new(RecordingSender::new("email")));
         
         // Create update command
         let phone = PhoneNumber("555-123-4567".to_string());
@@ -7,7 +7,9 @@ mod user_query_handler_tests {
     use crate::application::query_handlers::UserQueryHandler;
     use crate::domain::entities::{User, UserRole, UserStatus};
     use crate::domain::repositories::UserRepository;
+    use crate::domain::filters::{UserFilter, UserSortField};
     use crate::domain::value_objects::{Email, UserId};
+    use crate::shared::query::{paginate, ListQuery, Page};
     use crate::shared::errors::AppResult;
     use async_trait::async_trait;
 
@@ -69,6 +71,14 @@ mod user_query_handler_tests {
             Ok(users)
         }
 
+        async fn list(&self, query: &ListQuery<UserFilter, UserSortField>) -> AppResult<Page<User>> {
+            let users = self.users.iter()
+                .filter(|u| query.filter.matches(u))
+                .cloned()
+                .collect();
+            Ok(paginate(users, query))
+        }
+
         async fn count_all(&self) -> AppResult<i64> {
             Ok(self.users.len() as i64)
         }
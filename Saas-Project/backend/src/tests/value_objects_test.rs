@@ -64,4 +64,90 @@ mod value_objects_tests {
         assert_eq!(money2.amount, 500);
         assert_eq!(money2.currency, Currency::IDR);
     }
+
+    #[test]
+    fn test_phone_number_normalization() {
+        // All accepted prefixes normalize to E.164
+        let expected = "+6281234567890";
+        assert_eq!(PhoneNumber::new("081234567890").unwrap().as_str(), expected);
+        assert_eq!(PhoneNumber::new("6281234567890").unwrap().as_str(), expected);
+        assert_eq!(PhoneNumber::new("+62 812-3456-7890").unwrap().as_str(), expected);
+        assert_eq!(PhoneNumber::new("(0812) 3456.7890").unwrap().as_str(), expected);
+
+        let landline = PhoneNumber::new("021-5551234").unwrap();
+        assert_eq!(landline.as_str(), "+62215551234");
+        assert_eq!(landline.national_format(), "0215551234");
+        assert!(!landline.is_mobile());
+
+        // Invalid numbers
+        assert!(PhoneNumber::new("").is_err());
+        assert!(PhoneNumber::new("555-123-4567").is_err());
+        assert!(PhoneNumber::new("+1 202 555 0100").is_err());
+        assert!(PhoneNumber::new("0812").is_err());
+        assert!(PhoneNumber::new("08123456789012345").is_err());
+        assert!(PhoneNumber::new("0812abc4567").is_err());
+    }
+
+    #[test]
+    fn test_npwp_validation() {
+        // Formatted and unformatted legacy numbers are stored as digits
+        let npwp = NPWP::new("01.000.013.1-093.000".to_string()).unwrap();
+        assert_eq!(npwp.as_str(), "010000131093000");
+        assert_eq!(npwp.formatted(), "01.000.013.1-093.000");
+        assert_eq!(npwp.tax_office_code(), Some("093"));
+        assert!(npwp.is_legacy());
+        assert_eq!(NPWP::new("010000131093000".to_string()).unwrap(), npwp);
+
+        // Wrong check digit
+        assert!(NPWP::new("01.000.013.2-093.000".to_string()).is_err());
+
+        // 16-digit forms: zero-prefixed legacy number or a NIK
+        let entity = NPWP::new("0010000131093000".to_string()).unwrap();
+        assert!(!entity.is_legacy());
+        assert_eq!(entity.tax_office_code(), Some("093"));
+        assert!(NPWP::new("3171014501900001".to_string()).is_ok());
+        assert!(NPWP::new("0010000132093000".to_string()).is_err());
+
+        // Bad lengths and characters
+        assert!(NPWP::new("12345".to_string()).is_err());
+        assert!(NPWP::new("01.000.013.1/093.000".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_nik_decoding() {
+        // Jakarta Pusat, female born 5 January 1990
+        let nik = NIK::new("3171014501900001".to_string()).unwrap();
+        let region = nik.region();
+        assert_eq!(region.province_code, "31");
+        assert_eq!(region.regency_code, "3171");
+        assert_eq!(region.district_code, "317101");
+        assert_eq!(region.province_name, Some("DKI Jakarta"));
+        assert_eq!(nik.gender(), Gender::Female);
+        assert_eq!(
+            nik.birth_date(),
+            chrono::NaiveDate::from_ymd_opt(1990, 1, 5).unwrap()
+        );
+
+        // Male born 17 August 2001
+        let nik = NIK::new("3273011708010002".to_string()).unwrap();
+        assert_eq!(nik.gender(), Gender::Male);
+        assert_eq!(
+            nik.birth_date(),
+            chrono::NaiveDate::from_ymd_opt(2001, 8, 17).unwrap()
+        );
+
+        // Invalid province, date and serial
+        assert!(NIK::new("9971014501900001".to_string()).is_err());
+        assert!(NIK::new("3171013202900001".to_string()).is_err());
+        assert!(NIK::new("3171014501900000".to_string()).is_err());
+        assert!(NIK::new("317101450190".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_identity_value_objects_deserialize_with_validation() {
+        let phone: PhoneNumber = serde_json::from_str("\"0812-3456-7890\"").unwrap();
+        assert_eq!(phone.as_str(), "+6281234567890");
+        assert!(serde_json::from_str::<NPWP>("\"01.000.013.2-093.000\"").is_err());
+        assert_eq!(serde_json::to_string(&phone).unwrap(), "\"+6281234567890\"");
+    }
 }